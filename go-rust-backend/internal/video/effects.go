@@ -82,6 +82,16 @@ func goProgressGateway(userData unsafe.Pointer, percent C.float) {
 	}
 }
 
+// checkABI ensures the linked Rust library matches the header this package
+// was compiled against, before any struct crosses the boundary.
+func checkABI() error {
+	if got, want := uint32(C.focusframe_abi_version()), uint32(C.FOCUSFRAME_ABI_VERSION); got != want {
+		return fmt.Errorf("incompatible video engine (%s): ABI version %d, expected %d",
+			C.GoString(C.focusframe_version()), got, want)
+	}
+	return nil
+}
+
 // ProcessVideoWithCursor renders a video with smooth cursor overlay.
 // This function is thread-safe and can be called concurrently.
 func ProcessVideoWithCursor(
@@ -96,6 +106,10 @@ func ProcessVideoWithCursor(
 		return fmt.Errorf("no mouse history provided")
 	}
 
+	if err := checkABI(); err != nil {
+		return err
+	}
+
 	// Convert strings to C strings (heap allocation)
 	cInputPath := C.CString(inputVideoPath)
 	defer C.free(unsafe.Pointer(cInputPath))
@@ -163,6 +177,7 @@ func ProcessVideoWithCursor(
 		&cConfig,
 		C.ProgressCallback(C.goProgressGateway), // Function pointer
 		unsafe.Pointer(handle),                  // Context (the "cookie")
+		nil,                                     // Processing summary not needed
 	)
 
 	// Clean up
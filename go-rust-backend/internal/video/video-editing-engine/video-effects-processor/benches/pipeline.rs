@@ -0,0 +1,100 @@
+// pipeline.rs - Criterion benchmark for whole renders, serial vs pipelined
+//
+// Input: two seconds of a 1920x1080 30 fps test pattern, generated with the
+// ffmpeg CLI (the benchmark is skipped without it), rendered to H.264 with
+// a cursor and click ripples (RGBA compositing, the default path):
+//
+//   render/serial       decode, composite and encode one frame at a time
+//   render/pipelined    each stage on its own thread, a few frames apart
+//
+// Serial throughput is bounded by the sum of the three stages, pipelined by
+// the slowest of them (usually the encoder), so the gain grows with the
+// number of cores left over after x264's own threads.
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use video_effects_processor::engine::{
+    self, CClickEvent, CPoint, ClickEffect, CursorSprite, ProcessingOptions, Progress, BUTTON_LEFT,
+};
+
+const DURATION_MS: f64 = 2000.0;
+
+/// The 1080p test clip, or None without the ffmpeg CLI
+fn synthetic_clip(dir: &Path) -> Option<PathBuf> {
+    let path = dir.join("input.mp4");
+    let status = Command::new("ffmpeg")
+        .args(["-v", "error", "-y"])
+        .args(["-f", "lavfi", "-i", "testsrc2=size=1920x1080:rate=30"])
+        .args(["-t", &(DURATION_MS / 1000.0).to_string()])
+        .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+        .arg(&path)
+        .status();
+    status.is_ok_and(|s| s.success()).then_some(path)
+}
+
+/// Slow Lissajous sweep across the frame, with a click every 250 ms
+fn cursor_path() -> (Vec<CPoint>, Vec<CClickEvent>) {
+    let points = (0..=(DURATION_MS / 4.0) as u32)
+        .map(|i| {
+            let t = f64::from(i) * 4.0;
+            CPoint {
+                x: (960.0 + 700.0 * (t / 1700.0).sin()) as f32,
+                y: (540.0 + 400.0 * (t / 2300.0).cos()) as f32,
+                timestamp_ms: t,
+            }
+        })
+        .collect::<Vec<_>>();
+    let clicks = points
+        .iter()
+        .step_by(63)
+        .map(|p| CClickEvent {
+            x: p.x,
+            y: p.y,
+            timestamp_ms: p.timestamp_ms,
+            button: BUTTON_LEFT,
+        })
+        .collect();
+    (points, clicks)
+}
+
+fn renders(c: &mut Criterion) {
+    let dir = std::env::temp_dir().join(format!("focusframe-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let Some(input) = synthetic_clip(&dir) else {
+        eprintln!("ffmpeg not found, skipping the render benchmarks");
+        return;
+    };
+    let output = dir.join("output.mp4");
+    let sprite = CursorSprite::from_rgba8(vec![255; 24 * 24 * 4], 24, 24, false);
+    let (points, clicks) = cursor_path();
+
+    let mut group = c.benchmark_group("render");
+    group.sample_size(10);
+    for (name, pipelined) in [("serial", false), ("pipelined", true)] {
+        let opts = ProcessingOptions {
+            frame_rate: 30,
+            click_effect: Some(ClickEffect::default()),
+            pipelined,
+            ..ProcessingOptions::default()
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                engine::process_video_with_clicks(
+                    &input,
+                    &output,
+                    &sprite,
+                    &points,
+                    &clicks,
+                    &opts,
+                    |_: Progress| {},
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+criterion_group!(benches, renders);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+// build.rs - Bake the git revision into the library for bug reports
+use std::process::Command;
+
+fn main() {
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=FOCUSFRAME_GIT_HASH={}", git_hash);
+    println!("cargo:rerun-if-changed=build.rs");
+    // Repository root is five levels above this crate
+    println!("cargo:rerun-if-changed=../../../../../.git/HEAD");
+}
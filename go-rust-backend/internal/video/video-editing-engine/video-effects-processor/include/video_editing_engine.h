@@ -3,6 +3,44 @@
 
 #include <stddef.h>
 #include <stdint.h>
+#ifdef _WIN32
+#include <wchar.h>
+#endif
+
+// ABI version this header describes. Compare against focusframe_abi_version()
+// before calling any function that passes structs across the boundary.
+#define FOCUSFRAME_ABI_VERSION 1
+
+/**
+ * Build description: crate version, git hash and linked libavcodec /
+ * libavformat versions. Static string, do not free.
+ */
+const char *focusframe_version(void);
+
+/**
+ * ABI version of the loaded library (see FOCUSFRAME_ABI_VERSION).
+ */
+uint32_t focusframe_abi_version(void);
+
+/**
+ * Code and message of the failure of the calling thread's last call (0 and
+ * "" if it succeeded); every function that can fail clears them on entry.
+ * The message stays valid until the next call on the same thread; do not
+ * free it.
+ */
+int32_t focusframe_last_error_code(void);
+const char *focusframe_last_error_message(void);
+
+// Log callback: level uses the log_level scale (1=error .. 5=trace).
+// message is only valid for the duration of the call.
+typedef void (*LogCallback)(int level, const char *message);
+
+/**
+ * Forward all engine (and FFmpeg-level filtered) log output to callback.
+ * Pass NULL to restore the default stderr output. Verbosity is controlled by
+ * VideoProcessingConfig.log_level; 0 disables logging entirely.
+ */
+void set_log_callback(LogCallback callback);
 
 // Point structure matching Rust's CPoint
 typedef struct {
@@ -11,6 +49,143 @@ typedef struct {
   double timestamp_ms;
 } CPoint;
 
+// CEventPoint.event_type values
+#define FOCUSFRAME_EVENT_MOVE 0
+#define FOCUSFRAME_EVENT_DOWN 1 // Mouse button pressed at this sample
+#define FOCUSFRAME_EVENT_UP 2   // Mouse button released at this sample
+
+// CPoint tagged with the input event that produced it. The smoothed path
+// passes exactly through DOWN/UP samples; unknown types count as moves.
+typedef struct {
+  float x;
+  float y;
+  double timestamp_ms;
+  int32_t event_type; // FOCUSFRAME_EVENT_*
+} CEventPoint;
+
+// CClickEvent.button values
+#define FOCUSFRAME_BUTTON_LEFT 0
+#define FOCUSFRAME_BUTTON_RIGHT 1
+#define FOCUSFRAME_BUTTON_MIDDLE 2
+
+// Mouse button press, on the same clock and in the same coordinates as the
+// cursor points
+typedef struct {
+  float x;
+  float y;
+  double timestamp_ms;
+  int32_t button; // FOCUSFRAME_BUTTON_*
+} CClickEvent;
+
+// Key press to show on screen, on the same clock as the cursor points
+typedef struct {
+  double timestamp_ms;
+  const char *text; // NUL-terminated UTF-8, e.g. "⌘+Shift+4"
+} CKeyEvent;
+
+// Cursor shape change, on the same clock as the cursor points
+typedef struct {
+  double timestamp_ms;
+  const char *kind; // NUL-terminated UTF-8, e.g. "ibeam"
+} CCursorKindEvent;
+
+// Sprite drawn while the cursor has one kind
+typedef struct {
+  const char *kind;
+  const char *sprite_path;
+  float hotspot_x; // Unscaled sprite px placed on the cursor position;
+  float hotspot_y; // (0, 0) = the hotspot stored in .cur/.ani files
+} CCursorKindSprite;
+
+// CRedactionRegion.mode values
+#define FOCUSFRAME_REDACT_BLUR 0
+#define FOCUSFRAME_REDACT_PIXELATE 1
+
+// Rectangle of the recording to obscure for a span of the video
+typedef struct {
+  int32_t x;          // Top-left corner in source pixels; may hang off the
+  int32_t y;          // frame
+  uint32_t width;
+  uint32_t height;
+  double start_ms;    // Video time the region appears
+  double end_ms;      // Video time it disappears; negative = the rest of the
+                      // video
+  int32_t mode;       // FOCUSFRAME_REDACT_*
+  uint32_t amount_px; // Blur radius (1-256) or pixelate block size (2-512)
+} CRedactionRegion;
+
+// Container tag to set on the output (see RenderExtras)
+typedef struct {
+  const char *key;   // NUL-terminated UTF-8, e.g. "title" or "comment"
+  const char *value; // NUL-terminated UTF-8
+} CMetadataTag;
+
+// Everything process_video_ex can add to a render besides the cursor points.
+// Each array may be NULL when its length is 0 and a NULL string leaves its
+// feature off, so a zeroed struct adds nothing.
+//
+// captions_json is a JSON array of objects:
+//
+//   [{"text": "Step 2: open Settings", "start_ms": 3000, "end_ms": 6000,
+//     "position": "bottom", "font_size_px": 36, "color": [255, 255, 255],
+//     "background": [0, 0, 0, 180]}]
+//
+// Only text, start_ms and end_ms are required; the other values shown are
+// the defaults. position is "top", "middle" or "bottom"; background is
+// straight RGBA, or null for no box. "\n" in text starts a new line, and
+// lines wrap between words to config->caption_max_width_percent. Captions on
+// screen at the same position stack top to bottom in array order. Malformed
+// JSON, empty time ranges, blank text and right-to-left scripts (not
+// supported yet) return -10.
+//
+// zoom_keyframes_json is a JSON array of objects:
+//
+//   [{"start_ms": 5000, "end_ms": 9000, "zoom": 2.0,
+//     "center_x": 800, "center_y": 400, "easing": "spring"}]
+//
+// zoom is clamped to 1-4; center_x/center_y are in source pixels and the
+// crop is kept inside the frame. easing is "smoothstep" (default) or
+// "spring" and shapes the zoom in and out at each end of the keyframe. The
+// full frame shows between keyframes. Keyframes replace config->auto_zoom
+// and config->click_zoom; malformed JSON and overlapping keyframes return
+// -10.
+typedef struct {
+  const CEventPoint *cursor_events; // Replaces raw_cursor_points when set: the
+  size_t cursor_events_len;         // rendered cursor sits exactly on every
+                                    // DOWN/UP position at the click's frame
+  const CClickEvent *clicks;        // Drawn as rings when
+  size_t clicks_len;                // config->click_effect is set
+  const CKeyEvent *key_events;      // Shown as a badge when
+  size_t key_events_len;            // config->keystroke_display is set, in the
+  const char *keystroke_font_path;  // TrueType/OpenType font at this path
+                                    // (required with key events; it must
+                                    // cover every character shown)
+  const CCursorKindEvent *kind_events;   // Sprite switches: a change applies
+  size_t kind_events_len;                // from its own timestamp on.
+  const CCursorKindSprite *kind_sprites; // cursor_sprite_path is drawn before
+  size_t kind_sprites_len;               // the first change and for kinds
+                                         // without an entry (logged as a
+                                         // warning)
+  const char *zoom_keyframes_json; // See above
+  const char *watermark_path; // Image (any format cursor sprites accept, SVG
+                              // included) stamped in every frame above the
+                              // cursor, at config->watermark_corner inset by
+                              // the watermark margins
+  const CRedactionRegion *redactions; // Blurred or pixelated from start_ms up
+  size_t redactions_len;              // to end_ms, in order, before any zoom,
+                                      // styled background or other effect, so
+                                      // overlapping ones compound. An unknown
+                                      // mode or an empty rectangle or time
+                                      // range returns -10.
+  const char *captions_json;     // See above; drawn below the cursor in the
+  const char *caption_font_path; // TrueType/OpenType font at this path
+                                 // (required with captions_json)
+  const CMetadataTag *metadata; // Tags set on the output container, over any
+  size_t metadata_len;          // copied with config->copy_metadata; a later
+                                // tag with the same key wins. An empty key
+                                // returns -10.
+} RenderExtras;
+
 // Smoothed path result
 typedef struct {
   CPoint *points;
@@ -26,27 +201,860 @@ typedef struct {
   int32_t log_level;     // 0=off, 1=error, 2=warn, 3=info, 4=debug, 5=trace
 } VideoProcessingConfig;
 
-// Progress callback function pointer type
+// VideoProcessingConfig plus cursor appearance (the v1 struct is frozen).
+// New fields are only ever appended. Set struct_size to
+// sizeof(VideoProcessingConfigV2) (video_processing_config_v2_default does):
+// fields past it keep their defaults, so a newer library still accepts this
+// header's layout.
+typedef struct {
+  uint32_t struct_size;
+  float smoothing_alpha;
+  float responsiveness;
+  float smoothness;
+  int32_t frame_rate;
+  int32_t log_level;
+  float cursor_scale;   // Sprite size multiplier, 1.0 = native (0.1-8.0)
+  float hotspot_x;      // Sprite pixel (unscaled) drawn on the cursor position,
+  float hotspot_y;      // e.g. the arrow tip; (w/2, h/2) centers the sprite.
+                        // (0, 0) uses a .cur/.ani sprite's own hotspot
+  float cursor_opacity; // 0.0 = invisible, 1.0 = sprite's own alpha
+  int32_t codec;        // FOCUSFRAME_CODEC_*
+  int32_t container;    // FOCUSFRAME_CONTAINER_*
+  int32_t deterministic; // Non-zero: bit-identical output for identical inputs.
+                         // Encodes single-threaded with pinned settings and
+                         // no creation time/version tags; several times
+                         // slower on multi-core machines. Meant for CI.
+  int32_t smoothing_mode;    // FOCUSFRAME_SMOOTHING_*
+  float one_euro_min_cutoff; // Hz at rest (default 1.0); ONE_EURO only
+  float one_euro_beta;       // Cutoff gain per px/s of speed (default 0.007)
+  float one_euro_d_cutoff;   // Hz for the speed estimate (default 1.0)
+  float kalman_process_noise;     // px^2/s^3 (default 1e5); KALMAN only
+  float kalman_measurement_noise; // px^2 (default 4.0)
+  int32_t interpolation;          // FOCUSFRAME_INTERP_*
+  int32_t reject_outliers;        // Non-zero: drop single-sample glitches
+                                  // (jumps above 20000 px/s that snap back)
+                                  // before smoothing; real warps are kept
+  float max_gap_ms;  // Capture gaps longer than this (ms) hold the cursor at
+                     // its pre-gap position instead of splining across the
+                     // hole (0 = off)
+  float gap_ramp_ms; // The last gap_ramp_ms of such a gap move the cursor to
+                     // its post-gap position (default 150, max 1000)
+  float rest_radius_px; // SPRING only: once the raw cursor stays within this
+                        // radius for rest_dwell_ms, the output settles
+                        // exactly on it (default 2.0, 0 = off)
+  float rest_dwell_ms;  // default 120
+  float physics_max_step_sec; // SPRING only: longest integration substep in
+                              // seconds (0 = derived from the stiffness so
+                              // the filter cannot blow up; max 0.1)
+  int32_t physics_model_v2; // SPRING only, non-zero: responsiveness sets the
+                            // 2% settling time (0.6 s .. 0.08 s) and
+                            // smoothness the damping ratio (0.6 .. 1.0).
+                            // Feels different from the default mapping.
+  int32_t frame_bounds; // FOCUSFRAME_BOUNDS_*
+  float max_input_rate_hz; // Average cursor input denser than this (Hz)
+                           // before smoothing, keeping reversals and the
+                           // endpoints; 240 suits 1000 Hz mice (0 = off,
+                           // 30 .. 8000)
+  int32_t timestamp_unit; // FOCUSFRAME_TIME_*
+  float simplify_epsilon_px; // Drop smoothed points within this distance
+                             // (px) of a straight path before upsampling;
+                             // kept points stay <= 50 ms apart (0 = off,
+                             // max 10)
+  // Captured region in recorded coordinates. For a single display of a
+  // multi-monitor desktop, set it to that display's bounds in global desktop
+  // coordinates (e.g. x = 1920 for a display right of a 1080p one). Points
+  // map to (p - capture_xy) * capture_scale; 0 x 0 = unbounded.
+  float capture_x;
+  float capture_y;
+  float capture_width;
+  float capture_height;
+  float capture_scale;     // Video pixels per recorded unit (default 1.0;
+                           // 2.0 for points on a 2x Retina display). The
+                           // cursor sprite is not scaled by it.
+  int32_t capture_outside; // FOCUSFRAME_OUTSIDE_*
+  int32_t auto_pixel_scale; // Non-zero: ignore capture_scale and use video
+                            // size / capture_width x capture_height (per
+                            // axis) when rendering
+  int32_t enforce_kinematic_limits; // Non-zero: re-time strokes faster than
+                                    // 20000 px/s or 250000 px/s^2 along
+                                    // their path; stroke start/end times are
+                                    // kept (a stroke that cannot fit gets the
+                                    // gentlest profile that does)
+  int32_t tail_behavior; // FOCUSFRAME_TAIL_*
+  uint32_t tail_ms;      // HIDE: hold this long (ms) before hiding;
+                         // EXTRAPOLATE: keep moving this long (max 500)
+  int32_t gamma_correct_blend; // Non-zero: blend the cursor in linear light so
+                               // soft edges don't darken on bright video
+                               // (slightly slower compositing)
+  uint32_t cursor_sheet_frames; // > 1: the sprite is a horizontal strip of
+                                // this many equally wide animation frames
+                                // (0 = a still image, GIF, APNG or .ani)
+  float cursor_sheet_fps;       // Playback rate of those frames (default 10)
+  int32_t orient_to_velocity;   // Non-zero: rotate the sprite about its
+                                // hotspot to face the direction of travel
+                                // (draw the sprite pointing right); the
+                                // heading holds while the cursor rests
+  int32_t click_effect;         // Non-zero: draw an expanding, fading ring
+                                // under the cursor at each click (the clicks
+                                // and DOWN cursor_events of RenderExtras)
+  float click_duration_ms;      // Lifetime of one ring (default 400, max 5000)
+  float click_start_radius_px;  // Ring radius at the click (default 6)
+  float click_end_radius_px;    // Ring radius as it fades out (default 36)
+  float click_ring_width_px;    // Ring thickness (default 4)
+  int32_t click_curve;          // FOCUSFRAME_RIPPLE_*
+  uint32_t click_color;         // 0xRRGGBB of left-click rings
+  uint32_t click_alt_color;     // 0xRRGGBB of right/middle-click rings
+  float click_opacity;          // Ring opacity at the click, fading to 0
+                                // (default 0.6)
+  int32_t cursor_shadow;        // Non-zero: draw a blurred silhouette of the
+                                // sprite beneath it. Made from the scaled
+                                // sprite, so don't bake a shadow into the
+                                // image as well.
+  float shadow_offset_x;        // Shadow displacement in px (default 2, 3)
+  float shadow_offset_y;
+  float shadow_blur_px;         // Blur radius (default 4, max 64; 0 = hard)
+  float shadow_opacity;         // Darkness under opaque sprite pixels
+                                // (default 0.35)
+  uint32_t motion_trail_samples; // > 0: draw up to this many (max 16) fading
+                                 // copies of the cursor along the path since
+                                 // the previous frame; steps under a pixel
+                                 // are skipped, so slow moves get none
+  float motion_trail_falloff;    // Opacity of the newest copy, multiplied in
+                                 // again for each older one (default 0.6)
+  int32_t keystroke_display;     // Non-zero: show RenderExtras.key_events
+                                 // as a badge.
+                                 // A press made while the badge is up joins
+                                 // it (last 6 presses shown).
+  float keystroke_font_size_px;  // Text line height (default 28, max 256)
+  int32_t keystroke_corner;      // FOCUSFRAME_CORNER_*
+  float keystroke_margin_px;     // Gap to the frame edges (default 32)
+  float keystroke_hold_ms;       // Time a press stays on screen, including
+                                 // the fade (default 1500, max 10000)
+  float keystroke_fade_ms;       // Fade-out at the end of the hold
+                                 // (default 300)
+  uint32_t keystroke_text_color; // 0xRRGGBB, drawn opaque (default white)
+  uint32_t keystroke_background_color; // 0xRRGGBB of the rounded badge
+  float keystroke_background_opacity;  // Badge background opacity
+                                       // (default 0.75)
+  int32_t yuv_compositing;       // Non-zero: blend the cursor straight into
+                                 // the decoded YUV frames, skipping two
+                                 // colorspace conversions per frame. Ignored
+                                 // when click ripples, keystrokes, the
+                                 // spotlight, auto_zoom, click_zoom,
+                                 // frame_style, a watermark, redactions,
+                                 // captions, orient_to_velocity or
+                                 // gamma_correct_blend need an RGBA frame.
+  int32_t spotlight;             // Non-zero: darken the frame except for a
+                                 // soft-edged circle around the cursor
+                                 // (below ripples, keystrokes and cursor)
+  float spotlight_radius_px;     // Undimmed radius (default 160, max 2048)
+  float spotlight_feather_px;    // Ramp width from undimmed to fully dimmed
+                                 // outside the radius (default 60, max 512)
+  float spotlight_dim_opacity;   // Brightness taken away outside the circle
+                                 // (0-1, default 0.6)
+  int32_t sampling_quality;      // FOCUSFRAME_SAMPLING_* (ignored by
+                                 // yuv_compositing)
+  float auto_zoom;               // Crop into the frame by this factor and pan
+                                 // to follow the cursor (1 = off, max 4).
+                                 // Other layers keep their sizes.
+  float zoom_margin_x_px;        // Closest the cursor comes to the left/right
+  float zoom_margin_y_px;        // and top/bottom edges of the zoomed frame
+                                 // before the camera pans (default 240/160)
+  float click_zoom;              // Zoom in by this factor around each click
+                                 // (1 = off, max 4; default 1.8 when on).
+                                 // Clicks inside the zoomed view keep it.
+                                 // Takes precedence over auto_zoom.
+  float click_zoom_dwell_ms;     // Time without clicks or cursor movement in
+                                 // the zoomed view before zooming back out
+                                 // (default 1500)
+  float click_zoom_spacing_ms;   // Least time from a zoom-out to the next
+                                 // zoom-in (default 1000)
+  int32_t frame_style;           // Non-zero: shrink the recording onto a
+                                 // background, corners rounded, with a drop
+                                 // shadow. Output size is unchanged; the
+                                 // cursor and effects follow the recording.
+  float frame_padding_percent;   // Gap around it, % of the frame's width
+                                 // and height per side (0-40, default 6)
+  float frame_corner_radius_px;  // Corner radius (default 16)
+  uint32_t frame_background_top;    // 0xRRGGBB at the top and bottom of a
+  uint32_t frame_background_bottom; // vertical gradient; equal = solid
+  float frame_shadow_blur_px;    // Shadow blur radius (0-128, default 32)
+  float frame_shadow_opacity;    // Shadow darkness (0-1, default 0.45)
+  float frame_shadow_offset_x;   // Shadow displacement (default 0, 12)
+  float frame_shadow_offset_y;
+  int32_t watermark_corner;      // FOCUSFRAME_CORNER_* of the logo at
+                                 // RenderExtras.watermark_path (default
+                                 // bottom right)
+  float watermark_margin_x_px;   // Gap to the side edge and the top/bottom
+  float watermark_margin_y_px;   // edge (default 24/24)
+  float watermark_scale;         // Logo size relative to the image (0.05-8)
+  float watermark_opacity;       // Multiplies the logo's own alpha (default
+                                 // 0.6)
+  float caption_max_width_percent; // Widest caption box before its lines
+                                   // wrap, % of the frame width (10-100,
+                                   // default 80)
+  float caption_margin_px;       // Gap between top/bottom captions and the
+                                 // frame edge (default 48)
+  float caption_spacing_px;      // Gap between captions stacked at the same
+                                 // position (default 12)
+  float caption_fade_in_ms;      // Fade at the start and end of each caption
+  float caption_fade_out_ms;     // (0-5000, default 200/200)
+  uint32_t audio_bitrate_kbps;   // AAC bitrate for source audio the container
+                                 // cannot hold as is, e.g. PCM into MP4
+                                 // (32-512, default 192); other audio is
+                                 // copied untouched
+  int32_t encoder_backend;       // FOCUSFRAME_ENCODER_* (default software)
+  int32_t vp9_deadline;          // FOCUSFRAME_VP9_DEADLINE_* (default GOOD)
+  uint32_t vp9_cpu_used;         // libvpx-vp9 speed, 0-8: higher encodes
+                                 // faster into larger files (default 4)
+  int32_t gif_frame_rate;        // FOCUSFRAME_CODEC_GIF only: frames per
+                                 // second, replacing frame_rate (1-50,
+                                 // default 15)
+  uint32_t gif_max_width;        // Wider frames are scaled down to this
+                                 // width (0 = source size, the default)
+  int32_t gif_loop;              // Non-zero = loop forever (default), 0 =
+                                 // play once
+  int32_t rate_control;          // FOCUSFRAME_RATE_CONTROL_* (default CRF)
+  int32_t crf;                   // CRF, lower is better: 0-51, VP9 0-63;
+                                 // -1 = codec default (H.264 18, HEVC 20,
+                                 // VP9 30). NVENC cq / VA-API qp
+  uint32_t video_bitrate_kbps;   // ABR/CBR target (100-200000, default 8000)
+  uint32_t max_bitrate_kbps;     // Peak rate capping CRF/ABR output (0 =
+                                 // none, the default)
+  uint32_t bufsize_kbits;        // Buffer the peak rate is measured over
+                                 // (0 = twice the peak rate)
+  int32_t encoder_preset;        // FOCUSFRAME_PRESET_* (x264/x265 only,
+                                 // default FAST)
+  int32_t encoder_tune;          // FOCUSFRAME_TUNE_* (x264; x265 ignores
+                                 // FILM and STILLIMAGE)
+  uint32_t output_width;         // Output frame size; 0 derives it from the
+  uint32_t output_height;        // other with the source aspect ratio, both
+                                 // 0 keep the source size (rounded down to
+                                 // even, at most 8192)
+  int32_t scale_filter;          // FOCUSFRAME_SCALE_* for that resize
+  uint32_t crop_x;               // Source region to export, rounded down to
+  uint32_t crop_y;               // even (crop_width or crop_height 0 = the
+  uint32_t crop_width;           // whole frame). Cursor points, clicks,
+  uint32_t crop_height;          // redactions and zoom centers stay in
+                                 // source coordinates.
+  double trim_start_ms;          // Input time range to export, in ms from
+  double trim_end_ms;            // the first frame (trim_end_ms <= 0 = to
+                                 // the end). Cursor times stay on the
+                                 // input's clock.
+  float idle_speedup;            // Play idle stretches this much faster (1 =
+                                 // off, at most 16); the output is silent
+                                 // when anything is sped up
+  float idle_min_ms;             // Idle = at least this long with the cursor
+  float idle_max_displacement_px; // within this distance of where it came
+  float idle_max_frame_change_percent; // to rest and at most this much of
+                                 // the picture changing between samples
+                                 // (0 = judge by the cursor alone)
+  int32_t bit_depth;             // Output bits per channel: 8 (0 = 8) or 10
+                                 // (x264 High 10, x265 Main 10, VP9 profile
+                                 // 2). Deeper sources are dithered down to 8.
+  int32_t rotation_handling;     // FOCUSFRAME_ROTATION_* for sources with
+                                 // rotation metadata. Cursor points, clicks,
+                                 // redactions and the crop stay in stored
+                                 // frame coordinates.
+  double cursor_sync_offset_ms;  // Added to each frame's time before the
+                                 // cursor is looked up, after the automatic
+                                 // alignment; +40 draws it where it was 40 ms
+                                 // later (for mouse events lagging the video).
+                                 // Clicks and key presses move with it.
+  int32_t pipelined;             // Non-zero: decode, composite and encode on
+                                 // threads of their own, linked by short
+                                 // queues. The output is the same; callbacks
+                                 // stay on the calling thread. Default 1.
+  int32_t stream_copy;           // Non-zero: copy the input's streams without
+                                 // re-encoding when there is nothing to draw
+                                 // (no cursor points, effects, crop, scaling
+                                 // or idle speed-up). Source codecs are kept;
+                                 // a trim starts at the keyframe at or before
+                                 // trim_start_ms.
+                                 // Otherwise the video is re-encoded with
+                                 // FOCUSFRAME_WARN_STREAM_COPY_UNAVAILABLE.
+  int32_t allow_still_image;     // Non-zero: render an image (or a video of
+                                 // one frame) as a one-frame video instead
+                                 // of failing with -21.
+  int32_t skip_decode_errors;    // Non-zero: leave out video packets that
+                                 // fail to decode, holding the last good
+                                 // frame, with
+                                 // FOCUSFRAME_WARN_UNDECODABLE_PACKET.
+                                 // Otherwise the render fails with -22.
+  int32_t sequence_frame_rate;   // Frame rate (1-240, 0 = 30) of an image
+                                 // sequence input: input_video_path a
+                                 // directory of PNGs whose names end in a
+                                 // frame number, or a pattern such as
+                                 // "frame_%05d.png". A missing number holds
+                                 // the frame before it, with
+                                 // FOCUSFRAME_WARN_SEQUENCE_FRAMES_MISSING.
+  int32_t overlay_only;          // Non-zero: draw only the cursor, clicks,
+                                 // keystrokes, captions and watermark on a
+                                 // transparent background, for compositing
+                                 // over other footage. The input is not
+                                 // decoded, only opened for its frame size;
+                                 // frames run from trim_start_ms to the end
+                                 // of the cursor path, without audio. codec
+                                 // must be PRORES (4444, MOV/MKV) or VP9
+                                 // (WebM/MKV), else -9. Zooms, spotlights,
+                                 // redactions, crops, idle speed-ups and
+                                 // frame styles are refused with -10.
+  int32_t faststart;             // Non-zero: write the MP4/MOV index (moov)
+                                 // before the media data, so web playback
+                                 // starts before the download finishes.
+                                 // Costs a second pass over the file at
+                                 // the end. Other containers fail with -9.
+  int32_t fragmented;            // Non-zero: write MP4/MOV as fragments
+                                 // from each keyframe after an empty index,
+                                 // so a file cut short by a crash plays up
+                                 // to its last fragment. Overrides
+                                 // faststart. Other containers fail with -9.
+  int32_t copy_metadata;         // Non-zero: copy the input's container
+                                 // tags (creation_time, title, ...) to the
+                                 // output, and the tags of its audio stream
+                                 // (e.g. language). The muxer writes its own
+                                 // encoder tag.
+  int32_t preview_mode;          // Non-zero: render a quick proxy of the
+                                 // export for previewing. Each side is 4
+                                 // times smaller, at most 30 fps, 8-bit,
+                                 // with the ultrafast preset at a high CRF,
+                                 // and without motion trails, gamma-correct
+                                 // blending or bicubic sampling. Zooms,
+                                 // smoothing and overlays are kept, drawn at
+                                 // full size before scaling, so the proxy
+                                 // matches the export. ProcessingResult.proxy
+                                 // is set.
+} VideoProcessingConfigV2;
+
+// Click ring radius growth from start to end radius
+#define FOCUSFRAME_RIPPLE_EASE_OUT 0
+#define FOCUSFRAME_RIPPLE_LINEAR 1
+
+// Frame corner the keystroke badge is pinned to
+#define FOCUSFRAME_CORNER_BOTTOM_LEFT 0
+#define FOCUSFRAME_CORNER_BOTTOM_RIGHT 1
+#define FOCUSFRAME_CORNER_TOP_LEFT 2
+#define FOCUSFRAME_CORNER_TOP_RIGHT 3
+
+// Cursor for video frames before the first or after the last cursor sample.
+// HOLD keeps it at the path end; HIDE stops drawing it tail_ms past the end;
+// EXTRAPOLATE keeps the end velocity for tail_ms, then holds.
+#define FOCUSFRAME_TAIL_HOLD 0
+#define FOCUSFRAME_TAIL_HIDE 1
+#define FOCUSFRAME_TAIL_EXTRAPOLATE 2
+
+// Output codec (encoders: libx264, libx265, libvpx-vp9, gif, prores_ks).
+// GIF dithers the whole clip onto one 256-color palette, holding every frame
+// in memory until the end, so inputs longer than 30 s are rejected (-19).
+// ProRes is 422 HQ, or 4444 with alpha for overlay_only; VP9 also carries
+// alpha there.
+#define FOCUSFRAME_CODEC_H264 0
+#define FOCUSFRAME_CODEC_HEVC 1
+#define FOCUSFRAME_CODEC_VP9 2
+#define FOCUSFRAME_CODEC_GIF 3
+#define FOCUSFRAME_CODEC_PRORES 4
+
+// Encoder implementation. Hardware encoders that are missing or fail to open
+// fall back to software with a warning; deterministic renders always use
+// software. AUTO tries VideoToolbox on macOS, NVENC then VA-API on Linux and
+// NVENC elsewhere. VideoToolbox and VA-API encode at a fixed bitrate instead
+// of constant quality; VideoToolbox and NVENC have no VP9 encoder.
+#define FOCUSFRAME_ENCODER_SOFTWARE 0
+#define FOCUSFRAME_ENCODER_AUTO 1
+#define FOCUSFRAME_ENCODER_VIDEOTOOLBOX 2
+#define FOCUSFRAME_ENCODER_NVENC 3
+#define FOCUSFRAME_ENCODER_VAAPI 4
+
+// libvpx-vp9 time per frame. BEST is many times slower than GOOD for slightly
+// smaller files; REALTIME is fastest. With GOOD, vp9_cpu_used above 5 acts
+// as 5.
+#define FOCUSFRAME_VP9_DEADLINE_GOOD 0
+#define FOCUSFRAME_VP9_DEADLINE_BEST 1
+#define FOCUSFRAME_VP9_DEADLINE_REALTIME 2
+
+// Video rate control. CRF holds quality constant and lets size follow the
+// content; ABR hits video_bitrate_kbps on average; CBR holds it throughout
+// (signalled as CBR in the H.264 stream). max_bitrate_kbps caps CRF and ABR.
+// GIF ignores all of these.
+#define FOCUSFRAME_RATE_CONTROL_CRF 0
+#define FOCUSFRAME_RATE_CONTROL_ABR 1
+#define FOCUSFRAME_RATE_CONTROL_CBR 2
+
+// x264/x265 preset: slower presets compress better at the same quality
+#define FOCUSFRAME_PRESET_ULTRAFAST 0
+#define FOCUSFRAME_PRESET_SUPERFAST 1
+#define FOCUSFRAME_PRESET_VERYFAST 2
+#define FOCUSFRAME_PRESET_FASTER 3
+#define FOCUSFRAME_PRESET_FAST 4
+#define FOCUSFRAME_PRESET_MEDIUM 5
+#define FOCUSFRAME_PRESET_SLOW 6
+#define FOCUSFRAME_PRESET_SLOWER 7
+#define FOCUSFRAME_PRESET_VERYSLOW 8
+#define FOCUSFRAME_PRESET_PLACEBO 9
+
+// x264 tune (ANIMATION suits most screen recordings)
+#define FOCUSFRAME_TUNE_NONE 0
+#define FOCUSFRAME_TUNE_FILM 1
+#define FOCUSFRAME_TUNE_ANIMATION 2
+#define FOCUSFRAME_TUNE_GRAIN 3
+#define FOCUSFRAME_TUNE_STILLIMAGE 4
+#define FOCUSFRAME_TUNE_FASTDECODE 5
+#define FOCUSFRAME_TUNE_ZEROLATENCY 6
+
+// Output resize filter (AUTO = bilinear, Lanczos beyond a 2x downscale)
+#define FOCUSFRAME_SCALE_AUTO 0
+#define FOCUSFRAME_SCALE_BILINEAR 1
+#define FOCUSFRAME_SCALE_BICUBIC 2
+#define FOCUSFRAME_SCALE_LANCZOS 3
+#define FOCUSFRAME_SCALE_AREA 4
+
+// Source rotation metadata (display matrix, e.g. from phone recordings)
+#define FOCUSFRAME_ROTATION_ROTATE 0 // Turn the frames upright, drop the metadata
+#define FOCUSFRAME_ROTATION_KEEP 1   // Encode frames as stored, copy the metadata
+
+// First smoothing pass. SPRING uses responsiveness/smoothness; ONE_EURO
+// adapts its cutoff to cursor speed (less lag on fast flicks) and uses the
+// one_euro_* fields instead. KALMAN (constant-velocity model) suits sparse,
+// irregular event rates and uses the kalman_* fields. BEZIER_STROKES replaces
+// both passes: each gesture between pauses becomes one eased cubic Bezier
+// (the interpolation and simplify_epsilon_px settings are ignored).
+#define FOCUSFRAME_SMOOTHING_SPRING 0
+#define FOCUSFRAME_SMOOTHING_ONE_EURO 1
+#define FOCUSFRAME_SMOOTHING_KALMAN 2
+#define FOCUSFRAME_SMOOTHING_BEZIER_STROKES 3
+#define FOCUSFRAME_SMOOTHING_PASSTHROUGH 4 // No filtering, straight lines between
+                                           // samples (for checking capture sync)
+
+// Upsampling to the frame rate. HERMITE uses the smoothing pass's velocities
+// as tangents and avoids the slight corner-cutting of CATMULL_ROM.
+#define FOCUSFRAME_INTERP_CATMULL_ROM 0
+#define FOCUSFRAME_INTERP_HERMITE 1
+
+// Cursor sprite resampling. BICUBIC (Catmull-Rom) keeps sprites drawn above
+// 1x sharp but costs several times more per cursor pixel than BILINEAR.
+#define FOCUSFRAME_SAMPLING_BILINEAR 0
+#define FOCUSFRAME_SAMPLING_BICUBIC 1
+
+// Smoothed positions outside the video frame (spline overshoot at screen
+// edges). CLAMP pins them to the edge pixel, REFLECT mirrors the overshoot.
+#define FOCUSFRAME_BOUNDS_OFF 0
+#define FOCUSFRAME_BOUNDS_CLAMP 1
+#define FOCUSFRAME_BOUNDS_REFLECT 2
+
+// Unit of CPoint.timestamp_ms in cursor input. AUTO treats recordings
+// spanning fewer than 1000 units as seconds, which misreads sub-second
+// millisecond clips; set the unit explicitly when it is known.
+#define FOCUSFRAME_TIME_AUTO 0
+#define FOCUSFRAME_TIME_MILLISECONDS 1
+#define FOCUSFRAME_TIME_SECONDS 2
+#define FOCUSFRAME_TIME_MICROSECONDS 3
+
+// Cursor samples outside the capture region. HIDE draws no cursor there
+// (smoothed paths contain NaN positions); CLAMP pins it to the region edge.
+#define FOCUSFRAME_OUTSIDE_HIDE 0
+#define FOCUSFRAME_OUTSIDE_CLAMP 1
+
+// Output container. AUTO infers it from the output file extension.
+// MP4 accepts H.264 and HEVC, MOV those and ProRes, MKV all codecs but GIF,
+// WebM only VP9 and GIF only GIF.
+#define FOCUSFRAME_CONTAINER_AUTO 0
+#define FOCUSFRAME_CONTAINER_MP4 1
+#define FOCUSFRAME_CONTAINER_MKV 2
+#define FOCUSFRAME_CONTAINER_MOV 3
+#define FOCUSFRAME_CONTAINER_WEBM 4
+#define FOCUSFRAME_CONTAINER_GIF 5
+
+// Properties of the best video stream in a file
+typedef struct {
+  uint32_t width;
+  uint32_t height;
+  int32_t fps_num;
+  int32_t fps_den;
+  int64_t duration_ms; // -1 if unknown
+  int32_t codec_id;    // FFmpeg AVCodecID
+  int64_t nb_frames;   // estimated from the duration if the container does
+                       // not report it; 0 if unknown
+} VideoMetadata;
+
+// Summary of a completed render
+typedef struct {
+  uint64_t frames_encoded;
+  double output_duration_ms;
+  uint64_t output_file_size_bytes;
+  double wall_clock_ms;
+  uint64_t frames_where_cursor_was_clamped; // frames outside the cursor path
+  uint64_t warnings_cursor_outside_frame;   // cursor position off the frame
+  uint64_t warnings_frames_dropped;         // source frames dropped by fps
+  uint64_t warnings_frames_duplicated;      // output frames repeated by fps
+  uint64_t warnings_sprite_clipped;         // sprite partly past the edge
+  uint64_t warnings_outliers_rejected;      // glitch samples dropped
+  int32_t encoder_backend;                  // FOCUSFRAME_ENCODER_* that encoded
+                                            // the video (never AUTO)
+  double source_duration_ms;                // input covered; longer than the
+                                            // output with idle_speedup
+  uint64_t warnings_stream_copy_unavailable; // stream_copy fell back
+  int32_t stream_copied; // non-zero: streams copied, not re-encoded
+                         // (frames_encoded counts video packets)
+  uint64_t warnings_undecodable_packets; // skip_decode_errors skipped these
+  uint64_t warnings_sequence_frames_missing; // gaps in an image sequence
+  int32_t proxy; // non-zero: a preview_mode proxy, not the export
+} ProcessingResult;
+
+// Motion statistics of a smoothed path (compute_smoothed_path_with_metrics)
+typedef struct {
+  double max_speed_px_per_sec;
+  double max_acceleration_px_per_sec2;
+  double rms_jerk_px_per_sec3; // lower = smoother
+  double max_deviation_px;     // vs. the raw sample nearest in time; high
+                               // values mean lag or cut corners
+  double total_length_px;
+} PathMetrics;
+
+// Progress callback function pointer type. `percent` is 0.0-1.0, or -1.0
+// while rendering an input of unknown length (the heartbeat's frame_index
+// still counts frames).
 typedef void (*ProgressCallback)(void *user_data, float percent);
 
+// Timing for the frames encoded since the previous heartbeat (microseconds)
+typedef struct {
+  uint64_t frame_index;        // Last frame of the interval
+  uint32_t frames_in_interval;
+  uint64_t decode_us;          // Demux + decode + fps/format filtering
+  uint64_t composite_us;       // Cursor compositing
+  uint64_t encode_us;          // Color conversion + encode + mux
+} RenderHeartbeat;
+
+// Heartbeat callback; heartbeat is only valid for the duration of the call
+typedef void (*HeartbeatCallback)(void *user_data,
+                                  const RenderHeartbeat *heartbeat);
+
+// Warning codes passed to WarningCallback
+#define FOCUSFRAME_WARN_TIMESTAMP_CLAMPED 1
+#define FOCUSFRAME_WARN_CURSOR_OUTSIDE_FRAME 2
+#define FOCUSFRAME_WARN_FRAME_DROPPED 3
+#define FOCUSFRAME_WARN_FRAME_DUPLICATED 4
+#define FOCUSFRAME_WARN_SPRITE_CLIPPED 5
+#define FOCUSFRAME_WARN_OUTLIERS_REJECTED 6 // Once, before rendering (frame = -1)
+#define FOCUSFRAME_WARN_STREAM_COPY_UNAVAILABLE 7 // Once, before rendering
+#define FOCUSFRAME_WARN_UNDECODABLE_PACKET 8
+#define FOCUSFRAME_WARN_SEQUENCE_FRAMES_MISSING 9 // Once, before rendering
+
+// Non-fatal warning. Rate-limited: only the first occurrence of each code is
+// reported with its frame index; after the render a summary with frame = -1
+// follows for every code seen more than once. message is only valid for the
+// duration of the call.
+typedef void (*WarningCallback)(void *user_data, int code, int64_t frame,
+                                const char *message);
+
+// Callbacks for process_video_with_cursor_v2. Any field may be NULL/0.
+typedef struct {
+  ProgressCallback progress;
+  HeartbeatCallback heartbeat;
+  uint32_t heartbeat_interval_frames; // 0 = every 60 frames
+  WarningCallback warning;
+  void *user_data; // Passed to every callback
+} ProcessingCallbacks;
+
 /**
  * Process video with cursor smoothing and overlay in one call.
+ * Thread-safe: multiple renders may run in parallel on different outputs.
  *
  * Returns:
  *   0: Success
  *  -1: Null pointer argument
- *  -2: Invalid UTF-8 in path
+ *  -2: Path not representable (invalid UTF-8 on Windows, interior NUL)
  *  -3: Cursor path smoothing error
  *  -4: Video rendering error
+ *  -5: Input has no video stream (the message lists the streams it has)
+ *  -6: Input could not be opened (or an image sequence has no frames, or
+ *      two files with the same frame number)
+ *  -7: frame_rate out of range (must be 1-240)
+ *  -8: Fewer than 4 cursor points
+ *  -9: Codec/container mismatch, encoder missing from the FFmpeg build,
+ *      no 10-bit encoder for bit_depth 10, or a codec without alpha for
+ *      overlay_only, or faststart/fragmented for a container other than
+ *      MP4 or MOV
+ * -10: Unknown codec, container, smoothing mode, interpolation, bounds,
+ *      timestamp unit, capture_outside or bit_depth value (v2 config
+ *      only), or malformed/overlapping zoom keyframes, or a crop region
+ *      outside the input frame, or a trim end not after its start, or an
+ *      effect overlay_only cannot draw without the source video, or an
+ *      empty metadata tag key
+ * -11: Internal panic (caught; details in focusframe_last_error_message)
+ * -12: Frame buffer too small for stride * height (composite_cursor_on_buffer)
+ * -13: Cursor path builder already sealed by a render
+ * -14: Cursor points file could not be read
+ * -15: Cursor points file has the wrong magic
+ * -16: Cursor points file version not supported
+ * -17: Cursor points file truncated or corrupt
+ * -18: Cursor timestamps go backwards by more than 5 ms
+ * -19: Input longer than 30 s for GIF export
+ * -20: Trim start at or past the end of the input
+ * -21: Input is a still image (see allow_still_image)
+ * -22: A video packet could not be decoded (see skip_decode_errors)
+ *
+ * responsiveness, smoothness and smoothing_alpha are clamped to 0-1
+ * (with a logged warning) before processing.
  */
 int32_t process_video_with_cursor(
     const char *input_video_path, const char *output_video_path,
     const char *cursor_sprite_path, const CPoint *raw_cursor_points,
     size_t raw_cursor_points_len, const VideoProcessingConfig *config,
     ProgressCallback progress_callback, // Can be NULL
-    void *user_data                     // ADDED: Context pointer
+    void *user_data,                    // ADDED: Context pointer
+    ProcessingResult *result            // Can be NULL; filled on success
 );
 
+/**
+ * Same as process_video_with_cursor, additionally applying cursor_scale,
+ * hotspot, cursor_opacity and the requested codec/container. The v1 function
+ * draws the sprite at native size with its top-left corner on the cursor
+ * position and always encodes H.264.
+ *
+ * callbacks may be NULL. The heartbeat fires every heartbeat_interval_frames
+ * encoded frames for the whole render; a long gap between heartbeats means
+ * the pipeline is stalled.
+ */
+int32_t process_video_with_cursor_v2(
+    const char *input_video_path, const char *output_video_path,
+    const char *cursor_sprite_path, const CPoint *raw_cursor_points,
+    size_t raw_cursor_points_len, const VideoProcessingConfigV2 *config,
+    const ProcessingCallbacks *callbacks, ProcessingResult *result);
+
+/**
+ * process_video_with_cursor_v2 with any mix of the extras in extras (NULL for
+ * none), e.g. captions together with a watermark, redactions and zoom
+ * keyframes in one render. With extras->cursor_events set, raw_cursor_points
+ * is ignored and may be NULL.
+ */
+int32_t process_video_ex(
+    const char *input_video_path, const char *output_video_path,
+    const char *cursor_sprite_path, const CPoint *raw_cursor_points,
+    size_t raw_cursor_points_len, const RenderExtras *extras,
+    const VideoProcessingConfigV2 *config, const ProcessingCallbacks *callbacks,
+    ProcessingResult *result);
+
+// Opaque, growable cursor point buffer for very long recordings
+typedef struct CursorPathBuilder CursorPathBuilder;
+
+/**
+ * Create an empty builder; release with cursor_path_builder_free().
+ */
+const CursorPathBuilder *cursor_path_builder_new(void);
+
+/**
+ * Append len points (copied). Returns 0, -1 for NULL arguments, or -13 once
+ * a render using this builder has started.
+ */
+int cursor_path_builder_append(const CursorPathBuilder *builder,
+                               const CPoint *points, size_t len);
+
+/**
+ * Number of points appended so far (0 for NULL).
+ */
+size_t cursor_path_builder_len(const CursorPathBuilder *builder);
+
+/**
+ * Release the builder. NULL is ignored. A render that uses it keeps its own
+ * reference, taken inside process_video_with_cursor_builder: free the builder
+ * only once that call has returned or reported progress, never concurrently
+ * with its entry or with any other call on the builder.
+ */
+void cursor_path_builder_free(const CursorPathBuilder *builder);
+
+/**
+ * process_video_with_cursor_v2 with the points taken from builder, without
+ * copying them again. Seals the builder against further appends.
+ */
+int32_t process_video_with_cursor_builder(
+    const char *input_video_path, const char *output_video_path,
+    const char *cursor_sprite_path, const CursorPathBuilder *builder,
+    const VideoProcessingConfigV2 *config,
+    const ProcessingCallbacks *callbacks, ProcessingResult *result);
+
+/**
+ * process_video_with_cursor_v2 with the points read from a sidecar file.
+ * Format (little-endian): "FFCP", u32 version (1), u64 count, then count
+ * records of { float x; float y; double timestamp_ms; } (16 bytes each).
+ */
+int32_t process_video_with_cursor_from_file(
+    const char *input_video_path, const char *output_video_path,
+    const char *cursor_sprite_path, const char *points_file_path,
+    const VideoProcessingConfigV2 *config,
+    const ProcessingCallbacks *callbacks, ProcessingResult *result);
+
+/**
+ * Fill out with the recommended defaults (alpha 0.5, responsiveness 0.5,
+ * smoothness 0.7, 60 fps, info logging).
+ */
+void video_processing_config_default(VideoProcessingConfig *out);
+
+/**
+ * Fill out with the v1 defaults plus a native-size (scale 1.0), fully opaque
+ * cursor with its hotspot at the top-left corner (or the sprite's own hotspot
+ * for .cur/.ani files). Also sets struct_size.
+ */
+void video_processing_config_v2_default(VideoProcessingConfigV2 *out);
+
+// Validation flags returned by validate_processing_inputs (bitmask)
+#define VALID_INPUT_VIDEO_UNREADABLE (1 << 0)
+#define VALID_INPUT_NO_VIDEO_STREAM (1 << 1)
+#define VALID_INPUT_VIDEO_UNDECODABLE (1 << 2)
+#define VALID_SPRITE_UNREADABLE (1 << 3)
+#define VALID_TOO_FEW_POINTS (1 << 4)
+#define VALID_NON_MONOTONIC_TIMESTAMPS (1 << 5)
+#define VALID_NON_FINITE_POINT (1 << 6)
+#define VALID_BAD_FRAME_RATE (1 << 7)
+#define VALID_UNSUPPORTED_OUTPUT (1 << 8)
+
+/**
+ * Check all inputs before starting an export. Every problem is reported,
+ * not just the first one.
+ *
+ * Returns:
+ *   0: All inputs valid
+ *  >0: Bitmask of VALID_* flags
+ *  -1: Null pointer argument (paths or config)
+ *  -2: Invalid UTF-8 in path
+ * -11: Internal panic
+ */
+int validate_processing_inputs(const char *input_video_path,
+                               const char *cursor_sprite_path,
+                               const CPoint *raw_cursor_points,
+                               size_t raw_cursor_points_len,
+                               const VideoProcessingConfig *config);
+
+/**
+ * Read width/height, frame rate, duration and codec of the best video stream.
+ *
+ * Returns 0 on success, -1 for NULL arguments, -2 for an unusable path,
+ * -5 if the file has no video stream (e.g. audio-only), -6 if unreadable,
+ * -11 on an internal panic.
+ */
+int get_video_metadata(const char *input_video_path, VideoMetadata *out);
+
+/**
+ * Render one frame at timestamp_ms (milliseconds of video time) with the
+ * smoothed cursor composited on top, and write it to output_png_path.
+ * Timestamps past the end of the video clamp to the last frame.
+ *
+ * Returns the same error codes as process_video_with_cursor.
+ */
+int render_preview_frame(const char *input_video_path,
+                         const char *cursor_sprite_path,
+                         const CPoint *raw_cursor_points,
+                         size_t raw_cursor_points_len,
+                         const VideoProcessingConfig *config,
+                         double timestamp_ms, const char *output_png_path);
+
+/**
+ * render_preview_frame with the cursor appearance from VideoProcessingConfigV2.
+ */
+int render_preview_frame_v2(const char *input_video_path,
+                            const char *cursor_sprite_path,
+                            const CPoint *raw_cursor_points,
+                            size_t raw_cursor_points_len,
+                            const VideoProcessingConfigV2 *config,
+                            double timestamp_ms, const char *output_png_path);
+
+/**
+ * Write the frame of video_path shown at timestamp_ms to out_png_path as an
+ * RGB PNG, at most max_width pixels wide with the aspect ratio kept
+ * (0 = full size). Timestamps past the end give the last frame. For a
+ * poster frame, call it on a finished export at about 10% of its duration.
+ *
+ * Returns 0 on success, -1 for NULL arguments, -2 for an unusable path,
+ * -5 if the file has no video stream, -6 if unreadable, -11 on an internal
+ * panic, or another process_video_with_cursor code if decoding or writing
+ * the PNG fails.
+ */
+int generate_thumbnail(const char *video_path, const char *out_png_path,
+                       double timestamp_ms, uint32_t max_width);
+
+/*
+ * Paths: on Unix, const char * paths are passed to the filesystem byte for
+ * byte, so non-UTF-8 names work. On Windows they must be UTF-8; use the _w
+ * variants below to pass native UTF-16 paths instead.
+ */
+#ifdef _WIN32
+int32_t process_video_with_cursor_v2_w(
+    const wchar_t *input_video_path, const wchar_t *output_video_path,
+    const wchar_t *cursor_sprite_path, const CPoint *raw_cursor_points,
+    size_t raw_cursor_points_len, const VideoProcessingConfigV2 *config,
+    const ProcessingCallbacks *callbacks, ProcessingResult *result);
+
+int get_video_metadata_w(const wchar_t *input_video_path, VideoMetadata *out);
+
+int render_preview_frame_v2_w(const wchar_t *input_video_path,
+                              const wchar_t *cursor_sprite_path,
+                              const CPoint *raw_cursor_points,
+                              size_t raw_cursor_points_len,
+                              const VideoProcessingConfigV2 *config,
+                              double timestamp_ms,
+                              const wchar_t *output_png_path);
+
+int generate_thumbnail_w(const wchar_t *video_path,
+                         const wchar_t *out_png_path, double timestamp_ms,
+                         uint32_t max_width);
+#endif
+
+/**
+ * Draw the cursor sprite onto a caller-owned RGBA8 frame (no FFmpeg involved).
+ * stride is the row pitch in bytes (>= width * 4); frame_len must be at least
+ * stride * height. (x, y) may lie partially or fully outside the frame.
+ * The sprite is decoded once and cached by path until the file's size or
+ * modification time changes; the 32 most recently used sprites are kept.
+ * Animated sprites show their first frame.
+ *
+ * Returns 0 on success, -1 for NULL arguments, -2 for invalid UTF-8,
+ * -4 if the sprite cannot be loaded, -12 for an undersized buffer.
+ */
+int composite_cursor_on_buffer(uint8_t *frame, size_t frame_len, uint32_t width,
+                               uint32_t height, uint32_t stride,
+                               const char *sprite_path, float x, float y);
+
+/**
+ * Same as composite_cursor_on_buffer, but an animated sprite (GIF/APNG/ANI)
+ * shows the frame due timestamp_ms into its loop instead of its first frame.
+ */
+int composite_cursor_on_buffer_at(uint8_t *frame, size_t frame_len,
+                                  uint32_t width, uint32_t height,
+                                  uint32_t stride, const char *sprite_path,
+                                  float x, float y, double timestamp_ms);
+
+/**
+ * Drop the cached sprite for sprite_path, or every cached sprite when
+ * sprite_path is NULL. Edited files are reloaded without this.
+ */
+void invalidate_cursor_sprite_cache(const char *sprite_path);
+
+/**
+ * Run exactly the smoothing process_video_with_cursor_v2 applies, without
+ * decoding or rendering anything (for drawing the path in the UI).
+ * On failure (NULL arguments, fewer than 2 points, bad frame_rate) the
+ * returned path is empty; query focusframe_last_error_code() for the reason.
+ * Caller must free result with free_smoothed_path().
+ */
+CSmoothedPath compute_smoothed_path(const CPoint *raw_cursor_points,
+                                    size_t raw_cursor_points_len,
+                                    const VideoProcessingConfigV2 *config);
+
+/**
+ * compute_smoothed_path that also fills metrics (may be NULL) with motion
+ * statistics of the returned path. metrics is untouched on failure.
+ */
+CSmoothedPath compute_smoothed_path_with_metrics(
+    const CPoint *raw_cursor_points, size_t raw_cursor_points_len,
+    const VideoProcessingConfigV2 *config, PathMetrics *metrics);
+
+/**
+ * compute_smoothed_path for event-tagged samples (see
+ * RenderExtras.cursor_events).
+ */
+CSmoothedPath compute_smoothed_path_events(const CEventPoint *cursor_events,
+                                           size_t cursor_events_len,
+                                           const VideoProcessingConfigV2 *config);
+
 /**
  * Smooth cursor path using Catmull-Rom splines.
  * Caller must free result with free_smoothed_path().
@@ -58,7 +1066,43 @@ CSmoothedPath smooth_cursor_path(const CPoint *raw_points_ptr,
                                  float tension, float friction, float mass);
 
 /**
- * Free memory allocated by smooth_cursor_path.
+ * Smooth cursor path with the dual-pass pipeline used for video rendering
+ * (spring-damper physics filter + centripetal Catmull-Rom upsampling).
+ *
+ * responsiveness/smoothness are in the 0-1 range (see VideoProcessingConfig).
+ * Returns an empty path for NULL input or frame_rate <= 0.
+ * Caller must free result with free_smoothed_path().
+ */
+CSmoothedPath smooth_cursor_path_v2(const CPoint *raw_points_ptr,
+                                    size_t raw_points_len, int32_t frame_rate,
+                                    float responsiveness, float smoothness,
+                                    float alpha);
+
+/**
+ * Run only the spring-damper filter (the first smoothing pass) at the input's
+ * native rate. The result has exactly raw_points_len points with the input
+ * timestamps unchanged; it is empty only for NULL/empty input.
+ * responsiveness/smoothness are clamped to 0-1.
+ * Caller must free result with free_smoothed_path().
+ */
+CSmoothedPath physics_filter_path(const CPoint *raw_points_ptr,
+                                  size_t raw_points_len, float responsiveness,
+                                  float smoothness);
+
+/**
+ * Run only the Catmull-Rom upsampling (the second smoothing pass). Output
+ * point i has timestamp start + i * (1000 / frame_rate) exactly, so it can be
+ * indexed by frame number. A single point or a zero-duration path returns one
+ * point. Returns an empty path for NULL/empty input or frame_rate outside
+ * 1-240 (-7 via focusframe_last_error_code).
+ * Caller must free result with free_smoothed_path().
+ */
+CSmoothedPath resample_path_to_framerate(const CPoint *points_ptr,
+                                         size_t points_len, int32_t frame_rate,
+                                         float alpha);
+
+/**
+ * Free memory allocated by any function returning a CSmoothedPath.
  */
 void free_smoothed_path(CSmoothedPath path);
 
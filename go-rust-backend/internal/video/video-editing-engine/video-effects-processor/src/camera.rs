@@ -0,0 +1,547 @@
+// camera.rs - Zoom: a crop rectangle per frame, following the cursor, around
+// clicks or from editor keyframes, and the layer that crops and scales frames
+// to it
+
+use crate::constants::{
+    CAMERA_RESPONSIVENESS, CAMERA_SMOOTHNESS, CLICK_ZOOM_ACTIVITY_SPEED_PX_PER_SEC,
+    ZOOM_TRANSITION_MS,
+};
+use crate::renderer::{FrameView, Overlay};
+use crate::smoothing::{apply_physics_filter, CClickEvent, CPoint, SpringTuning};
+use serde::Deserialize;
+use std::cell::RefCell;
+
+/// Magnified view that pans to follow the cursor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoZoom {
+    /// Magnification (1.0 = the whole frame, 2.0 = half its width and height)
+    pub zoom: f32,
+    /// Closest the cursor comes to the left/right and top/bottom edges of the
+    /// rendered frame before the camera pans (output px)
+    pub margin_x_px: f32,
+    pub margin_y_px: f32,
+}
+
+impl Default for AutoZoom {
+    fn default() -> Self {
+        Self {
+            zoom: 1.5,
+            margin_x_px: 240.0,
+            margin_y_px: 160.0,
+        }
+    }
+}
+
+/// Zoom in around clicks and back out once the activity there stops
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClickZoom {
+    /// Magnification while zoomed in
+    pub zoom: f32,
+    /// Time (ms) without clicks or cursor movement inside the zoomed view
+    /// before zooming back out
+    pub dwell_ms: f32,
+    /// Least time (ms) from one zoom-out to the next zoom-in. Clicks outside
+    /// the zoomed view before then are ignored.
+    pub min_spacing_ms: f32,
+}
+
+impl Default for ClickZoom {
+    fn default() -> Self {
+        Self {
+            zoom: 1.8,
+            dwell_ms: 1500.0,
+            min_spacing_ms: 1000.0,
+        }
+    }
+}
+
+/// Zoom into a fixed point for a time range, e.g. "2x on (800, 400) from 5 s
+/// to 9 s". The zoom eases in over the first `ZOOM_TRANSITION_MS` of the
+/// range and back out to the full frame over the last.
+///
+/// Parsed from JSON as an object with these field names; `easing` is
+/// optional.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ZoomKeyframe {
+    pub start_ms: f64,
+    pub end_ms: f64,
+    /// Magnification at the hold (1.0-4.0)
+    pub zoom: f32,
+    /// Source point kept centered while zoomed (moved in from the frame
+    /// edges when the crop would leave the frame)
+    pub center_x: f32,
+    pub center_y: f32,
+    #[serde(default)]
+    pub easing: ZoomEasing,
+}
+
+/// Curve of a `ZoomKeyframe`'s transitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoomEasing {
+    /// Symmetric ease-in-out
+    #[default]
+    Smoothstep,
+    /// Critically damped spring: leaves quickly and settles gently
+    Spring,
+}
+
+/// Critically damped step response 1 - (1 + kt)e^(-kt) is within 2% of
+/// its target at kt = 5.83
+const SPRING_SETTLING: f32 = 5.83;
+
+impl ZoomEasing {
+    /// Eased `t` (0-1); 0 and 1 map to themselves
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Smoothstep => t * t * (3.0 - 2.0 * t),
+            Self::Spring => {
+                let step =
+                    |t: f32| 1.0 - (1.0 + SPRING_SETTLING * t) * (-SPRING_SETTLING * t).exp();
+                step(t) / step(1.0)
+            }
+        }
+    }
+}
+
+impl ZoomKeyframe {
+    /// How far the view is zoomed in at `timestamp_ms`: 0 = full frame,
+    /// 1 = this keyframe's crop
+    fn weight(&self, timestamp_ms: f64) -> f32 {
+        let ramp = ZOOM_TRANSITION_MS.min((self.end_ms - self.start_ms) / 2.0);
+        if ramp <= 0.0 {
+            return 0.0;
+        }
+        let into = (timestamp_ms - self.start_ms).min(self.end_ms - timestamp_ms) / ramp;
+        self.easing.apply(into.clamp(0.0, 1.0) as f32)
+    }
+}
+
+/// Part of the source frame shown in an output frame (source px)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl CropRect {
+    /// `width` x `height` around (`cx`, `cy`), moved inside the frame
+    fn centered(cx: f32, cy: f32, width: f32, height: f32, frame: (f32, f32)) -> Self {
+        Self {
+            x: (cx - width / 2.0).clamp(0.0, frame.0 - width),
+            y: (cy - height / 2.0).clamp(0.0, frame.1 - height),
+            width,
+            height,
+        }
+    }
+
+    fn contains(&self, x: f32, y: f32) -> bool {
+        (self.x..=self.x + self.width).contains(&x) && (self.y..=self.y + self.height).contains(&y)
+    }
+
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        Self {
+            x: mix(self.x, to.x),
+            y: mix(self.y, to.y),
+            width: mix(self.width, to.width),
+            height: mix(self.height, to.height),
+        }
+    }
+}
+
+/// The crop rectangle of every output frame
+pub struct CameraPlan {
+    frame_width: f32,
+    frame_height: f32,
+    shots: Shots,
+}
+
+enum Shots {
+    /// Crop of each cursor path sample, in path order
+    Follow(Vec<(f64, CropRect)>),
+    /// Sorted by start time, not overlapping
+    Keyframes(Vec<ZoomKeyframe>),
+}
+
+impl CameraPlan {
+    /// Follow the cursor along `path` at a fixed zoom.
+    ///
+    /// The camera holds still while the cursor moves inside the margins and
+    /// otherwise chases the nearest position that puts it back inside, through
+    /// the same spring the cursor path uses, so it glides instead of jittering.
+    /// The spring's lag is then capped: the cursor never leaves the margins
+    /// except where the crop is already against a frame edge.
+    pub fn new(path: &[CPoint], frame_width: u32, frame_height: u32, zoom: &AutoZoom) -> Self {
+        let magnification = zoom.zoom.max(1.0);
+        let (frame_w, frame_h) = (frame_width as f32, frame_height as f32);
+        let width = frame_w / magnification;
+        let height = frame_h / magnification;
+        // Distance from the crop center the cursor may stray (source px)
+        let reach_x = (width / 2.0 - zoom.margin_x_px.max(0.0) / magnification).max(0.0);
+        let reach_y = (height / 2.0 - zoom.margin_y_px.max(0.0) / magnification).max(0.0);
+        let keep_in_frame = |cx: f32, cy: f32| {
+            (
+                cx.clamp(width / 2.0, frame_w - width / 2.0),
+                cy.clamp(height / 2.0, frame_h - height / 2.0),
+            )
+        };
+        let follow = |(cx, cy): (f32, f32), p: &CPoint| {
+            if !(p.x.is_finite() && p.y.is_finite()) {
+                // Hidden cursor: the camera stays where it is
+                return keep_in_frame(cx, cy);
+            }
+            keep_in_frame(
+                cx.clamp(p.x - reach_x, p.x + reach_x),
+                cy.clamp(p.y - reach_y, p.y + reach_y),
+            )
+        };
+
+        // 1. Dead-zone target: the smallest move that brings the cursor back
+        // inside the margins
+        let start = path
+            .iter()
+            .find(|p| p.x.is_finite() && p.y.is_finite())
+            .map_or((frame_w / 2.0, frame_h / 2.0), |p| keep_in_frame(p.x, p.y));
+        let mut center = start;
+        let targets: Vec<CPoint> = path
+            .iter()
+            .map(|p| {
+                center = follow(center, p);
+                CPoint {
+                    x: center.0,
+                    y: center.1,
+                    timestamp_ms: p.timestamp_ms,
+                }
+            })
+            .collect();
+
+        // 2. Glide toward it, then pull the cursor back inside wherever the
+        // spring lags too far behind
+        let tuning = SpringTuning {
+            physics_model_v2: true,
+            ..SpringTuning::default()
+        };
+        let glide =
+            apply_physics_filter(&targets, CAMERA_RESPONSIVENESS, CAMERA_SMOOTHNESS, &tuning);
+        let crops = path
+            .iter()
+            .zip(&glide)
+            .map(|(p, c)| {
+                let (cx, cy) = follow((c.x, c.y), p);
+                let crop = CropRect {
+                    x: cx - width / 2.0,
+                    y: cy - height / 2.0,
+                    width,
+                    height,
+                };
+                (p.timestamp_ms, crop)
+            })
+            .collect();
+
+        Self {
+            frame_width: frame_w,
+            frame_height: frame_h,
+            shots: Shots::Follow(crops),
+        }
+    }
+
+    /// Zoom in at each click, the view centered on it, and back out after
+    /// `dwell_ms` without activity inside the view; `path` is the smoothed
+    /// cursor path, whose samples also time the crops.
+    ///
+    /// Clicks inside the view before it zooms out join the same zoom (so a
+    /// burst of clicks is one zoom, not a pumping in and out). Cursor
+    /// movement inside the view holds it too. The crop moves between full
+    /// frame and zoomed view through the camera spring.
+    pub fn from_clicks(
+        clicks: &[CClickEvent],
+        path: &[CPoint],
+        frame_width: u32,
+        frame_height: u32,
+        zoom: &ClickZoom,
+    ) -> Self {
+        let (frame_w, frame_h) = (frame_width as f32, frame_height as f32);
+        let full = CropRect {
+            x: 0.0,
+            y: 0.0,
+            width: frame_w,
+            height: frame_h,
+        };
+        let magnification = zoom.zoom.max(1.0);
+        let (width, height) = (frame_w / magnification, frame_h / magnification);
+        let dwell_ms = zoom.dwell_ms.max(0.0) as f64;
+        let spacing_ms = zoom.min_spacing_ms.max(0.0) as f64;
+
+        let mut clicks: Vec<&CClickEvent> = clicks
+            .iter()
+            .filter(|c| c.x.is_finite() && c.y.is_finite() && c.timestamp_ms.is_finite())
+            .collect();
+        clicks.sort_by(|a, b| a.timestamp_ms.total_cmp(&b.timestamp_ms));
+
+        // 1. Zoomed stretches: (start, last activity, view)
+        let mut regions: Vec<(f64, f64, CropRect)> = Vec::new();
+        for click in clicks {
+            if let Some((_, active_ms, view)) = regions.last_mut() {
+                *active_ms = last_activity(path, view, *active_ms, dwell_ms);
+                if click.timestamp_ms <= *active_ms + dwell_ms && view.contains(click.x, click.y) {
+                    *active_ms = active_ms.max(click.timestamp_ms);
+                    continue;
+                }
+                if click.timestamp_ms < *active_ms + dwell_ms + spacing_ms {
+                    continue;
+                }
+            }
+            let view = CropRect::centered(click.x, click.y, width, height, (frame_w, frame_h));
+            regions.push((click.timestamp_ms, click.timestamp_ms, view));
+        }
+        if let Some((_, active_ms, view)) = regions.last_mut() {
+            *active_ms = last_activity(path, view, *active_ms, dwell_ms);
+        }
+
+        // 2. Target crop at each path sample, then glide between them: the
+        // spring moves the crop's corner and width, keeping its aspect
+        let mut corners = Vec::with_capacity(path.len());
+        let mut widths = Vec::with_capacity(path.len());
+        for p in path {
+            let idx = regions.partition_point(|r| r.1 + dwell_ms < p.timestamp_ms);
+            let target = regions
+                .get(idx)
+                .filter(|r| r.0 <= p.timestamp_ms)
+                .map_or(full, |r| r.2);
+            corners.push(CPoint {
+                x: target.x,
+                y: target.y,
+                timestamp_ms: p.timestamp_ms,
+            });
+            widths.push(CPoint {
+                x: target.width,
+                y: 0.0,
+                timestamp_ms: p.timestamp_ms,
+            });
+        }
+        let tuning = SpringTuning {
+            physics_model_v2: true,
+            ..SpringTuning::default()
+        };
+        let corners =
+            apply_physics_filter(&corners, CAMERA_RESPONSIVENESS, CAMERA_SMOOTHNESS, &tuning);
+        let widths =
+            apply_physics_filter(&widths, CAMERA_RESPONSIVENESS, CAMERA_SMOOTHNESS, &tuning);
+        let crops = corners
+            .iter()
+            .zip(&widths)
+            .map(|(corner, w)| {
+                let width = w.x.clamp(width, frame_w);
+                let height = width * frame_h / frame_w;
+                let crop = CropRect {
+                    x: corner.x.clamp(0.0, frame_w - width),
+                    y: corner.y.clamp(0.0, frame_h - height),
+                    width,
+                    height,
+                };
+                (corner.timestamp_ms, crop)
+            })
+            .collect();
+
+        Self {
+            frame_width: frame_w,
+            frame_height: frame_h,
+            shots: Shots::Follow(crops),
+        }
+    }
+
+    /// Zoom only during `keyframes`, showing the full frame between them.
+    /// Expects keyframes that don't overlap (see `ProcessingOptions::validated`).
+    pub fn from_keyframes(keyframes: &[ZoomKeyframe], frame_width: u32, frame_height: u32) -> Self {
+        let mut keyframes = keyframes.to_vec();
+        keyframes.sort_by(|a, b| a.start_ms.total_cmp(&b.start_ms));
+        Self {
+            frame_width: frame_width as f32,
+            frame_height: frame_height as f32,
+            shots: Shots::Keyframes(keyframes),
+        }
+    }
+
+    /// The uncropped frame
+    fn full_frame(&self) -> CropRect {
+        CropRect {
+            x: 0.0,
+            y: 0.0,
+            width: self.frame_width,
+            height: self.frame_height,
+        }
+    }
+
+    /// Crop at `timestamp_ms`. Follow crops are interpolated between path
+    /// samples and held before the first and after the last.
+    pub fn crop_at(&self, timestamp_ms: f64) -> CropRect {
+        match &self.shots {
+            Shots::Follow(crops) => {
+                let idx = crops.partition_point(|c| c.0 < timestamp_ms);
+                match (idx.checked_sub(1), crops.get(idx)) {
+                    (Some(i), Some((t1, to))) => {
+                        let (t0, from) = &crops[i];
+                        let s = if t1 - t0 > 1e-6 {
+                            ((timestamp_ms - t0) / (t1 - t0)) as f32
+                        } else {
+                            1.0
+                        };
+                        from.lerp(to, s)
+                    }
+                    (None, Some((_, crop))) => *crop,
+                    (Some(i), None) => crops[i].1,
+                    (None, None) => self.full_frame(),
+                }
+            }
+            Shots::Keyframes(keyframes) => {
+                let full = self.full_frame();
+                let idx = keyframes.partition_point(|k| k.end_ms <= timestamp_ms);
+                let Some(key) = keyframes.get(idx).filter(|k| k.start_ms <= timestamp_ms) else {
+                    return full;
+                };
+                let zoom = key.zoom.max(1.0);
+                let target = CropRect::centered(
+                    key.center_x,
+                    key.center_y,
+                    full.width / zoom,
+                    full.height / zoom,
+                    (full.width, full.height),
+                );
+                full.lerp(&target, key.weight(timestamp_ms))
+            }
+        }
+    }
+
+    /// Where source point (`x`, `y`) lands in the output frame at `timestamp_ms`
+    pub fn to_output(&self, timestamp_ms: f64, x: f32, y: f32) -> (f32, f32) {
+        let crop = self.crop_at(timestamp_ms);
+        // Source px per output px, as the overlay samples them
+        let step_x = crop.width / self.frame_width;
+        let step_y = crop.height / self.frame_height;
+        ((x - crop.x) / step_x, (y - crop.y) / step_y)
+    }
+
+    /// `lookup` positions moved into output coordinates
+    pub fn map_lookup(&self, lookup: &mut [(f64, f32, f32)]) {
+        for (t, x, y) in lookup.iter_mut() {
+            (*x, *y) = self.to_output(*t, *x, *y);
+        }
+    }
+
+    /// `clicks` moved into output coordinates
+    pub fn map_clicks(&self, clicks: &[CClickEvent]) -> Vec<CClickEvent> {
+        clicks
+            .iter()
+            .map(|click| {
+                let (x, y) = self.to_output(click.timestamp_ms, click.x, click.y);
+                CClickEvent { x, y, ..*click }
+            })
+            .collect()
+    }
+}
+
+/// Latest time the cursor moves inside `view` without a pause longer than
+/// `dwell_ms`, starting from activity at `active_ms`
+fn last_activity(path: &[CPoint], view: &CropRect, mut active_ms: f64, dwell_ms: f64) -> f64 {
+    let start = path.partition_point(|p| p.timestamp_ms <= active_ms).max(1);
+    for pair in path.get(start - 1..).unwrap_or_default().windows(2) {
+        let (prev, p) = (&pair[0], &pair[1]);
+        if p.timestamp_ms > active_ms + dwell_ms {
+            break;
+        }
+        let dt = (p.timestamp_ms - prev.timestamp_ms) / 1000.0;
+        let moving = dt > 0.0
+            && (p.x - prev.x).hypot(p.y - prev.y) as f64
+                >= CLICK_ZOOM_ACTIVITY_SPEED_PX_PER_SEC as f64 * dt;
+        if moving && view.contains(p.x, p.y) {
+            active_ms = p.timestamp_ms;
+        }
+    }
+    active_ms
+}
+
+/// Replaces each frame with its `CameraPlan` crop scaled (bilinear) back up
+/// to the full frame; the bottom layer, so the others draw on the zoomed view.
+/// Frames showing the full frame are left untouched.
+pub struct CameraOverlay<'a> {
+    plan: &'a CameraPlan,
+    /// Unpadded copy of the frame being resampled
+    source: RefCell<Vec<u8>>,
+}
+
+impl<'a> CameraOverlay<'a> {
+    pub fn new(plan: &'a CameraPlan) -> Self {
+        Self {
+            plan,
+            source: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl Overlay for CameraOverlay<'_> {
+    fn render(&self, frame: &mut FrameView<'_>, timestamp_ms: f64) {
+        let (width, height) = (frame.width(), frame.height());
+        let crop = self.plan.crop_at(timestamp_ms);
+        if width == 0 || height == 0 || crop == self.plan.full_frame() {
+            return;
+        }
+        let row_bytes = width as usize * 4;
+        let mut source = self.source.borrow_mut();
+        source.clear();
+        for y in 0..height {
+            if let Some(row) = frame.row_mut(y) {
+                source.extend_from_slice(row);
+            }
+        }
+
+        // Output pixel i samples source position crop.x + i / zoom
+        let step_x = crop.width / self.plan.frame_width;
+        let step_y = crop.height / self.plan.frame_height;
+        let columns = bilinear_taps(crop.x, step_x, width, width - 1);
+        let rows = bilinear_taps(crop.y, step_y, height, height - 1);
+        for (y, &(top, bottom, v)) in (0..height).zip(&rows) {
+            let top = &source[top as usize * row_bytes..][..row_bytes];
+            let bottom = &source[bottom as usize * row_bytes..][..row_bytes];
+            let Some(row) = frame.row_mut(y) else {
+                break;
+            };
+            for (px, &(left, right, u)) in row.chunks_exact_mut(4).zip(&columns) {
+                let (left, right) = (left as usize * 4, right as usize * 4);
+                for (c, out) in px.iter_mut().enumerate() {
+                    *out = bilinear_mix(
+                        [top[left + c], top[right + c]],
+                        [bottom[left + c], bottom[right + c]],
+                        u,
+                        v,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Bilinear taps of the source positions `origin + i * step` for i in
+/// 0..`count`, clamped to 0..=`last`: the two neighbouring source indices and
+/// the second one's weight, 8-bit fixed point (0-256)
+pub(crate) fn bilinear_taps(origin: f32, step: f32, count: u32, last: u32) -> Vec<(u32, u32, u32)> {
+    (0..count)
+        .map(|i| {
+            let s = (origin + i as f32 * step).clamp(0.0, last as f32);
+            let lo = s as u32;
+            let weight = ((s - lo as f32) * 256.0).round() as u32;
+            (lo, (lo + 1).min(last), weight)
+        })
+        .collect()
+}
+
+/// One channel blended from a 2x2 texel block with `bilinear_taps` weights
+/// (`u` across, `v` down)
+#[inline]
+pub(crate) fn bilinear_mix(top: [u8; 2], bottom: [u8; 2], u: u32, v: u32) -> u8 {
+    let t = top[0] as u32 * (256 - u) + top[1] as u32 * u;
+    let b = bottom[0] as u32 * (256 - u) + bottom[1] as u32 * u;
+    ((t * (256 - v) + b * v + (1 << 15)) >> 16) as u8
+}
@@ -0,0 +1,305 @@
+// constants.rs - Smoothing tuning defaults (see smoothing.rs for the filters)
+
+// ============================================================================
+// One Euro Filter
+// ============================================================================
+
+/// Cutoff (Hz) while the cursor is at rest. Low enough to hide the tremor of a
+/// resting hand, high enough that a parked cursor settles within ~150 ms.
+pub const ONE_EURO_MIN_CUTOFF: f32 = 1.0;
+
+/// Cutoff increase per px/s of cursor speed. Cursor speeds are in the
+/// thousands of px/s during a flick, so 0.007 lifts the cutoff to ~10-30 Hz
+/// exactly when lag would be visible.
+pub const ONE_EURO_BETA: f32 = 0.007;
+
+/// Cutoff (Hz) for the speed estimate itself. 1 Hz is the value recommended
+/// by the filter's authors; the speed only steers the main cutoff, so it can
+/// be smoothed heavily.
+pub const ONE_EURO_D_CUTOFF: f32 = 1.0;
+
+/// Accepted cutoff range (Hz). Above ~60 Hz the filter passes input through
+/// at typical 60-125 Hz capture rates.
+pub const ONE_EURO_MIN_CUTOFF_HZ: f32 = 0.01;
+pub const ONE_EURO_MAX_CUTOFF_HZ: f32 = 60.0;
+
+/// Accepted `beta` range
+pub const ONE_EURO_MAX_BETA: f32 = 1.0;
+
+// ============================================================================
+// Kalman Filter
+// ============================================================================
+
+/// Spectral density of the random acceleration (px^2/s^3). A hand moving a
+/// mouse changes speed by a few thousand px/s within one 16 ms event, which
+/// works out to ~1e5; lower values trust the constant-velocity model more.
+pub const KALMAN_PROCESS_NOISE: f32 = 1.0e5;
+
+/// Variance of a single position sample (px^2). Scaled/HiDPI coordinates are
+/// rounded to the nearest device pixel, so ~2 px of noise is typical.
+pub const KALMAN_MEASUREMENT_NOISE: f32 = 4.0;
+
+/// Accepted noise ranges; outside them the filter degenerates into either a
+/// pass-through or a straight line through the whole recording.
+pub const KALMAN_MIN_PROCESS_NOISE: f32 = 1.0;
+pub const KALMAN_MAX_PROCESS_NOISE: f32 = 1.0e9;
+pub const KALMAN_MIN_MEASUREMENT_NOISE: f32 = 0.01;
+pub const KALMAN_MAX_MEASUREMENT_NOISE: f32 = 1.0e4;
+
+// ============================================================================
+// Bezier Strokes
+// ============================================================================
+
+/// Speed (px/s) under which the cursor counts as dwelling. Hand tremor on a
+/// resting mouse stays well below this; a deliberate slow drag does not.
+pub const BEZIER_DWELL_SPEED_PX_PER_SEC: f32 = 40.0;
+
+/// Time (ms) the cursor must stay under the dwell speed to end a stroke.
+/// Shorter pauses are the hesitations inside one gesture.
+pub const BEZIER_DWELL_MS: f32 = 120.0;
+
+/// Accepted dwell ranges
+pub const BEZIER_MIN_DWELL_SPEED_PX_PER_SEC: f32 = 1.0;
+pub const BEZIER_MAX_DWELL_SPEED_PX_PER_SEC: f32 = 1000.0;
+pub const BEZIER_MIN_DWELL_MS: f32 = 10.0;
+pub const BEZIER_MAX_DWELL_MS: f32 = 2000.0;
+
+// ============================================================================
+// Outlier Rejection
+// ============================================================================
+
+/// Fastest plausible cursor speed (px/s). Flicks across a 4K display peak
+/// around 10-15k px/s; a sample implying more than this is an event glitch
+/// (a jump to (0,0), a stale multi-monitor coordinate), not hand movement.
+pub const VELOCITY_MAX_PX_PER_SEC: f64 = 20_000.0;
+
+/// Samples that must stay consistent with an over-speed jump before it is
+/// accepted as a real warp (e.g. the app moved the cursor). A one- or
+/// two-event glitch snaps back before this many samples.
+pub const OUTLIER_CONFIRM_SAMPLES: usize = 3;
+
+// ============================================================================
+// Kinematic Limits
+// ============================================================================
+
+/// Hardest acceleration (px/s^2) of the reprofiled cursor. A flick reaches
+/// ~10k px/s within 40-50 ms, i.e. ~250k px/s^2; anything above that reads
+/// as a teleport rather than a hand. The speed cap is
+/// `VELOCITY_MAX_PX_PER_SEC`.
+pub const ACCELERATION_MAX_PX_PER_SEC2: f64 = 250_000.0;
+
+// ============================================================================
+// Timestamp Sanitation
+// ============================================================================
+
+/// Largest backwards step (ms) treated as jitter and merged into the previous
+/// sample. Events read on different OS threads arrive up to ~1 ms out of
+/// order; a larger step means a clock jump or concatenated recordings.
+pub const TIMESTAMP_BACKSTEP_TOLERANCE_MS: f64 = 5.0;
+
+// ============================================================================
+// Coordinate Mapping
+// ============================================================================
+
+/// Bounds for the recorded-units-to-video-pixels scale. 0.25 covers a 4K
+/// display recorded at 1080p downscaled twice over; 8 covers 2x HiDPI
+/// recorded at 4x.
+pub const MIN_COORDINATE_SCALE: f32 = 0.25;
+pub const MAX_COORDINATE_SCALE: f32 = 8.0;
+
+// ============================================================================
+// Input Decimation
+// ============================================================================
+
+/// Bounds (Hz) for the decimation target rate. Below 30 Hz the filters see
+/// visibly fewer samples than the 60 fps output; above 8 kHz nothing is
+/// ever merged.
+pub const MIN_INPUT_RATE_HZ: f32 = 30.0;
+pub const MAX_INPUT_RATE_HZ: f32 = 8000.0;
+
+/// Smallest step (px) on both sides of a sample that makes it a direction
+/// reversal worth keeping. Sub-pixel sensor noise reverses constantly and
+/// must not defeat the averaging.
+pub const DECIMATION_EXTREMUM_PX: f32 = 1.0;
+
+// ============================================================================
+// Path Simplification
+// ============================================================================
+
+/// Largest accepted simplification tolerance (px). Beyond this, corners of
+/// small deliberate moves start to disappear.
+pub const MAX_SIMPLIFY_EPSILON_PX: f32 = 10.0;
+
+/// Longest time span (ms) simplification may leave without a point. The
+/// upsampling pass places frames by time, so long spans would turn a pause
+/// followed by a move into one slow glide.
+pub const SIMPLIFY_MAX_GAP_MS: f64 = 50.0;
+
+// ============================================================================
+// Click Anchors
+// ============================================================================
+
+/// Time (ms) on each side of a click over which the filtered path is pulled
+/// onto the click position. Long enough that the correction reads as part of
+/// the approach, short enough not to bend the previous gesture.
+pub const CLICK_ANCHOR_BLEND_MS: f64 = 120.0;
+
+// ============================================================================
+// Path Ends
+// ============================================================================
+
+/// Longest extrapolation (ms) past either end of the cursor path. Beyond a
+/// few frames the final velocity no longer says where the hand went.
+pub const MAX_TAIL_EXTRAPOLATE_MS: u32 = 500;
+
+// ============================================================================
+// Capture Gaps
+// ============================================================================
+
+/// Time (ms) the cursor takes to move to its post-gap position. Reads as a
+/// quick deliberate move, not the slow sweep a spline across the gap draws.
+pub const DEFAULT_GAP_RAMP_MS: f32 = 150.0;
+
+/// Longest accepted ramp (ms)
+pub const MAX_GAP_RAMP_MS: f32 = 1000.0;
+
+// ============================================================================
+// Rest Detection (Spring Filter)
+// ============================================================================
+
+/// Raw movement (px) still counted as "at rest". Covers sensor noise and
+/// sub-pixel HiDPI rounding without catching slow deliberate moves.
+pub const REST_RADIUS_PX: f32 = 2.0;
+
+/// Time (ms) inside the radius before the cursor counts as resting. Shorter
+/// than a human pause, longer than the gap between two slow-drag events.
+pub const REST_DWELL_MS: f32 = 120.0;
+
+/// Time constant (s) of the final settle onto the resting position. Fast
+/// enough to end the creep, slow enough that the last pixel does not pop.
+pub const REST_SETTLE_TIME_CONSTANT_S: f32 = 0.04;
+
+/// Longest accepted dwell (ms)
+pub const MAX_REST_DWELL_MS: f32 = 5000.0;
+
+// ============================================================================
+// Spring Integration
+// ============================================================================
+
+/// Bounds (s) for the time between two input samples. The lower bound keeps
+/// coalesced events from producing huge accelerations; the upper bound keeps
+/// a capture hiccup from being simulated as one long unforced coast.
+pub const PHYSICS_MIN_DT_SEC: f32 = 0.001;
+pub const PHYSICS_MAX_DT_SEC: f32 = 0.1;
+
+/// Fraction of the stability limit used for automatic substeps. Semi-implicit
+/// Euler diverges once a step exceeds ~2/omega (or 2m/c when heavily damped);
+/// staying at a quarter of that also keeps the phase error invisible.
+pub const PHYSICS_STABLE_STEP_FRACTION: f32 = 0.25;
+
+/// Smallest accepted explicit substep (s); bounds the work per sample
+pub const PHYSICS_MIN_STEP_SEC: f32 = 0.0001;
+
+// ============================================================================
+// Spring Model (physics_model_v2)
+// ============================================================================
+
+/// 2% settling time (s) of a step response at responsiveness 0.0. Slow
+/// enough to read as a deliberate glide, short of feeling disconnected.
+pub const RESPONSIVENESS_MAX_SETTLING_SEC: f32 = 0.6;
+
+/// 2% settling time (s) at responsiveness 1.0. About five 60 fps frames;
+/// any faster and capture jitter passes straight through.
+pub const RESPONSIVENESS_MIN_SETTLING_SEC: f32 = 0.08;
+
+/// Damping ratio at smoothness 0.0. 0.6 overshoots by ~10%, which reads as a
+/// lively cursor rather than a wobble.
+pub const SMOOTHNESS_MIN_DAMPING: f32 = 0.6;
+
+/// Damping ratio at smoothness 1.0. Critical damping: the fastest response
+/// with no overshoot at all.
+pub const SMOOTHNESS_MAX_DAMPING: f32 = 1.0;
+
+/// Simulated cursor mass (kg). Only the ratios k/m and c/m matter, so 1.0
+/// keeps stiffness and damping in directly readable units.
+pub const SPRING_MASS_DEFAULT: f32 = 1.0;
+
+/// Settling band used to define the settling time (fraction of the step)
+pub const SETTLING_TOLERANCE: f32 = 0.02;
+
+// ============================================================================
+// Camera (auto_zoom)
+// ============================================================================
+
+/// Camera spring on the `physics_model_v2` scale: the slowest settling time
+/// (0.6 s) and critical damping, so pans read as one deliberate glide and
+/// never overshoot the cursor
+pub const CAMERA_RESPONSIVENESS: f32 = 0.0;
+pub const CAMERA_SMOOTHNESS: f32 = 1.0;
+
+/// Ease in and out time (ms) of a zoom keyframe, at most half its length
+pub const ZOOM_TRANSITION_MS: f64 = 600.0;
+
+/// Cursor speed (px/s) inside a click zoom that counts as activity and holds
+/// the zoom. Same threshold as a stroke's dwell: a resting hand stays below
+/// it, so an idle cursor still lets the view zoom back out.
+pub const CLICK_ZOOM_ACTIVITY_SPEED_PX_PER_SEC: f32 = 40.0;
+
+// ============================================================================
+// Cursor Heading (orient_to_velocity)
+// ============================================================================
+
+/// Speed (px/s) above which a resting cursor starts turning to its heading.
+/// Well above capture jitter, so hand tremor at rest never spins the sprite.
+pub const HEADING_MOVING_SPEED_PX_PER_SEC: f32 = 120.0;
+
+/// Speed (px/s) below which a moving cursor keeps its last heading. The gap
+/// to the moving threshold stops a slow drag from flickering between the two.
+pub const HEADING_STOPPED_SPEED_PX_PER_SEC: f32 = 40.0;
+
+// ============================================================================
+// Motion Trail
+// ============================================================================
+
+/// Closest spacing (px) between motion trail ghosts. Nearer ghosts would
+/// overlap the sprite almost exactly and only thicken its edges.
+pub const TRAIL_MIN_STEP_PX: f32 = 1.0;
+
+// ============================================================================
+// Idle Speed-Up
+// ============================================================================
+
+/// Time (ms) between the frames compared for `IdleSpeedup`'s picture check.
+/// Four samples a second catch a scroll or a video playing without decoding
+/// much more than the render itself does.
+pub const IDLE_SAMPLE_INTERVAL_MS: f64 = 250.0;
+
+/// Width (px) frames are scaled down to before comparing. Compression noise
+/// averages out; anything a viewer would notice still spans several pixels.
+pub const IDLE_SAMPLE_WIDTH: u32 = 160;
+
+/// Luma change (of 255) above which a sampled pixel counts as changed
+pub const IDLE_PIXEL_CHANGE_LEVELS: u8 = 12;
+
+// ============================================================================
+// Start Alignment
+// ============================================================================
+
+/// Frames whose timestamps fix where the cursor clock's 0 falls on the
+/// video. Capture warm-up and B-frame reordering leave the first frame's
+/// timestamp 30-100 ms off; the median over fifteen outvotes it and a few
+/// more like it.
+pub const ALIGNMENT_SAMPLE_COUNT: usize = 15;
+
+/// Longest span (ms) of video held back while sampling, for low frame rates.
+/// The held frames are decoded pictures, so this also caps the memory.
+pub const ALIGNMENT_MAX_WINDOW_MS: f64 = 500.0;
+
+// ============================================================================
+// Pipelined Rendering
+// ============================================================================
+
+/// Frames each queue of the render pipeline holds (decoder -> compositing ->
+/// encoder). Two or three ride out a slow frame in either neighbour; more
+/// only add memory, ~33 MB per queued 4K RGBA frame.
+pub const PIPELINE_QUEUE_FRAMES: usize = 3;
@@ -0,0 +1,3197 @@
+// engine.rs - Safe Rust API (the FFI layer in lib.rs is a thin wrapper over this)
+use crate::constants::{
+    BEZIER_DWELL_MS, BEZIER_DWELL_SPEED_PX_PER_SEC, BEZIER_MAX_DWELL_MS,
+    BEZIER_MAX_DWELL_SPEED_PX_PER_SEC, BEZIER_MIN_DWELL_MS, BEZIER_MIN_DWELL_SPEED_PX_PER_SEC,
+    DEFAULT_GAP_RAMP_MS, KALMAN_MAX_MEASUREMENT_NOISE, KALMAN_MAX_PROCESS_NOISE,
+    KALMAN_MEASUREMENT_NOISE, KALMAN_MIN_MEASUREMENT_NOISE, KALMAN_MIN_PROCESS_NOISE,
+    KALMAN_PROCESS_NOISE, MAX_COORDINATE_SCALE, MAX_GAP_RAMP_MS, MAX_INPUT_RATE_HZ,
+    MAX_REST_DWELL_MS, MAX_SIMPLIFY_EPSILON_PX, MAX_TAIL_EXTRAPOLATE_MS, MIN_COORDINATE_SCALE,
+    MIN_INPUT_RATE_HZ, ONE_EURO_BETA, ONE_EURO_D_CUTOFF, ONE_EURO_MAX_BETA, ONE_EURO_MAX_CUTOFF_HZ,
+    ONE_EURO_MIN_CUTOFF, ONE_EURO_MIN_CUTOFF_HZ, PHYSICS_MAX_DT_SEC, PHYSICS_MIN_STEP_SEC,
+    REST_DWELL_MS, REST_RADIUS_PX, TRAIL_MIN_STEP_PX, VELOCITY_MAX_PX_PER_SEC,
+};
+use crate::renderer;
+use crate::sequence::ImageSequence;
+use crate::smoothing;
+use crate::utils;
+use crate::validation::{self, MIN_CURSOR_POINTS};
+use crate::video;
+use ffmpeg_next as ffmpeg;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub use crate::camera::{
+    AutoZoom, CameraOverlay, CameraPlan, ClickZoom, CropRect, ZoomEasing, ZoomKeyframe,
+};
+pub use crate::frame_style::{FrameBackground, FrameStyle, FrameStyleOverlay};
+pub use crate::orientation::{Orientation, RotationHandling};
+pub use crate::renderer::{
+    composite_cursor_yuv420, AnimationFrame, BadgeCorner, Caption, CaptionOverlay, CaptionPosition,
+    CaptionStyle, ClickEffect, ClickRipples, CursorKindSprite, CursorShadow, CursorSprite,
+    CursorStyle, FrameView, KeystrokeDisplay, KeystrokeOverlay, Overlay, RedactionMode,
+    RedactionOverlay, RedactionRegion, RippleCurve, SamplingQuality, Spotlight, SpotlightOverlay,
+    SpriteSchedule, Watermark, WatermarkImage, WatermarkOverlay, Yuv420Frame, YuvSample, YuvSprite,
+    SPRITE_CACHE_CAPACITY,
+};
+pub use crate::smoothing::{
+    click_events, decimate_path, simplify_path, split_events, CClickEvent, CEventPoint, CPoint,
+    CoordinateSpace, CursorKindEvent, GapHold, Interpolation, KeyEvent, OutsideRegion, PathCleanup,
+    PathMetrics, RestSnap, SmoothingMode, SpringTuning, TimestampUnit, BUTTON_LEFT, BUTTON_MIDDLE,
+    BUTTON_RIGHT, EVENT_DOWN, EVENT_MOVE, EVENT_UP,
+};
+pub use crate::speedup::{IdleSpeedup, TimeMap};
+pub use crate::VideoMetadata;
+
+// ============================================================================
+// Limits
+// ============================================================================
+
+/// Highest output frame rate accepted by the pipeline
+pub const MAX_FRAME_RATE: i32 = 240;
+
+/// Frame rate image sequences are read at where no options say otherwise
+/// (`read_metadata`, input validation)
+pub const DEFAULT_SEQUENCE_FRAME_RATE: i32 = 30;
+
+/// Default `ProcessingOptions::thumbnail_max_width`
+pub const DEFAULT_THUMBNAIL_MAX_WIDTH: u32 = 320;
+
+/// Each side of a `ProcessingOptions::preview_mode` render is this many
+/// times smaller than the export's
+pub const PREVIEW_SCALE_DIVISOR: u32 = 4;
+
+/// Highest frame rate of a `ProcessingOptions::preview_mode` render
+pub const PREVIEW_MAX_FRAME_RATE: i32 = 30;
+
+/// CRF of `preview_mode` renders with x264/x265 and libvpx-vp9: small
+/// files, soft but readable at the reduced size
+const PREVIEW_CRF: u32 = 32;
+const PREVIEW_CRF_VP9: u32 = 48;
+
+/// Where in the output `ProcessingOptions::thumbnail_path` takes its frame,
+/// as a fraction of the duration: past any fade-in or title card
+const THUMBNAIL_POSITION: f64 = 0.1;
+
+/// Accepted range for `cursor_scale`
+pub const MIN_CURSOR_SCALE: f32 = 0.1;
+pub const MAX_CURSOR_SCALE: f32 = 8.0;
+
+/// Longest accepted click ripple (ms)
+pub const MAX_CLICK_DURATION_MS: f32 = 5000.0;
+
+/// Largest accepted click ripple radius or ring width (px)
+pub const MAX_CLICK_RADIUS_PX: f32 = 1000.0;
+
+/// Largest accepted cursor shadow blur radius (px)
+pub const MAX_SHADOW_BLUR_PX: f32 = 64.0;
+
+/// Most motion trail ghosts per frame
+pub const MAX_TRAIL_SAMPLES: u32 = 16;
+
+/// Largest accepted keystroke badge text size (px)
+pub const MAX_KEYSTROKE_FONT_PX: f32 = 256.0;
+
+/// Longest accepted keystroke badge hold (ms)
+pub const MAX_KEYSTROKE_HOLD_MS: f32 = 10_000.0;
+
+/// Largest accepted spotlight radius (px)
+pub const MAX_SPOTLIGHT_RADIUS_PX: f32 = 2048.0;
+
+/// Widest accepted spotlight edge feather (px)
+pub const MAX_SPOTLIGHT_FEATHER_PX: f32 = 512.0;
+
+/// Strongest accepted follow-cursor zoom
+pub const MAX_AUTO_ZOOM: f32 = 4.0;
+
+/// Widest accepted follow-cursor zoom margin (output px)
+pub const MAX_ZOOM_MARGIN_PX: f32 = 2048.0;
+
+/// Longest accepted click zoom dwell and spacing (ms)
+pub const MAX_CLICK_ZOOM_DWELL_MS: f32 = 30_000.0;
+pub const MAX_CLICK_ZOOM_SPACING_MS: f32 = 30_000.0;
+
+/// Widest accepted styled-background padding (% of the frame size per side)
+pub const MAX_FRAME_PADDING_PERCENT: f32 = 40.0;
+
+/// Largest accepted styled-background corner radius (px)
+pub const MAX_FRAME_CORNER_RADIUS_PX: f32 = 512.0;
+
+/// Largest accepted styled-background shadow blur (px)
+pub const MAX_FRAME_SHADOW_BLUR_PX: f32 = 128.0;
+
+/// Accepted range for a watermark's scale
+pub const MIN_WATERMARK_SCALE: f32 = 0.05;
+pub const MAX_WATERMARK_SCALE: f32 = 8.0;
+
+/// Largest accepted redaction blur radius and pixelate block (px)
+pub const MAX_REDACTION_BLUR_PX: u32 = 256;
+pub const MAX_REDACTION_BLOCK_PX: u32 = 512;
+
+/// Largest accepted caption text size (px)
+pub const MAX_CAPTION_FONT_PX: f32 = 256.0;
+
+/// Longest accepted caption fade in or out (ms)
+pub const MAX_CAPTION_FADE_MS: f32 = 5000.0;
+
+/// Accepted range for the AAC bitrate of transcoded audio (kbit/s)
+pub const MIN_AUDIO_BITRATE_KBPS: u32 = 32;
+pub const MAX_AUDIO_BITRATE_KBPS: u32 = 512;
+
+/// Fastest accepted libvpx-vp9 `cpu-used` (0 = slowest, best compression)
+pub const MAX_VP9_CPU_USED: u32 = 8;
+
+/// Largest accepted `output_width` / `output_height` (px)
+pub const MAX_OUTPUT_DIMENSION: u32 = 8192;
+
+/// Downscales by more than this factor (in either dimension) use Lanczos
+/// under `ScaleFilter::Auto`; bilinear aliases text and thin lines there
+pub const SHARP_DOWNSCALE_RATIO: f64 = 2.0;
+
+/// Highest GIF frame rate; frame delays are whole hundredths of a second and
+/// players slow anything shorter than 2 of them down
+pub const MAX_GIF_FRAME_RATE: i32 = 50;
+
+/// Longest input accepted for GIF export (ms). Every frame is held in memory
+/// until the shared palette is known.
+pub const MAX_GIF_DURATION_MS: f64 = 30_000.0;
+
+/// Fastest playback of idle stretches (`IdleSpeedup::factor`)
+pub const MAX_IDLE_SPEEDUP: f32 = 16.0;
+
+/// Highest `IdleSpeedup::min_idle_ms` (ms) and `max_displacement_px`
+pub const MAX_IDLE_MS: f32 = 600_000.0;
+pub const MAX_IDLE_DISPLACEMENT_PX: f32 = 256.0;
+
+/// Highest CRF of x264/x265 (also the NVENC `cq` and VA-API `qp` ceiling)
+/// and of libvpx-vp9
+pub const MAX_X264_CRF: u32 = 51;
+pub const MAX_VP9_CRF: u32 = 63;
+
+/// Accepted range for video bitrates, VBV caps and buffer sizes (kbit/s, kbit)
+pub const MIN_VIDEO_BITRATE_KBPS: u32 = 100;
+pub const MAX_VIDEO_BITRATE_KBPS: u32 = 200_000;
+
+// ============================================================================
+// Output Format
+// ============================================================================
+
+/// Output video codec
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VideoCodec {
+    #[default]
+    H264,
+    Hevc,
+    Vp9,
+    /// Animated GIF with one palette for the whole clip (see `GifExport`)
+    Gif,
+    /// Apple ProRes, intra-only: 422 HQ, or 4444 with an alpha channel for
+    /// `overlay_only` renders. MOV or MKV only.
+    ProRes,
+}
+
+impl VideoCodec {
+    /// FFI value (0 = H.264, 1 = HEVC, 2 = VP9, 3 = GIF, 4 = ProRes)
+    pub fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::H264),
+            1 => Some(Self::Hevc),
+            2 => Some(Self::Vp9),
+            3 => Some(Self::Gif),
+            4 => Some(Self::ProRes),
+            _ => None,
+        }
+    }
+
+    /// FFmpeg encoder looked up by name, so a missing library is reported
+    /// instead of silently falling back to another implementation
+    pub fn encoder_name(self) -> &'static str {
+        match self {
+            Self::H264 => "libx264",
+            Self::Hevc => "libx265",
+            Self::Vp9 => "libvpx-vp9",
+            Self::Gif => "gif",
+            Self::ProRes => "prores_ks",
+        }
+    }
+
+    /// Whether the codec can carry an alpha channel (`overlay_only`)
+    pub fn supports_alpha(self) -> bool {
+        matches!(self, Self::Vp9 | Self::ProRes)
+    }
+}
+
+/// libvpx-vp9 `deadline`: how much time the encoder may spend per frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Vp9Deadline {
+    /// Balanced; `cpu-used` above 5 encodes like 5
+    #[default]
+    Good,
+    /// Smallest files, many times slower than `Good`
+    Best,
+    /// Fastest, at a noticeable cost in size for screen content
+    Realtime,
+}
+
+impl Vp9Deadline {
+    /// FFI value (0 = good, 1 = best, 2 = realtime)
+    pub fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Good),
+            1 => Some(Self::Best),
+            2 => Some(Self::Realtime),
+            _ => None,
+        }
+    }
+
+    /// libvpx option value
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Good => "good",
+            Self::Best => "best",
+            Self::Realtime => "realtime",
+        }
+    }
+}
+
+/// Encoder implementation behind `VideoCodec`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncoderBackend {
+    /// The platform's hardware encoder when one opens, else software
+    Auto,
+    /// libx264 / libx265 / libvpx-vp9
+    #[default]
+    Software,
+    /// Apple VideoToolbox (macOS)
+    VideoToolbox,
+    /// NVIDIA NVENC
+    Nvenc,
+    /// VA-API (Intel and AMD GPUs on Linux)
+    Vaapi,
+}
+
+impl EncoderBackend {
+    /// FFI value (0 = software, 1 = auto, 2 = VideoToolbox, 3 = NVENC,
+    /// 4 = VA-API)
+    pub fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Software),
+            1 => Some(Self::Auto),
+            2 => Some(Self::VideoToolbox),
+            3 => Some(Self::Nvenc),
+            4 => Some(Self::Vaapi),
+            _ => None,
+        }
+    }
+
+    /// FFI value, as in `from_raw`
+    pub fn to_raw(self) -> i32 {
+        match self {
+            Self::Software => 0,
+            Self::Auto => 1,
+            Self::VideoToolbox => 2,
+            Self::Nvenc => 3,
+            Self::Vaapi => 4,
+        }
+    }
+
+    /// FFmpeg encoder for `codec` on this backend (None for `Auto`, or when
+    /// the backend cannot encode `codec`)
+    pub fn encoder_name(self, codec: VideoCodec) -> Option<&'static str> {
+        match (self, codec) {
+            (Self::Auto, _) => None,
+            (Self::Software, codec) => Some(codec.encoder_name()),
+            (Self::VideoToolbox, VideoCodec::H264) => Some("h264_videotoolbox"),
+            (Self::VideoToolbox, VideoCodec::Hevc) => Some("hevc_videotoolbox"),
+            (Self::Nvenc, VideoCodec::H264) => Some("h264_nvenc"),
+            (Self::Nvenc, VideoCodec::Hevc) => Some("hevc_nvenc"),
+            (Self::Vaapi, VideoCodec::H264) => Some("h264_vaapi"),
+            (Self::Vaapi, VideoCodec::Hevc) => Some("hevc_vaapi"),
+            (Self::Vaapi, VideoCodec::Vp9) => Some("vp9_vaapi"),
+            (Self::VideoToolbox | Self::Nvenc, VideoCodec::Vp9) => None,
+            (
+                Self::VideoToolbox | Self::Nvenc | Self::Vaapi,
+                VideoCodec::Gif | VideoCodec::ProRes,
+            ) => None,
+        }
+    }
+
+    /// Encoders to try for `codec`, best first: the hardware encoders this
+    /// backend stands for (`Auto` = those usual on this platform), then the
+    /// software encoder as the fallback
+    pub fn candidates(self, codec: VideoCodec) -> Vec<(EncoderBackend, &'static str)> {
+        let hardware = match self {
+            Self::Software => vec![],
+            Self::Auto if cfg!(target_os = "macos") => vec![Self::VideoToolbox],
+            Self::Auto if cfg!(target_os = "linux") => vec![Self::Nvenc, Self::Vaapi],
+            Self::Auto => vec![Self::Nvenc],
+            backend => vec![backend],
+        };
+        hardware
+            .into_iter()
+            .chain([Self::Software])
+            .filter_map(|backend| Some((backend, backend.encoder_name(codec)?)))
+            .collect()
+    }
+}
+
+/// Open the first of `candidates` that `open` succeeds on, warning about each
+/// one skipped. When none opens, the last candidate's error is returned.
+pub fn select_encoder<T>(
+    candidates: &[(EncoderBackend, &'static str)],
+    mut open: impl FnMut(EncoderBackend, &'static str) -> Result<T, EngineError>,
+) -> Result<(EncoderBackend, T), EngineError> {
+    let mut last_error = EngineError::MissingComponent("video encoder");
+    for (i, &(backend, name)) in candidates.iter().enumerate() {
+        match open(backend, name) {
+            Ok(opened) => return Ok((backend, opened)),
+            Err(e) => {
+                if let Some((_, next)) = candidates.get(i + 1) {
+                    log::warn!(
+                        "{} encoder unusable ({}), falling back to {}",
+                        name,
+                        e,
+                        next
+                    );
+                }
+                last_error = e;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+/// Output container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Container {
+    /// Infer from the output file extension
+    #[default]
+    Auto,
+    Mp4,
+    Mkv,
+    Mov,
+    WebM,
+    Gif,
+}
+
+impl Container {
+    /// FFI value (0 = auto, 1 = MP4, 2 = MKV, 3 = MOV, 4 = WebM, 5 = GIF)
+    pub fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Auto),
+            1 => Some(Self::Mp4),
+            2 => Some(Self::Mkv),
+            3 => Some(Self::Mov),
+            4 => Some(Self::WebM),
+            5 => Some(Self::Gif),
+            _ => None,
+        }
+    }
+
+    /// Resolve `Auto` from the extension of `path`. Unknown extensions stay
+    /// `Auto` and are left to FFmpeg's own guessing.
+    pub fn resolve(self, path: &Path) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("mp4" | "m4v") => Self::Mp4,
+            Some("mkv") => Self::Mkv,
+            Some("mov") => Self::Mov,
+            Some("webm") => Self::WebM,
+            Some("gif") => Self::Gif,
+            _ => Self::Auto,
+        }
+    }
+
+    /// FFmpeg muxer name (None = guess from the path)
+    pub fn muxer_name(self) -> Option<&'static str> {
+        match self {
+            Self::Auto => None,
+            Self::Mp4 => Some("mp4"),
+            Self::Mkv => Some("matroska"),
+            Self::Mov => Some("mov"),
+            Self::WebM => Some("webm"),
+            Self::Gif => Some("gif"),
+        }
+    }
+
+    /// MP4 or MOV, the containers `faststart` and `fragmented` apply to
+    pub fn is_quicktime(self) -> bool {
+        matches!(self, Self::Mp4 | Self::Mov)
+    }
+
+    /// Whether `codec` can be stored in this container (`Auto` defers to FFmpeg)
+    pub fn supports(self, codec: VideoCodec) -> bool {
+        match self {
+            Self::Auto => true,
+            Self::Mkv => codec != VideoCodec::Gif,
+            Self::Mp4 => matches!(codec, VideoCodec::H264 | VideoCodec::Hevc),
+            Self::Mov => matches!(
+                codec,
+                VideoCodec::H264 | VideoCodec::Hevc | VideoCodec::ProRes
+            ),
+            Self::WebM => codec == VideoCodec::Vp9,
+            Self::Gif => codec == VideoCodec::Gif,
+        }
+    }
+}
+
+/// Animated GIF settings, used when `codec` is `VideoCodec::Gif`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GifExport {
+    /// Frames per second (1-`MAX_GIF_FRAME_RATE`); replaces `frame_rate`
+    pub frame_rate: i32,
+    /// Wider frames are scaled down to this width, keeping the aspect ratio
+    /// (0 = keep the source size)
+    pub max_width: u32,
+    /// Play forever rather than once
+    pub loop_forever: bool,
+}
+
+impl Default for GifExport {
+    fn default() -> Self {
+        Self {
+            frame_rate: 15,
+            max_width: 0,
+            loop_forever: true,
+        }
+    }
+}
+
+/// How the video encoder spends bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RateControl {
+    /// Constant quality: size follows content (x264 `crf`, libvpx `crf` with
+    /// `b` 0, NVENC `cq`, VA-API CQP `qp`)
+    #[default]
+    Crf,
+    /// Average bitrate: `bitrate_kbps` over the whole clip (`b`)
+    Abr,
+    /// Constant bitrate: `b` = `minrate` = `maxrate`, plus x264 `nal-hrd=cbr`
+    /// so players see a CBR stream
+    Cbr,
+}
+
+impl RateControl {
+    /// FFI value (0 = CRF, 1 = ABR, 2 = CBR)
+    pub fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Crf),
+            1 => Some(Self::Abr),
+            2 => Some(Self::Cbr),
+            _ => None,
+        }
+    }
+}
+
+/// x264/x265 `preset`: encode speed against compression at equal quality
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncoderPreset {
+    Ultrafast,
+    Superfast,
+    Veryfast,
+    Faster,
+    #[default]
+    Fast,
+    Medium,
+    Slow,
+    Slower,
+    Veryslow,
+    Placebo,
+}
+
+impl EncoderPreset {
+    /// FFI value, 0 = ultrafast .. 9 = placebo
+    pub fn from_raw(value: i32) -> Option<Self> {
+        const PRESETS: [EncoderPreset; 10] = [
+            EncoderPreset::Ultrafast,
+            EncoderPreset::Superfast,
+            EncoderPreset::Veryfast,
+            EncoderPreset::Faster,
+            EncoderPreset::Fast,
+            EncoderPreset::Medium,
+            EncoderPreset::Slow,
+            EncoderPreset::Slower,
+            EncoderPreset::Veryslow,
+            EncoderPreset::Placebo,
+        ];
+        usize::try_from(value)
+            .ok()
+            .and_then(|i| PRESETS.get(i).copied())
+    }
+
+    /// x264/x265 option value
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ultrafast => "ultrafast",
+            Self::Superfast => "superfast",
+            Self::Veryfast => "veryfast",
+            Self::Faster => "faster",
+            Self::Fast => "fast",
+            Self::Medium => "medium",
+            Self::Slow => "slow",
+            Self::Slower => "slower",
+            Self::Veryslow => "veryslow",
+            Self::Placebo => "placebo",
+        }
+    }
+}
+
+/// x264 `tune`: biases the encoder toward a kind of content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncoderTune {
+    Film,
+    /// Flat areas and sharp edges, the closest fit for screen recordings
+    Animation,
+    Grain,
+    StillImage,
+    FastDecode,
+    ZeroLatency,
+}
+
+impl EncoderTune {
+    /// FFI value (0 = none, 1 = film, 2 = animation, 3 = grain,
+    /// 4 = stillimage, 5 = fastdecode, 6 = zerolatency); `Some(None)` is no tune
+    pub fn from_raw(value: i32) -> Option<Option<Self>> {
+        match value {
+            0 => Some(None),
+            1 => Some(Some(Self::Film)),
+            2 => Some(Some(Self::Animation)),
+            3 => Some(Some(Self::Grain)),
+            4 => Some(Some(Self::StillImage)),
+            5 => Some(Some(Self::FastDecode)),
+            6 => Some(Some(Self::ZeroLatency)),
+            _ => None,
+        }
+    }
+
+    /// x264 option value
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Film => "film",
+            Self::Animation => "animation",
+            Self::Grain => "grain",
+            Self::StillImage => "stillimage",
+            Self::FastDecode => "fastdecode",
+            Self::ZeroLatency => "zerolatency",
+        }
+    }
+
+    /// Whether x265 has this tune too (it lacks film and stillimage)
+    pub fn in_x265(self) -> bool {
+        !matches!(self, Self::Film | Self::StillImage)
+    }
+}
+
+/// Video size/quality trade-off. The hardware encoders only follow
+/// `rate_control` and the rates; preset and tune are x264/x265 settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncoderQuality {
+    pub rate_control: RateControl,
+    /// CRF for `RateControl::Crf`, lower is better (0-`MAX_X264_CRF`, VP9
+    /// 0-`MAX_VP9_CRF`). None = the codec's default: 18 for H.264, 20 for
+    /// HEVC (x265's scale sits ~2 below x264's), 30 for VP9, cq 19 on NVENC
+    pub crf: Option<u32>,
+    /// Target for ABR and CBR (`b`)
+    pub bitrate_kbps: u32,
+    /// Peak rate (`maxrate`) capping CRF and ABR, so the output fits a
+    /// streaming or upload limit (0 = no cap; CBR always peaks at
+    /// `bitrate_kbps`)
+    pub max_bitrate_kbps: u32,
+    /// Decoder buffer the peak rate is measured over (`bufsize`; 0 = twice
+    /// the peak rate)
+    pub bufsize_kbits: u32,
+    pub preset: EncoderPreset,
+    pub tune: Option<EncoderTune>,
+}
+
+impl Default for EncoderQuality {
+    fn default() -> Self {
+        Self {
+            rate_control: RateControl::Crf,
+            crf: None,
+            bitrate_kbps: 8000,
+            max_bitrate_kbps: 0,
+            bufsize_kbits: 0,
+            preset: EncoderPreset::Fast,
+            tune: None,
+        }
+    }
+}
+
+impl EncoderQuality {
+    /// Peak rate and buffer size to enforce (kbit/s, kbit), if any
+    pub fn vbv(&self) -> Option<(u32, u32)> {
+        let peak = match self.rate_control {
+            RateControl::Cbr => self.bitrate_kbps,
+            _ => self.max_bitrate_kbps,
+        };
+        let bufsize = match self.bufsize_kbits {
+            0 => peak.saturating_mul(2),
+            bufsize => bufsize,
+        };
+        (peak > 0).then_some((peak, bufsize))
+    }
+}
+
+/// Region of the source frame to export (see `ProcessingOptions::crop`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropRegion {
+    /// Top-left corner (source px)
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CropRegion {
+    /// This region rounded down to even values, as 4:2:0 chroma needs, if
+    /// it still covers at least 2x2 pixels inside a `frame_width` x
+    /// `frame_height` frame
+    pub fn fit(self, frame_width: u32, frame_height: u32) -> Result<Self, EngineError> {
+        let even = |value: u32| value & !1;
+        let fitted = Self {
+            x: even(self.x),
+            y: even(self.y),
+            width: even(self.width),
+            height: even(self.height),
+        };
+        let inside = |start: u32, len: u32, frame: u32| {
+            len >= 2 && start.checked_add(len).is_some_and(|end| end <= frame)
+        };
+        if inside(fitted.x, fitted.width, frame_width)
+            && inside(fitted.y, fitted.height, frame_height)
+        {
+            Ok(fitted)
+        } else {
+            Err(EngineError::InvalidCrop(format!(
+                "{}x{} at ({}, {}) does not fit the {}x{} input",
+                self.width, self.height, self.x, self.y, frame_width, frame_height
+            )))
+        }
+    }
+}
+
+/// swscale filter for frames resized on the way to the encoder (see
+/// `ProcessingOptions::output_width`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleFilter {
+    /// Bilinear, or Lanczos past `SHARP_DOWNSCALE_RATIO`
+    #[default]
+    Auto,
+    Bilinear,
+    Bicubic,
+    Lanczos,
+    /// Averages the source pixels each output pixel covers
+    Area,
+}
+
+impl ScaleFilter {
+    /// FFI value (0 = auto, 1 = bilinear, 2 = bicubic, 3 = Lanczos, 4 = area)
+    pub fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Auto),
+            1 => Some(Self::Bilinear),
+            2 => Some(Self::Bicubic),
+            3 => Some(Self::Lanczos),
+            4 => Some(Self::Area),
+            _ => None,
+        }
+    }
+
+    /// The filter for scaling `from` to `to` (never `Auto`)
+    pub fn resolve(self, from: (u32, u32), to: (u32, u32)) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        let ratio = |src: u32, dst: u32| f64::from(src) / f64::from(dst.max(1));
+        if ratio(from.0, to.0).max(ratio(from.1, to.1)) > SHARP_DOWNSCALE_RATIO {
+            Self::Lanczos
+        } else {
+            Self::Bilinear
+        }
+    }
+}
+
+/// Bits per channel of the encoded video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitDepth {
+    /// Sources with more bits are dithered down rather than truncated
+    #[default]
+    Eight,
+    /// 10-bit 4:2:0: x264 High 10, x265 Main 10 or VP9 profile 2
+    Ten,
+}
+
+impl BitDepth {
+    /// FFI value (0 or 8 = 8-bit, 10 = 10-bit)
+    pub fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 | 8 => Some(Self::Eight),
+            10 => Some(Self::Ten),
+            _ => None,
+        }
+    }
+
+    pub fn bits(self) -> u32 {
+        match self {
+            Self::Eight => 8,
+            Self::Ten => 10,
+        }
+    }
+}
+
+/// Handling of smoothed positions that land outside the video frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameBounds {
+    /// Leave positions alone (the sprite may be clipped or vanish)
+    #[default]
+    Off,
+    /// Pin to the nearest edge pixel
+    Clamp,
+    /// Mirror the overshoot back into the frame
+    Reflect,
+}
+
+impl FrameBounds {
+    /// FFI value (0 = off, 1 = clamp, 2 = reflect)
+    pub fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Off),
+            1 => Some(Self::Clamp),
+            2 => Some(Self::Reflect),
+            _ => None,
+        }
+    }
+
+    /// Map `(x, y)` into `[0, width-1] x [0, height-1]`
+    pub fn apply(self, x: f32, y: f32, width: u32, height: u32) -> (f32, f32) {
+        let max_x = width.saturating_sub(1) as f32;
+        let max_y = height.saturating_sub(1) as f32;
+        match self {
+            Self::Off => (x, y),
+            Self::Clamp => (x.clamp(0.0, max_x), y.clamp(0.0, max_y)),
+            Self::Reflect => (reflect(x, max_x), reflect(y, max_y)),
+        }
+    }
+}
+
+/// Cursor shown for video frames before the first or after the last sample
+/// of the cursor path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TailBehavior {
+    /// Keep drawing the cursor at the nearest end of the path
+    #[default]
+    HoldLastPosition,
+    /// Hold for this long (ms), then stop drawing the cursor
+    HideAfterMs(u32),
+    /// Keep moving at the path's end velocity for this long (ms), then hold
+    ExtrapolateMs(u32),
+}
+
+impl TailBehavior {
+    /// FFI value (0 = hold, 1 = hide, 2 = extrapolate) and its duration
+    pub fn from_raw(value: i32, ms: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::HoldLastPosition),
+            1 => Some(Self::HideAfterMs(ms)),
+            2 => Some(Self::ExtrapolateMs(ms)),
+            _ => None,
+        }
+    }
+}
+
+/// Fading copies of the sprite along the path covered since the previous
+/// frame, so fast moves read as motion instead of a jump
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionTrail {
+    /// Ghosts per frame (1-16)
+    pub samples: u32,
+    /// Opacity of the newest ghost; each older one is multiplied by it again
+    pub falloff: f32,
+}
+
+impl Default for MotionTrail {
+    fn default() -> Self {
+        Self {
+            samples: 4,
+            falloff: 0.6,
+        }
+    }
+}
+
+impl MotionTrail {
+    /// Ghost positions and opacities for the frame at `timestamp_ms`, oldest
+    /// first, along the path `position_at` (non-finite where the cursor is
+    /// hidden).
+    ///
+    /// Ghosts sample the path evenly back to the previous frame. A sample
+    /// within `TRAIL_MIN_STEP_PX` of the last one kept (starting from the
+    /// cursor itself) is skipped, so a slow or resting cursor gets no trail
+    /// at all.
+    pub fn ghosts(
+        &self,
+        position_at: impl Fn(f64) -> (f32, f32),
+        timestamp_ms: f64,
+        frame_interval_ms: f64,
+    ) -> Vec<(f32, f32, f32)> {
+        let (mut last_x, mut last_y) = position_at(timestamp_ms);
+        if !last_x.is_finite() || !last_y.is_finite() {
+            return Vec::new();
+        }
+        let mut ghosts = Vec::with_capacity(self.samples as usize);
+        let mut opacity = 1.0;
+        for i in 1..=self.samples {
+            opacity *= self.falloff;
+            let t = timestamp_ms - frame_interval_ms * i as f64 / self.samples as f64;
+            let (x, y) = position_at(t);
+            // Hidden before this point (e.g. outside the capture region)
+            if !x.is_finite() || !y.is_finite() {
+                break;
+            }
+            if (x - last_x).hypot(y - last_y) < TRAIL_MIN_STEP_PX {
+                continue;
+            }
+            (last_x, last_y) = (x, y);
+            ghosts.push((x, y, opacity));
+        }
+        ghosts.reverse();
+        ghosts
+    }
+}
+
+/// Mirror `v` at 0 and `max`; overshoots larger than the frame end up clamped
+fn reflect(v: f32, max: f32) -> f32 {
+    let mirrored = if v < 0.0 {
+        -v
+    } else if v > max {
+        2.0 * max - v
+    } else {
+        v
+    };
+    mirrored.clamp(0.0, max)
+}
+
+// ============================================================================
+// Processing Options
+// ============================================================================
+
+/// Tuning for cursor smoothing and rendering
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessingOptions {
+    /// First-pass jitter filter
+    pub smoothing_mode: SmoothingMode,
+    /// Second-pass upsampling curve
+    pub interpolation: Interpolation,
+    /// Drop isolated glitch samples (implausibly fast jumps) before smoothing
+    pub reject_outliers: bool,
+    /// Capture gaps longer than this (ms) hold the cursor instead of being
+    /// interpolated across (0 = off)
+    pub max_gap_ms: f32,
+    /// Time (ms) at the end of a gap spent moving to the post-gap position
+    pub gap_ramp_ms: f32,
+    /// Unit of the raw cursor timestamps (Auto guesses from the duration)
+    pub timestamp_unit: TimestampUnit,
+    /// Where the recording sits in the recorded coordinates, e.g. the bounds
+    /// of the captured display on a multi-monitor desktop (None = the
+    /// coordinates already are video pixels)
+    pub coordinate_space: Option<CoordinateSpace>,
+    /// Replace the coordinate space scale with video size / capture region
+    /// size when rendering (HiDPI displays report points, videos are pixels)
+    pub auto_pixel_scale: bool,
+    /// Average denser cursor input down to this rate (Hz) before filtering;
+    /// 0 = off. 240 keeps 1000 Hz gaming-mouse traces fast without visible
+    /// change.
+    pub max_input_rate_hz: f32,
+    /// Drop filtered points closer than this (px) to a straight path before
+    /// upsampling; 0 = off. Saves work on long still stretches.
+    pub simplify_epsilon_px: f32,
+    /// Raw movement (px) that still counts as resting (0 = no rest snapping;
+    /// spring filter only)
+    pub rest_radius_px: f32,
+    /// Time (ms) within the radius before the cursor settles exactly
+    pub rest_dwell_ms: f32,
+    /// Longest spring integration substep in seconds (0 = derived from the
+    /// stiffness, always stable)
+    pub physics_max_step_sec: f32,
+    /// Interpret responsiveness/smoothness as settling time/damping ratio.
+    /// Changes the feel of existing presets, so it is opt-in for now.
+    pub physics_model_v2: bool,
+    /// Catmull-Rom parameter (0.5 = centripetal, recommended)
+    pub smoothing_alpha: f32,
+    /// Spring stiffness, 0.0 = slow/floaty .. 1.0 = snappy
+    pub responsiveness: f32,
+    /// Damping, 0.0 = slight overshoot .. 1.0 = no overshoot
+    pub smoothness: f32,
+    /// Output frame rate (1-240); GIF exports use `gif.frame_rate` instead
+    pub frame_rate: i32,
+    /// Keep the smoothed cursor inside the video frame (applied at render
+    /// time, once the frame size is known)
+    pub frame_bounds: FrameBounds,
+    /// Cursor shown where the video runs past either end of the cursor path
+    pub tail_behavior: TailBehavior,
+    /// Added (ms, may be negative) to each frame's time before the cursor is
+    /// looked up, after the automatic alignment: +40 draws the cursor where
+    /// it was 40 ms later, for capture setups whose mouse events lag the
+    /// video. Clicks, key presses and cursor kinds move with it.
+    pub cursor_sync_offset_ms: f64,
+    /// Re-time strokes that move or accelerate faster than a hand can (see
+    /// `reprofile_speed`); each stroke keeps its start and end time
+    pub enforce_kinematic_limits: bool,
+    /// Sprite draw size multiplier (0.1-8.0)
+    pub cursor_scale: f32,
+    /// Sprite pixel (unscaled) placed on the cursor position, e.g. the arrow tip.
+    /// (0, 0) uses the hotspot stored in .cur/.ani sprites (top-left otherwise).
+    pub hotspot_x: f32,
+    pub hotspot_y: f32,
+    /// 0.0 = invisible .. 1.0 = sprite's own alpha
+    pub cursor_opacity: f32,
+    /// Blend the sprite in linear light (see `CursorStyle::gamma_correct_blend`)
+    pub gamma_correct_blend: bool,
+    /// Sprite resampling filter. Bicubic keeps scaled-up sprites sharp but
+    /// draws every pixel on its own; YUV compositing ignores it.
+    pub sampling_quality: SamplingQuality,
+    /// Turn the sprite to face the cursor's direction of travel. The sprite
+    /// should point right (+x) as drawn; the heading holds while the cursor rests.
+    pub orient_to_velocity: bool,
+    /// Ripple drawn under the cursor at each click (None = off). Clicks come
+    /// from `process_video_with_clicks`, or the button-down events of
+    /// `process_video_with_events`.
+    pub click_effect: Option<ClickEffect>,
+    /// Blurred silhouette under the sprite (None = off). Generated from the
+    /// scaled sprite, so don't bake a shadow into the sprite image as well.
+    pub cursor_shadow: Option<CursorShadow>,
+    /// Ghost sprites along fast moves (None = off)
+    pub motion_trail: Option<MotionTrail>,
+    /// Dim the frame outside a circle around the cursor (None = off); drawn
+    /// below the click ripples, keystroke badge and cursor
+    pub spotlight: Option<Spotlight>,
+    /// Crop into the frame and pan to follow the cursor (None or zoom 1.0 =
+    /// off). Every other layer is drawn on the zoomed frame at its configured
+    /// size; the cursor path and clicks move with the crop.
+    pub auto_zoom: Option<AutoZoom>,
+    /// Zoom in around each click and back out when things go quiet (None or
+    /// zoom 1.0 = off). Takes precedence over `auto_zoom`.
+    pub click_zoom: Option<ClickZoom>,
+    /// Editor-placed zooms, full frame between them. Must not overlap; when
+    /// present `auto_zoom` and `click_zoom` are ignored.
+    pub zoom_keyframes: Vec<ZoomKeyframe>,
+    /// Blurred or pixelated rectangles on the video timeline, in source
+    /// frame coordinates. Applied before any zoom and every other layer.
+    pub redactions: Vec<RedactionRegion>,
+    /// Export only this part of the source frame (None = all of it). Cursor
+    /// points, clicks, redactions and zoom keyframe centers stay in source
+    /// coordinates and move with the crop; the cursor is not drawn while it
+    /// is outside. Checked against the input size when rendering starts.
+    pub crop: Option<CropRegion>,
+    /// What to do with rotation metadata (a display matrix) on the source.
+    /// Either way overlays are drawn on the upright picture, while cursor
+    /// points, clicks, redactions, zoom keyframe centers and `crop` stay in
+    /// the stored frame's coordinates and turn with it.
+    pub rotation: RotationHandling,
+    /// Export only this span of the video (ms from its first frame; None =
+    /// to the end). The export starts at 0, while cursor points and the
+    /// other timelines (clicks, zooms, captions, ...) stay on the full
+    /// video's clock. Cuts are frame-accurate, not keyframe-aligned (except
+    /// with `stream_copy`).
+    pub trim_start_ms: f64,
+    pub trim_end_ms: Option<f64>,
+    /// Play stretches where nothing moves faster (None or factor 1.0 = off).
+    /// The cursor and every other timeline follow the sped-up picture; the
+    /// output is silent when anything is sped up.
+    pub idle_speedup: Option<IdleSpeedup>,
+    /// Shrink the recording onto a styled background (None = off). Applied
+    /// after any zoom and below every other layer; the cursor path and clicks
+    /// move with the recording, sprite and effect sizes stay as configured.
+    pub frame_style: Option<FrameStyle>,
+    /// Badge showing `key_events` as they are pressed (None = off)
+    pub keystrokes: Option<KeystrokeDisplay>,
+    /// Text burned in over time ranges of the video, below the cursor
+    pub captions: Vec<Caption>,
+    /// Font and layout of `captions`
+    pub caption_style: CaptionStyle,
+    /// Logo in a corner of every frame, drawn above the cursor (None = off)
+    pub watermark: Option<Watermark>,
+    /// Key presses on the cursor points' clock
+    pub key_events: Vec<KeyEvent>,
+    /// Sprite per cursor kind, e.g. an I-beam for "ibeam". Kinds without an
+    /// entry draw the default sprite.
+    pub cursor_kind_sprites: BTreeMap<String, CursorKindSprite>,
+    /// Cursor kind changes on the cursor points' clock; the default sprite
+    /// shows until the first one
+    pub cursor_kind_events: Vec<CursorKindEvent>,
+    /// Blend the cursor straight into the decoded YUV420P frames instead of
+    /// converting every frame to RGBA and back, which dominates 4K renders.
+    /// Positions snap to whole pixels. Click ripples, keystroke badges, the
+    /// spotlight, auto zoom, `orient_to_velocity` and `gamma_correct_blend`
+    /// need RGBA, so any of them turns this off.
+    pub yuv_compositing: bool,
+    pub codec: VideoCodec,
+    /// Hardware or software encoder for `codec`; hardware encoders that are
+    /// missing or fail to open fall back to software. `deterministic` renders
+    /// always use software.
+    pub encoder_backend: EncoderBackend,
+    pub container: Container,
+    /// Write the MP4/MOV index (the moov atom) before the media data, so a
+    /// download can start playing before it finishes. The muxer moves it
+    /// in a second pass over the file once the render is done.
+    pub faststart: bool,
+    /// Write MP4/MOV as fragments starting at each keyframe, after an empty
+    /// index, so a file cut short by a crash still plays up to its last
+    /// fragment. Takes precedence over `faststart`, which it makes moot.
+    pub fragmented: bool,
+    /// Copy the input's container tags (creation_time, title, ...) to the
+    /// output, and the tags of each copied or re-encoded audio and subtitle
+    /// stream (e.g. its language). The muxer writes its own `encoder` tag.
+    pub copy_metadata: bool,
+    /// Container tags to set on the output (e.g. title, comment), over any
+    /// copied by `copy_metadata`
+    pub metadata: BTreeMap<String, String>,
+    /// VP9 encode speed against file size: the `deadline` and `cpu-used`
+    /// (0-`MAX_VP9_CPU_USED`, higher is faster) of libvpx-vp9. Ignored by
+    /// the other codecs and by VA-API.
+    pub vp9_deadline: Vp9Deadline,
+    pub vp9_cpu_used: u32,
+    pub gif: GifExport,
+    /// Output frame size (0 = derived from the other one with the source's
+    /// aspect ratio, both 0 = the source size). Rounded down to even sizes
+    /// for 4:2:0 chroma. The overlays are drawn on the source frame before it
+    /// is scaled, so the cursor, sprite and effects shrink or grow with the
+    /// video. For GIF, `gif.max_width` then applies to this size.
+    pub output_width: u32,
+    pub output_height: u32,
+    pub scale_filter: ScaleFilter,
+    /// Rate control, CRF and bitrates of the video encoder
+    pub quality: EncoderQuality,
+    /// Bits per channel of the output; GIF ignores it. For `Ten` the cursor
+    /// is composited in 10-bit YUV, as with `yuv_compositing`, unless an
+    /// option that needs RGBA is on, in which case frames are composited in
+    /// 8-bit RGBA and converted up. The encoder must take 10-bit frames;
+    /// hardware encoders that don't fall back to software.
+    pub bit_depth: BitDepth,
+    /// AAC bitrate for source audio the container cannot hold as is (e.g.
+    /// PCM or Opus into MP4); audio it can hold is copied untouched
+    pub audio_bitrate_kbps: u32,
+    /// Frames between `RenderObserver::heartbeat` calls (0 = never)
+    pub heartbeat_interval_frames: u32,
+    /// Decode, composite and encode on threads of their own, a few frames
+    /// apart, rather than one frame at a time on the calling thread. The
+    /// output is the same either way and the observer is still called on
+    /// the calling thread, but heartbeat stage times overlap.
+    pub pipelined: bool,
+    /// Copy the input's streams into the output instead of decoding and
+    /// re-encoding them, when there is nothing to draw or reframe: no cursor
+    /// points and no effects, captions, redactions, crop, scaling or idle
+    /// speed-up. The source codecs and rotation metadata are kept, so
+    /// `codec`, `quality`, `bit_depth` and the encoder settings do not apply.
+    /// Packets are only cut at keyframes, so a trimmed copy starts at the
+    /// keyframe at or before `trim_start_ms`. When the streams cannot be
+    /// copied (something to draw, or a source codec the container cannot
+    /// hold) the video is re-encoded, with a `StreamCopyUnavailable` warning.
+    pub stream_copy: bool,
+    /// Render a still image (an image file, or a video of a single frame) as
+    /// a one-frame video instead of failing with `EngineError::StillImage`
+    pub allow_still_image: bool,
+    /// Leave out video packets that fail to decode, with an
+    /// `UndecodablePacket` warning, instead of failing the render with
+    /// `EngineError::DecodeFailed`. The fps filter fills the gap by holding
+    /// the last good frame.
+    pub skip_decode_errors: bool,
+    /// Frame rate (1-240) of an image sequence input: a directory of PNGs
+    /// whose names end in a frame number, or a pattern such as
+    /// `frame_%05d.png`. Frame N is shown N / rate s after frame 0; a missing
+    /// number holds the frame before it, with a `SequenceFramesMissing`
+    /// warning. Video inputs keep their own timing.
+    pub sequence_frame_rate: i32,
+    /// Render only the cursor, click effects, keystrokes, captions and
+    /// watermark on a transparent background, for compositing over other
+    /// footage. The input is not decoded: frames have its size (read from
+    /// its header) and `frame_rate`, are scaled to `output_width` x
+    /// `output_height`, and run from `trim_start_ms` to the end of the cursor
+    /// path (or `trim_end_ms`), without audio. `codec` must carry alpha
+    /// (`VideoCodec::supports_alpha`): ProRes 4444 in MOV or MKV, or VP9 in
+    /// WebM or MKV. Zooms, spotlights, redactions, crops, idle speed-ups and
+    /// frame styles need the source video and fail with
+    /// `EngineError::OverlayUnsupported`.
+    pub overlay_only: bool,
+    /// Write a PNG poster frame of the finished output here (None = off),
+    /// from 10% of the way through it, as `generate_thumbnail` would
+    pub thumbnail_path: Option<PathBuf>,
+    /// Width the thumbnail is shrunk to, keeping the aspect ratio (0 = the
+    /// output width); narrower outputs are not enlarged
+    pub thumbnail_max_width: u32,
+    /// Render a quick proxy of the export for previewing: each side
+    /// `PREVIEW_SCALE_DIVISOR` times smaller, at most `PREVIEW_MAX_FRAME_RATE`
+    /// fps, 8-bit, encoded with the ultrafast preset at a high CRF, without
+    /// motion trails, gamma-correct blending or bicubic sampling. Zooms,
+    /// smoothing and every overlay are kept, drawn on the full-size frame
+    /// before it is scaled, so the proxy shows what the export will. The
+    /// report's `proxy` is set.
+    pub preview_mode: bool,
+    /// Bit-identical output for identical inputs: single-threaded encoding,
+    /// pinned encoder settings and no wall-clock or version metadata. Encoding
+    /// runs several times slower on multi-core machines, so use it for
+    /// regression tests, not user exports.
+    pub deterministic: bool,
+}
+
+impl Default for ProcessingOptions {
+    fn default() -> Self {
+        Self {
+            smoothing_mode: SmoothingMode::Spring,
+            interpolation: Interpolation::CatmullRom,
+            reject_outliers: false,
+            max_gap_ms: 0.0,
+            gap_ramp_ms: DEFAULT_GAP_RAMP_MS,
+            timestamp_unit: TimestampUnit::Auto,
+            coordinate_space: None,
+            auto_pixel_scale: false,
+            max_input_rate_hz: 0.0,
+            simplify_epsilon_px: 0.0,
+            rest_radius_px: REST_RADIUS_PX,
+            rest_dwell_ms: REST_DWELL_MS,
+            physics_max_step_sec: 0.0,
+            physics_model_v2: false,
+            smoothing_alpha: 0.5, // Centripetal Catmull-Rom
+            responsiveness: 0.5,  // Balanced response time
+            smoothness: 0.7,      // Mostly smooth with minimal overshoot
+            frame_rate: 60,
+            frame_bounds: FrameBounds::Off,
+            tail_behavior: TailBehavior::HoldLastPosition,
+            cursor_sync_offset_ms: 0.0,
+            enforce_kinematic_limits: false,
+            cursor_scale: 1.0,
+            hotspot_x: 0.0,
+            hotspot_y: 0.0,
+            cursor_opacity: 1.0,
+            gamma_correct_blend: false,
+            sampling_quality: SamplingQuality::Bilinear,
+            orient_to_velocity: false,
+            click_effect: None,
+            cursor_shadow: None,
+            motion_trail: None,
+            spotlight: None,
+            auto_zoom: None,
+            click_zoom: None,
+            zoom_keyframes: Vec::new(),
+            redactions: Vec::new(),
+            crop: None,
+            rotation: RotationHandling::Rotate,
+            trim_start_ms: 0.0,
+            trim_end_ms: None,
+            idle_speedup: None,
+            frame_style: None,
+            keystrokes: None,
+            captions: Vec::new(),
+            caption_style: CaptionStyle::default(),
+            watermark: None,
+            key_events: Vec::new(),
+            cursor_kind_sprites: BTreeMap::new(),
+            cursor_kind_events: Vec::new(),
+            yuv_compositing: false,
+            codec: VideoCodec::H264,
+            encoder_backend: EncoderBackend::Software,
+            container: Container::Auto,
+            faststart: false,
+            fragmented: false,
+            copy_metadata: false,
+            metadata: BTreeMap::new(),
+            vp9_deadline: Vp9Deadline::Good,
+            vp9_cpu_used: 4,
+            gif: GifExport::default(),
+            output_width: 0,
+            output_height: 0,
+            scale_filter: ScaleFilter::Auto,
+            quality: EncoderQuality::default(),
+            bit_depth: BitDepth::Eight,
+            audio_bitrate_kbps: 192,
+            heartbeat_interval_frames: 60,
+            pipelined: true,
+            stream_copy: false,
+            allow_still_image: false,
+            skip_decode_errors: false,
+            sequence_frame_rate: DEFAULT_SEQUENCE_FRAME_RATE,
+            overlay_only: false,
+            thumbnail_path: None,
+            thumbnail_max_width: DEFAULT_THUMBNAIL_MAX_WIDTH,
+            preview_mode: false,
+            deterministic: false,
+        }
+    }
+}
+
+impl ProcessingOptions {
+    /// Clamp out-of-range tuning values (warning about each adjustment) and
+    /// reject frame rates the pipeline cannot produce.
+    pub fn validated(&self) -> Result<Self, EngineError> {
+        if self.frame_rate <= 0 || self.frame_rate > MAX_FRAME_RATE {
+            return Err(EngineError::InvalidFrameRate(self.frame_rate));
+        }
+        if self.sequence_frame_rate <= 0 || self.sequence_frame_rate > MAX_FRAME_RATE {
+            return Err(EngineError::InvalidOption {
+                name: "sequence_frame_rate",
+                value: self.sequence_frame_rate,
+            });
+        }
+
+        let defaults = Self::default();
+        Ok(Self {
+            smoothing_mode: validated_mode(self.smoothing_mode),
+            coordinate_space: self.coordinate_space.map(validated_coordinate_space),
+            click_effect: self.click_effect.map(validated_click_effect),
+            cursor_shadow: self.cursor_shadow.map(validated_cursor_shadow),
+            motion_trail: self.motion_trail.map(validated_motion_trail),
+            spotlight: self.spotlight.map(validated_spotlight),
+            auto_zoom: self.auto_zoom.map(validated_auto_zoom),
+            click_zoom: self.click_zoom.map(validated_click_zoom),
+            idle_speedup: self.idle_speedup.map(validated_idle_speedup),
+            zoom_keyframes: validated_zoom_keyframes(&self.zoom_keyframes)?,
+            trim_end_ms: validated_trim(self.trim_start_ms, self.trim_end_ms)?,
+            redactions: self
+                .redactions
+                .iter()
+                .map(validated_redaction)
+                .collect::<Result<_, _>>()?,
+            frame_style: self.frame_style.map(validated_frame_style),
+            gif: validated_gif(self.gif),
+            quality: validated_quality(self.quality, self.codec),
+            keystrokes: self.keystrokes.clone().map(validated_keystrokes),
+            captions: self
+                .captions
+                .iter()
+                .map(validated_caption)
+                .collect::<Result<_, _>>()?,
+            caption_style: validated_caption_style(&self.caption_style),
+            metadata: validated_metadata(&self.metadata)?,
+            watermark: self
+                .watermark
+                .as_ref()
+                .map(validated_watermark)
+                .transpose()?,
+            cursor_kind_sprites: self
+                .cursor_kind_sprites
+                .iter()
+                .map(|(kind, sprite)| (kind.clone(), validated_kind_sprite(sprite)))
+                .collect(),
+            smoothing_alpha: clamp_unit(
+                "smoothing_alpha",
+                self.smoothing_alpha,
+                defaults.smoothing_alpha,
+            ),
+            responsiveness: clamp_unit(
+                "responsiveness",
+                self.responsiveness,
+                defaults.responsiveness,
+            ),
+            smoothness: clamp_unit("smoothness", self.smoothness, defaults.smoothness),
+            cursor_scale: clamp_range(
+                "cursor_scale",
+                self.cursor_scale,
+                MIN_CURSOR_SCALE,
+                MAX_CURSOR_SCALE,
+                defaults.cursor_scale,
+            ),
+            hotspot_x: finite_or("hotspot_x", self.hotspot_x, defaults.hotspot_x),
+            hotspot_y: finite_or("hotspot_y", self.hotspot_y, defaults.hotspot_y),
+            cursor_opacity: clamp_unit(
+                "cursor_opacity",
+                self.cursor_opacity,
+                defaults.cursor_opacity,
+            ),
+            max_gap_ms: clamp_range("max_gap_ms", self.max_gap_ms, 0.0, f32::MAX, 0.0),
+            gap_ramp_ms: clamp_range(
+                "gap_ramp_ms",
+                self.gap_ramp_ms,
+                0.0,
+                MAX_GAP_RAMP_MS,
+                defaults.gap_ramp_ms,
+            ),
+            max_input_rate_hz: if self.max_input_rate_hz == 0.0 {
+                0.0
+            } else {
+                clamp_range(
+                    "max_input_rate_hz",
+                    self.max_input_rate_hz,
+                    MIN_INPUT_RATE_HZ,
+                    MAX_INPUT_RATE_HZ,
+                    defaults.max_input_rate_hz,
+                )
+            },
+            simplify_epsilon_px: clamp_range(
+                "simplify_epsilon_px",
+                self.simplify_epsilon_px,
+                0.0,
+                MAX_SIMPLIFY_EPSILON_PX,
+                defaults.simplify_epsilon_px,
+            ),
+            rest_radius_px: clamp_range(
+                "rest_radius_px",
+                self.rest_radius_px,
+                0.0,
+                f32::MAX,
+                defaults.rest_radius_px,
+            ),
+            rest_dwell_ms: clamp_range(
+                "rest_dwell_ms",
+                self.rest_dwell_ms,
+                0.0,
+                MAX_REST_DWELL_MS,
+                defaults.rest_dwell_ms,
+            ),
+            physics_max_step_sec: if self.physics_max_step_sec == 0.0 {
+                0.0
+            } else {
+                clamp_range(
+                    "physics_max_step_sec",
+                    self.physics_max_step_sec,
+                    PHYSICS_MIN_STEP_SEC,
+                    PHYSICS_MAX_DT_SEC,
+                    defaults.physics_max_step_sec,
+                )
+            },
+            tail_behavior: match self.tail_behavior {
+                TailBehavior::ExtrapolateMs(ms) if ms > MAX_TAIL_EXTRAPOLATE_MS => {
+                    log::warn!(
+                        "tail extrapolation {} ms out of range, clamped to {}",
+                        ms,
+                        MAX_TAIL_EXTRAPOLATE_MS
+                    );
+                    TailBehavior::ExtrapolateMs(MAX_TAIL_EXTRAPOLATE_MS)
+                }
+                tail => tail,
+            },
+            cursor_sync_offset_ms: if self.cursor_sync_offset_ms.is_finite() {
+                self.cursor_sync_offset_ms
+            } else {
+                log::warn!(
+                    "cursor_sync_offset_ms is {}, using 0",
+                    self.cursor_sync_offset_ms
+                );
+                0.0
+            },
+            audio_bitrate_kbps: {
+                let clamped = self
+                    .audio_bitrate_kbps
+                    .clamp(MIN_AUDIO_BITRATE_KBPS, MAX_AUDIO_BITRATE_KBPS);
+                if clamped != self.audio_bitrate_kbps {
+                    log::warn!(
+                        "audio_bitrate_kbps {} out of range, clamped to {}",
+                        self.audio_bitrate_kbps,
+                        clamped
+                    );
+                }
+                clamped
+            },
+            output_width: clamp_dimension("output_width", self.output_width),
+            output_height: clamp_dimension("output_height", self.output_height),
+            vp9_cpu_used: {
+                let clamped = self.vp9_cpu_used.min(MAX_VP9_CPU_USED);
+                if clamped != self.vp9_cpu_used {
+                    log::warn!(
+                        "vp9_cpu_used {} out of range, clamped to {}",
+                        self.vp9_cpu_used,
+                        clamped
+                    );
+                }
+                clamped
+            },
+            ..self.clone()
+        })
+    }
+
+    /// Spring-filter settings: rest snapping, integration substeps and model
+    pub fn spring_tuning(&self) -> SpringTuning {
+        SpringTuning {
+            rest: (self.rest_radius_px > 0.0).then(|| RestSnap {
+                radius_px: self.rest_radius_px,
+                dwell_ms: self.rest_dwell_ms as f64,
+            }),
+            max_step_sec: (self.physics_max_step_sec > 0.0).then_some(self.physics_max_step_sec),
+            physics_model_v2: self.physics_model_v2,
+        }
+    }
+
+    /// Timestamp units, outlier rejection, decimation and simplification
+    pub fn path_cleanup(&self) -> PathCleanup {
+        PathCleanup {
+            timestamp_unit: self.timestamp_unit,
+            coordinates: self.coordinate_space,
+            max_speed_px_per_sec: self.reject_outliers.then_some(VELOCITY_MAX_PX_PER_SEC),
+            max_input_rate_hz: (self.max_input_rate_hz > 0.0)
+                .then_some(self.max_input_rate_hz as f64),
+            simplify_epsilon_px: (self.simplify_epsilon_px > 0.0)
+                .then_some(self.simplify_epsilon_px),
+        }
+    }
+
+    /// Gap handling for the upsampling pass, if enabled
+    pub fn gap_hold(&self) -> Option<GapHold> {
+        (self.max_gap_ms > 0.0).then(|| GapHold {
+            max_gap_ms: self.max_gap_ms as f64,
+            ramp_ms: self.gap_ramp_ms as f64,
+        })
+    }
+
+    /// Encoded frame size for a `source_width` x `source_height` input
+    pub fn output_size(&self, source_width: u32, source_height: u32) -> (u32, u32) {
+        let scaled = |value: u32, to: u32, from: u32| {
+            (u64::from(value) * u64::from(to) + u64::from(from) / 2) / u64::from(from.max(1))
+        };
+        let (width, height) = match (self.output_width, self.output_height) {
+            (0, 0) if !self.preview_mode => return (source_width, source_height),
+            (0, 0) => (source_width, source_height),
+            (0, height) => (scaled(source_width, height, source_height) as u32, height),
+            (width, 0) => (width, scaled(source_height, width, source_width) as u32),
+            size => size,
+        };
+        let (width, height) = if self.preview_mode {
+            (
+                width / PREVIEW_SCALE_DIVISOR,
+                height / PREVIEW_SCALE_DIVISOR,
+            )
+        } else {
+            (width, height)
+        };
+        ((width & !1).max(2), (height & !1).max(2))
+    }
+
+    /// The settings `preview_mode` overrides: frame rate, encoder quality
+    /// and the effects that cost the most for the least visible difference
+    /// at a reduced size. The size itself is taken care of by `output_size`.
+    fn apply_preview_mode(&mut self) {
+        self.frame_rate = self.frame_rate.min(PREVIEW_MAX_FRAME_RATE);
+        self.quality = EncoderQuality {
+            rate_control: RateControl::Crf,
+            crf: Some(match self.codec {
+                VideoCodec::Vp9 => PREVIEW_CRF_VP9,
+                _ => PREVIEW_CRF,
+            }),
+            preset: EncoderPreset::Ultrafast,
+            tune: None,
+            ..EncoderQuality::default()
+        };
+        self.bit_depth = BitDepth::Eight;
+        self.vp9_deadline = Vp9Deadline::Realtime;
+        self.vp9_cpu_used = MAX_VP9_CPU_USED;
+        self.scale_filter = ScaleFilter::Bilinear;
+        // Preview-skippable effects
+        self.motion_trail = None;
+        self.gamma_correct_blend = false;
+        self.sampling_quality = SamplingQuality::Bilinear;
+    }
+
+    /// Sprite placement used by the compositor
+    pub fn cursor_style(&self) -> CursorStyle {
+        CursorStyle {
+            scale: self.cursor_scale,
+            hotspot_x: self.hotspot_x,
+            hotspot_y: self.hotspot_y,
+            opacity: self.cursor_opacity,
+            gamma_correct_blend: self.gamma_correct_blend,
+            // Per frame, from the path heading (see `orient_to_velocity`)
+            rotation: 0.0,
+            sampling: self.sampling_quality,
+        }
+    }
+}
+
+/// Clamp filter parameters to ranges that keep the filter stable
+fn validated_mode(mode: SmoothingMode) -> SmoothingMode {
+    match mode {
+        SmoothingMode::Spring | SmoothingMode::Passthrough => mode,
+        SmoothingMode::OneEuro {
+            min_cutoff,
+            beta,
+            d_cutoff,
+        } => SmoothingMode::OneEuro {
+            min_cutoff: clamp_range(
+                "one_euro_min_cutoff",
+                min_cutoff,
+                ONE_EURO_MIN_CUTOFF_HZ,
+                ONE_EURO_MAX_CUTOFF_HZ,
+                ONE_EURO_MIN_CUTOFF,
+            ),
+            beta: clamp_range("one_euro_beta", beta, 0.0, ONE_EURO_MAX_BETA, ONE_EURO_BETA),
+            d_cutoff: clamp_range(
+                "one_euro_d_cutoff",
+                d_cutoff,
+                ONE_EURO_MIN_CUTOFF_HZ,
+                ONE_EURO_MAX_CUTOFF_HZ,
+                ONE_EURO_D_CUTOFF,
+            ),
+        },
+        SmoothingMode::Kalman {
+            process_noise,
+            measurement_noise,
+        } => SmoothingMode::Kalman {
+            process_noise: clamp_range(
+                "kalman_process_noise",
+                process_noise,
+                KALMAN_MIN_PROCESS_NOISE,
+                KALMAN_MAX_PROCESS_NOISE,
+                KALMAN_PROCESS_NOISE,
+            ),
+            measurement_noise: clamp_range(
+                "kalman_measurement_noise",
+                measurement_noise,
+                KALMAN_MIN_MEASUREMENT_NOISE,
+                KALMAN_MAX_MEASUREMENT_NOISE,
+                KALMAN_MEASUREMENT_NOISE,
+            ),
+        },
+        SmoothingMode::BezierStrokes {
+            dwell_speed_px_per_sec,
+            dwell_ms,
+        } => SmoothingMode::BezierStrokes {
+            dwell_speed_px_per_sec: clamp_range(
+                "bezier_dwell_speed_px_per_sec",
+                dwell_speed_px_per_sec,
+                BEZIER_MIN_DWELL_SPEED_PX_PER_SEC,
+                BEZIER_MAX_DWELL_SPEED_PX_PER_SEC,
+                BEZIER_DWELL_SPEED_PX_PER_SEC,
+            ),
+            dwell_ms: clamp_range(
+                "bezier_dwell_ms",
+                dwell_ms,
+                BEZIER_MIN_DWELL_MS,
+                BEZIER_MAX_DWELL_MS,
+                BEZIER_DWELL_MS,
+            ),
+        },
+    }
+}
+
+fn validated_coordinate_space(space: CoordinateSpace) -> CoordinateSpace {
+    let size = space.size.filter(|&(w, h)| {
+        let valid = w.is_finite() && h.is_finite() && w > 0.0 && h > 0.0;
+        if !valid {
+            log::warn!("capture region size {}x{} is invalid, ignoring it", w, h);
+        }
+        valid
+    });
+    CoordinateSpace {
+        origin_x: finite_or("capture_x", space.origin_x, 0.0),
+        origin_y: finite_or("capture_y", space.origin_y, 0.0),
+        scale_x: clamp_range(
+            "capture_scale_x",
+            space.scale_x,
+            MIN_COORDINATE_SCALE,
+            MAX_COORDINATE_SCALE,
+            1.0,
+        ),
+        scale_y: clamp_range(
+            "capture_scale_y",
+            space.scale_y,
+            MIN_COORDINATE_SCALE,
+            MAX_COORDINATE_SCALE,
+            1.0,
+        ),
+        size,
+        outside: space.outside,
+    }
+}
+
+fn validated_click_effect(effect: ClickEffect) -> ClickEffect {
+    let defaults = ClickEffect::default();
+    let radius = |name, value, default| clamp_range(name, value, 0.0, MAX_CLICK_RADIUS_PX, default);
+    ClickEffect {
+        duration_ms: clamp_range(
+            "click_duration_ms",
+            effect.duration_ms,
+            0.0,
+            MAX_CLICK_DURATION_MS,
+            defaults.duration_ms,
+        ),
+        start_radius_px: radius(
+            "click_start_radius_px",
+            effect.start_radius_px,
+            defaults.start_radius_px,
+        ),
+        end_radius_px: radius(
+            "click_end_radius_px",
+            effect.end_radius_px,
+            defaults.end_radius_px,
+        ),
+        ring_width_px: radius(
+            "click_ring_width_px",
+            effect.ring_width_px,
+            defaults.ring_width_px,
+        ),
+        opacity: clamp_unit("click_opacity", effect.opacity, defaults.opacity),
+        ..effect
+    }
+}
+
+fn validated_cursor_shadow(shadow: CursorShadow) -> CursorShadow {
+    let defaults = CursorShadow::default();
+    CursorShadow {
+        offset_x: finite_or("shadow_offset_x", shadow.offset_x, defaults.offset_x),
+        offset_y: finite_or("shadow_offset_y", shadow.offset_y, defaults.offset_y),
+        blur_radius_px: clamp_range(
+            "shadow_blur_px",
+            shadow.blur_radius_px,
+            0.0,
+            MAX_SHADOW_BLUR_PX,
+            defaults.blur_radius_px,
+        ),
+        opacity: clamp_unit("shadow_opacity", shadow.opacity, defaults.opacity),
+    }
+}
+
+fn validated_motion_trail(trail: MotionTrail) -> MotionTrail {
+    let samples = trail.samples.clamp(1, MAX_TRAIL_SAMPLES);
+    if samples != trail.samples {
+        log::warn!(
+            "motion_trail_samples {} out of range, clamped to {}",
+            trail.samples,
+            samples
+        );
+    }
+    MotionTrail {
+        samples,
+        falloff: clamp_unit(
+            "motion_trail_falloff",
+            trail.falloff,
+            MotionTrail::default().falloff,
+        ),
+    }
+}
+
+fn validated_spotlight(spotlight: Spotlight) -> Spotlight {
+    let defaults = Spotlight::default();
+    Spotlight {
+        radius_px: clamp_range(
+            "spotlight_radius_px",
+            spotlight.radius_px,
+            0.0,
+            MAX_SPOTLIGHT_RADIUS_PX,
+            defaults.radius_px,
+        ),
+        feather_px: clamp_range(
+            "spotlight_feather_px",
+            spotlight.feather_px,
+            0.0,
+            MAX_SPOTLIGHT_FEATHER_PX,
+            defaults.feather_px,
+        ),
+        dim_opacity: clamp_unit(
+            "spotlight_dim_opacity",
+            spotlight.dim_opacity,
+            defaults.dim_opacity,
+        ),
+    }
+}
+
+fn validated_auto_zoom(zoom: AutoZoom) -> AutoZoom {
+    let defaults = AutoZoom::default();
+    AutoZoom {
+        zoom: clamp_range("auto_zoom", zoom.zoom, 1.0, MAX_AUTO_ZOOM, defaults.zoom),
+        margin_x_px: clamp_range(
+            "zoom_margin_x_px",
+            zoom.margin_x_px,
+            0.0,
+            MAX_ZOOM_MARGIN_PX,
+            defaults.margin_x_px,
+        ),
+        margin_y_px: clamp_range(
+            "zoom_margin_y_px",
+            zoom.margin_y_px,
+            0.0,
+            MAX_ZOOM_MARGIN_PX,
+            defaults.margin_y_px,
+        ),
+    }
+}
+
+fn validated_frame_style(style: FrameStyle) -> FrameStyle {
+    let defaults = FrameStyle::default();
+    FrameStyle {
+        padding_percent: clamp_range(
+            "frame_padding_percent",
+            style.padding_percent,
+            0.0,
+            MAX_FRAME_PADDING_PERCENT,
+            defaults.padding_percent,
+        ),
+        corner_radius_px: clamp_range(
+            "frame_corner_radius_px",
+            style.corner_radius_px,
+            0.0,
+            MAX_FRAME_CORNER_RADIUS_PX,
+            defaults.corner_radius_px,
+        ),
+        background: style.background,
+        shadow_blur_px: clamp_range(
+            "frame_shadow_blur_px",
+            style.shadow_blur_px,
+            0.0,
+            MAX_FRAME_SHADOW_BLUR_PX,
+            defaults.shadow_blur_px,
+        ),
+        shadow_opacity: clamp_unit(
+            "frame_shadow_opacity",
+            style.shadow_opacity,
+            defaults.shadow_opacity,
+        ),
+        shadow_offset_x: finite_or(
+            "frame_shadow_offset_x",
+            style.shadow_offset_x,
+            defaults.shadow_offset_x,
+        ),
+        shadow_offset_y: finite_or(
+            "frame_shadow_offset_y",
+            style.shadow_offset_y,
+            defaults.shadow_offset_y,
+        ),
+    }
+}
+
+fn validated_quality(quality: EncoderQuality, codec: VideoCodec) -> EncoderQuality {
+    let max_crf = match codec {
+        VideoCodec::Vp9 => MAX_VP9_CRF,
+        _ => MAX_X264_CRF,
+    };
+    let clamp_rate = |name: &str, value: u32, min: u32, max: u32| {
+        let clamped = value.clamp(min, max);
+        if clamped != value {
+            log::warn!("{} {} out of range, clamped to {}", name, value, clamped);
+        }
+        clamped
+    };
+    // 0 turns the cap and the buffer size off rather than being out of range
+    let optional_rate = |name: &str, value: u32, max: u32| match value {
+        0 => 0,
+        value => clamp_rate(name, value, MIN_VIDEO_BITRATE_KBPS, max),
+    };
+    EncoderQuality {
+        crf: quality.crf.map(|crf| clamp_rate("crf", crf, 0, max_crf)),
+        bitrate_kbps: clamp_rate(
+            "video_bitrate_kbps",
+            quality.bitrate_kbps,
+            MIN_VIDEO_BITRATE_KBPS,
+            MAX_VIDEO_BITRATE_KBPS,
+        ),
+        max_bitrate_kbps: optional_rate(
+            "max_bitrate_kbps",
+            quality.max_bitrate_kbps,
+            MAX_VIDEO_BITRATE_KBPS,
+        ),
+        bufsize_kbits: optional_rate(
+            "bufsize_kbits",
+            quality.bufsize_kbits,
+            MAX_VIDEO_BITRATE_KBPS * 2,
+        ),
+        ..quality
+    }
+}
+
+/// Clamp an output dimension to `MAX_OUTPUT_DIMENSION` (0 stays 0)
+fn clamp_dimension(name: &str, value: u32) -> u32 {
+    let clamped = value.min(MAX_OUTPUT_DIMENSION);
+    if clamped != value {
+        log::warn!("{} {} out of range, clamped to {}", name, value, clamped);
+    }
+    clamped
+}
+
+fn validated_gif(gif: GifExport) -> GifExport {
+    let frame_rate = gif.frame_rate.clamp(1, MAX_GIF_FRAME_RATE);
+    if frame_rate != gif.frame_rate {
+        log::warn!(
+            "gif_frame_rate {} out of range, clamped to {}",
+            gif.frame_rate,
+            frame_rate
+        );
+    }
+    GifExport { frame_rate, ..gif }
+}
+
+fn validated_click_zoom(zoom: ClickZoom) -> ClickZoom {
+    let defaults = ClickZoom::default();
+    ClickZoom {
+        zoom: clamp_range("click_zoom", zoom.zoom, 1.0, MAX_AUTO_ZOOM, defaults.zoom),
+        dwell_ms: clamp_range(
+            "click_zoom_dwell_ms",
+            zoom.dwell_ms,
+            0.0,
+            MAX_CLICK_ZOOM_DWELL_MS,
+            defaults.dwell_ms,
+        ),
+        min_spacing_ms: clamp_range(
+            "click_zoom_spacing_ms",
+            zoom.min_spacing_ms,
+            0.0,
+            MAX_CLICK_ZOOM_SPACING_MS,
+            defaults.min_spacing_ms,
+        ),
+    }
+}
+
+fn validated_idle_speedup(speedup: IdleSpeedup) -> IdleSpeedup {
+    let defaults = IdleSpeedup::default();
+    IdleSpeedup {
+        factor: clamp_range(
+            "idle_speedup",
+            speedup.factor,
+            1.0,
+            MAX_IDLE_SPEEDUP,
+            defaults.factor,
+        ),
+        min_idle_ms: clamp_range(
+            "idle_min_ms",
+            speedup.min_idle_ms,
+            0.0,
+            MAX_IDLE_MS,
+            defaults.min_idle_ms,
+        ),
+        max_displacement_px: clamp_range(
+            "idle_max_displacement_px",
+            speedup.max_displacement_px,
+            0.0,
+            MAX_IDLE_DISPLACEMENT_PX,
+            defaults.max_displacement_px,
+        ),
+        max_frame_change_percent: clamp_range(
+            "idle_max_frame_change_percent",
+            speedup.max_frame_change_percent,
+            0.0,
+            100.0,
+            defaults.max_frame_change_percent,
+        ),
+    }
+}
+
+/// Sorted zoom keyframes with clamped zooms; empty or overlapping time
+/// ranges are errors
+fn validated_zoom_keyframes(keyframes: &[ZoomKeyframe]) -> Result<Vec<ZoomKeyframe>, EngineError> {
+    let mut sorted = Vec::with_capacity(keyframes.len());
+    for key in keyframes {
+        if !(key.start_ms.is_finite() && key.end_ms.is_finite() && key.end_ms > key.start_ms) {
+            return Err(EngineError::InvalidZoomKeyframes(format!(
+                "keyframe {}-{} ms has no duration",
+                key.start_ms, key.end_ms
+            )));
+        }
+        if !(key.center_x.is_finite() && key.center_y.is_finite()) {
+            return Err(EngineError::InvalidZoomKeyframes(format!(
+                "keyframe {}-{} ms has center ({}, {})",
+                key.start_ms, key.end_ms, key.center_x, key.center_y
+            )));
+        }
+        sorted.push(ZoomKeyframe {
+            zoom: clamp_range("zoom_keyframe_zoom", key.zoom, 1.0, MAX_AUTO_ZOOM, 2.0),
+            ..*key
+        });
+    }
+    sorted.sort_by(|a, b| a.start_ms.total_cmp(&b.start_ms));
+    if let Some(pair) = sorted.windows(2).find(|w| w[1].start_ms < w[0].end_ms) {
+        return Err(EngineError::InvalidZoomKeyframes(format!(
+            "keyframes {}-{} ms and {}-{} ms overlap",
+            pair[0].start_ms, pair[0].end_ms, pair[1].start_ms, pair[1].end_ms
+        )));
+    }
+    Ok(sorted)
+}
+
+/// `end_ms` if the trim range starts at or after 0 and ends after it starts
+fn validated_trim(start_ms: f64, end_ms: Option<f64>) -> Result<Option<f64>, EngineError> {
+    let valid = start_ms.is_finite()
+        && start_ms >= 0.0
+        && end_ms.is_none_or(|end| end.is_finite() && end > start_ms);
+    if !valid {
+        return Err(EngineError::InvalidTrim(format!(
+            "{} to {:?} ms",
+            start_ms, end_ms
+        )));
+    }
+    Ok(end_ms)
+}
+
+/// `region` with its blur radius or block size clamped; an empty rectangle
+/// or time range is an error
+fn validated_redaction(region: &RedactionRegion) -> Result<RedactionRegion, EngineError> {
+    let timing_ok = region.start_ms.is_finite()
+        && !matches!(region.end_ms, Some(end) if end.is_nan() || end <= region.start_ms);
+    if !timing_ok {
+        return Err(EngineError::InvalidRedaction(format!(
+            "region at ({}, {}) runs from {} to {:?} ms",
+            region.x, region.y, region.start_ms, region.end_ms
+        )));
+    }
+    if region.width == 0 || region.height == 0 {
+        return Err(EngineError::InvalidRedaction(format!(
+            "region at ({}, {}) is {}x{}",
+            region.x, region.y, region.width, region.height
+        )));
+    }
+    let clamp = |name: &str, value: u32, min: u32, max: u32| {
+        let clamped = value.clamp(min, max);
+        if clamped != value {
+            log::warn!("{} {} out of range, clamped to {}", name, value, clamped);
+        }
+        clamped
+    };
+    let mode = match region.mode {
+        RedactionMode::Blur { radius_px } => RedactionMode::Blur {
+            radius_px: clamp("redaction_blur_px", radius_px, 1, MAX_REDACTION_BLUR_PX),
+        },
+        RedactionMode::Pixelate { block_px } => RedactionMode::Pixelate {
+            block_px: clamp("redaction_block_px", block_px, 2, MAX_REDACTION_BLOCK_PX),
+        },
+    };
+    Ok(RedactionRegion { mode, ..*region })
+}
+
+fn validated_kind_sprite(sprite: &CursorKindSprite) -> CursorKindSprite {
+    CursorKindSprite {
+        hotspot_x: finite_or("cursor_kind_hotspot_x", sprite.hotspot_x, 0.0),
+        hotspot_y: finite_or("cursor_kind_hotspot_y", sprite.hotspot_y, 0.0),
+        ..sprite.clone()
+    }
+}
+
+/// `watermark` with its numbers clamped; an RGBA image of the wrong size is
+/// an error
+fn validated_watermark(watermark: &Watermark) -> Result<Watermark, EngineError> {
+    if let WatermarkImage::Rgba {
+        data,
+        width,
+        height,
+    } = &watermark.image
+    {
+        let needed = *width as usize * *height as usize * 4;
+        if data.len() != needed {
+            return Err(EngineError::InvalidBuffer(format!(
+                "watermark has {} bytes, width * height * 4 is {}",
+                data.len(),
+                needed
+            )));
+        }
+    }
+    let defaults = Watermark::default();
+    Ok(Watermark {
+        margin_x_px: clamp_range(
+            "watermark_margin_x_px",
+            watermark.margin_x_px,
+            0.0,
+            f32::MAX,
+            defaults.margin_x_px,
+        ),
+        margin_y_px: clamp_range(
+            "watermark_margin_y_px",
+            watermark.margin_y_px,
+            0.0,
+            f32::MAX,
+            defaults.margin_y_px,
+        ),
+        scale: clamp_range(
+            "watermark_scale",
+            watermark.scale,
+            MIN_WATERMARK_SCALE,
+            MAX_WATERMARK_SCALE,
+            defaults.scale,
+        ),
+        opacity: clamp_unit("watermark_opacity", watermark.opacity, defaults.opacity),
+        ..watermark.clone()
+    })
+}
+
+/// `caption` with its text size clamped; blank text, an empty time range and
+/// right-to-left text are errors
+fn validated_caption(caption: &Caption) -> Result<Caption, EngineError> {
+    let range_ok = caption.start_ms.is_finite()
+        && caption.end_ms.is_finite()
+        && caption.end_ms > caption.start_ms;
+    if !range_ok {
+        return Err(EngineError::InvalidCaption(format!(
+            "{:?} runs from {} to {} ms",
+            caption.text, caption.start_ms, caption.end_ms
+        )));
+    }
+    if caption.text.trim().is_empty() {
+        return Err(EngineError::InvalidCaption(format!(
+            "caption at {}-{} ms has no text",
+            caption.start_ms, caption.end_ms
+        )));
+    }
+    // Drawn left to right and unshaped, it would come out garbled
+    if caption.has_right_to_left() {
+        return Err(EngineError::InvalidCaption(format!(
+            "{:?}: right-to-left text is not supported",
+            caption.text
+        )));
+    }
+    Ok(Caption {
+        font_size_px: clamp_range(
+            "caption_font_size_px",
+            caption.font_size_px,
+            1.0,
+            MAX_CAPTION_FONT_PX,
+            Caption::default().font_size_px,
+        ),
+        ..caption.clone()
+    })
+}
+
+/// `metadata` as given; empty keys and NULs, which FFmpeg's dictionaries
+/// cannot hold, are errors
+fn validated_metadata(
+    metadata: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, EngineError> {
+    for (key, value) in metadata {
+        if key.is_empty() {
+            return Err(EngineError::InvalidMetadata(format!(
+                "empty key for {:?}",
+                value
+            )));
+        }
+        if key.contains('\0') || value.contains('\0') {
+            return Err(EngineError::InvalidMetadata(format!(
+                "{:?} contains a NUL",
+                key
+            )));
+        }
+    }
+    Ok(metadata.clone())
+}
+
+fn validated_caption_style(style: &CaptionStyle) -> CaptionStyle {
+    let defaults = CaptionStyle::default();
+    CaptionStyle {
+        max_width_percent: clamp_range(
+            "caption_max_width_percent",
+            style.max_width_percent,
+            10.0,
+            100.0,
+            defaults.max_width_percent,
+        ),
+        margin_px: clamp_range(
+            "caption_margin_px",
+            style.margin_px,
+            0.0,
+            f32::MAX,
+            defaults.margin_px,
+        ),
+        spacing_px: clamp_range(
+            "caption_spacing_px",
+            style.spacing_px,
+            0.0,
+            f32::MAX,
+            defaults.spacing_px,
+        ),
+        fade_in_ms: clamp_range(
+            "caption_fade_in_ms",
+            style.fade_in_ms,
+            0.0,
+            MAX_CAPTION_FADE_MS,
+            defaults.fade_in_ms,
+        ),
+        fade_out_ms: clamp_range(
+            "caption_fade_out_ms",
+            style.fade_out_ms,
+            0.0,
+            MAX_CAPTION_FADE_MS,
+            defaults.fade_out_ms,
+        ),
+        ..style.clone()
+    }
+}
+
+fn validated_keystrokes(display: KeystrokeDisplay) -> KeystrokeDisplay {
+    let defaults = KeystrokeDisplay::default();
+    let hold_ms = clamp_range(
+        "keystroke_hold_ms",
+        display.hold_ms,
+        0.0,
+        MAX_KEYSTROKE_HOLD_MS,
+        defaults.hold_ms,
+    );
+    KeystrokeDisplay {
+        font_size_px: clamp_range(
+            "keystroke_font_size_px",
+            display.font_size_px,
+            1.0,
+            MAX_KEYSTROKE_FONT_PX,
+            defaults.font_size_px,
+        ),
+        margin_px: clamp_range(
+            "keystroke_margin_px",
+            display.margin_px,
+            0.0,
+            f32::MAX,
+            defaults.margin_px,
+        ),
+        hold_ms,
+        // The fade is the end of the hold, never longer than it
+        fade_ms: clamp_range(
+            "keystroke_fade_ms",
+            display.fade_ms,
+            0.0,
+            hold_ms,
+            defaults.fade_ms.min(hold_ms),
+        ),
+        background_opacity: clamp_unit(
+            "keystroke_background_opacity",
+            display.background_opacity,
+            defaults.background_opacity,
+        ),
+        ..display
+    }
+}
+
+/// Clamp a 0-1 parameter; non-finite values fall back to the default
+fn clamp_unit(name: &str, value: f32, default: f32) -> f32 {
+    clamp_range(name, value, 0.0, 1.0, default)
+}
+
+/// Clamp to `min..=max`; non-finite values fall back to the default
+fn clamp_range(name: &str, value: f32, min: f32, max: f32, default: f32) -> f32 {
+    let value = finite_or(name, value, default);
+    let clamped = value.clamp(min, max);
+    if clamped != value {
+        log::warn!("{} {} out of range, clamped to {}", name, value, clamped);
+    }
+    clamped
+}
+
+fn finite_or(name: &str, value: f32, default: f32) -> f32 {
+    if value.is_finite() {
+        value
+    } else {
+        log::warn!("{} is {}, using default {}", name, value, default);
+        default
+    }
+}
+
+// ============================================================================
+// Results & Errors
+// ============================================================================
+
+/// `Progress::fraction` while rendering an input whose length is unknown
+pub const INDETERMINATE_PROGRESS: f32 = -1.0;
+
+/// Progress update passed to the caller's closure
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    /// Overall completion, 0.0-1.0, or `INDETERMINATE_PROGRESS` while the
+    /// render cannot tell how far along it is
+    pub fraction: f32,
+    /// Output frames rendered so far
+    pub frames: u64,
+}
+
+/// Timing for the frames encoded since the previous heartbeat
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Heartbeat {
+    /// Index of the last frame in the interval
+    pub frame_index: u64,
+    /// Frames in the interval
+    pub frames: u32,
+    /// Demux + decode + fps/format filtering
+    pub decode: Duration,
+    /// Cursor lookup and compositing
+    pub composite: Duration,
+    /// RGBA -> YUV conversion, encoding and muxing
+    pub encode: Duration,
+}
+
+/// Non-fatal quality issue detected during a render
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Frame time outside the cursor path; the position was held at an endpoint
+    TimestampClamped,
+    /// Cursor position outside the video frame
+    CursorOutsideFrame,
+    /// The fps filter dropped a source frame
+    FrameDropped,
+    /// The fps filter duplicated a source frame
+    FrameDuplicated,
+    /// Part of the sprite fell past the frame edge and was not drawn
+    SpriteClipped,
+    /// Glitch samples removed from the cursor path before smoothing
+    OutliersRejected,
+    /// `stream_copy` was asked for but the video had to be re-encoded
+    StreamCopyUnavailable,
+    /// A video packet failed to decode and was skipped
+    UndecodablePacket,
+    /// Numbers missing from an image sequence; the frame before each gap
+    /// was held
+    SequenceFramesMissing,
+}
+
+impl WarningKind {
+    /// Stable code passed across the FFI boundary
+    pub fn code(self) -> i32 {
+        match self {
+            Self::TimestampClamped => 1,
+            Self::CursorOutsideFrame => 2,
+            Self::FrameDropped => 3,
+            Self::FrameDuplicated => 4,
+            Self::SpriteClipped => 5,
+            Self::OutliersRejected => 6,
+            Self::StreamCopyUnavailable => 7,
+            Self::UndecodablePacket => 8,
+            Self::SequenceFramesMissing => 9,
+        }
+    }
+}
+
+/// A warning as delivered to `RenderObserver::warning`.
+///
+/// Only the first occurrence of each kind is delivered with its frame; once
+/// the render finishes, a summary (`frame: None`) follows for every kind that
+/// occurred more than once.
+#[derive(Debug, Clone)]
+pub struct RenderWarning {
+    pub kind: WarningKind,
+    pub frame: Option<u64>,
+    pub message: String,
+}
+
+/// Occurrences of each warning kind over a whole render
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WarningCounts {
+    pub timestamp_clamped: u64,
+    pub cursor_outside_frame: u64,
+    pub frames_dropped: u64,
+    pub frames_duplicated: u64,
+    pub sprite_clipped: u64,
+    pub outliers_rejected: u64,
+    pub stream_copy_unavailable: u64,
+    pub undecodable_packets: u64,
+    pub sequence_frames_missing: u64,
+}
+
+impl WarningCounts {
+    pub fn get(&self, kind: WarningKind) -> u64 {
+        match kind {
+            WarningKind::TimestampClamped => self.timestamp_clamped,
+            WarningKind::CursorOutsideFrame => self.cursor_outside_frame,
+            WarningKind::FrameDropped => self.frames_dropped,
+            WarningKind::FrameDuplicated => self.frames_duplicated,
+            WarningKind::SpriteClipped => self.sprite_clipped,
+            WarningKind::OutliersRejected => self.outliers_rejected,
+            WarningKind::StreamCopyUnavailable => self.stream_copy_unavailable,
+            WarningKind::UndecodablePacket => self.undecodable_packets,
+            WarningKind::SequenceFramesMissing => self.sequence_frames_missing,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: WarningKind) -> &mut u64 {
+        match kind {
+            WarningKind::TimestampClamped => &mut self.timestamp_clamped,
+            WarningKind::CursorOutsideFrame => &mut self.cursor_outside_frame,
+            WarningKind::FrameDropped => &mut self.frames_dropped,
+            WarningKind::FrameDuplicated => &mut self.frames_duplicated,
+            WarningKind::SpriteClipped => &mut self.sprite_clipped,
+            WarningKind::OutliersRejected => &mut self.outliers_rejected,
+            WarningKind::StreamCopyUnavailable => &mut self.stream_copy_unavailable,
+            WarningKind::UndecodablePacket => &mut self.undecodable_packets,
+            WarningKind::SequenceFramesMissing => &mut self.sequence_frames_missing,
+        }
+    }
+}
+
+/// Receives updates while a render runs.
+///
+/// Any `FnMut(Progress)` closure is an observer that ignores heartbeats
+/// and warnings.
+pub trait RenderObserver {
+    fn progress(&mut self, _progress: Progress) {}
+    fn heartbeat(&mut self, _heartbeat: &Heartbeat) {}
+    fn warning(&mut self, _warning: &RenderWarning) {}
+}
+
+impl<F: FnMut(Progress)> RenderObserver for F {
+    fn progress(&mut self, progress: Progress) {
+        self(progress)
+    }
+}
+
+/// Maps the renderer's own 0-1 progress onto the 0.15-1.0 band of the job
+/// (smoothing accounts for the first 15%)
+struct RenderPhase<'a, O: RenderObserver>(&'a mut O);
+
+impl<O: RenderObserver> RenderObserver for RenderPhase<'_, O> {
+    fn progress(&mut self, progress: Progress) {
+        let fraction = if progress.fraction < 0.0 {
+            INDETERMINATE_PROGRESS
+        } else {
+            0.15 + progress.fraction * 0.85
+        };
+        self.0.progress(Progress {
+            fraction,
+            frames: progress.frames,
+        });
+    }
+
+    fn heartbeat(&mut self, heartbeat: &Heartbeat) {
+        self.0.heartbeat(heartbeat);
+    }
+
+    fn warning(&mut self, warning: &RenderWarning) {
+        self.0.warning(warning);
+    }
+}
+
+/// Summary of a completed render
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessingReport {
+    pub frames_encoded: u64,
+    pub output_duration: Duration,
+    /// Length of the input the output covers; longer than `output_duration`
+    /// when idle stretches were sped up
+    pub source_duration: Duration,
+    pub output_file_size_bytes: u64,
+    pub wall_clock: Duration,
+    /// Encoder that produced the video (software after a hardware fallback;
+    /// meaningless when `stream_copied`)
+    pub encoder_backend: EncoderBackend,
+    /// Total occurrences of each warning (`timestamp_clamped` counts frames
+    /// whose time fell outside the cursor path)
+    pub warnings: WarningCounts,
+    /// The input's streams were copied rather than re-encoded (see
+    /// `ProcessingOptions::stream_copy`); `frames_encoded` then counts the
+    /// video packets copied
+    pub stream_copied: bool,
+    /// The output is a reduced proxy (see `ProcessingOptions::preview_mode`)
+    pub proxy: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EngineError {
+    #[error("invalid frame rate {0} (must be 1-{max})", max = MAX_FRAME_RATE)]
+    InvalidFrameRate(i32),
+    #[error("cursor smoothing produced no points")]
+    EmptyCursorPath,
+    #[error("too few cursor points: {0} (need at least {min})", min = MIN_CURSOR_POINTS)]
+    TooFewPoints(usize),
+    #[error("failed to load cursor sprite: {0}")]
+    Sprite(image::ImageError),
+    #[error("failed to load keystroke font: {0}")]
+    Font(String),
+    #[error("could not open input: {0}")]
+    InputUnreadable(ffmpeg::Error),
+    #[error("input has no video stream (found: {0})")]
+    NoVideoStream(String),
+    #[error("input is a still image, not a video")]
+    StillImage,
+    #[error("invalid image sequence: {0}")]
+    InvalidSequence(String),
+    #[error("video decoding failed at packet {packet}: {error}")]
+    DecodeFailed { packet: u64, error: ffmpeg::Error },
+    #[error("input has no decodable video frames")]
+    Undecodable,
+    #[error("invalid {name} value {value}")]
+    InvalidOption { name: &'static str, value: i32 },
+    #[error("{codec:?} video cannot be stored in a {container:?} container")]
+    UnsupportedOutput {
+        codec: VideoCodec,
+        container: Container,
+    },
+    #[error(
+        "input is {:.1} s long, GIF export takes at most {:.0} s",
+        duration_ms / 1000.0,
+        MAX_GIF_DURATION_MS / 1000.0
+    )]
+    GifTooLong { duration_ms: f64 },
+    #[error("encoder '{0}' is not available in the linked FFmpeg build")]
+    EncoderUnavailable(&'static str),
+    #[error("encoder '{0}' cannot encode 10-bit video")]
+    TenBitUnavailable(&'static str),
+    #[error("encoder '{0}' cannot encode an alpha channel")]
+    AlphaUnavailable(&'static str),
+    #[error("{0} need the source video, which overlay-only renders do not decode")]
+    OverlayUnsupported(&'static str),
+    #[error("{option} applies to MP4 and MOV outputs, not {container:?}")]
+    MuxerOptionUnsupported {
+        option: &'static str,
+        container: Container,
+    },
+    #[error("could not read cursor points file: {0}")]
+    PointsFileUnreadable(std::io::Error),
+    #[error("not a cursor points file (bad magic)")]
+    PointsFileBadMagic,
+    #[error("unsupported cursor points file version {0}")]
+    PointsFileUnsupportedVersion(u32),
+    #[error("corrupt cursor points file: {0}")]
+    PointsFileCorrupt(String),
+    #[error("path cannot be passed to ffmpeg: {0}")]
+    InvalidPath(String),
+    #[error("cursor timestamps go backwards by {backstep_ms:.1} ms at index {index}")]
+    NonMonotonicTimestamps { index: usize, backstep_ms: f64 },
+    #[error("invalid frame buffer: {0}")]
+    InvalidBuffer(String),
+    #[error("invalid zoom keyframes: {0}")]
+    InvalidZoomKeyframes(String),
+    #[error("invalid redaction region: {0}")]
+    InvalidRedaction(String),
+    #[error("invalid caption: {0}")]
+    InvalidCaption(String),
+    #[error("invalid metadata tag: {0}")]
+    InvalidMetadata(String),
+    #[error("invalid crop region: {0}")]
+    InvalidCrop(String),
+    #[error("invalid trim range: {0}")]
+    InvalidTrim(String),
+    #[error(
+        "trim starts at {:.1} s, past the end of the {:.1} s input",
+        start_ms / 1000.0,
+        duration_ms / 1000.0
+    )]
+    TrimPastEnd { start_ms: f64, duration_ms: f64 },
+    #[error("ffmpeg component not available: {0}")]
+    MissingComponent(&'static str),
+    #[error("ffmpeg error: {0}")]
+    Ffmpeg(#[from] ffmpeg::Error),
+    #[error("image encoding failed: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Parse zoom keyframes from a JSON array of `ZoomKeyframe` objects, e.g.
+/// `[{"start_ms": 5000, "end_ms": 9000, "zoom": 2, "center_x": 800,
+/// "center_y": 400, "easing": "spring"}]`. Overlaps are caught later, by
+/// `ProcessingOptions::validated`.
+pub fn parse_zoom_keyframes(json: &str) -> Result<Vec<ZoomKeyframe>, EngineError> {
+    serde_json::from_str(json).map_err(|e| EngineError::InvalidZoomKeyframes(e.to_string()))
+}
+
+/// Parse captions from a JSON array of `Caption` objects, e.g.
+/// `[{"text": "Step 2: open Settings", "start_ms": 3000, "end_ms": 6000,
+/// "position": "top", "font_size_px": 40, "color": [255, 255, 255],
+/// "background": [0, 0, 0, 180]}]`. Time ranges and text are checked later,
+/// by `ProcessingOptions::validated`.
+pub fn parse_captions(json: &str) -> Result<Vec<Caption>, EngineError> {
+    serde_json::from_str(json).map_err(|e| EngineError::InvalidCaption(e.to_string()))
+}
+
+/// Load a cursor image (any format supported by the `image` crate, plus
+/// Windows .cur and .ani cursors, keeping their hotspot). Animated GIFs,
+/// APNGs and .ani cursors keep all their frames and timing. SVG sprites need
+/// the `svg` feature and load at their native size (see
+/// `load_cursor_sprite_for`).
+pub fn load_cursor_sprite(path: &Path) -> Result<CursorSprite, EngineError> {
+    renderer::load_cursor_sprite(path).map_err(EngineError::Sprite)
+}
+
+/// Load the cursor sprite for a render with `opts`. SVG sprites (`svg`
+/// feature) are rasterized at `opts.cursor_scale` rather than resampled, and
+/// `opts` is updated to draw them at their rasterized size.
+pub fn load_cursor_sprite_for(
+    path: &Path,
+    opts: &mut ProcessingOptions,
+) -> Result<CursorSprite, EngineError> {
+    let style = CursorStyle {
+        scale: clamp_range(
+            "cursor_scale",
+            opts.cursor_scale,
+            MIN_CURSOR_SCALE,
+            MAX_CURSOR_SCALE,
+            1.0,
+        ),
+        ..opts.cursor_style()
+    };
+    let (sprite, style) =
+        renderer::load_cursor_sprite_prescaled(path, &style).map_err(EngineError::Sprite)?;
+    opts.cursor_scale = style.scale;
+    opts.hotspot_x = style.hotspot_x;
+    opts.hotspot_y = style.hotspot_y;
+    Ok(sprite)
+}
+
+/// Load a horizontal sprite sheet of `frame_count` equally wide frames as an
+/// animation played at `fps` (the width must divide evenly)
+pub fn load_cursor_sprite_sheet(
+    path: &Path,
+    frame_count: u32,
+    fps: f32,
+) -> Result<CursorSprite, EngineError> {
+    if frame_count == 0 {
+        return Err(EngineError::InvalidOption {
+            name: "cursor_sheet_frames",
+            value: 0,
+        });
+    }
+    if !(fps.is_finite() && fps > 0.0) {
+        return Err(EngineError::InvalidOption {
+            name: "cursor_sheet_fps",
+            value: fps as i32,
+        });
+    }
+    renderer::load_cursor_sprite_sheet(path, frame_count, fps).map_err(EngineError::Sprite)
+}
+
+/// Run the cursor smoothing used by the render pipeline, without rendering.
+///
+/// `process_video` calls this too, so a previewed path always matches the export.
+pub fn smooth_path(
+    raw_points: &[CPoint],
+    opts: &ProcessingOptions,
+) -> Result<Vec<CPoint>, EngineError> {
+    smooth_path_counting_outliers(raw_points, &[], opts).map(|(points, _)| points)
+}
+
+/// `smooth_path` for event-tagged samples: the path passes exactly through
+/// every button down/up position
+pub fn smooth_events(
+    events: &[CEventPoint],
+    opts: &ProcessingOptions,
+) -> Result<Vec<CPoint>, EngineError> {
+    let (raw_points, clicks) = split_events(events);
+    smooth_path_counting_outliers(&raw_points, &clicks, opts).map(|(points, _)| points)
+}
+
+/// `smooth_path` plus motion statistics of the result (see `PathMetrics`)
+pub fn smooth_path_with_metrics(
+    raw_points: &[CPoint],
+    opts: &ProcessingOptions,
+) -> Result<(Vec<CPoint>, PathMetrics), EngineError> {
+    let points = smooth_path(raw_points, opts)?;
+    let metrics = smoothing::path_metrics(&points, raw_points, opts.timestamp_unit);
+    Ok((points, metrics))
+}
+
+/// `smooth_path` plus the number of samples dropped by outlier rejection.
+/// `click_anchors` indexes the samples the path must pass through.
+fn smooth_path_counting_outliers(
+    raw_points: &[CPoint],
+    click_anchors: &[usize],
+    opts: &ProcessingOptions,
+) -> Result<(Vec<CPoint>, usize), EngineError> {
+    let opts = opts.validated()?;
+    if raw_points.len() < MIN_CURSOR_POINTS {
+        return Err(EngineError::TooFewPoints(raw_points.len()));
+    }
+
+    let (smoothed_points, rejected) = smoothing::smooth_cursor_path_dual_pass(
+        raw_points,
+        opts.frame_rate,
+        opts.smoothing_mode,
+        opts.interpolation,
+        opts.responsiveness,
+        opts.smoothness,
+        opts.smoothing_alpha,
+        &opts.path_cleanup(),
+        opts.gap_hold(),
+        &opts.spring_tuning(),
+        click_anchors,
+    )?;
+    let smoothed_points = if opts.enforce_kinematic_limits {
+        smoothing::reprofile_speed(&smoothed_points)
+    } else {
+        smoothed_points
+    };
+
+    if smoothed_points.is_empty() {
+        log::error!(
+            "Smoothing failed! Raw points: {}, Options: {:?}",
+            raw_points.len(),
+            opts
+        );
+        return Err(EngineError::EmptyCursorPath);
+    }
+
+    Ok((smoothed_points, rejected))
+}
+
+/// Run only the spring-damper pass at the input's native sample rate.
+///
+/// Returns one point per input point with the timestamps untouched (no unit
+/// normalization, no resampling). Parameters are clamped to 0-1; rest
+/// snapping and substeps use the defaults, as a default render does.
+pub fn physics_filter(raw_points: &[CPoint], responsiveness: f32, smoothness: f32) -> Vec<CPoint> {
+    let defaults = ProcessingOptions::default();
+    smoothing::apply_physics_filter(
+        raw_points,
+        clamp_unit("responsiveness", responsiveness, defaults.responsiveness),
+        clamp_unit("smoothness", smoothness, defaults.smoothness),
+        &defaults.spring_tuning(),
+    )
+    .into_iter()
+    .map(CPoint::from)
+    .collect()
+}
+
+/// Run only the Catmull-Rom upsampling pass onto the `frame_rate` grid.
+///
+/// Output timestamps are exactly `start + i * 1000 / frame_rate`, so point `i`
+/// belongs to frame `i`. A single point, or a path with zero duration, comes
+/// back as one point.
+pub fn resample_to_framerate(
+    points: &[CPoint],
+    frame_rate: i32,
+    alpha: f32,
+) -> Result<Vec<CPoint>, EngineError> {
+    if frame_rate <= 0 || frame_rate > MAX_FRAME_RATE {
+        return Err(EngineError::InvalidFrameRate(frame_rate));
+    }
+    let start = points.first().map_or(0.0, |p| p.timestamp_ms);
+    Ok(smoothing::interpolate_to_framerate(
+        points, frame_rate, alpha, None, start,
+    ))
+}
+
+/// Render `input` to `output` with the smoothed cursor overlaid
+pub fn process_video(
+    input: &Path,
+    output: &Path,
+    sprite: &CursorSprite,
+    points: &[CPoint],
+    opts: &ProcessingOptions,
+    observer: impl RenderObserver,
+) -> Result<ProcessingReport, EngineError> {
+    render_cursor_video(input, output, sprite, points, &[], &[], opts, observer)
+}
+
+/// `process_video` with a ripple at each of `clicks` (see
+/// `ProcessingOptions::click_effect`). Clicks use the same clock and
+/// coordinates as `points`.
+pub fn process_video_with_clicks(
+    input: &Path,
+    output: &Path,
+    sprite: &CursorSprite,
+    points: &[CPoint],
+    clicks: &[CClickEvent],
+    opts: &ProcessingOptions,
+    observer: impl RenderObserver,
+) -> Result<ProcessingReport, EngineError> {
+    render_cursor_video(input, output, sprite, points, &[], clicks, opts, observer)
+}
+
+/// `process_video` for event-tagged samples (see `smooth_events`)
+pub fn process_video_with_events(
+    input: &Path,
+    output: &Path,
+    sprite: &CursorSprite,
+    events: &[CEventPoint],
+    opts: &ProcessingOptions,
+    observer: impl RenderObserver,
+) -> Result<ProcessingReport, EngineError> {
+    process_video_with_events_and_clicks(input, output, sprite, events, &[], opts, observer)
+}
+
+/// `process_video_with_events` with a ripple at each of `clicks` as well as
+/// at the events' own button-downs
+pub fn process_video_with_events_and_clicks(
+    input: &Path,
+    output: &Path,
+    sprite: &CursorSprite,
+    events: &[CEventPoint],
+    clicks: &[CClickEvent],
+    opts: &ProcessingOptions,
+    observer: impl RenderObserver,
+) -> Result<ProcessingReport, EngineError> {
+    let (points, anchors) = split_events(events);
+    let mut all_clicks = click_events(events);
+    all_clicks.extend_from_slice(clicks);
+    // The ripples are drawn oldest first
+    all_clicks.sort_by(|a, b| a.timestamp_ms.total_cmp(&b.timestamp_ms));
+    render_cursor_video(
+        input,
+        output,
+        sprite,
+        &points,
+        &anchors,
+        &all_clicks,
+        opts,
+        observer,
+    )
+}
+
+/// Shared body of the `process_video*` functions
+#[allow(clippy::too_many_arguments)]
+fn render_cursor_video(
+    input: &Path,
+    output: &Path,
+    sprite: &CursorSprite,
+    points: &[CPoint],
+    click_anchors: &[usize],
+    clicks: &[CClickEvent],
+    opts: &ProcessingOptions,
+    mut observer: impl RenderObserver,
+) -> Result<ProcessingReport, EngineError> {
+    let started = Instant::now();
+    let mut opts = opts.validated()?;
+    if opts.preview_mode {
+        opts.apply_preview_mode();
+    }
+
+    opts.container = opts.container.resolve(output);
+    if let Some((option, _)) = opts.movflags() {
+        if !opts.container.is_quicktime() {
+            return Err(EngineError::MuxerOptionUnsupported {
+                option,
+                container: opts.container,
+            });
+        }
+    }
+    if opts.overlay_only {
+        if let Some(blocker) = opts.overlay_blocker() {
+            return Err(EngineError::OverlayUnsupported(blocker));
+        }
+        if !opts.codec.supports_alpha() {
+            return Err(EngineError::AlphaUnavailable(opts.codec.encoder_name()));
+        }
+        if opts.gamma_correct_blend {
+            // Linear-light blending needs the colour underneath
+            log::info!("Overlay-only frames have no background, ignoring gamma_correct_blend");
+            opts.gamma_correct_blend = false;
+        }
+    }
+    // Gaps in an image sequence hold the frame before them
+    let mut sequence_frames_missing = 0;
+    if let Some(sequence) = ImageSequence::find(input)? {
+        if let Some(first) = sequence.first_missing() {
+            sequence_frames_missing = sequence.missing_frames();
+            observer.warning(&RenderWarning {
+                kind: WarningKind::SequenceFramesMissing,
+                frame: None,
+                message: format!(
+                    "{} frames missing from the image sequence (the first is number {}), \
+                     holding the frame before each gap",
+                    sequence_frames_missing, first
+                ),
+            });
+        }
+    }
+    // Copy the streams as they are when nothing needs drawing
+    let mut stream_copy_unavailable = 0;
+    if opts.stream_copy {
+        let copy = match opts.stream_copy_blocker(points) {
+            Some(blocker) => {
+                video::StreamCopy::Unavailable(format!("{} need a re-encode", blocker))
+            }
+            None => video::remux_video(input, output, &opts, &mut observer)?,
+        };
+        match copy {
+            video::StreamCopy::Copied(stats) => {
+                let summary = ProcessingReport {
+                    frames_encoded: stats.frames_encoded,
+                    output_duration: stats.source_duration,
+                    source_duration: stats.source_duration,
+                    output_file_size_bytes: std::fs::metadata(output).map(|m| m.len()).unwrap_or(0),
+                    wall_clock: started.elapsed(),
+                    encoder_backend: stats.encoder_backend,
+                    warnings: WarningCounts {
+                        sequence_frames_missing,
+                        ..stats.warnings
+                    },
+                    stream_copied: true,
+                    proxy: false,
+                };
+                log::info!(
+                    "Copied {:.1}s of video in {:.2}s, {} bytes",
+                    summary.output_duration.as_secs_f64(),
+                    summary.wall_clock.as_secs_f64(),
+                    summary.output_file_size_bytes
+                );
+                write_thumbnail(&opts, output, summary.output_duration)?;
+                return Ok(summary);
+            }
+            video::StreamCopy::Unavailable(reason) => {
+                observer.warning(&RenderWarning {
+                    kind: WarningKind::StreamCopyUnavailable,
+                    frame: None,
+                    message: format!("Cannot copy the streams ({}), re-encoding", reason),
+                });
+                stream_copy_unavailable = 1;
+            }
+        }
+    }
+
+    // Reject codec/container mismatches before any decoding starts
+    if !opts.container.supports(opts.codec) {
+        return Err(EngineError::UnsupportedOutput {
+            codec: opts.codec,
+            container: opts.container,
+        });
+    }
+    // GIFs render straight at their own, lower rate
+    if opts.codec == VideoCodec::Gif {
+        opts.frame_rate = opts.gif.frame_rate;
+    }
+
+    observer.progress(Progress {
+        fraction: 0.05,
+        frames: 0,
+    });
+    log::info!(
+        "Starting processing with {} raw cursor points",
+        points.len()
+    );
+
+    resolve_pixel_scale(&mut opts, input)?;
+
+    // Step 1: Smooth cursor path
+    let (mut smoothed_points, outliers_rejected) =
+        smooth_path_counting_outliers(points, click_anchors, &opts)?;
+    log::info!(
+        "Smoothing complete. Generated {} interpolated points",
+        smoothed_points.len()
+    );
+    if outliers_rejected > 0 {
+        observer.warning(&RenderWarning {
+            kind: WarningKind::OutliersRejected,
+            frame: None,
+            message: format!(
+                "{} of {} cursor samples rejected as glitches",
+                outliers_rejected,
+                points.len()
+            ),
+        });
+    }
+    let mut clicks = if opts.click_effect.is_some() {
+        smoothing::clicks_to_video_space(points, clicks, &opts.path_cleanup())
+    } else {
+        Vec::new()
+    };
+    if opts.keystrokes.is_some() {
+        opts.key_events =
+            smoothing::keys_to_video_time(points, &opts.key_events, opts.timestamp_unit);
+    }
+    opts.cursor_kind_events = smoothing::cursor_kinds_to_video_time(
+        points,
+        &opts.cursor_kind_events,
+        opts.timestamp_unit,
+    );
+    apply_sync_offset(&mut opts, &mut smoothed_points, &mut clicks);
+    observer.progress(Progress {
+        fraction: 0.15,
+        frames: 0,
+    });
+
+    // Step 2: Process video (or draw the overlays alone)
+    let mut stats = if opts.overlay_only {
+        video::render_overlay(
+            input,
+            output,
+            &smoothed_points,
+            &clicks,
+            sprite,
+            &opts,
+            &mut RenderPhase(&mut observer),
+        )?
+    } else {
+        video::process_video(
+            input,
+            output,
+            &smoothed_points,
+            &clicks,
+            sprite,
+            &opts,
+            &mut RenderPhase(&mut observer),
+        )?
+    };
+
+    observer.progress(Progress {
+        fraction: 1.0,
+        frames: stats.frames_encoded,
+    });
+    stats.warnings.outliers_rejected = outliers_rejected as u64;
+    stats.warnings.stream_copy_unavailable = stream_copy_unavailable;
+    stats.warnings.sequence_frames_missing = sequence_frames_missing;
+
+    let summary = ProcessingReport {
+        frames_encoded: stats.frames_encoded,
+        output_duration: Duration::from_secs_f64(
+            stats.frames_encoded as f64 / opts.frame_rate as f64,
+        ),
+        source_duration: stats.source_duration,
+        output_file_size_bytes: std::fs::metadata(output).map(|m| m.len()).unwrap_or(0),
+        wall_clock: started.elapsed(),
+        encoder_backend: stats.encoder_backend,
+        warnings: stats.warnings,
+        stream_copied: false,
+        proxy: opts.preview_mode,
+    };
+    log::info!(
+        "Encoded {} frames ({:.1}s) in {:.1}s ({:.2}x realtime), {} bytes",
+        summary.frames_encoded,
+        summary.output_duration.as_secs_f64(),
+        summary.wall_clock.as_secs_f64(),
+        summary.output_duration.as_secs_f64() / summary.wall_clock.as_secs_f64().max(1e-3),
+        summary.output_file_size_bytes
+    );
+    write_thumbnail(&opts, output, summary.output_duration)?;
+
+    Ok(summary)
+}
+
+/// Write the `thumbnail_path` poster frame of a finished output, if asked for
+fn write_thumbnail(
+    opts: &ProcessingOptions,
+    output: &Path,
+    output_duration: Duration,
+) -> Result<(), EngineError> {
+    let Some(thumbnail_path) = &opts.thumbnail_path else {
+        return Ok(());
+    };
+    video::generate_thumbnail(
+        output,
+        thumbnail_path,
+        output_duration.as_secs_f64() * 1000.0 * THUMBNAIL_POSITION,
+        opts.thumbnail_max_width,
+    )
+}
+
+impl ProcessingOptions {
+    /// What keeps a render of `points` from copying the input's streams (see
+    /// `stream_copy`), if anything
+    fn stream_copy_blocker(&self, points: &[CPoint]) -> Option<&'static str> {
+        [
+            (!points.is_empty(), "cursor points"),
+            (self.click_effect.is_some(), "click effects"),
+            (self.spotlight.is_some(), "spotlights"),
+            (
+                self.auto_zoom.is_some()
+                    || self.click_zoom.is_some()
+                    || !self.zoom_keyframes.is_empty(),
+                "zooms",
+            ),
+            (!self.redactions.is_empty(), "redactions"),
+            (self.crop.is_some(), "crops"),
+            (self.idle_speedup.is_some(), "idle speed-ups"),
+            (self.frame_style.is_some(), "frame styles"),
+            (self.keystrokes.is_some(), "keystrokes"),
+            (!self.captions.is_empty(), "captions"),
+            (self.watermark.is_some(), "watermarks"),
+            (
+                self.output_width != 0 || self.output_height != 0,
+                "scaled sizes",
+            ),
+            (self.codec == VideoCodec::Gif, "GIFs"),
+            (self.overlay_only, "overlay-only renders"),
+            (self.preview_mode, "previews"),
+        ]
+        .into_iter()
+        .find_map(|(blocks, blocker)| blocks.then_some(blocker))
+    }
+
+    /// What keeps an `overlay_only` render from drawing without the source
+    /// video, if anything
+    fn overlay_blocker(&self) -> Option<&'static str> {
+        [
+            (
+                self.auto_zoom.is_some()
+                    || self.click_zoom.is_some()
+                    || !self.zoom_keyframes.is_empty(),
+                "zooms",
+            ),
+            (self.spotlight.is_some(), "spotlights"),
+            (!self.redactions.is_empty(), "redactions"),
+            (self.crop.is_some(), "crops"),
+            (self.idle_speedup.is_some(), "idle speed-ups"),
+            (self.frame_style.is_some(), "frame styles"),
+        ]
+        .into_iter()
+        .find_map(|(blocks, blocker)| blocks.then_some(blocker))
+    }
+
+    /// The option asking for MP4/MOV muxer flags and the `movflags` value
+    /// it sets, if any
+    pub(crate) fn movflags(&self) -> Option<(&'static str, &'static str)> {
+        if self.fragmented {
+            Some(("fragmented", "+frag_keyframe+empty_moov"))
+        } else if self.faststart {
+            Some(("faststart", "+faststart"))
+        } else {
+            None
+        }
+    }
+}
+
+/// Apply `auto_pixel_scale` using the dimensions of `input`
+fn resolve_pixel_scale(opts: &mut ProcessingOptions, input: &Path) -> Result<(), EngineError> {
+    if !opts.auto_pixel_scale {
+        return Ok(());
+    }
+    let Some(space) = opts.coordinate_space.as_mut().filter(|s| s.size.is_some()) else {
+        log::warn!("auto_pixel_scale needs the capture region size, keeping the configured scale");
+        return Ok(());
+    };
+
+    let metadata = video::read_metadata(input)?;
+    if let Some((scale_x, scale_y)) = space.scale_for_video(metadata.width, metadata.height) {
+        log::info!(
+            "Pixel scale {:.3} x {:.3} derived from the {}x{} video",
+            scale_x,
+            scale_y,
+            metadata.width,
+            metadata.height
+        );
+        space.scale_x = scale_x;
+        space.scale_y = scale_y;
+        *space = validated_coordinate_space(*space);
+    }
+    Ok(())
+}
+
+/// Render one composited frame at `timestamp_ms` (video time) to a PNG
+pub fn render_preview_frame(
+    input: &Path,
+    output_png: &Path,
+    sprite: &CursorSprite,
+    points: &[CPoint],
+    opts: &ProcessingOptions,
+    timestamp_ms: f64,
+) -> Result<(), EngineError> {
+    let mut opts = opts.validated()?;
+    resolve_pixel_scale(&mut opts, input)?;
+    let mut smoothed_points = smooth_path(points, &opts)?;
+    apply_sync_offset(&mut opts, &mut smoothed_points, &mut []);
+    video::render_preview_frame(
+        input,
+        output_png,
+        &smoothed_points,
+        sprite,
+        &opts.cursor_style(),
+        opts.frame_bounds,
+        opts.tail_behavior,
+        opts.orient_to_velocity,
+        opts.cursor_shadow.as_ref(),
+        opts.sequence_frame_rate,
+        timestamp_ms,
+    )
+}
+
+/// Move `points`, `clicks` and the options' key and cursor kind events
+/// `cursor_sync_offset_ms` earlier on the video's clock, so each frame finds
+/// the cursor that much later
+fn apply_sync_offset(
+    opts: &mut ProcessingOptions,
+    points: &mut [CPoint],
+    clicks: &mut [CClickEvent],
+) {
+    let offset_ms = opts.cursor_sync_offset_ms;
+    if offset_ms == 0.0 {
+        return;
+    }
+    log::info!("Cursor shifted by {:+.1} ms against the video", offset_ms);
+    let times = points
+        .iter_mut()
+        .map(|point| &mut point.timestamp_ms)
+        .chain(clicks.iter_mut().map(|click| &mut click.timestamp_ms))
+        .chain(opts.key_events.iter_mut().map(|key| &mut key.timestamp_ms))
+        .chain(
+            opts.cursor_kind_events
+                .iter_mut()
+                .map(|event| &mut event.timestamp_ms),
+        );
+    for timestamp_ms in times {
+        *timestamp_ms -= offset_ms;
+    }
+}
+
+/// Caller-owned RGBA8 image whose rows are `stride` bytes apart
+pub struct RgbaBufferMut<'a> {
+    pub data: &'a mut [u8],
+    pub width: u32,
+    pub height: u32,
+    pub stride: usize,
+}
+
+/// Composite `sprite` onto `buffer` with the hotspot at (`x`, `y`).
+///
+/// Positions partially or entirely outside the buffer are clipped; non-finite
+/// positions draw nothing.
+pub fn composite_cursor_on_buffer(
+    buffer: RgbaBufferMut<'_>,
+    sprite: &CursorSprite,
+    x: f32,
+    y: f32,
+    style: &CursorStyle,
+) -> Result<(), EngineError> {
+    let row_bytes = buffer.width as usize * 4;
+    if buffer.stride < row_bytes {
+        return Err(EngineError::InvalidBuffer(format!(
+            "stride {} is smaller than width * 4 ({})",
+            buffer.stride, row_bytes
+        )));
+    }
+    let needed = buffer.stride * buffer.height as usize;
+    if buffer.data.len() < needed {
+        return Err(EngineError::InvalidBuffer(format!(
+            "{} bytes supplied, stride * height needs {}",
+            buffer.data.len(),
+            needed
+        )));
+    }
+    if !x.is_finite() || !y.is_finite() || row_bytes == 0 || buffer.height == 0 {
+        return Ok(());
+    }
+
+    renderer::composite_cursor_subpixel(
+        &mut buffer.data[..needed],
+        buffer.width,
+        buffer.height,
+        buffer.stride,
+        sprite,
+        x,
+        y,
+        style,
+    );
+    Ok(())
+}
+
+/// Like `load_cursor_sprite`, but decodes each path only once per process,
+/// until the file's size or modification time changes. At most
+/// `SPRITE_CACHE_CAPACITY` sprites are kept (see `invalidate_sprite_cache`).
+pub fn load_cursor_sprite_cached(path: &Path) -> Result<Arc<CursorSprite>, EngineError> {
+    renderer::load_cursor_sprite_cached(path).map_err(EngineError::Sprite)
+}
+
+/// Forget the cached sprite for `path` (or all sprites for `None`), e.g.
+/// to free the memory of sprites no longer in use
+pub fn invalidate_sprite_cache(path: Option<&Path>) {
+    renderer::invalidate_sprite_cache(path);
+}
+
+/// Load cursor points from a sidecar file written by the capture daemon
+pub fn read_cursor_points_file(path: &Path) -> Result<Vec<CPoint>, EngineError> {
+    utils::read_cursor_points(path)
+}
+
+/// Write cursor points in the sidecar format read by `read_cursor_points_file`
+pub fn write_cursor_points_file(path: &Path, points: &[CPoint]) -> Result<(), EngineError> {
+    Ok(utils::write_cursor_points(path, points)?)
+}
+
+/// Save the frame of `video` shown at `timestamp_ms` as a PNG no wider than
+/// `max_width` (0 = the video's width), e.g. a poster frame for an export.
+/// Times past the end give the last frame.
+pub fn generate_thumbnail(
+    video: &Path,
+    output_png: &Path,
+    timestamp_ms: f64,
+    max_width: u32,
+) -> Result<(), EngineError> {
+    video::generate_thumbnail(video, output_png, timestamp_ms, max_width)
+}
+
+/// Dimensions, frame rate, duration and codec of the best video stream (an
+/// image sequence is read at `DEFAULT_SEQUENCE_FRAME_RATE`)
+pub fn read_metadata(input: &Path) -> Result<VideoMetadata, EngineError> {
+    video::read_metadata(input)
+}
+
+/// Check every input without rendering; returns a bitmask of `VALID_*` flags (0 = valid)
+pub fn validate_inputs(
+    input: &Path,
+    sprite_path: &Path,
+    points: &[CPoint],
+    opts: &ProcessingOptions,
+) -> i32 {
+    validation::validate_inputs(input, sprite_path, points, opts)
+}
@@ -0,0 +1,272 @@
+// frame_style.rs - Styled background: the recording shrunk onto a solid or
+// gradient backdrop with rounded corners and a drop shadow
+
+use crate::camera::{bilinear_mix, bilinear_taps};
+use crate::renderer::{gaussian_blur, FrameView, Overlay};
+use crate::smoothing::CClickEvent;
+use std::cell::RefCell;
+use std::ops::Range;
+
+/// Fill behind the recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameBackground {
+    Solid([u8; 3]),
+    /// Top row to bottom row
+    Gradient {
+        top: [u8; 3],
+        bottom: [u8; 3],
+    },
+}
+
+impl FrameBackground {
+    /// Color of row `y` of `height`
+    fn row_color(&self, y: u32, height: u32) -> [u8; 3] {
+        match *self {
+            Self::Solid(color) => color,
+            Self::Gradient { top, bottom } => {
+                let t = y as f32 / height.saturating_sub(1).max(1) as f32;
+                std::array::from_fn(|c| {
+                    (top[c] as f32 + (bottom[c] as f32 - top[c] as f32) * t).round() as u8
+                })
+            }
+        }
+    }
+}
+
+/// The recording scaled down and centered on a background, its corners
+/// rounded and a shadow under it. The output keeps the input's size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameStyle {
+    /// Gap around the recording, in percent of the frame's width (left and
+    /// right) and height (top and bottom)
+    pub padding_percent: f32,
+    /// Radius of the recording's corners (output px)
+    pub corner_radius_px: f32,
+    pub background: FrameBackground,
+    /// Gaussian blur radius of the shadow (output px, about 3 sigma)
+    pub shadow_blur_px: f32,
+    /// Darkness of the shadow under the recording (0.0-1.0; 0 = no shadow)
+    pub shadow_opacity: f32,
+    /// Shadow displacement from the recording (output px)
+    pub shadow_offset_x: f32,
+    pub shadow_offset_y: f32,
+}
+
+impl Default for FrameStyle {
+    fn default() -> Self {
+        Self {
+            padding_percent: 6.0,
+            corner_radius_px: 16.0,
+            background: FrameBackground::Gradient {
+                top: [0x4F, 0x46, 0xE5],
+                bottom: [0xDB, 0x27, 0x77],
+            },
+            shadow_blur_px: 32.0,
+            shadow_opacity: 0.45,
+            shadow_offset_x: 0.0,
+            shadow_offset_y: 12.0,
+        }
+    }
+}
+
+/// Replaces each frame with the styled frame. Everything but the recording's
+/// pixels is fixed, so the backdrop (background plus shadow) and the corner
+/// mask are built once.
+pub struct FrameStyleOverlay {
+    width: u32,
+    height: u32,
+    /// Where the recording lands: output = origin + source * scale
+    origin_x: f32,
+    origin_y: f32,
+    scale: f32,
+    /// Background with the shadow drawn in, unpadded RGBA
+    backdrop: Vec<u8>,
+    /// Output columns and rows the recording covers, at least partly
+    columns: Range<u32>,
+    rows: Range<u32>,
+    /// Recording coverage (0-255) of `columns` x `rows`, rounded corners
+    /// anti-aliased
+    mask: Vec<u8>,
+    /// `bilinear_taps` of the source for each of `columns` and `rows`
+    column_taps: Vec<(u32, u32, u32)>,
+    row_taps: Vec<(u32, u32, u32)>,
+    /// Unpadded copy of the frame being resampled
+    source: RefCell<Vec<u8>>,
+}
+
+impl FrameStyleOverlay {
+    /// Precompute `style` for `width` x `height` frames
+    pub fn new(style: &FrameStyle, width: u32, height: u32) -> Self {
+        let (frame_w, frame_h) = (width as f32, height as f32);
+        let scale = (1.0 - 2.0 * style.padding_percent.clamp(0.0, 50.0) / 100.0).max(0.0);
+        let (inner_w, inner_h) = (frame_w * scale, frame_h * scale);
+        let (origin_x, origin_y) = ((frame_w - inner_w) / 2.0, (frame_h - inner_h) / 2.0);
+        let radius = style.corner_radius_px.max(0.0);
+        let recording = (origin_x, origin_y, inner_w, inner_h);
+
+        // Shadow: the recording's shape, offset and blurred
+        let (w, h) = (width as usize, height as usize);
+        let mut shadow = vec![0.0f32; w * h];
+        if style.shadow_opacity > 0.0 {
+            let offset = (
+                origin_x + style.shadow_offset_x,
+                origin_y + style.shadow_offset_y,
+                inner_w,
+                inner_h,
+            );
+            for (i, a) in shadow.iter_mut().enumerate() {
+                let (x, y) = ((i % w) as f32 + 0.5, (i / w) as f32 + 0.5);
+                *a = rounded_rect_coverage(x, y, offset, radius);
+            }
+            if style.shadow_blur_px > 0.0 && w > 0 && h > 0 {
+                gaussian_blur(&mut shadow, w, h, style.shadow_blur_px);
+            }
+        }
+        let mut backdrop = Vec::with_capacity(w * h * 4);
+        for (y, row) in shadow.chunks_exact(w.max(1)).take(h).enumerate() {
+            let color = style.background.row_color(y as u32, height);
+            for &a in row {
+                let keep = 1.0 - (a * style.shadow_opacity).clamp(0.0, 1.0);
+                let [r, g, b] = color.map(|c| (c as f32 * keep).round() as u8);
+                backdrop.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+
+        let span = |origin: f32, size: f32, limit: u32| {
+            (origin.floor().max(0.0) as u32)..((origin + size).ceil() as u32).min(limit)
+        };
+        let columns = span(origin_x, inner_w, width);
+        let rows = span(origin_y, inner_h, height);
+        let mut mask = Vec::with_capacity(columns.len() * rows.len());
+        for y in rows.clone() {
+            for x in columns.clone() {
+                let a = rounded_rect_coverage(x as f32 + 0.5, y as f32 + 0.5, recording, radius);
+                mask.push((a * 255.0).round() as u8);
+            }
+        }
+
+        // Output pixel centers mapped back to source pixel positions
+        let step = if scale > 0.0 { 1.0 / scale } else { 0.0 };
+        let first = |start: u32, origin: f32| (start as f32 + 0.5 - origin) * step - 0.5;
+        let column_taps = bilinear_taps(
+            first(columns.start, origin_x),
+            step,
+            columns.len() as u32,
+            width.saturating_sub(1),
+        );
+        let row_taps = bilinear_taps(
+            first(rows.start, origin_y),
+            step,
+            rows.len() as u32,
+            height.saturating_sub(1),
+        );
+
+        Self {
+            width,
+            height,
+            origin_x,
+            origin_y,
+            scale,
+            backdrop,
+            columns,
+            rows,
+            mask,
+            column_taps,
+            row_taps,
+            source: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Where source point (`x`, `y`) lands in the styled frame
+    pub fn to_output(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.origin_x + x * self.scale,
+            self.origin_y + y * self.scale,
+        )
+    }
+
+    /// `lookup` positions moved into styled-frame coordinates
+    pub fn map_lookup(&self, lookup: &mut [(f64, f32, f32)]) {
+        for (_, x, y) in lookup.iter_mut() {
+            (*x, *y) = self.to_output(*x, *y);
+        }
+    }
+
+    /// `clicks` moved into styled-frame coordinates
+    pub fn map_clicks(&self, clicks: &[CClickEvent]) -> Vec<CClickEvent> {
+        clicks
+            .iter()
+            .map(|click| {
+                let (x, y) = self.to_output(click.x, click.y);
+                CClickEvent { x, y, ..*click }
+            })
+            .collect()
+    }
+}
+
+impl Overlay for FrameStyleOverlay {
+    fn render(&self, frame: &mut FrameView<'_>, _timestamp_ms: f64) {
+        // Built for another frame size (never the case within one render)
+        if frame.width() != self.width || frame.height() != self.height || self.width == 0 {
+            return;
+        }
+        let row_bytes = self.width as usize * 4;
+        let mut source = self.source.borrow_mut();
+        source.clear();
+        for y in 0..self.height {
+            if let Some(row) = frame.row_mut(y) {
+                source.extend_from_slice(row);
+            }
+        }
+
+        let covered = self.columns.start as usize * 4..self.columns.end as usize * 4;
+        for (y, backdrop) in (0..self.height).zip(self.backdrop.chunks_exact(row_bytes)) {
+            let Some(row) = frame.row_mut(y) else {
+                break;
+            };
+            row.copy_from_slice(backdrop);
+            if !self.rows.contains(&y) {
+                continue;
+            }
+            let i = (y - self.rows.start) as usize;
+            let (top, bottom, v) = self.row_taps[i];
+            let top = &source[top as usize * row_bytes..][..row_bytes];
+            let bottom = &source[bottom as usize * row_bytes..][..row_bytes];
+            let mask = &self.mask[i * self.column_taps.len()..][..self.column_taps.len()];
+            for ((px, &(left, right, u)), &coverage) in row[covered.clone()]
+                .chunks_exact_mut(4)
+                .zip(&self.column_taps)
+                .zip(mask)
+            {
+                if coverage == 0 {
+                    continue;
+                }
+                let (left, right) = (left as usize * 4, right as usize * 4);
+                let m = coverage as u32;
+                for (c, out) in px.iter_mut().enumerate() {
+                    let sample = bilinear_mix(
+                        [top[left + c], top[right + c]],
+                        [bottom[left + c], bottom[right + c]],
+                        u,
+                        v,
+                    ) as u32;
+                    *out = ((sample * m + *out as u32 * (255 - m) + 127) / 255) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Coverage (0-1) of the pixel centered on (`px`, `py`) by the rectangle
+/// (x, y, width, height) with corners rounded to `radius`, anti-aliased over
+/// one pixel
+fn rounded_rect_coverage(px: f32, py: f32, rect: (f32, f32, f32, f32), radius: f32) -> f32 {
+    let (x, y, width, height) = rect;
+    let (half_w, half_h) = (width / 2.0, height / 2.0);
+    let radius = radius.min(half_w).min(half_h).max(0.0);
+    // Signed distance to the rounded rectangle's edge (negative inside)
+    let qx = (px - x - half_w).abs() - (half_w - radius);
+    let qy = (py - y - half_h).abs() - (half_h - radius);
+    let distance = qx.max(0.0).hypot(qy.max(0.0)) + qx.max(qy).min(0.0) - radius;
+    (0.5 - distance).clamp(0.0, 1.0)
+}
@@ -1,14 +1,41 @@
 // lib.rs - Foreign Function Interface boundary
+mod camera;
+mod constants;
+pub mod engine;
+mod frame_style;
+mod orientation;
 mod renderer;
+mod sequence;
 mod smoothing;
+mod speedup;
+mod types;
 mod utils;
+mod validation;
 mod video;
 
-use std::ffi::{c_char, c_void, CStr};
+use engine::{
+    AutoZoom, BadgeCorner, BitDepth, Caption, CaptionStyle, ClickEffect, ClickZoom, Container,
+    CoordinateSpace, CropRegion, CursorKindEvent, CursorKindSprite, CursorShadow, CursorSprite,
+    EncoderBackend, EncoderPreset, EncoderQuality, EncoderTune, EngineError, FrameBackground,
+    FrameBounds, FrameStyle, GifExport, Heartbeat, IdleSpeedup, Interpolation, KeyEvent,
+    KeystrokeDisplay, MotionTrail, OutsideRegion, PathCleanup, PathMetrics, ProcessingOptions,
+    ProcessingReport, Progress, RateControl, RedactionMode, RedactionRegion, RenderObserver,
+    RenderWarning, RippleCurve, RotationHandling, SamplingQuality, ScaleFilter, SmoothingMode,
+    Spotlight, SpringTuning, TailBehavior, TimestampUnit, VideoCodec, Vp9Deadline, Watermark,
+    WatermarkImage, ZoomKeyframe, DEFAULT_SEQUENCE_FRAME_RATE,
+};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
 use std::panic::AssertUnwindSafe;
+use std::path::Path;
+#[cfg(windows)]
+use std::path::PathBuf;
 use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
 
-pub use smoothing::CPoint; // Re-export for consistency
+pub use smoothing::{CClickEvent, CEventPoint, CPoint}; // Re-export for consistency
 
 // ============================================================================
 // FFI Type Definitions
@@ -20,6 +47,130 @@ pub struct CSmoothedPath {
     pub len: usize,
 }
 
+/// Key press to show on screen (see `RenderExtras`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CKeyEvent {
+    pub timestamp_ms: f64,   // Same clock as the cursor points
+    pub text: *const c_char, // NUL-terminated UTF-8, e.g. "⌘+Shift+4"
+}
+
+/// Cursor shape change (see `RenderExtras`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CCursorKindEvent {
+    pub timestamp_ms: f64,   // Same clock as the cursor points
+    pub kind: *const c_char, // NUL-terminated UTF-8, e.g. "ibeam"
+}
+
+/// Sprite for one cursor kind (see `RenderExtras`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CCursorKindSprite {
+    pub kind: *const c_char,
+    pub sprite_path: *const c_char,
+    pub hotspot_x: f32, // Unscaled sprite px; (0, 0) = the file's own hotspot
+    pub hotspot_y: f32,
+}
+
+/// Container tag to set on the output (see `RenderExtras`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CMetadataTag {
+    pub key: *const c_char,   // NUL-terminated UTF-8, e.g. "title"
+    pub value: *const c_char, // NUL-terminated UTF-8
+}
+
+/// Rectangle to obscure (see `RenderExtras`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CRedactionRegion {
+    pub x: i32, // Source px; may hang off the frame
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub start_ms: f64,  // Video time
+    pub end_ms: f64,    // Exclusive; negative = the rest of the video
+    pub mode: i32,      // 0 = blur, 1 = pixelate
+    pub amount_px: u32, // Blur radius or pixelate block size
+}
+
+/// Everything `process_video_ex` can add to a render besides the cursor
+/// points. Each array may be NULL when its length is 0, and a NULL string
+/// leaves its feature off, so a zeroed struct adds nothing.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RenderExtras {
+    /// Event-tagged samples that replace the cursor points when set; the
+    /// rendered cursor passes exactly through every button down/up position
+    pub cursor_events: *const CEventPoint,
+    pub cursor_events_len: usize,
+    /// Clicks drawn as ripples when `config.click_effect` is set
+    pub clicks: *const CClickEvent,
+    pub clicks_len: usize,
+    /// Key presses shown as a badge when `config.keystroke_display` is set,
+    /// drawn in the TrueType/OpenType font at `keystroke_font_path` (required
+    /// with key presses)
+    pub key_events: *const CKeyEvent,
+    pub key_events_len: usize,
+    pub keystroke_font_path: *const c_char,
+    /// Cursor shape changes (arrow, I-beam, pointing hand, ...). The main
+    /// sprite is drawn before the first change and for kinds missing from
+    /// `kind_sprites`.
+    pub kind_events: *const CCursorKindEvent,
+    pub kind_events_len: usize,
+    pub kind_sprites: *const CCursorKindSprite,
+    pub kind_sprites_len: usize,
+    /// Manual zooms (see `engine::parse_zoom_keyframes` for the format) that
+    /// replace `config.auto_zoom` and `config.click_zoom`; malformed or
+    /// overlapping ones fail with ERR_INVALID_CONFIG
+    pub zoom_keyframes_json: *const c_char,
+    /// Image stamped above the cursor in every frame, placed by the
+    /// `config.watermark_*` fields
+    pub watermark_path: *const c_char,
+    /// Regions blurred or pixelated while they are active, before the zoom
+    /// and every other layer. Unknown modes and empty rectangles or time
+    /// ranges fail with ERR_INVALID_CONFIG.
+    pub redactions: *const CRedactionRegion,
+    pub redactions_len: usize,
+    /// Captions (see `engine::parse_captions` for the format) drawn in the
+    /// TrueType/OpenType font at `caption_font_path` (required with
+    /// captions). Malformed JSON, empty time ranges and right-to-left text
+    /// fail with ERR_INVALID_CONFIG.
+    pub captions_json: *const c_char,
+    pub caption_font_path: *const c_char,
+    /// Tags set on the output container, over any the input's copied with
+    /// `config.copy_metadata`; a later tag with the same key wins
+    pub metadata: *const CMetadataTag,
+    pub metadata_len: usize,
+}
+
+impl Default for RenderExtras {
+    fn default() -> Self {
+        Self {
+            cursor_events: std::ptr::null(),
+            cursor_events_len: 0,
+            clicks: std::ptr::null(),
+            clicks_len: 0,
+            key_events: std::ptr::null(),
+            key_events_len: 0,
+            keystroke_font_path: std::ptr::null(),
+            kind_events: std::ptr::null(),
+            kind_events_len: 0,
+            kind_sprites: std::ptr::null(),
+            kind_sprites_len: 0,
+            zoom_keyframes_json: std::ptr::null(),
+            watermark_path: std::ptr::null(),
+            redactions: std::ptr::null(),
+            redactions_len: 0,
+            captions_json: std::ptr::null(),
+            caption_font_path: std::ptr::null(),
+            metadata: std::ptr::null(),
+            metadata_len: 0,
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct VideoProcessingConfig {
@@ -30,110 +181,2292 @@ pub struct VideoProcessingConfig {
     pub log_level: i32,
 }
 
-type ProgressCallback = extern "C" fn(*mut c_void, f32);
-
+impl Default for VideoProcessingConfig {
+    fn default() -> Self {
+        Self {
+            smoothing_alpha: 0.5, // Centripetal Catmull-Rom
+            responsiveness: 0.5,  // Balanced response time
+            smoothness: 0.7,      // Mostly smooth with minimal overshoot
+            frame_rate: 60,
+            log_level: 3, // Info
+        }
+    }
+}
+
+impl From<&VideoProcessingConfig> for ProcessingOptions {
+    fn from(config: &VideoProcessingConfig) -> Self {
+        Self {
+            smoothing_alpha: config.smoothing_alpha,
+            responsiveness: config.responsiveness,
+            smoothness: config.smoothness,
+            frame_rate: config.frame_rate,
+            ..Self::default()
+        }
+    }
+}
+
+/// `VideoProcessingConfig` plus cursor appearance. The v1 struct is frozen
+/// for ABI compatibility; new fields are only ever appended here, and
+/// `struct_size` tells how many of them the caller knows about (see
+/// `read_config`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VideoProcessingConfigV2 {
+    pub struct_size: u32, // size_of::<VideoProcessingConfigV2>() the caller was built with
+    pub smoothing_alpha: f32,
+    pub responsiveness: f32,
+    pub smoothness: f32,
+    pub frame_rate: i32,
+    pub log_level: i32,
+    pub cursor_scale: f32, // 1.0 = native sprite size
+    pub hotspot_x: f32,    // Sprite pixel placed on the cursor position
+    pub hotspot_y: f32,
+    pub cursor_opacity: f32,      // 0.0-1.0
+    pub codec: i32,               // 0 = H.264, 1 = HEVC, 2 = VP9
+    pub container: i32,           // 0 = from extension, 1 = MP4, 2 = MKV, 3 = MOV, 4 = WebM
+    pub deterministic: i32,       // Non-zero = bit-identical output across runs (slower)
+    pub smoothing_mode: i32,      // 0 = spring, 1 = One Euro, 2 = Kalman, 3 = Bezier, 4 = raw
+    pub one_euro_min_cutoff: f32, // Hz, One Euro only
+    pub one_euro_beta: f32,
+    pub one_euro_d_cutoff: f32,        // Hz
+    pub kalman_process_noise: f32,     // px^2/s^3, Kalman only
+    pub kalman_measurement_noise: f32, // px^2
+    pub interpolation: i32,            // 0 = Catmull-Rom, 1 = Hermite (filter velocities)
+    pub reject_outliers: i32,          // Non-zero = drop glitch samples before smoothing
+    pub max_gap_ms: f32,               // Hold the cursor across longer capture gaps (0 = off)
+    pub gap_ramp_ms: f32,              // Move to the post-gap position over this long
+    pub rest_radius_px: f32,           // Spring only: settle exactly once at rest (0 = off)
+    pub rest_dwell_ms: f32,            // Time inside the radius that counts as rest
+    pub physics_max_step_sec: f32,     // Spring substep limit (0 = automatic, stable)
+    pub physics_model_v2: i32,         // Non-zero = settling-time/damping-ratio spring model
+    pub frame_bounds: i32,             // 0 = off, 1 = clamp to frame, 2 = reflect overshoot
+    pub max_input_rate_hz: f32,        // Average denser input down to this rate (0 = off)
+    pub timestamp_unit: i32,           // 0 = guess, 1 = ms, 2 = seconds, 3 = microseconds
+    pub simplify_epsilon_px: f32,      // Drop near-collinear filtered points (0 = off)
+    pub capture_x: f32,                // Captured region (e.g. the recorded display) in
+    pub capture_y: f32,                //   recorded coordinates; 0 x 0 = unbounded
+    pub capture_width: f32,
+    pub capture_height: f32,
+    pub capture_scale: f32,            // Video pixels per recorded unit
+    pub capture_outside: i32,          // 0 = hide the cursor outside the region, 1 = clamp
+    pub auto_pixel_scale: i32,         // Non-zero = capture_scale from video size / region size
+    pub enforce_kinematic_limits: i32, // Non-zero = cap cursor speed/acceleration per stroke
+    pub tail_behavior: i32,            // Past the path ends: 0 = hold, 1 = hide, 2 = extrapolate
+    pub tail_ms: u32,                  // Hold (hide) or extrapolation (extrapolate) time
+    pub gamma_correct_blend: i32,      // Non-zero = blend the cursor in linear light
+    pub cursor_sheet_frames: u32,      // > 1 = the sprite is a horizontal strip of this many frames
+    pub cursor_sheet_fps: f32,         // Playback rate of those frames
+    pub orient_to_velocity: i32,       // Non-zero = rotate the sprite to face its heading
+    pub click_effect: i32,             // Non-zero = ripple under the cursor at each click
+    pub click_duration_ms: f32,        // Lifetime of one ripple
+    pub click_start_radius_px: f32,    // Ring radius at the click
+    pub click_end_radius_px: f32,      //   and when it fades out
+    pub click_ring_width_px: f32,      // Ring thickness
+    pub click_curve: i32,              // Radius growth: 0 = ease-out, 1 = linear
+    pub click_color: u32,              // 0xRRGGBB of left-click rings
+    pub click_alt_color: u32,          // 0xRRGGBB of right/middle-click rings
+    pub click_opacity: f32,            // Ring opacity at the click, fading to 0
+    pub cursor_shadow: i32,            // Non-zero = blurred silhouette under the sprite
+    pub shadow_offset_x: f32,          // Shadow displacement (px)
+    pub shadow_offset_y: f32,          //   (x right, y down)
+    pub shadow_blur_px: f32,           // Blur radius (0 = hard edge)
+    pub shadow_opacity: f32,           // Darkness under opaque sprite pixels
+    pub motion_trail_samples: u32,     // Ghost sprites per frame along fast moves (0 = off)
+    pub motion_trail_falloff: f32,     // Newest ghost's opacity, compounding per older ghost
+    pub keystroke_display: i32,        // Non-zero = badge showing the pressed keys
+    pub keystroke_font_size_px: f32,   // Badge text line height
+    pub keystroke_corner: i32,         // 0/1 = bottom left/right, 2/3 = top left/right
+    pub keystroke_margin_px: f32,      // Gap between the badge and the frame edges
+    pub keystroke_hold_ms: f32,        // Time a press stays on screen, including the fade
+    pub keystroke_fade_ms: f32,        // Fade-out at the end of the hold
+    pub keystroke_text_color: u32,     // 0xRRGGBB, opaque (background below)
+    pub keystroke_background_color: u32,
+    pub keystroke_background_opacity: f32,
+    pub yuv_compositing: i32,        // Non-zero = blend in YUV when possible
+    pub spotlight: i32,              // Non-zero = dim all but a circle at the cursor
+    pub spotlight_radius_px: f32,    // Undimmed circle radius
+    pub spotlight_feather_px: f32,   // Soft edge width outside the radius
+    pub spotlight_dim_opacity: f32,  // Brightness taken away outside
+    pub sampling_quality: i32,       // Sprite filter: 0 = bilinear, 1 = bicubic
+    pub auto_zoom: f32,              // Follow-cursor zoom factor (1 = off)
+    pub zoom_margin_x_px: f32,       // Cursor distance from the zoomed frame's
+    pub zoom_margin_y_px: f32,       //   edges before the camera pans
+    pub click_zoom: f32,             // Zoom factor around clicks (1 = off)
+    pub click_zoom_dwell_ms: f32,    // Idle time before zooming back out
+    pub click_zoom_spacing_ms: f32,  // Least time from a zoom-out to the next zoom-in
+    pub frame_style: i32,            // Non-zero = recording inset on a styled background
+    pub frame_padding_percent: f32,  // Gap around the recording (% of width/height)
+    pub frame_corner_radius_px: f32, // Rounded corners of the recording
+    pub frame_background_top: u32,   // 0xRRGGBB at the top of the background
+    pub frame_background_bottom: u32, //   and at the bottom (same = solid)
+    pub frame_shadow_blur_px: f32,   // Shadow under the recording
+    pub frame_shadow_opacity: f32,
+    pub frame_shadow_offset_x: f32,
+    pub frame_shadow_offset_y: f32,
+    pub watermark_corner: i32,          // Logo corner, as keystroke_corner
+    pub watermark_margin_x_px: f32,     // Gap between the logo and the side edge
+    pub watermark_margin_y_px: f32,     //   and the top/bottom edge
+    pub watermark_scale: f32,           // Logo size relative to the image
+    pub watermark_opacity: f32,         // Multiplies the logo's own alpha
+    pub caption_max_width_percent: f32, // Widest caption box (% of the frame width)
+    pub caption_margin_px: f32,         // Gap between captions and the top/bottom edge
+    pub caption_spacing_px: f32,        // Gap between stacked captions
+    pub caption_fade_in_ms: f32,        // Fade at the start of each caption
+    pub caption_fade_out_ms: f32,       //   and at its end
+    pub audio_bitrate_kbps: u32,        // AAC bitrate when source audio must be re-encoded
+    pub encoder_backend: i32,           // 0 = software, 1 = auto, 2 = VideoToolbox,
+                                        //   3 = NVENC, 4 = VA-API
+    pub vp9_deadline: i32,              // 0 = good, 1 = best, 2 = realtime
+    pub vp9_cpu_used: u32,              // libvpx-vp9 speed (0-8, higher is faster)
+    pub gif_frame_rate: i32,            // GIF export: frames per second (1-50),
+    pub gif_max_width: u32,             //   widest frame (0 = source width) and
+    pub gif_loop: i32,                  //   non-zero = loop forever, 0 = play once
+    pub rate_control: i32,              // 0 = CRF, 1 = ABR, 2 = CBR
+    pub crf: i32,                       // CRF rate control (-1 = codec default)
+    pub video_bitrate_kbps: u32,        // ABR/CBR target
+    pub max_bitrate_kbps: u32,          // Peak rate cap (0 = none)
+    pub bufsize_kbits: u32,             //   measured over this buffer (0 = 2x peak)
+    pub encoder_preset: i32,            // x264/x265 preset, 0 = ultrafast .. 9 = placebo
+    pub encoder_tune: i32,              // x264 tune, 0 = none (see EncoderTune::from_raw)
+    pub output_width: u32,              // Output frame size (0 = keep the aspect ratio,
+    pub output_height: u32,             //   both 0 = source size; rounded down to even)
+    pub scale_filter: i32,              // 0 = auto, 1 = bilinear, 2 = bicubic,
+                                        //   3 = Lanczos, 4 = area
+    pub crop_x: u32,                    // Source region to export (crop_width or
+    pub crop_y: u32,                    //   crop_height 0 = the whole frame),
+    pub crop_width: u32,                //   rounded down to even
+    pub crop_height: u32,
+    pub trim_start_ms: f64,             // Input time range to export, from the first
+    pub trim_end_ms: f64,               //   frame (trim_end_ms <= 0 = to the end)
+    pub idle_speedup: f32,              // Speed of idle stretches (1 = off; output is silent)
+    pub idle_min_ms: f32,               //   still for at least this long,
+    pub idle_max_displacement_px: f32,  //   cursor within this distance of its rest point,
+    pub idle_max_frame_change_percent: f32, //   and at most this % of the picture
+                                        //   changing (0 = don't sample frames)
+    pub bit_depth: i32,                 // Output bits per channel: 8 (or 0) or 10
+    pub rotation_handling: i32,         // Source rotation metadata: 0 = turn the frames
+                                        //   upright, 1 = keep them and copy the metadata
+    pub cursor_sync_offset_ms: f64,     // Added to frame times before the cursor is looked
+                                        //   up (+ = draw it where it was later)
+    pub pipelined: i32,                 // Non-zero = decode, composite and encode on
+                                        //   threads of their own (same output)
+    pub stream_copy: i32,               // Non-zero = copy the streams without re-encoding
+                                        //   when there is nothing to draw
+    pub allow_still_image: i32,         // Non-zero = render an image as one frame
+                                        //   instead of failing
+    pub skip_decode_errors: i32,        // Non-zero = skip undecodable video packets
+                                        //   with a warning instead of failing
+    pub sequence_frame_rate: i32,       // Frame rate of an image sequence input
+                                        //   (0 = 30)
+    pub overlay_only: i32,              // Non-zero = draw only the overlays on a
+                                        //   transparent background (ProRes or VP9)
+    pub faststart: i32,                 // Non-zero = MP4/MOV index before the media
+                                        //   data, for playback while downloading
+    pub fragmented: i32,                // Non-zero = fragmented MP4/MOV, playable
+                                        //   up to the last fragment after a crash
+    pub copy_metadata: i32,             // Non-zero = copy the input's container and
+                                        //   audio stream tags to the output
+    pub preview_mode: i32,              // Non-zero = quick proxy render, each side 1/4,
+                                        //   at most 30 fps, fastest encoder settings
+}
+
+impl Default for VideoProcessingConfigV2 {
+    fn default() -> Self {
+        Self::from(&VideoProcessingConfig::default())
+    }
+}
+
+impl From<&VideoProcessingConfig> for VideoProcessingConfigV2 {
+    fn from(config: &VideoProcessingConfig) -> Self {
+        let ripple = ClickEffect::default();
+        let shadow = CursorShadow::default();
+        let keystrokes = KeystrokeDisplay::default();
+        let spotlight = Spotlight::default();
+        let zoom = AutoZoom::default();
+        let click_zoom = ClickZoom::default();
+        let frame_style = FrameStyle::default();
+        let watermark = Watermark::default();
+        let captions = CaptionStyle::default();
+        let gif = GifExport::default();
+        let quality = EncoderQuality::default();
+        let idle = IdleSpeedup::default();
+        let (background_top, background_bottom) = match frame_style.background {
+            FrameBackground::Solid(color) => (color, color),
+            FrameBackground::Gradient { top, bottom } => (top, bottom),
+        };
+        Self {
+            struct_size: std::mem::size_of::<Self>() as u32,
+            smoothing_alpha: config.smoothing_alpha,
+            responsiveness: config.responsiveness,
+            smoothness: config.smoothness,
+            frame_rate: config.frame_rate,
+            log_level: config.log_level,
+            cursor_scale: 1.0,
+            hotspot_x: 0.0,
+            hotspot_y: 0.0,
+            cursor_opacity: 1.0,
+            codec: 0,
+            container: 0,
+            deterministic: 0,
+            smoothing_mode: 0,
+            one_euro_min_cutoff: constants::ONE_EURO_MIN_CUTOFF,
+            one_euro_beta: constants::ONE_EURO_BETA,
+            one_euro_d_cutoff: constants::ONE_EURO_D_CUTOFF,
+            kalman_process_noise: constants::KALMAN_PROCESS_NOISE,
+            kalman_measurement_noise: constants::KALMAN_MEASUREMENT_NOISE,
+            interpolation: 0,
+            reject_outliers: 0,
+            max_gap_ms: 0.0,
+            gap_ramp_ms: constants::DEFAULT_GAP_RAMP_MS,
+            rest_radius_px: constants::REST_RADIUS_PX,
+            rest_dwell_ms: constants::REST_DWELL_MS,
+            physics_max_step_sec: 0.0,
+            physics_model_v2: 0,
+            frame_bounds: 0,
+            max_input_rate_hz: 0.0,
+            timestamp_unit: 0,
+            simplify_epsilon_px: 0.0,
+            capture_x: 0.0,
+            capture_y: 0.0,
+            capture_width: 0.0,
+            capture_height: 0.0,
+            capture_scale: 1.0,
+            capture_outside: 0,
+            auto_pixel_scale: 0,
+            enforce_kinematic_limits: 0,
+            tail_behavior: 0,
+            tail_ms: 0,
+            gamma_correct_blend: 0,
+            cursor_sheet_frames: 0,
+            cursor_sheet_fps: 10.0,
+            orient_to_velocity: 0,
+            click_effect: 0,
+            click_duration_ms: ripple.duration_ms,
+            click_start_radius_px: ripple.start_radius_px,
+            click_end_radius_px: ripple.end_radius_px,
+            click_ring_width_px: ripple.ring_width_px,
+            click_curve: 0,
+            click_color: rgb_to_raw(ripple.color),
+            click_alt_color: rgb_to_raw(ripple.alt_color),
+            click_opacity: ripple.opacity,
+            cursor_shadow: 0,
+            shadow_offset_x: shadow.offset_x,
+            shadow_offset_y: shadow.offset_y,
+            shadow_blur_px: shadow.blur_radius_px,
+            shadow_opacity: shadow.opacity,
+            motion_trail_samples: 0,
+            motion_trail_falloff: MotionTrail::default().falloff,
+            keystroke_display: 0,
+            keystroke_font_size_px: keystrokes.font_size_px,
+            keystroke_corner: 0,
+            keystroke_margin_px: keystrokes.margin_px,
+            keystroke_hold_ms: keystrokes.hold_ms,
+            keystroke_fade_ms: keystrokes.fade_ms,
+            keystroke_text_color: rgb_to_raw(keystrokes.text_color),
+            keystroke_background_color: rgb_to_raw(keystrokes.background_color),
+            keystroke_background_opacity: keystrokes.background_opacity,
+            yuv_compositing: 0,
+            spotlight: 0,
+            spotlight_radius_px: spotlight.radius_px,
+            spotlight_feather_px: spotlight.feather_px,
+            spotlight_dim_opacity: spotlight.dim_opacity,
+            sampling_quality: 0,
+            auto_zoom: 1.0,
+            zoom_margin_x_px: zoom.margin_x_px,
+            zoom_margin_y_px: zoom.margin_y_px,
+            click_zoom: 1.0,
+            click_zoom_dwell_ms: click_zoom.dwell_ms,
+            click_zoom_spacing_ms: click_zoom.min_spacing_ms,
+            frame_style: 0,
+            frame_padding_percent: frame_style.padding_percent,
+            frame_corner_radius_px: frame_style.corner_radius_px,
+            frame_background_top: rgb_to_raw(background_top),
+            frame_background_bottom: rgb_to_raw(background_bottom),
+            frame_shadow_blur_px: frame_style.shadow_blur_px,
+            frame_shadow_opacity: frame_style.shadow_opacity,
+            frame_shadow_offset_x: frame_style.shadow_offset_x,
+            frame_shadow_offset_y: frame_style.shadow_offset_y,
+            watermark_corner: 1,
+            watermark_margin_x_px: watermark.margin_x_px,
+            watermark_margin_y_px: watermark.margin_y_px,
+            watermark_scale: watermark.scale,
+            watermark_opacity: watermark.opacity,
+            caption_max_width_percent: captions.max_width_percent,
+            caption_margin_px: captions.margin_px,
+            caption_spacing_px: captions.spacing_px,
+            caption_fade_in_ms: captions.fade_in_ms,
+            caption_fade_out_ms: captions.fade_out_ms,
+            audio_bitrate_kbps: ProcessingOptions::default().audio_bitrate_kbps,
+            encoder_backend: 0,
+            vp9_deadline: 0,
+            vp9_cpu_used: ProcessingOptions::default().vp9_cpu_used,
+            gif_frame_rate: gif.frame_rate,
+            gif_max_width: gif.max_width,
+            gif_loop: gif.loop_forever as i32,
+            rate_control: 0,
+            crf: -1,
+            video_bitrate_kbps: quality.bitrate_kbps,
+            max_bitrate_kbps: quality.max_bitrate_kbps,
+            bufsize_kbits: quality.bufsize_kbits,
+            encoder_preset: quality.preset as i32,
+            encoder_tune: 0,
+            output_width: 0,
+            output_height: 0,
+            scale_filter: 0,
+            crop_x: 0,
+            crop_y: 0,
+            crop_width: 0,
+            crop_height: 0,
+            trim_start_ms: 0.0,
+            trim_end_ms: 0.0,
+            idle_speedup: 1.0,
+            idle_min_ms: idle.min_idle_ms,
+            idle_max_displacement_px: idle.max_displacement_px,
+            idle_max_frame_change_percent: idle.max_frame_change_percent,
+            bit_depth: 8,
+            rotation_handling: 0,
+            cursor_sync_offset_ms: 0.0,
+            pipelined: 1,
+            stream_copy: 0,
+            allow_still_image: 0,
+            skip_decode_errors: 0,
+            sequence_frame_rate: DEFAULT_SEQUENCE_FRAME_RATE,
+            overlay_only: 0,
+            faststart: 0,
+            fragmented: 0,
+            copy_metadata: 0,
+            preview_mode: 0,
+        }
+    }
+}
+
+/// Size of `VideoProcessingConfigV2` in ABI version 1, the smallest a caller
+/// may pass
+const CONFIG_V2_MIN_SIZE: usize =
+    std::mem::offset_of!(VideoProcessingConfigV2, preview_mode) + std::mem::size_of::<i32>();
+
+/// Copy the caller's config. Fields appended after the caller was built
+/// (past its `struct_size`) keep their defaults, so adding one does not break
+/// older callers; fields this build does not know are ignored.
+unsafe fn read_config(
+    config: *const VideoProcessingConfigV2,
+) -> Result<VideoProcessingConfigV2, EngineError> {
+    let struct_size = (*config).struct_size;
+    if (struct_size as usize) < CONFIG_V2_MIN_SIZE {
+        return Err(EngineError::InvalidOption {
+            name: "struct_size",
+            value: struct_size as i32,
+        });
+    }
+    let mut read = VideoProcessingConfigV2::default();
+    let len = (struct_size as usize).min(std::mem::size_of::<VideoProcessingConfigV2>());
+    // SAFETY: every field is a plain number, so any bytes are a valid value
+    std::ptr::copy_nonoverlapping(
+        config.cast::<u8>(),
+        (&mut read as *mut VideoProcessingConfigV2).cast::<u8>(),
+        len,
+    );
+    read.struct_size = std::mem::size_of::<VideoProcessingConfigV2>() as u32;
+    Ok(read)
+}
+
+impl TryFrom<&VideoProcessingConfigV2> for ProcessingOptions {
+    type Error = EngineError;
+
+    fn try_from(config: &VideoProcessingConfigV2) -> Result<Self, EngineError> {
+        let codec = VideoCodec::from_raw(config.codec).ok_or(EngineError::InvalidOption {
+            name: "codec",
+            value: config.codec,
+        })?;
+        let container =
+            Container::from_raw(config.container).ok_or(EngineError::InvalidOption {
+                name: "container",
+                value: config.container,
+            })?;
+        let encoder_backend =
+            EncoderBackend::from_raw(config.encoder_backend).ok_or(EngineError::InvalidOption {
+                name: "encoder_backend",
+                value: config.encoder_backend,
+            })?;
+        let vp9_deadline =
+            Vp9Deadline::from_raw(config.vp9_deadline).ok_or(EngineError::InvalidOption {
+                name: "vp9_deadline",
+                value: config.vp9_deadline,
+            })?;
+        let rate_control =
+            RateControl::from_raw(config.rate_control).ok_or(EngineError::InvalidOption {
+                name: "rate_control",
+                value: config.rate_control,
+            })?;
+        let preset =
+            EncoderPreset::from_raw(config.encoder_preset).ok_or(EngineError::InvalidOption {
+                name: "encoder_preset",
+                value: config.encoder_preset,
+            })?;
+        let tune =
+            EncoderTune::from_raw(config.encoder_tune).ok_or(EngineError::InvalidOption {
+                name: "encoder_tune",
+                value: config.encoder_tune,
+            })?;
+        let scale_filter =
+            ScaleFilter::from_raw(config.scale_filter).ok_or(EngineError::InvalidOption {
+                name: "scale_filter",
+                value: config.scale_filter,
+            })?;
+        let bit_depth = BitDepth::from_raw(config.bit_depth).ok_or(EngineError::InvalidOption {
+            name: "bit_depth",
+            value: config.bit_depth,
+        })?;
+        let rotation = RotationHandling::from_raw(config.rotation_handling).ok_or(
+            EngineError::InvalidOption {
+                name: "rotation_handling",
+                value: config.rotation_handling,
+            },
+        )?;
+
+        let smoothing_mode = match config.smoothing_mode {
+            0 => SmoothingMode::Spring,
+            1 => SmoothingMode::OneEuro {
+                min_cutoff: config.one_euro_min_cutoff,
+                beta: config.one_euro_beta,
+                d_cutoff: config.one_euro_d_cutoff,
+            },
+            2 => SmoothingMode::Kalman {
+                process_noise: config.kalman_process_noise,
+                measurement_noise: config.kalman_measurement_noise,
+            },
+            3 => SmoothingMode::bezier_strokes(),
+            4 => SmoothingMode::Passthrough,
+            value => {
+                return Err(EngineError::InvalidOption {
+                    name: "smoothing_mode",
+                    value,
+                })
+            }
+        };
+
+        let interpolation = match config.interpolation {
+            0 => Interpolation::CatmullRom,
+            1 => Interpolation::Hermite,
+            value => {
+                return Err(EngineError::InvalidOption {
+                    name: "interpolation",
+                    value,
+                })
+            }
+        };
+
+        let sampling_quality = match config.sampling_quality {
+            0 => SamplingQuality::Bilinear,
+            1 => SamplingQuality::Bicubic,
+            value => {
+                return Err(EngineError::InvalidOption {
+                    name: "sampling_quality",
+                    value,
+                })
+            }
+        };
+
+        let timestamp_unit = match config.timestamp_unit {
+            0 => TimestampUnit::Auto,
+            1 => TimestampUnit::Milliseconds,
+            2 => TimestampUnit::Seconds,
+            3 => TimestampUnit::Microseconds,
+            value => {
+                return Err(EngineError::InvalidOption {
+                    name: "timestamp_unit",
+                    value,
+                })
+            }
+        };
+
+        let outside = match config.capture_outside {
+            0 => OutsideRegion::Hide,
+            1 => OutsideRegion::Clamp,
+            value => {
+                return Err(EngineError::InvalidOption {
+                    name: "capture_outside",
+                    value,
+                })
+            }
+        };
+        let has_region = config.capture_width != 0.0 || config.capture_height != 0.0;
+        let coordinate_space = (has_region
+            || config.capture_x != 0.0
+            || config.capture_y != 0.0
+            || config.capture_scale != 1.0)
+            .then(|| CoordinateSpace {
+                origin_x: config.capture_x,
+                origin_y: config.capture_y,
+                scale_x: config.capture_scale,
+                scale_y: config.capture_scale,
+                size: has_region.then_some((config.capture_width, config.capture_height)),
+                outside,
+            });
+
+        let frame_bounds =
+            FrameBounds::from_raw(config.frame_bounds).ok_or(EngineError::InvalidOption {
+                name: "frame_bounds",
+                value: config.frame_bounds,
+            })?;
+
+        let tail_behavior = TailBehavior::from_raw(config.tail_behavior, config.tail_ms).ok_or(
+            EngineError::InvalidOption {
+                name: "tail_behavior",
+                value: config.tail_behavior,
+            },
+        )?;
+
+        let click_effect = if config.click_effect != 0 {
+            let curve = match config.click_curve {
+                0 => RippleCurve::EaseOut,
+                1 => RippleCurve::Linear,
+                value => {
+                    return Err(EngineError::InvalidOption {
+                        name: "click_curve",
+                        value,
+                    })
+                }
+            };
+            Some(ClickEffect {
+                duration_ms: config.click_duration_ms,
+                start_radius_px: config.click_start_radius_px,
+                end_radius_px: config.click_end_radius_px,
+                ring_width_px: config.click_ring_width_px,
+                curve,
+                color: raw_to_rgb(config.click_color),
+                alt_color: raw_to_rgb(config.click_alt_color),
+                opacity: config.click_opacity,
+            })
+        } else {
+            None
+        };
+
+        let keystrokes = if config.keystroke_display != 0 {
+            let corner = BadgeCorner::from_raw(config.keystroke_corner).ok_or(
+                EngineError::InvalidOption {
+                    name: "keystroke_corner",
+                    value: config.keystroke_corner,
+                },
+            )?;
+            // The font path is a field of RenderExtras
+            Some(KeystrokeDisplay {
+                font_size_px: config.keystroke_font_size_px,
+                corner,
+                margin_px: config.keystroke_margin_px,
+                hold_ms: config.keystroke_hold_ms,
+                fade_ms: config.keystroke_fade_ms,
+                text_color: raw_to_rgb(config.keystroke_text_color),
+                background_color: raw_to_rgb(config.keystroke_background_color),
+                background_opacity: config.keystroke_background_opacity,
+                ..KeystrokeDisplay::default()
+            })
+        } else {
+            None
+        };
+
+        Ok(Self {
+            smoothing_mode,
+            timestamp_unit,
+            coordinate_space,
+            frame_bounds,
+            tail_behavior,
+            cursor_sync_offset_ms: config.cursor_sync_offset_ms,
+            interpolation,
+            smoothing_alpha: config.smoothing_alpha,
+            responsiveness: config.responsiveness,
+            smoothness: config.smoothness,
+            frame_rate: config.frame_rate,
+            cursor_scale: config.cursor_scale,
+            hotspot_x: config.hotspot_x,
+            hotspot_y: config.hotspot_y,
+            cursor_opacity: config.cursor_opacity,
+            codec,
+            encoder_backend,
+            container,
+            vp9_deadline,
+            vp9_cpu_used: config.vp9_cpu_used,
+            gif: GifExport {
+                frame_rate: config.gif_frame_rate,
+                max_width: config.gif_max_width,
+                loop_forever: config.gif_loop != 0,
+            },
+            quality: EncoderQuality {
+                rate_control,
+                crf: u32::try_from(config.crf).ok(),
+                bitrate_kbps: config.video_bitrate_kbps,
+                max_bitrate_kbps: config.max_bitrate_kbps,
+                bufsize_kbits: config.bufsize_kbits,
+                preset,
+                tune,
+            },
+            output_width: config.output_width,
+            output_height: config.output_height,
+            scale_filter,
+            bit_depth,
+            rotation,
+            crop: (config.crop_width > 0 && config.crop_height > 0).then_some(CropRegion {
+                x: config.crop_x,
+                y: config.crop_y,
+                width: config.crop_width,
+                height: config.crop_height,
+            }),
+            trim_start_ms: config.trim_start_ms,
+            trim_end_ms: (config.trim_end_ms > 0.0).then_some(config.trim_end_ms),
+            idle_speedup: (config.idle_speedup != 1.0).then_some(IdleSpeedup {
+                factor: config.idle_speedup,
+                min_idle_ms: config.idle_min_ms,
+                max_displacement_px: config.idle_max_displacement_px,
+                max_frame_change_percent: config.idle_max_frame_change_percent,
+            }),
+            audio_bitrate_kbps: config.audio_bitrate_kbps,
+            deterministic: config.deterministic != 0,
+            pipelined: config.pipelined != 0,
+            stream_copy: config.stream_copy != 0,
+            allow_still_image: config.allow_still_image != 0,
+            skip_decode_errors: config.skip_decode_errors != 0,
+            sequence_frame_rate: match config.sequence_frame_rate {
+                0 => DEFAULT_SEQUENCE_FRAME_RATE,
+                rate => rate,
+            },
+            overlay_only: config.overlay_only != 0,
+            faststart: config.faststart != 0,
+            fragmented: config.fragmented != 0,
+            copy_metadata: config.copy_metadata != 0,
+            preview_mode: config.preview_mode != 0,
+            auto_pixel_scale: config.auto_pixel_scale != 0,
+            enforce_kinematic_limits: config.enforce_kinematic_limits != 0,
+            gamma_correct_blend: config.gamma_correct_blend != 0,
+            sampling_quality,
+            orient_to_velocity: config.orient_to_velocity != 0,
+            click_effect,
+            keystrokes,
+            cursor_shadow: (config.cursor_shadow != 0).then_some(CursorShadow {
+                offset_x: config.shadow_offset_x,
+                offset_y: config.shadow_offset_y,
+                blur_radius_px: config.shadow_blur_px,
+                opacity: config.shadow_opacity,
+            }),
+            motion_trail: (config.motion_trail_samples > 0).then_some(MotionTrail {
+                samples: config.motion_trail_samples,
+                falloff: config.motion_trail_falloff,
+            }),
+            reject_outliers: config.reject_outliers != 0,
+            max_input_rate_hz: config.max_input_rate_hz,
+            simplify_epsilon_px: config.simplify_epsilon_px,
+            max_gap_ms: config.max_gap_ms,
+            gap_ramp_ms: config.gap_ramp_ms,
+            rest_radius_px: config.rest_radius_px,
+            rest_dwell_ms: config.rest_dwell_ms,
+            physics_max_step_sec: config.physics_max_step_sec,
+            physics_model_v2: config.physics_model_v2 != 0,
+            yuv_compositing: config.yuv_compositing != 0,
+            spotlight: (config.spotlight != 0).then_some(Spotlight {
+                radius_px: config.spotlight_radius_px,
+                feather_px: config.spotlight_feather_px,
+                dim_opacity: config.spotlight_dim_opacity,
+            }),
+            auto_zoom: (config.auto_zoom != 1.0).then_some(AutoZoom {
+                zoom: config.auto_zoom,
+                margin_x_px: config.zoom_margin_x_px,
+                margin_y_px: config.zoom_margin_y_px,
+            }),
+            click_zoom: (config.click_zoom != 1.0).then_some(ClickZoom {
+                zoom: config.click_zoom,
+                dwell_ms: config.click_zoom_dwell_ms,
+                min_spacing_ms: config.click_zoom_spacing_ms,
+            }),
+            frame_style: (config.frame_style != 0).then(|| {
+                let (top, bottom) = (
+                    raw_to_rgb(config.frame_background_top),
+                    raw_to_rgb(config.frame_background_bottom),
+                );
+                FrameStyle {
+                    padding_percent: config.frame_padding_percent,
+                    corner_radius_px: config.frame_corner_radius_px,
+                    background: if top == bottom {
+                        FrameBackground::Solid(top)
+                    } else {
+                        FrameBackground::Gradient { top, bottom }
+                    },
+                    shadow_blur_px: config.frame_shadow_blur_px,
+                    shadow_opacity: config.frame_shadow_opacity,
+                    shadow_offset_x: config.frame_shadow_offset_x,
+                    shadow_offset_y: config.frame_shadow_offset_y,
+                }
+            }),
+            // The font path is a field of RenderExtras
+            caption_style: CaptionStyle {
+                max_width_percent: config.caption_max_width_percent,
+                margin_px: config.caption_margin_px,
+                spacing_px: config.caption_spacing_px,
+                fade_in_ms: config.caption_fade_in_ms,
+                fade_out_ms: config.caption_fade_out_ms,
+                ..CaptionStyle::default()
+            },
+            ..Self::default()
+        })
+    }
+}
+
+/// 0xRRGGBB as RGB bytes (the top byte is ignored)
+fn raw_to_rgb(value: u32) -> [u8; 3] {
+    let [_, r, g, b] = value.to_be_bytes();
+    [r, g, b]
+}
+
+/// RGB bytes as 0xRRGGBB
+fn rgb_to_raw([r, g, b]: [u8; 3]) -> u32 {
+    u32::from_be_bytes([0, r, g, b])
+}
+
+/// Properties of the best video stream in a file (see `get_video_metadata`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VideoMetadata {
+    pub width: u32,
+    pub height: u32,
+    pub fps_num: i32,
+    pub fps_den: i32,
+    pub duration_ms: i64, // -1 when the container does not report a duration
+    pub codec_id: i32,    // FFmpeg AVCodecID
+    pub nb_frames: i64,   // Estimated from the duration when not stored; 0 if unknown
+}
+
+/// Summary of a completed render (see `process_video_with_cursor`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessingResult {
+    pub frames_encoded: u64,
+    pub output_duration_ms: f64,
+    pub output_file_size_bytes: u64,
+    pub wall_clock_ms: f64,
+    pub frames_where_cursor_was_clamped: u64,
+    pub warnings_cursor_outside_frame: u64,
+    pub warnings_frames_dropped: u64,
+    pub warnings_frames_duplicated: u64,
+    pub warnings_sprite_clipped: u64,
+    pub warnings_outliers_rejected: u64,
+    pub encoder_backend: i32, // Encoder used, as VideoProcessingConfigV2::encoder_backend
+    pub source_duration_ms: f64, // Input covered (longer than the output with idle_speedup)
+    pub warnings_stream_copy_unavailable: u64,
+    pub stream_copied: i32, // Non-zero when the streams were copied, not re-encoded
+    pub warnings_undecodable_packets: u64,
+    pub warnings_sequence_frames_missing: u64,
+    pub proxy: i32, // Non-zero when the output is a preview_mode proxy
+}
+
+impl From<&ProcessingReport> for ProcessingResult {
+    fn from(report: &ProcessingReport) -> Self {
+        Self {
+            frames_encoded: report.frames_encoded,
+            output_duration_ms: report.output_duration.as_secs_f64() * 1000.0,
+            output_file_size_bytes: report.output_file_size_bytes,
+            wall_clock_ms: report.wall_clock.as_secs_f64() * 1000.0,
+            frames_where_cursor_was_clamped: report.warnings.timestamp_clamped,
+            warnings_cursor_outside_frame: report.warnings.cursor_outside_frame,
+            warnings_frames_dropped: report.warnings.frames_dropped,
+            warnings_frames_duplicated: report.warnings.frames_duplicated,
+            warnings_sprite_clipped: report.warnings.sprite_clipped,
+            warnings_outliers_rejected: report.warnings.outliers_rejected,
+            encoder_backend: report.encoder_backend.to_raw(),
+            source_duration_ms: report.source_duration.as_secs_f64() * 1000.0,
+            warnings_stream_copy_unavailable: report.warnings.stream_copy_unavailable,
+            stream_copied: report.stream_copied as i32,
+            warnings_undecodable_packets: report.warnings.undecodable_packets,
+            warnings_sequence_frames_missing: report.warnings.sequence_frames_missing,
+            proxy: report.proxy as i32,
+        }
+    }
+}
+
+/// Per-interval timing passed to the heartbeat callback (all times in µs)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderHeartbeat {
+    pub frame_index: u64,
+    pub frames_in_interval: u32,
+    pub decode_us: u64,
+    pub composite_us: u64,
+    pub encode_us: u64,
+}
+
+impl From<&Heartbeat> for RenderHeartbeat {
+    fn from(heartbeat: &Heartbeat) -> Self {
+        Self {
+            frame_index: heartbeat.frame_index,
+            frames_in_interval: heartbeat.frames,
+            decode_us: heartbeat.decode.as_micros() as u64,
+            composite_us: heartbeat.composite.as_micros() as u64,
+            encode_us: heartbeat.encode.as_micros() as u64,
+        }
+    }
+}
+
+type ProgressCallback = extern "C" fn(*mut c_void, f32);
+type HeartbeatCallback = extern "C" fn(*mut c_void, *const RenderHeartbeat);
+/// (user_data, warning code, frame index or -1 for a summary, message)
+type WarningCallback = extern "C" fn(*mut c_void, c_int, i64, *const c_char);
+
+/// Callbacks for `process_video_with_cursor_v2` (every field may be NULL/0)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessingCallbacks {
+    pub progress: Option<ProgressCallback>,
+    pub heartbeat: Option<HeartbeatCallback>,
+    /// Frames between heartbeats (0 = default of 60)
+    pub heartbeat_interval_frames: u32,
+    pub warning: Option<WarningCallback>,
+    pub user_data: *mut c_void,
+}
+
+impl Default for ProcessingCallbacks {
+    fn default() -> Self {
+        Self {
+            progress: None,
+            heartbeat: None,
+            heartbeat_interval_frames: 0,
+            warning: None,
+            user_data: std::ptr::null_mut(),
+        }
+    }
+}
+pub use utils::LogCallback;
+
+// ============================================================================
+// ABI Versioning
+// ============================================================================
+
+/// Bump whenever the layout of any struct passed across the FFI boundary
+/// (`CPoint`, `VideoProcessingConfig`, `VideoMetadata`, ...) or the signature
+/// of an exported function changes. The Go side refuses to run on a mismatch.
+/// Fields appended to `VideoProcessingConfigV2` do not need a bump (see
+/// `read_config`).
+const ABI_VERSION: u32 = 1;
+
+static VERSION_STRING: OnceLock<CString> = OnceLock::new();
+
+/// Static, NUL-terminated build description (never freed by the caller)
+#[no_mangle]
+pub extern "C" fn focusframe_version() -> *const c_char {
+    VERSION_STRING
+        .get_or_init(|| CString::new(utils::build_info()).unwrap_or_default())
+        .as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn focusframe_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+// ============================================================================
+// Logging
+// ============================================================================
+
+/// Forward all log output to `callback` (pass NULL to restore stderr).
+/// The verbosity is set per call from `VideoProcessingConfig::log_level`.
+#[no_mangle]
+pub extern "C" fn set_log_callback(callback: Option<LogCallback>) {
+    utils::set_log_callback(callback);
+}
+
+// ============================================================================
+// Last Error (per thread)
+// ============================================================================
+
+thread_local! {
+    static LAST_ERROR: RefCell<(i32, CString)> = RefCell::new((SUCCESS, CString::default()));
+    /// Exported function the calling thread is inside (see `ffi_guard`)
+    static CURRENT_CALL: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+fn set_last_error(code: i32, message: &str) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = (code, message));
+}
+
+/// Code of the failure of the calling thread's last call (0 if it succeeded).
+/// Every entry point that can fail clears it on entry.
+#[no_mangle]
+pub extern "C" fn focusframe_last_error_code() -> i32 {
+    LAST_ERROR.with(|slot| slot.borrow().0)
+}
+
+/// Message of the failure of the calling thread's last call ("" if it
+/// succeeded). Valid until the next call on the same thread; do not free.
+#[no_mangle]
+pub extern "C" fn focusframe_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|slot| slot.borrow().1.as_ptr())
+}
+
+// ============================================================================
+// Error Codes
+// ============================================================================
+
+const SUCCESS: i32 = 0;
+const ERR_NULL_POINTER: i32 = -1;
+const ERR_INVALID_UTF8: i32 = -2;
+const ERR_SMOOTHING_FAILED: i32 = -3;
+const ERR_RENDERING_FAILED: i32 = -4;
+const ERR_NO_VIDEO_STREAM: i32 = -5;
+const ERR_INPUT_UNREADABLE: i32 = -6;
+const ERR_INVALID_FRAME_RATE: i32 = -7;
+const ERR_TOO_FEW_POINTS: i32 = -8;
+const ERR_UNSUPPORTED_OUTPUT: i32 = -9;
+const ERR_INVALID_CONFIG: i32 = -10;
+const ERR_PANIC: i32 = -11;
+const ERR_INVALID_BUFFER: i32 = -12;
+const ERR_BUILDER_SEALED: i32 = -13;
+const ERR_POINTS_FILE_UNREADABLE: i32 = -14;
+const ERR_POINTS_FILE_BAD_MAGIC: i32 = -15;
+const ERR_POINTS_FILE_VERSION: i32 = -16;
+const ERR_POINTS_FILE_CORRUPT: i32 = -17;
+const ERR_NON_MONOTONIC_TIMESTAMPS: i32 = -18;
+const ERR_GIF_TOO_LONG: i32 = -19;
+const ERR_TRIM_PAST_END: i32 = -20;
+const ERR_STILL_IMAGE: i32 = -21;
+const ERR_DECODE_FAILED: i32 = -22;
+
+/// Map an engine error to the stable FFI error code
+fn error_code(error: &EngineError) -> i32 {
+    match error {
+        EngineError::InvalidFrameRate(_) => ERR_INVALID_FRAME_RATE,
+        EngineError::EmptyCursorPath => ERR_SMOOTHING_FAILED,
+        EngineError::TooFewPoints(_) => ERR_TOO_FEW_POINTS,
+        EngineError::UnsupportedOutput { .. }
+        | EngineError::EncoderUnavailable(_)
+        | EngineError::TenBitUnavailable(_)
+        | EngineError::AlphaUnavailable(_)
+        | EngineError::MuxerOptionUnsupported { .. } => ERR_UNSUPPORTED_OUTPUT,
+        EngineError::InvalidOption { .. } => ERR_INVALID_CONFIG,
+        EngineError::InvalidZoomKeyframes(_) => ERR_INVALID_CONFIG,
+        EngineError::InvalidRedaction(_) => ERR_INVALID_CONFIG,
+        EngineError::InvalidCaption(_) => ERR_INVALID_CONFIG,
+        EngineError::InvalidMetadata(_) => ERR_INVALID_CONFIG,
+        EngineError::InvalidCrop(_) => ERR_INVALID_CONFIG,
+        EngineError::InvalidTrim(_) => ERR_INVALID_CONFIG,
+        EngineError::OverlayUnsupported(_) => ERR_INVALID_CONFIG,
+        EngineError::InvalidBuffer(_) => ERR_INVALID_BUFFER,
+        EngineError::InvalidPath(_) => ERR_INVALID_UTF8,
+        EngineError::PointsFileUnreadable(_) => ERR_POINTS_FILE_UNREADABLE,
+        EngineError::PointsFileBadMagic => ERR_POINTS_FILE_BAD_MAGIC,
+        EngineError::PointsFileUnsupportedVersion(_) => ERR_POINTS_FILE_VERSION,
+        EngineError::PointsFileCorrupt(_) => ERR_POINTS_FILE_CORRUPT,
+        EngineError::NonMonotonicTimestamps { .. } => ERR_NON_MONOTONIC_TIMESTAMPS,
+        EngineError::GifTooLong { .. } => ERR_GIF_TOO_LONG,
+        EngineError::TrimPastEnd { .. } => ERR_TRIM_PAST_END,
+        EngineError::StillImage => ERR_STILL_IMAGE,
+        EngineError::DecodeFailed { .. } => ERR_DECODE_FAILED,
+        EngineError::NoVideoStream(_) => ERR_NO_VIDEO_STREAM,
+        EngineError::InputUnreadable(_) | EngineError::InvalidSequence(_) => ERR_INPUT_UNREADABLE,
+        _ => ERR_RENDERING_FAILED,
+    }
+}
+
+/// Log `error`, record it as the thread's last error and return its code
+fn fail(context: &str, error: &EngineError) -> i32 {
+    log::error!("{}: {}", context, error);
+    let code = error_code(error);
+    set_last_error(code, &format!("{}: {}", context, error));
+    code
+}
+
+/// Record `code` as the last error of the current call, with a message naming
+/// the entry point, and return it. For failures with no `EngineError` to
+/// describe them (see `fail`).
+fn refuse(code: i32) -> i32 {
+    let function = CURRENT_CALL.with(Cell::get).unwrap_or("focusframe");
+    let reason = match code {
+        ERR_NULL_POINTER => "NULL argument",
+        ERR_INVALID_UTF8 => "path or string is not valid UTF-8",
+        _ => "failed",
+    };
+    set_last_error(code, &format!("{}: {}", function, reason));
+    code
+}
+
+/// Run an FFI body, converting a panic into `on_panic`.
+///
+/// Unwinding across `extern "C"` into Go is undefined behavior (and aborts the
+/// host app in practice), so every exported function that does real work runs
+/// inside this guard. The panic message is stored as the last error.
+///
+/// The outermost guard of a call clears the last error first, so it never
+/// describes an earlier call.
+fn ffi_guard<T>(function: &'static str, on_panic: T, body: impl FnOnce() -> T) -> T {
+    let outermost = CURRENT_CALL.with(|call| call.get().is_none());
+    if outermost {
+        CURRENT_CALL.with(|call| call.set(Some(function)));
+        set_last_error(SUCCESS, "");
+    }
+    // AssertUnwindSafe: the bodies only read caller-owned memory, so nothing
+    // is left half-modified on the C side when we bail out.
+    let outcome = match std::panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(value) => value,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown cause".to_string());
+            log::error!("CRITICAL RUST PANIC in {}: {}", function, message);
+            set_last_error(ERR_PANIC, &format!("panic in {}: {}", function, message));
+            on_panic
+        }
+    };
+    if outermost {
+        CURRENT_CALL.with(|call| call.set(None));
+    }
+    outcome
+}
+
+/// Borrow a NUL-terminated C path (valid for the duration of the FFI call).
+///
+/// On Unix the bytes are used as-is, so non-UTF-8 file names work. Elsewhere
+/// narrow paths must be UTF-8; use the `*_w` entry points on Windows.
+#[cfg(unix)]
+unsafe fn c_path<'a>(ptr: *const c_char) -> Result<&'a Path, i32> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(Path::new(std::ffi::OsStr::from_bytes(
+        CStr::from_ptr(ptr).to_bytes(),
+    )))
+}
+
+#[cfg(not(unix))]
+unsafe fn c_path<'a>(ptr: *const c_char) -> Result<&'a Path, i32> {
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(Path::new)
+        .map_err(|_| refuse(ERR_INVALID_UTF8))
+}
+
+/// Copy a NUL-terminated UTF-16 path (Windows `wchar_t *`)
+#[cfg(windows)]
+unsafe fn w_path(ptr: *const u16) -> PathBuf {
+    use std::os::windows::ffi::OsStringExt;
+    let mut len = 0;
+    while *ptr.add(len) != 0 {
+        len += 1;
+    }
+    PathBuf::from(std::ffi::OsString::from_wide(slice::from_raw_parts(
+        ptr, len,
+    )))
+}
+
+// ============================================================================
+// Main FFI Entry Point
+// ============================================================================
+
+/// Render `input_video_path` with the smoothed cursor overlaid.
+///
+/// The sprite is drawn at native size with its top-left corner on the cursor
+/// position; use `process_video_with_cursor_v2` to control scale and hotspot.
+#[no_mangle]
+pub unsafe extern "C" fn process_video_with_cursor(
+    input_video_path: *const c_char,
+    output_video_path: *const c_char,
+    cursor_sprite_path: *const c_char,
+    raw_cursor_points: *const CPoint,
+    raw_cursor_points_len: usize,
+    config: *const VideoProcessingConfig,
+    progress_callback: Option<ProgressCallback>,
+    user_data: *mut c_void,
+    result: *mut ProcessingResult, // Optional (may be NULL)
+) -> i32 {
+    ffi_guard("process_video_with_cursor", ERR_PANIC, || {
+        if config.is_null() {
+            return refuse(ERR_NULL_POINTER);
+        }
+        let config_v2 = VideoProcessingConfigV2::from(&*config);
+        let callbacks = ProcessingCallbacks {
+            progress: progress_callback,
+            user_data,
+            ..ProcessingCallbacks::default()
+        };
+
+        process_video_with_cursor_v2(
+            input_video_path,
+            output_video_path,
+            cursor_sprite_path,
+            raw_cursor_points,
+            raw_cursor_points_len,
+            &config_v2,
+            &callbacks,
+            result,
+        )
+    })
+}
+
+/// Render `input_video_path` with the smoothed cursor overlaid, using the
+/// cursor scale, hotspot and opacity from `config`.
+///
+/// Safe to call concurrently from multiple threads (including threads created
+/// by the Go runtime) as long as each call uses its own output path.
+#[no_mangle]
+pub unsafe extern "C" fn process_video_with_cursor_v2(
+    input_video_path: *const c_char,
+    output_video_path: *const c_char,
+    cursor_sprite_path: *const c_char,
+    raw_cursor_points: *const CPoint,
+    raw_cursor_points_len: usize,
+    config: *const VideoProcessingConfigV2,
+    callbacks: *const ProcessingCallbacks, // Optional (may be NULL)
+    result: *mut ProcessingResult,         // Optional (may be NULL)
+) -> i32 {
+    process_video_ex(
+        input_video_path,
+        output_video_path,
+        cursor_sprite_path,
+        raw_cursor_points,
+        raw_cursor_points_len,
+        std::ptr::null(),
+        config,
+        callbacks,
+        result,
+    )
+}
+
+/// `process_video_with_cursor_v2` with any mix of the extras in `extras`
+/// (NULL for none): clicks, key presses, cursor kinds, zoom keyframes, a
+/// watermark, redactions, captions and container tags. With
+/// `extras.cursor_events` set, `raw_cursor_points` is ignored and may be NULL.
+#[no_mangle]
+pub unsafe extern "C" fn process_video_ex(
+    input_video_path: *const c_char,
+    output_video_path: *const c_char,
+    cursor_sprite_path: *const c_char,
+    raw_cursor_points: *const CPoint,
+    raw_cursor_points_len: usize,
+    extras: *const RenderExtras, // Optional (may be NULL)
+    config: *const VideoProcessingConfigV2,
+    callbacks: *const ProcessingCallbacks, // Optional (may be NULL)
+    result: *mut ProcessingResult,         // Optional (may be NULL)
+) -> i32 {
+    // 1. SAFETY: Panics are caught by ffi_guard. The callback observer lives
+    // inside the guarded closure, so it can never fire after a caught panic.
+    ffi_guard("process_video_ex", ERR_PANIC, || {
+        let extras = if extras.is_null() {
+            RenderExtras::default()
+        } else {
+            *extras
+        };
+        let cursor = if extras.cursor_events.is_null() {
+            if raw_cursor_points.is_null() {
+                return refuse(ERR_NULL_POINTER);
+            }
+            CursorInput::Points(slice::from_raw_parts(
+                raw_cursor_points,
+                raw_cursor_points_len,
+            ))
+        } else {
+            CursorInput::Events(slice::from_raw_parts(
+                extras.cursor_events,
+                extras.cursor_events_len,
+            ))
+        };
+        let extras = match read_extras(&extras) {
+            Ok(extras) => extras,
+            Err(code) => return code,
+        };
+
+        render_with_points(
+            input_video_path,
+            output_video_path,
+            cursor_sprite_path,
+            cursor,
+            extras,
+            config,
+            callbacks,
+            result,
+        )
+    })
+}
+
+/// Cursor samples handed to the render entry points
+#[derive(Clone, Copy)]
+enum CursorInput<'a> {
+    Points(&'a [CPoint]),
+    Events(&'a [CEventPoint]),
+}
+
+/// `RenderExtras` read out of the caller's memory
+#[derive(Default)]
+struct Extras<'a> {
+    clicks: &'a [CClickEvent],
+    keys: Vec<KeyEvent>,
+    keystroke_font: Option<&'a Path>,
+    kind_events: Vec<CursorKindEvent>,
+    kind_sprites: BTreeMap<String, CursorKindSprite>,
+    zoom_keyframes: Vec<ZoomKeyframe>,
+    watermark: Option<&'a Path>,
+    redactions: Vec<RedactionRegion>,
+    /// Captions and the font to draw them in
+    captions: Option<(Vec<Caption>, &'a Path)>,
+    metadata: BTreeMap<String, String>,
+}
+
+impl<'a> Extras<'a> {
+    /// Move everything but the clicks (which the engine takes separately)
+    /// into `opts`, which `config` was turned into
+    fn apply(
+        self,
+        opts: &mut ProcessingOptions,
+        config: &VideoProcessingConfigV2,
+    ) -> Result<&'a [CClickEvent], EngineError> {
+        if let (Some(display), Some(font_path)) = (opts.keystrokes.as_mut(), self.keystroke_font) {
+            display.font_path = font_path.to_path_buf();
+        }
+        opts.key_events = self.keys;
+        opts.cursor_kind_events = self.kind_events;
+        opts.cursor_kind_sprites = self.kind_sprites;
+        opts.zoom_keyframes = self.zoom_keyframes;
+        if let Some(image_path) = self.watermark {
+            opts.watermark = Some(watermark_from(config, image_path)?);
+        }
+        opts.redactions = self.redactions;
+        if let Some((captions, font_path)) = self.captions {
+            opts.captions = captions;
+            opts.caption_style.font_path = font_path.to_path_buf();
+        }
+        opts.metadata = self.metadata;
+        Ok(self.clicks)
+    }
+}
+
+/// Borrow a C array that may be NULL when `len` is 0
+unsafe fn c_array<'a, T>(ptr: *const T, len: usize) -> Result<&'a [T], i32> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err(refuse(ERR_NULL_POINTER)),
+        (false, _) => Ok(slice::from_raw_parts(ptr, len)),
+    }
+}
+
+/// Borrow a NUL-terminated UTF-8 string
+unsafe fn c_str<'a>(ptr: *const c_char) -> Result<&'a str, i32> {
+    if ptr.is_null() {
+        return Err(refuse(ERR_NULL_POINTER));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| refuse(ERR_INVALID_UTF8))
+}
+
+/// Copy the extras out of `extras`; the strings are only borrowed for this call
+unsafe fn read_extras<'a>(extras: &RenderExtras) -> Result<Extras<'a>, i32> {
+    let mut read = Extras {
+        clicks: c_array(extras.clicks, extras.clicks_len)?,
+        ..Extras::default()
+    };
+
+    let key_events = c_array(extras.key_events, extras.key_events_len)?;
+    if !key_events.is_empty() && extras.keystroke_font_path.is_null() {
+        return Err(refuse(ERR_NULL_POINTER));
+    }
+    for key in key_events {
+        read.keys.push(KeyEvent {
+            timestamp_ms: key.timestamp_ms,
+            text: c_str(key.text)?.to_string(),
+        });
+    }
+    if !extras.keystroke_font_path.is_null() {
+        read.keystroke_font = Some(c_path(extras.keystroke_font_path)?);
+    }
+
+    for event in c_array(extras.kind_events, extras.kind_events_len)? {
+        read.kind_events.push(CursorKindEvent {
+            timestamp_ms: event.timestamp_ms,
+            kind: c_str(event.kind)?.to_string(),
+        });
+    }
+    for entry in c_array(extras.kind_sprites, extras.kind_sprites_len)? {
+        let kind = c_str(entry.kind)?;
+        if entry.sprite_path.is_null() {
+            return Err(refuse(ERR_NULL_POINTER));
+        }
+        read.kind_sprites.insert(
+            kind.to_string(),
+            CursorKindSprite {
+                sprite_path: c_path(entry.sprite_path)?.to_path_buf(),
+                hotspot_x: entry.hotspot_x,
+                hotspot_y: entry.hotspot_y,
+            },
+        );
+    }
+
+    if !extras.zoom_keyframes_json.is_null() {
+        let json = c_str(extras.zoom_keyframes_json)?;
+        read.zoom_keyframes =
+            engine::parse_zoom_keyframes(json).map_err(|e| fail("Invalid configuration", &e))?;
+    }
+
+    if !extras.watermark_path.is_null() {
+        read.watermark = Some(c_path(extras.watermark_path)?);
+    }
+
+    for region in c_array(extras.redactions, extras.redactions_len)? {
+        let Some(mode) = RedactionMode::from_raw(region.mode, region.amount_px) else {
+            let e = EngineError::InvalidOption {
+                name: "redaction_mode",
+                value: region.mode,
+            };
+            return Err(fail("Invalid configuration", &e));
+        };
+        read.redactions.push(RedactionRegion {
+            x: region.x,
+            y: region.y,
+            width: region.width,
+            height: region.height,
+            start_ms: region.start_ms,
+            end_ms: (region.end_ms >= 0.0).then_some(region.end_ms),
+            mode,
+        });
+    }
+
+    if !extras.captions_json.is_null() {
+        if extras.caption_font_path.is_null() {
+            return Err(refuse(ERR_NULL_POINTER));
+        }
+        let json = c_str(extras.captions_json)?;
+        let captions =
+            engine::parse_captions(json).map_err(|e| fail("Invalid configuration", &e))?;
+        read.captions = Some((captions, c_path(extras.caption_font_path)?));
+    }
+
+    // A later tag with the same key wins
+    for tag in c_array(extras.metadata, extras.metadata_len)? {
+        let (key, value) = (c_str(tag.key)?, c_str(tag.value)?);
+        read.metadata.insert(key.to_string(), value.to_string());
+    }
+    Ok(read)
+}
+
+/// Shared body of the render entry points (the caller provides the panic guard)
+#[allow(clippy::too_many_arguments)]
+unsafe fn render_with_points(
+    input_video_path: *const c_char,
+    output_video_path: *const c_char,
+    cursor_sprite_path: *const c_char,
+    cursor: CursorInput,
+    extras: Extras,
+    config: *const VideoProcessingConfigV2,
+    callbacks: *const ProcessingCallbacks,
+    result: *mut ProcessingResult,
+) -> i32 {
+    // 2. Null Pointer Checks (Fast Fail)
+    if input_video_path.is_null()
+        || output_video_path.is_null()
+        || cursor_sprite_path.is_null()
+        || config.is_null()
+    {
+        return refuse(ERR_NULL_POINTER);
+    }
+
+    // 3. String Conversions
+    // Note: These borrows are valid only within this call
+    let input_path = match c_path(input_video_path) {
+        Ok(p) => p,
+        Err(code) => return code,
+    };
+    let output_path = match c_path(output_video_path) {
+        Ok(p) => p,
+        Err(code) => return code,
+    };
+    let cursor_path = match c_path(cursor_sprite_path) {
+        Ok(p) => p,
+        Err(code) => return code,
+    };
+
+    render_paths(
+        input_path,
+        output_path,
+        cursor_path,
+        cursor,
+        extras,
+        config,
+        callbacks,
+        result,
+    )
+}
+
+/// The watermark `config` lays out, showing the image at `path`
+fn watermark_from(config: &VideoProcessingConfigV2, path: &Path) -> Result<Watermark, EngineError> {
+    let corner =
+        BadgeCorner::from_raw(config.watermark_corner).ok_or(EngineError::InvalidOption {
+            name: "watermark_corner",
+            value: config.watermark_corner,
+        })?;
+    Ok(Watermark {
+        image: WatermarkImage::Path(path.to_path_buf()),
+        corner,
+        margin_x_px: config.watermark_margin_x_px,
+        margin_y_px: config.watermark_margin_y_px,
+        scale: config.watermark_scale,
+        opacity: config.watermark_opacity,
+    })
+}
+
+/// Load the cursor, as a sprite sheet animation if `config` declares one
+/// (SVG sprites are rasterized at the scale in `opts`, see
+/// `engine::load_cursor_sprite_for`)
+fn load_sprite(
+    path: &Path,
+    config: &VideoProcessingConfigV2,
+    opts: &mut ProcessingOptions,
+) -> Result<CursorSprite, EngineError> {
+    if config.cursor_sheet_frames > 1 {
+        engine::load_cursor_sprite_sheet(path, config.cursor_sheet_frames, config.cursor_sheet_fps)
+    } else {
+        engine::load_cursor_sprite_for(path, opts)
+    }
+}
+
+/// Render once the paths are decoded (shared by the narrow and wide entry points)
+#[allow(clippy::too_many_arguments)]
+unsafe fn render_paths(
+    input_path: &Path,
+    output_path: &Path,
+    cursor_path: &Path,
+    cursor: CursorInput,
+    extras: Extras,
+    config: *const VideoProcessingConfigV2,
+    callbacks: *const ProcessingCallbacks,
+    result: *mut ProcessingResult,
+) -> i32 {
+    if config.is_null() {
+        return refuse(ERR_NULL_POINTER);
+    }
+
+    // 4. Dereference Config
+    let config = match read_config(config) {
+        Ok(config) => config,
+        Err(e) => return fail("Invalid configuration", &e),
+    };
+    utils::init_logging(config.log_level);
+    let mut opts = match ProcessingOptions::try_from(&config) {
+        Ok(opts) => opts,
+        Err(e) => return fail("Invalid configuration", &e),
+    };
+
+    // 5. Setup Callbacks
+    let callbacks = if callbacks.is_null() {
+        ProcessingCallbacks::default()
+    } else {
+        *callbacks
+    };
+    if callbacks.heartbeat_interval_frames > 0 {
+        opts.heartbeat_interval_frames = callbacks.heartbeat_interval_frames;
+    }
+    let observer = FfiObserver { callbacks };
+
+    // 6. Run Internal Logic
+    let outcome = load_sprite(cursor_path, &config, &mut opts).and_then(|sprite| {
+        let clicks = extras.apply(&mut opts, &config)?;
+        match cursor {
+            CursorInput::Points(points) => engine::process_video_with_clicks(
+                input_path,
+                output_path,
+                &sprite,
+                points,
+                clicks,
+                &opts,
+                observer,
+            ),
+            CursorInput::Events(events) => engine::process_video_with_events_and_clicks(
+                input_path,
+                output_path,
+                &sprite,
+                events,
+                clicks,
+                &opts,
+                observer,
+            ),
+        }
+    });
+
+    match outcome {
+        Ok(report) => {
+            if !result.is_null() {
+                *result = ProcessingResult::from(&report);
+            }
+            SUCCESS
+        }
+        Err(e) => fail("Video processing failed", &e),
+    }
+}
+
+/// `process_video_with_cursor_v2` with UTF-16 paths (Windows only).
+#[cfg(windows)]
+#[no_mangle]
+pub unsafe extern "C" fn process_video_with_cursor_v2_w(
+    input_video_path: *const u16,
+    output_video_path: *const u16,
+    cursor_sprite_path: *const u16,
+    raw_cursor_points: *const CPoint,
+    raw_cursor_points_len: usize,
+    config: *const VideoProcessingConfigV2,
+    callbacks: *const ProcessingCallbacks, // Optional (may be NULL)
+    result: *mut ProcessingResult,         // Optional (may be NULL)
+) -> i32 {
+    ffi_guard("process_video_with_cursor_v2_w", ERR_PANIC, || {
+        if input_video_path.is_null()
+            || output_video_path.is_null()
+            || cursor_sprite_path.is_null()
+            || raw_cursor_points.is_null()
+        {
+            return refuse(ERR_NULL_POINTER);
+        }
+        let raw_points = slice::from_raw_parts(raw_cursor_points, raw_cursor_points_len);
+
+        render_paths(
+            &w_path(input_video_path),
+            &w_path(output_video_path),
+            &w_path(cursor_sprite_path),
+            CursorInput::Points(raw_points),
+            Extras::default(),
+            config,
+            callbacks,
+            result,
+        )
+    })
+}
+
+// ============================================================================
+// Streaming Point Ingestion
+// ============================================================================
+
+/// Cursor points accumulated across several FFI calls, so very long
+/// recordings never need one giant array on the Go side.
+///
+/// Handed out as `Arc::into_raw`; a render holds its own reference, so the
+/// handle may be freed at any time, even while a render is still running.
+pub struct CursorPathBuilder {
+    points: RwLock<Vec<CPoint>>,
+    /// Set once a render starts; later appends are rejected
+    sealed: AtomicBool,
+}
+
+/// Create an empty builder. Release it with `cursor_path_builder_free`.
+#[no_mangle]
+pub extern "C" fn cursor_path_builder_new() -> *const CursorPathBuilder {
+    Arc::into_raw(Arc::new(CursorPathBuilder {
+        points: RwLock::new(Vec::new()),
+        sealed: AtomicBool::new(false),
+    }))
+}
+
+/// Append `len` points (copied; the caller keeps ownership of `points`).
+///
+/// Returns `ERR_BUILDER_SEALED` once a render using this builder has started.
+#[no_mangle]
+pub unsafe extern "C" fn cursor_path_builder_append(
+    builder: *const CursorPathBuilder,
+    points: *const CPoint,
+    len: usize,
+) -> c_int {
+    ffi_guard("cursor_path_builder_append", ERR_PANIC, || {
+        if builder.is_null() || (points.is_null() && len > 0) {
+            return refuse(ERR_NULL_POINTER);
+        }
+        let builder = &*builder;
+
+        // Check the seal under the write lock: a render seals before taking
+        // its read lock, so nothing can slip in after processing started
+        let mut stored = builder.points.write().unwrap_or_else(|e| e.into_inner());
+        if builder.sealed.load(Ordering::Acquire) {
+            set_last_error(
+                ERR_BUILDER_SEALED,
+                "cursor_path_builder_append: render already started",
+            );
+            return ERR_BUILDER_SEALED;
+        }
+        if len > 0 {
+            stored.extend_from_slice(slice::from_raw_parts(points, len));
+        }
+        SUCCESS
+    })
+}
+
+/// Number of points appended so far
+#[no_mangle]
+pub unsafe extern "C" fn cursor_path_builder_len(builder: *const CursorPathBuilder) -> usize {
+    if builder.is_null() {
+        return 0;
+    }
+    (*builder)
+        .points
+        .read()
+        .map(|points| points.len())
+        .unwrap_or(0)
+}
+
+/// Release the caller's reference (NULL is ignored). A render in progress
+/// keeps the points alive until it finishes, but it only takes its reference
+/// inside `process_video_with_cursor_builder`: the free must happen after that
+/// call has returned or has reported progress, never concurrently with its
+/// entry or with any other call on the builder.
+#[no_mangle]
+pub unsafe extern "C" fn cursor_path_builder_free(builder: *const CursorPathBuilder) {
+    if !builder.is_null() {
+        drop(Arc::from_raw(builder));
+    }
+}
+
+/// `process_video_with_cursor_v2` reading the points straight from `builder`
+/// (no further copy). Seals the builder.
+#[no_mangle]
+pub unsafe extern "C" fn process_video_with_cursor_builder(
+    input_video_path: *const c_char,
+    output_video_path: *const c_char,
+    cursor_sprite_path: *const c_char,
+    builder: *const CursorPathBuilder,
+    config: *const VideoProcessingConfigV2,
+    callbacks: *const ProcessingCallbacks, // Optional (may be NULL)
+    result: *mut ProcessingResult,         // Optional (may be NULL)
+) -> i32 {
+    ffi_guard("process_video_with_cursor_builder", ERR_PANIC, || {
+        if builder.is_null() {
+            return refuse(ERR_NULL_POINTER);
+        }
+
+        // Take our own reference so a concurrent free cannot pull the points away
+        Arc::increment_strong_count(builder);
+        let builder = Arc::from_raw(builder);
+
+        builder.sealed.store(true, Ordering::Release);
+        let points = builder.points.read().unwrap_or_else(|e| e.into_inner());
+
+        render_with_points(
+            input_video_path,
+            output_video_path,
+            cursor_sprite_path,
+            CursorInput::Points(&points),
+            Extras::default(),
+            config,
+            callbacks,
+            result,
+        )
+    })
+}
+
+/// `process_video_with_cursor_v2` reading the points from a sidecar file
+/// (see `utils.rs` for the format) instead of an FFI array.
+#[no_mangle]
+pub unsafe extern "C" fn process_video_with_cursor_from_file(
+    input_video_path: *const c_char,
+    output_video_path: *const c_char,
+    cursor_sprite_path: *const c_char,
+    points_file_path: *const c_char,
+    config: *const VideoProcessingConfigV2,
+    callbacks: *const ProcessingCallbacks, // Optional (may be NULL)
+    result: *mut ProcessingResult,         // Optional (may be NULL)
+) -> i32 {
+    ffi_guard("process_video_with_cursor_from_file", ERR_PANIC, || {
+        if points_file_path.is_null() {
+            return refuse(ERR_NULL_POINTER);
+        }
+        let points_path = match c_path(points_file_path) {
+            Ok(p) => p,
+            Err(code) => return code,
+        };
+
+        let raw_points = match engine::read_cursor_points_file(points_path) {
+            Ok(points) => points,
+            Err(e) => return fail("Reading cursor points failed", &e),
+        };
+
+        render_with_points(
+            input_video_path,
+            output_video_path,
+            cursor_sprite_path,
+            CursorInput::Points(&raw_points),
+            Extras::default(),
+            config,
+            callbacks,
+            result,
+        )
+    })
+}
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Fill `out` with the recommended default configuration.
+#[no_mangle]
+pub unsafe extern "C" fn video_processing_config_default(out: *mut VideoProcessingConfig) {
+    if !out.is_null() {
+        *out = VideoProcessingConfig::default();
+    }
+}
+
+/// Fill `out` with the recommended defaults (native-size, fully opaque cursor).
+#[no_mangle]
+pub unsafe extern "C" fn video_processing_config_v2_default(out: *mut VideoProcessingConfigV2) {
+    if !out.is_null() {
+        *out = VideoProcessingConfigV2::default();
+    }
+}
+
+// ============================================================================
+// Pre-flight Validation
+// ============================================================================
+
+/// Validate all inputs without rendering.
+///
+/// Returns 0 when everything is valid, a positive bitmask of `VALID_*` flags
+/// describing every problem found, or a negative error code for bad arguments.
+#[no_mangle]
+pub unsafe extern "C" fn validate_processing_inputs(
+    input_video_path: *const c_char,
+    cursor_sprite_path: *const c_char,
+    raw_cursor_points: *const CPoint,
+    raw_cursor_points_len: usize,
+    config: *const VideoProcessingConfig,
+) -> c_int {
+    ffi_guard("validate_processing_inputs", ERR_PANIC, || {
+        if input_video_path.is_null() || cursor_sprite_path.is_null() || config.is_null() {
+            return refuse(ERR_NULL_POINTER);
+        }
+
+        let input_path = match c_path(input_video_path) {
+            Ok(p) => p,
+            Err(code) => return code,
+        };
+        let cursor_path = match c_path(cursor_sprite_path) {
+            Ok(p) => p,
+            Err(code) => return code,
+        };
+
+        utils::init_logging((*config).log_level);
+        let opts = ProcessingOptions::from(&*config);
+
+        // A NULL array is reported as "too few points" rather than an argument error
+        let raw_points: &[CPoint] = if raw_cursor_points.is_null() {
+            &[]
+        } else {
+            slice::from_raw_parts(raw_cursor_points, raw_cursor_points_len)
+        };
+
+        engine::validate_inputs(input_path, cursor_path, raw_points, &opts)
+    })
+}
+
+// ============================================================================
+// Metadata Query
+// ============================================================================
+
+/// Fill `out` with the properties of the best video stream in `input_video_path`.
+#[no_mangle]
+pub unsafe extern "C" fn get_video_metadata(
+    input_video_path: *const c_char,
+    out: *mut VideoMetadata,
+) -> c_int {
+    ffi_guard("get_video_metadata", ERR_PANIC, || {
+        if input_video_path.is_null() || out.is_null() {
+            return refuse(ERR_NULL_POINTER);
+        }
+
+        let input_path = match c_path(input_video_path) {
+            Ok(p) => p,
+            Err(code) => return code,
+        };
+
+        metadata_for_path(input_path, out)
+    })
+}
+
+/// `get_video_metadata` with a UTF-16 path (Windows only).
+#[cfg(windows)]
+#[no_mangle]
+pub unsafe extern "C" fn get_video_metadata_w(
+    input_video_path: *const u16,
+    out: *mut VideoMetadata,
+) -> c_int {
+    ffi_guard("get_video_metadata_w", ERR_PANIC, || {
+        if input_video_path.is_null() || out.is_null() {
+            return refuse(ERR_NULL_POINTER);
+        }
+        metadata_for_path(&w_path(input_video_path), out)
+    })
+}
+
+unsafe fn metadata_for_path(input_path: &Path, out: *mut VideoMetadata) -> c_int {
+    match engine::read_metadata(input_path) {
+        Ok(metadata) => {
+            *out = metadata;
+            SUCCESS
+        }
+        Err(e @ EngineError::NoVideoStream(_)) => {
+            let message = format!("'{}': {}", input_path.display(), e);
+            log::error!("{}", message);
+            set_last_error(ERR_NO_VIDEO_STREAM, &message);
+            ERR_NO_VIDEO_STREAM
+        }
+        Err(e) => {
+            let message = format!(
+                "Failed to read metadata from '{}': {}",
+                input_path.display(),
+                e
+            );
+            log::error!("{}", message);
+            set_last_error(ERR_INPUT_UNREADABLE, &message);
+            ERR_INPUT_UNREADABLE
+        }
+    }
+}
+
 // ============================================================================
-// Error Codes
+// Preview Rendering
 // ============================================================================
 
-const SUCCESS: i32 = 0;
-const ERR_NULL_POINTER: i32 = -1;
-const ERR_INVALID_UTF8: i32 = -2;
-#[allow(dead_code)]
-const ERR_SMOOTHING_FAILED: i32 = -3;
-const ERR_RENDERING_FAILED: i32 = -4;
+/// Render a single composited frame at `timestamp_ms` (video time) to a PNG.
+///
+/// Uses the same smoothing and cursor overlay as `process_video_with_cursor`.
+#[no_mangle]
+pub unsafe extern "C" fn render_preview_frame(
+    input_video_path: *const c_char,
+    cursor_sprite_path: *const c_char,
+    raw_cursor_points: *const CPoint,
+    raw_cursor_points_len: usize,
+    config: *const VideoProcessingConfig,
+    timestamp_ms: f64,
+    output_png_path: *const c_char,
+) -> c_int {
+    ffi_guard("render_preview_frame", ERR_PANIC, || {
+        if config.is_null() {
+            return refuse(ERR_NULL_POINTER);
+        }
+        let config_v2 = VideoProcessingConfigV2::from(&*config);
 
-// ============================================================================
-// Main FFI Entry Point
-// ============================================================================
+        render_preview_frame_v2(
+            input_video_path,
+            cursor_sprite_path,
+            raw_cursor_points,
+            raw_cursor_points_len,
+            &config_v2,
+            timestamp_ms,
+            output_png_path,
+        )
+    })
+}
 
+/// Render a single composited frame at `timestamp_ms` (video time) to a PNG.
+///
+/// Uses the same smoothing and cursor overlay as `process_video_with_cursor_v2`.
 #[no_mangle]
-pub unsafe extern "C" fn process_video_with_cursor(
+pub unsafe extern "C" fn render_preview_frame_v2(
     input_video_path: *const c_char,
-    output_video_path: *const c_char,
     cursor_sprite_path: *const c_char,
     raw_cursor_points: *const CPoint,
     raw_cursor_points_len: usize,
-    config: *const VideoProcessingConfig,
-    progress_callback: Option<ProgressCallback>,
-    user_data: *mut c_void,
-) -> i32 {
-    // 1. SAFETY: Wrap the entire execution in catch_unwind
-    // We use AssertUnwindSafe because we are passing raw C pointers into the closure.
-    // We guarantee that if this panics, we aren't leaving external C state corrupted
-    // (since we only read these pointers).
-    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
-        // 2. Null Pointer Checks (Fast Fail)
+    config: *const VideoProcessingConfigV2,
+    timestamp_ms: f64,
+    output_png_path: *const c_char,
+) -> c_int {
+    ffi_guard("render_preview_frame_v2", ERR_PANIC, || {
         if input_video_path.is_null()
-            || output_video_path.is_null()
             || cursor_sprite_path.is_null()
             || raw_cursor_points.is_null()
             || config.is_null()
+            || output_png_path.is_null()
         {
-            return ERR_NULL_POINTER;
+            return refuse(ERR_NULL_POINTER);
         }
 
-        // 3. String Conversions
-        // Note: These borrows are valid only within this block
-        let input_path = match CStr::from_ptr(input_video_path).to_str() {
-            Ok(s) => s,
-            Err(_) => return ERR_INVALID_UTF8,
+        let input_path = match c_path(input_video_path) {
+            Ok(p) => p,
+            Err(code) => return code,
         };
-        let output_path = match CStr::from_ptr(output_video_path).to_str() {
-            Ok(s) => s,
-            Err(_) => return ERR_INVALID_UTF8,
+        let cursor_path = match c_path(cursor_sprite_path) {
+            Ok(p) => p,
+            Err(code) => return code,
         };
-        let cursor_path = match CStr::from_ptr(cursor_sprite_path).to_str() {
-            Ok(s) => s,
-            Err(_) => return ERR_INVALID_UTF8,
+        let output_path = match c_path(output_png_path) {
+            Ok(p) => p,
+            Err(code) => return code,
         };
 
-        // 4. Dereference Config & Slice
-        let cfg = &*config;
-        utils::init_logging(cfg.log_level);
+        let raw_points = slice::from_raw_parts(raw_cursor_points, raw_cursor_points_len);
+        preview_paths(
+            input_path,
+            cursor_path,
+            raw_points,
+            config,
+            timestamp_ms,
+            output_path,
+        )
+    })
+}
+
+/// `render_preview_frame_v2` with UTF-16 paths (Windows only).
+#[cfg(windows)]
+#[no_mangle]
+pub unsafe extern "C" fn render_preview_frame_v2_w(
+    input_video_path: *const u16,
+    cursor_sprite_path: *const u16,
+    raw_cursor_points: *const CPoint,
+    raw_cursor_points_len: usize,
+    config: *const VideoProcessingConfigV2,
+    timestamp_ms: f64,
+    output_png_path: *const u16,
+) -> c_int {
+    ffi_guard("render_preview_frame_v2_w", ERR_PANIC, || {
+        if input_video_path.is_null()
+            || cursor_sprite_path.is_null()
+            || raw_cursor_points.is_null()
+            || config.is_null()
+            || output_png_path.is_null()
+        {
+            return refuse(ERR_NULL_POINTER);
+        }
 
-        // Create slice from raw parts
         let raw_points = slice::from_raw_parts(raw_cursor_points, raw_cursor_points_len);
+        preview_paths(
+            &w_path(input_video_path),
+            &w_path(cursor_sprite_path),
+            raw_points,
+            config,
+            timestamp_ms,
+            &w_path(output_png_path),
+        )
+    })
+}
 
-        // 5. Setup Progress Callback
-        let progress_reporter = ProgressReporter {
-            callback: progress_callback,
-            user_data, // raw pointer, captured by AssertUnwindSafe
-        };
+unsafe fn preview_paths(
+    input_path: &Path,
+    cursor_path: &Path,
+    raw_points: &[CPoint],
+    config: *const VideoProcessingConfigV2,
+    timestamp_ms: f64,
+    output_path: &Path,
+) -> c_int {
+    let config = match read_config(config) {
+        Ok(config) => config,
+        Err(e) => return fail("Invalid configuration", &e),
+    };
+    utils::init_logging(config.log_level);
+    let mut opts = match ProcessingOptions::try_from(&config) {
+        Ok(opts) => opts,
+        Err(e) => return fail("Invalid configuration", &e),
+    };
 
-        // 6. Run Internal Logic
-        match process_video_internal(
+    let outcome = load_sprite(cursor_path, &config, &mut opts).and_then(|sprite| {
+        engine::render_preview_frame(
             input_path,
             output_path,
-            cursor_path,
+            &sprite,
             raw_points,
-            cfg,
-            progress_reporter,
-        ) {
-            Ok(_) => SUCCESS,
+            &opts,
+            timestamp_ms,
+        )
+    });
+
+    match outcome {
+        Ok(()) => SUCCESS,
+        Err(e) => fail("Preview rendering failed", &e),
+    }
+}
+
+/// Save the frame of `video_path` shown at `timestamp_ms` to `out_png_path`,
+/// shrunk to at most `max_width` pixels wide (0 = full size) with the aspect
+/// ratio kept. Times past the end give the last frame.
+#[no_mangle]
+pub unsafe extern "C" fn generate_thumbnail(
+    video_path: *const c_char,
+    out_png_path: *const c_char,
+    timestamp_ms: f64,
+    max_width: u32,
+) -> c_int {
+    ffi_guard("generate_thumbnail", ERR_PANIC, || {
+        if video_path.is_null() || out_png_path.is_null() {
+            return refuse(ERR_NULL_POINTER);
+        }
+
+        let video_path = match c_path(video_path) {
+            Ok(p) => p,
+            Err(code) => return code,
+        };
+        let output_path = match c_path(out_png_path) {
+            Ok(p) => p,
+            Err(code) => return code,
+        };
+
+        thumbnail_paths(video_path, output_path, timestamp_ms, max_width)
+    })
+}
+
+/// `generate_thumbnail` with UTF-16 paths (Windows only).
+#[cfg(windows)]
+#[no_mangle]
+pub unsafe extern "C" fn generate_thumbnail_w(
+    video_path: *const u16,
+    out_png_path: *const u16,
+    timestamp_ms: f64,
+    max_width: u32,
+) -> c_int {
+    ffi_guard("generate_thumbnail_w", ERR_PANIC, || {
+        if video_path.is_null() || out_png_path.is_null() {
+            return refuse(ERR_NULL_POINTER);
+        }
+        thumbnail_paths(
+            &w_path(video_path),
+            &w_path(out_png_path),
+            timestamp_ms,
+            max_width,
+        )
+    })
+}
+
+fn thumbnail_paths(
+    video_path: &Path,
+    output_path: &Path,
+    timestamp_ms: f64,
+    max_width: u32,
+) -> c_int {
+    match engine::generate_thumbnail(video_path, output_path, timestamp_ms, max_width) {
+        Ok(()) => SUCCESS,
+        Err(e) => fail("Thumbnail generation failed", &e),
+    }
+}
+
+// ============================================================================
+// Live Compositing (caller-owned frame, no FFmpeg)
+// ============================================================================
+
+/// Draw the cursor sprite onto an RGBA8 frame owned by the caller.
+///
+/// `stride` is the distance between rows in bytes (>= width * 4) and
+/// `frame_len` must cover at least `stride * height` bytes. The sprite is
+/// decoded on first use and cached by path; call
+/// `invalidate_cursor_sprite_cache` after the file changes. Animated sprites
+/// show their first frame (see `composite_cursor_on_buffer_at`).
+#[no_mangle]
+pub unsafe extern "C" fn composite_cursor_on_buffer(
+    frame: *mut u8,
+    frame_len: usize,
+    width: u32,
+    height: u32,
+    stride: u32,
+    sprite_path: *const c_char,
+    x: f32,
+    y: f32,
+) -> c_int {
+    ffi_guard("composite_cursor_on_buffer", ERR_PANIC, || {
+        composite_at(
+            frame,
+            frame_len,
+            width,
+            height,
+            stride,
+            sprite_path,
+            x,
+            y,
+            0.0,
+        )
+    })
+}
+
+/// Like `composite_cursor_on_buffer`, drawing the frame of an animated
+/// sprite (GIF/APNG) shown `timestamp_ms` into the video
+#[no_mangle]
+pub unsafe extern "C" fn composite_cursor_on_buffer_at(
+    frame: *mut u8,
+    frame_len: usize,
+    width: u32,
+    height: u32,
+    stride: u32,
+    sprite_path: *const c_char,
+    x: f32,
+    y: f32,
+    timestamp_ms: f64,
+) -> c_int {
+    ffi_guard("composite_cursor_on_buffer_at", ERR_PANIC, || {
+        composite_at(
+            frame,
+            frame_len,
+            width,
+            height,
+            stride,
+            sprite_path,
+            x,
+            y,
+            timestamp_ms,
+        )
+    })
+}
+
+/// Shared body of the live compositing entry points
+#[allow(clippy::too_many_arguments)]
+unsafe fn composite_at(
+    frame: *mut u8,
+    frame_len: usize,
+    width: u32,
+    height: u32,
+    stride: u32,
+    sprite_path: *const c_char,
+    x: f32,
+    y: f32,
+    timestamp_ms: f64,
+) -> c_int {
+    if frame.is_null() || sprite_path.is_null() {
+        return refuse(ERR_NULL_POINTER);
+    }
+
+    let sprite_path = match c_path(sprite_path) {
+        Ok(p) => p,
+        Err(code) => return code,
+    };
+
+    let buffer = engine::RgbaBufferMut {
+        data: slice::from_raw_parts_mut(frame, frame_len),
+        width,
+        height,
+        stride: stride as usize,
+    };
+
+    let outcome = engine::load_cursor_sprite_cached(sprite_path).and_then(|sprite| {
+        engine::composite_cursor_on_buffer(
+            buffer,
+            sprite.frame_at(timestamp_ms),
+            x,
+            y,
+            &Default::default(),
+        )
+    });
+
+    match outcome {
+        Ok(()) => SUCCESS,
+        Err(e) => fail("Cursor compositing failed", &e),
+    }
+}
+
+/// Drop the cached sprite for `sprite_path`, or every cached sprite if NULL.
+#[no_mangle]
+pub unsafe extern "C" fn invalidate_cursor_sprite_cache(sprite_path: *const c_char) {
+    ffi_guard("invalidate_cursor_sprite_cache", (), || {
+        if sprite_path.is_null() {
+            engine::invalidate_sprite_cache(None);
+        } else if let Ok(path) = c_path(sprite_path) {
+            engine::invalidate_sprite_cache(Some(path));
+        }
+    })
+}
+
+// ============================================================================
+// Path Preview (smoothing only, no FFmpeg)
+// ============================================================================
+
+/// Smooth `raw_cursor_points` exactly as `process_video_with_cursor_v2` would,
+/// without touching the video. Free the result with `free_smoothed_path`.
+///
+/// On failure the returned path is empty and the reason is available from
+/// `focusframe_last_error_code` / `focusframe_last_error_message`.
+#[no_mangle]
+pub unsafe extern "C" fn compute_smoothed_path(
+    raw_cursor_points: *const CPoint,
+    raw_cursor_points_len: usize,
+    config: *const VideoProcessingConfigV2,
+) -> CSmoothedPath {
+    compute_smoothed_path_with_metrics(
+        raw_cursor_points,
+        raw_cursor_points_len,
+        config,
+        std::ptr::null_mut(),
+    )
+}
+
+/// `compute_smoothed_path` that also fills `metrics` (optional, may be NULL)
+/// with motion statistics of the smoothed path. `metrics` is left untouched
+/// on failure.
+#[no_mangle]
+pub unsafe extern "C" fn compute_smoothed_path_with_metrics(
+    raw_cursor_points: *const CPoint,
+    raw_cursor_points_len: usize,
+    config: *const VideoProcessingConfigV2,
+    metrics: *mut PathMetrics, // Optional (may be NULL)
+) -> CSmoothedPath {
+    ffi_guard("compute_smoothed_path", into_c_path(Vec::new()), || {
+        if raw_cursor_points.is_null() || config.is_null() {
+            set_last_error(ERR_NULL_POINTER, "compute_smoothed_path: NULL argument");
+            return into_c_path(Vec::new());
+        }
+
+        let opts = match read_config(config).and_then(|config| {
+            utils::init_logging(config.log_level);
+            ProcessingOptions::try_from(&config)
+        }) {
+            Ok(opts) => opts,
             Err(e) => {
-                log::error!("Video processing failed: {}", e);
-                ERR_RENDERING_FAILED
+                fail("Invalid configuration", &e);
+                return into_c_path(Vec::new());
             }
-        }
-    }));
+        };
+        let raw_points = slice::from_raw_parts(raw_cursor_points, raw_cursor_points_len);
 
-    // 7. Handle Result
-    match result {
-        Ok(return_code) => return_code,
-        Err(e) => {
-            // Log panic details if possible
-            if let Some(s) = e.downcast_ref::<&str>() {
-                log::error!("CRITICAL RUST PANIC: {}", s);
-            } else if let Some(s) = e.downcast_ref::<String>() {
-                log::error!("CRITICAL RUST PANIC: {}", s);
-            } else {
-                log::error!("CRITICAL RUST PANIC: Unknown cause");
+        let smoothed = if metrics.is_null() {
+            engine::smooth_path(raw_points, &opts).map(|points| (points, None))
+        } else {
+            engine::smooth_path_with_metrics(raw_points, &opts)
+                .map(|(points, path_metrics)| (points, Some(path_metrics)))
+        };
+
+        match smoothed {
+            Ok((points, path_metrics)) => {
+                if let Some(path_metrics) = path_metrics {
+                    *metrics = path_metrics;
+                }
+                into_c_path(points)
+            }
+            Err(e) => {
+                fail("Path smoothing failed", &e);
+                into_c_path(Vec::new())
             }
-            // Ensure we return a strict error code so Go knows to abort cleanly
-            ERR_RENDERING_FAILED
         }
-    }
+    })
+}
+
+/// `compute_smoothed_path` for event-tagged samples (see
+/// `RenderExtras::cursor_events`)
+#[no_mangle]
+pub unsafe extern "C" fn compute_smoothed_path_events(
+    cursor_events: *const CEventPoint,
+    cursor_events_len: usize,
+    config: *const VideoProcessingConfigV2,
+) -> CSmoothedPath {
+    ffi_guard(
+        "compute_smoothed_path_events",
+        into_c_path(Vec::new()),
+        || {
+            if cursor_events.is_null() || config.is_null() {
+                set_last_error(
+                    ERR_NULL_POINTER,
+                    "compute_smoothed_path_events: NULL argument",
+                );
+                return into_c_path(Vec::new());
+            }
+
+            let opts = match read_config(config).and_then(|config| {
+                utils::init_logging(config.log_level);
+                ProcessingOptions::try_from(&config)
+            }) {
+                Ok(opts) => opts,
+                Err(e) => {
+                    fail("Invalid configuration", &e);
+                    return into_c_path(Vec::new());
+                }
+            };
+            let events = slice::from_raw_parts(cursor_events, cursor_events_len);
+
+            match engine::smooth_events(events, &opts) {
+                Ok(points) => into_c_path(points),
+                Err(e) => {
+                    fail("Path smoothing failed", &e);
+                    into_c_path(Vec::new())
+                }
+            }
+        },
+    )
 }
 
 // ============================================================================
@@ -151,34 +2484,145 @@ pub unsafe extern "C" fn smooth_cursor_path(
     friction: f32,
     _mass: f32,
 ) -> CSmoothedPath {
-    if raw_points_ptr.is_null() || raw_points_len == 0 {
-        return CSmoothedPath {
-            points: std::ptr::null_mut(),
-            len: 0,
-        };
-    }
+    ffi_guard("smooth_cursor_path", into_c_path(Vec::new()), || {
+        if raw_points_ptr.is_null() || raw_points_len == 0 {
+            return CSmoothedPath {
+                points: std::ptr::null_mut(),
+                len: 0,
+            };
+        }
 
-    let raw_points = slice::from_raw_parts(raw_points_ptr, raw_points_len);
+        let raw_points = slice::from_raw_parts(raw_points_ptr, raw_points_len);
 
-    // Map legacy parameters to new API
-    let responsiveness = (tension - 50.0) / 450.0;
-    let smoothness = (friction - 5.0) / 45.0;
+        // Map legacy parameters to new API
+        let responsiveness = (tension - 50.0) / 450.0;
+        let smoothness = (friction - 5.0) / 45.0;
 
-    let result = smoothing::smooth_cursor_path_dual_pass(
-        raw_points,
-        60, // Default frame rate for standalone
-        responsiveness.clamp(0.0, 1.0),
-        smoothness.clamp(0.0, 1.0),
-        alpha,
-    );
+        let result = smoothing::smooth_cursor_path_dual_pass(
+            raw_points,
+            60, // Default frame rate for standalone
+            SmoothingMode::Spring,
+            Interpolation::CatmullRom,
+            responsiveness.clamp(0.0, 1.0),
+            smoothness.clamp(0.0, 1.0),
+            alpha,
+            &PathCleanup::default(),
+            None,
+            &SpringTuning::default(),
+            &[],
+        );
 
-    // Transfer ownership to C
-    let mut boxed_slice = result.into_boxed_slice();
-    let len = boxed_slice.len();
-    let ptr = boxed_slice.as_mut_ptr();
-    std::mem::forget(boxed_slice);
+        match result {
+            Ok((points, _)) => into_c_path(points),
+            Err(e) => {
+                fail("Path smoothing failed", &e);
+                into_c_path(Vec::new())
+            }
+        }
+    })
+}
 
-    CSmoothedPath { points: ptr, len }
+/// Dual-pass smoothing with the same parameters used by the video pipeline.
+///
+/// Unlike the legacy `smooth_cursor_path`, the physics filter is driven
+/// directly by `responsiveness`/`smoothness` (0.0-1.0) and the output is
+/// resampled onto the `frame_rate` grid.
+#[no_mangle]
+pub unsafe extern "C" fn smooth_cursor_path_v2(
+    raw_points_ptr: *const CPoint,
+    raw_points_len: usize,
+    frame_rate: i32,
+    responsiveness: f32,
+    smoothness: f32,
+    alpha: f32,
+) -> CSmoothedPath {
+    ffi_guard("smooth_cursor_path_v2", into_c_path(Vec::new()), || {
+        if raw_points_ptr.is_null() || raw_points_len == 0 || frame_rate <= 0 {
+            return CSmoothedPath {
+                points: std::ptr::null_mut(),
+                len: 0,
+            };
+        }
+
+        let raw_points = slice::from_raw_parts(raw_points_ptr, raw_points_len);
+
+        // <2 points and zero-duration paths collapse to a single point inside
+        // the pipeline, exactly as they do during a full render.
+        let result = smoothing::smooth_cursor_path_dual_pass(
+            raw_points,
+            frame_rate,
+            SmoothingMode::Spring,
+            Interpolation::CatmullRom,
+            responsiveness.clamp(0.0, 1.0),
+            smoothness.clamp(0.0, 1.0),
+            alpha,
+            &PathCleanup::default(),
+            None,
+            &ProcessingOptions::default().spring_tuning(),
+            &[],
+        );
+
+        match result {
+            Ok((points, _)) => into_c_path(points),
+            Err(e) => {
+                fail("Path smoothing failed", &e);
+                into_c_path(Vec::new())
+            }
+        }
+    })
+}
+
+/// Spring-damper pass only: one output point per input point, timestamps
+/// copied through unchanged. Empty only for empty (or NULL) input.
+#[no_mangle]
+pub unsafe extern "C" fn physics_filter_path(
+    raw_points_ptr: *const CPoint,
+    raw_points_len: usize,
+    responsiveness: f32,
+    smoothness: f32,
+) -> CSmoothedPath {
+    ffi_guard("physics_filter_path", into_c_path(Vec::new()), || {
+        if raw_points_ptr.is_null() || raw_points_len == 0 {
+            return into_c_path(Vec::new());
+        }
+
+        let raw_points = slice::from_raw_parts(raw_points_ptr, raw_points_len);
+        into_c_path(engine::physics_filter(
+            raw_points,
+            responsiveness,
+            smoothness,
+        ))
+    })
+}
+
+/// Catmull-Rom upsampling pass only, onto the `start + i * 1000 / frame_rate`
+/// grid. On an invalid frame rate the result is empty and the reason is
+/// available from `focusframe_last_error_code`.
+#[no_mangle]
+pub unsafe extern "C" fn resample_path_to_framerate(
+    points_ptr: *const CPoint,
+    points_len: usize,
+    frame_rate: i32,
+    alpha: f32,
+) -> CSmoothedPath {
+    ffi_guard(
+        "resample_path_to_framerate",
+        into_c_path(Vec::new()),
+        || {
+            if points_ptr.is_null() || points_len == 0 {
+                return into_c_path(Vec::new());
+            }
+
+            let points = slice::from_raw_parts(points_ptr, points_len);
+            match engine::resample_to_framerate(points, frame_rate, alpha) {
+                Ok(resampled) => into_c_path(resampled),
+                Err(e) => {
+                    fail("Resampling failed", &e);
+                    into_c_path(Vec::new())
+                }
+            }
+        },
+    )
 }
 
 #[no_mangle]
@@ -188,79 +2632,558 @@ pub unsafe extern "C" fn free_smoothed_path(path: CSmoothedPath) {
     }
 }
 
+/// Transfer ownership of a point buffer to C (released by `free_smoothed_path`)
+fn into_c_path(points: Vec<CPoint>) -> CSmoothedPath {
+    if points.is_empty() {
+        return CSmoothedPath {
+            points: std::ptr::null_mut(),
+            len: 0,
+        };
+    }
+
+    // Boxed slice guarantees capacity == len for the later from_raw_parts
+    let mut boxed_slice = points.into_boxed_slice();
+    let len = boxed_slice.len();
+    let ptr = boxed_slice.as_mut_ptr();
+    std::mem::forget(boxed_slice);
+
+    CSmoothedPath { points: ptr, len }
+}
+
 // ============================================================================
-// Internal Safe Processing Function
+// Progress Reporting
 // ============================================================================
 
-struct ProgressReporter {
-    callback: Option<ProgressCallback>,
-    user_data: *mut c_void,
+/// Forwards render updates to the C callbacks
+struct FfiObserver {
+    callbacks: ProcessingCallbacks,
 }
 
-impl ProgressReporter {
-    fn report(&self, percent: f32) {
-        if let Some(cb) = self.callback {
-            cb(self.user_data, percent);
+impl RenderObserver for FfiObserver {
+    fn progress(&mut self, progress: Progress) {
+        if let Some(cb) = self.callbacks.progress {
+            cb(self.callbacks.user_data, progress.fraction);
+        }
+    }
+
+    fn heartbeat(&mut self, heartbeat: &Heartbeat) {
+        if let Some(cb) = self.callbacks.heartbeat {
+            let c_heartbeat = RenderHeartbeat::from(heartbeat);
+            cb(self.callbacks.user_data, &c_heartbeat);
+        }
+    }
+
+    fn warning(&mut self, warning: &RenderWarning) {
+        if let Some(cb) = self.callbacks.warning {
+            let frame = warning.frame.map_or(-1, |f| f as i64);
+            let message = CString::new(warning.message.replace('\0', " ")).unwrap_or_default();
+            cb(
+                self.callbacks.user_data,
+                warning.kind.code(),
+                frame,
+                message.as_ptr(),
+            );
         }
     }
 }
 
 // Unsafe Send for raw pointers (we guarantee Go handles thread safety)
-unsafe impl Send for ProgressReporter {}
+unsafe impl Send for FfiObserver {}
 
-fn process_video_internal(
-    input_path: &str,
-    output_path: &str,
-    cursor_path: &str,
-    raw_points: &[CPoint],
-    config: &VideoProcessingConfig,
-    progress: ProgressReporter,
-) -> Result<(), Box<dyn std::error::Error>> {
-    progress.report(0.05);
-    log::info!(
-        "Starting processing with {} raw cursor points",
-        raw_points.len()
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Step 1: Smooth cursor path
-    let smoothed_points = smoothing::smooth_cursor_path_dual_pass(
-        raw_points,
-        config.frame_rate,
-        config.responsiveness,
-        config.smoothness,
-        config.smoothing_alpha,
-    );
+    #[test]
+    fn zero_frame_rate_is_refused() {
+        let config = VideoProcessingConfigV2 {
+            frame_rate: 0,
+            ..VideoProcessingConfigV2::default()
+        };
+        let error = ProcessingOptions::try_from(&config)
+            .and_then(|opts| opts.validated())
+            .unwrap_err();
+        assert!(
+            matches!(error, EngineError::InvalidFrameRate(0)),
+            "{:?}",
+            error
+        );
+        assert_eq!(error_code(&error), ERR_INVALID_FRAME_RATE);
 
-    log::info!(
-        "Smoothing complete. Generated {} interpolated points",
-        smoothed_points.len()
-    );
+        // The legacy config takes the same path
+        let legacy = VideoProcessingConfig {
+            frame_rate: 0,
+            ..VideoProcessingConfig::default()
+        };
+        let error = ProcessingOptions::from(&legacy).validated().unwrap_err();
+        assert!(matches!(error, EngineError::InvalidFrameRate(0)));
+    }
+
+    #[test]
+    fn config_struct_size_is_checked_and_extra_fields_ignored() {
+        // A caller built against a newer header passes a longer struct
+        #[repr(C)]
+        struct Newer {
+            config: VideoProcessingConfigV2,
+            appended: [u32; 4],
+        }
+        let mut newer = Newer {
+            config: VideoProcessingConfigV2 {
+                frame_rate: 24,
+                preview_mode: 1,
+                ..VideoProcessingConfigV2::default()
+            },
+            appended: [u32::MAX; 4],
+        };
+        newer.config.struct_size = std::mem::size_of::<Newer>() as u32;
+        let read = unsafe { read_config(&newer.config).unwrap() };
+        assert_eq!(read.frame_rate, 24);
+        assert_eq!(read.preview_mode, 1);
+        assert_eq!(
+            read.struct_size as usize,
+            std::mem::size_of::<VideoProcessingConfigV2>()
+        );
 
-    if smoothed_points.is_empty() {
-        log::error!(
-            "Smoothing failed! Raw points: {}, Config: {:?}",
-            raw_points.len(),
-            config
+        // Anything shorter than the first published layout is refused
+        let short = VideoProcessingConfigV2 {
+            struct_size: CONFIG_V2_MIN_SIZE as u32 - 4,
+            ..VideoProcessingConfigV2::default()
+        };
+        let error = unsafe { read_config(&short).unwrap_err() };
+        assert!(
+            matches!(
+                error,
+                EngineError::InvalidOption {
+                    name: "struct_size",
+                    ..
+                }
+            ),
+            "{:?}",
+            error
         );
-        return Err("Cursor smoothing produced no points".into());
+        let point = CPoint {
+            x: 0.0,
+            y: 0.0,
+            timestamp_ms: 0.0,
+        };
+        let path = unsafe { compute_smoothed_path(&point, 1, &short) };
+        assert!(path.points.is_null());
+        assert_eq!(focusframe_last_error_code(), error_code(&error));
     }
 
-    progress.report(0.10);
+    /// Code and message of the calling thread's last error
+    fn last_error() -> (i32, String) {
+        let message = unsafe { CStr::from_ptr(focusframe_last_error_message()) };
+        (
+            focusframe_last_error_code(),
+            message.to_string_lossy().into_owned(),
+        )
+    }
 
-    // Step 2: Load cursor sprite
-    let cursor_sprite = renderer::load_cursor_sprite(cursor_path)?;
-    progress.report(0.15);
+    #[test]
+    fn each_thread_reads_back_its_own_last_error() {
+        // Both fail before either reads, so a shared slot would show one
+        // thread the other's error
+        let barrier = std::sync::Barrier::new(2);
+        let (null_argument, bad_frame_rate) = std::thread::scope(|scope| {
+            let null_argument = scope.spawn(|| {
+                let path = unsafe { compute_smoothed_path(std::ptr::null(), 0, std::ptr::null()) };
+                assert_eq!(path.len, 0);
+                barrier.wait();
+                last_error()
+            });
+            let bad_frame_rate = scope.spawn(|| {
+                let points = [CPoint {
+                    x: 0.0,
+                    y: 0.0,
+                    timestamp_ms: 0.0,
+                }];
+                let path = unsafe { resample_path_to_framerate(points.as_ptr(), 1, 0, 0.5) };
+                assert_eq!(path.len, 0);
+                barrier.wait();
+                last_error()
+            });
+            (
+                null_argument.join().unwrap(),
+                bad_frame_rate.join().unwrap(),
+            )
+        });
 
-    // Step 3: Process video
-    video::process_video(
-        input_path,
-        output_path,
-        &smoothed_points,
-        &cursor_sprite,
-        config,
-        |p| progress.report(0.15 + p * 0.85),
-    )?;
+        assert_eq!(null_argument.0, ERR_NULL_POINTER);
+        assert_eq!(null_argument.1, "compute_smoothed_path: NULL argument");
+        assert_eq!(bad_frame_rate.0, ERR_INVALID_FRAME_RATE);
+        assert!(
+            bad_frame_rate
+                .1
+                .starts_with("Resampling failed: invalid frame rate 0"),
+            "{}",
+            bad_frame_rate.1
+        );
+        // Nothing failed on this thread
+        assert_eq!(last_error(), (SUCCESS, String::new()));
+    }
+
+    #[test]
+    fn each_call_replaces_the_last_error() {
+        let points = [CPoint {
+            x: 0.0,
+            y: 0.0,
+            timestamp_ms: 0.0,
+        }];
+        unsafe { resample_path_to_framerate(points.as_ptr(), 1, 0, 0.5) };
+        assert_eq!(last_error().0, ERR_INVALID_FRAME_RATE);
+
+        // A NULL argument is reported as such, not as the earlier failure
+        let code = unsafe { get_video_metadata(std::ptr::null(), std::ptr::null_mut()) };
+        assert_eq!(code, ERR_NULL_POINTER);
+        assert_eq!(
+            last_error(),
+            (
+                ERR_NULL_POINTER,
+                "get_video_metadata: NULL argument".to_string()
+            )
+        );
+
+        // Including one found in a helper, named after the entry point
+        let config = VideoProcessingConfig::default();
+        let code = unsafe {
+            process_video_with_cursor(
+                c"in.mp4".as_ptr(),
+                c"out.mp4".as_ptr(),
+                c"cursor.png".as_ptr(),
+                std::ptr::null(),
+                0,
+                &config,
+                None,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        assert_eq!(code, ERR_NULL_POINTER);
+        assert_eq!(last_error().1, "process_video_with_cursor: NULL argument");
+
+        // A call that succeeds clears it
+        let builder = cursor_path_builder_new();
+        let code = unsafe { cursor_path_builder_append(builder, points.as_ptr(), 1) };
+        assert_eq!(code, SUCCESS);
+        assert_eq!(last_error(), (SUCCESS, String::new()));
+        unsafe { cursor_path_builder_free(builder) };
+    }
+
+    #[test]
+    fn smooth_cursor_path_v2_matches_the_engine_defaults() {
+        let raw: Vec<CPoint> = (0..40)
+            .map(|i| CPoint {
+                x: (i * 17 % 300) as f32,
+                y: 40.0 + (i * i % 90) as f32,
+                timestamp_ms: i as f64 * 25.0,
+            })
+            .collect();
+        let opts = ProcessingOptions::default();
+        let expected = engine::smooth_path(&raw, &opts).unwrap();
+
+        let path = unsafe {
+            smooth_cursor_path_v2(
+                raw.as_ptr(),
+                raw.len(),
+                opts.frame_rate,
+                opts.responsiveness,
+                opts.smoothness,
+                opts.smoothing_alpha,
+            )
+        };
+        let points = unsafe { slice::from_raw_parts(path.points, path.len) };
+        assert_eq!(points.len(), expected.len());
+        for (got, want) in points.iter().zip(&expected) {
+            assert_eq!(
+                (got.x, got.y, got.timestamp_ms),
+                (want.x, want.y, want.timestamp_ms)
+            );
+        }
+        unsafe { free_smoothed_path(path) };
+
+        // No points or no frame rate is an empty path, not an error
+        for (ptr, len, frame_rate) in [
+            (std::ptr::null(), raw.len(), 60),
+            (raw.as_ptr(), 0, 60),
+            (raw.as_ptr(), raw.len(), 0),
+            (raw.as_ptr(), raw.len(), -30),
+        ] {
+            let path = unsafe { smooth_cursor_path_v2(ptr, len, frame_rate, 0.5, 0.7, 0.5) };
+            assert!(path.points.is_null());
+            assert_eq!(path.len, 0);
+        }
+    }
 
-    progress.report(1.0);
-    Ok(())
+    #[test]
+    fn processing_result_copies_every_report_field() {
+        use engine::{EncoderBackend, WarningCounts};
+        use std::time::Duration;
+
+        let report = ProcessingReport {
+            frames_encoded: 1,
+            output_duration: Duration::from_millis(2_500),
+            source_duration: Duration::from_millis(4_000),
+            output_file_size_bytes: 3,
+            wall_clock: Duration::from_micros(1_250),
+            encoder_backend: EncoderBackend::Nvenc,
+            warnings: WarningCounts {
+                timestamp_clamped: 11,
+                cursor_outside_frame: 12,
+                frames_dropped: 13,
+                frames_duplicated: 14,
+                sprite_clipped: 15,
+                outliers_rejected: 16,
+                stream_copy_unavailable: 17,
+                undecodable_packets: 18,
+                sequence_frames_missing: 19,
+            },
+            stream_copied: true,
+            proxy: true,
+        };
+        let result = ProcessingResult::from(&report);
+
+        assert_eq!(result.frames_encoded, 1);
+        assert_eq!(result.output_duration_ms, 2_500.0);
+        assert_eq!(result.source_duration_ms, 4_000.0);
+        assert_eq!(result.output_file_size_bytes, 3);
+        assert_eq!(result.wall_clock_ms, 1.25);
+        assert_eq!(result.encoder_backend, 3);
+        assert_eq!(result.frames_where_cursor_was_clamped, 11);
+        assert_eq!(result.warnings_cursor_outside_frame, 12);
+        assert_eq!(result.warnings_frames_dropped, 13);
+        assert_eq!(result.warnings_frames_duplicated, 14);
+        assert_eq!(result.warnings_sprite_clipped, 15);
+        assert_eq!(result.warnings_outliers_rejected, 16);
+        assert_eq!(result.warnings_stream_copy_unavailable, 17);
+        assert_eq!(result.warnings_undecodable_packets, 18);
+        assert_eq!(result.warnings_sequence_frames_missing, 19);
+        assert_eq!(result.stream_copied, 1);
+        assert_eq!(result.proxy, 1);
+    }
+
+    #[test]
+    fn builder_counts_points_and_seals_when_a_render_starts() {
+        let points: Vec<CPoint> = (0..5)
+            .map(|i| CPoint {
+                x: i as f32,
+                y: 0.0,
+                timestamp_ms: f64::from(i) * 100.0,
+            })
+            .collect();
+        let builder = cursor_path_builder_new();
+        unsafe {
+            assert_eq!(cursor_path_builder_len(builder), 0);
+            assert_eq!(
+                cursor_path_builder_append(builder, points.as_ptr(), 3),
+                SUCCESS
+            );
+            assert_eq!(
+                cursor_path_builder_append(builder, points[3..].as_ptr(), 2),
+                SUCCESS
+            );
+            assert_eq!(
+                cursor_path_builder_append(builder, std::ptr::null(), 0),
+                SUCCESS
+            );
+            assert_eq!(cursor_path_builder_len(builder), 5);
+            assert_eq!(
+                cursor_path_builder_append(builder, std::ptr::null(), 1),
+                ERR_NULL_POINTER
+            );
+            assert_eq!(cursor_path_builder_len(std::ptr::null()), 0);
+
+            // The render fails (there is no sprite), but it has started
+            let config = VideoProcessingConfigV2::default();
+            let code = process_video_with_cursor_builder(
+                c"absent.mp4".as_ptr(),
+                c"out.mp4".as_ptr(),
+                c"absent.png".as_ptr(),
+                builder,
+                &config,
+                std::ptr::null(),
+                std::ptr::null_mut(),
+            );
+            assert_ne!(code, SUCCESS);
+            assert_eq!(
+                cursor_path_builder_append(builder, points.as_ptr(), 1),
+                ERR_BUILDER_SEALED
+            );
+            assert_eq!(
+                last_error(),
+                (
+                    ERR_BUILDER_SEALED,
+                    "cursor_path_builder_append: render already started".to_string()
+                )
+            );
+            assert_eq!(cursor_path_builder_len(builder), 5);
+
+            // The render has dropped its reference again; ours is the last
+            let shared = Arc::from_raw(builder);
+            assert_eq!(Arc::strong_count(&shared), 1);
+            cursor_path_builder_free(Arc::into_raw(shared));
+            cursor_path_builder_free(std::ptr::null());
+        }
+    }
+
+    #[test]
+    fn panic_in_the_guard_becomes_err_panic() {
+        let code = ffi_guard("test_entry", ERR_PANIC, || -> i32 {
+            panic!("sprite index {} out of range", 7)
+        });
+        assert_eq!(code, ERR_PANIC);
+        assert_eq!(
+            last_error(),
+            (
+                ERR_PANIC,
+                "panic in test_entry: sprite index 7 out of range".to_string()
+            )
+        );
+
+        // A &str payload reads the same, and a body that returns is untouched
+        let code = ffi_guard("test_entry", ERR_PANIC, || -> i32 { panic!("plain") });
+        assert_eq!(code, ERR_PANIC);
+        assert_eq!(last_error().1, "panic in test_entry: plain");
+        assert_eq!(ffi_guard("test_entry", ERR_PANIC, || SUCCESS), SUCCESS);
+    }
+
+    #[test]
+    fn extras_combine_in_one_render() {
+        let captions = c"[{\"text\": \"Step 1\", \"start_ms\": 0, \"end_ms\": 500}]";
+        let zoom = c"[{\"start_ms\": 0, \"end_ms\": 400, \"zoom\": 2.0, \"center_x\": 8, \"center_y\": 8}]";
+        let (font, watermark) = (c"font.ttf", c"logo.png");
+        let tags = [CMetadataTag {
+            key: c"title".as_ptr(),
+            value: c"Demo".as_ptr(),
+        }];
+        let regions = [CRedactionRegion {
+            x: 0,
+            y: 0,
+            width: 4,
+            height: 4,
+            start_ms: 0.0,
+            end_ms: -1.0,
+            mode: 1,
+            amount_px: 2,
+        }];
+        let clicks = [CClickEvent {
+            x: 1.0,
+            y: 2.0,
+            timestamp_ms: 100.0,
+            button: 0,
+        }];
+        let raw = RenderExtras {
+            clicks: clicks.as_ptr(),
+            clicks_len: clicks.len(),
+            zoom_keyframes_json: zoom.as_ptr(),
+            watermark_path: watermark.as_ptr(),
+            redactions: regions.as_ptr(),
+            redactions_len: regions.len(),
+            captions_json: captions.as_ptr(),
+            caption_font_path: font.as_ptr(),
+            metadata: tags.as_ptr(),
+            metadata_len: tags.len(),
+            ..RenderExtras::default()
+        };
+
+        let config = VideoProcessingConfigV2::default();
+        let mut opts = ProcessingOptions::try_from(&config).unwrap();
+        let extras = unsafe { read_extras(&raw) }.unwrap();
+        let clicks = extras.apply(&mut opts, &config).unwrap();
+        assert_eq!(clicks.len(), 1);
+        assert_eq!(opts.zoom_keyframes.len(), 1);
+        assert!(opts.watermark.is_some());
+        assert_eq!(opts.redactions.len(), 1);
+        assert_eq!(opts.redactions[0].end_ms, None);
+        assert_eq!(opts.captions.len(), 1);
+        assert_eq!(opts.caption_style.font_path, Path::new("font.ttf"));
+        assert_eq!(opts.metadata["title"], "Demo");
+
+        // A zeroed struct adds nothing
+        let extras = unsafe { read_extras(&RenderExtras::default()) }.unwrap();
+        assert!(extras.clicks.is_empty() && extras.captions.is_none());
+    }
+
+    #[test]
+    fn missing_extras_are_null_pointer_errors() {
+        // An array with a length but no data
+        let raw = RenderExtras {
+            redactions_len: 2,
+            ..RenderExtras::default()
+        };
+        assert_eq!(unsafe { read_extras(&raw) }.err(), Some(ERR_NULL_POINTER));
+
+        // Key presses without the font to draw them in
+        let keys = [CKeyEvent {
+            timestamp_ms: 0.0,
+            text: c"a".as_ptr(),
+        }];
+        let raw = RenderExtras {
+            key_events: keys.as_ptr(),
+            key_events_len: keys.len(),
+            ..RenderExtras::default()
+        };
+        assert_eq!(unsafe { read_extras(&raw) }.err(), Some(ERR_NULL_POINTER));
+
+        // No cursor points and no cursor events
+        let config = VideoProcessingConfigV2::default();
+        let code = unsafe {
+            process_video_ex(
+                c"in.mp4".as_ptr(),
+                c"out.mp4".as_ptr(),
+                c"cursor.png".as_ptr(),
+                std::ptr::null(),
+                0,
+                std::ptr::null(),
+                &config,
+                std::ptr::null(),
+                std::ptr::null_mut(),
+            )
+        };
+        assert_eq!(code, ERR_NULL_POINTER);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_load_or_fail_without_panicking() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = std::env::temp_dir().join(format!("lib-non-utf8-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Latin-1 "curseur-é.png": 0xE9 alone is not UTF-8
+        let name = std::ffi::OsStr::from_bytes(b"curseur-\xe9.png");
+        assert!(name.to_str().is_none());
+        let sprite_path = dir.join(name);
+        image::RgbaImage::from_pixel(4, 4, image::Rgba([255; 4]))
+            .save_with_format(&sprite_path, image::ImageFormat::Png)
+            .unwrap();
+
+        let (width, height) = (8, 8);
+        let mut frame = vec![0u8; width as usize * height as usize * 4];
+        let composite = |path: &Path, frame: &mut [u8]| {
+            let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+            unsafe {
+                composite_cursor_on_buffer(
+                    frame.as_mut_ptr(),
+                    frame.len(),
+                    width,
+                    height,
+                    width * 4,
+                    path.as_ptr(),
+                    2.0,
+                    2.0,
+                )
+            }
+        };
+        assert_eq!(composite(&sprite_path, &mut frame), SUCCESS);
+        assert_eq!(frame[(2 * width as usize + 2) * 4], 255);
+
+        // A missing file with such a name is an ordinary error
+        let missing = dir.join(std::ffi::OsStr::from_bytes(b"absent-\xff.png"));
+        assert_eq!(composite(&missing, &mut frame), ERR_RENDERING_FAILED);
+        let (code, message) = last_error();
+        assert_eq!(code, ERR_RENDERING_FAILED);
+        assert!(!message.contains("panic"), "{}", message);
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
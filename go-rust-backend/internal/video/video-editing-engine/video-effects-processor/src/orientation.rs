@@ -0,0 +1,126 @@
+// orientation.rs - Source rotation metadata: the display matrix phones and
+// some capture tools store instead of turning the frames, as the mirroring
+// and quarter turns that stand the stored frames upright
+
+/// What to do with a source whose stream carries rotation metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RotationHandling {
+    /// Turn the frames upright and leave the metadata out of the output
+    #[default]
+    Rotate,
+    /// Encode the frames as stored and copy the metadata for players to
+    /// apply. Overlays are still drawn on the upright picture.
+    KeepMetadata,
+}
+
+impl RotationHandling {
+    /// FFI value (0 = rotate, 1 = keep the metadata)
+    pub fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Rotate),
+            1 => Some(Self::KeepMetadata),
+            _ => None,
+        }
+    }
+}
+
+/// How stored frames are stood upright: mirrored left to right when
+/// `mirrored`, then turned clockwise by `quarter_turns` x 90 degrees
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Orientation {
+    pub quarter_turns: u8,
+    pub mirrored: bool,
+}
+
+impl Orientation {
+    /// What a player does for `matrix`, a display matrix as stored in side
+    /// data (row-major 3x3, 16.16 fixed point but for the last column),
+    /// following the ffmpeg CLI's autorotation. None when it turns by
+    /// something other than a multiple of 90 degrees.
+    pub fn from_display_matrix(matrix: &[i32; 9]) -> Option<Self> {
+        let fixed = |i: usize| f64::from(matrix[i]) / 65536.0;
+        let scale_x = fixed(0).hypot(fixed(3));
+        let scale_y = fixed(1).hypot(fixed(4));
+        if scale_x == 0.0 || scale_y == 0.0 {
+            return None;
+        }
+        let degrees = (fixed(1) / scale_y)
+            .atan2(fixed(0) / scale_x)
+            .to_degrees()
+            .round()
+            .rem_euclid(360.0);
+        let (quarter_turns, mirrored) = match degrees as u32 {
+            // Upside down and mirrored, i.e. flipped vertically
+            0 if matrix[4] < 0 => (2, true),
+            0 => (0, false),
+            90 if matrix[3] > 0 => (3, true),
+            90 => (1, false),
+            180 => match (matrix[0] < 0, matrix[4] < 0) {
+                (true, true) => (2, false),
+                (true, false) => (0, true),
+                (false, true) => (2, true),
+                (false, false) => (0, false),
+            },
+            270 if matrix[3] < 0 => (1, true),
+            270 => (3, false),
+            _ => return None,
+        };
+        Some(Self {
+            quarter_turns,
+            mirrored,
+        })
+    }
+
+    pub fn is_upright(self) -> bool {
+        self.quarter_turns.is_multiple_of(4) && !self.mirrored
+    }
+
+    /// The orientation that undoes this one
+    pub fn inverse(self) -> Self {
+        if self.mirrored {
+            // Mirroring then turning is a reflection, its own inverse
+            self
+        } else {
+            Self {
+                quarter_turns: (4 - self.quarter_turns % 4) % 4,
+                mirrored: false,
+            }
+        }
+    }
+
+    /// Size of a `width` x `height` stored frame once upright
+    pub fn upright_size(self, (width, height): (u32, u32)) -> (u32, u32) {
+        if self.quarter_turns % 2 == 1 {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Position (px) `point` of a `width` x `height` stored frame moves to
+    /// once the frame is upright
+    pub fn map_point(self, (x, y): (f32, f32), (width, height): (u32, u32)) -> (f32, f32) {
+        let (width, height) = (width as f32, height as f32);
+        let x = if self.mirrored { width - x } else { x };
+        match self.quarter_turns % 4 {
+            0 => (x, y),
+            1 => (height - y, x),
+            2 => (width - x, height - y),
+            _ => (y, width - x),
+        }
+    }
+
+    /// ffmpeg filters (name, arguments) that apply this orientation, in order
+    pub(crate) fn filters(self) -> &'static [(&'static str, &'static str)] {
+        match (self.quarter_turns % 4, self.mirrored) {
+            (0, false) => &[],
+            (0, true) => &[("hflip", "")],
+            (1, false) => &[("transpose", "clock")],
+            (1, true) => &[("transpose", "clock_flip")],
+            (2, false) => &[("hflip", ""), ("vflip", "")],
+            (2, true) => &[("vflip", "")],
+            (3, false) => &[("transpose", "cclock")],
+            _ => &[("transpose", "cclock_flip")],
+        }
+    }
+}
@@ -1,137 +1,3414 @@
+use crate::smoothing::{CClickEvent, CursorKindEvent, KeyEvent, BUTTON_LEFT};
+use ab_glyph::{point, Font, FontArc, Glyph, PxScale, ScaleFont};
+use image::imageops::FilterType;
 use image::GenericImageView;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::error::Error;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
 
+/// How the sprite is placed and blended relative to the cursor position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorStyle {
+    /// Draw size multiplier (1.0 = native sprite size)
+    pub scale: f32,
+    /// Hotspot in unscaled sprite pixels; this point lands on the cursor position
+    pub hotspot_x: f32,
+    pub hotspot_y: f32,
+    /// Multiplies the sprite's own alpha (0.0-1.0)
+    pub opacity: f32,
+    /// Blend in linear light instead of on sRGB bytes, so soft edges don't
+    /// darken against bright backgrounds
+    pub gamma_correct_blend: bool,
+    /// Clockwise rotation about the hotspot in radians (0 = as drawn)
+    pub rotation: f32,
+    /// Filter used to resample the sprite between texels
+    pub sampling: SamplingQuality,
+}
+
+/// Resampling filter for sprites drawn at fractional positions or scales
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SamplingQuality {
+    /// 2x2 texels, row-sliced; soft when scaled up
+    #[default]
+    Bilinear,
+    /// Catmull-Rom over 4x4 texels; keeps upscaled edges crisp at several
+    /// times the cost per pixel
+    Bicubic,
+}
+
+impl Default for CursorStyle {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            hotspot_x: 0.0,
+            hotspot_y: 0.0,
+            opacity: 1.0,
+            gamma_correct_blend: false,
+            rotation: 0.0,
+            sampling: SamplingQuality::Bilinear,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct CursorSprite {
     pub data: Vec<u8>, // Raw RGBA8 bytes
     pub width: u32,
     pub height: u32,
+    /// RGB already multiplied by alpha; straight sprites are multiplied per sample
+    pub is_premultiplied: bool,
+    /// The sprite's own hotspot in unscaled pixels, read from .cur/.ani files
+    /// ((0, 0) for other formats). A non-zero `CursorStyle` hotspot overrides it.
+    pub hotspot_x: f32,
+    pub hotspot_y: f32,
+    /// Every frame of an animated sprite, in order; empty for a still image.
+    /// The pixels above are a copy of the first frame.
+    pub animation: Vec<AnimationFrame>,
+}
+
+/// One frame of an animated cursor (GIF, APNG, .ani or sprite sheet)
+#[derive(Clone)]
+pub struct AnimationFrame {
+    pub sprite: CursorSprite,
+    pub duration_ms: u32,
+}
+
+/// Frame time for GIF/APNG frames that declare none (what browsers use)
+const DEFAULT_FRAME_MS: u32 = 100;
+
+impl CursorSprite {
+    /// Wrap caller-supplied RGBA8 pixels, premultiplying them unless they already are
+    pub fn from_rgba8(mut data: Vec<u8>, width: u32, height: u32, is_premultiplied: bool) -> Self {
+        if !is_premultiplied {
+            premultiply_alpha(&mut data);
+        }
+        Self {
+            data,
+            width,
+            height,
+            is_premultiplied: true,
+            hotspot_x: 0.0,
+            hotspot_y: 0.0,
+            animation: Vec::new(),
+        }
+    }
+
+    /// Play `frames` in a loop (a single frame gives a still sprite).
+    /// Frames should share one size; each keeps its own hotspot.
+    pub fn animated(frames: Vec<AnimationFrame>) -> Option<CursorSprite> {
+        let first = frames.first()?.sprite.clone();
+        if frames.len() == 1 {
+            return Some(first);
+        }
+        Some(CursorSprite {
+            animation: frames,
+            ..first
+        })
+    }
+
+    /// Index of the `animation` frame shown `timestamp_ms` into the video;
+    /// always 0 for still sprites
+    pub fn frame_index(&self, timestamp_ms: f64) -> usize {
+        let total_ms: u64 = self.animation.iter().map(|f| f.duration_ms as u64).sum();
+        if total_ms == 0 || !timestamp_ms.is_finite() {
+            return 0;
+        }
+        let mut t = timestamp_ms.rem_euclid(total_ms as f64) as u64;
+        for (i, frame) in self.animation.iter().enumerate() {
+            if t < frame.duration_ms as u64 {
+                return i;
+            }
+            t -= frame.duration_ms as u64;
+        }
+        self.animation.len() - 1
+    }
+
+    /// The still sprite to draw at `timestamp_ms` (`self` unless animated)
+    pub fn frame_at(&self, timestamp_ms: f64) -> &CursorSprite {
+        match self.animation.get(self.frame_index(timestamp_ms)) {
+            Some(frame) => &frame.sprite,
+            None => self,
+        }
+    }
+
+    /// Resample to `factor` times the size with a Catmull-Rom filter.
+    ///
+    /// Much sharper than stretching with the compositor's bilinear sampler at
+    /// large factors, and done once instead of every frame (see `prescale`).
+    pub fn scaled(&self, factor: f32) -> CursorSprite {
+        let width = ((self.width as f32 * factor).round() as u32).max(1);
+        let height = ((self.height as f32 * factor).round() as u32).max(1);
+        // Resample premultiplied so transparent texels cannot tint the edges
+        let mut data = self.data.clone();
+        if !self.is_premultiplied {
+            premultiply_alpha(&mut data);
+        }
+        let Some(img) = image::RgbaImage::from_raw(self.width, self.height, data) else {
+            return self.clone();
+        };
+        let mut data =
+            image::imageops::resize(&img, width, height, FilterType::CatmullRom).into_raw();
+        // Overshoot next to hard edges can leave colour above alpha
+        for px in data.chunks_exact_mut(4) {
+            let a = px[3];
+            for c in &mut px[..3] {
+                *c = (*c).min(a);
+            }
+        }
+
+        CursorSprite {
+            data,
+            width,
+            height,
+            is_premultiplied: true,
+            hotspot_x: self.hotspot_x * width as f32 / self.width as f32,
+            hotspot_y: self.hotspot_y * height as f32 / self.height as f32,
+            animation: self
+                .animation
+                .iter()
+                .map(|frame| AnimationFrame {
+                    sprite: frame.sprite.scaled(factor),
+                    duration_ms: frame.duration_ms,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// `sprite` resampled to `style.scale` ahead of a render, with the style to
+/// draw it at (scale 1.0, hotspot in resampled pixels). Native-size styles
+/// borrow `sprite` unchanged.
+pub fn prescale<'a>(
+    sprite: &'a CursorSprite,
+    style: &CursorStyle,
+) -> (Cow<'a, CursorSprite>, CursorStyle) {
+    if style.scale == 1.0 || style.scale <= 0.0 || sprite.width == 0 || sprite.height == 0 {
+        return (Cow::Borrowed(sprite), *style);
+    }
+    let scaled = sprite.scaled(style.scale);
+    let style = CursorStyle {
+        scale: 1.0,
+        hotspot_x: style.hotspot_x * scaled.width as f32 / sprite.width as f32,
+        hotspot_y: style.hotspot_y * scaled.height as f32 / sprite.height as f32,
+        ..*style
+    };
+    (Cow::Owned(scaled), style)
+}
+
+// ============================================================================
+// Cursor Kinds
+// ============================================================================
+
+/// Sprite drawn while the cursor has some kind (see `CursorKindEvent`)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CursorKindSprite {
+    pub sprite_path: PathBuf,
+    /// Sprite pixel (unscaled) placed on the cursor position; (0, 0) uses
+    /// the hotspot stored in .cur/.ani sprites (top-left otherwise)
+    pub hotspot_x: f32,
+    pub hotspot_y: f32,
+}
+
+/// Which sprite shows when: 0 is the default sprite, `i + 1` the i-th of the
+/// kinds the schedule was built with
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SpriteSchedule {
+    /// (time, sprite index), sorted by time
+    switches: Vec<(f64, usize)>,
+}
+
+impl SpriteSchedule {
+    /// `events` must be sorted by time. Kinds missing from `kinds` switch
+    /// back to the default sprite.
+    pub fn new(events: &[CursorKindEvent], kinds: &[&str]) -> Self {
+        let switches = events
+            .iter()
+            .map(|event| {
+                let index = kinds
+                    .iter()
+                    .position(|kind| *kind == event.kind)
+                    .map_or(0, |i| i + 1);
+                (event.timestamp_ms, index)
+            })
+            .collect();
+        Self { switches }
+    }
+
+    /// Sprite showing at `timestamp_ms`; a change applies from its own
+    /// timestamp on
+    pub fn index_at(&self, timestamp_ms: f64) -> usize {
+        match self.switches.partition_point(|&(t, _)| t <= timestamp_ms) {
+            0 => 0,
+            n => self.switches[n - 1].1,
+        }
+    }
+}
+
+// ============================================================================
+// Drop Shadow
+// ============================================================================
+
+/// Blurred black silhouette of the sprite, drawn beneath it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorShadow {
+    /// Displacement from the cursor (frame px)
+    pub offset_x: f32,
+    pub offset_y: f32,
+    /// Gaussian blur radius (sprite px, about 3 sigma; 0 = hard edge)
+    pub blur_radius_px: f32,
+    /// Darkness where the sprite is opaque (0.0-1.0)
+    pub opacity: f32,
+}
+
+impl Default for CursorShadow {
+    fn default() -> Self {
+        Self {
+            offset_x: 2.0,
+            offset_y: 3.0,
+            blur_radius_px: 4.0,
+            opacity: 0.35,
+        }
+    }
+}
+
+impl CursorShadow {
+    /// Transparent margin the blur spreads into, on every side
+    fn padding(&self) -> u32 {
+        self.blur_radius_px.max(0.0).ceil() as u32
+    }
+
+    /// Blurred silhouette of `sprite` and of each of its animation frames.
+    ///
+    /// Build it once per render from the sprite as drawn (after `prescale`),
+    /// so the blur radius stays the same whatever the cursor scale.
+    pub fn silhouette(&self, sprite: &CursorSprite) -> CursorSprite {
+        if sprite.width == 0 || sprite.height == 0 {
+            return sprite.clone();
+        }
+        let pad = self.padding() as usize;
+        let (src_width, src_height) = (sprite.width as usize, sprite.height as usize);
+        let (width, height) = (src_width + 2 * pad, src_height + 2 * pad);
+        let mut alpha = vec![0.0f32; width * height];
+        for (y, row) in sprite
+            .data
+            .chunks_exact(src_width * 4)
+            .take(src_height)
+            .enumerate()
+        {
+            let dst = &mut alpha[(y + pad) * width + pad..][..src_width];
+            for (a, px) in dst.iter_mut().zip(row.chunks_exact(4)) {
+                *a = px[3] as f32;
+            }
+        }
+        if self.blur_radius_px > 0.0 {
+            gaussian_blur(&mut alpha, width, height, self.blur_radius_px);
+        }
+
+        CursorSprite {
+            data: alpha
+                .iter()
+                .flat_map(|&a| [0, 0, 0, (a + 0.5) as u8])
+                .collect(),
+            width: width as u32,
+            height: height as u32,
+            is_premultiplied: true,
+            hotspot_x: sprite.hotspot_x + pad as f32,
+            hotspot_y: sprite.hotspot_y + pad as f32,
+            animation: sprite
+                .animation
+                .iter()
+                .map(|frame| AnimationFrame {
+                    sprite: self.silhouette(&frame.sprite),
+                    duration_ms: frame.duration_ms,
+                })
+                .collect(),
+        }
+    }
+
+    /// Position and style that draw the `silhouette` under a cursor drawn at
+    /// (`x`, `y`) with `style`
+    pub fn placement(&self, x: f32, y: f32, style: &CursorStyle) -> (f32, f32, CursorStyle) {
+        // The silhouette's own hotspot is already shifted by the padding; an
+        // explicit style hotspot needs the same shift
+        let pad = self.padding() as f32;
+        let (hotspot_x, hotspot_y) = if style.hotspot_x == 0.0 && style.hotspot_y == 0.0 {
+            (0.0, 0.0)
+        } else {
+            (style.hotspot_x + pad, style.hotspot_y + pad)
+        };
+        let shadow_style = CursorStyle {
+            hotspot_x,
+            hotspot_y,
+            opacity: style.opacity * self.opacity,
+            ..*style
+        };
+        (x + self.offset_x, y + self.offset_y, shadow_style)
+    }
+}
+
+/// Separable Gaussian blur of a one-channel image, `radius` being about
+/// 3 sigma. Pixels past the edges count as 0.
+pub(crate) fn gaussian_blur(values: &mut [f32], width: usize, height: usize, radius: f32) {
+    let sigma = radius / 3.0;
+    let reach = radius.ceil() as usize;
+    let mut kernel: Vec<f32> = (0..=2 * reach)
+        .map(|i| {
+            let d = i as f32 - reach as f32;
+            (-d * d / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let total: f32 = kernel.iter().sum();
+    for w in &mut kernel {
+        *w /= total;
+    }
+
+    // Horizontal pass into `blurred`, vertical pass back into `values`
+    let mut blurred = vec![0.0f32; values.len()];
+    for y in 0..height {
+        let row = &values[y * width..(y + 1) * width];
+        for x in 0..width {
+            let first = x.saturating_sub(reach);
+            let last = (x + reach).min(width - 1);
+            blurred[y * width + x] = (first..=last)
+                .map(|sx| row[sx] * kernel[sx + reach - x])
+                .sum();
+        }
+    }
+    for y in 0..height {
+        let first = y.saturating_sub(reach);
+        let last = (y + reach).min(height - 1);
+        for x in 0..width {
+            values[y * width + x] = (first..=last)
+                .map(|sy| blurred[sy * width + x] * kernel[sy + reach - y])
+                .sum();
+        }
+    }
+}
+
+/// Scale each pixel's RGB by its alpha in place.
+///
+/// Filtering premultiplied texels keeps fully transparent neighbours (usually
+/// black RGB) from bleeding a dark fringe into anti-aliased edges.
+fn premultiply_alpha(data: &mut [u8]) {
+    for px in data.chunks_exact_mut(4) {
+        let a = px[3] as u16;
+        for c in &mut px[..3] {
+            *c = ((*c as u16 * a + 127) / 255) as u8;
+        }
+    }
+}
+
+pub fn load_cursor_sprite(path: &Path) -> Result<CursorSprite, image::ImageError> {
+    let bytes = std::fs::read(path)?;
+    if is_svg_path(path) {
+        return rasterize_svg(&bytes, 1.0);
+    }
+    if is_ani(&bytes) {
+        let frames = ani_frames(&bytes)?;
+        // ani_frames fails rather than return no frames
+        return Ok(CursorSprite::animated(frames).expect(".ani cursor has frames"));
+    }
+    if let Some(sprite) = decode_animation(path)?.and_then(CursorSprite::animated) {
+        return Ok(sprite);
+    }
+
+    let (img, hotspot) = decode_sprite_image(path, &bytes)?;
+    Ok(still_sprite(img, hotspot))
+}
+
+/// `load_cursor_sprite` for drawing with `style`, like `prescale`: SVG
+/// sprites are rasterized at `style.scale` instead of being resampled, and
+/// come back with the style to draw them at (scale 1.0, hotspot in
+/// rasterized pixels). Other formats come back with `style` unchanged.
+pub fn load_cursor_sprite_prescaled(
+    path: &Path,
+    style: &CursorStyle,
+) -> Result<(CursorSprite, CursorStyle), image::ImageError> {
+    if !is_svg_path(path) || style.scale == 1.0 || style.scale <= 0.0 {
+        return Ok((load_cursor_sprite(path)?, *style));
+    }
+    let bytes = std::fs::read(path)?;
+    let native = rasterize_svg(&bytes, 1.0)?;
+    let sprite = rasterize_svg(&bytes, style.scale)?;
+    let style = CursorStyle {
+        scale: 1.0,
+        hotspot_x: style.hotspot_x * sprite.width as f32 / native.width as f32,
+        hotspot_y: style.hotspot_y * sprite.height as f32 / native.height as f32,
+        ..*style
+    };
+    Ok((sprite, style))
+}
+
+/// SVG sprites go by extension (.svgz is gzip-compressed SVG)
+fn is_svg_path(path: &Path) -> bool {
+    matches!(
+        path.extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref(),
+        Some("svg" | "svgz")
+    )
+}
+
+/// Render an SVG document at `scale` times its own size
+#[cfg(feature = "svg")]
+fn rasterize_svg(bytes: &[u8], scale: f32) -> Result<CursorSprite, image::ImageError> {
+    use resvg::{tiny_skia, usvg};
+
+    let tree = usvg::Tree::from_data(bytes, &usvg::Options::default())
+        .map_err(|e| decode_error("SVG", e.to_string()))?;
+    let size = tree.size();
+    let width = ((size.width() * scale).round() as u32).max(1);
+    let height = ((size.height() * scale).round() as u32).max(1);
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| decode_error("SVG", format!("cannot rasterize at {}x{}", width, height)))?;
+    let transform = tiny_skia::Transform::from_scale(
+        width as f32 / size.width(),
+        height as f32 / size.height(),
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    // tiny-skia pixmaps are premultiplied RGBA8, like decoded sprites
+    Ok(CursorSprite::from_rgba8(pixmap.take(), width, height, true))
+}
+
+#[cfg(not(feature = "svg"))]
+fn rasterize_svg(_bytes: &[u8], _scale: f32) -> Result<CursorSprite, image::ImageError> {
+    Err(decode_error(
+        "SVG",
+        "SVG support not compiled in (build with the `svg` feature)",
+    ))
 }
 
-pub fn load_cursor_sprite(path: &str) -> Result<CursorSprite, Box<dyn Error>> {
-    let img = image::open(path)?;
+/// Straight-alpha image as a sprite with the given hotspot
+fn still_sprite(img: image::DynamicImage, (hotspot_x, hotspot_y): (f32, f32)) -> CursorSprite {
     let (width, height) = img.dimensions();
     // Pre-convert to raw RGBA bytes for O(1) access
     let data = img.to_rgba8().into_raw();
 
-    Ok(CursorSprite {
-        data,
-        width,
-        height,
+    CursorSprite {
+        hotspot_x,
+        hotspot_y,
+        ..CursorSprite::from_rgba8(data, width, height, false)
+    }
+}
+
+/// Split a horizontal strip of `frame_count` equally wide frames into an
+/// animation played at `fps`
+pub fn load_cursor_sprite_sheet(
+    path: &Path,
+    frame_count: u32,
+    fps: f32,
+) -> Result<CursorSprite, image::ImageError> {
+    let sheet = image::open(path)?.to_rgba8();
+    let (width, height) = sheet.dimensions();
+    if frame_count == 0 || width % frame_count != 0 {
+        return Err(image::ImageError::Parameter(
+            image::error::ParameterError::from_kind(
+                image::error::ParameterErrorKind::DimensionMismatch,
+            ),
+        ));
+    }
+
+    let frame_width = width / frame_count;
+    let duration_ms = ((1000.0 / fps).round() as u32).max(1);
+    let frames = (0..frame_count)
+        .map(|i| {
+            let frame = image::imageops::crop_imm(&sheet, i * frame_width, 0, frame_width, height);
+            AnimationFrame {
+                sprite: CursorSprite::from_rgba8(
+                    frame.to_image().into_raw(),
+                    frame_width,
+                    height,
+                    false,
+                ),
+                duration_ms,
+            }
+        })
+        .collect();
+    // frame_count > 0, so there is at least one frame
+    Ok(CursorSprite::animated(frames).expect("sprite sheet has frames"))
+}
+
+/// Every frame of an animated GIF or APNG, or `None` for other files and
+/// single-frame images
+fn decode_animation(path: &Path) -> Result<Option<Vec<AnimationFrame>>, image::ImageError> {
+    use image::codecs::{gif::GifDecoder, png::PngDecoder};
+    use image::AnimationDecoder;
+
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    let open = || -> std::io::Result<_> { Ok(std::io::BufReader::new(std::fs::File::open(path)?)) };
+    let frames = match extension.as_deref() {
+        Some("gif") => GifDecoder::new(open()?)?.into_frames().collect_frames()?,
+        Some("png" | "apng") => {
+            let decoder = PngDecoder::new(open()?)?;
+            if !decoder.is_apng()? {
+                return Ok(None);
+            }
+            decoder.apng()?.into_frames().collect_frames()?
+        }
+        _ => return Ok(None),
+    };
+    if frames.len() < 2 {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        frames
+            .into_iter()
+            .map(|frame| {
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                let duration_ms = match (numer as f64 / denom.max(1) as f64).round() as u32 {
+                    0 => DEFAULT_FRAME_MS,
+                    ms => ms,
+                };
+                // Frames come out composited onto the full canvas
+                let buffer = frame.into_buffer();
+                let (width, height) = buffer.dimensions();
+                AnimationFrame {
+                    sprite: CursorSprite::from_rgba8(buffer.into_raw(), width, height, false),
+                    duration_ms,
+                }
+            })
+            .collect(),
+    ))
+}
+
+/// Decode any format `image` supports, plus Windows .cur and (first frame
+/// only) .ani cursors, whose hotspot is returned alongside
+fn open_sprite_image(path: &Path) -> Result<(image::DynamicImage, (f32, f32)), image::ImageError> {
+    decode_sprite_image(path, &std::fs::read(path)?)
+}
+
+/// `open_sprite_image` on the already read contents of `path`. Cursor files
+/// are recognised by their header, whatever the extension.
+fn decode_sprite_image(
+    path: &Path,
+    bytes: &[u8],
+) -> Result<(image::DynamicImage, (f32, f32)), image::ImageError> {
+    if is_ani(bytes) {
+        let ani = parse_ani(bytes)?;
+        decode_cur(ani.icons[ani.steps[0].0])
+    } else if is_cur(bytes) {
+        decode_cur(bytes)
+    } else {
+        Ok((image::open(path)?, (0.0, 0.0)))
+    }
+}
+
+/// Error for a sprite file that cannot be decoded as `format`
+fn decode_error(format: &str, message: impl Into<String>) -> image::ImageError {
+    image::ImageError::Decoding(image::error::DecodingError::new(
+        image::error::ImageFormatHint::Name(format.to_string()),
+        message.into(),
+    ))
+}
+
+/// A .cur file: the .ico layout with resource type 2
+fn is_cur(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0, 0, 2, 0])
+}
+
+/// An animated .ani cursor (RIFF form type "ACON")
+fn is_ani(bytes: &[u8]) -> bool {
+    bytes.len() >= 12 && bytes[..4] == *b"RIFF" && bytes[8..12] == *b"ACON"
+}
+
+/// The largest cursor of a .cur file and its hotspot
+fn decode_cur(bytes: &[u8]) -> Result<(image::DynamicImage, (f32, f32)), image::ImageError> {
+    let (ico, hotspot) = cur_as_ico(bytes)?;
+    let img = image::load_from_memory_with_format(&ico, image::ImageFormat::Ico)?;
+    Ok((img, hotspot))
+}
+
+/// Rewrite a .cur file as a single-image .ico holding its largest cursor, and
+/// return that cursor's hotspot.
+///
+/// .cur shares the .ico layout but stores the hotspot where .ico keeps planes
+/// and bit depth, which `image` would misread when choosing an entry.
+fn cur_as_ico(bytes: &[u8]) -> Result<(Vec<u8>, (f32, f32)), image::ImageError> {
+    const DIR_LEN: usize = 6;
+    const ENTRY_LEN: usize = 16;
+    if bytes.len() < DIR_LEN {
+        return Err(decode_error("CUR", "truncated header"));
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+    let u32_at = |e: &[u8], i: usize| u32::from_le_bytes([e[i], e[i + 1], e[i + 2], e[i + 3]]);
+    let count = u16_at(4) as usize;
+    if count == 0 {
+        return Err(decode_error("CUR", "no images"));
+    }
+    let entries = bytes
+        .get(DIR_LEN..DIR_LEN + count * ENTRY_LEN)
+        .ok_or_else(|| {
+            decode_error("CUR", format!("directory of {} images is truncated", count))
+        })?;
+
+    // Width/height bytes of 0 mean 256
+    let side = |b: u8| if b == 0 { 256 } else { b as u32 };
+    let (index, entry) = entries
+        .chunks_exact(ENTRY_LEN)
+        .enumerate()
+        .max_by_key(|(_, e)| side(e[0]) * side(e[1]))
+        .expect("count > 0");
+    let (size, offset) = (u32_at(entry, 8) as usize, u32_at(entry, 12) as usize);
+    if offset.checked_add(size).is_none_or(|end| end > bytes.len()) {
+        return Err(decode_error(
+            "CUR",
+            format!("image {} lies past the end of the file", index),
+        ));
+    }
+    let hotspot_at = DIR_LEN + index * ENTRY_LEN + 4;
+    let hotspot = (u16_at(hotspot_at) as f32, u16_at(hotspot_at + 2) as f32);
+
+    // Image offsets are absolute, so only the directory has to change
+    let mut ico = bytes.to_vec();
+    ico[2] = 1; // Type: icon
+    ico[4..6].copy_from_slice(&1u16.to_le_bytes());
+    ico[DIR_LEN..DIR_LEN + ENTRY_LEN].copy_from_slice(entry);
+    ico[DIR_LEN + 4..DIR_LEN + 8].fill(0); // Planes and bit depth unspecified
+    Ok((ico, hotspot))
+}
+
+/// anih flag: frames are .cur/.ico files (not raw bitmaps)
+const ANI_FLAG_ICON: u32 = 1;
+/// ANI durations count jiffies of 1/60 s
+const ANI_JIFFY_MS: f64 = 1000.0 / 60.0;
+
+/// The embedded frames of an .ani cursor and the order they play in
+struct AniCursor<'a> {
+    /// One .cur/.ico file per frame
+    icons: Vec<&'a [u8]>,
+    /// (index into `icons`, duration in ms) per step; never empty
+    steps: Vec<(usize, u32)>,
+}
+
+/// Split an .ani cursor into its frames and their timing. The optional `seq `
+/// chunk reorders or repeats frames and `rate` gives each step its own
+/// duration; without them every frame shows once for the header's rate.
+fn parse_ani(bytes: &[u8]) -> Result<AniCursor<'_>, image::ImageError> {
+    let declared = u32::from_le_bytes(bytes[4..8].try_into().expect("4 bytes")) as usize;
+    let form = bytes
+        .get(12..(8 + declared).min(bytes.len()))
+        .unwrap_or_default();
+
+    let mut header = None;
+    let mut icons = Vec::new();
+    let mut rate = None;
+    let mut seq = None;
+    for (id, body) in riff_chunks(form)? {
+        match id {
+            b"anih" => header = Some(body),
+            b"rate" => rate = Some(body),
+            b"seq " => seq = Some(body),
+            b"LIST" if body.starts_with(b"fram") => {
+                for (id, icon) in riff_chunks(&body[4..])? {
+                    if id == b"icon" {
+                        icons.push(icon);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let header = header.ok_or_else(|| decode_error("ANI", "missing anih header"))?;
+    if header.len() < 36 {
+        return Err(decode_error("ANI", "truncated anih header"));
+    }
+    let header_u32 = |i: usize| u32::from_le_bytes(header[i..i + 4].try_into().expect("4 bytes"));
+    if header_u32(32) & ANI_FLAG_ICON == 0 {
+        return Err(decode_error("ANI", "raw bitmap frames are not supported"));
+    }
+    if icons.is_empty() {
+        return Err(decode_error("ANI", "no frames"));
+    }
+
+    // The header's step count only matters when seq/rate describe the steps
+    let step_count = match header_u32(8) as usize {
+        0 => icons.len(),
+        _ if seq.is_none() && rate.is_none() => icons.len(),
+        n => n,
+    };
+    let table = |chunk: Option<&[u8]>, name: &str| -> Result<Option<Vec<u32>>, image::ImageError> {
+        let Some(chunk) = chunk else {
+            return Ok(None);
+        };
+        if chunk.len() < step_count * 4 {
+            return Err(decode_error(
+                "ANI",
+                format!("{} chunk is shorter than {} steps", name, step_count),
+            ));
+        }
+        Ok(Some(
+            chunk
+                .chunks_exact(4)
+                .take(step_count)
+                .map(|v| u32::from_le_bytes(v.try_into().expect("4 bytes")))
+                .collect(),
+        ))
+    };
+    let seq = table(seq, "seq")?;
+    let rate = table(rate, "rate")?;
+
+    let jiffies_to_ms = |jiffies: u32| match (jiffies as f64 * ANI_JIFFY_MS).round() as u32 {
+        0 => DEFAULT_FRAME_MS,
+        ms => ms,
+    };
+    let steps = (0..step_count)
+        .map(|step| {
+            let frame = seq.as_ref().map_or(step, |seq| seq[step] as usize);
+            if frame >= icons.len() {
+                return Err(decode_error(
+                    "ANI",
+                    format!("step {} shows frame {} of {}", step, frame, icons.len()),
+                ));
+            }
+            let jiffies = rate.as_ref().map_or(header_u32(28), |rate| rate[step]);
+            Ok((frame, jiffies_to_ms(jiffies)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(AniCursor { icons, steps })
+}
+
+/// Every step of an .ani cursor as an animation frame with its own hotspot
+fn ani_frames(bytes: &[u8]) -> Result<Vec<AnimationFrame>, image::ImageError> {
+    let ani = parse_ani(bytes)?;
+    // Decode each frame once, however often the sequence repeats it
+    let mut decoded: Vec<Option<CursorSprite>> = vec![None; ani.icons.len()];
+    ani.steps
+        .iter()
+        .map(|&(frame, duration_ms)| {
+            let sprite = match &decoded[frame] {
+                Some(sprite) => sprite.clone(),
+                None => {
+                    let (img, hotspot) = decode_cur(ani.icons[frame])?;
+                    let sprite = still_sprite(img, hotspot);
+                    decoded[frame] = Some(sprite.clone());
+                    sprite
+                }
+            };
+            Ok(AnimationFrame {
+                sprite,
+                duration_ms,
+            })
+        })
+        .collect()
+}
+
+/// A RIFF chunk's id and data
+type RiffChunk<'a> = (&'a [u8; 4], &'a [u8]);
+
+/// RIFF chunks: 4-byte id, little-endian u32 length, data padded to even
+fn riff_chunks(mut data: &[u8]) -> Result<Vec<RiffChunk<'_>>, image::ImageError> {
+    let mut chunks = Vec::new();
+    while data.len() >= 8 {
+        let id: &[u8; 4] = data[..4].try_into().expect("4 bytes");
+        let len = u32::from_le_bytes(data[4..8].try_into().expect("4 bytes")) as usize;
+        let body = data.get(8..8 + len).ok_or_else(|| {
+            decode_error(
+                "ANI",
+                format!(
+                    "{} chunk runs past the end of the file",
+                    String::from_utf8_lossy(id)
+                ),
+            )
+        })?;
+        chunks.push((id, body));
+        data = data.get(8 + len + (len & 1)..).unwrap_or_default();
+    }
+    Ok(chunks)
+}
+
+// ============================================================================
+// Sprite Cache (for repeated compositing onto caller buffers)
+// ============================================================================
+
+/// Most sprites kept at once; a long-running host that composites many
+/// distinct cursor files evicts the least recently used beyond this
+pub const SPRITE_CACHE_CAPACITY: usize = 32;
+
+/// Size and modification time of a sprite file when it was decoded, so an
+/// edited file is reloaded instead of served stale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+struct CachedSprite {
+    stamp: FileStamp,
+    sprite: Arc<CursorSprite>,
+    /// Value of `SpriteCache::clock` at the last hit
+    used: u64,
+}
+
+#[derive(Default)]
+struct SpriteCache {
+    entries: HashMap<PathBuf, CachedSprite>,
+    clock: u64,
+}
+
+impl SpriteCache {
+    fn get(&mut self, path: &Path, stamp: FileStamp) -> Option<Arc<CursorSprite>> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(path)?;
+        if entry.stamp != stamp {
+            return None;
+        }
+        entry.used = self.clock;
+        Some(Arc::clone(&entry.sprite))
+    }
+
+    fn insert(&mut self, path: &Path, stamp: FileStamp, sprite: Arc<CursorSprite>) {
+        self.clock += 1;
+        if !self.entries.contains_key(path) && self.entries.len() >= SPRITE_CACHE_CAPACITY {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        let used = self.clock;
+        self.entries.insert(
+            path.to_path_buf(),
+            CachedSprite {
+                stamp,
+                sprite,
+                used,
+            },
+        );
+    }
+}
+
+static SPRITE_CACHE: OnceLock<Mutex<SpriteCache>> = OnceLock::new();
+
+fn sprite_cache() -> &'static Mutex<SpriteCache> {
+    SPRITE_CACHE.get_or_init(Default::default)
+}
+
+/// Load a sprite once per path and share it afterwards. The file is
+/// decoded again when its size or modification time changes.
+pub fn load_cursor_sprite_cached(path: &Path) -> Result<Arc<CursorSprite>, image::ImageError> {
+    // An unreadable file falls through to the load below for its error
+    let Some(stamp) = FileStamp::of(path) else {
+        return load_cursor_sprite(path).map(Arc::new);
+    };
+    if let Some(sprite) = sprite_cache()
+        .lock()
+        .ok()
+        .and_then(|mut c| c.get(path, stamp))
+    {
+        return Ok(sprite);
+    }
+
+    // Decode without holding the lock; a concurrent load of the same path
+    // just replaces an identical entry
+    let sprite = Arc::new(load_cursor_sprite(path)?);
+    if let Ok(mut cache) = sprite_cache().lock() {
+        cache.insert(path, stamp, Arc::clone(&sprite));
+    }
+    Ok(sprite)
+}
+
+/// Drop the cached sprite for `path`, or every cached sprite for `None`
+pub fn invalidate_sprite_cache(path: Option<&Path>) {
+    if let Ok(mut cache) = sprite_cache().lock() {
+        match path {
+            Some(path) => {
+                cache.entries.remove(path);
+            }
+            None => cache.entries.clear(),
+        }
+    }
+}
+
+/// Decode only the image header to check the sprite is loadable (cursor
+/// files are small, so .cur/.ani/.svg are decoded in full)
+pub fn probe_cursor_sprite(path: &Path) -> Result<(u32, u32), Box<dyn Error>> {
+    let cursor_file = matches!(
+        path.extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref(),
+        Some("cur" | "ani")
+    );
+    let (width, height) = if is_svg_path(path) {
+        let sprite = load_cursor_sprite(path)?;
+        (sprite.width, sprite.height)
+    } else if cursor_file {
+        open_sprite_image(path)?.0.dimensions()
+    } else {
+        image::ImageReader::open(path)?
+            .with_guessed_format()?
+            .into_dimensions()?
+    };
+
+    if width == 0 || height == 0 {
+        return Err("Cursor sprite has zero size".into());
+    }
+
+    Ok((width, height))
+}
+
+// ============================================================================
+// Linear-Light Blending
+// ============================================================================
+
+/// Index resolution of the linear -> sRGB table; 4096 steps keep every
+/// round trip within one 8-bit code of the exact transfer function
+const LINEAR_LUT_SIZE: usize = 4096;
+
+struct GammaLuts {
+    to_linear: [f32; 256],
+    to_srgb: [u8; LINEAR_LUT_SIZE],
+}
+
+static GAMMA_LUTS: OnceLock<GammaLuts> = OnceLock::new();
+
+/// sRGB transfer tables, built on first use
+fn gamma_luts() -> &'static GammaLuts {
+    GAMMA_LUTS.get_or_init(|| {
+        let mut to_linear = [0.0; 256];
+        for (i, v) in to_linear.iter_mut().enumerate() {
+            let c = i as f32 / 255.0;
+            *v = if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            };
+        }
+        let mut to_srgb = [0; LINEAR_LUT_SIZE];
+        for (i, v) in to_srgb.iter_mut().enumerate() {
+            let l = i as f32 / (LINEAR_LUT_SIZE - 1) as f32;
+            let c = if l <= 0.003_130_8 {
+                l * 12.92
+            } else {
+                1.055 * l.powf(1.0 / 2.4) - 0.055
+            };
+            *v = (c * 255.0 + 0.5) as u8;
+        }
+        GammaLuts { to_linear, to_srgb }
     })
 }
 
-/// Composite cursor onto RGBA frame buffer with sub-pixel accuracy
+/// Like `blend`, but mixes `bg` and the straight (unpremultiplied) sprite
+/// colour `fg` in linear light
+#[inline(always)]
+fn blend_linear(luts: &GammaLuts, bg: u8, fg: f32, alpha: f32) -> u8 {
+    // `as u8` saturates, so both indices stay in range without branches
+    let fg = luts.to_linear[(fg + 0.5) as u8 as usize];
+    let l = luts.to_linear[bg as usize] * (1.0 - alpha) + fg * alpha;
+    let i = (l * (LINEAR_LUT_SIZE - 1) as f32 + 0.5) as u16 as usize;
+    luts.to_srgb[i.min(LINEAR_LUT_SIZE - 1)]
+}
+
+/// Composite cursor onto RGBA frame buffer with sub-pixel accuracy.
+///
+/// Rows start `stride_bytes` apart, so padded frames (stride > width * 4) are
+/// drawn correctly and their padding is left untouched.
+///
+/// Returns true when part of the sprite fell outside the frame and was clipped.
+#[allow(clippy::too_many_arguments)]
 pub fn composite_cursor_subpixel(
     frame: &mut [u8],
     frame_width: u32,
-    _frame_height: u32, // Unused in optimized math
+    frame_height: u32,
+    stride_bytes: usize,
     cursor: &CursorSprite,
     x: f32,
     y: f32,
-) {
+    style: &CursorStyle,
+) -> bool {
+    // Non-finite positions mark a hidden cursor (e.g. outside the capture region)
+    if style.opacity <= 0.0 || style.scale <= 0.0 || !x.is_finite() || !y.is_finite() {
+        return false;
+    }
+    let row_bytes = frame_width as usize * 4;
+    if row_bytes == 0 || stride_bytes < row_bytes {
+        return false;
+    }
+    // Never trust frame_height past the end of the slice (the last row may be unpadded)
+    let available_rows = (frame.len() + stride_bytes - row_bytes) / stride_bytes;
+    let frame_rows = (frame_height as usize).min(available_rows) as i32;
+    let luts = style.gamma_correct_blend.then(gamma_luts);
+
+    // 0. Translate so the hotspot (not the top-left corner) sits on (x, y);
+    // a (0, 0) style hotspot defers to the sprite's own
+    let (hotspot_x, hotspot_y) = if style.hotspot_x == 0.0 && style.hotspot_y == 0.0 {
+        (cursor.hotspot_x, cursor.hotspot_y)
+    } else {
+        (style.hotspot_x, style.hotspot_y)
+    };
+    if style.rotation != 0.0 || style.sampling == SamplingQuality::Bicubic {
+        return composite_gathered(
+            frame,
+            frame_width,
+            frame_rows,
+            stride_bytes,
+            cursor,
+            (x, y),
+            (hotspot_x, hotspot_y),
+            style,
+            luts,
+        );
+    }
+    let scale = style.scale;
+    let inv_scale = 1.0 / scale;
+    let origin_x = x - hotspot_x * scale;
+    let origin_y = y - hotspot_y * scale;
+
     // 1. Determine the integer bounding box on the FRAME
-    let start_x = x.floor() as i32;
-    let start_y = y.floor() as i32;
-    let end_x = start_x + cursor.width as i32 + 1; // +1 for bilinear spill
-    let end_y = start_y + cursor.height as i32 + 1;
+    // (+1 for bilinear spill; saturating so far off-screen positions clip, not wrap)
+    let start_x = origin_x.floor() as i32;
+    let start_y = origin_y.floor() as i32;
+    let sprite_w = (cursor.width as f32 * scale).ceil() as i32;
+    let sprite_h = (cursor.height as f32 * scale).ceil() as i32;
+    let end_x = start_x.saturating_add(sprite_w + 1);
+    let end_y = start_y.saturating_add(sprite_h + 1);
 
     // 2. Clamp to frame boundaries
     let draw_start_x = start_x.max(0);
     let draw_start_y = start_y.max(0);
     let draw_end_x = end_x.min(frame_width as i32);
-    let draw_end_y = end_y.min((frame.len() / (frame_width as usize * 4)) as i32);
+    let draw_end_y = end_y.min(frame_rows);
+    let clipped = start_x < 0
+        || start_y < 0
+        || start_x.saturating_add(sprite_w) > frame_width as i32
+        || start_y.saturating_add(sprite_h) > frame_rows;
+
+    // 3. Destination columns whose sample lands inside the sprite
+    let max_src_x = cursor.width as f32 - 0.5;
+    let max_src_y = cursor.height as f32 - 0.5;
+    let (span_start, span_end) = sample_span(
+        draw_start_x,
+        draw_end_x,
+        origin_x,
+        scale,
+        inv_scale,
+        max_src_x,
+    );
+    if span_start >= span_end {
+        return clipped;
+    }
+
+    // 4. Horizontal sample positions, identical for every row
+    let last_tex_x = cursor.width as i32 - 1;
+    let columns: Vec<SampleColumn> = (span_start..span_end)
+        .map(|dx| {
+            let src_x = ((dx as f32) - origin_x) * inv_scale;
+            let x_floor = fast_floor(src_x);
+            let u = src_x - x_floor as f32; // Weight for Right
+            SampleColumn {
+                left: x_floor.clamp(0, last_tex_x) as usize * 4,
+                right: (x_floor + 1).clamp(0, last_tex_x) as usize * 4,
+                u,
+                inv_u: 1.0 - u, // Weight for Left
+            }
+        })
+        .collect();
 
-    // 3. Iterate DESTINATION pixels (Gather)
+    // 5. Iterate DESTINATION rows (Gather). Each blends two horizontally
+    // filtered sprite rows; consecutive rows usually share one, so it is kept.
+    let mut top = FilteredRow::new(columns.len());
+    let mut bottom = FilteredRow::new(columns.len());
     for dy in draw_start_y..draw_end_y {
-        for dx in draw_start_x..draw_end_x {
-            // Map pixel center back to cursor space
-            let src_x = (dx as f32) - x;
-            let src_y = (dy as f32) - y;
+        // Map pixel center back to (unscaled) cursor space
+        let src_y = ((dy as f32) - origin_y) * inv_scale;
+        if src_y < -0.5 || src_y >= max_src_y {
+            continue;
+        }
+        let y_floor = fast_floor(src_y);
+        let v = src_y - y_floor as f32; // Weight for Bottom
+        let inv_v = 1.0 - v; // Weight for Top
+        let last_tex_y = cursor.height as i32 - 1;
+        let top_y = y_floor.clamp(0, last_tex_y);
+        let bottom_y = (y_floor + 1).clamp(0, last_tex_y);
+        if top.tex_y != top_y {
+            if bottom.tex_y == top_y {
+                std::mem::swap(&mut top, &mut bottom);
+            } else {
+                top.fill(cursor, top_y, &columns);
+            }
+        }
+        if bottom.tex_y != bottom_y {
+            bottom.fill(cursor, bottom_y, &columns);
+        }
 
-            // Bilinear Sample
-            if let Some((r, g, b, a)) = sample_bilinear_fast(cursor, src_x, src_y) {
-                let alpha = a as f32 / 255.0;
-                if alpha > 0.0 {
-                    let idx = ((dy as u32 * frame_width + dx as u32) * 4) as usize;
-                    // Standard Over Operator
-                    frame[idx] = blend(frame[idx], r, alpha);
-                    frame[idx + 1] = blend(frame[idx + 1], g, alpha);
-                    frame[idx + 2] = blend(frame[idx + 2], b, alpha);
-                    // Assume frame alpha stays 255 (opaque video)
-                }
+        let row_start = dy as usize * stride_bytes;
+        let row =
+            &mut frame[row_start + span_start as usize * 4..row_start + span_end as usize * 4];
+        let samples = top.texels.iter().zip(&bottom.texels);
+        for (px, (t, b)) in row.chunks_exact_mut(4).zip(samples) {
+            // Bilinear Sample (premultiplied), all four channels at once
+            let mut c = [0.0f32; 4];
+            for i in 0..4 {
+                c[i] = t[i] * inv_v + b[i] * v;
             }
+            blend_pixel(px, c, style.opacity, luts);
         }
     }
+
+    clipped
 }
 
+/// Blend one premultiplied sample over an RGBA frame pixel
 #[inline(always)]
-fn blend(bg: u8, fg: u8, alpha: f32) -> u8 {
-    ((bg as f32 * (1.0 - alpha)) + (fg as f32 * alpha)) as u8
+fn blend_pixel(px: &mut [u8], c: [f32; 4], opacity: f32, luts: Option<&GammaLuts>) {
+    let alpha = c[3] / 255.0 * opacity;
+    if alpha <= 0.0 {
+        return;
+    }
+    // Opaque texels replace the background in either space
+    if let Some(luts) = luts.filter(|_| alpha < 1.0) {
+        // sRGB bytes can't be blended linearly while premultiplied
+        let unmul = 255.0 / c[3];
+        for i in 0..3 {
+            px[i] = blend_linear(luts, px[i], c[i] * unmul, alpha);
+        }
+    } else {
+        // Premultiplied Over Operator
+        for i in 0..3 {
+            px[i] = blend(px[i], c[i] * opacity, alpha);
+        }
+    }
+    // Over for coverage too: opaque video stays 255, and a transparent frame
+    // (`overlay_only`) ends up premultiplied, like the colours above
+    px[3] = blend(px[3], 255.0 * alpha, alpha);
 }
 
-#[inline]
-#[allow(dead_code)]
-fn _blend_channel(bg: u8, fg: u8, alpha: f32) -> u8 {
-    ((bg as f32 * (1.0 - alpha)) + (fg as f32 * alpha)).clamp(0.0, 255.0) as u8
-}
+/// `composite_cursor_subpixel` for a sprite turned by `style.rotation` or
+/// sampled bicubically.
+///
+/// Rows can no longer share filtered sprite rows, so every destination pixel
+/// in the rotated bounding box is mapped back through the inverse rotation
+/// about the hotspot and sampled on its own.
+#[allow(clippy::too_many_arguments)]
+fn composite_gathered(
+    frame: &mut [u8],
+    frame_width: u32,
+    frame_rows: i32,
+    stride_bytes: usize,
+    cursor: &CursorSprite,
+    (x, y): (f32, f32),
+    (hotspot_x, hotspot_y): (f32, f32),
+    style: &CursorStyle,
+    luts: Option<&GammaLuts>,
+) -> bool {
+    let (sin, cos) = style.rotation.sin_cos();
+    let scale = style.scale;
+    let inv_scale = 1.0 / scale;
 
-#[inline(always)]
-fn sample_bilinear_fast(cursor: &CursorSprite, x: f32, y: f32) -> Option<(u8, u8, u8, u8)> {
-    // 1. Bounds Check (Strict)
-    // We allow -0.5 to allow sampling the center of the edge pixels,
-    // but we must not access data outside valid indices.
-    if x < -0.5 || y < -0.5 || x >= (cursor.width as f32 - 0.5) || y >= (cursor.height as f32 - 0.5)
-    {
-        return None;
+    // 1. Rotated extents of the scaled sprite around the hotspot
+    let left = -hotspot_x * scale;
+    let top = -hotspot_y * scale;
+    let right = (cursor.width as f32 - hotspot_x) * scale;
+    let bottom = (cursor.height as f32 - hotspot_y) * scale;
+    let (mut min_x, mut min_y) = (f32::MAX, f32::MAX);
+    let (mut max_x, mut max_y) = (f32::MIN, f32::MIN);
+    for (cx, cy) in [(left, top), (right, top), (left, bottom), (right, bottom)] {
+        let rx = cx * cos - cy * sin;
+        let ry = cx * sin + cy * cos;
+        min_x = min_x.min(rx);
+        max_x = max_x.max(rx);
+        min_y = min_y.min(ry);
+        max_y = max_y.max(ry);
     }
 
-    // 2. Identify neighbors (Top-Left coordinate)
-    let x_floor = x.floor();
-    let y_floor = y.floor();
-
-    // Indices for Top-Left (tl)
-    let tl_x = x_floor as i32;
-    let tl_y = y_floor as i32;
-
-    // Weights (fractional part)
-    let u = x - x_floor; // Weight for Right
-    let v = y - y_floor; // Weight for Bottom
-    let inv_u = 1.0 - u; // Weight for Left
-    let inv_v = 1.0 - v; // Weight for Top
-
-    // Helper to safely get a pixel (handling edges where x+1 might be OOB)
-    // In valid range due to strict bounds check above, but safe clamping is good practice
-    let get_pixel = |cx: i32, cy: i32| -> (f32, f32, f32, f32) {
-        let cx = cx.clamp(0, cursor.width as i32 - 1) as usize;
-        let cy = cy.clamp(0, cursor.height as i32 - 1) as usize;
-        let idx = (cy * cursor.width as usize + cx) * 4;
-        (
-            cursor.data[idx] as f32,     // R
-            cursor.data[idx + 1] as f32, // G
-            cursor.data[idx + 2] as f32, // B
-            cursor.data[idx + 3] as f32, // A
-        )
-    };
+    // 2. Integer bounding box (+1 for bilinear spill), clamped to the frame
+    let start_x = (x + min_x).floor() as i32;
+    let start_y = (y + min_y).floor() as i32;
+    let last_x = (x + max_x).ceil() as i32;
+    let last_y = (y + max_y).ceil() as i32;
+    let clipped = start_x < 0 || start_y < 0 || last_x > frame_width as i32 || last_y > frame_rows;
+    let draw_start_x = start_x.max(0);
+    let draw_start_y = start_y.max(0);
+    let draw_end_x = last_x.saturating_add(1).min(frame_width as i32);
+    let draw_end_y = last_y.saturating_add(1).min(frame_rows);
 
-    // 3. Fetch 4 Neighbors
-    let tl = get_pixel(tl_x, tl_y);
-    let tr = get_pixel(tl_x + 1, tl_y);
-    let bl = get_pixel(tl_x, tl_y + 1);
-    let br = get_pixel(tl_x + 1, tl_y + 1);
-
-    // 4. Interpolate Each Channel
-    // Formula: P = TL*(1-u)(1-v) + TR*u(1-v) + BL*(1-u)v + BR*uv
-    let interp = |c_tl, c_tr, c_bl, c_br| -> u8 {
-        let top = c_tl * inv_u + c_tr * u;
-        let bot = c_bl * inv_u + c_br * u;
-        let val = top * inv_v + bot * v;
-        val as u8
-    };
+    // 3. Gather: un-rotate each destination pixel into (unscaled) cursor space
+    for dy in draw_start_y..draw_end_y {
+        let ry = dy as f32 - y;
+        let row_start = dy as usize * stride_bytes;
+        for dx in draw_start_x..draw_end_x {
+            let rx = dx as f32 - x;
+            let src_x = (rx * cos + ry * sin) * inv_scale + hotspot_x;
+            let src_y = (ry * cos - rx * sin) * inv_scale + hotspot_y;
+            let sample = match style.sampling {
+                SamplingQuality::Bilinear => sample_bilinear(cursor, src_x, src_y),
+                SamplingQuality::Bicubic => sample_bicubic(cursor, src_x, src_y),
+            };
+            if let Some(c) = sample {
+                let idx = row_start + dx as usize * 4;
+                blend_pixel(&mut frame[idx..idx + 4], c, style.opacity, luts);
+            }
+        }
+    }
 
-    Some((
-        interp(tl.0, tr.0, bl.0, br.0),
-        interp(tl.1, tr.1, bl.1, br.1),
-        interp(tl.2, tr.2, bl.2, br.2),
-        interp(tl.3, tr.3, bl.3, br.3),
-    ))
+    clipped
+}
+
+/// Premultiplied bilinear sample at a sprite position, `None` outside the
+/// `[-0.5, size - 0.5)` footprint the row-sliced path also draws
+#[inline(always)]
+fn sample_bilinear(cursor: &CursorSprite, x: f32, y: f32) -> Option<[f32; 4]> {
+    if x < -0.5 || y < -0.5 || x >= cursor.width as f32 - 0.5 || y >= cursor.height as f32 - 0.5 {
+        return None;
+    }
+    let x_floor = fast_floor(x);
+    let y_floor = fast_floor(y);
+    let u = x - x_floor as f32;
+    let v = y - y_floor as f32;
+    let last_tex_x = cursor.width as i32 - 1;
+    let last_tex_y = cursor.height as i32 - 1;
+    let row_bytes = cursor.width as usize * 4;
+    let row = |tex_y: i32| {
+        let start = tex_y.clamp(0, last_tex_y) as usize * row_bytes;
+        &cursor.data[start..start + row_bytes]
+    };
+    let (top, bottom) = (row(y_floor), row(y_floor + 1));
+    let left = x_floor.clamp(0, last_tex_x) as usize * 4;
+    let right = (x_floor + 1).clamp(0, last_tex_x) as usize * 4;
+    let premultiplied = cursor.is_premultiplied;
+    let (tl, tr) = (
+        texel(top, left, premultiplied),
+        texel(top, right, premultiplied),
+    );
+    let (bl, br) = (
+        texel(bottom, left, premultiplied),
+        texel(bottom, right, premultiplied),
+    );
+
+    let mut c = [0.0f32; 4];
+    for i in 0..4 {
+        let t = tl[i] * (1.0 - u) + tr[i] * u;
+        let b = bl[i] * (1.0 - u) + br[i] * u;
+        c[i] = t * (1.0 - v) + b * v;
+    }
+    Some(c)
+}
+
+/// Premultiplied Catmull-Rom sample over the 4x4 texels around a sprite
+/// position (edge texels repeat), on the same footprint as `sample_bilinear`
+#[inline(always)]
+fn sample_bicubic(cursor: &CursorSprite, x: f32, y: f32) -> Option<[f32; 4]> {
+    if x < -0.5 || y < -0.5 || x >= cursor.width as f32 - 0.5 || y >= cursor.height as f32 - 0.5 {
+        return None;
+    }
+    let x_floor = fast_floor(x);
+    let y_floor = fast_floor(y);
+    let weights_x = catmull_rom_weights(x - x_floor as f32);
+    let weights_y = catmull_rom_weights(y - y_floor as f32);
+    let last_tex_x = cursor.width as i32 - 1;
+    let last_tex_y = cursor.height as i32 - 1;
+    let row_bytes = cursor.width as usize * 4;
+    let columns: [usize; 4] =
+        std::array::from_fn(|i| (x_floor + i as i32 - 1).clamp(0, last_tex_x) as usize * 4);
+    let premultiplied = cursor.is_premultiplied;
+
+    let mut c = [0.0f32; 4];
+    for (j, wy) in weights_y.into_iter().enumerate() {
+        let start = (y_floor + j as i32 - 1).clamp(0, last_tex_y) as usize * row_bytes;
+        let row = &cursor.data[start..start + row_bytes];
+        for (&offset, wx) in columns.iter().zip(weights_x) {
+            let t = texel(row, offset, premultiplied);
+            let w = wx * wy;
+            for i in 0..4 {
+                c[i] += t[i] * w;
+            }
+        }
+    }
+    // The negative lobes overshoot next to hard edges; keep every channel a
+    // byte and colour within alpha so the premultiplied blend can't wrap
+    c[3] = c[3].clamp(0.0, 255.0);
+    for i in 0..3 {
+        c[i] = c[i].clamp(0.0, c[3]);
+    }
+    Some(c)
+}
+
+/// Catmull-Rom weights of the texels 1 before to 2 after a sample `t` (0-1)
+/// past a texel. They sum to 1 and are (0, 1, 0, 0) at t = 0, so whole-texel
+/// positions reproduce the sprite exactly.
+#[inline(always)]
+fn catmull_rom_weights(t: f32) -> [f32; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
+
+/// Destination columns in `[draw_start, draw_end)` whose sample x falls in
+/// `[-0.5, max_src_x)`, found exactly rather than tested per pixel
+fn sample_span(
+    draw_start: i32,
+    draw_end: i32,
+    origin_x: f32,
+    scale: f32,
+    inv_scale: f32,
+    max_src_x: f32,
+) -> (i32, i32) {
+    if draw_start >= draw_end {
+        return (draw_start, draw_start);
+    }
+    let src = |dx: i32| ((dx as f32) - origin_x) * inv_scale;
+
+    // Start from the analytic edges, then step until they agree with the
+    // per-pixel test (rounding can put them one column off)
+    let mut start = ((origin_x - 0.5 * scale).ceil() as i32).clamp(draw_start, draw_end);
+    while start > draw_start && src(start - 1) >= -0.5 {
+        start -= 1;
+    }
+    while start < draw_end && src(start) < -0.5 {
+        start += 1;
+    }
+    let mut end = ((origin_x + max_src_x * scale).ceil() as i32).clamp(start, draw_end);
+    while end > start && src(end - 1) >= max_src_x {
+        end -= 1;
+    }
+    while end < draw_end && src(end) < max_src_x {
+        end += 1;
+    }
+    (start, end)
+}
+
+/// Sprite texels and weights feeding one destination column
+struct SampleColumn {
+    left: usize,
+    right: usize,
+    u: f32,
+    inv_u: f32,
+}
+
+/// One sprite row filtered horizontally at every `SampleColumn`
+struct FilteredRow {
+    /// Sprite row held in `texels` (-1 = none yet)
+    tex_y: i32,
+    texels: Vec<[f32; 4]>,
+}
+
+impl FilteredRow {
+    fn new(len: usize) -> Self {
+        Self {
+            tex_y: -1,
+            texels: vec![[0.0; 4]; len],
+        }
+    }
+
+    fn fill(&mut self, cursor: &CursorSprite, tex_y: i32, columns: &[SampleColumn]) {
+        let row_bytes = cursor.width as usize * 4;
+        let start = tex_y as usize * row_bytes;
+        let row = &cursor.data[start..start + row_bytes];
+        for (out, col) in self.texels.iter_mut().zip(columns) {
+            let l = texel(row, col.left, cursor.is_premultiplied);
+            let r = texel(row, col.right, cursor.is_premultiplied);
+            for i in 0..4 {
+                out[i] = l[i] * col.inv_u + r[i] * col.u;
+            }
+        }
+        self.tex_y = tex_y;
+    }
+}
+
+/// `floor` for values well inside the i32 range. `f32::floor` is a libm call
+/// on baseline x86-64, which dominated the inner loop.
+#[inline(always)]
+fn fast_floor(v: f32) -> i32 {
+    let t = v as i32;
+    if (t as f32) > v {
+        t - 1
+    } else {
+        t
+    }
+}
+
+/// RGBA at byte offset `i` of a sprite row, premultiplied if it isn't yet
+#[inline(always)]
+fn texel(row: &[u8], i: usize, premultiplied: bool) -> [f32; 4] {
+    let px = &row[i..i + 4];
+    let a = px[3] as f32;
+    // Straight sprites are premultiplied here so every texel filters the same way
+    let k = if premultiplied { 1.0 } else { a / 255.0 };
+    [px[0] as f32 * k, px[1] as f32 * k, px[2] as f32 * k, a]
+}
+
+/// `fg` is already scaled by `alpha`
+#[inline(always)]
+fn blend(bg: u8, fg: f32, alpha: f32) -> u8 {
+    (bg as f32 * (1.0 - alpha) + fg + 0.5).min(255.0) as u8
+}
+
+#[inline]
+#[allow(dead_code)]
+fn _blend_channel(bg: u8, fg: u8, alpha: f32) -> u8 {
+    ((bg as f32 * (1.0 - alpha)) + (fg as f32 * alpha)).clamp(0.0, 255.0) as u8
+}
+
+// ============================================================================
+// YUV Compositing
+// ============================================================================
+
+/// BT.601 limited-range RGB -> YUV rows (Y, U, V), what swscale uses for
+/// RGBA -> YUV420P unless told otherwise
+const BT601_RGB_TO_YUV: [[f32; 3]; 3] = [
+    [0.256_788, 0.504_129, 0.097_906],
+    [-0.148_223, -0.290_993, 0.439_216],
+    [0.439_216, -0.367_788, -0.071_427],
+];
+const BT601_OFFSETS: [f32; 3] = [16.0, 128.0, 128.0];
+
+/// A still sprite converted for `composite_cursor_yuv420`: per pixel,
+/// alpha-premultiplied BT.601 Y, U and V (0-255) plus alpha (0-1), at full
+/// resolution so chroma can be subsampled wherever the sprite lands
+pub struct YuvSprite {
+    pixels: Vec<[f32; 4]>,
+    pub width: u32,
+    pub height: u32,
+    pub hotspot_x: f32,
+    pub hotspot_y: f32,
+}
+
+impl YuvSprite {
+    /// Convert `sprite` (its first frame if animated; see `frames`)
+    pub fn new(sprite: &CursorSprite) -> Self {
+        let pixels = sprite
+            .data
+            .chunks_exact(4)
+            .map(|px| {
+                let alpha = px[3] as f32 / 255.0;
+                let rgb = if sprite.is_premultiplied {
+                    [px[0] as f32, px[1] as f32, px[2] as f32]
+                } else {
+                    [
+                        px[0] as f32 * alpha,
+                        px[1] as f32 * alpha,
+                        px[2] as f32 * alpha,
+                    ]
+                };
+                // The conversion is affine, so premultiplying scales the offset too
+                let [y, u, v] = std::array::from_fn(|i| {
+                    let [kr, kg, kb] = BT601_RGB_TO_YUV[i];
+                    BT601_OFFSETS[i] * alpha + kr * rgb[0] + kg * rgb[1] + kb * rgb[2]
+                });
+                [y, u, v, alpha]
+            })
+            .collect();
+        Self {
+            pixels,
+            width: sprite.width,
+            height: sprite.height,
+            hotspot_x: sprite.hotspot_x,
+            hotspot_y: sprite.hotspot_y,
+        }
+    }
+
+    /// Every animation frame converted, indexed like `CursorSprite::frame_index`
+    pub fn frames(sprite: &CursorSprite) -> Vec<YuvSprite> {
+        if sprite.animation.is_empty() {
+            vec![Self::new(sprite)]
+        } else {
+            sprite
+                .animation
+                .iter()
+                .map(|frame| Self::new(&frame.sprite))
+                .collect()
+        }
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Option<[f32; 4]> {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return None;
+        }
+        Some(self.pixels[y as usize * self.width as usize + x as usize])
+    }
+}
+
+/// A sample of a `Yuv420Frame` plane: 8-bit, or up to 16 bits held in the
+/// low bits of a `u16` (YUV420P10LE and the like)
+pub trait YuvSample: Copy {
+    fn to_f32(self) -> f32;
+    /// `value` is already rounded and within the frame's bit depth
+    fn from_f32(value: f32) -> Self;
+}
+
+impl YuvSample for u8 {
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value as u8
+    }
+}
+
+impl YuvSample for u16 {
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value as u16
+    }
+}
+
+/// The planes of a 4:2:0 frame: full-size luma, then U and V at half the
+/// width and height (rounded up). Strides count samples, not bytes.
+pub struct Yuv420Frame<'a, S: YuvSample = u8> {
+    pub planes: [&'a mut [S]; 3],
+    pub strides: [usize; 3],
+    pub width: u32,
+    pub height: u32,
+    /// Bits used of each sample (8 for `u8` planes, e.g. 10 for YUV420P10)
+    pub bit_depth: u32,
+}
+
+/// Blend `sprite` straight onto a 4:2:0 frame with its hotspot on (`x`,
+/// `y`) rounded to whole pixels. Each chroma sample takes the average of
+/// the sprite's premultiplied chroma and alpha over its 2x2 luma block.
+/// Frames deeper than 8 bits get the sprite widened to their depth and keep
+/// their own precision where it is translucent.
+///
+/// Uses the hotspot and opacity of `style`; the sprite must already be
+/// scaled (see `prescale`) and cannot be rotated. Returns true when part of
+/// the sprite fell outside the frame, like `composite_cursor_subpixel`.
+pub fn composite_cursor_yuv420<S: YuvSample>(
+    frame: &mut Yuv420Frame<'_, S>,
+    sprite: &YuvSprite,
+    x: f32,
+    y: f32,
+    style: &CursorStyle,
+) -> bool {
+    if style.opacity <= 0.0 || !x.is_finite() || !y.is_finite() {
+        return false;
+    }
+    let opacity = style.opacity.min(1.0);
+    let (hotspot_x, hotspot_y) = if style.hotspot_x == 0.0 && style.hotspot_y == 0.0 {
+        (sprite.hotspot_x, sprite.hotspot_y)
+    } else {
+        (style.hotspot_x, style.hotspot_y)
+    };
+    let origin_x = (x - hotspot_x).round() as i64;
+    let origin_y = (y - hotspot_y).round() as i64;
+    let (width, height) = (frame.width as i64, frame.height as i64);
+    let (sprite_w, sprite_h) = (sprite.width as i64, sprite.height as i64);
+    // The sprite is on the 8-bit scale, which maps onto deeper ones by shifting
+    let bit_depth = frame.bit_depth.clamp(8, 16);
+    let widen = (1u32 << (bit_depth - 8)) as f32;
+    let peak = ((1u32 << bit_depth) - 1) as f32;
+
+    // Luma, one sample per pixel
+    for sy in 0..sprite_h {
+        let fy = origin_y + sy;
+        if fy < 0 || fy >= height {
+            continue;
+        }
+        for sx in 0..sprite_w {
+            let fx = origin_x + sx;
+            if fx < 0 || fx >= width {
+                continue;
+            }
+            let [luma, _, _, alpha] = sprite.pixels[(sy * sprite_w + sx) as usize];
+            if alpha <= 0.0 {
+                continue;
+            }
+            let i = fy as usize * frame.strides[0] + fx as usize;
+            if let Some(px) = frame.planes[0].get_mut(i) {
+                *px = blend_plane(*px, luma * widen * opacity, alpha * opacity, peak);
+            }
+        }
+    }
+
+    // Chroma, one sample per 2x2 block of luma pixels
+    let (chroma_w, chroma_h) = ((width + 1) / 2, (height + 1) / 2);
+    let first_x = origin_x.div_euclid(2).max(0);
+    let last_x = (origin_x + sprite_w - 1).div_euclid(2).min(chroma_w - 1);
+    let first_y = origin_y.div_euclid(2).max(0);
+    let last_y = (origin_y + sprite_h - 1).div_euclid(2).min(chroma_h - 1);
+    for cy in first_y..=last_y {
+        for cx in first_x..=last_x {
+            // Odd-sized frames end in blocks that are partly outside
+            let mut sum = [0.0f32; 4];
+            let mut count = 0.0;
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let (fx, fy) = (2 * cx + dx, 2 * cy + dy);
+                if fx >= width || fy >= height {
+                    continue;
+                }
+                count += 1.0;
+                if let Some(texel) = sprite.texel(fx - origin_x, fy - origin_y) {
+                    for (s, t) in sum.iter_mut().zip(texel) {
+                        *s += t;
+                    }
+                }
+            }
+            let weight = opacity / count;
+            let alpha = sum[3] * weight;
+            if alpha <= 0.0 {
+                continue;
+            }
+            for (plane, total) in [(1, sum[1]), (2, sum[2])] {
+                let i = cy as usize * frame.strides[plane] + cx as usize;
+                if let Some(px) = frame.planes[plane].get_mut(i) {
+                    *px = blend_plane(*px, total * widen * weight, alpha, peak);
+                }
+            }
+        }
+    }
+
+    origin_x < 0 || origin_y < 0 || origin_x + sprite_w > width || origin_y + sprite_h > height
+}
+
+/// Premultiplied "over" for one plane sample, `peak` being its largest value
+fn blend_plane<S: YuvSample>(bg: S, fg: f32, alpha: f32, peak: f32) -> S {
+    S::from_f32((fg + bg.to_f32() * (1.0 - alpha)).round().clamp(0.0, peak))
+}
+
+// ============================================================================
+// Click Ripples
+// ============================================================================
+
+/// How a ripple's radius grows over its lifetime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RippleCurve {
+    Linear,
+    /// Fast at the click, settling into the end radius (cubic ease-out)
+    #[default]
+    EaseOut,
+}
+
+impl RippleCurve {
+    /// Expansion (0-1) at `progress` (0-1) through the ripple
+    fn apply(self, progress: f32) -> f32 {
+        match self {
+            RippleCurve::Linear => progress,
+            RippleCurve::EaseOut => 1.0 - (1.0 - progress).powi(3),
+        }
+    }
+}
+
+/// Expanding ring drawn under the cursor after each click, fading out as it grows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClickEffect {
+    /// Lifetime of one ripple (ms)
+    pub duration_ms: f32,
+    /// Radius (px, to the middle of the ring) at the click and at the end
+    pub start_radius_px: f32,
+    pub end_radius_px: f32,
+    /// Ring thickness (px)
+    pub ring_width_px: f32,
+    pub curve: RippleCurve,
+    /// RGB of left-click rings
+    pub color: [u8; 3],
+    /// RGB of right/middle-click rings
+    pub alt_color: [u8; 3],
+    /// Opacity at the click, falling linearly to 0 at the end (0.0-1.0)
+    pub opacity: f32,
+}
+
+impl Default for ClickEffect {
+    fn default() -> Self {
+        Self {
+            duration_ms: 400.0,
+            start_radius_px: 6.0,
+            end_radius_px: 36.0,
+            ring_width_px: 4.0,
+            curve: RippleCurve::EaseOut,
+            color: [255, 200, 61],
+            alt_color: [61, 155, 255],
+            opacity: 0.6,
+        }
+    }
+}
+
+/// Draw the ring of every click still rippling at `timestamp_ms`.
+///
+/// `clicks` must be sorted by time; overlapping rings are drawn oldest first,
+/// so a newer ring lies over an older one. Rows start `stride_bytes` apart, as
+/// in `composite_cursor_subpixel`.
+#[allow(clippy::too_many_arguments)]
+pub fn composite_click_ripples(
+    frame: &mut [u8],
+    frame_width: u32,
+    frame_height: u32,
+    stride_bytes: usize,
+    clicks: &[CClickEvent],
+    timestamp_ms: f64,
+    effect: &ClickEffect,
+    gamma_correct_blend: bool,
+) {
+    let duration_ms = effect.duration_ms as f64;
+    let row_bytes = frame_width as usize * 4;
+    if effect.opacity <= 0.0 || duration_ms <= 0.0 || row_bytes == 0 || stride_bytes < row_bytes {
+        return;
+    }
+    let available_rows = (frame.len() + stride_bytes - row_bytes) / stride_bytes;
+    let frame_rows = (frame_height as usize).min(available_rows) as i32;
+    let luts = gamma_correct_blend.then(gamma_luts);
+
+    // Clicks in (timestamp - duration, timestamp] are still rippling
+    let first = clicks.partition_point(|c| c.timestamp_ms <= timestamp_ms - duration_ms);
+    let last = clicks.partition_point(|c| c.timestamp_ms <= timestamp_ms);
+    for click in clicks.get(first..last).unwrap_or(&[]) {
+        if !click.x.is_finite() || !click.y.is_finite() {
+            continue;
+        }
+        let progress = ((timestamp_ms - click.timestamp_ms) / duration_ms) as f32;
+        let radius = effect.start_radius_px
+            + (effect.end_radius_px - effect.start_radius_px) * effect.curve.apply(progress);
+        let color = if click.button == BUTTON_LEFT {
+            effect.color
+        } else {
+            effect.alt_color
+        };
+        draw_ring(
+            frame,
+            frame_width as i32,
+            frame_rows,
+            stride_bytes,
+            (click.x, click.y),
+            radius,
+            effect.ring_width_px,
+            color,
+            effect.opacity * (1.0 - progress),
+            luts,
+        );
+    }
+}
+
+/// Anti-aliased ring of `width` px centred on `radius`, clipped to the frame
+#[allow(clippy::too_many_arguments)]
+fn draw_ring(
+    frame: &mut [u8],
+    frame_width: i32,
+    frame_rows: i32,
+    stride_bytes: usize,
+    (cx, cy): (f32, f32),
+    radius: f32,
+    width: f32,
+    color: [u8; 3],
+    opacity: f32,
+    luts: Option<&GammaLuts>,
+) {
+    let half_width = width * 0.5;
+    // Coverage reaches 0 half a pixel past either edge
+    let reach = radius.max(0.0) + half_width + 0.5;
+    let draw_start_x = ((cx - reach).floor() as i32).max(0);
+    let draw_start_y = ((cy - reach).floor() as i32).max(0);
+    let draw_end_x = ((cx + reach).ceil() as i32)
+        .saturating_add(1)
+        .min(frame_width);
+    let draw_end_y = ((cy + reach).ceil() as i32)
+        .saturating_add(1)
+        .min(frame_rows);
+
+    for dy in draw_start_y..draw_end_y {
+        let ry = dy as f32 - cy;
+        let row_start = dy as usize * stride_bytes;
+        for dx in draw_start_x..draw_end_x {
+            let rx = dx as f32 - cx;
+            let distance = (rx * rx + ry * ry).sqrt();
+            // A ring thinner than a pixel never covers more than its width
+            let coverage = (half_width + 0.5 - (distance - radius).abs())
+                .min(width)
+                .clamp(0.0, 1.0);
+            if coverage <= 0.0 {
+                continue;
+            }
+            // Premultiplied, in the units `blend_pixel` expects
+            let c = [
+                color[0] as f32 * coverage,
+                color[1] as f32 * coverage,
+                color[2] as f32 * coverage,
+                255.0 * coverage,
+            ];
+            let idx = row_start + dx as usize * 4;
+            blend_pixel(&mut frame[idx..idx + 4], c, opacity, luts);
+        }
+    }
+}
+
+/// Click ripples as an overlay layer (see `composite_click_ripples`)
+pub struct ClickRipples<'a> {
+    /// Sorted by time, on the video timeline and in video pixels
+    pub clicks: &'a [CClickEvent],
+    pub effect: ClickEffect,
+    pub gamma_correct_blend: bool,
+}
+
+impl Overlay for ClickRipples<'_> {
+    fn render(&self, frame: &mut FrameView<'_>, timestamp_ms: f64) {
+        composite_click_ripples(
+            frame.data,
+            frame.width,
+            frame.height,
+            frame.stride,
+            self.clicks,
+            timestamp_ms,
+            &self.effect,
+            self.gamma_correct_blend,
+        );
+    }
+}
+
+// ============================================================================
+// Spotlight
+// ============================================================================
+
+/// Darkens the frame except for a soft-edged circle around the cursor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spotlight {
+    /// Radius of the undimmed circle (px)
+    pub radius_px: f32,
+    /// Width of the ramp from undimmed to fully dimmed outside the radius (px)
+    pub feather_px: f32,
+    /// Brightness taken away outside the circle (0.0-1.0)
+    pub dim_opacity: f32,
+}
+
+impl Default for Spotlight {
+    fn default() -> Self {
+        Self {
+            radius_px: 160.0,
+            feather_px: 60.0,
+            dim_opacity: 0.6,
+        }
+    }
+}
+
+/// `Spotlight` following the point `center` returns for each frame time
+pub struct SpotlightOverlay<F> {
+    spotlight: Spotlight,
+    /// Brightness kept in the feather ring (65535 = all), indexed by squared
+    /// distance from the center minus radius²
+    feather: Vec<u16>,
+    /// Each channel value once fully dimmed
+    dimmed: [u8; 256],
+    center: F,
+}
+
+impl<F: Fn(f64) -> (f32, f32)> SpotlightOverlay<F> {
+    pub fn new(spotlight: Spotlight, center: F) -> Self {
+        let radius = spotlight.radius_px.max(0.0);
+        let feather_px = spotlight.feather_px.max(0.0);
+        let inner_sq = radius * radius;
+        let ring_len = ((radius + feather_px).powi(2) - inner_sq).ceil() as usize;
+        let feather = (0..ring_len)
+            .map(|i| {
+                let distance = (inner_sq + i as f32 + 0.5).sqrt();
+                let t = ((distance - radius) / feather_px).clamp(0.0, 1.0);
+                let dim = spotlight.dim_opacity * t * t * (3.0 - 2.0 * t);
+                ((1.0 - dim) * 65535.0).round() as u16
+            })
+            .collect();
+        let keep = 1.0 - spotlight.dim_opacity;
+        Self {
+            spotlight,
+            feather,
+            dimmed: std::array::from_fn(|v| (v as f32 * keep).round() as u8),
+            center,
+        }
+    }
+
+    /// Dim `frame` outside the circle around (`cx`, `cy`), which may lie
+    /// off the frame. Pixels inside the radius are not touched.
+    pub fn apply(&self, frame: &mut FrameView<'_>, cx: f32, cy: f32) {
+        let radius = self.spotlight.radius_px.max(0.0);
+        let inner_sq = radius * radius;
+        let outer_sq = inner_sq + self.feather.len() as f32;
+        let width = frame.width();
+        if !(cx.is_finite() && cy.is_finite()) {
+            for y in 0..frame.height() {
+                if let Some(row) = frame.row_mut(y) {
+                    self.dim_fully(row);
+                }
+            }
+            return;
+        }
+        // First column at or right of `x` (pixel centers sit at +0.5)
+        let column = |x: f32| (x - 0.5).ceil().clamp(0.0, width as f32) as usize;
+
+        for y in 0..frame.height() {
+            let Some(row) = frame.row_mut(y) else {
+                break;
+            };
+            let dy = y as f32 + 0.5 - cy;
+            let dy_sq = dy * dy;
+            // Columns within the outer circle; everything else is fully dimmed
+            let (ring_start, ring_end) = if dy_sq < outer_sq {
+                let half = (outer_sq - dy_sq).sqrt();
+                (
+                    column(cx - half).saturating_sub(1),
+                    (column(cx + half) + 1).min(width as usize),
+                )
+            } else {
+                (0, 0)
+            };
+            // Columns certainly inside the radius, left alone
+            let (lit_start, lit_end) = if dy_sq < inner_sq {
+                let half = (inner_sq - dy_sq).sqrt();
+                (column(cx - half) + 1, column(cx + half).saturating_sub(1))
+            } else {
+                (ring_start, ring_start)
+            };
+
+            let (head, rest) = row.split_at_mut(ring_start * 4);
+            let (ring, tail) = rest.split_at_mut((ring_end - ring_start) * 4);
+            self.dim_fully(head);
+            self.dim_fully(tail);
+            for (i, px) in ring.chunks_exact_mut(4).enumerate() {
+                let x = ring_start + i;
+                if (lit_start..lit_end).contains(&x) {
+                    continue;
+                }
+                let dx = x as f32 + 0.5 - cx;
+                let distance_sq = dx * dx + dy_sq;
+                if distance_sq < inner_sq {
+                    continue;
+                }
+                match self.feather.get((distance_sq - inner_sq) as usize) {
+                    Some(&keep) => {
+                        let keep = keep as u32;
+                        for c in &mut px[..3] {
+                            *c = ((*c as u32 * keep + 32767) / 65535) as u8;
+                        }
+                    }
+                    None => self.dim_fully(px),
+                }
+            }
+        }
+    }
+
+    /// Apply the full dim to RGBA pixels (alpha is kept)
+    fn dim_fully(&self, pixels: &mut [u8]) {
+        for px in pixels.chunks_exact_mut(4) {
+            for c in &mut px[..3] {
+                *c = self.dimmed[*c as usize];
+            }
+        }
+    }
+}
+
+impl<F: Fn(f64) -> (f32, f32)> Overlay for SpotlightOverlay<F> {
+    fn render(&self, frame: &mut FrameView<'_>, timestamp_ms: f64) {
+        let (cx, cy) = (self.center)(timestamp_ms);
+        self.apply(frame, cx, cy);
+    }
+}
+
+// ============================================================================
+// Keystroke Badges
+// ============================================================================
+
+/// Badge padding around the text, as a fraction of the font size
+const BADGE_PADDING_EM: f32 = 0.5;
+/// Badge corner radius, as a fraction of the font size
+const BADGE_CORNER_RADIUS_EM: f32 = 0.4;
+/// Presses shown in one badge; older ones scroll off the front
+const BADGE_MAX_KEYS: usize = 6;
+
+/// Frame corner the keystroke badge or watermark is pinned to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BadgeCorner {
+    #[default]
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
+}
+
+impl BadgeCorner {
+    /// FFI value (0 = bottom left, 1 = bottom right, 2 = top left, 3 = top right)
+    pub fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::BottomLeft),
+            1 => Some(Self::BottomRight),
+            2 => Some(Self::TopLeft),
+            3 => Some(Self::TopRight),
+            _ => None,
+        }
+    }
+
+    /// Top-left corner of a `width` x `height` badge `margin_x` px in from
+    /// this corner's side edge and `margin_y` px from its top/bottom edge
+    fn place(
+        self,
+        width: u32,
+        height: u32,
+        frame: &FrameView<'_>,
+        margin_x: f32,
+        margin_y: f32,
+    ) -> (f32, f32) {
+        let left = margin_x;
+        let top = margin_y;
+        let right = frame.width() as f32 - margin_x - width as f32;
+        let bottom = frame.height() as f32 - margin_y - height as f32;
+        let (x, y) = match self {
+            Self::BottomLeft => (left, bottom),
+            Self::BottomRight => (right, bottom),
+            Self::TopLeft => (left, top),
+            Self::TopRight => (right, top),
+        };
+        // Whole pixels keep the text crisp
+        (x.round(), y.round())
+    }
+}
+
+/// Look of the on-screen keystroke badges
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeystrokeDisplay {
+    /// TrueType/OpenType font for the key text; it must cover every
+    /// character shown (e.g. ⌘ and ⇧ for macOS shortcuts)
+    pub font_path: PathBuf,
+    /// Text line height (px)
+    pub font_size_px: f32,
+    pub corner: BadgeCorner,
+    /// Gap between the badge and the frame edges (px)
+    pub margin_px: f32,
+    /// Time a press stays on screen, including the fade (ms)
+    pub hold_ms: f32,
+    /// Fade-out at the end of the hold (ms)
+    pub fade_ms: f32,
+    pub text_color: [u8; 3],
+    pub background_color: [u8; 3],
+    /// Opacity of the background; the text is drawn opaque
+    pub background_opacity: f32,
+}
+
+impl Default for KeystrokeDisplay {
+    fn default() -> Self {
+        Self {
+            font_path: PathBuf::new(),
+            font_size_px: 28.0,
+            corner: BadgeCorner::BottomLeft,
+            margin_px: 32.0,
+            hold_ms: 1500.0,
+            fade_ms: 300.0,
+            text_color: [255, 255, 255],
+            background_color: [24, 24, 24],
+            background_opacity: 0.75,
+        }
+    }
+}
+
+/// Load a TrueType/OpenType font for keystroke badges
+pub fn load_font(path: &Path) -> Result<FontArc, Box<dyn Error>> {
+    let data = std::fs::read(path)?;
+    Ok(FontArc::try_from_vec(data)?)
+}
+
+/// Badge text from `start_ms` until the next span starts or `hide_ms`
+struct BadgeSpan {
+    start_ms: f64,
+    hide_ms: f64,
+    text: String,
+}
+
+/// Keystroke badges as an overlay layer. A press made while the previous
+/// badge is still up joins it (keeping the last `BADGE_MAX_KEYS`), and the
+/// badge then stays until `hold_ms` after the newest press.
+pub struct KeystrokeOverlay {
+    spans: Vec<BadgeSpan>,
+    display: KeystrokeDisplay,
+    font: FontArc,
+    gamma_correct_blend: bool,
+    /// Rasterized badges by text, so each string is drawn once per render
+    badges: RefCell<HashMap<String, CursorSprite>>,
+}
+
+impl KeystrokeOverlay {
+    /// `keys` sorted by time, on the video timeline (see `keys_to_video_time`)
+    pub fn new(
+        keys: &[KeyEvent],
+        display: &KeystrokeDisplay,
+        font: FontArc,
+        gamma_correct_blend: bool,
+    ) -> Self {
+        let hold_ms = display.hold_ms as f64;
+        let mut spans: Vec<BadgeSpan> = Vec::with_capacity(keys.len());
+        let mut group: Vec<&str> = Vec::new();
+        for key in keys {
+            if !spans.last().is_some_and(|s| key.timestamp_ms < s.hide_ms) {
+                group.clear();
+            }
+            group.push(&key.text);
+            let shown = &group[group.len().saturating_sub(BADGE_MAX_KEYS)..];
+            spans.push(BadgeSpan {
+                start_ms: key.timestamp_ms,
+                hide_ms: key.timestamp_ms + hold_ms,
+                text: shown.join(" "),
+            });
+        }
+        Self {
+            spans,
+            display: display.clone(),
+            font,
+            gamma_correct_blend,
+            badges: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The span on screen at `timestamp_ms` and its opacity
+    fn visible_at(&self, timestamp_ms: f64) -> Option<(&BadgeSpan, f32)> {
+        let index = self
+            .spans
+            .partition_point(|s| s.start_ms <= timestamp_ms)
+            .checked_sub(1)?;
+        let span = &self.spans[index];
+        let remaining_ms = span.hide_ms - timestamp_ms;
+        if remaining_ms <= 0.0 {
+            return None;
+        }
+        let fade_ms = self.display.fade_ms as f64;
+        let opacity = if fade_ms > 0.0 {
+            (remaining_ms / fade_ms).min(1.0) as f32
+        } else {
+            1.0
+        };
+        Some((span, opacity))
+    }
+}
+
+impl Overlay for KeystrokeOverlay {
+    fn render(&self, frame: &mut FrameView<'_>, timestamp_ms: f64) {
+        let Some((span, opacity)) = self.visible_at(timestamp_ms) else {
+            return;
+        };
+        let mut badges = self.badges.borrow_mut();
+        if !badges.contains_key(&span.text) {
+            let badge = rasterize_badge(&self.font, &span.text, &self.display);
+            badges.insert(span.text.clone(), badge);
+        }
+        let badge = &badges[&span.text];
+        let margin = self.display.margin_px;
+        let (x, y) = self
+            .display
+            .corner
+            .place(badge.width, badge.height, frame, margin, margin);
+        let style = CursorStyle {
+            opacity,
+            gamma_correct_blend: self.gamma_correct_blend,
+            ..CursorStyle::default()
+        };
+        frame.composite_sprite(badge, x, y, &style);
+    }
+}
+
+/// `text` on a rounded rectangle, premultiplied
+fn rasterize_badge(font: &FontArc, text: &str, display: &KeystrokeDisplay) -> CursorSprite {
+    let scale = PxScale::from(display.font_size_px);
+    let padding = (display.font_size_px * BADGE_PADDING_EM).round();
+
+    // Lay the text out on one line, baseline one ascent below the padding
+    let baseline = padding + font.as_scaled(scale).ascent();
+    let (glyphs, advance) = layout_line(font, scale, text, padding, baseline);
+    let width = (advance + 2.0 * padding).ceil().max(1.0) as u32;
+    let height = (font.as_scaled(scale).height() + 2.0 * padding)
+        .ceil()
+        .max(1.0) as u32;
+
+    let radius = display.font_size_px * BADGE_CORNER_RADIUS_EM;
+    let alpha = display.background_opacity * 255.0;
+    let mut data = rounded_box(width, height, radius, display.background_color, alpha);
+    draw_glyphs(font, glyphs, &mut data, width, height, display.text_color);
+    CursorSprite::from_rgba8(data, width, height, true)
+}
+
+/// Glyphs of `text` on one line, starting at `x` on the `baseline`, and the
+/// line's advance width
+fn layout_line(
+    font: &FontArc,
+    scale: PxScale,
+    text: &str,
+    x: f32,
+    baseline: f32,
+) -> (Vec<Glyph>, f32) {
+    let scaled = font.as_scaled(scale);
+    let mut glyphs = Vec::new();
+    let mut caret = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        glyphs.push(id.with_scale_and_position(scale, point(x + caret, baseline)));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    (glyphs, caret)
+}
+
+/// Premultiplied `width` x `height` RGBA of a `color` rectangle with corners
+/// rounded to `radius`, at `alpha` (0-255), anti-aliased
+fn rounded_box(width: u32, height: u32, radius: f32, color: [u8; 3], alpha: f32) -> Vec<u8> {
+    let radius = radius.min(width.min(height) as f32 / 2.0);
+    let (half_w, half_h) = (width as f32 / 2.0, height as f32 / 2.0);
+    let mut data = vec![0u8; width as usize * height as usize * 4];
+    for (i, px) in data.chunks_exact_mut(4).enumerate() {
+        // Coverage of the rounded rectangle at the pixel centre
+        let x = (i % width as usize) as f32 + 0.5;
+        let y = (i / width as usize) as f32 + 0.5;
+        let qx = (x - half_w).abs() - (half_w - radius);
+        let qy = (y - half_h).abs() - (half_h - radius);
+        let distance = qx.max(0.0).hypot(qy.max(0.0)) + qx.max(qy).min(0.0) - radius;
+        let a = alpha * (0.5 - distance).clamp(0.0, 1.0);
+        for (c, &bg) in px[..3].iter_mut().zip(&color) {
+            *c = (bg as f32 * a / 255.0).round() as u8;
+        }
+        px[3] = a.round() as u8;
+    }
+    data
+}
+
+/// Draw `glyphs` in opaque `color` "over" premultiplied `data`
+fn draw_glyphs(
+    font: &FontArc,
+    glyphs: Vec<Glyph>,
+    data: &mut [u8],
+    width: u32,
+    height: u32,
+    color: [u8; 3],
+) {
+    for glyph in glyphs {
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let x = bounds.min.x as i64 + gx as i64;
+            let y = bounds.min.y as i64 + gy as i64;
+            if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+                return;
+            }
+            let coverage = coverage.clamp(0.0, 1.0);
+            let i = (y as usize * width as usize + x as usize) * 4;
+            let px = &mut data[i..i + 4];
+            for (c, &fg) in px[..3].iter_mut().zip(&color) {
+                *c = (fg as f32 * coverage + *c as f32 * (1.0 - coverage)).round() as u8;
+            }
+            px[3] = (255.0 * coverage + px[3] as f32 * (1.0 - coverage)).round() as u8;
+        });
+    }
+}
+
+// ============================================================================
+// Captions
+// ============================================================================
+
+/// Band of the frame a caption sits in; captions are always centered
+/// horizontally
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionPosition {
+    Top,
+    Middle,
+    #[default]
+    Bottom,
+}
+
+/// Text burned into the video for a span of it, e.g. "Step 2: open Settings".
+///
+/// Parsed from JSON as an object with these field names; all but `text`,
+/// `start_ms` and `end_ms` are optional.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Caption {
+    /// "\n" starts a new line, and lines wider than the style allows wrap
+    /// between words. Right-to-left scripts are not supported.
+    pub text: String,
+    /// Video time the caption appears (ms, inclusive)
+    pub start_ms: f64,
+    /// Video time it disappears (ms, exclusive)
+    pub end_ms: f64,
+    pub position: CaptionPosition,
+    /// Text line height (px)
+    pub font_size_px: f32,
+    pub color: [u8; 3],
+    /// Straight RGBA of the rounded box behind the text (None = no box)
+    pub background: Option<[u8; 4]>,
+}
+
+impl Default for Caption {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            start_ms: 0.0,
+            end_ms: 0.0,
+            position: CaptionPosition::Bottom,
+            font_size_px: 36.0,
+            color: [255, 255, 255],
+            background: Some([0, 0, 0, 180]),
+        }
+    }
+}
+
+impl Caption {
+    /// Whether the text holds right-to-left characters (Hebrew, Arabic, ...),
+    /// which this layout would draw backwards and unshaped
+    pub fn has_right_to_left(&self) -> bool {
+        self.text.chars().any(|c| {
+            matches!(
+                c as u32,
+                0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFC | 0x10800..=0x10FFF
+                    | 0x1E800..=0x1EFFF
+            )
+        })
+    }
+}
+
+/// Look shared by every caption of a render
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptionStyle {
+    /// TrueType/OpenType font for the caption text; it must cover every
+    /// character shown
+    pub font_path: PathBuf,
+    /// Widest a caption's box gets before its lines wrap, in percent of the
+    /// frame width
+    pub max_width_percent: f32,
+    /// Gap between the captions and the frame's top or bottom edge (px)
+    pub margin_px: f32,
+    /// Gap between captions stacked at the same position (px)
+    pub spacing_px: f32,
+    /// Fade at the start and at the end of each caption (ms, 0 = cut)
+    pub fade_in_ms: f32,
+    pub fade_out_ms: f32,
+}
+
+impl Default for CaptionStyle {
+    fn default() -> Self {
+        Self {
+            font_path: PathBuf::new(),
+            max_width_percent: 80.0,
+            margin_px: 48.0,
+            spacing_px: 12.0,
+            fade_in_ms: 200.0,
+            fade_out_ms: 200.0,
+        }
+    }
+}
+
+/// Captions as an overlay layer. Captions on screen at the same position
+/// stack top to bottom in list order.
+pub struct CaptionOverlay {
+    captions: Vec<Caption>,
+    style: CaptionStyle,
+    font: FontArc,
+    gamma_correct_blend: bool,
+    /// Rasterized captions by index, each drawn once per render
+    strips: RefCell<HashMap<usize, CursorSprite>>,
+}
+
+impl CaptionOverlay {
+    /// `captions` on the video timeline
+    pub fn new(
+        captions: &[Caption],
+        style: &CaptionStyle,
+        font: FontArc,
+        gamma_correct_blend: bool,
+    ) -> Self {
+        Self {
+            captions: captions.to_vec(),
+            style: style.clone(),
+            font,
+            gamma_correct_blend,
+            strips: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Opacity of `caption` at `timestamp_ms`, None when it is not on screen
+    fn opacity_at(&self, caption: &Caption, timestamp_ms: f64) -> Option<f32> {
+        if !(caption.start_ms..caption.end_ms).contains(&timestamp_ms) {
+            return None;
+        }
+        let ramp = |elapsed_ms: f64, fade_ms: f32| {
+            if fade_ms > 0.0 {
+                (elapsed_ms / fade_ms as f64).min(1.0) as f32
+            } else {
+                1.0
+            }
+        };
+        let fade_in = ramp(timestamp_ms - caption.start_ms, self.style.fade_in_ms);
+        let fade_out = ramp(caption.end_ms - timestamp_ms, self.style.fade_out_ms);
+        Some(fade_in.min(fade_out))
+    }
+}
+
+impl Overlay for CaptionOverlay {
+    fn render(&self, frame: &mut FrameView<'_>, timestamp_ms: f64) {
+        let mut strips = self.strips.borrow_mut();
+        let max_width = frame.width() as f32 * self.style.max_width_percent / 100.0;
+        let (frame_w, frame_h) = (frame.width() as f32, frame.height() as f32);
+        for position in [
+            CaptionPosition::Top,
+            CaptionPosition::Middle,
+            CaptionPosition::Bottom,
+        ] {
+            let shown: Vec<(usize, f32)> = self
+                .captions
+                .iter()
+                .enumerate()
+                .filter(|(_, caption)| caption.position == position)
+                .filter_map(|(i, caption)| Some((i, self.opacity_at(caption, timestamp_ms)?)))
+                .collect();
+            if shown.is_empty() {
+                continue;
+            }
+            for &(i, _) in &shown {
+                strips
+                    .entry(i)
+                    .or_insert_with(|| rasterize_caption(&self.font, &self.captions[i], max_width));
+            }
+
+            let spacing = self.style.spacing_px;
+            let stack_height = shown
+                .iter()
+                .map(|(i, _)| strips[i].height as f32 + spacing)
+                .sum::<f32>()
+                - spacing;
+            let mut y = match position {
+                CaptionPosition::Top => self.style.margin_px,
+                CaptionPosition::Middle => (frame_h - stack_height) / 2.0,
+                CaptionPosition::Bottom => frame_h - self.style.margin_px - stack_height,
+            };
+            for (i, opacity) in shown {
+                let strip = &strips[&i];
+                let style = CursorStyle {
+                    opacity,
+                    gamma_correct_blend: self.gamma_correct_blend,
+                    ..CursorStyle::default()
+                };
+                // Whole pixels keep the text crisp
+                let x = ((frame_w - strip.width as f32) / 2.0).round();
+                frame.composite_sprite(strip, x, y.round(), &style);
+                y += strip.height as f32 + spacing;
+            }
+        }
+    }
+}
+
+/// `caption`'s lines on its box, centered, the box no wider than
+/// `max_width` px (unless a single character is), premultiplied
+fn rasterize_caption(font: &FontArc, caption: &Caption, max_width: f32) -> CursorSprite {
+    let scale = PxScale::from(caption.font_size_px);
+    let scaled = font.as_scaled(scale);
+    let padding = (caption.font_size_px * BADGE_PADDING_EM).round();
+    let lines = wrap_text(font, scale, &caption.text, max_width - 2.0 * padding);
+    let line_height = scaled.height() + scaled.line_gap();
+
+    let advances: Vec<f32> = lines
+        .iter()
+        .map(|line| layout_line(font, scale, line, 0.0, 0.0).1)
+        .collect();
+    let widest = advances.iter().cloned().fold(0.0, f32::max);
+    let width = (widest + 2.0 * padding).ceil().max(1.0) as u32;
+    let text_height = line_height * lines.len() as f32 - scaled.line_gap();
+    let height = (text_height.max(0.0) + 2.0 * padding).ceil().max(1.0) as u32;
+
+    let mut data = match caption.background {
+        Some([r, g, b, a]) => rounded_box(
+            width,
+            height,
+            caption.font_size_px * BADGE_CORNER_RADIUS_EM,
+            [r, g, b],
+            a as f32,
+        ),
+        None => vec![0u8; width as usize * height as usize * 4],
+    };
+    for (k, (line, advance)) in lines.iter().zip(advances).enumerate() {
+        let x = (width as f32 - advance) / 2.0;
+        let baseline = padding + scaled.ascent() + line_height * k as f32;
+        let (glyphs, _) = layout_line(font, scale, line, x, baseline);
+        draw_glyphs(font, glyphs, &mut data, width, height, caption.color);
+    }
+    CursorSprite::from_rgba8(data, width, height, true)
+}
+
+/// `text` split at its line breaks and wrapped between words so that no line
+/// is wider than `max_width` px. A word too wide on its own is broken between
+/// characters.
+fn wrap_text(font: &FontArc, scale: PxScale, text: &str, max_width: f32) -> Vec<String> {
+    let fits = |line: &str| layout_line(font, scale, line, 0.0, 0.0).1 <= max_width;
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let joined = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if fits(&joined) {
+                line = joined;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                line.push(c);
+                if line.chars().nth(1).is_some() && !fits(&line) {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+// ============================================================================
+// Watermark
+// ============================================================================
+
+/// Logo stamped in a corner of every frame, above the cursor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    pub image: WatermarkImage,
+    pub corner: BadgeCorner,
+    /// Gap between the logo and the frame's side edge (px)
+    pub margin_x_px: f32,
+    /// Gap between the logo and the frame's top/bottom edge (px)
+    pub margin_y_px: f32,
+    /// Logo size relative to the image (1.0 = as drawn)
+    pub scale: f32,
+    /// Multiplies the image's own alpha (0.0-1.0)
+    pub opacity: f32,
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Self {
+            image: WatermarkImage::Path(PathBuf::new()),
+            corner: BadgeCorner::BottomRight,
+            margin_x_px: 24.0,
+            margin_y_px: 24.0,
+            scale: 1.0,
+            opacity: 0.6,
+        }
+    }
+}
+
+/// Where a `Watermark`'s pixels come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatermarkImage {
+    /// Any image `load_cursor_sprite` reads (SVGs rasterized at the scale)
+    Path(PathBuf),
+    /// Straight (not premultiplied) RGBA8 pixels, `width * height * 4` bytes
+    Rgba {
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// The watermark as an overlay layer; the logo is loaded and scaled once
+pub struct WatermarkOverlay {
+    logo: CursorSprite,
+    corner: BadgeCorner,
+    margin_x_px: f32,
+    margin_y_px: f32,
+    style: CursorStyle,
+}
+
+impl WatermarkOverlay {
+    pub fn new(
+        watermark: &Watermark,
+        gamma_correct_blend: bool,
+    ) -> Result<Self, image::ImageError> {
+        let style = CursorStyle {
+            scale: watermark.scale,
+            ..CursorStyle::default()
+        };
+        let (logo, style) = match &watermark.image {
+            WatermarkImage::Path(path) => load_cursor_sprite_prescaled(path, &style)?,
+            WatermarkImage::Rgba {
+                data,
+                width,
+                height,
+            } => (
+                CursorSprite::from_rgba8(data.clone(), *width, *height, false),
+                style,
+            ),
+        };
+        let (logo, _) = prescale(&logo, &style);
+        let mut logo = logo.into_owned();
+        // Placed by its top-left corner, whatever hotspot a .cur declared
+        logo.hotspot_x = 0.0;
+        logo.hotspot_y = 0.0;
+        for frame in &mut logo.animation {
+            frame.sprite.hotspot_x = 0.0;
+            frame.sprite.hotspot_y = 0.0;
+        }
+        Ok(Self {
+            logo,
+            corner: watermark.corner,
+            margin_x_px: watermark.margin_x_px,
+            margin_y_px: watermark.margin_y_px,
+            style: CursorStyle {
+                opacity: watermark.opacity,
+                gamma_correct_blend,
+                ..CursorStyle::default()
+            },
+        })
+    }
+}
+
+impl Overlay for WatermarkOverlay {
+    fn render(&self, frame: &mut FrameView<'_>, timestamp_ms: f64) {
+        let logo = self.logo.frame_at(timestamp_ms);
+        let (x, y) = self.corner.place(
+            logo.width,
+            logo.height,
+            frame,
+            self.margin_x_px,
+            self.margin_y_px,
+        );
+        frame.composite_sprite(logo, x, y, &self.style);
+    }
+}
+
+// ============================================================================
+// Redaction
+// ============================================================================
+
+/// How a `RedactionRegion` hides what is under it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactionMode {
+    /// Box blur reaching `radius_px` to each side
+    Blur { radius_px: u32 },
+    /// Squares of `block_px`, each filled with its average color
+    Pixelate { block_px: u32 },
+}
+
+/// A rectangle of the recording obscured for a span of the video, e.g. an
+/// email address or an API token
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RedactionRegion {
+    /// Top-left corner (source px); may hang off the frame
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Video time the region appears (ms, inclusive)
+    pub start_ms: f64,
+    /// Video time it disappears (ms, exclusive; None = the rest of the video)
+    pub end_ms: Option<f64>,
+    pub mode: RedactionMode,
+}
+
+impl RedactionMode {
+    /// FFI value (0 = blur, 1 = pixelate) and its radius or block size
+    pub fn from_raw(value: i32, px: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Blur { radius_px: px }),
+            1 => Some(Self::Pixelate { block_px: px }),
+            _ => None,
+        }
+    }
+}
+
+impl RedactionRegion {
+    /// Whether the region is applied to the frame at `timestamp_ms`
+    pub fn active_at(&self, timestamp_ms: f64) -> bool {
+        timestamp_ms >= self.start_ms && !matches!(self.end_ms, Some(end) if timestamp_ms >= end)
+    }
+
+    /// Columns and rows of a `width` x `height` frame inside the region
+    fn clip(&self, width: u32, height: u32) -> Option<(Range<u32>, Range<u32>)> {
+        let span = |start: i32, len: u32, limit: u32| {
+            let (start, limit) = (start as i64, limit as i64);
+            (start.clamp(0, limit) as u32)..((start + len as i64).clamp(0, limit) as u32)
+        };
+        let columns = span(self.x, self.width, width);
+        let rows = span(self.y, self.height, height);
+        (!columns.is_empty() && !rows.is_empty()).then_some((columns, rows))
+    }
+}
+
+/// Applies each active region in list order, so a region overlapping an
+/// earlier one works on its already redacted pixels. Drawn first, under
+/// every other layer.
+pub struct RedactionOverlay {
+    regions: Vec<RedactionRegion>,
+    /// Per-channel box sums of the blur passes
+    sums: RefCell<Vec<u64>>,
+}
+
+impl RedactionOverlay {
+    pub fn new(regions: Vec<RedactionRegion>) -> Self {
+        Self {
+            regions,
+            sums: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl Overlay for RedactionOverlay {
+    fn render(&self, frame: &mut FrameView<'_>, timestamp_ms: f64) {
+        let mut sums = self.sums.borrow_mut();
+        for region in self.regions.iter().filter(|r| r.active_at(timestamp_ms)) {
+            let Some((columns, rows)) = region.clip(frame.width(), frame.height()) else {
+                continue;
+            };
+            match region.mode {
+                RedactionMode::Blur { radius_px } => {
+                    box_blur_region(frame, columns, rows, radius_px, &mut sums)
+                }
+                RedactionMode::Pixelate { block_px } => {
+                    pixelate_region(frame, columns, rows, (region.x, region.y), block_px)
+                }
+            }
+        }
+    }
+}
+
+/// Separable box blur of the RGB of `columns` x `rows`. The window reads up
+/// to `radius` pixels past the region (the apron) and repeats the edge
+/// pixels past the frame; only the region is written.
+fn box_blur_region(
+    frame: &mut FrameView<'_>,
+    columns: Range<u32>,
+    rows: Range<u32>,
+    radius: u32,
+    sums: &mut Vec<u64>,
+) {
+    let (width, height) = (frame.width() as i64, frame.height() as i64);
+    let r = radius as i64;
+    let apron =
+        rows.start.saturating_sub(radius)..rows.end.saturating_add(radius).min(frame.height());
+    let span = columns.len() * 3;
+    sums.clear();
+    sums.resize((apron.len() + 1) * span, 0);
+    let (horizontal, vertical) = sums.split_at_mut(apron.len() * span);
+    let at = |i: i64, limit: i64| i.clamp(0, limit - 1) as usize;
+
+    // Horizontal window sums of every apron row
+    let x0 = columns.start as i64;
+    for (y, out) in apron.clone().zip(horizontal.chunks_exact_mut(span)) {
+        let Some(row) = frame.row_mut(y) else {
+            break;
+        };
+        let mut sum = [0u64; 3];
+        for k in x0 - r..=x0 + r {
+            let px = at(k, width) * 4;
+            for (c, s) in sum.iter_mut().enumerate() {
+                *s += row[px + c] as u64;
+            }
+        }
+        for (i, out) in out.chunks_exact_mut(3).enumerate() {
+            out.copy_from_slice(&sum);
+            let x = x0 + i as i64;
+            let (add, drop) = (at(x + r + 1, width) * 4, at(x - r, width) * 4);
+            for (c, s) in sum.iter_mut().enumerate() {
+                *s = *s + row[add + c] as u64 - row[drop + c] as u64;
+            }
+        }
+    }
+
+    // Vertical window over those sums, one running total per column
+    let first = apron.start as i64;
+    let row_of = |y: i64| (at(y, height) as i64 - first) as usize * span;
+    let y0 = rows.start as i64;
+    for k in y0 - r..=y0 + r {
+        let src = &horizontal[row_of(k)..][..span];
+        for (v, h) in vertical.iter_mut().zip(src) {
+            *v += h;
+        }
+    }
+    let area = ((2 * r + 1) * (2 * r + 1)) as u64;
+    for y in rows.clone() {
+        let Some(row) = frame.row_mut(y) else {
+            break;
+        };
+        let out = &mut row[columns.start as usize * 4..columns.end as usize * 4];
+        for (px, v) in out.chunks_exact_mut(4).zip(vertical.chunks_exact(3)) {
+            for (c, &s) in px.iter_mut().zip(v) {
+                *c = ((s + area / 2) / area) as u8;
+            }
+        }
+        // The window past the last row reaches beyond the apron
+        if y + 1 == rows.end {
+            break;
+        }
+        let y = y as i64;
+        let (add, drop) = (row_of(y + r + 1), row_of(y - r));
+        for (i, v) in vertical.iter_mut().enumerate() {
+            *v = *v + horizontal[add + i] - horizontal[drop + i];
+        }
+    }
+}
+
+/// Fill each `block`-px square of `columns` x `rows` with its average RGB.
+/// Squares are laid out from `origin` (the region's unclipped corner) so they
+/// don't shift when the region hangs off the frame.
+fn pixelate_region(
+    frame: &mut FrameView<'_>,
+    columns: Range<u32>,
+    rows: Range<u32>,
+    origin: (i32, i32),
+    block: u32,
+) {
+    let block = block.max(1) as i64;
+    // End of the square containing `start`, cut at `end`
+    let square_end = |start: u32, origin: i32, end: u32| {
+        let offset = (start as i64 - origin as i64).rem_euclid(block);
+        (start as i64 - offset + block).min(end as i64) as u32
+    };
+    let mut top = rows.start;
+    while top < rows.end {
+        let bottom = square_end(top, origin.1, rows.end);
+        let mut left = columns.start;
+        while left < columns.end {
+            let right = square_end(left, origin.0, columns.end);
+            let bytes = left as usize * 4..right as usize * 4;
+            let mut sum = [0u64; 3];
+            for y in top..bottom {
+                if let Some(row) = frame.row_mut(y) {
+                    for px in row[bytes.clone()].chunks_exact(4) {
+                        for (s, &c) in sum.iter_mut().zip(px) {
+                            *s += c as u64;
+                        }
+                    }
+                }
+            }
+            let count = ((bottom - top) * (right - left)) as u64;
+            let average = sum.map(|s| ((s + count / 2) / count) as u8);
+            for y in top..bottom {
+                if let Some(row) = frame.row_mut(y) {
+                    for px in row[bytes.clone()].chunks_exact_mut(4) {
+                        px[..3].copy_from_slice(&average);
+                    }
+                }
+            }
+            left = right;
+        }
+        top = bottom;
+    }
+}
+
+// ============================================================================
+// Overlay Layers
+// ============================================================================
+
+/// Something drawn onto every output frame. A render draws its layers in
+/// list order, so each one covers those before it.
+pub trait Overlay {
+    fn render(&self, frame: &mut FrameView<'_>, timestamp_ms: f64);
+
+    /// Whether the last `render` was cut off at a frame edge
+    fn clipped(&self) -> bool {
+        false
+    }
+}
+
+/// An RGBA8 frame whose rows start `stride` bytes apart. Only rows that fit
+/// in the buffer are reachable, and row padding is never handed out.
+pub struct FrameView<'a> {
+    data: &'a mut [u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+}
+
+impl<'a> FrameView<'a> {
+    pub fn new(data: &'a mut [u8], width: u32, height: u32, stride: usize) -> Self {
+        let row_bytes = width as usize * 4;
+        // Never trust `height` past the end of the slice (the last row may be unpadded)
+        let rows = if row_bytes == 0 || stride < row_bytes {
+            0
+        } else {
+            (data.len() + stride - row_bytes) / stride
+        };
+        Self {
+            data,
+            width,
+            height: (height as usize).min(rows) as u32,
+            stride,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Rows actually present, which can be fewer than the frame claimed
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// The `width * 4` pixel bytes of row `y`
+    pub fn row_mut(&mut self, y: u32) -> Option<&mut [u8]> {
+        if y >= self.height {
+            return None;
+        }
+        let start = y as usize * self.stride;
+        Some(&mut self.data[start..start + self.width as usize * 4])
+    }
+
+    /// Turn premultiplied pixels (what blending onto a transparent frame
+    /// leaves) back into straight alpha, which encoders expect
+    pub fn unpremultiply(&mut self) {
+        for y in 0..self.height {
+            let Some(row) = self.row_mut(y) else {
+                break;
+            };
+            for px in row.chunks_exact_mut(4) {
+                let a = px[3];
+                if a == 0 || a == 255 {
+                    continue;
+                }
+                for c in &mut px[..3] {
+                    *c = ((*c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+                }
+            }
+        }
+    }
+
+    /// The RGBA bytes of pixel (`x`, `y`)
+    pub fn pixel_mut(&mut self, x: u32, y: u32) -> Option<&mut [u8]> {
+        if x >= self.width {
+            return None;
+        }
+        let start = x as usize * 4;
+        self.row_mut(y).map(|row| &mut row[start..start + 4])
+    }
+
+    /// `composite_cursor_subpixel` onto this frame; true when clipped
+    pub fn composite_sprite(
+        &mut self,
+        sprite: &CursorSprite,
+        x: f32,
+        y: f32,
+        style: &CursorStyle,
+    ) -> bool {
+        composite_cursor_subpixel(
+            self.data,
+            self.width,
+            self.height,
+            self.stride,
+            sprite,
+            x,
+            y,
+            style,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Opaque black RGBA frame, rows `stride` bytes apart
+    fn black_frame(width: u32, height: u32, stride: usize) -> Vec<u8> {
+        let mut frame = vec![0; stride * height as usize];
+        for row in frame.chunks_mut(stride) {
+            for pixel in row[..width as usize * 4].chunks_mut(4) {
+                pixel[3] = 255;
+            }
+        }
+        frame
+    }
+
+    /// Opaque white `size`x`size` sprite
+    fn white_sprite(size: u32) -> CursorSprite {
+        CursorSprite::from_rgba8(vec![255; (size * size * 4) as usize], size, size, false)
+    }
+
+    /// Bounding box `(x0, y0, x1, y1)` of the pixels whose red is over half
+    fn lit_box(frame: &[u8], width: u32, stride: usize) -> Option<(u32, u32, u32, u32)> {
+        let mut found: Option<(u32, u32, u32, u32)> = None;
+        for (y, row) in frame.chunks(stride).enumerate() {
+            for x in 0..width {
+                if row[x as usize * 4] > 128 {
+                    let (x, y) = (x, y as u32);
+                    let (x0, y0, x1, y1) = found.unwrap_or((x, y, x, y));
+                    found = Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y)));
+                }
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn centered_hotspot_centers_the_sprite_on_the_point() {
+        let (width, height, stride) = (40, 40, 160);
+        let style = CursorStyle {
+            hotspot_x: 4.0,
+            hotspot_y: 4.0,
+            ..CursorStyle::default()
+        };
+        let mut frame = black_frame(width, height, stride);
+        let clipped = composite_cursor_subpixel(
+            &mut frame,
+            width,
+            height,
+            stride,
+            &white_sprite(8),
+            20.0,
+            20.0,
+            &style,
+        );
+        assert!(!clipped);
+        assert_eq!(lit_box(&frame, width, stride), Some((16, 16, 23, 23)));
+
+        // The hotspot is in unscaled pixels, so it stays centered at 2x
+        let mut frame = black_frame(width, height, stride);
+        let scaled = CursorStyle {
+            scale: 2.0,
+            ..style
+        };
+        composite_cursor_subpixel(
+            &mut frame,
+            width,
+            height,
+            stride,
+            &white_sprite(8),
+            20.0,
+            20.0,
+            &scaled,
+        );
+        let (x0, y0, x1, y1) = lit_box(&frame, width, stride).unwrap();
+        assert_eq!((x0, y0), (12, 12));
+        // The last row and column sample the sprite's outer half texel
+        assert!((26..=27).contains(&x1) && (26..=27).contains(&y1));
+
+        // The default hotspot is the top-left corner
+        let mut frame = black_frame(width, height, stride);
+        composite_cursor_subpixel(
+            &mut frame,
+            width,
+            height,
+            stride,
+            &white_sprite(8),
+            20.0,
+            20.0,
+            &CursorStyle::default(),
+        );
+        assert_eq!(lit_box(&frame, width, stride), Some((20, 20, 27, 27)));
+    }
+
+    #[test]
+    fn crosshair_center_lands_on_the_cursor_position() {
+        // 9x9 white crosshair, one pixel thick, with its hotspot in the middle
+        let mut data = vec![0; 9 * 9 * 4];
+        for (i, pixel) in data.chunks_mut(4).enumerate() {
+            if i % 9 == 4 || i / 9 == 4 {
+                pixel.fill(255);
+            }
+        }
+        let mut crosshair = CursorSprite::from_rgba8(data, 9, 9, false);
+        (crosshair.hotspot_x, crosshair.hotspot_y) = (4.0, 4.0);
+
+        let (width, height, stride) = (200, 200, 800);
+        let mut frame = black_frame(width, height, stride);
+        composite_cursor_subpixel(
+            &mut frame,
+            width,
+            height,
+            stride,
+            &crosshair,
+            100.0,
+            100.0,
+            &CursorStyle::default(),
+        );
+        let lit = |x: usize, y: usize| frame[y * stride + x * 4] == 255;
+        assert!(lit(100, 100));
+        assert!((96..=104).all(|i| lit(i, 100) && lit(100, i)));
+        assert!(!lit(99, 99) && !lit(101, 101) && !lit(95, 100) && !lit(100, 105));
+        assert_eq!(lit_box(&frame, width, stride), Some((96, 96, 104, 104)));
+    }
+
+    #[test]
+    fn padded_rows_keep_their_padding() {
+        // 12 padding bytes a row, as ffmpeg aligns a 40-pixel-wide frame
+        let (width, height, stride) = (40, 20, 40 * 4 + 12);
+        let mut frame = black_frame(width, height, stride);
+        for row in frame.chunks_mut(stride) {
+            row[width as usize * 4..].fill(0xAB);
+        }
+        // The last row ends at its pixels, with no padding after it
+        frame.truncate(stride * (height as usize - 1) + width as usize * 4);
+
+        // Runs off the right and bottom edges
+        let clipped = composite_cursor_subpixel(
+            &mut frame,
+            width,
+            height,
+            stride,
+            &white_sprite(8),
+            36.0,
+            15.0,
+            &CursorStyle::default(),
+        );
+        assert!(clipped);
+        assert_eq!(lit_box(&frame, width, stride), Some((36, 15, 39, 19)));
+        for row in frame.chunks(stride) {
+            assert!(row[width as usize * 4..].iter().all(|&byte| byte == 0xAB));
+        }
+    }
+
+    #[test]
+    fn linear_light_blend_matches_the_transfer_function() {
+        let to_linear = |c: f64| {
+            let c = c / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let to_srgb = |l: f64| {
+            let c = if l <= 0.003_130_8 {
+                l * 12.92
+            } else {
+                1.055 * l.powf(1.0 / 2.4) - 0.055
+            };
+            c * 255.0
+        };
+
+        let luts = gamma_luts();
+        for bg in (0..=255).step_by(15) {
+            for fg in (0..=255).step_by(15) {
+                for alpha in [0.0, 0.25, 0.5, 0.75, 1.0] {
+                    let expected = to_srgb(
+                        to_linear(bg as f64) * (1.0 - alpha as f64)
+                            + to_linear(fg as f64) * alpha as f64,
+                    );
+                    let actual = blend_linear(luts, bg, fg as f32, alpha);
+                    assert!(
+                        (actual as f64 - expected).abs() <= 1.0,
+                        "{} over {} at {}: {}, not {:.2}",
+                        fg,
+                        bg,
+                        alpha,
+                        actual,
+                        expected
+                    );
+                }
+            }
+        }
+
+        // Half-transparent white over black: mid-gray in light, not in code
+        let (width, height, stride) = (16, 16, 64);
+        let composite = |gamma_correct_blend| {
+            let mut frame = black_frame(width, height, stride);
+            let style = CursorStyle {
+                opacity: 0.5,
+                gamma_correct_blend,
+                ..CursorStyle::default()
+            };
+            composite_cursor_subpixel(
+                &mut frame,
+                width,
+                height,
+                stride,
+                &white_sprite(8),
+                4.0,
+                4.0,
+                &style,
+            );
+            frame[(8 * stride + 8 * 4)..][..3].to_vec()
+        };
+        assert_eq!(composite(true), vec![188; 3]);
+        assert!(composite(false).iter().all(|&c| (127..=128).contains(&c)));
+    }
+
+    /// xorshift64: reproducible noise without a dependency
+    struct Noise(u64);
+
+    impl Noise {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn byte(&mut self) -> u8 {
+            (self.next() >> 56) as u8
+        }
+
+        /// Uniform in `lo..hi`
+        fn range(&mut self, lo: f32, hi: f32) -> f32 {
+            lo + (hi - lo) * (self.next() >> 40) as f32 / (1u64 << 24) as f32
+        }
+    }
+
+    #[test]
+    fn row_sliced_compositing_matches_per_pixel_sampling() {
+        let (width, height, stride) = (64, 48, 64 * 4 + 8);
+        let mut noise = Noise(0x9E37_79B9_7F4A_7C15);
+        for case in 0..200 {
+            let (w, h) = (1 + noise.next() % 24, 1 + noise.next() % 24);
+            let data = (0..w * h * 4).map(|_| noise.byte()).collect();
+            let sprite = CursorSprite::from_rgba8(data, w as u32, h as u32, case % 2 == 0);
+            let style = CursorStyle {
+                scale: noise.range(0.3, 3.5),
+                opacity: noise.range(0.1, 1.0),
+                gamma_correct_blend: case % 3 == 0,
+                hotspot_x: noise.range(0.0, w as f32),
+                hotspot_y: noise.range(0.0, h as f32),
+                ..CursorStyle::default()
+            };
+            // Partly off the frame now and then
+            let (x, y) = (noise.range(-20.0, 84.0), noise.range(-20.0, 68.0));
+            let background: Vec<u8> = (0..stride * height as usize)
+                .map(|_| noise.byte())
+                .collect();
+
+            let mut sliced = background.clone();
+            composite_cursor_subpixel(&mut sliced, width, height, stride, &sprite, x, y, &style);
+            let mut gathered = background;
+            composite_gathered(
+                &mut gathered,
+                width,
+                height as i32,
+                stride,
+                &sprite,
+                (x, y),
+                (style.hotspot_x, style.hotspot_y),
+                &style,
+                style.gamma_correct_blend.then(gamma_luts),
+            );
+
+            for (i, (a, b)) in sliced.iter().zip(&gathered).enumerate() {
+                assert!(
+                    a.abs_diff(*b) <= 1,
+                    "case {} ({}x{} sprite at ({}, {}), {:?}): byte {} is {}, per-pixel {}",
+                    case,
+                    w,
+                    h,
+                    x,
+                    y,
+                    style,
+                    i,
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn scaled_sprites_keep_their_silhouette() {
+        // 10x10, an opaque white 6x6 square in the middle, hotspot at its corner
+        let mut data = vec![0; 10 * 10 * 4];
+        for (i, pixel) in data.chunks_mut(4).enumerate() {
+            if (2..8).contains(&(i % 10)) && (2..8).contains(&(i / 10)) {
+                pixel.fill(255);
+            }
+        }
+        let mut square = CursorSprite::from_rgba8(data, 10, 10, false);
+        (square.hotspot_x, square.hotspot_y) = (2.0, 2.0);
+
+        for (factor, size) in [(0.5, 5), (0.75, 8), (1.5, 15), (2.0, 20), (3.0, 30)] {
+            let scaled = square.scaled(factor);
+            assert_eq!((scaled.width, scaled.height), (size, size), "{}x", factor);
+            assert!(scaled.is_premultiplied);
+            let ratio = size as f32 / 10.0;
+            assert_eq!(
+                (scaled.hotspot_x, scaled.hotspot_y),
+                (2.0 * ratio, 2.0 * ratio)
+            );
+
+            for (i, pixel) in scaled.data.chunks_exact(4).enumerate() {
+                // Distance (source px) of the pixel center outside the square
+                let source = |d: u32| (d as f32 + 0.5) / ratio;
+                let (sx, sy) = (source(i as u32 % size), source(i as u32 / size));
+                let outside = (2.0 - sx).max(sx - 8.0).max(2.0 - sy).max(sy - 8.0);
+                let alpha = pixel[3];
+                // Catmull-Rom reaches 2 source px (further when shrinking);
+                // past one, only the corner lobes' 1/255 is left
+                let reach = 2.0 / factor.min(1.0);
+                if outside > reach {
+                    assert_eq!(alpha, 0, "{}x: ringing at {:?}", factor, (sx, sy));
+                } else if outside > 1.0 {
+                    assert!(alpha <= 1, "{}x: ringing at {:?}", factor, (sx, sy));
+                } else if outside < -1.0 {
+                    assert_eq!(alpha, 255, "{}x: dip at {:?}", factor, (sx, sy));
+                }
+                assert!(pixel[..3].iter().all(|&c| c <= alpha), "{}x", factor);
+            }
+        }
+    }
+
+    #[test]
+    fn edited_sprite_file_is_decoded_again() {
+        let dir = std::env::temp_dir().join(format!("renderer-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cursor.png");
+        image::RgbaImage::new(4, 4).save(&path).unwrap();
+        let first = load_cursor_sprite_cached(&path).unwrap();
+        assert!(Arc::ptr_eq(
+            &first,
+            &load_cursor_sprite_cached(&path).unwrap()
+        ));
+
+        // A different size changes the length even if the mtime does not tick
+        image::RgbaImage::new(6, 6).save(&path).unwrap();
+        let second = load_cursor_sprite_cached(&path).unwrap();
+        assert_eq!((second.width, second.height), (6, 6));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn full_sprite_cache_evicts_the_least_recently_used() {
+        let mut cache = SpriteCache::default();
+        let stamp = FileStamp {
+            modified: None,
+            len: 1,
+        };
+        let path = |i: usize| PathBuf::from(format!("cursor-{}.png", i));
+        for i in 0..SPRITE_CACHE_CAPACITY {
+            cache.insert(&path(i), stamp, Arc::new(white_sprite(1)));
+        }
+        // Touch the oldest so the second oldest goes instead
+        assert!(cache.get(&path(0), stamp).is_some());
+        cache.insert(
+            &path(SPRITE_CACHE_CAPACITY),
+            stamp,
+            Arc::new(white_sprite(1)),
+        );
+
+        assert_eq!(cache.entries.len(), SPRITE_CACHE_CAPACITY);
+        assert!(cache.get(&path(0), stamp).is_some());
+        assert!(cache.get(&path(1), stamp).is_none());
+        assert!(cache.get(&path(SPRITE_CACHE_CAPACITY), stamp).is_some());
+        // A changed stamp misses without evicting anything
+        let edited = FileStamp { len: 2, ..stamp };
+        assert!(cache.get(&path(2), edited).is_none());
+        assert_eq!(cache.entries.len(), SPRITE_CACHE_CAPACITY);
+    }
 }
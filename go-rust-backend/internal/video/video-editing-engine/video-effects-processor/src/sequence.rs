@@ -0,0 +1,198 @@
+// sequence.rs - Image sequences as input: a directory of numbered PNG frames,
+// or a printf-style pattern such as frame_%05d.png, played back through
+// ffmpeg's concat demuxer with each frame timed by its number
+
+use crate::engine::EngineError;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// The frames of an image sequence. Frame N is shown (N - first) frame
+/// periods after the first, so a missing number holds the frame before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageSequence {
+    /// Frame numbers and files, in order
+    frames: Vec<(u64, PathBuf)>,
+}
+
+impl ImageSequence {
+    /// The sequence `path` names: every PNG in a directory whose name ends in
+    /// a frame number, or the files matching a pattern with one `%d` or
+    /// `%0Nd` in its file name. None for an existing file or any other path.
+    pub fn find(path: &Path) -> Result<Option<Self>, EngineError> {
+        let (dir, names) = if path.is_dir() {
+            (path, FileNames::NumberedPng)
+        } else if path.exists() {
+            return Ok(None);
+        } else {
+            let Some(names) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(FileNames::pattern)
+            else {
+                return Ok(None);
+            };
+            let dir = path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            (dir, names)
+        };
+        // The concat demuxer resolves paths against the script, not the
+        // working directory
+        let dir = std::path::absolute(dir)?;
+        if dir.to_str().is_none() {
+            return Err(EngineError::InvalidPath(dir.display().to_string()));
+        }
+
+        let mut frames = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let number = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| names.frame_number(name));
+            if let Some(number) = number {
+                frames.push((number, entry.path()));
+            }
+        }
+        frames.sort();
+        if frames.is_empty() {
+            return Err(EngineError::InvalidSequence(format!(
+                "no frames found for {}",
+                path.display()
+            )));
+        }
+        if let Some(pair) = frames.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(EngineError::InvalidSequence(format!(
+                "{} and {} are both frame {}",
+                pair[0].1.display(),
+                pair[1].1.display(),
+                pair[0].0
+            )));
+        }
+        Ok(Some(Self { frames }))
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Numbers between the first frame and the last with no file
+    pub fn missing_frames(&self) -> u64 {
+        let first = self.frames[0].0;
+        let last = self.frames[self.frames.len() - 1].0;
+        last - first + 1 - self.frames.len() as u64
+    }
+
+    /// The first number with no file, if any
+    pub fn first_missing(&self) -> Option<u64> {
+        self.frames
+            .windows(2)
+            .find(|pair| pair[1].0 > pair[0].0 + 1)
+            .map(|pair| pair[0].0 + 1)
+    }
+
+    /// ffconcat script playing the frames at `frame_rate`, each one lasting
+    /// until the next frame's number comes up. Times are rounded to whole
+    /// µs from the first frame, so they do not drift over long sequences.
+    pub fn concat_script(&self, frame_rate: i32) -> String {
+        let first = self.frames[0].0;
+        let start_us = |number: u64| {
+            ((number - first) as f64 * 1_000_000.0 / f64::from(frame_rate)).round() as u64
+        };
+        let mut script = String::from("ffconcat version 1.0\n");
+        for (i, (number, path)) in self.frames.iter().enumerate() {
+            let next = self.frames.get(i + 1).map_or(number + 1, |(next, _)| *next);
+            let duration_us = start_us(next) - start_us(*number);
+            // Quoted, with the file: scheme so the script's own URL is never
+            // used as a base; a quote is closed, escaped and reopened
+            let path = path.to_string_lossy().replace('\'', r"'\''");
+            let _ = writeln!(script, "file 'file:{}'", path);
+            // The image demuxer's own timing, so timestamps map exactly
+            let _ = writeln!(script, "option framerate {}", frame_rate);
+            let _ = writeln!(
+                script,
+                "duration {}.{:06}",
+                duration_us / 1_000_000,
+                duration_us % 1_000_000
+            );
+        }
+        script
+    }
+}
+
+/// Which file names in the directory are frames, and their numbers
+enum FileNames {
+    /// `*.png` (any case), numbered by the digits the name ends in
+    NumberedPng,
+    /// `prefix`, then a number of at least `width` digits, then `suffix`
+    Pattern {
+        prefix: String,
+        width: usize,
+        suffix: String,
+    },
+}
+
+impl FileNames {
+    /// A file name with one `%d` or `%0Nd` conversion (`%%` for a literal %)
+    fn pattern(name: &str) -> Option<Self> {
+        let (mut prefix, mut rest) = (String::new(), name);
+        loop {
+            let at = rest.find('%')?;
+            prefix.push_str(&rest[..at]);
+            rest = &rest[at + 1..];
+            match rest.strip_prefix('%') {
+                Some(after) => {
+                    prefix.push('%');
+                    rest = after;
+                }
+                None => break,
+            }
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let (spec, rest) = rest.split_at(digits);
+        let suffix = rest.strip_prefix('d')?;
+        let width = match spec {
+            "" => 1,
+            _ if spec.starts_with('0') => spec.parse().ok()?,
+            _ => return None,
+        };
+        if suffix.contains('%') {
+            return None;
+        }
+        Some(Self::Pattern {
+            prefix,
+            width,
+            suffix: suffix.to_string(),
+        })
+    }
+
+    fn frame_number(&self, name: &str) -> Option<u64> {
+        let digits = match self {
+            Self::NumberedPng => {
+                let split = name.len().checked_sub(4)?;
+                let (stem, extension) = (name.get(..split)?, name.get(split..)?);
+                if !extension.eq_ignore_ascii_case(".png") {
+                    return None;
+                }
+                &stem[stem.trim_end_matches(|c: char| c.is_ascii_digit()).len()..]
+            }
+            Self::Pattern {
+                prefix,
+                width,
+                suffix,
+            } => {
+                let digits = name.strip_prefix(prefix.as_str())?;
+                let digits = digits.strip_suffix(suffix.as_str())?;
+                if digits.len() < *width || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                digits
+            }
+        };
+        if digits.is_empty() {
+            return None;
+        }
+        digits.parse().ok()
+    }
+}
@@ -1,5 +1,18 @@
 // Dual-pass cursor path smoothing: Physics filtering + Catmull-Rom interpolation
+use crate::constants::{
+    ACCELERATION_MAX_PX_PER_SEC2, BEZIER_DWELL_MS, BEZIER_DWELL_SPEED_PX_PER_SEC,
+    CLICK_ANCHOR_BLEND_MS, DECIMATION_EXTREMUM_PX, KALMAN_MEASUREMENT_NOISE, KALMAN_PROCESS_NOISE,
+    ONE_EURO_BETA, ONE_EURO_D_CUTOFF, ONE_EURO_MIN_CUTOFF, OUTLIER_CONFIRM_SAMPLES,
+    PHYSICS_MAX_DT_SEC, PHYSICS_MIN_DT_SEC, PHYSICS_STABLE_STEP_FRACTION,
+    RESPONSIVENESS_MAX_SETTLING_SEC, RESPONSIVENESS_MIN_SETTLING_SEC, REST_SETTLE_TIME_CONSTANT_S,
+    SETTLING_TOLERANCE, SIMPLIFY_MAX_GAP_MS, SMOOTHNESS_MAX_DAMPING, SMOOTHNESS_MIN_DAMPING,
+    SPRING_MASS_DEFAULT, TIMESTAMP_BACKSTEP_TOLERANCE_MS, VELOCITY_MAX_PX_PER_SEC,
+};
+use crate::engine::EngineError;
+use crate::types::PathPoint;
+use rayon::prelude::*;
 use std::cmp::Ordering;
+use std::f32::consts::PI;
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -9,327 +22,3360 @@ pub struct CPoint {
     pub timestamp_ms: f64,
 }
 
+/// `CEventPoint::event_type` values
+pub const EVENT_MOVE: i32 = 0;
+pub const EVENT_DOWN: i32 = 1;
+pub const EVENT_UP: i32 = 2;
+
+/// Cursor sample tagged with the input event that produced it
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CEventPoint {
+    pub x: f32,
+    pub y: f32,
+    pub timestamp_ms: f64,
+    pub event_type: i32, // EVENT_*
+}
+
+/// Split tagged samples into plain points and the indices of the button
+/// events, which the smoothed path must pass through exactly. Unknown event
+/// types are treated as moves.
+pub fn split_events(events: &[CEventPoint]) -> (Vec<CPoint>, Vec<usize>) {
+    let points = events
+        .iter()
+        .map(|e| CPoint {
+            x: e.x,
+            y: e.y,
+            timestamp_ms: e.timestamp_ms,
+        })
+        .collect();
+    let clicks = events
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| matches!(e.event_type, EVENT_DOWN | EVENT_UP))
+        .map(|(i, _)| i)
+        .collect();
+    (points, clicks)
+}
+
+/// `CClickEvent::button` values
+pub const BUTTON_LEFT: i32 = 0;
+pub const BUTTON_RIGHT: i32 = 1;
+pub const BUTTON_MIDDLE: i32 = 2;
+
+/// Mouse button press, recorded on the same clock and in the same
+/// coordinates as the cursor samples
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CClickEvent {
+    pub x: f32,
+    pub y: f32,
+    pub timestamp_ms: f64,
+    pub button: i32, // BUTTON_*
+}
+
+/// A left click at every button-down sample (event-tagged samples carry no
+/// button)
+pub fn click_events(events: &[CEventPoint]) -> Vec<CClickEvent> {
+    events
+        .iter()
+        .filter(|e| e.event_type == EVENT_DOWN)
+        .map(|e| CClickEvent {
+            x: e.x,
+            y: e.y,
+            timestamp_ms: e.timestamp_ms,
+            button: BUTTON_LEFT,
+        })
+        .collect()
+}
+
+/// Move clicks onto the rendered timeline and into video pixels, the way
+/// `smooth_cursor_path_dual_pass` moves `raw_points`. Clicks with non-finite
+/// values or outside the captured region are dropped; the rest come back
+/// sorted by time.
+pub fn clicks_to_video_space(
+    raw_points: &[CPoint],
+    clicks: &[CClickEvent],
+    cleanup: &PathCleanup,
+) -> Vec<CClickEvent> {
+    let Some((start_offset, ms_per_unit)) = relative_timeline(raw_points, cleanup.timestamp_unit)
+    else {
+        return Vec::new();
+    };
+    let mut mapped: Vec<CClickEvent> = clicks
+        .iter()
+        .filter(|c| c.x.is_finite() && c.y.is_finite() && c.timestamp_ms.is_finite())
+        .filter_map(|c| {
+            let point = CPoint {
+                x: c.x,
+                y: c.y,
+                timestamp_ms: (c.timestamp_ms - start_offset) * ms_per_unit,
+            };
+            let (point, inside) = match &cleanup.coordinates {
+                Some(space) => space.map(&point),
+                None => (point, true),
+            };
+            inside.then_some(CClickEvent {
+                x: point.x,
+                y: point.y,
+                timestamp_ms: point.timestamp_ms,
+                button: c.button,
+            })
+        })
+        .collect();
+    mapped.sort_by(|a, b| a.timestamp_ms.total_cmp(&b.timestamp_ms));
+    mapped
+}
+
+/// Key press to show on screen, recorded on the same clock as the cursor
+/// samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyEvent {
+    pub timestamp_ms: f64,
+    /// What to display, e.g. "⌘+Shift+4"
+    pub text: String,
+}
+
+/// Move key presses onto the rendered timeline (see `clicks_to_video_space`).
+/// Presses with a non-finite time or blank text are dropped; the rest come
+/// back sorted by time.
+pub fn keys_to_video_time(
+    raw_points: &[CPoint],
+    keys: &[KeyEvent],
+    unit: TimestampUnit,
+) -> Vec<KeyEvent> {
+    let Some((start_offset, ms_per_unit)) = relative_timeline(raw_points, unit) else {
+        return Vec::new();
+    };
+    let mut mapped: Vec<KeyEvent> = keys
+        .iter()
+        .filter(|k| k.timestamp_ms.is_finite() && !k.text.trim().is_empty())
+        .map(|k| KeyEvent {
+            timestamp_ms: (k.timestamp_ms - start_offset) * ms_per_unit,
+            text: k.text.clone(),
+        })
+        .collect();
+    mapped.sort_by(|a, b| a.timestamp_ms.total_cmp(&b.timestamp_ms));
+    mapped
+}
+
+/// The cursor changing shape, recorded on the same clock as the cursor
+/// samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorKindEvent {
+    pub timestamp_ms: f64,
+    /// Shape from this time on, e.g. "arrow", "ibeam" or "pointing_hand"
+    pub kind: String,
+}
+
+/// Move cursor kind changes onto the rendered timeline (see
+/// `keys_to_video_time`). Changes with a non-finite time are dropped; the
+/// rest come back sorted by time.
+pub fn cursor_kinds_to_video_time(
+    raw_points: &[CPoint],
+    events: &[CursorKindEvent],
+    unit: TimestampUnit,
+) -> Vec<CursorKindEvent> {
+    let Some((start_offset, ms_per_unit)) = relative_timeline(raw_points, unit) else {
+        return Vec::new();
+    };
+    let mut mapped: Vec<CursorKindEvent> = events
+        .iter()
+        .filter(|e| e.timestamp_ms.is_finite())
+        .map(|e| CursorKindEvent {
+            timestamp_ms: (e.timestamp_ms - start_offset) * ms_per_unit,
+            kind: e.kind.clone(),
+        })
+        .collect();
+    // Stable, so simultaneous changes keep their recorded order
+    mapped.sort_by(|a, b| a.timestamp_ms.total_cmp(&b.timestamp_ms));
+    mapped
+}
+
+/// Curve used for the second (upsampling) pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Centripetal Catmull-Rom through the filtered positions
+    #[default]
+    CatmullRom,
+    /// Cubic Hermite using the filter's velocities as tangents; follows
+    /// direction changes without cutting corners
+    Hermite,
+}
+
+/// Capture gaps longer than `max_gap_ms` are not interpolated across: the
+/// cursor holds its pre-gap position and eases to the post-gap one during the
+/// last `ramp_ms` of the gap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GapHold {
+    pub max_gap_ms: f64,
+    pub ramp_ms: f64,
+}
+
+impl GapHold {
+    fn spans(&self, from_ms: f64, to_ms: f64) -> bool {
+        to_ms - from_ms > self.max_gap_ms
+    }
+
+    /// Position at `t_ms` inside the gap between `before` and `after`
+    fn position(&self, before: &CPoint, after: &CPoint, t_ms: f64) -> (f32, f32) {
+        let gap_ms = after.timestamp_ms - before.timestamp_ms;
+        let ramp_start = after.timestamp_ms - self.ramp_ms.min(gap_ms);
+        if t_ms <= ramp_start {
+            return (before.x, before.y);
+        }
+
+        // Smoothstep: no velocity jump at either end of the ramp
+        let s = ((t_ms - ramp_start) / (after.timestamp_ms - ramp_start)).clamp(0.0, 1.0) as f32;
+        let s = s * s * (3.0 - 2.0 * s);
+        (
+            before.x + (after.x - before.x) * s,
+            before.y + (after.y - before.y) * s,
+        )
+    }
+}
+
+/// Once the raw cursor has stayed within `radius_px` for `dwell_ms`, the
+/// spring filter settles exactly onto it with zero velocity instead of
+/// creeping toward it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RestSnap {
+    pub radius_px: f32,
+    pub dwell_ms: f64,
+}
+
+/// Spring-filter settings beyond responsiveness/smoothness
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SpringTuning {
+    pub rest: Option<RestSnap>,
+    /// Longest integration substep (s); None = derived from the stiffness so
+    /// the simulation stays stable
+    pub max_step_sec: Option<f32>,
+    /// Map responsiveness/smoothness to settling time and damping ratio
+    /// (see `settling_time_spring`) instead of the original linear mapping
+    pub physics_model_v2: bool,
+}
+
+/// Handling of samples recorded outside the capture region
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutsideRegion {
+    /// Hide the cursor while it is away (output positions become NaN)
+    #[default]
+    Hide,
+    /// Pin the cursor to the region edge
+    Clamp,
+}
+
+/// Maps recorded coordinates (global desktop coordinates on a multi-monitor
+/// setup, points rather than pixels on HiDPI displays) into video pixels:
+/// `(p - origin) * scale`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordinateSpace {
+    /// Top-left of the captured region, in recorded units
+    pub origin_x: f32,
+    pub origin_y: f32,
+    /// Video pixels per recorded unit (2.0 on a 2x Retina display)
+    pub scale_x: f32,
+    pub scale_y: f32,
+    /// Region size in recorded units (None = unbounded, nothing is outside)
+    pub size: Option<(f32, f32)>,
+    pub outside: OutsideRegion,
+}
+
+impl CoordinateSpace {
+    /// Recording of one display, given its bounds in desktop coordinates
+    pub fn display(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            origin_x: x,
+            origin_y: y,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            size: Some((width, height)),
+            outside: OutsideRegion::Hide,
+        }
+    }
+
+    /// Coordinates that only need scaling (e.g. full-screen HiDPI capture)
+    pub fn scaled(scale: f32) -> Self {
+        Self {
+            origin_x: 0.0,
+            origin_y: 0.0,
+            scale_x: scale,
+            scale_y: scale,
+            size: None,
+            outside: OutsideRegion::Hide,
+        }
+    }
+
+    /// Scale that maps the region onto a `width` x `height` video; None when
+    /// the region size is unknown
+    pub fn scale_for_video(&self, width: u32, height: u32) -> Option<(f32, f32)> {
+        let (w, h) = self.size?;
+        Some((width as f32 / w, height as f32 / h))
+    }
+
+    /// `p` in video pixels, and whether it was recorded inside the region
+    fn map(&self, p: &CPoint) -> (CPoint, bool) {
+        let (mut x, mut y) = (p.x - self.origin_x, p.y - self.origin_y);
+        let inside = self
+            .size
+            .is_none_or(|(w, h)| (0.0..=w).contains(&x) && (0.0..=h).contains(&y));
+        if let (false, OutsideRegion::Clamp, Some((w, h))) = (inside, self.outside, self.size) {
+            x = x.clamp(0.0, w);
+            y = y.clamp(0.0, h);
+        }
+        let mapped = CPoint {
+            x: x * self.scale_x,
+            y: y * self.scale_y,
+            timestamp_ms: p.timestamp_ms,
+        };
+        (mapped, inside)
+    }
+}
+
+/// Unit of the raw `timestamp_ms` values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampUnit {
+    /// Guess from the duration: under 1000 units means seconds. Misreads
+    /// millisecond recordings shorter than one second.
+    #[default]
+    Auto,
+    Milliseconds,
+    Seconds,
+    Microseconds,
+}
+
+impl TimestampUnit {
+    /// Factor converting this unit to milliseconds (None for `Auto`)
+    fn ms_per_unit(self) -> Option<f64> {
+        match self {
+            Self::Auto => None,
+            Self::Milliseconds => Some(1.0),
+            Self::Seconds => Some(1000.0),
+            Self::Microseconds => Some(0.001),
+        }
+    }
+}
+
+/// Clean-up applied to the raw samples before the filter pass
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PathCleanup {
+    /// Unit of the incoming timestamps
+    pub timestamp_unit: TimestampUnit,
+    /// Map recorded coordinates into video pixels (see `CoordinateSpace`)
+    pub coordinates: Option<CoordinateSpace>,
+    /// Drop glitch samples faster than this (see `reject_outliers`)
+    pub max_speed_px_per_sec: Option<f64>,
+    /// Downsample denser input to this rate (see `decimate_path`)
+    pub max_input_rate_hz: Option<f64>,
+    /// Drop filtered points within this distance (px) of the simplified path
+    /// before upsampling (see `simplify_path`)
+    pub simplify_epsilon_px: Option<f32>,
+}
+
+/// Filter used for the first (jitter removal) pass
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmoothingMode {
+    /// Spring-damper driven by `responsiveness`/`smoothness`
+    Spring,
+    /// Speed-adaptive low-pass: heavy smoothing at rest, little lag on fast
+    /// flicks. `responsiveness`/`smoothness` are ignored.
+    OneEuro {
+        min_cutoff: f32, // Hz
+        beta: f32,       // Hz per px/s
+        d_cutoff: f32,   // Hz
+    },
+    /// Constant-velocity Kalman filter; copes best with sparse, irregular
+    /// event rates. `responsiveness`/`smoothness` are ignored.
+    Kalman {
+        process_noise: f32,     // px^2/s^3
+        measurement_noise: f32, // px^2
+    },
+    /// No filtering: the sanitized samples are joined by straight lines on
+    /// the frame grid, for checking capture sync. `responsiveness`,
+    /// `smoothness` and `interpolation` are ignored.
+    Passthrough,
+    /// Each gesture between two dwells becomes one least-squares cubic
+    /// Bezier, traversed with ease-in-out timing over the gesture's own
+    /// duration. Replaces both passes; `responsiveness`/`smoothness`,
+    /// `interpolation` and path simplification are ignored.
+    BezierStrokes {
+        dwell_speed_px_per_sec: f32,
+        dwell_ms: f32,
+    },
+}
+
+impl SmoothingMode {
+    /// One Euro filter with the defaults from constants.rs
+    pub fn one_euro() -> Self {
+        Self::OneEuro {
+            min_cutoff: ONE_EURO_MIN_CUTOFF,
+            beta: ONE_EURO_BETA,
+            d_cutoff: ONE_EURO_D_CUTOFF,
+        }
+    }
+
+    /// Kalman filter with the defaults from constants.rs
+    pub fn kalman() -> Self {
+        Self::Kalman {
+            process_noise: KALMAN_PROCESS_NOISE,
+            measurement_noise: KALMAN_MEASUREMENT_NOISE,
+        }
+    }
+
+    /// Bezier strokes with the defaults from constants.rs
+    pub fn bezier_strokes() -> Self {
+        Self::BezierStrokes {
+            dwell_speed_px_per_sec: BEZIER_DWELL_SPEED_PX_PER_SEC,
+            dwell_ms: BEZIER_DWELL_MS,
+        }
+    }
+}
+
+// ============================================================================
+// PASS 0: Input Sanitation
+// ============================================================================
+
+/// Make a raw path safe for the filters: drop non-finite samples, merge
+/// samples sharing a timestamp (averaging their positions) and fold small
+/// backwards steps into the previous sample.
+///
+/// Fails on a backwards step larger than `TIMESTAMP_BACKSTEP_TOLERANCE_MS`;
+/// such data is not a single recording and cannot be repaired.
+pub fn sanitize_path(points: &[CPoint]) -> Result<Vec<CPoint>, EngineError> {
+    let mut clean: Vec<CPoint> = Vec::with_capacity(points.len());
+    let mut merged = 1;
+    let mut non_finite = 0;
+
+    for (index, p) in points.iter().enumerate() {
+        if !p.x.is_finite() || !p.y.is_finite() || !p.timestamp_ms.is_finite() {
+            non_finite += 1;
+            continue;
+        }
+
+        if let Some(last) = clean.last_mut() {
+            let step_ms = p.timestamp_ms - last.timestamp_ms;
+            if step_ms < -TIMESTAMP_BACKSTEP_TOLERANCE_MS {
+                return Err(EngineError::NonMonotonicTimestamps {
+                    index,
+                    backstep_ms: -step_ms,
+                });
+            }
+            if step_ms <= 0.0 {
+                // Coalesced event: running average keeps the merged position centered
+                merged += 1;
+                let w = 1.0 / merged as f32;
+                last.x += (p.x - last.x) * w;
+                last.y += (p.y - last.y) * w;
+                continue;
+            }
+        }
+
+        clean.push(*p);
+        merged = 1;
+    }
+
+    if non_finite > 0 || clean.len() < points.len() {
+        log::debug!(
+            "Sanitized cursor path: {} -> {} samples ({} non-finite)",
+            points.len(),
+            clean.len(),
+            non_finite
+        );
+    }
+
+    Ok(clean)
+}
+
+/// Map a sanitized path into video pixels.
+///
+/// With `OutsideRegion::Hide`, also returns each sample's time and whether it
+/// was inside the region (see `hide_outside_region`); None when nothing has
+/// to be hidden.
+fn map_to_video_space(
+    points: &[CPoint],
+    space: &CoordinateSpace,
+) -> (Vec<CPoint>, Option<Vec<(f64, bool)>>) {
+    let (mapped, inside): (Vec<CPoint>, Vec<bool>) = points.iter().map(|p| space.map(p)).unzip();
+
+    let outside_count = inside.iter().filter(|&&inside| !inside).count();
+    if outside_count == 0 {
+        return (mapped, None);
+    }
+    log::debug!(
+        "{} of {} samples lie outside the capture region ({:?})",
+        outside_count,
+        points.len(),
+        space.outside
+    );
+    if space.outside != OutsideRegion::Hide {
+        return (mapped, None);
+    }
+
+    let visibility = points
+        .iter()
+        .zip(inside)
+        .map(|(p, inside)| (p.timestamp_ms, inside))
+        .collect();
+    (mapped, Some(visibility))
+}
+
+/// NaN out output points whose nearest-in-time sample was recorded outside
+/// the capture region; the renderers draw no cursor at non-finite positions.
+fn hide_outside_region(points: &mut [CPoint], visibility: &[(f64, bool)]) {
+    if visibility.is_empty() {
+        return;
+    }
+
+    let mut j = 0;
+    for p in points.iter_mut() {
+        while j + 1 < visibility.len()
+            && (visibility[j + 1].0 - p.timestamp_ms).abs()
+                <= (visibility[j].0 - p.timestamp_ms).abs()
+        {
+            j += 1;
+        }
+        if !visibility[j].1 {
+            p.x = f32::NAN;
+            p.y = f32::NAN;
+        }
+    }
+}
+
+// ============================================================================
+// PASS 0b: Outlier Rejection (Optional)
+// ============================================================================
+
+/// Drop isolated samples that imply a speed above `max_speed_px_per_sec`.
+///
+/// A jump is kept when the next `OUTLIER_CONFIRM_SAMPLES` samples continue
+/// from the new position at plausible speed (a real warp); otherwise it is a
+/// glitch and the sample is removed. The first sample is always kept.
+pub fn reject_outliers(points: &[CPoint], max_speed_px_per_sec: f64) -> Vec<CPoint> {
+    let Some(&first) = points.first() else {
+        return Vec::new();
+    };
+
+    let mut kept = Vec::with_capacity(points.len());
+    kept.push(first);
+    let mut prev = first;
+
+    for i in 1..points.len() {
+        if speed_between(&prev, &points[i]) > max_speed_px_per_sec {
+            let window = &points[i..(i + 1 + OUTLIER_CONFIRM_SAMPLES).min(points.len())];
+            let sustained = window.len() == OUTLIER_CONFIRM_SAMPLES + 1
+                && window
+                    .windows(2)
+                    .all(|w| speed_between(&w[0], &w[1]) <= max_speed_px_per_sec);
+            if !sustained {
+                continue;
+            }
+        }
+        kept.push(points[i]);
+        prev = points[i];
+    }
+
+    kept
+}
+
+/// Downsample a sanitized path to at most `max_rate_hz` samples per second.
+///
+/// Samples are averaged within time buckets of `1000 / max_rate_hz` ms. The
+/// first and last samples are kept as-is, and a bucket holding a direction
+/// reversal (a local extremum in x or y) keeps that sample instead of the
+/// average so flick turnarounds are not shaved off. Paths whose mean rate is
+/// already at or below the target are returned unchanged.
+pub fn decimate_path(points: &[CPoint], max_rate_hz: f64) -> Vec<CPoint> {
+    if points.len() < 3 || max_rate_hz <= 0.0 {
+        return points.to_vec();
+    }
+
+    let start = points[0].timestamp_ms;
+    let last = points.len() - 1;
+    let duration_ms = points[last].timestamp_ms - start;
+    if last as f64 <= duration_ms * max_rate_hz / 1000.0 {
+        return points.to_vec();
+    }
+
+    let bucket_ms = 1000.0 / max_rate_hz;
+    let bucket_of = |p: &CPoint| ((p.timestamp_ms - start) / bucket_ms).floor();
+
+    let mut decimated = Vec::with_capacity((duration_ms / bucket_ms) as usize + 2);
+    decimated.push(points[0]);
+
+    let mut i = 1;
+    while i < last {
+        let bucket = bucket_of(&points[i]);
+        let mut end = i + 1;
+        while end < last && bucket_of(&points[end]) == bucket {
+            end += 1;
+        }
+
+        match (i..end).find(|&k| is_direction_reversal(points, k)) {
+            Some(k) => decimated.push(points[k]),
+            None => decimated.push(average_point(&points[i..end])),
+        }
+        i = end;
+    }
+
+    decimated.push(points[last]);
+    decimated
+}
+
+/// True when the path turns back in x or y at `k` by more than sensor noise
+fn is_direction_reversal(points: &[CPoint], k: usize) -> bool {
+    let (prev, cur, next) = (&points[k - 1], &points[k], &points[k + 1]);
+    let reverses = |a: f32, b: f32, c: f32| {
+        let (before, after) = (b - a, c - b);
+        before * after < 0.0 && before.abs().min(after.abs()) >= DECIMATION_EXTREMUM_PX
+    };
+    reverses(prev.x, cur.x, next.x) || reverses(prev.y, cur.y, next.y)
+}
+
+fn average_point(points: &[CPoint]) -> CPoint {
+    let n = points.len() as f64;
+    let (sx, sy, st) = points.iter().fold((0.0, 0.0, 0.0), |(sx, sy, st), p| {
+        (sx + p.x as f64, sy + p.y as f64, st + p.timestamp_ms)
+    });
+    CPoint {
+        x: (sx / n) as f32,
+        y: (sy / n) as f32,
+        timestamp_ms: st / n,
+    }
+}
+
+/// Speed (px/s) implied by moving from `a` to `b`; identical timestamps count as 1 ms
+fn speed_between(a: &CPoint, b: &CPoint) -> f64 {
+    let dx = (b.x - a.x) as f64;
+    let dy = (b.y - a.y) as f64;
+    let dt = ((b.timestamp_ms - a.timestamp_ms) / 1000.0).max(0.001);
+    (dx * dx + dy * dy).sqrt() / dt
+}
+
 // ============================================================================
 // PASS 1: Physics-Based Filtering (Remove Jitter)
 // ============================================================================
 
-/// Apply spring-damper physics to filter jitter at native sample rate
+/// Apply spring-damper physics to filter jitter at native sample rate.
+///
+/// Each output point carries the integrator's velocity (px/s). Every
+/// inter-sample interval is integrated in equal substeps no longer than
+/// `tuning.max_step_sec`. With `tuning.rest` set, a resting cursor settles
+/// exactly on the raw position (see `RestSnap`).
 pub fn apply_physics_filter(
     raw_points: &[CPoint],
     responsiveness: f32, // 0.0-1.0
     smoothness: f32,     // 0.0-1.0
+    tuning: &SpringTuning,
+) -> Vec<PathPoint> {
+    if raw_points.len() < 2 {
+        return with_finite_difference_velocity(raw_points);
+    }
+
+    // Map user-friendly parameters to physics constants
+    let (tension, friction, mass) = if tuning.physics_model_v2 {
+        settling_time_spring(responsiveness, smoothness)
+    } else {
+        let tension = 50.0 + responsiveness * 450.0; // Spring stiffness: 50-500 N/m
+        let friction = 5.0 + smoothness * 45.0; // Damping: 5-50 Ns/m
+        let mass = 1.0; // Inertia: 1 kg
+        (tension, friction, mass)
+    };
+    let max_step = tuning
+        .max_step_sec
+        .unwrap_or_else(|| stable_step_sec(tension, friction, mass));
+
+    let mut filtered = Vec::with_capacity(raw_points.len());
+
+    // Initialize state
+    let mut x = raw_points[0].x;
+    let mut y = raw_points[0].y;
+    let mut vx = 0.0_f32;
+    let mut vy = 0.0_f32;
+
+    filtered.push(PathPoint {
+        x,
+        y,
+        vx,
+        vy,
+        timestamp_ms: raw_points[0].timestamp_ms,
+    });
+
+    // First raw sample of the current still period
+    let mut rest_anchor = raw_points[0];
+
+    // Simulate using actual timestamp deltas
+    // Iterating pairs keeps indexing (and its bounds checks) out of the loop
+    for pair in raw_points.windows(2) {
+        let (prev, raw) = (&pair[0], &pair[1]);
+        let dt = ((raw.timestamp_ms - prev.timestamp_ms) / 1000.0) as f32;
+        let dt = dt.clamp(PHYSICS_MIN_DT_SEC, PHYSICS_MAX_DT_SEC); // Timestamp glitches
+
+        let target_x = raw.x;
+        let target_y = raw.y;
+
+        if let Some(rest) = tuning.rest {
+            if distance(&rest_anchor, raw) > rest.radius_px {
+                rest_anchor = *raw;
+            } else if raw.timestamp_ms - rest_anchor.timestamp_ms >= rest.dwell_ms {
+                // At rest: drop all momentum and settle onto the raw position.
+                // Leaving rest restarts the spring from zero velocity, so
+                // there is no pop on re-acceleration.
+                vx = 0.0;
+                vy = 0.0;
+                let k = 1.0 - (-dt / REST_SETTLE_TIME_CONSTANT_S).exp();
+                x += (target_x - x) * k;
+                y += (target_y - y) * k;
+                if (target_x - x).abs() < 0.01 && (target_y - y).abs() < 0.01 {
+                    x = target_x;
+                    y = target_y;
+                }
+
+                filtered.push(PathPoint {
+                    x,
+                    y,
+                    vx,
+                    vy,
+                    timestamp_ms: raw.timestamp_ms,
+                });
+                continue;
+            }
+        }
+
+        // Equal substeps; the target is held for the whole interval
+        let substeps = (dt / max_step).ceil().max(1.0);
+        let h = dt / substeps;
+        for _ in 0..substeps as u32 {
+            // Spring-damper force calculation
+            let dx = target_x - x;
+            let dy = target_y - y;
+            let fx = tension * dx - friction * vx;
+            let fy = tension * dy - friction * vy;
+
+            // Semi-implicit Euler integration
+            let ax = fx / mass;
+            let ay = fy / mass;
+            vx += ax * h;
+            vy += ay * h;
+            x += vx * h;
+            y += vy * h;
+        }
+
+        filtered.push(PathPoint {
+            x,
+            y,
+            vx,
+            vy,
+            timestamp_ms: raw.timestamp_ms,
+        });
+    }
+
+    filtered
+}
+
+/// (stiffness, damping, mass) for the settling-time model.
+///
+/// Responsiveness picks the 2% settling time between
+/// `RESPONSIVENESS_MAX_SETTLING_SEC` and `RESPONSIVENESS_MIN_SETTLING_SEC`;
+/// smoothness picks the damping ratio between `SMOOTHNESS_MIN_DAMPING` and
+/// `SMOOTHNESS_MAX_DAMPING`. The natural frequency is then chosen so a step
+/// response settles in exactly that time.
+fn settling_time_spring(responsiveness: f32, smoothness: f32) -> (f32, f32, f32) {
+    let settling_sec = RESPONSIVENESS_MAX_SETTLING_SEC
+        + (RESPONSIVENESS_MIN_SETTLING_SEC - RESPONSIVENESS_MAX_SETTLING_SEC) * responsiveness;
+    let zeta =
+        SMOOTHNESS_MIN_DAMPING + (SMOOTHNESS_MAX_DAMPING - SMOOTHNESS_MIN_DAMPING) * smoothness;
+
+    let omega = normalized_settling_time(zeta) / settling_sec;
+    let mass = SPRING_MASS_DEFAULT;
+    (mass * omega * omega, 2.0 * zeta * mass * omega, mass)
+}
+
+/// 2% settling time of a unit step response with natural frequency 1 rad/s.
+///
+/// The usual 4/zeta rule is off by ~45% near critical damping, so the exact
+/// step response is scanned for the last exit from the tolerance band.
+fn normalized_settling_time(zeta: f32) -> f32 {
+    let zeta = zeta.clamp(0.05, 1.0) as f64;
+    let step_response = |t: f64| {
+        if zeta >= 1.0 {
+            1.0 - (1.0 + t) * (-t).exp()
+        } else {
+            let wd = (1.0 - zeta * zeta).sqrt();
+            1.0 - (-zeta * t).exp() * ((wd * t).cos() + zeta / wd * (wd * t).sin())
+        }
+    };
+
+    const DT: f64 = 0.001;
+    let horizon = 10.0 / zeta; // Envelope is far inside the band by then
+    let mut settled_at = 0.0;
+    let mut t = 0.0;
+    while t < horizon {
+        if (step_response(t) - 1.0).abs() > SETTLING_TOLERANCE as f64 {
+            settled_at = t + DT;
+        }
+        t += DT;
+    }
+    settled_at as f32
+}
+
+/// Largest semi-implicit Euler step that stays stable for this spring
+fn stable_step_sec(tension: f32, friction: f32, mass: f32) -> f32 {
+    let oscillation_limit = (mass / tension).sqrt(); // 1/omega
+    let damping_limit = mass / friction.max(f32::EPSILON);
+    PHYSICS_STABLE_STEP_FRACTION * oscillation_limit.min(damping_limit)
+}
+
+/// Apply the One Euro filter (Casiez et al., CHI 2012) at native sample rate.
+///
+/// The cutoff rises with cursor speed, so jitter is removed while the cursor
+/// is slow and fast movements pass with little lag.
+pub fn apply_one_euro_filter(
+    raw_points: &[CPoint],
+    min_cutoff: f32, // Hz
+    beta: f32,       // Hz per px/s
+    d_cutoff: f32,   // Hz
 ) -> Vec<CPoint> {
     if raw_points.len() < 2 {
         return raw_points.to_vec();
     }
 
-    // Map user-friendly parameters to physics constants
-    let tension = 50.0 + responsiveness * 450.0; // Spring stiffness: 50-500 N/m
-    let friction = 5.0 + smoothness * 45.0; // Damping: 5-50 Ns/m
-    let mass = 1.0; // Inertia: 1 kg
+    let mut filtered = Vec::with_capacity(raw_points.len());
+
+    // Initialize state
+    let mut x = raw_points[0].x;
+    let mut y = raw_points[0].y;
+    let mut vx = 0.0_f32;
+    let mut vy = 0.0_f32;
+
+    filtered.push(raw_points[0]);
+
+    for i in 1..raw_points.len() {
+        let dt = ((raw_points[i].timestamp_ms - raw_points[i - 1].timestamp_ms) / 1000.0) as f32;
+        let dt = dt.clamp(PHYSICS_MIN_DT_SEC, PHYSICS_MAX_DT_SEC); // Timestamp glitches
+
+        // Low-passed velocity estimate drives the adaptive cutoff
+        let a_d = one_euro_alpha(d_cutoff, dt);
+        vx += a_d * ((raw_points[i].x - x) / dt - vx);
+        vy += a_d * ((raw_points[i].y - y) / dt - vy);
+
+        // Use the speed magnitude for both axes so diagonal moves filter evenly
+        let speed = (vx * vx + vy * vy).sqrt();
+        let a = one_euro_alpha(min_cutoff + beta * speed, dt);
+        x += a * (raw_points[i].x - x);
+        y += a * (raw_points[i].y - y);
+
+        filtered.push(CPoint {
+            x,
+            y,
+            timestamp_ms: raw_points[i].timestamp_ms,
+        });
+    }
+
+    filtered
+}
+
+/// Attach central-difference velocities (px/s) to a filter output that has none
+fn with_finite_difference_velocity(points: &[CPoint]) -> Vec<PathPoint> {
+    let last = points.len().saturating_sub(1);
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let a = &points[i.saturating_sub(1)];
+            let b = &points[(i + 1).min(last)];
+            let dt = ((b.timestamp_ms - a.timestamp_ms) / 1000.0) as f32;
+            let (vx, vy) = if dt > 1e-6 {
+                ((b.x - a.x) / dt, (b.y - a.y) / dt)
+            } else {
+                (0.0, 0.0)
+            };
+            PathPoint {
+                x: p.x,
+                y: p.y,
+                vx,
+                vy,
+                timestamp_ms: p.timestamp_ms,
+            }
+        })
+        .collect()
+}
+
+/// Exponential smoothing factor for a first-order low-pass at `cutoff` Hz
+fn one_euro_alpha(cutoff: f32, dt: f32) -> f32 {
+    let r = 2.0 * PI * cutoff * dt;
+    r / (r + 1.0)
+}
+
+/// Constant-velocity Kalman filter over (x, y, vx, vy) at native sample rate.
+///
+/// Uses the real timestamp deltas, so irregular event rates are handled
+/// exactly. Returns one point per input point with the velocity estimate
+/// (px/s) attached.
+pub fn kalman_filter_path(
+    points: &[CPoint],
+    process_noise: f64,     // px^2/s^3 (random acceleration density)
+    measurement_noise: f64, // px^2
+) -> Vec<PathPoint> {
+    let Some(first) = points.first() else {
+        return Vec::new();
+    };
+
+    // With independent noise on x and y the 4x4 covariance is block-diagonal,
+    // so each axis runs as its own (position, velocity) filter.
+    let mut kx = AxisKalman::new(first.x as f64, measurement_noise);
+    let mut ky = AxisKalman::new(first.y as f64, measurement_noise);
+
+    let mut filtered = Vec::with_capacity(points.len());
+    filtered.push(PathPoint {
+        x: first.x,
+        y: first.y,
+        vx: 0.0,
+        vy: 0.0,
+        timestamp_ms: first.timestamp_ms,
+    });
+
+    for i in 1..points.len() {
+        // Duplicate or backwards timestamps: update without predicting
+        let dt = ((points[i].timestamp_ms - points[i - 1].timestamp_ms) / 1000.0).max(0.0);
+
+        kx.step(points[i].x as f64, dt, process_noise, measurement_noise);
+        ky.step(points[i].y as f64, dt, process_noise, measurement_noise);
+
+        filtered.push(PathPoint {
+            x: kx.pos as f32,
+            y: ky.pos as f32,
+            vx: kx.vel as f32,
+            vy: ky.vel as f32,
+            timestamp_ms: points[i].timestamp_ms,
+        });
+    }
+
+    filtered
+}
+
+/// One axis of the constant-velocity model: state (pos, vel), covariance P
+struct AxisKalman {
+    pos: f64,
+    vel: f64,
+    p00: f64,
+    p01: f64,
+    p11: f64,
+}
+
+impl AxisKalman {
+    /// Unknown initial speed: start with ~1000 px/s of velocity uncertainty
+    fn new(pos: f64, measurement_noise: f64) -> Self {
+        Self {
+            pos,
+            vel: 0.0,
+            p00: measurement_noise,
+            p01: 0.0,
+            p11: 1.0e6,
+        }
+    }
+
+    fn step(&mut self, measured: f64, dt: f64, q: f64, r: f64) {
+        // Predict: x = F x, P = F P F^T + Q (white-noise acceleration)
+        self.pos += self.vel * dt;
+        let dt2 = dt * dt;
+        self.p00 += 2.0 * dt * self.p01 + dt2 * self.p11 + q * dt2 * dt / 3.0;
+        self.p01 += dt * self.p11 + q * dt2 / 2.0;
+        self.p11 += q * dt;
+
+        // Update with the position measurement (H = [1, 0])
+        let s = self.p00 + r;
+        let k0 = self.p00 / s;
+        let k1 = self.p01 / s;
+        let innovation = measured - self.pos;
+        self.pos += k0 * innovation;
+        self.vel += k1 * innovation;
+
+        let (p00, p01) = (self.p00, self.p01);
+        self.p00 -= k0 * p00;
+        self.p01 -= k0 * p01;
+        self.p11 -= k1 * p01;
+    }
+}
+
+// ============================================================================
+// PASS 1b: Path Simplification (Optional)
+// ============================================================================
+
+/// Ramer-Douglas-Peucker simplification: drop points that lie within
+/// `epsilon_px` of the line between the points kept around them.
+///
+/// A span is never longer than `max_gap_ms`, even when it is straight, so
+/// the time-based upsampling pass still sees where the cursor paused.
+pub fn simplify_path(points: &[CPoint], epsilon_px: f32, max_gap_ms: f64) -> Vec<CPoint> {
+    simplify_indices(points, epsilon_px, max_gap_ms)
+        .into_iter()
+        .map(|i| points[i])
+        .collect()
+}
+
+/// Indices (ascending) of the points `simplify_path` keeps
+fn simplify_indices(points: &[CPoint], epsilon_px: f32, max_gap_ms: f64) -> Vec<usize> {
+    if points.len() < 3 {
+        return (0..points.len()).collect();
+    }
+
+    let last = points.len() - 1;
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[last] = true;
+
+    // Explicit stack: dense recordings would overflow a recursive version
+    let mut spans = vec![(0, last)];
+    while let Some((start, end)) = spans.pop() {
+        if end - start < 2 {
+            continue;
+        }
+
+        let (farthest, max_dist) = (start + 1..end)
+            .map(|i| {
+                (
+                    i,
+                    distance_to_segment(&points[i], &points[start], &points[end]),
+                )
+            })
+            .fold((start + 1, -1.0f32), |best, cur| {
+                if cur.1 > best.1 {
+                    cur
+                } else {
+                    best
+                }
+            });
+
+        let split = if max_dist > epsilon_px {
+            farthest
+        } else if points[end].timestamp_ms - points[start].timestamp_ms > max_gap_ms {
+            // Straight but too long: split where the time is halfway
+            let mid_ms = (points[start].timestamp_ms + points[end].timestamp_ms) / 2.0;
+            (start + 1..end)
+                .min_by(|&a, &b| {
+                    (points[a].timestamp_ms - mid_ms)
+                        .abs()
+                        .total_cmp(&(points[b].timestamp_ms - mid_ms).abs())
+                })
+                .unwrap_or(start + 1)
+        } else {
+            continue;
+        };
+
+        keep[split] = true;
+        spans.push((start, split));
+        spans.push((split, end));
+    }
+
+    (0..points.len()).filter(|&i| keep[i]).collect()
+}
+
+/// Distance from `p` to the segment `a`-`b`
+fn distance_to_segment(p: &CPoint, a: &CPoint, b: &CPoint) -> f32 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len_sq = dx * dx + dy * dy;
+    if len_sq < 1e-12 {
+        return distance(a, p);
+    }
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / len_sq).clamp(0.0, 1.0);
+    let closest = CPoint {
+        x: a.x + t * dx,
+        y: a.y + t * dy,
+        timestamp_ms: p.timestamp_ms,
+    };
+    distance(&closest, p)
+}
+
+// ============================================================================
+// PASS 1c: Click Anchors
+// ============================================================================
+
+/// Pull the filtered path onto each anchor (relative ms, video pixels).
+///
+/// The remaining offset at an anchor is spread over `CLICK_ANCHOR_BLEND_MS`
+/// on either side with a linearly decaying weight, then the anchor itself is
+/// inserted as a knot so the spline passes through it exactly.
+fn pin_to_anchors(mut path: Vec<PathPoint>, anchors: &[CPoint]) -> Vec<PathPoint> {
+    if path.is_empty() {
+        return path;
+    }
+
+    for anchor in anchors {
+        let at = sample_at(&path, anchor.timestamp_ms);
+        let (dx, dy) = (anchor.x - at.x, anchor.y - at.y);
+        let from =
+            path.partition_point(|p| p.timestamp_ms < anchor.timestamp_ms - CLICK_ANCHOR_BLEND_MS);
+        let to =
+            path.partition_point(|p| p.timestamp_ms <= anchor.timestamp_ms + CLICK_ANCHOR_BLEND_MS);
+        for p in &mut path[from..to] {
+            let w =
+                (1.0 - (p.timestamp_ms - anchor.timestamp_ms).abs() / CLICK_ANCHOR_BLEND_MS) as f32;
+            p.x += dx * w;
+            p.y += dy * w;
+        }
+    }
+
+    // Knots last: a later anchor's blend may have nudged an earlier one
+    for anchor in anchors {
+        match path.binary_search_by(|p| p.timestamp_ms.total_cmp(&anchor.timestamp_ms)) {
+            Ok(i) => {
+                path[i].x = anchor.x;
+                path[i].y = anchor.y;
+            }
+            Err(i) => {
+                let at = sample_at(&path, anchor.timestamp_ms);
+                path.insert(
+                    i,
+                    PathPoint {
+                        x: anchor.x,
+                        y: anchor.y,
+                        ..at
+                    },
+                );
+            }
+        }
+    }
+
+    path
+}
+
+/// Linear interpolation of position and velocity at `t_ms` (held at the ends)
+fn sample_at(path: &[PathPoint], t_ms: f64) -> PathPoint {
+    let i = path.partition_point(|p| p.timestamp_ms < t_ms);
+    if i == 0 || i == path.len() {
+        let p = path[i.min(path.len() - 1)];
+        return PathPoint {
+            timestamp_ms: t_ms,
+            ..p
+        };
+    }
+
+    let (a, b) = (&path[i - 1], &path[i]);
+    let span = b.timestamp_ms - a.timestamp_ms;
+    let s = if span > 0.0 {
+        ((t_ms - a.timestamp_ms) / span) as f32
+    } else {
+        1.0
+    };
+    PathPoint {
+        x: a.x + (b.x - a.x) * s,
+        y: a.y + (b.y - a.y) * s,
+        vx: a.vx + (b.vx - a.vx) * s,
+        vy: a.vy + (b.vy - a.vy) * s,
+        timestamp_ms: t_ms,
+    }
+}
+
+/// Put each anchor's exact position on the frame closest to it, so the
+/// rendered click lands on the clicked pixel even between frame times
+fn snap_frames_to_anchors(frames: &mut [CPoint], anchors: &[CPoint], frame_rate: i32) {
+    let half_frame_ms = 500.0 / frame_rate as f64;
+    for anchor in anchors {
+        let i = frames.partition_point(|p| p.timestamp_ms < anchor.timestamp_ms);
+        let nearest = [i.checked_sub(1), (i < frames.len()).then_some(i)]
+            .into_iter()
+            .flatten()
+            .min_by(|&a, &b| {
+                (frames[a].timestamp_ms - anchor.timestamp_ms)
+                    .abs()
+                    .total_cmp(&(frames[b].timestamp_ms - anchor.timestamp_ms).abs())
+            });
+        if let Some(k) = nearest {
+            if (frames[k].timestamp_ms - anchor.timestamp_ms).abs() <= half_frame_ms {
+                frames[k].x = anchor.x;
+                frames[k].y = anchor.y;
+            }
+        }
+    }
+}
+
+// ============================================================================
+// PASS 2: Catmull-Rom Spline Interpolation (Upsample to Frame Rate)
+// ============================================================================
+
+/// Interpolate sparse points to match video frame rate using Catmull-Rom splines.
+///
+/// Output timestamps lie on the `grid_origin_ms + k * 1000 / frame_rate` grid
+/// (see `frame_grid`).
+pub fn interpolate_to_framerate(
+    clean_points: &[CPoint],
+    frame_rate: i32,
+    _alpha: f32,
+    gap: Option<GapHold>,
+    grid_origin_ms: f64,
+) -> Vec<CPoint> {
+    // 1. Handle Empty Input
+    if clean_points.is_empty() {
+        return Vec::new();
+    }
+
+    // 2. Handle Single Point (Static Cursor)
+    if clean_points.len() == 1 {
+        // Return at least one point so video processing doesn't fail
+        return vec![clean_points[0]];
+    }
+
+    let start_time = clean_points.first().unwrap().timestamp_ms;
+    let end_time = clean_points.last().unwrap().timestamp_ms;
+
+    // 3. Handle Zero Duration (Start == End)
+    if (end_time - start_time).abs() < 1e-6 {
+        return vec![clean_points[0]];
+    }
+
+    // Frames are independent (each reads a 4-point window), so chunks of the
+    // grid run in parallel. Frame times only increase, so each chunk does one
+    // search and then walks the points forward.
+    let frames: Vec<f64> = frame_grid(start_time, end_time, frame_rate, grid_origin_ms).collect();
+    let mut dense_path: Vec<CPoint> = frames
+        .par_chunks(UPSAMPLE_CHUNK_FRAMES)
+        .flat_map_iter(|chunk| {
+            let mut idx = clean_points.partition_point(|p| p.timestamp_ms < chunk[0]);
+            chunk.iter().map(move |&t_target| {
+                while idx < clean_points.len() && clean_points[idx].timestamp_ms < t_target {
+                    idx += 1;
+                }
+                catmull_rom_frame(clean_points, idx, t_target, gap)
+            })
+        })
+        .collect();
+
+    // Fallback: If logic produced 0 points (e.g. num_frames calculation oddity), force 1 point
+    if dense_path.is_empty() {
+        dense_path.push(clean_points[0]);
+    }
+
+    dense_path
+}
+
+/// Frames per parallel upsampling task; large enough that scheduling costs
+/// vanish next to the spline work
+const UPSAMPLE_CHUNK_FRAMES: usize = 4096;
+
+/// Catmull-Rom position at `t_target`, where `idx` is the first point at or
+/// after it
+fn catmull_rom_frame(
+    clean_points: &[CPoint],
+    idx: usize,
+    t_target: f64,
+    gap: Option<GapHold>,
+) -> CPoint {
+    let len = clean_points.len();
+
+    // Inside a capture gap: hold, then ramp (no spline across the hole)
+    if let Some(gap) = gap {
+        if idx > 0
+            && idx < len
+            && gap.spans(
+                clean_points[idx - 1].timestamp_ms,
+                clean_points[idx].timestamp_ms,
+            )
+        {
+            let (x, y) = gap.position(&clean_points[idx - 1], &clean_points[idx], t_target);
+            return CPoint {
+                x,
+                y,
+                timestamp_ms: t_target,
+            };
+        }
+    }
+
+    // The segment runs from the last point at or before the frame; `idx` is
+    // one past it unless the frame lands exactly on a point
+    let on_point = idx < len && clean_points[idx].timestamp_ms <= t_target;
+    let i1 = if on_point { idx } else { idx.saturating_sub(1) }.min(len - 1);
+    let mut i0 = i1.saturating_sub(1);
+    let mut i2 = (i1 + 1).min(len - 1);
+    let mut i3 = (i1 + 2).min(len - 1);
+
+    // Never let the spline's neighbours reach across a gap
+    if let Some(gap) = gap {
+        let t = |i: usize| clean_points[i].timestamp_ms;
+        if gap.spans(t(i0), t(i1)) {
+            i0 = i1;
+        }
+        if gap.spans(t(i1), t(i2)) {
+            i2 = i1;
+        }
+        if gap.spans(t(i2), t(i3)) {
+            i3 = i2;
+        }
+    }
+
+    let p0 = &clean_points[i0];
+    let p1 = &clean_points[i1];
+    let p2 = &clean_points[i2];
+    let p3 = &clean_points[i3];
+
+    // If time interval is tiny, just use p1 position
+    if (p2.timestamp_ms - p1.timestamp_ms).abs() < 1e-6 {
+        return CPoint {
+            x: p1.x,
+            y: p1.y,
+            timestamp_ms: t_target,
+        };
+    }
+
+    let t = t_target as f32;
+    let x = catmull_rom_1d(
+        t,
+        p0.timestamp_ms as f32,
+        p1.timestamp_ms as f32,
+        p2.timestamp_ms as f32,
+        p3.timestamp_ms as f32,
+        p0.x,
+        p1.x,
+        p2.x,
+        p3.x,
+    );
+    let y = catmull_rom_1d(
+        t,
+        p0.timestamp_ms as f32,
+        p1.timestamp_ms as f32,
+        p2.timestamp_ms as f32,
+        p3.timestamp_ms as f32,
+        p0.y,
+        p1.y,
+        p2.y,
+        p3.y,
+    );
+
+    CPoint {
+        x,
+        y,
+        timestamp_ms: t_target,
+    }
+}
+
+/// Times (ms) of the frames `grid_origin_ms + k * 1000 / frame_rate` from the
+/// first one at or after `start_ms` up to the last one before `end_ms`
+/// (at least one frame).
+///
+/// With the origin at video time 0, every output point lands exactly on an
+/// encoder frame, so the renderer never interpolates between samples.
+fn frame_grid(
+    start_ms: f64,
+    end_ms: f64,
+    frame_rate: i32,
+    grid_origin_ms: f64,
+) -> impl ExactSizeIterator<Item = f64> {
+    let fps = frame_rate as f64;
+    // The epsilon keeps float noise from skipping a frame sitting on `start_ms`
+    let first = ((start_ms - grid_origin_ms) * fps / 1000.0 - 1e-9).ceil() as i64;
+    let end = ((end_ms - grid_origin_ms) * fps / 1000.0).ceil() as i64;
+    let count = (end.max(first + 1) - first) as usize;
+    (0..count).map(move |i| grid_origin_ms + (first + i as i64) as f64 * 1000.0 / fps)
+}
+
+/// Interpolate filtered points onto the frame grid with cubic Hermite segments.
+///
+/// Tangents are the stored velocities, so the curve passes through every
+/// point with the filter's own speed and direction (C1 at every knot). Output
+/// timestamps follow the same grid as `interpolate_to_framerate`.
+pub fn interpolate_to_framerate_hermite(
+    points: &[PathPoint],
+    frame_rate: i32,
+    gap: Option<GapHold>,
+    grid_origin_ms: f64,
+) -> Vec<CPoint> {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return Vec::new();
+    };
+    if points.len() == 1 || (last.timestamp_ms - first.timestamp_ms).abs() < 1e-6 {
+        return vec![CPoint::from(*first)];
+    }
+
+    let frames = frame_grid(
+        first.timestamp_ms,
+        last.timestamp_ms,
+        frame_rate,
+        grid_origin_ms,
+    );
+    let mut dense_path = Vec::with_capacity(frames.len());
+    let mut seg = 0;
+    for t_target in frames {
+        // Frames are monotonic, so the segment index only moves forward
+        while seg + 2 < points.len() && points[seg + 1].timestamp_ms < t_target {
+            seg += 1;
+        }
+        let (p0, p1) = (&points[seg], &points[seg + 1]);
+        let (x, y) = match gap {
+            Some(gap) if gap.spans(p0.timestamp_ms, p1.timestamp_ms) => {
+                gap.position(&CPoint::from(*p0), &CPoint::from(*p1), t_target)
+            }
+            _ => hermite_point(p0, p1, t_target),
+        };
+
+        dense_path.push(CPoint {
+            x,
+            y,
+            timestamp_ms: t_target,
+        });
+    }
+
+    dense_path
+}
+
+/// Evaluate the Hermite segment p0..p1 at `t_ms` (held at the ends)
+fn hermite_point(p0: &PathPoint, p1: &PathPoint, t_ms: f64) -> (f32, f32) {
+    let span_ms = p1.timestamp_ms - p0.timestamp_ms;
+    if span_ms < 1e-6 {
+        return (p1.x, p1.y);
+    }
+
+    let s = ((t_ms - p0.timestamp_ms) / span_ms).clamp(0.0, 1.0) as f32;
+    let h = (span_ms / 1000.0) as f32; // Velocities are per second
+    let s2 = s * s;
+    let s3 = s2 * s;
+    let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+    let h10 = s3 - 2.0 * s2 + s;
+    let h01 = -2.0 * s3 + 3.0 * s2;
+    let h11 = s3 - s2;
+
+    (
+        h00 * p0.x + h10 * h * p0.vx + h01 * p1.x + h11 * h * p1.vx,
+        h00 * p0.y + h10 * h * p0.vy + h01 * p1.y + h11 * h * p1.vy,
+    )
+}
+
+/// Interpolate points onto the frame grid with straight segments.
+///
+/// Used by `SmoothingMode::Passthrough`: a frame landing on a sample shows
+/// exactly that sample, anything else lies on the line between its two
+/// neighbours. Output timestamps follow the same grid as
+/// `interpolate_to_framerate`.
+pub fn interpolate_to_framerate_linear(
+    points: &[CPoint],
+    frame_rate: i32,
+    gap: Option<GapHold>,
+    grid_origin_ms: f64,
+) -> Vec<CPoint> {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return Vec::new();
+    };
+    if points.len() == 1 || (last.timestamp_ms - first.timestamp_ms).abs() < 1e-6 {
+        return vec![*first];
+    }
+
+    let frames = frame_grid(
+        first.timestamp_ms,
+        last.timestamp_ms,
+        frame_rate,
+        grid_origin_ms,
+    );
+    let mut dense_path = Vec::with_capacity(frames.len());
+    let mut seg = 0;
+    for t_target in frames {
+        while seg + 2 < points.len() && points[seg + 1].timestamp_ms < t_target {
+            seg += 1;
+        }
+        let (p0, p1) = (&points[seg], &points[seg + 1]);
+        let (x, y) = match gap {
+            Some(gap) if gap.spans(p0.timestamp_ms, p1.timestamp_ms) => {
+                gap.position(p0, p1, t_target)
+            }
+            _ => linear_point(p0, p1, t_target),
+        };
+
+        dense_path.push(CPoint {
+            x,
+            y,
+            timestamp_ms: t_target,
+        });
+    }
+
+    dense_path
+}
+
+/// Point on the segment p0..p1 at `t_ms` (held at the ends, exact at both)
+fn linear_point(p0: &CPoint, p1: &CPoint, t_ms: f64) -> (f32, f32) {
+    let span_ms = p1.timestamp_ms - p0.timestamp_ms;
+    let s = if span_ms < 1e-6 {
+        1.0
+    } else {
+        (t_ms - p0.timestamp_ms) / span_ms
+    };
+    if s <= 0.0 {
+        (p0.x, p0.y)
+    } else if s >= 1.0 {
+        (p1.x, p1.y)
+    } else {
+        let s = s as f32;
+        (p0.x + (p1.x - p0.x) * s, p0.y + (p1.y - p0.y) * s)
+    }
+}
+
+/// Evaluate Catmull-Rom spline at parameter t using Barry-Goldman algorithm
+#[allow(dead_code)]
+fn catmull_rom_point(
+    t: f32,
+    p0: &CPoint,
+    p1: &CPoint,
+    p2: &CPoint,
+    p3: &CPoint,
+    alpha: f32,
+) -> (f32, f32) {
+    // Knot intervals based on chord length
+    let d01 = distance(p0, p1).powf(alpha).max(1e-6);
+    let d12 = distance(p1, p2).powf(alpha).max(1e-6);
+    let d23 = distance(p2, p3).powf(alpha).max(1e-6);
+
+    let t0 = 0.0;
+    let t1 = t0 + d01;
+    let t2 = t1 + d12;
+    let t3 = t2 + d23;
+
+    let t_mapped = t1 + t * d12;
+
+    let x = catmull_rom_1d(t_mapped, t0, t1, t2, t3, p0.x, p1.x, p2.x, p3.x);
+    let y = catmull_rom_1d(t_mapped, t0, t1, t2, t3, p0.y, p1.y, p2.y, p3.y);
+
+    (x, y)
+}
+
+/// Barry-Goldman recursive formula for 1D Catmull-Rom interpolation
+fn catmull_rom_1d(
+    t: f32,
+    t0: f32,
+    t1: f32,
+    t2: f32,
+    t3: f32,
+    p0: f32,
+    p1: f32,
+    p2: f32,
+    p3: f32,
+) -> f32 {
+    // Helper closure for safe division
+    let safe_lerp = |start_val: f32, end_val: f32, start_t: f32, end_t: f32, current_t: f32| {
+        if (end_t - start_t).abs() < 1e-6 {
+            start_val // Avoid NaN if timestamps are identical
+        } else {
+            let f = (current_t - start_t) / (end_t - start_t);
+            start_val + (end_val - start_val) * f
+        }
+    };
+
+    // Level 1 (Linear)
+    let a1 = safe_lerp(p0, p1, t0, t1, t);
+    let a2 = safe_lerp(p1, p2, t1, t2, t);
+    let a3 = safe_lerp(p2, p3, t2, t3, t);
+
+    // Level 2 (Quadratic)
+    let b1 = safe_lerp(a1, a2, t0, t2, t);
+    let b2 = safe_lerp(a2, a3, t1, t3, t);
+
+    // Level 3 (Cubic) - The Result
+    safe_lerp(b1, b2, t1, t2, t)
+}
+
+fn distance(a: &CPoint, b: &CPoint) -> f32 {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    (dx * dx + dy * dy).sqrt()
+}
+
+#[allow(dead_code)]
+fn find_segment_index(points: &[CPoint], timestamp: f64) -> usize {
+    match points.binary_search_by(|p| {
+        if p.timestamp_ms < timestamp {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }) {
+        Ok(idx) => idx,
+        Err(idx) => idx,
+    }
+}
+
+// ============================================================================
+// PASS 3: Kinematic Limits (Optional)
+// ============================================================================
+
+/// Re-time a dense path along its arc length so the cursor never moves
+/// faster than `VELOCITY_MAX_PX_PER_SEC` or accelerates harder than
+/// `ACCELERATION_MAX_PX_PER_SEC2`.
+///
+/// Works per stroke (a run of frames between two stationary ones). A stroke
+/// within both limits is left untouched; any other stroke gets a trapezoidal
+/// speed profile over its original start and end times, so the path shape
+/// and the sync with the video are kept. A stroke too long to cover in its
+/// duration within the limits gets the lowest peak speed that still covers
+/// it. Timestamps are unchanged; hidden (NaN) frames end a stroke.
+pub fn reprofile_speed(points: &[CPoint]) -> Vec<CPoint> {
+    let mut out = points.to_vec();
+    let moves = |i: usize| {
+        let (a, b) = (&points[i], &points[i + 1]);
+        a.x.is_finite()
+            && a.y.is_finite()
+            && b.x.is_finite()
+            && b.y.is_finite()
+            && distance(a, b) > 1e-3
+    };
+
+    let mut i = 0;
+    while i + 1 < points.len() {
+        if !moves(i) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i + 1 < points.len() && moves(i) {
+            i += 1;
+        }
+        // Frames `start..=i` form the stroke
+        let stroke = &mut out[start..=i];
+        if exceeds_kinematic_limits(stroke) {
+            retime_stroke(stroke);
+        }
+    }
+
+    out
+}
+
+/// Peak speed or acceleration of a stroke (at rest on both ends) above the caps
+fn exceeds_kinematic_limits(stroke: &[CPoint]) -> bool {
+    let mut speeds = vec![0.0];
+    for pair in stroke.windows(2) {
+        let dt = (pair[1].timestamp_ms - pair[0].timestamp_ms) / 1000.0;
+        if dt <= 0.0 {
+            return true;
+        }
+        speeds.push(distance(&pair[0], &pair[1]) as f64 / dt);
+    }
+    speeds.push(0.0);
+
+    let frame_sec = (stroke[1].timestamp_ms - stroke[0].timestamp_ms) / 1000.0;
+    speeds.iter().any(|&v| v > VELOCITY_MAX_PX_PER_SEC)
+        || speeds
+            .windows(2)
+            .any(|v| (v[1] - v[0]).abs() / frame_sec > ACCELERATION_MAX_PX_PER_SEC2)
+}
+
+/// Move the stroke's frames along its own polyline on a trapezoidal profile
+fn retime_stroke(stroke: &mut [CPoint]) {
+    let shape: Vec<CPoint> = stroke.to_vec();
+    let mut arc = Vec::with_capacity(shape.len());
+    arc.push(0.0);
+    for pair in shape.windows(2) {
+        arc.push(arc[arc.len() - 1] + distance(&pair[0], &pair[1]) as f64);
+    }
+
+    let t0 = shape[0].timestamp_ms;
+    let duration = (shape[shape.len() - 1].timestamp_ms - t0) / 1000.0;
+    let length = arc[arc.len() - 1];
+    if duration <= 0.0 || length <= 0.0 {
+        return;
+    }
+
+    // Peak speed v with ramps at `accel`: length = v * (duration - v / accel)
+    let a_max = ACCELERATION_MAX_PX_PER_SEC2;
+    let discriminant = (a_max * duration).powi(2) - 4.0 * a_max * length;
+    let (peak, accel) = if discriminant >= 0.0 {
+        ((a_max * duration - discriminant.sqrt()) / 2.0, a_max)
+    } else {
+        // Even a pure triangle at the cap is too slow; ramp just hard enough
+        (
+            2.0 * length / duration,
+            4.0 * length / (duration * duration),
+        )
+    };
+    if peak > VELOCITY_MAX_PX_PER_SEC {
+        log::debug!(
+            "Stroke of {:.0} px in {:.0} ms cannot stay under {:.0} px/s",
+            length,
+            duration * 1000.0,
+            VELOCITY_MAX_PX_PER_SEC
+        );
+    }
+    let ramp = peak / accel;
+
+    for p in stroke.iter_mut() {
+        let t = (p.timestamp_ms - t0) / 1000.0;
+        let travelled = if t < ramp {
+            0.5 * accel * t * t
+        } else if t < duration - ramp {
+            0.5 * accel * ramp * ramp + peak * (t - ramp)
+        } else {
+            length - 0.5 * accel * (duration - t).powi(2)
+        };
+        let (x, y) = point_at_arc_length(&shape, &arc, travelled.clamp(0.0, length));
+        p.x = x;
+        p.y = y;
+    }
+}
+
+/// Position `s` px along the polyline `shape` (`arc` holds cumulative lengths)
+fn point_at_arc_length(shape: &[CPoint], arc: &[f64], s: f64) -> (f32, f32) {
+    let i = arc.partition_point(|&d| d < s).clamp(1, arc.len() - 1);
+    let span = arc[i] - arc[i - 1];
+    let f = if span > 0.0 {
+        ((s - arc[i - 1]) / span) as f32
+    } else {
+        1.0
+    };
+    let (a, b) = (&shape[i - 1], &shape[i]);
+    (a.x + (b.x - a.x) * f, a.y + (b.y - a.y) * f)
+}
+
+// ============================================================================
+// Bezier Stroke Mode (Replaces Both Passes)
+// ============================================================================
+
+/// One gesture of a path: samples `start..=end`, bounded by dwells or the
+/// ends of the recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stroke {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Split a path into strokes at dwells: runs of samples moving slower than
+/// `dwell_speed_px_per_sec` for at least `dwell_ms`. A stroke ends on the
+/// first sample of the dwell and the next one starts on its last sample.
+pub fn segment_strokes(
+    points: &[CPoint],
+    dwell_speed_px_per_sec: f32,
+    dwell_ms: f64,
+) -> Vec<Stroke> {
+    let mut strokes = Vec::new();
+    if points.len() < 2 {
+        return strokes;
+    }
+
+    let is_slow = |i: usize| {
+        let dt_ms = points[i + 1].timestamp_ms - points[i].timestamp_ms;
+        dt_ms <= 0.0
+            || (distance(&points[i], &points[i + 1]) as f64) * 1000.0 / dt_ms
+                < dwell_speed_px_per_sec as f64
+    };
+
+    let mut stroke_start = 0;
+    let mut i = 0;
+    while i < points.len() - 1 {
+        if !is_slow(i) {
+            i += 1;
+            continue;
+        }
+
+        let slow_start = i;
+        while i < points.len() - 1 && is_slow(i) {
+            i += 1;
+        }
+        // Point `i` is the last sample of the slow run
+        let slow_ms = points[i].timestamp_ms - points[slow_start].timestamp_ms;
+        if slow_ms >= dwell_ms {
+            if slow_start > stroke_start {
+                strokes.push(Stroke {
+                    start: stroke_start,
+                    end: slow_start,
+                });
+            }
+            stroke_start = i;
+        }
+    }
+    if stroke_start < points.len() - 1 {
+        strokes.push(Stroke {
+            start: stroke_start,
+            end: points.len() - 1,
+        });
+    }
+
+    strokes
+}
+
+/// Cubic Bezier control points `[P0, P1, P2, P3]`
+type CubicBezier = [(f64, f64); 4];
+
+/// Least-squares cubic through `points` with the end points fixed at `from`
+/// and the last sample, using chord-length parameters. Falls back to a
+/// straight line when the inner control points are underdetermined.
+fn fit_cubic_bezier(points: &[CPoint], from: (f64, f64)) -> CubicBezier {
+    let last = points[points.len() - 1];
+    let p0 = from;
+    let p3 = (last.x as f64, last.y as f64);
+    let straight = [
+        p0,
+        (p0.0 + (p3.0 - p0.0) / 3.0, p0.1 + (p3.1 - p0.1) / 3.0),
+        (
+            p0.0 + (p3.0 - p0.0) * 2.0 / 3.0,
+            p0.1 + (p3.1 - p0.1) * 2.0 / 3.0,
+        ),
+        p3,
+    ];
+
+    let mut params = Vec::with_capacity(points.len());
+    let mut length = 0.0;
+    params.push(0.0);
+    for pair in points.windows(2) {
+        length += distance(&pair[0], &pair[1]) as f64;
+        params.push(length);
+    }
+    if points.len() < 4 || length < 1e-6 {
+        return straight;
+    }
+
+    // Normal equations for P1, P2 with P0, P3 fixed
+    let (mut c11, mut c12, mut c22) = (0.0, 0.0, 0.0);
+    let (mut x1, mut x2) = ((0.0, 0.0), (0.0, 0.0));
+    for (p, &d) in points.iter().zip(&params) {
+        let t = d / length;
+        let u = 1.0 - t;
+        let (b0, b1, b2, b3) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+        let rx = p.x as f64 - b0 * p0.0 - b3 * p3.0;
+        let ry = p.y as f64 - b0 * p0.1 - b3 * p3.1;
+        c11 += b1 * b1;
+        c12 += b1 * b2;
+        c22 += b2 * b2;
+        x1 = (x1.0 + b1 * rx, x1.1 + b1 * ry);
+        x2 = (x2.0 + b2 * rx, x2.1 + b2 * ry);
+    }
+
+    let det = c11 * c22 - c12 * c12;
+    if det.abs() < 1e-12 {
+        return straight;
+    }
+    [
+        p0,
+        (
+            (x1.0 * c22 - x2.0 * c12) / det,
+            (x1.1 * c22 - x2.1 * c12) / det,
+        ),
+        (
+            (c11 * x2.0 - c12 * x1.0) / det,
+            (c11 * x2.1 - c12 * x1.1) / det,
+        ),
+        p3,
+    ]
+}
+
+fn bezier_point(curve: &CubicBezier, t: f64) -> (f64, f64) {
+    let u = 1.0 - t;
+    let (b0, b1, b2, b3) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+    let [p0, p1, p2, p3] = curve;
+    (
+        b0 * p0.0 + b1 * p1.0 + b2 * p2.0 + b3 * p3.0,
+        b0 * p0.1 + b1 * p1.1 + b2 * p2.1 + b3 * p3.1,
+    )
+}
+
+/// Replace each stroke of `points` (relative ms) by a fitted cubic Bezier and
+/// sample it on the frame grid. Time inside a stroke is eased in and out
+/// (smoothstep), so every gesture starts and stops at rest; dwells hold the
+/// position the previous stroke ended on.
+pub fn bezier_strokes_to_framerate(
+    points: &[CPoint],
+    frame_rate: i32,
+    dwell_speed_px_per_sec: f32,
+    dwell_ms: f64,
+) -> Vec<CPoint> {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return Vec::new();
+    };
+    if points.len() == 1 || (last.timestamp_ms - first.timestamp_ms).abs() < 1e-6 {
+        return vec![*first];
+    }
+
+    // Each stroke starts where the previous one came to rest, so dwell drift
+    // never shows up as a jump
+    let strokes = segment_strokes(points, dwell_speed_px_per_sec, dwell_ms);
+    let mut rest = (first.x as f64, first.y as f64);
+    let mut curves = Vec::with_capacity(strokes.len());
+    for stroke in &strokes {
+        let curve = fit_cubic_bezier(&points[stroke.start..=stroke.end], rest);
+        rest = curve[3];
+        curves.push(curve);
+    }
+
+    let frames = frame_grid(first.timestamp_ms, last.timestamp_ms, frame_rate, 0.0);
+    let mut dense_path = Vec::with_capacity(frames.len());
+    let mut next = 0;
+    let mut held = (first.x as f64, first.y as f64);
+    for t in frames {
+        while next < strokes.len() && points[strokes[next].end].timestamp_ms <= t {
+            held = curves[next][3];
+            next += 1;
+        }
+
+        let (x, y) = match strokes.get(next) {
+            Some(stroke) if points[stroke.start].timestamp_ms < t => {
+                let t0 = points[stroke.start].timestamp_ms;
+                let t1 = points[stroke.end].timestamp_ms;
+                let s = ((t - t0) / (t1 - t0)).clamp(0.0, 1.0);
+                bezier_point(&curves[next], s * s * (3.0 - 2.0 * s))
+            }
+            _ => held,
+        };
+        dense_path.push(CPoint {
+            x: x as f32,
+            y: y as f32,
+            timestamp_ms: t,
+        });
+    }
+
+    dense_path
+}
+
+// ============================================================================
+// Path Metrics
+// ============================================================================
+
+/// Motion statistics of a smoothed path, for judging a tuning
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PathMetrics {
+    pub max_speed_px_per_sec: f64,
+    pub max_acceleration_px_per_sec2: f64,
+    /// Root mean square of the jerk (px/s^3); lower reads as smoother
+    pub rms_jerk_px_per_sec3: f64,
+    /// Largest distance between a smoothed point and the raw sample nearest
+    /// to it in time; high values mean the cursor lags or cuts corners
+    pub max_deviation_px: f64,
+    pub total_length_px: f64,
+}
+
+/// Measure `smoothed` (relative ms, as returned by
+/// `smooth_cursor_path_dual_pass`) against the `raw` input it came from.
+///
+/// Derivatives are finite differences between consecutive points. `raw` is
+/// brought onto the same relative-ms timeline using `unit`.
+pub fn path_metrics(smoothed: &[CPoint], raw: &[CPoint], unit: TimestampUnit) -> PathMetrics {
+    let mut metrics = PathMetrics::default();
+
+    // Hidden stretches (NaN positions, see `hide_outside_region`) are skipped
+    let mut velocities: Vec<(f64, f64, f64)> = Vec::with_capacity(smoothed.len());
+    for w in smoothed
+        .windows(2)
+        .filter(|w| w.iter().all(|p| p.x.is_finite() && p.y.is_finite()))
+    {
+        metrics.total_length_px += distance(&w[0], &w[1]) as f64;
+        if let Some((vx, vy)) = rate_of_change(
+            (w[0].x as f64, w[0].y as f64, w[0].timestamp_ms),
+            (w[1].x as f64, w[1].y as f64, w[1].timestamp_ms),
+        ) {
+            let t = (w[0].timestamp_ms + w[1].timestamp_ms) / 2.0;
+            metrics.max_speed_px_per_sec = metrics.max_speed_px_per_sec.max(vx.hypot(vy));
+            velocities.push((vx, vy, t));
+        }
+    }
+
+    let accelerations: Vec<(f64, f64, f64)> = velocities
+        .windows(2)
+        .filter_map(|w| {
+            let (ax, ay) = rate_of_change(w[0], w[1])?;
+            Some((ax, ay, (w[0].2 + w[1].2) / 2.0))
+        })
+        .collect();
+    for &(ax, ay, _) in &accelerations {
+        metrics.max_acceleration_px_per_sec2 =
+            metrics.max_acceleration_px_per_sec2.max(ax.hypot(ay));
+    }
+
+    let jerk_sq: Vec<f64> = accelerations
+        .windows(2)
+        .filter_map(|w| rate_of_change(w[0], w[1]))
+        .map(|(jx, jy)| jx * jx + jy * jy)
+        .collect();
+    if !jerk_sq.is_empty() {
+        metrics.rms_jerk_px_per_sec3 = (jerk_sq.iter().sum::<f64>() / jerk_sq.len() as f64).sqrt();
+    }
+
+    let reference: Vec<CPoint> = normalize_to_relative_ms(raw, unit)
+        .into_iter()
+        .filter(|p| p.x.is_finite() && p.y.is_finite() && p.timestamp_ms.is_finite())
+        .collect();
+    metrics.max_deviation_px = max_deviation_nearest_in_time(smoothed, &reference);
+
+    metrics
+}
+
+/// Per-second rate of change between two `(x, y, t_ms)` samples
+fn rate_of_change(a: (f64, f64, f64), b: (f64, f64, f64)) -> Option<(f64, f64)> {
+    let dt_sec = (b.2 - a.2) / 1000.0;
+    (dt_sec > 0.0).then(|| ((b.0 - a.0) / dt_sec, (b.1 - a.1) / dt_sec))
+}
+
+/// Largest distance from a smoothed point to its nearest-in-time raw sample.
+/// Both paths are sorted by time, so a single forward scan finds every match.
+fn max_deviation_nearest_in_time(smoothed: &[CPoint], raw: &[CPoint]) -> f64 {
+    if raw.is_empty() {
+        return 0.0;
+    }
+
+    let mut j = 0;
+    let mut max_dev = 0.0f64;
+    for p in smoothed {
+        while j + 1 < raw.len()
+            && (raw[j + 1].timestamp_ms - p.timestamp_ms).abs()
+                <= (raw[j].timestamp_ms - p.timestamp_ms).abs()
+        {
+            j += 1;
+        }
+        max_dev = max_dev.max(distance(p, &raw[j]) as f64);
+    }
+    max_dev
+}
+
+// ============================================================================
+// PUBLIC API: Complete Dual-Pass Pipeline
+// ============================================================================
+
+/// Complete smoothing pipeline: Physics filtering + Spline interpolation.
+///
+/// The input is sanitized first (see `sanitize_path`), then cleaned up as
+/// configured: glitch samples removed (`reject_outliers`) and dense input
+/// downsampled (`decimate_path`). The filtered path can be simplified
+/// (`simplify_path`) before upsampling. Samples listed in `click_anchors`
+/// (indices into `raw_points`, see `split_events`) are hard constraints: the
+/// path passes exactly through them. Returns the dense path, on the video
+/// frame grid (point `k` is frame `k`), and the number of glitch samples
+/// removed.
+pub fn smooth_cursor_path_dual_pass(
+    raw_points: &[CPoint],
+    frame_rate: i32,
+    mode: SmoothingMode,
+    interpolation: Interpolation,
+    responsiveness: f32, // 0.0-1.0 (controls physics spring stiffness)
+    smoothness: f32,     // 0.0-1.0 (controls physics damping)
+    spline_alpha: f32,   // 0.5 recommended (centripetal Catmull-Rom)
+    cleanup: &PathCleanup,
+    gap: Option<GapHold>,
+    spring: &SpringTuning,
+    click_anchors: &[usize],
+) -> Result<(Vec<CPoint>, usize), EngineError> {
+    if raw_points.is_empty() {
+        return Ok((Vec::new(), 0));
+    }
+
+    // Normalize timestamps to relative milliseconds
+    let relative = normalize_to_relative_ms(raw_points, cleanup.timestamp_unit);
+    let mut anchors: Vec<CPoint> = click_anchors
+        .iter()
+        .filter_map(|&i| relative.get(i).copied())
+        .filter(|p| p.x.is_finite() && p.y.is_finite() && p.timestamp_ms.is_finite())
+        .collect();
+    let mut normalized_points = sanitize_path(&relative)?;
+
+    let mut visibility = None;
+    if let Some(space) = &cleanup.coordinates {
+        let (mapped, inside) = map_to_video_space(&normalized_points, space);
+        normalized_points = mapped;
+        visibility = inside;
+        // A click outside the captured region is not visible; nothing to hit
+        anchors = anchors
+            .iter()
+            .map(|a| space.map(a))
+            .filter_map(|(a, inside)| inside.then_some(a))
+            .collect();
+    }
+    anchors.sort_by(|a, b| a.timestamp_ms.total_cmp(&b.timestamp_ms));
+
+    let mut rejected = 0;
+    if let Some(max_speed) = cleanup.max_speed_px_per_sec {
+        let cleaned = reject_outliers(&normalized_points, max_speed);
+        rejected = normalized_points.len() - cleaned.len();
+        if rejected > 0 {
+            log::debug!(
+                "Rejected {} outlier samples above {:.0} px/s",
+                rejected,
+                max_speed
+            );
+        }
+        normalized_points = cleaned;
+    }
+
+    if let Some(max_rate) = cleanup.max_input_rate_hz {
+        let before = normalized_points.len();
+        normalized_points = decimate_path(&normalized_points, max_rate);
+        if normalized_points.len() < before {
+            log::debug!(
+                "Decimated {} input samples to {} (max {:.0} Hz)",
+                before,
+                normalized_points.len(),
+                max_rate
+            );
+        }
+    }
+
+    let mut upsampled = match mode {
+        SmoothingMode::BezierStrokes {
+            dwell_speed_px_per_sec,
+            dwell_ms,
+        } => bezier_strokes_to_framerate(
+            &normalized_points,
+            frame_rate,
+            dwell_speed_px_per_sec,
+            dwell_ms as f64,
+        ),
+        SmoothingMode::Passthrough => {
+            interpolate_to_framerate_linear(&normalized_points, frame_rate, gap, 0.0)
+        }
+        _ => {
+            let filtered =
+                filter_path(&normalized_points, mode, responsiveness, smoothness, spring);
+            let filtered = match cleanup.simplify_epsilon_px {
+                Some(epsilon) => {
+                    let positions: Vec<CPoint> =
+                        filtered.iter().copied().map(CPoint::from).collect();
+                    let kept = simplify_indices(&positions, epsilon, SIMPLIFY_MAX_GAP_MS);
+                    log::debug!(
+                        "Simplified {} filtered points to {} (epsilon {:.2} px)",
+                        filtered.len(),
+                        kept.len(),
+                        epsilon
+                    );
+                    kept.into_iter().map(|i| filtered[i]).collect()
+                }
+                None => filtered,
+            };
+            let filtered = if anchors.is_empty() {
+                filtered
+            } else {
+                pin_to_anchors(filtered, &anchors)
+            };
+            match interpolation {
+                Interpolation::CatmullRom => {
+                    let positions: Vec<CPoint> = filtered.into_iter().map(CPoint::from).collect();
+                    interpolate_to_framerate(&positions, frame_rate, spline_alpha, gap, 0.0)
+                }
+                Interpolation::Hermite => {
+                    interpolate_to_framerate_hermite(&filtered, frame_rate, gap, 0.0)
+                }
+            }
+        }
+    };
+
+    snap_frames_to_anchors(&mut upsampled, &anchors, frame_rate);
+    if let Some(visibility) = &visibility {
+        hide_outside_region(&mut upsampled, visibility);
+    }
+
+    Ok((upsampled, rejected))
+}
+
+/// First pass: run the filter selected by `mode` over the sanitized path
+fn filter_path(
+    points: &[CPoint],
+    mode: SmoothingMode,
+    responsiveness: f32,
+    smoothness: f32,
+    spring: &SpringTuning,
+) -> Vec<PathPoint> {
+    match mode {
+        SmoothingMode::Spring => apply_physics_filter(points, responsiveness, smoothness, spring),
+        SmoothingMode::OneEuro {
+            min_cutoff,
+            beta,
+            d_cutoff,
+        } => with_finite_difference_velocity(&apply_one_euro_filter(
+            points, min_cutoff, beta, d_cutoff,
+        )),
+        SmoothingMode::Kalman {
+            process_noise,
+            measurement_noise,
+        } => kalman_filter_path(points, process_noise as f64, measurement_noise as f64),
+        // No filter, or the stroke fit does its own smoothing
+        SmoothingMode::Passthrough | SmoothingMode::BezierStrokes { .. } => {
+            with_finite_difference_velocity(points)
+        }
+    }
+}
+
+/// Shift timestamps to start at 0 and convert them to milliseconds.
+/// With `TimestampUnit::Auto`, a duration under 1000 is taken as seconds.
+fn normalize_to_relative_ms(points: &[CPoint], unit: TimestampUnit) -> Vec<CPoint> {
+    let Some((start_offset, ms_per_unit)) = relative_timeline(points, unit) else {
+        return points.to_vec();
+    };
+
+    // Relative timeline removes Unix Epoch noise
+    points
+        .iter()
+        .map(|p| CPoint {
+            x: p.x,
+            y: p.y,
+            timestamp_ms: (p.timestamp_ms - start_offset) * ms_per_unit,
+        })
+        .collect()
+}
+
+/// First finite timestamp and the milliseconds per timestamp unit; `None`
+/// when no timestamp is finite
+fn relative_timeline(points: &[CPoint], unit: TimestampUnit) -> Option<(f64, f64)> {
+    // Non-finite samples are dropped later by `sanitize_path`; skip them here
+    let mut finite_times = points
+        .iter()
+        .map(|p| p.timestamp_ms)
+        .filter(|t| t.is_finite());
+    let start_offset = finite_times.next()?;
+    let end_time = finite_times.last().unwrap_or(start_offset);
+    let duration = end_time - start_offset;
+
+    if let Some(scale) = unit.ms_per_unit() {
+        log::debug!("Timestamps in {:?} (Duration: {:.2})", unit, duration);
+        return Some((start_offset, scale));
+    }
+
+    // HEURISTIC: If relative duration is small (< 1000), it's definitely Seconds.
+    // (A 1000ms video is 1 second, unlikely to be the full recording).
+    // Screen recordings are typically 5s - 300s.
+    if duration > 0.0 && duration < 1000.0 {
+        log::info!(
+            "Detected SECONDS (Duration: {:.2}s). Converting to MS.",
+            duration
+        );
+        Some((start_offset, 1000.0))
+    } else {
+        log::info!(
+            "Detected MILLISECONDS (Duration: {:.2}ms). Keeping units.",
+            duration
+        );
+        Some((start_offset, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{REST_DWELL_MS, REST_RADIUS_PX};
+    use crate::engine;
+
+    /// `count` samples at (x, y), `interval_ms` apart from `start_ms`
+    fn still(count: usize, (x, y): (f32, f32), start_ms: f64, interval_ms: f64) -> Vec<CPoint> {
+        (0..count)
+            .map(|i| CPoint {
+                x,
+                y,
+                timestamp_ms: start_ms + i as f64 * interval_ms,
+            })
+            .collect()
+    }
+
+    /// 50 samples at the origin, then 150 at (100, 0), 8 ms apart
+    fn step() -> Vec<CPoint> {
+        let mut points = still(200, (0.0, 0.0), 0.0, 8.0);
+        for point in &mut points[50..] {
+            point.x = 100.0;
+        }
+        points
+    }
+
+    /// Samples 8 ms apart jittering 2 px either side of (50, 50)
+    fn jitter(count: usize) -> Vec<CPoint> {
+        let mut points = still(count, (50.0, 50.0), 0.0, 8.0);
+        for (i, point) in points.iter_mut().enumerate() {
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            point.x += 2.0 * sign;
+            point.y -= 2.0 * sign;
+        }
+        points
+    }
+
+    /// Largest minus smallest x over `points`
+    fn x_spread(points: impl IntoIterator<Item = f32>) -> f32 {
+        let (low, high) = points
+            .into_iter()
+            .fold((f32::MAX, f32::MIN), |(low, high), x| {
+                (low.min(x), high.max(x))
+            });
+        high - low
+    }
+
+    #[test]
+    fn physics_filter_keeps_one_point_per_sample() {
+        let mut raw = still(40, (0.0, 0.0), 5_000.0, 8.0);
+        for (i, point) in raw.iter_mut().enumerate() {
+            point.x = (i * i) as f32 * 0.5;
+        }
+        for input in [&raw[..], &raw[..1], &raw[..2], &[]] {
+            let filtered = engine::physics_filter(input, 0.7, 0.3);
+            assert_eq!(filtered.len(), input.len());
+            for (out, raw) in filtered.iter().zip(input) {
+                assert_eq!(out.timestamp_ms, raw.timestamp_ms);
+            }
+        }
+    }
+
+    #[test]
+    fn physics_filter_leaves_a_still_cursor_still() {
+        let raw = still(60, (412.5, -37.25), 0.0, 16.0);
+        let rest = RestSnap {
+            radius_px: 2.0,
+            dwell_ms: 100.0,
+        };
+        for physics_model_v2 in [false, true] {
+            for rest in [None, Some(rest)] {
+                let tuning = SpringTuning {
+                    rest,
+                    physics_model_v2,
+                    ..SpringTuning::default()
+                };
+                for (responsiveness, smoothness) in [(0.0, 0.0), (0.5, 0.5), (1.0, 1.0)] {
+                    let filtered = apply_physics_filter(&raw, responsiveness, smoothness, &tuning);
+                    assert!(
+                        filtered
+                            .iter()
+                            .all(|p| (p.x, p.y, p.vx, p.vy) == (412.5, -37.25, 0.0, 0.0)),
+                        "{:?} at ({}, {}) drifted",
+                        tuning,
+                        responsiveness,
+                        smoothness
+                    );
+                }
+            }
+        }
+    }
+
+    /// A straight 0..`length_px` move along x over `duration_ms`, `count`
+    /// samples evenly spaced from `start_ms`
+    fn sweep(count: usize, length_px: f32, start_ms: f64, duration_ms: f64) -> Vec<CPoint> {
+        (0..count)
+            .map(|i| {
+                let t = i as f64 / (count - 1) as f64;
+                CPoint {
+                    x: length_px * t as f32,
+                    y: 50.0,
+                    timestamp_ms: start_ms + duration_ms * t,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resampled_point_i_lies_on_frame_i() {
+        // (samples, duration, frame rate, frames out): the grid runs from
+        // the first sample up to, not including, the last one
+        let cases = [
+            (2, 100.0, 24, 3),
+            (2, 1000.0, 24, 24),
+            (9, 1000.0, 60, 60),
+            (9, 1010.0, 60, 61),
+            (30, 2000.0, 30, 60),
+            (5, 250.0, 144, 36),
+        ];
+        for start_ms in [0.0, 1234.5, 1_700_000_000_000.0] {
+            for (count, duration_ms, frame_rate, frames) in cases {
+                let points = sweep(count, 300.0, start_ms, duration_ms);
+                let dense = engine::resample_to_framerate(&points, frame_rate, 0.5).unwrap();
+                assert_eq!(
+                    dense.len(),
+                    frames,
+                    "{} ms at {} fps",
+                    duration_ms,
+                    frame_rate
+                );
+                for (i, point) in dense.iter().enumerate() {
+                    assert_eq!(
+                        point.timestamp_ms,
+                        start_ms + i as f64 * 1000.0 / f64::from(frame_rate),
+                        "frame {} of {} ms at {} fps from {}",
+                        i,
+                        duration_ms,
+                        frame_rate,
+                        start_ms
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn two_point_path_resamples_along_the_segment() {
+        let points = sweep(2, 240.0, 0.0, 1000.0);
+        let dense = interpolate_to_framerate(&points, 24, 0.5, None, 0.0);
+        assert_eq!(dense.len(), 24);
+        assert_eq!((dense[0].x, dense[0].y), (0.0, 50.0));
+        for pair in dense.windows(2) {
+            assert!(pair[1].x > pair[0].x, "{:?}", pair);
+            assert!((pair[0].y - 50.0).abs() < 1e-4);
+        }
+        // Frame 12 is half way through the second
+        assert!((dense[12].x - 120.0).abs() < 1.0, "{:?}", dense[12]);
+    }
 
-    let mut filtered = Vec::with_capacity(raw_points.len());
+    #[test]
+    fn sub_frame_path_resamples_to_its_first_point() {
+        // 5 ms is well under one 60 fps frame (16.7 ms)
+        let points = sweep(3, 4.0, 500.0, 5.0);
+        for dense in [
+            interpolate_to_framerate(&points, 60, 0.5, None, 500.0),
+            engine::resample_to_framerate(&points, 60, 0.5).unwrap(),
+        ] {
+            assert_eq!(dense.len(), 1);
+            assert_eq!(
+                (dense[0].x, dense[0].y, dense[0].timestamp_ms),
+                (0.0, 50.0, 500.0)
+            );
+        }
+        // Zero duration and a single point come back as one point too
+        let same_time = still(4, (7.0, 8.0), 500.0, 0.0);
+        assert_eq!(
+            interpolate_to_framerate(&same_time, 60, 0.5, None, 0.0).len(),
+            1
+        );
+        assert_eq!(
+            engine::resample_to_framerate(&points[..1], 60, 0.5)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(interpolate_to_framerate(&[], 60, 0.5, None, 0.0).is_empty());
+    }
 
-    // Initialize state
-    let mut x = raw_points[0].x;
-    let mut y = raw_points[0].y;
-    let mut vx = 0.0_f32;
-    let mut vy = 0.0_f32;
+    #[test]
+    fn one_euro_filter_holds_still_input_and_settles_after_a_step() {
+        let filter = |points: &[CPoint]| {
+            apply_one_euro_filter(
+                points,
+                ONE_EURO_MIN_CUTOFF,
+                ONE_EURO_BETA,
+                ONE_EURO_D_CUTOFF,
+            )
+        };
+        let resting = still(100, (640.0, 360.0), 0.0, 8.0);
+        let filtered = filter(&resting);
+        assert_eq!(filtered.len(), resting.len());
+        assert!(filtered.iter().all(|p| (p.x, p.y) == (640.0, 360.0)));
 
-    filtered.push(raw_points[0]);
+        // A low-pass: the output climbs toward the new position without
+        // overshooting it, and arrives
+        let raw = step();
+        let filtered = filter(&raw);
+        assert_eq!(filtered.len(), raw.len());
+        for (out, raw) in filtered.iter().zip(&raw) {
+            assert_eq!(out.timestamp_ms, raw.timestamp_ms);
+        }
+        assert!(filtered[..50].iter().all(|p| p.x == 0.0));
+        for pair in filtered.windows(2) {
+            assert!(pair[1].x >= pair[0].x && pair[1].x <= 100.0, "{:?}", pair);
+        }
+        assert!(filtered[50].x < 100.0);
+        assert!((filtered.last().unwrap().x - 100.0).abs() < 0.5);
+    }
 
-    // Simulate using actual timestamp deltas
-    for i in 1..raw_points.len() {
-        let dt = ((raw_points[i].timestamp_ms - raw_points[i - 1].timestamp_ms) / 1000.0) as f32;
-        let dt = dt.clamp(0.001, 0.1); // Prevent instability from timestamp glitches
+    #[test]
+    fn one_euro_filter_removes_jitter_at_rest() {
+        let filtered = apply_one_euro_filter(
+            &jitter(250),
+            ONE_EURO_MIN_CUTOFF,
+            ONE_EURO_BETA,
+            ONE_EURO_D_CUTOFF,
+        );
+        // 4 px of raw swing; skip the first half second while it settles
+        let spread = x_spread(filtered[60..].iter().map(|p| p.x));
+        assert!(spread < 1.0, "{} px of jitter left", spread);
+    }
 
-        let target_x = raw_points[i].x;
-        let target_y = raw_points[i].y;
+    #[test]
+    fn kalman_filter_tracks_position_and_velocity() {
+        let filter = |points: &[CPoint]| {
+            kalman_filter_path(
+                points,
+                KALMAN_PROCESS_NOISE as f64,
+                KALMAN_MEASUREMENT_NOISE as f64,
+            )
+        };
+        let resting = still(100, (640.0, 360.0), 0.0, 8.0);
+        let filtered = filter(&resting);
+        assert_eq!(filtered.len(), resting.len());
+        assert!(filtered
+            .iter()
+            .all(|p| (p.x - 640.0).abs() < 1e-3 && (p.y - 360.0).abs() < 1e-3));
+        assert!(filtered
+            .iter()
+            .all(|p| p.vx.abs() < 1e-3 && p.vy.abs() < 1e-3));
 
-        // Spring-damper force calculation
-        let dx = target_x - x;
-        let dy = target_y - y;
-        let fx = tension * dx - friction * vx;
-        let fy = tension * dy - friction * vy;
+        // 300 px/s to the right: after a quarter second the estimate has
+        // locked on to both the speed and the position
+        let mut ramp = still(125, (0.0, 100.0), 0.0, 8.0);
+        for (i, point) in ramp.iter_mut().enumerate() {
+            point.x = 300.0 * i as f32 * 0.008;
+        }
+        let filtered = filter(&ramp);
+        assert_eq!(filtered.len(), ramp.len());
+        for (out, raw) in filtered.iter().zip(&ramp).skip(30) {
+            assert!((out.vx - 300.0).abs() < 5.0, "{:?}", out);
+            assert!((out.x - raw.x).abs() < 0.5, "{:?} vs {:?}", out, raw);
+            assert_eq!(out.timestamp_ms, raw.timestamp_ms);
+        }
+    }
 
-        // Semi-implicit Euler integration (stable)
-        let ax = fx / mass;
-        let ay = fy / mass;
-        vx += ax * dt;
-        vy += ay * dt;
-        x += vx * dt;
-        y += vy * dt;
+    #[test]
+    fn kalman_filter_survives_repeated_timestamps() {
+        let mut points = still(20, (10.0, 10.0), 0.0, 8.0);
+        points[8].timestamp_ms = points[7].timestamp_ms;
+        points[9].timestamp_ms = points[7].timestamp_ms - 4.0;
+        points[9].x = 12.0;
+        let filtered = kalman_filter_path(
+            &points,
+            KALMAN_PROCESS_NOISE as f64,
+            KALMAN_MEASUREMENT_NOISE as f64,
+        );
+        assert_eq!(filtered.len(), points.len());
+        assert!(filtered
+            .iter()
+            .all(|p| p.x.is_finite() && p.vx.is_finite() && p.y.is_finite()));
+        assert!(kalman_filter_path(&[], 1.0, 1.0).is_empty());
+    }
 
-        filtered.push(CPoint {
-            x,
-            y,
-            timestamp_ms: raw_points[i].timestamp_ms,
-        });
+    /// `PathPoint`s on a 200 px/s move along x, 100 ms apart, carrying that
+    /// velocity
+    fn moving_knots(count: usize) -> Vec<PathPoint> {
+        (0..count)
+            .map(|i| PathPoint {
+                x: 20.0 * i as f32,
+                y: 40.0,
+                vx: 200.0,
+                vy: 0.0,
+                timestamp_ms: 100.0 * i as f64,
+            })
+            .collect()
     }
 
-    filtered
-}
+    #[test]
+    fn hermite_upsampling_follows_the_knots_and_their_velocity() {
+        let knots = moving_knots(6);
+        let dense = interpolate_to_framerate_hermite(&knots, 60, None, 0.0);
+        assert_eq!(dense.len(), 30);
+        // Matching tangents make every segment the straight line itself
+        for (i, point) in dense.iter().enumerate() {
+            let t_ms = i as f64 * 1000.0 / 60.0;
+            assert_eq!(point.timestamp_ms, t_ms);
+            assert!((point.x - 0.2 * t_ms as f32).abs() < 1e-3, "{:?}", point);
+            assert_eq!(point.y, 40.0);
+        }
+        // Frames landing on a knot show it exactly
+        for (frame, knot) in dense.iter().step_by(6).zip(&knots) {
+            assert_eq!((frame.x, frame.y), (knot.x, knot.y));
+        }
 
-// ============================================================================
-// PASS 2: Catmull-Rom Spline Interpolation (Upsample to Frame Rate)
-// ============================================================================
+        // Zero velocity at rest stays put
+        let mut resting = moving_knots(4);
+        for knot in &mut resting {
+            (knot.x, knot.vx) = (75.0, 0.0);
+        }
+        let dense = interpolate_to_framerate_hermite(&resting, 60, None, 0.0);
+        assert_eq!(dense.len(), 18);
+        assert!(dense.iter().all(|p| (p.x, p.y) == (75.0, 40.0)));
+    }
 
-/// Interpolate sparse points to match video frame rate using Catmull-Rom splines
-pub fn interpolate_to_framerate(
-    clean_points: &[CPoint],
-    frame_rate: i32,
-    _alpha: f32,
-) -> Vec<CPoint> {
-    // 1. Handle Empty Input
-    if clean_points.is_empty() {
-        return Vec::new();
+    #[test]
+    fn hermite_segment_leaves_each_knot_at_its_velocity() {
+        // Both knots sit at x = 0 moving right: the curve leaves the first
+        // to the right and comes back from the left to arrive at the second
+        let p0 = PathPoint {
+            x: 0.0,
+            y: 0.0,
+            vx: 100.0,
+            vy: 0.0,
+            timestamp_ms: 0.0,
+        };
+        let p1 = PathPoint {
+            timestamp_ms: 100.0,
+            ..p0
+        };
+        let (early, _) = hermite_point(&p0, &p1, 10.0);
+        let (late, _) = hermite_point(&p0, &p1, 90.0);
+        assert!(early > 0.0 && late < 0.0, "{} {}", early, late);
+        assert_eq!(hermite_point(&p0, &p1, -5.0), (0.0, 0.0));
+        assert_eq!(hermite_point(&p0, &p1, 105.0), (0.0, 0.0));
     }
 
-    // 2. Handle Single Point (Static Cursor)
-    if clean_points.len() == 1 {
-        // Return at least one point so video processing doesn't fail
-        return vec![clean_points[0]];
+    /// 40 samples 8 ms apart moving right at 125 px/s
+    fn slow_drag() -> Vec<CPoint> {
+        let mut points = still(40, (0.0, 300.0), 0.0, 8.0);
+        for (i, point) in points.iter_mut().enumerate() {
+            point.x = i as f32;
+        }
+        points
     }
 
-    let start_time = clean_points.first().unwrap().timestamp_ms;
-    let end_time = clean_points.last().unwrap().timestamp_ms;
+    #[test]
+    fn single_sample_glitches_are_rejected() {
+        let raw = slow_drag();
+        assert_eq!(
+            reject_outliers(&raw, VELOCITY_MAX_PX_PER_SEC).len(),
+            raw.len()
+        );
 
-    // 3. Handle Zero Duration (Start == End)
-    if (end_time - start_time).abs() < 1e-6 {
-        return vec![clean_points[0]];
+        // One sample thrown 3000 px off in 8 ms, and one pair of them
+        let mut glitched = raw.clone();
+        glitched[10].x += 3000.0;
+        glitched[25].y -= 3000.0;
+        glitched[26].y -= 3000.0;
+        let kept = reject_outliers(&glitched, VELOCITY_MAX_PX_PER_SEC);
+        assert_eq!(kept.len(), raw.len() - 3);
+        assert!(kept.iter().all(|p| p.x < 100.0 && p.y == 300.0));
+        assert_eq!(
+            kept.last().unwrap().timestamp_ms,
+            raw.last().unwrap().timestamp_ms
+        );
     }
 
-    // Calculate exact number of frames
-    // Use max(1) to ensure we always produce points if duration > 0
-    let num_frames = ((end_time - start_time) / 1000.0 * frame_rate as f64).ceil() as usize;
-    let num_frames = num_frames.max(1);
+    #[test]
+    fn sustained_jump_is_kept() {
+        // Moving to another display: the cursor stays where it landed
+        let mut raw = slow_drag();
+        for point in &mut raw[20..] {
+            point.x += 2000.0;
+        }
+        let kept = reject_outliers(&raw, VELOCITY_MAX_PX_PER_SEC);
+        assert_eq!(kept.len(), raw.len());
+
+        // A jump in the last few samples cannot be confirmed, so it goes
+        let mut late = slow_drag();
+        let end = late.len() - 1;
+        late[end].x += 2000.0;
+        assert_eq!(reject_outliers(&late, VELOCITY_MAX_PX_PER_SEC).len(), end);
+    }
 
-    let mut dense_path = Vec::with_capacity(num_frames);
-    let frame_dur = 1000.0 / frame_rate as f64;
+    #[test]
+    fn sanitizing_merges_repeated_times_and_drops_non_finite_samples() {
+        let point = |x: f32, timestamp_ms: f64| CPoint {
+            x,
+            y: 0.0,
+            timestamp_ms,
+        };
+        let raw = [
+            point(0.0, 0.0),
+            point(10.0, 8.0),
+            // Coalesced events at 8 ms, and one 2 ms early
+            point(20.0, 8.0),
+            point(30.0, 6.0),
+            point(f32::NAN, 12.0),
+            point(40.0, f64::INFINITY),
+            point(50.0, 16.0),
+        ];
+        let clean = sanitize_path(&raw).unwrap();
+        let kept: Vec<(f32, f64)> = clean.iter().map(|p| (p.x, p.timestamp_ms)).collect();
+        // The merged sample sits at the mean of the three
+        assert_eq!(kept, [(0.0, 0.0), (20.0, 8.0), (50.0, 16.0)]);
+        assert!(clean
+            .windows(2)
+            .all(|pair| pair[1].timestamp_ms > pair[0].timestamp_ms));
 
-    for i in 0..num_frames {
-        let t_target = start_time + i as f64 * frame_dur;
+        // Already clean input passes through untouched
+        let drag = slow_drag();
+        let clean = sanitize_path(&drag).unwrap();
+        assert_eq!(clean.len(), drag.len());
+        assert!(clean
+            .iter()
+            .zip(&drag)
+            .all(|(a, b)| (a.x, a.timestamp_ms) == (b.x, b.timestamp_ms)));
+    }
 
-        // Safety: Don't overshoot the end time significantly due to float math
-        if t_target > end_time + frame_dur {
-            break;
+    #[test]
+    fn sanitizing_refuses_a_step_back_past_the_tolerance() {
+        let mut raw = slow_drag();
+        raw[12].timestamp_ms = raw[11].timestamp_ms - TIMESTAMP_BACKSTEP_TOLERANCE_MS - 1.0;
+        match sanitize_path(&raw) {
+            Err(EngineError::NonMonotonicTimestamps { index, backstep_ms }) => {
+                assert_eq!(index, 12);
+                assert_eq!(backstep_ms, TIMESTAMP_BACKSTEP_TOLERANCE_MS + 1.0);
+            }
+            other => panic!("expected NonMonotonicTimestamps, got {:?}", other),
         }
+    }
 
-        let idx = match clean_points
-            .binary_search_by(|p| p.timestamp_ms.partial_cmp(&t_target).unwrap())
-        {
-            Ok(i) => i,
-            Err(i) => i,
+    #[test]
+    fn cursor_holds_across_a_capture_gap_then_ramps() {
+        // Five samples either side of a 2 s hole, 50 ms apart
+        let mut points = still(10, (0.0, 80.0), 0.0, 50.0);
+        for (i, point) in points.iter_mut().enumerate() {
+            point.x = 10.0 * i as f32;
+            if i >= 5 {
+                point.x += 500.0;
+                point.timestamp_ms += 1800.0;
+            }
+        }
+        let (before, after) = (points[4], points[5]);
+        let gap = GapHold {
+            max_gap_ms: 500.0,
+            ramp_ms: 100.0,
         };
+        let knots: Vec<PathPoint> = with_finite_difference_velocity(&points);
+        for (name, dense) in [
+            (
+                "catmull-rom",
+                interpolate_to_framerate(&points, 60, 0.5, Some(gap), 0.0),
+            ),
+            (
+                "hermite",
+                interpolate_to_framerate_hermite(&knots, 60, Some(gap), 0.0),
+            ),
+            (
+                "linear",
+                interpolate_to_framerate_linear(&points, 60, Some(gap), 0.0),
+            ),
+        ] {
+            let in_gap: Vec<&CPoint> = dense
+                .iter()
+                .filter(|p| {
+                    p.timestamp_ms > before.timestamp_ms && p.timestamp_ms < after.timestamp_ms
+                })
+                .collect();
+            let ramp_start = after.timestamp_ms - gap.ramp_ms;
+            for point in &in_gap {
+                if point.timestamp_ms <= ramp_start {
+                    assert_eq!(
+                        (point.x, point.y),
+                        (before.x, before.y),
+                        "{}: {:?}",
+                        name,
+                        point
+                    );
+                } else {
+                    assert!(
+                        point.x > before.x && point.x < after.x,
+                        "{}: {:?}",
+                        name,
+                        point
+                    );
+                }
+            }
+            assert!(
+                in_gap.windows(2).all(|pair| pair[1].x >= pair[0].x),
+                "{} went backwards in the gap",
+                name
+            );
+        }
 
-        let len = clean_points.len();
-        // Safe indexing with boundary clamping
-        let i1 = idx.min(len - 1);
-        let i0 = i1.saturating_sub(1);
-        let i2 = (i1 + 1).min(len - 1);
-        let i3 = (i1 + 2).min(len - 1);
-
-        let p0 = &clean_points[i0];
-        let p1 = &clean_points[i1];
-        let p2 = &clean_points[i2];
-        let p3 = &clean_points[i3];
-
-        // If time interval is tiny, just use p1 position
-        if (p2.timestamp_ms - p1.timestamp_ms).abs() < 1e-6 {
-            dense_path.push(CPoint {
-                x: p1.x,
-                y: p1.y,
-                timestamp_ms: t_target,
-            });
-            continue;
+        // Without the hold the spline glides across the whole hole
+        let glide = interpolate_to_framerate(&points, 60, 0.5, None, 0.0);
+        let middle = glide.iter().find(|p| p.timestamp_ms >= 1100.0).unwrap();
+        assert!(middle.x > before.x + 100.0, "{:?}", middle);
+    }
+
+    /// A 200 ms flick to (200, 100), then a second at rest there with the
+    /// sensor wobbling `wobble_px` either way
+    fn flick_then_rest(wobble_px: f32) -> Vec<CPoint> {
+        let mut points = still(150, (200.0, 100.0), 0.0, 8.0);
+        for (i, point) in points.iter_mut().enumerate() {
+            if i < 25 {
+                point.x = 8.0 * i as f32;
+            } else if i % 2 == 1 {
+                point.x += wobble_px;
+            }
         }
+        points
+    }
 
-        let t = t_target as f32;
-        let x = catmull_rom_1d(
-            t,
-            p0.timestamp_ms as f32,
-            p1.timestamp_ms as f32,
-            p2.timestamp_ms as f32,
-            p3.timestamp_ms as f32,
-            p0.x,
-            p1.x,
-            p2.x,
-            p3.x,
-        );
-        let y = catmull_rom_1d(
-            t,
-            p0.timestamp_ms as f32,
-            p1.timestamp_ms as f32,
-            p2.timestamp_ms as f32,
-            p3.timestamp_ms as f32,
-            p0.y,
-            p1.y,
-            p2.y,
-            p3.y,
+    #[test]
+    fn resting_cursor_settles_exactly_with_no_drift() {
+        let rest = SpringTuning {
+            rest: Some(RestSnap {
+                radius_px: REST_RADIUS_PX,
+                dwell_ms: REST_DWELL_MS as f64,
+            }),
+            ..SpringTuning::default()
+        };
+        let raw = flick_then_rest(0.0);
+        let filtered = apply_physics_filter(&raw, 0.5, 0.5, &rest);
+        let settled = &filtered[100..];
+        assert!(
+            settled
+                .iter()
+                .all(|p| (p.x, p.y, p.vx, p.vy) == (200.0, 100.0, 0.0, 0.0)),
+            "{:?}",
+            settled.first()
         );
 
-        dense_path.push(CPoint {
-            x,
-            y,
-            timestamp_ms: t_target,
-        });
+        // Sensor wobble within the radius: no momentum, and never further
+        // from the raw position than the wobble
+        let raw = flick_then_rest(0.5);
+        let filtered = apply_physics_filter(&raw, 0.5, 0.5, &rest);
+        for (out, raw) in filtered.iter().zip(&raw).skip(100) {
+            assert_eq!((out.vx, out.vy), (0.0, 0.0));
+            assert!((out.x - raw.x).abs() <= 0.5, "{:?} vs {:?}", out, raw);
+        }
+        // Without the snap the spring keeps chasing the wobble
+        let free = apply_physics_filter(&raw, 0.5, 0.5, &SpringTuning::default());
+        assert!(free[100..].iter().any(|p| p.vx != 0.0));
     }
 
-    // Fallback: If logic produced 0 points (e.g. num_frames calculation oddity), force 1 point
-    if dense_path.is_empty() {
-        dense_path.push(clean_points[0]);
-    }
+    #[test]
+    fn substeps_keep_sparse_input_stable() {
+        // 10 Hz input stepping 0 -> 100 px, with the stiffest spring
+        let mut raw = still(30, (0.0, 0.0), 0.0, 100.0);
+        for point in &mut raw[5..] {
+            point.x = 100.0;
+        }
+        for smoothness in [0.0, 0.5, 1.0] {
+            let derived = apply_physics_filter(&raw, 1.0, smoothness, &SpringTuning::default());
+            assert!(derived.iter().all(|p| p.x.is_finite() && p.x.abs() < 200.0));
+            assert!((derived.last().unwrap().x - 100.0).abs() < 0.5);
+        }
 
-    dense_path
-}
+        // Much finer steps stay within 5% of the step of it
+        let fine = SpringTuning {
+            max_step_sec: Some(0.0005),
+            ..SpringTuning::default()
+        };
+        let derived = apply_physics_filter(&raw, 1.0, 0.5, &SpringTuning::default());
+        let reference = apply_physics_filter(&raw, 1.0, 0.5, &fine);
+        for (a, b) in derived.iter().zip(&reference) {
+            assert!((a.x - b.x).abs() < 5.0, "{:?} vs {:?}", a, b);
+        }
 
-/// Evaluate Catmull-Rom spline at parameter t using Barry-Goldman algorithm
-#[allow(dead_code)]
-fn catmull_rom_point(
-    t: f32,
-    p0: &CPoint,
-    p1: &CPoint,
-    p2: &CPoint,
-    p3: &CPoint,
-    alpha: f32,
-) -> (f32, f32) {
-    // Knot intervals based on chord length
-    let d01 = distance(p0, p1).powf(alpha).max(1e-6);
-    let d12 = distance(p1, p2).powf(alpha).max(1e-6);
-    let d23 = distance(p2, p3).powf(alpha).max(1e-6);
+        // One Euler step per 100 ms sample blows up at this stiffness
+        let coarse = SpringTuning {
+            max_step_sec: Some(0.1),
+            ..SpringTuning::default()
+        };
+        let unstable = apply_physics_filter(&raw, 1.0, 0.0, &coarse);
+        assert!(unstable
+            .iter()
+            .any(|p| !p.x.is_finite() || p.x.abs() > 1000.0));
+    }
 
-    let t0 = 0.0;
-    let t1 = t0 + d01;
-    let t2 = t1 + d12;
-    let t3 = t2 + d23;
+    #[test]
+    fn long_capture_pause_is_integrated_as_the_largest_dt() {
+        // A 5 s pause before the jump counts as `PHYSICS_MAX_DT_SEC`, so the
+        // spring reaches the same point as after a 100 ms pause
+        let jump = |pause_ms: f64| {
+            let mut raw = still(3, (0.0, 0.0), 0.0, 8.0);
+            raw.push(CPoint {
+                x: 100.0,
+                y: 0.0,
+                timestamp_ms: 16.0 + pause_ms,
+            });
+            apply_physics_filter(&raw, 0.5, 0.5, &SpringTuning::default())
+        };
+        let (paused, short) = (jump(5000.0), jump(PHYSICS_MAX_DT_SEC as f64 * 1000.0));
+        assert_eq!(paused.last().unwrap().x, short.last().unwrap().x);
+        assert_eq!(paused.last().unwrap().timestamp_ms, 5016.0);
+    }
 
-    let t_mapped = t1 + t * d12;
+    #[test]
+    fn settling_time_model_settles_when_documented() {
+        // 2 ms samples: at rest until 100 ms, then at x = 100 for 1.5 s
+        let mut raw = still(800, (0.0, 0.0), 0.0, 2.0);
+        for point in &mut raw[50..] {
+            point.x = 100.0;
+        }
+        let v2 = SpringTuning {
+            physics_model_v2: true,
+            ..SpringTuning::default()
+        };
+        for responsiveness in [0.0, 0.5, 1.0] {
+            for smoothness in [0.0, 1.0] {
+                let filtered = apply_physics_filter(&raw, responsiveness, smoothness, &v2);
+                let last_outside = filtered
+                    .iter()
+                    .rposition(|p| (p.x - 100.0).abs() > 100.0 * SETTLING_TOLERANCE)
+                    .unwrap();
+                let settled_sec = (filtered[last_outside + 1].timestamp_ms - 100.0) / 1000.0;
+                let expected = RESPONSIVENESS_MAX_SETTLING_SEC
+                    + (RESPONSIVENESS_MIN_SETTLING_SEC - RESPONSIVENESS_MAX_SETTLING_SEC)
+                        * responsiveness;
+                assert!(
+                    (settled_sec as f32 - expected).abs() < 0.1 * expected,
+                    "({}, {}) settled in {} s, not {} s",
+                    responsiveness,
+                    smoothness,
+                    settled_sec,
+                    expected
+                );
 
-    let x = catmull_rom_1d(t_mapped, t0, t1, t2, t3, p0.x, p1.x, p2.x, p3.x);
-    let y = catmull_rom_1d(t_mapped, t0, t1, t2, t3, p0.y, p1.y, p2.y, p3.y);
+                // Full smoothness is critically damped; none overshoots
+                // like a damping ratio of 0.6 (~9.5% in theory)
+                let peak = filtered.iter().map(|p| p.x).fold(f32::MIN, f32::max);
+                if smoothness == 1.0 {
+                    assert!(peak <= 100.0 + 1e-3, "overshot to {}", peak);
+                } else {
+                    assert!(peak > 105.0 && peak < 112.0, "overshot to {}", peak);
+                }
+            }
+        }
+    }
 
-    (x, y)
-}
+    #[test]
+    fn decimation_leaves_sparse_input_alone() {
+        // 125 Hz, under every target rate tried
+        let raw = slow_drag();
+        for max_rate_hz in [125.0, 240.0, 1000.0] {
+            let decimated = decimate_path(&raw, max_rate_hz);
+            assert_eq!(decimated.len(), raw.len());
+            for (a, b) in decimated.iter().zip(&raw) {
+                assert_eq!((a.x, a.y, a.timestamp_ms), (b.x, b.y, b.timestamp_ms));
+            }
+        }
+        assert_eq!(decimate_path(&raw[..2], 30.0).len(), 2);
+    }
 
-/// Barry-Goldman recursive formula for 1D Catmull-Rom interpolation
-fn catmull_rom_1d(
-    t: f32,
-    t0: f32,
-    t1: f32,
-    t2: f32,
-    t3: f32,
-    p0: f32,
-    p1: f32,
-    p2: f32,
-    p3: f32,
-) -> f32 {
-    // Helper closure for safe division
-    let safe_lerp = |start_val: f32, end_val: f32, start_t: f32, end_t: f32, current_t: f32| {
-        if (end_t - start_t).abs() < 1e-6 {
-            start_val // Avoid NaN if timestamps are identical
-        } else {
-            let f = (current_t - start_t) / (end_t - start_t);
-            start_val + (end_val - start_val) * f
+    #[test]
+    fn decimation_averages_dense_input_but_keeps_turnarounds() {
+        // One second at 1000 Hz: right at 1000 px/s, then back from 600 ms
+        let raw: Vec<CPoint> = (0..=1000)
+            .map(|i| {
+                let x = if i <= 600 { i } else { 1200 - i };
+                CPoint {
+                    x: x as f32,
+                    y: 10.0,
+                    timestamp_ms: i as f64,
+                }
+            })
+            .collect();
+        let decimated = decimate_path(&raw, 240.0);
+        // A sample per 1000/240 ms bucket, plus the two ends
+        assert!(
+            (240..=243).contains(&decimated.len()),
+            "{}",
+            decimated.len()
+        );
+        assert_eq!(decimated[0].x, raw[0].x);
+        assert_eq!(decimated.last().unwrap().timestamp_ms, 1000.0);
+        assert!(decimated
+            .windows(2)
+            .all(|pair| pair[1].timestamp_ms > pair[0].timestamp_ms));
+        // The turnaround survives instead of being averaged down
+        let peak = decimated.iter().map(|p| p.x).fold(f32::MIN, f32::max);
+        assert_eq!(peak, 600.0);
+    }
+
+    #[test]
+    fn explicit_units_normalize_to_the_same_timeline() {
+        // 800 ms of samples 100 ms apart, recorded against a Unix clock
+        let relative_ms: Vec<f64> = (0..=8).map(|i| f64::from(i) * 100.0).collect();
+        let in_unit = |start: f64, per_ms: f64| -> Vec<CPoint> {
+            relative_ms
+                .iter()
+                .map(|&ms| CPoint {
+                    x: 1.0,
+                    y: 2.0,
+                    timestamp_ms: start + ms * per_ms,
+                })
+                .collect()
+        };
+        let cases = [
+            (
+                TimestampUnit::Milliseconds,
+                in_unit(1_700_000_000_000.0, 1.0),
+            ),
+            (TimestampUnit::Seconds, in_unit(1_700_000_000.0, 0.001)),
+            (
+                TimestampUnit::Microseconds,
+                in_unit(1_700_000_000_000_000.0, 1000.0),
+            ),
+        ];
+        for (unit, points) in &cases {
+            let normalized = normalize_to_relative_ms(points, *unit);
+            for (point, &expected) in normalized.iter().zip(&relative_ms) {
+                assert!(
+                    (point.timestamp_ms - expected).abs() < 1e-3,
+                    "{:?}: {} ms, not {}",
+                    unit,
+                    point.timestamp_ms,
+                    expected
+                );
+            }
         }
-    };
 
-    // Level 1 (Linear)
-    let a1 = safe_lerp(p0, p1, t0, t1, t);
-    let a2 = safe_lerp(p1, p2, t1, t2, t);
-    let a3 = safe_lerp(p2, p3, t2, t3, t);
+        // The guess reads under 1000 units as seconds, which is wrong here
+        let guessed = normalize_to_relative_ms(&cases[0].1, TimestampUnit::Auto);
+        assert_eq!(guessed.last().unwrap().timestamp_ms, 800_000.0);
+    }
 
-    // Level 2 (Quadratic)
-    let b1 = safe_lerp(a1, a2, t0, t2, t);
-    let b2 = safe_lerp(a2, a3, t1, t3, t);
+    #[test]
+    fn simplification_drops_straight_runs_but_keeps_corners_and_pauses() {
+        // 1 s across, then 1 s down: an L with its corner at sample 50
+        let mut points = sweep(51, 500.0, 0.0, 1000.0);
+        points.extend((1..=50).map(|i| CPoint {
+            x: 500.0,
+            y: 50.0 + i as f32 * 10.0,
+            timestamp_ms: 1000.0 + i as f64 * 20.0,
+        }));
 
-    // Level 3 (Cubic) - The Result
-    safe_lerp(b1, b2, t1, t2, t)
-}
+        let kept_ms = |simplified: &[CPoint]| -> Vec<f64> {
+            simplified.iter().map(|p| p.timestamp_ms).collect()
+        };
+        let simplified = simplify_path(&points, 1.0, f64::INFINITY);
+        assert_eq!(kept_ms(&simplified), vec![0.0, 1000.0, 2000.0]);
 
-#[allow(dead_code)]
-fn distance(a: &CPoint, b: &CPoint) -> f32 {
-    let dx = b.x - a.x;
-    let dy = b.y - a.y;
-    (dx * dx + dy * dy).sqrt()
-}
+        // With a gap limit no kept span is longer than it
+        let simplified = simplify_path(&points, 1.0, 300.0);
+        let kept = kept_ms(&simplified);
+        assert!(kept.contains(&1000.0));
+        assert_eq!((kept[0], kept[kept.len() - 1]), (0.0, 2000.0));
+        for pair in simplified.windows(2) {
+            let span_ms = pair[1].timestamp_ms - pair[0].timestamp_ms;
+            assert!(span_ms <= 300.0, "{} ms between kept points", span_ms);
+        }
 
-#[allow(dead_code)]
-fn find_segment_index(points: &[CPoint], timestamp: f64) -> usize {
-    match points.binary_search_by(|p| {
-        if p.timestamp_ms < timestamp {
-            Ordering::Less
-        } else {
-            Ordering::Greater
+        // Every dropped point lies within epsilon of the simplified path
+        for point in &points {
+            let nearest = simplified
+                .windows(2)
+                .map(|pair| distance_to_segment(point, &pair[0], &pair[1]))
+                .fold(f32::INFINITY, f32::min);
+            assert!(nearest <= 1.0, "{:?} is {} px off", point, nearest);
         }
-    }) {
-        Ok(idx) => idx,
-        Err(idx) => idx,
     }
-}
 
-// ============================================================================
-// PUBLIC API: Complete Dual-Pass Pipeline
-// ============================================================================
+    #[test]
+    fn path_metrics_measure_a_known_motion() {
+        // 500 px at a constant 500 px/s
+        let line = sweep(101, 500.0, 0.0, 1000.0);
+        let metrics = path_metrics(&line, &line, TimestampUnit::Milliseconds);
+        assert!((metrics.total_length_px - 500.0).abs() < 1e-3);
+        assert!((metrics.max_speed_px_per_sec - 500.0).abs() < 1e-3);
+        assert!(metrics.max_acceleration_px_per_sec2 < 1e-3);
+        assert!(metrics.rms_jerk_px_per_sec3 < 1e-3);
+        assert_eq!(metrics.max_deviation_px, 0.0);
 
-/// Complete smoothing pipeline: Physics filtering + Spline interpolation
-pub fn smooth_cursor_path_dual_pass(
-    raw_points: &[CPoint],
-    frame_rate: i32,
-    responsiveness: f32, // 0.0-1.0 (controls physics spring stiffness)
-    smoothness: f32,     // 0.0-1.0 (controls physics damping)
-    spline_alpha: f32,   // 0.5 recommended (centripetal Catmull-Rom)
-) -> Vec<CPoint> {
-    if raw_points.is_empty() {
-        return Vec::new();
+        // x = 500 t^2: constant 1000 px/s^2, no jerk, 1000 px/s at the end
+        let parabola: Vec<CPoint> = (0..=100)
+            .map(|i| {
+                let t = i as f64 / 100.0;
+                CPoint {
+                    x: (500.0 * t * t) as f32,
+                    y: 50.0,
+                    timestamp_ms: t * 1000.0,
+                }
+            })
+            .collect();
+        let metrics = path_metrics(&parabola, &parabola, TimestampUnit::Milliseconds);
+        assert!((metrics.max_acceleration_px_per_sec2 - 1000.0).abs() < 1.0);
+        assert!(metrics.rms_jerk_px_per_sec3 < 100.0);
+        assert!((metrics.max_speed_px_per_sec - 995.0).abs() < 1.0);
+
+        // The same line held 20 px off the raw samples, on a Unix clock
+        let raw: Vec<CPoint> = line
+            .iter()
+            .map(|p| CPoint {
+                y: p.y + 20.0,
+                timestamp_ms: p.timestamp_ms + 1_700_000_000_000.0,
+                ..*p
+            })
+            .collect();
+        let metrics = path_metrics(&line, &raw, TimestampUnit::Milliseconds);
+        assert!((metrics.max_deviation_px - 20.0).abs() < 1e-3);
     }
 
-    // Normalize timestamps to milliseconds (detect if input is in seconds)
-    let normalized_points = normalize_to_relative_ms(raw_points);
+    #[test]
+    fn right_display_coordinates_map_into_its_recording() {
+        // Recording the right-hand 1920x1080 display of a side-by-side pair
+        let mut space = CoordinateSpace::display(1920.0, 0.0, 1920.0, 1080.0);
+        let at = |x: f32, y: f32, timestamp_ms: f64| CPoint { x, y, timestamp_ms };
+        let points = [
+            at(2000.0, 100.0, 0.0),
+            at(3839.0, 1079.0, 10.0),
+            // Over on the left display
+            at(100.0, 500.0, 20.0),
+        ];
 
-    let filtered = apply_physics_filter(&normalized_points, responsiveness, smoothness);
-    let upsampled = interpolate_to_framerate(&filtered, frame_rate, spline_alpha);
+        let (mapped, visibility) = map_to_video_space(&points, &space);
+        let positions: Vec<(f32, f32)> = mapped.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(positions[..2], [(80.0, 100.0), (1919.0, 1079.0)]);
+        assert_eq!(
+            visibility,
+            Some(vec![(0.0, true), (10.0, true), (20.0, false)])
+        );
 
-    upsampled
-}
+        // Pinned to the shared edge instead of hidden
+        space.outside = OutsideRegion::Clamp;
+        let (mapped, visibility) = map_to_video_space(&points, &space);
+        assert_eq!((mapped[2].x, mapped[2].y), (0.0, 500.0));
+        assert_eq!(visibility, None);
 
-/// Detect timestamp units and convert to milliseconds if needed.
-/// Heuristic: If the last timestamp is < 10000 and duration < 1000, assume seconds.
-fn normalize_to_relative_ms(points: &[CPoint]) -> Vec<CPoint> {
-    if points.is_empty() {
-        return Vec::new();
+        // Hidden samples leave no cursor in the smoothed output
+        let mut dense = vec![at(80.0, 100.0, 0.0), at(0.0, 500.0, 20.0)];
+        hide_outside_region(&mut dense, &[(0.0, true), (20.0, false)]);
+        assert!(dense[0].x.is_finite());
+        assert!(dense[1].x.is_nan() && dense[1].y.is_nan());
     }
 
-    let start_offset = points[0].timestamp_ms;
+    #[test]
+    fn hidpi_points_scale_to_video_pixels() {
+        let point = CPoint {
+            x: 100.0,
+            y: 50.0,
+            timestamp_ms: 0.0,
+        };
+        let (mapped, inside) = CoordinateSpace::scaled(2.0).map(&point);
+        assert_eq!((mapped.x, mapped.y, inside), (200.0, 100.0, true));
 
-    // Create relative timeline first (removes Unix Epoch noise)
-    let mut relative_points: Vec<CPoint> = points
-        .iter()
-        .map(|p| CPoint {
-            x: p.x,
-            y: p.y,
-            timestamp_ms: p.timestamp_ms - start_offset,
-        })
-        .collect();
+        // A 1440x900-point display captured at 2880x1800, then downscaled to
+        // 1920x1080 (uneven per-axis scales)
+        let mut space = CoordinateSpace::display(0.0, 0.0, 1440.0, 900.0);
+        assert_eq!(space.scale_for_video(2880, 1800), Some((2.0, 2.0)));
+        let (scale_x, scale_y) = space.scale_for_video(1920, 1080).unwrap();
+        assert!((scale_x - 1.3333).abs() < 1e-3 && (scale_y - 1.2).abs() < 1e-6);
+        (space.scale_x, space.scale_y) = (scale_x, scale_y);
+        let corner = CPoint {
+            x: 1440.0,
+            y: 900.0,
+            timestamp_ms: 0.0,
+        };
+        let (mapped, _) = space.map(&corner);
+        assert!((mapped.x - 1920.0).abs() < 1e-3 && (mapped.y - 1080.0).abs() < 1e-3);
 
-    let duration = relative_points.last().unwrap().timestamp_ms;
+        // Without a region size there is nothing to derive the scale from
+        assert_eq!(
+            CoordinateSpace::scaled(2.0).scale_for_video(1920, 1080),
+            None
+        );
+    }
 
-    // HEURISTIC: If relative duration is small (< 1000), it's definitely Seconds.
-    // (A 1000ms video is 1 second, unlikely to be the full recording).
-    // Screen recordings are typically 5s - 300s.
-    if duration > 0.0 && duration < 1000.0 {
-        log::info!(
-            "Detected SECONDS (Duration: {:.2}s). Converting to MS.",
-            duration
+    #[test]
+    fn upsampled_frames_sit_on_the_video_frame_grid() {
+        // Samples starting a fraction of a frame in, not on the grid
+        let points = sweep(40, 300.0, 7.3, 500.0);
+        let knots = with_finite_difference_velocity(&points);
+        for origin_ms in [0.0, 3.0] {
+            for (name, dense) in [
+                (
+                    "catmull-rom",
+                    interpolate_to_framerate(&points, 30, 0.5, None, origin_ms),
+                ),
+                (
+                    "hermite",
+                    interpolate_to_framerate_hermite(&knots, 30, None, origin_ms),
+                ),
+                (
+                    "linear",
+                    interpolate_to_framerate_linear(&points, 30, None, origin_ms),
+                ),
+            ] {
+                // The first frame at or after the first sample, then one per frame
+                let first = ((7.3 - origin_ms) * 30.0 / 1000.0).ceil();
+                for (k, point) in dense.iter().enumerate() {
+                    let expected = origin_ms + (first + k as f64) * 1000.0 / 30.0;
+                    assert!(
+                        (point.timestamp_ms - expected).abs() < 1e-9,
+                        "{} (origin {}): point {} at {} ms, not {}",
+                        name,
+                        origin_ms,
+                        k,
+                        point.timestamp_ms,
+                        expected
+                    );
+                }
+                assert!(dense.last().unwrap().timestamp_ms < 507.3, "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn smoothed_path_passes_through_each_click() {
+        // A fast 1000 px/s sweep, pressed and released on the way
+        let events: Vec<CEventPoint> = sweep(101, 1000.0, 0.0, 1000.0)
+            .iter()
+            .enumerate()
+            .map(|(i, p)| CEventPoint {
+                x: p.x,
+                y: p.y,
+                timestamp_ms: p.timestamp_ms,
+                event_type: match i {
+                    30 => EVENT_DOWN,
+                    60 => EVENT_UP,
+                    _ => EVENT_MOVE,
+                },
+            })
+            .collect();
+        let (points, clicks) = split_events(&events);
+        assert_eq!(clicks, vec![30, 60]);
+
+        let smooth = |anchors: &[usize]| {
+            smooth_cursor_path_dual_pass(
+                &points,
+                100,
+                SmoothingMode::Spring,
+                Interpolation::CatmullRom,
+                0.5,
+                0.5,
+                0.5,
+                &PathCleanup::default(),
+                None,
+                &SpringTuning::default(),
+                anchors,
+            )
+            .unwrap()
+            .0
+        };
+        let pinned = smooth(&clicks);
+        let free = smooth(&[]);
+        for &i in &clicks {
+            // Frame i of the 100 fps output is at the click's 10 ms timestamp
+            let click = &points[i];
+            assert!(
+                (pinned[i].x - click.x).abs() < 0.01 && (pinned[i].y - click.y).abs() < 0.01,
+                "click at {:?}, cursor at {:?}",
+                click,
+                pinned[i]
+            );
+            // The spring alone lags behind the click
+            assert!(click.x - free[i].x > 5.0, "{:?}", free[i]);
+        }
+        // Away from the clicks the path is left to the filter
+        assert!((pinned[95].x - free[95].x).abs() < 0.01);
+    }
+
+    /// A 250 ms stroke right to (250, 0), 300 ms at rest there, then a 250 ms
+    /// stroke down to (250, 250); samples 10 ms apart
+    fn two_strokes() -> Vec<CPoint> {
+        let mut points = still(81, (250.0, 0.0), 0.0, 10.0);
+        for (i, point) in points.iter_mut().enumerate() {
+            if i < 25 {
+                point.x = 10.0 * i as f32;
+            } else if i > 55 {
+                point.y = 10.0 * (i - 55) as f32;
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn strokes_split_at_dwells() {
+        let points = two_strokes();
+        assert_eq!(
+            segment_strokes(&points, 50.0, 150.0),
+            vec![Stroke { start: 0, end: 25 }, Stroke { start: 55, end: 80 }]
         );
-        for p in &mut relative_points {
-            p.timestamp_ms *= 1000.0;
+        // A pause shorter than the dwell time does not split the gesture
+        assert_eq!(
+            segment_strokes(&points, 50.0, 400.0),
+            vec![Stroke { start: 0, end: 80 }]
+        );
+        assert!(segment_strokes(&still(20, (5.0, 5.0), 0.0, 10.0), 50.0, 150.0).is_empty());
+    }
+
+    #[test]
+    fn bezier_strokes_ease_along_each_gesture_and_hold_in_between() {
+        let points = two_strokes();
+        let dense = bezier_strokes_to_framerate(&points, 100, 50.0, 150.0);
+        assert_eq!(dense.len(), 80);
+
+        for point in &dense {
+            let t = point.timestamp_ms;
+            if t <= 250.0 {
+                // Straight samples fit a straight curve
+                assert!(point.y.abs() < 1e-3, "{:?}", point);
+            } else if t <= 550.0 {
+                assert_eq!((point.x, point.y), (250.0, 0.0), "{:?}", point);
+            } else {
+                assert!((point.x - 250.0).abs() < 1e-3, "{:?}", point);
+            }
         }
-    } else {
-        log::info!(
-            "Detected MILLISECONDS (Duration: {:.2}ms). Keeping units.",
-            duration
+        // Eased: slow out of and into each rest, fastest mid-stroke
+        let step = |i: usize| distance(&dense[i], &dense[i + 1]);
+        assert!(step(0) < 2.0 && step(24) < 2.0 && step(12) > 10.0);
+        assert!(step(55) < 2.0 && step(67) > 10.0);
+        // The last frame (790 ms) is nearly at the final sample
+        assert!(
+            dense[79].y > 245.0 && dense[79].y < 250.0,
+            "{:?}",
+            dense[79]
         );
+
+        // A cursor that never moves stays put
+        let still_path =
+            bezier_strokes_to_framerate(&still(20, (5.0, 5.0), 0.0, 10.0), 100, 50.0, 150.0);
+        assert!(still_path.iter().all(|p| (p.x, p.y) == (5.0, 5.0)));
+    }
+
+    /// 60 fps frames at rest, then `length_px` to the right at a constant
+    /// speed over frames 10..=22 (200 ms), then at rest again
+    fn abrupt_stroke(length_px: f32) -> Vec<CPoint> {
+        (0..=40)
+            .map(|k| CPoint {
+                x: length_px * (k.clamp(10, 22) - 10) as f32 / 12.0,
+                y: 50.0,
+                timestamp_ms: k as f64 * 1000.0 / 60.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reprofiled_strokes_stay_within_the_limits() {
+        // Starts and stops dead at 10000 px/s: far past the acceleration cap
+        let points = abrupt_stroke(2000.0);
+        let profiled = reprofile_speed(&points);
+        assert_eq!(profiled.len(), points.len());
+
+        let frame_sec = 1.0 / 60.0;
+        let speeds: Vec<f64> = profiled
+            .windows(2)
+            .map(|pair| distance(&pair[0], &pair[1]) as f64 / frame_sec)
+            .collect();
+        for pair in speeds.windows(2) {
+            let accel = (pair[1] - pair[0]).abs() / frame_sec;
+            // A ramp ends part way through a frame: allow that frame's share
+            assert!(
+                accel <= ACCELERATION_MAX_PX_PER_SEC2 * 1.01,
+                "{} px/s^2",
+                accel
+            );
+        }
+        assert!(speeds.iter().all(|&v| v <= VELOCITY_MAX_PX_PER_SEC));
+
+        // Same line, same ends, same timestamps
+        for (before, after) in points.iter().zip(&profiled) {
+            assert_eq!(before.timestamp_ms, after.timestamp_ms);
+            assert_eq!(after.y, 50.0);
+        }
+        assert_eq!((profiled[10].x, profiled[22].x), (0.0, 2000.0));
+        assert!(profiled.windows(2).all(|pair| pair[1].x >= pair[0].x));
+
+        // A gentle stroke is left alone
+        let gentle = abrupt_stroke(20.0);
+        let untouched = reprofile_speed(&gentle);
+        assert!(gentle
+            .iter()
+            .zip(&untouched)
+            .all(|(a, b)| (a.x, a.y) == (b.x, b.y)));
     }
 
-    relative_points
+    #[test]
+    fn passthrough_reproduces_samples_on_the_frame_grid() {
+        // A zigzag sampled once per 60 fps frame
+        let points: Vec<CPoint> = (0..30)
+            .map(|k| CPoint {
+                x: 100.0 + if k % 2 == 0 { 0.0 } else { 37.5 } + k as f32,
+                y: 80.0 - 3.0 * k as f32,
+                timestamp_ms: k as f64 * 1000.0 / 60.0,
+            })
+            .collect();
+        let passthrough = |points: &[CPoint], frame_rate: i32| {
+            smooth_cursor_path_dual_pass(
+                points,
+                frame_rate,
+                SmoothingMode::Passthrough,
+                Interpolation::CatmullRom,
+                0.5,
+                0.5,
+                0.5,
+                &PathCleanup {
+                    timestamp_unit: TimestampUnit::Milliseconds,
+                    ..PathCleanup::default()
+                },
+                None,
+                &SpringTuning::default(),
+                &[],
+            )
+            .unwrap()
+            .0
+        };
+
+        // The grid stops short of the last sample (see `frame_grid`)
+        let dense = passthrough(&points, 60);
+        assert_eq!(dense.len(), points.len() - 1);
+        for (raw, out) in points.iter().zip(&dense) {
+            assert_eq!((out.x, out.y), (raw.x, raw.y));
+            assert!((out.timestamp_ms - raw.timestamp_ms).abs() < 1e-9);
+        }
+
+        // Between samples, on the straight line joining them
+        let doubled = passthrough(&points, 120);
+        let (a, b, mid) = (&points[4], &points[5], &doubled[9]);
+        assert_eq!(((a.x + b.x) / 2.0, (a.y + b.y) / 2.0), (mid.x, mid.y));
+
+        // Sanitized like every other mode: the broken sample is dropped
+        let mut damaged = points.clone();
+        damaged[10].x = f32::NAN;
+        let dense = passthrough(&damaged, 60);
+        assert!(dense.iter().all(|p| p.x.is_finite()));
+        assert_eq!((dense[9].x, dense[11].x), (points[9].x, points[11].x));
+    }
 }
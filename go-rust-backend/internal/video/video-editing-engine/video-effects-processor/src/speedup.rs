@@ -0,0 +1,150 @@
+// speedup.rs - Idle speed-up: stretches where neither the cursor nor the
+// picture moves play faster, through a piecewise-linear map between the
+// source clock and the output clock
+
+use crate::smoothing::CPoint;
+
+/// Play stretches where nothing moves faster ("auto speed-up")
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdleSpeedup {
+    /// Playback speed of idle stretches (1.0 = off)
+    pub factor: f32,
+    /// Shortest stretch (ms) that counts as idle
+    pub min_idle_ms: f32,
+    /// How far (px) the cursor may drift from where it came to rest and still
+    /// count as still
+    pub max_displacement_px: f32,
+    /// Share of the picture (%) that may change between frame samples for a
+    /// stretch to count as still (0 = judge by the cursor alone, without a
+    /// frame sampling pass)
+    pub max_frame_change_percent: f32,
+}
+
+impl Default for IdleSpeedup {
+    fn default() -> Self {
+        Self {
+            factor: 4.0,
+            min_idle_ms: 3000.0,
+            max_displacement_px: 4.0,
+            max_frame_change_percent: 0.0,
+        }
+    }
+}
+
+/// Piecewise-linear map between source time and output time (ms on the
+/// video clock, 0 = first frame). Time passes at normal speed outside the
+/// sped-up stretches, so an empty map is the identity.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimeMap {
+    /// `(source ms, output ms)` at the start and end of each stretch
+    knots: Vec<(f64, f64)>,
+}
+
+impl TimeMap {
+    /// Speed up every stretch of `path` (the smoothed cursor path) where the
+    /// cursor stays within `max_displacement_px` of where it came to rest for
+    /// at least `min_idle_ms`. `activity` holds `(ms, % of the picture
+    /// changed since the previous sample)`; samples above
+    /// `max_frame_change_percent` break a stretch (ignored when that is 0).
+    pub fn idle(path: &[CPoint], speedup: &IdleSpeedup, activity: &[(f64, f32)]) -> Self {
+        if speedup.factor <= 1.0 {
+            return Self::default();
+        }
+        let busy: Vec<f64> = match speedup.max_frame_change_percent {
+            limit if limit > 0.0 => activity
+                .iter()
+                .filter(|&&(_, changed)| changed > limit)
+                .map(|&(ms, _)| ms)
+                .collect(),
+            _ => Vec::new(),
+        };
+        let min_idle_ms = f64::from(speedup.min_idle_ms);
+        let mut stretches = Vec::new();
+        let mut keep = |start: f64, end: f64| {
+            if end > start && end - start >= min_idle_ms {
+                stretches.push((start, end));
+            }
+        };
+
+        let mut rest = 0;
+        while rest < path.len() {
+            let anchor = &path[rest];
+            let still = path[rest..]
+                .iter()
+                .take_while(|p| {
+                    (p.x - anchor.x).hypot(p.y - anchor.y) <= speedup.max_displacement_px
+                })
+                .count();
+            let last = &path[rest + still - 1];
+            // A changed sample means the picture moved since the one before
+            let mut start = anchor.timestamp_ms;
+            for &ms in busy
+                .iter()
+                .filter(|&&ms| ms > anchor.timestamp_ms && ms <= last.timestamp_ms)
+            {
+                keep(start, ms - activity_interval(activity));
+                start = ms;
+            }
+            keep(start, last.timestamp_ms);
+            rest += still;
+        }
+        Self::from_stretches(&stretches, f64::from(speedup.factor))
+    }
+
+    /// Play each of the (sorted, disjoint) source `stretches` `factor` times
+    /// faster
+    fn from_stretches(stretches: &[(f64, f64)], factor: f64) -> Self {
+        let mut knots = Vec::with_capacity(stretches.len() * 2);
+        let mut saved = 0.0;
+        for &(start, end) in stretches {
+            knots.push((start, start - saved));
+            saved += (end - start) * (1.0 - 1.0 / factor);
+            knots.push((end, end - saved));
+        }
+        Self { knots }
+    }
+
+    /// Whether every moment plays at normal speed
+    pub fn is_identity(&self) -> bool {
+        self.knots.is_empty()
+    }
+
+    /// Source time ranges (ms) that play faster
+    pub fn stretches(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.knots
+            .chunks_exact(2)
+            .map(|pair| (pair[0].0, pair[1].0))
+    }
+
+    /// Output time of the source moment `source_ms`
+    pub fn output_ms(&self, source_ms: f64) -> f64 {
+        warp(&self.knots, source_ms, |knot| knot)
+    }
+
+    /// Source moment shown at output time `output_ms`
+    pub fn source_ms(&self, output_ms: f64) -> f64 {
+        warp(&self.knots, output_ms, |(source, output)| (output, source))
+    }
+}
+
+/// Map `t` through the knots, read as `(from, to)` by `axes`; normal speed
+/// before the first and after the last
+fn warp(knots: &[(f64, f64)], t: f64, axes: impl Fn((f64, f64)) -> (f64, f64)) -> f64 {
+    let next = knots.partition_point(|&knot| axes(knot).0 <= t);
+    match (next.checked_sub(1).map(|i| axes(knots[i])), knots.get(next)) {
+        (None, _) => t,
+        (Some((from, to)), None) => to + (t - from),
+        (Some((from0, to0)), Some(&knot)) => {
+            let (from1, to1) = axes(knot);
+            to0 + (t - from0) * (to1 - to0) / (from1 - from0)
+        }
+    }
+}
+
+/// Spacing (ms) of the frame samples (0 with fewer than two)
+fn activity_interval(activity: &[(f64, f32)]) -> f64 {
+    match activity {
+        [(first, _), (second, _), ..] => second - first,
+        _ => 0.0,
+    }
+}
@@ -0,0 +1,25 @@
+// types.rs - Internal path types shared by the smoothing passes
+use crate::smoothing::CPoint;
+
+/// Filtered cursor sample with a velocity estimate (px/s).
+///
+/// The velocity is used internally for Hermite interpolation between frames;
+/// it is dropped when the path crosses the FFI boundary as `CPoint`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathPoint {
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub timestamp_ms: f64,
+}
+
+impl From<PathPoint> for CPoint {
+    fn from(p: PathPoint) -> Self {
+        CPoint {
+            x: p.x,
+            y: p.y,
+            timestamp_ms: p.timestamp_ms,
+        }
+    }
+}
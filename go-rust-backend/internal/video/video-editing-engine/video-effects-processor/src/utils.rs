@@ -1,20 +1,471 @@
-use std::sync::Once;
+use crate::engine::EngineError;
+use crate::sequence::ImageSequence;
+use crate::smoothing::CPoint;
+use ffmpeg_next as ffmpeg;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::{Once, OnceLock, RwLock};
+
+// ============================================================================
+// Logging (forwarded to the host application through an FFI callback)
+// ============================================================================
+
+/// Receives (level, message); level uses the `log_level` scale (1=error .. 5=trace)
+pub type LogCallback = extern "C" fn(c_int, *const c_char);
 
 static INIT_LOGGER: Once = Once::new();
+static LOG_CALLBACK: RwLock<Option<LogCallback>> = RwLock::new(None);
+static LOGGER: FfiLogger = FfiLogger;
 
-pub fn init_logging(level: i32) {
+struct FfiLogger;
+
+impl Log for FfiLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let callback = LOG_CALLBACK.read().map(|cb| *cb).unwrap_or(None);
+        match callback {
+            Some(cb) => {
+                // Interior NULs would truncate the message on the C side
+                let message = format!("{}", record.args()).replace('\0', " ");
+                if let Ok(c_message) = CString::new(message) {
+                    cb(level_to_int(record.level()), c_message.as_ptr());
+                }
+            }
+            // No host callback: behave like a plain stderr logger
+            None => eprintln!("[{} {}] {}", record.level(), record.target(), record.args()),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn level_to_int(level: Level) -> c_int {
+    match level {
+        Level::Error => 1,
+        Level::Warn => 2,
+        Level::Info => 3,
+        Level::Debug => 4,
+        Level::Trace => 5,
+    }
+}
+
+fn install_logger() {
     INIT_LOGGER.call_once(|| {
-        let env_level = match level {
-            0 => return,
-            1 => "error",
-            2 => "warn",
-            3 => "info",
-            4 => "debug",
-            _ => "trace",
+        // Fails only if the host already installed a logger; keep theirs
+        let _ = log::set_logger(&LOGGER);
+    });
+}
+
+/// Route log records to `callback` (None restores stderr output)
+pub fn set_log_callback(callback: Option<LogCallback>) {
+    install_logger();
+    if let Ok(mut slot) = LOG_CALLBACK.write() {
+        *slot = callback;
+    }
+}
+
+/// Apply `log_level` (0=off .. 5=trace) to both Rust and FFmpeg logging
+pub fn init_logging(level: i32) {
+    install_logger();
+
+    let (filter, av_level) = match level {
+        i32::MIN..=0 => (LevelFilter::Off, ffmpeg::log::Level::Quiet),
+        1 => (LevelFilter::Error, ffmpeg::log::Level::Error),
+        2 => (LevelFilter::Warn, ffmpeg::log::Level::Warning),
+        3 => (LevelFilter::Info, ffmpeg::log::Level::Info),
+        4 => (LevelFilter::Debug, ffmpeg::log::Level::Verbose),
+        _ => (LevelFilter::Trace, ffmpeg::log::Level::Debug),
+    };
+
+    log::set_max_level(filter);
+    ffmpeg::log::set_level(av_level);
+}
+
+// ============================================================================
+// FFmpeg Initialization
+// ============================================================================
+
+static FFMPEG_INIT: OnceLock<Result<(), ffmpeg::Error>> = OnceLock::new();
+
+/// Initialize FFmpeg exactly once per process.
+///
+/// `ffmpeg::init()` registers global device/format tables, which must not
+/// race when two renders start on different (Go-created) threads. Everything
+/// else in the pipeline (demuxer, decoder, filter graph, scaler, encoder,
+/// muxer) is created per call and never shared, so concurrent renders are safe
+/// once this has run. The process-wide state that is left is synchronized:
+/// the log level and `LOG_CALLBACK` are last-writer-wins by design, and the
+/// sprite cache (`renderer::SPRITE_CACHE`) sits behind a mutex.
+pub fn init_ffmpeg() -> Result<(), ffmpeg::Error> {
+    *FFMPEG_INIT.get_or_init(ffmpeg::init)
+}
+
+/// Human-readable build description: crate version, git hash, linked FFmpeg libraries
+pub fn build_info() -> String {
+    format!(
+        "focusframe {} (git {}) libavcodec {} libavformat {}",
+        env!("CARGO_PKG_VERSION"),
+        env!("FOCUSFRAME_GIT_HASH"),
+        format_ffmpeg_version(ffmpeg::codec::version()),
+        format_ffmpeg_version(ffmpeg::format::version()),
+    )
+}
+
+/// FFmpeg packs versions as (major << 16) | (minor << 8) | micro
+fn format_ffmpeg_version(version: u32) -> String {
+    format!(
+        "{}.{}.{}",
+        version >> 16,
+        (version >> 8) & 0xff,
+        version & 0xff
+    )
+}
+
+// ============================================================================
+// Container I/O
+// ============================================================================
+//
+// ffmpeg-next's `format::input`/`output` go through `Path::to_str().unwrap()`,
+// which panics on non-UTF-8 file names. These open the same contexts with the
+// path passed byte-for-byte instead.
+
+/// Path as the NUL-terminated string libavformat expects.
+///
+/// Unix paths are raw bytes. On Windows libavformat converts UTF-8 to UTF-16
+/// itself, so the path only has to be valid Unicode.
+fn path_to_cstring(path: &Path) -> Result<CString, EngineError> {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    };
+    #[cfg(not(unix))]
+    let bytes = path
+        .to_str()
+        .ok_or_else(|| EngineError::InvalidPath(path.display().to_string()))?
+        .as_bytes()
+        .to_vec();
+
+    CString::new(bytes).map_err(|_| EngineError::InvalidPath(path.display().to_string()))
+}
+
+/// Open a media file for demuxing (equivalent to `ffmpeg::format::input`).
+/// A directory of numbered PNGs or a `frame_%05d.png` pattern opens as an
+/// image sequence (see `ImageSequence`) at `sequence_frame_rate`.
+pub fn open_input(
+    path: &Path,
+    sequence_frame_rate: i32,
+) -> Result<ffmpeg::format::context::Input, EngineError> {
+    let Some(sequence) = ImageSequence::find(path)? else {
+        return open_url(
+            &path_to_cstring(path)?,
+            std::ptr::null(),
+            ffmpeg::Dictionary::new(),
+        );
+    };
+    log::info!(
+        "Reading {} as an image sequence of {} frames at {} fps",
+        path.display(),
+        sequence.frame_count(),
+        sequence_frame_rate
+    );
+    // The script goes to the concat demuxer inline, as a data: URL
+    let script = format!("data:,{}", sequence.concat_script(sequence_frame_rate));
+    let url =
+        CString::new(script).map_err(|_| EngineError::InvalidPath(path.display().to_string()))?;
+    let concat = unsafe { ffmpeg::ffi::av_find_input_format(c"concat".as_ptr()) };
+    if concat.is_null() {
+        return Err(EngineError::MissingComponent("concat demuxer"));
+    }
+    let mut options = ffmpeg::Dictionary::new();
+    // Absolute file: paths, which the demuxer only takes when not "safe"
+    options.set("safe", "0");
+    open_url(&url, concat, options)
+}
+
+/// `avformat_open_input` and `avformat_find_stream_info` on `url`, with the
+/// demuxer `input_format` forced unless null
+fn open_url(
+    url: &CStr,
+    input_format: *const ffmpeg::ffi::AVInputFormat,
+    options: ffmpeg::Dictionary,
+) -> Result<ffmpeg::format::context::Input, EngineError> {
+    unsafe {
+        let mut ps = std::ptr::null_mut();
+        let mut options = options.disown();
+        let opened = ffmpeg::ffi::avformat_open_input(
+            &mut ps,
+            url.as_ptr(),
+            input_format as _,
+            &mut options,
+        );
+        ffmpeg::Dictionary::own(options);
+        match opened {
+            0 => match ffmpeg::ffi::avformat_find_stream_info(ps, std::ptr::null_mut()) {
+                r if r >= 0 => Ok(ffmpeg::format::context::Input::wrap(ps)),
+                e => {
+                    ffmpeg::ffi::avformat_close_input(&mut ps);
+                    Err(EngineError::InputUnreadable(ffmpeg::Error::from(e)))
+                }
+            },
+            e => Err(EngineError::InputUnreadable(ffmpeg::Error::from(e))),
+        }
+    }
+}
+
+/// Create a media file for muxing; `muxer` overrides the extension-based guess
+pub fn open_output(
+    path: &Path,
+    muxer: Option<&str>,
+) -> Result<ffmpeg::format::context::Output, EngineError> {
+    let c_path = path_to_cstring(path)?;
+    let c_muxer = muxer
+        .map(|m| CString::new(m).map_err(|_| EngineError::MissingComponent("muxer")))
+        .transpose()?;
+
+    unsafe {
+        let mut ps = std::ptr::null_mut();
+        match ffmpeg::ffi::avformat_alloc_output_context2(
+            &mut ps,
+            std::ptr::null(),
+            c_muxer.as_ref().map_or(std::ptr::null(), |m| m.as_ptr()),
+            c_path.as_ptr(),
+        ) {
+            0 => match ffmpeg::ffi::avio_open(
+                &mut (*ps).pb,
+                c_path.as_ptr(),
+                ffmpeg::ffi::AVIO_FLAG_WRITE,
+            ) {
+                0 => Ok(ffmpeg::format::context::Output::wrap(ps)),
+                e => {
+                    ffmpeg::ffi::avformat_free_context(ps);
+                    Err(ffmpeg::Error::from(e).into())
+                }
+            },
+            e => Err(ffmpeg::Error::from(e).into()),
+        }
+    }
+}
+
+// ============================================================================
+// Cursor Sidecar Files
+// ============================================================================
+//
+// Little-endian layout written by the capture daemon:
+//
+//   offset  size  field
+//   0       4     magic "FFCP"
+//   4       4     version (u32, currently 1)
+//   8       8     point count (u64)
+//   16      16*n  records: x (f32), y (f32), timestamp_ms (f64)
+
+pub const CURSOR_FILE_MAGIC: [u8; 4] = *b"FFCP";
+pub const CURSOR_FILE_VERSION: u32 = 1;
+const CURSOR_FILE_HEADER_LEN: u64 = 16;
+const CURSOR_FILE_RECORD_LEN: u64 = 16;
+
+/// Read a cursor sidecar file, rejecting anything that is not exactly
+/// header + count records
+pub fn read_cursor_points(path: &Path) -> Result<Vec<CPoint>, EngineError> {
+    let file = File::open(path).map_err(EngineError::PointsFileUnreadable)?;
+    let file_len = file
+        .metadata()
+        .map_err(EngineError::PointsFileUnreadable)?
+        .len();
+    let mut reader = BufReader::new(file);
+
+    let mut header = [0u8; CURSOR_FILE_HEADER_LEN as usize];
+    reader.read_exact(&mut header).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => EngineError::PointsFileCorrupt("truncated header".into()),
+        _ => EngineError::PointsFileUnreadable(e),
+    })?;
+
+    if header[0..4] != CURSOR_FILE_MAGIC {
+        return Err(EngineError::PointsFileBadMagic);
+    }
+    let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    if version != CURSOR_FILE_VERSION {
+        return Err(EngineError::PointsFileUnsupportedVersion(version));
+    }
+    let count = u64::from_le_bytes(header[8..16].try_into().unwrap());
+
+    // Check the size up front so a corrupt count cannot trigger a huge allocation
+    let expected_len = count
+        .checked_mul(CURSOR_FILE_RECORD_LEN)
+        .and_then(|n| n.checked_add(CURSOR_FILE_HEADER_LEN));
+    if expected_len != Some(file_len) {
+        return Err(EngineError::PointsFileCorrupt(format!(
+            "header declares {} points but the file is {} bytes",
+            count, file_len
+        )));
+    }
+
+    let mut points = Vec::with_capacity(count as usize);
+    let mut record = [0u8; CURSOR_FILE_RECORD_LEN as usize];
+    for _ in 0..count {
+        reader.read_exact(&mut record).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => {
+                EngineError::PointsFileCorrupt("file shrank while reading".into())
+            }
+            _ => EngineError::PointsFileUnreadable(e),
+        })?;
+        points.push(CPoint {
+            x: f32::from_le_bytes(record[0..4].try_into().unwrap()),
+            y: f32::from_le_bytes(record[4..8].try_into().unwrap()),
+            timestamp_ms: f64::from_le_bytes(record[8..16].try_into().unwrap()),
+        });
+    }
+
+    Ok(points)
+}
+
+/// Write `points` in the sidecar format (mirror of `read_cursor_points`)
+pub fn write_cursor_points(path: &Path, points: &[CPoint]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writer.write_all(&CURSOR_FILE_MAGIC)?;
+    writer.write_all(&CURSOR_FILE_VERSION.to_le_bytes())?;
+    writer.write_all(&(points.len() as u64).to_le_bytes())?;
+    for point in points {
+        writer.write_all(&point.x.to_le_bytes())?;
+        writer.write_all(&point.y.to_le_bytes())?;
+        writer.write_all(&point.timestamp_ms.to_le_bytes())?;
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Messages this module's tests logged, as the host received them
+    static RECEIVED: Mutex<Vec<(c_int, String)>> = Mutex::new(Vec::new());
+    const TAG: &str = "utils test: ";
+
+    extern "C" fn record(level: c_int, message: *const c_char) {
+        let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+        // Other tests log concurrently; keep only ours
+        if let Some(message) = message.strip_prefix(TAG) {
+            RECEIVED.lock().unwrap().push((level, message.to_string()));
+        }
+    }
+
+    #[test]
+    fn log_callback_receives_only_records_within_the_level() {
+        set_log_callback(Some(record));
+        let mut received = Vec::new();
+        for level in [0, 2, 5] {
+            init_logging(level);
+            log::error!("{}error", TAG);
+            log::warn!("{}warn", TAG);
+            log::info!("{}info", TAG);
+            log::debug!("{}debug", TAG);
+            log::trace!("{}trace", TAG);
+            received.push(std::mem::take(&mut *RECEIVED.lock().unwrap()));
+        }
+        set_log_callback(None);
+        init_logging(0);
+
+        let expected = |names: &[&str]| -> Vec<(c_int, String)> {
+            (1..)
+                .zip(names.iter().map(|name| name.to_string()))
+                .collect()
         };
+        assert_eq!(received[0], expected(&[]));
+        assert_eq!(received[1], expected(&["error", "warn"]));
+        assert_eq!(
+            received[2],
+            expected(&["error", "warn", "info", "debug", "trace"])
+        );
+    }
 
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(env_level))
-            .format_timestamp_millis()
-            .init();
-    });
+    #[test]
+    fn sidecar_points_read_back_as_written() {
+        let dir = std::env::temp_dir().join(format!("utils-sidecar-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cursor.ffcp");
+        let points: Vec<CPoint> = (0..50)
+            .map(|i| CPoint {
+                x: i as f32 * 1.25 - 10.0,
+                y: 1080.5 - i as f32,
+                timestamp_ms: 1_700_000_000_000.0 + f64::from(i) * 16.667,
+            })
+            .collect();
+        write_cursor_points(&path, &points).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 16 + 16 * points.len());
+        assert_eq!(bytes[0..4], CURSOR_FILE_MAGIC);
+        let read = read_cursor_points(&path).unwrap();
+        assert_eq!(read.len(), points.len());
+        for (read, written) in read.iter().zip(&points) {
+            assert_eq!(
+                (read.x, read.y, read.timestamp_ms),
+                (written.x, written.y, written.timestamp_ms)
+            );
+        }
+
+        // An empty path round-trips too
+        write_cursor_points(&path, &[]).unwrap();
+        assert!(read_cursor_points(&path).unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn damaged_sidecar_files_are_refused() {
+        let dir = std::env::temp_dir().join(format!("utils-sidecar-bad-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cursor.ffcp");
+        let point = CPoint {
+            x: 1.0,
+            y: 2.0,
+            timestamp_ms: 3.0,
+        };
+        write_cursor_points(&path, &[point, point]).unwrap();
+        let good = std::fs::read(&path).unwrap();
+        let read_bytes = |bytes: &[u8]| {
+            std::fs::write(&path, bytes).unwrap();
+            read_cursor_points(&path).unwrap_err()
+        };
+
+        let mut bad_magic = good.clone();
+        bad_magic[0] = b'X';
+        assert!(matches!(
+            read_bytes(&bad_magic),
+            EngineError::PointsFileBadMagic
+        ));
+        let mut version = good.clone();
+        version[4] = 2;
+        assert!(matches!(
+            read_bytes(&version),
+            EngineError::PointsFileUnsupportedVersion(2)
+        ));
+        assert!(matches!(
+            read_bytes(&good[..10]),
+            EngineError::PointsFileCorrupt(_)
+        ));
+        assert!(matches!(
+            read_bytes(&good[..good.len() - 1]),
+            EngineError::PointsFileCorrupt(_)
+        ));
+        let mut huge_count = good.clone();
+        huge_count[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            read_bytes(&huge_count),
+            EngineError::PointsFileCorrupt(_)
+        ));
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
@@ -0,0 +1,225 @@
+// Pre-flight validation of processing inputs (run before a multi-minute export)
+use crate::engine::{EngineError, ProcessingOptions, MAX_FRAME_RATE};
+use crate::renderer;
+use crate::smoothing::CPoint;
+use crate::video;
+use std::path::Path;
+
+// ============================================================================
+// Validation Flags (bitmask, 0 = all inputs valid)
+// ============================================================================
+
+pub const VALID_INPUT_VIDEO_UNREADABLE: i32 = 1 << 0;
+pub const VALID_INPUT_NO_VIDEO_STREAM: i32 = 1 << 1;
+pub const VALID_INPUT_VIDEO_UNDECODABLE: i32 = 1 << 2;
+pub const VALID_SPRITE_UNREADABLE: i32 = 1 << 3;
+pub const VALID_TOO_FEW_POINTS: i32 = 1 << 4;
+pub const VALID_NON_MONOTONIC_TIMESTAMPS: i32 = 1 << 5;
+pub const VALID_NON_FINITE_POINT: i32 = 1 << 6;
+pub const VALID_BAD_FRAME_RATE: i32 = 1 << 7;
+pub const VALID_UNSUPPORTED_OUTPUT: i32 = 1 << 8;
+
+/// A path needs a start and an end; shorter Catmull-Rom windows clamp their
+/// missing outer control points, so two or three points still render
+pub const MIN_CURSOR_POINTS: usize = 2;
+
+/// Check every input and collect all problems instead of stopping at the first
+pub fn validate_inputs(
+    input_path: &Path,
+    cursor_path: &Path,
+    raw_points: &[CPoint],
+    config: &ProcessingOptions,
+) -> i32 {
+    let mut flags = 0;
+
+    match video::probe_video(input_path) {
+        Ok(()) => {}
+        Err(e @ EngineError::NoVideoStream(_)) => {
+            log::warn!("Input '{}': {}", input_path.display(), e);
+            flags |= VALID_INPUT_NO_VIDEO_STREAM;
+        }
+        Err(EngineError::Undecodable) => {
+            log::warn!(
+                "Input '{}' has no decodable video frames",
+                input_path.display()
+            );
+            flags |= VALID_INPUT_VIDEO_UNDECODABLE;
+        }
+        Err(e) => {
+            log::warn!(
+                "Input video '{}' is unreadable: {}",
+                input_path.display(),
+                e
+            );
+            flags |= VALID_INPUT_VIDEO_UNREADABLE;
+        }
+    }
+
+    if let Err(e) = renderer::probe_cursor_sprite(cursor_path) {
+        log::warn!(
+            "Cursor sprite '{}' is unreadable: {}",
+            cursor_path.display(),
+            e
+        );
+        flags |= VALID_SPRITE_UNREADABLE;
+    }
+
+    flags |= validate_points(raw_points);
+
+    if config.frame_rate <= 0 || config.frame_rate > MAX_FRAME_RATE {
+        log::warn!("Invalid frame rate: {}", config.frame_rate);
+        flags |= VALID_BAD_FRAME_RATE;
+    }
+
+    if !config.container.supports(config.codec) {
+        log::warn!(
+            "{:?} video cannot be stored in a {:?} container",
+            config.codec,
+            config.container
+        );
+        flags |= VALID_UNSUPPORTED_OUTPUT;
+    } else if ffmpeg_next::encoder::find_by_name(config.codec.encoder_name()).is_none() {
+        log::warn!(
+            "Encoder '{}' is not available in the linked FFmpeg build",
+            config.codec.encoder_name()
+        );
+        flags |= VALID_UNSUPPORTED_OUTPUT;
+    }
+
+    flags
+}
+
+/// Point-only checks (no I/O)
+pub fn validate_points(raw_points: &[CPoint]) -> i32 {
+    let mut flags = 0;
+
+    if raw_points.len() < MIN_CURSOR_POINTS {
+        log::warn!(
+            "Too few cursor points: {} (need at least {})",
+            raw_points.len(),
+            MIN_CURSOR_POINTS
+        );
+        flags |= VALID_TOO_FEW_POINTS;
+    }
+
+    if raw_points
+        .iter()
+        .any(|p| !p.x.is_finite() || !p.y.is_finite() || !p.timestamp_ms.is_finite())
+    {
+        flags |= VALID_NON_FINITE_POINT;
+    }
+
+    if let Some(i) = raw_points
+        .windows(2)
+        .position(|w| w[1].timestamp_ms < w[0].timestamp_ms)
+    {
+        log::warn!(
+            "Cursor timestamps go backwards at index {} ({:.3} -> {:.3})",
+            i + 1,
+            raw_points[i].timestamp_ms,
+            raw_points[i + 1].timestamp_ms
+        );
+        flags |= VALID_NON_MONOTONIC_TIMESTAMPS;
+    }
+
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine;
+
+    fn point(x: f32, timestamp_ms: f64) -> CPoint {
+        CPoint {
+            x,
+            y: 100.0,
+            timestamp_ms,
+        }
+    }
+
+    /// A steady drag sampled every 200 ms
+    fn drag() -> Vec<CPoint> {
+        (0..8)
+            .map(|i| point(i as f32 * 5.0, i as f64 * 200.0))
+            .collect()
+    }
+
+    #[test]
+    fn text_file_is_not_a_video() {
+        let path =
+            std::env::temp_dir().join(format!("focusframe-notes-{}.mp4", std::process::id()));
+        std::fs::write(&path, "Meeting notes: ship the cursor export on Friday.\n").unwrap();
+
+        let probed = video::probe_video(&path);
+        let flags = validate_inputs(&path, &path, &drag(), &ProcessingOptions::default());
+        std::fs::remove_file(&path).ok();
+
+        assert!(
+            matches!(probed, Err(EngineError::InputUnreadable(_))),
+            "{:?}",
+            probed
+        );
+        assert_ne!(flags & VALID_INPUT_VIDEO_UNREADABLE, 0);
+        assert_ne!(flags & VALID_SPRITE_UNREADABLE, 0);
+        assert_eq!(flags & VALID_NON_MONOTONIC_TIMESTAMPS, 0);
+    }
+
+    #[test]
+    fn backwards_path_is_refused() {
+        let mut points = drag();
+        points.reverse();
+
+        assert_eq!(validate_points(&points), VALID_NON_MONOTONIC_TIMESTAMPS);
+        match engine::smooth_path(&points, &ProcessingOptions::default()) {
+            Err(EngineError::NonMonotonicTimestamps { index, backstep_ms }) => {
+                assert_eq!(index, 1);
+                assert_eq!(backstep_ms, 200.0);
+            }
+            other => panic!("expected NonMonotonicTimestamps, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn one_backwards_step_is_refused() {
+        let mut points = drag();
+        // Jumps back 50 ms, well past the tolerance for coalesced events
+        points[5].timestamp_ms = 750.0;
+
+        assert_eq!(validate_points(&points), VALID_NON_MONOTONIC_TIMESTAMPS);
+        match engine::smooth_path(&points, &ProcessingOptions::default()) {
+            Err(EngineError::NonMonotonicTimestamps { index, backstep_ms }) => {
+                assert_eq!(index, 5);
+                assert_eq!(backstep_ms, 50.0);
+            }
+            other => panic!("expected NonMonotonicTimestamps, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn too_few_points_are_refused() {
+        let points = &drag()[..MIN_CURSOR_POINTS - 1];
+
+        assert_eq!(validate_points(points), VALID_TOO_FEW_POINTS);
+        match engine::smooth_path(points, &ProcessingOptions::default()) {
+            Err(EngineError::TooFewPoints(count)) => assert_eq!(count, MIN_CURSOR_POINTS - 1),
+            other => panic!("expected TooFewPoints, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn two_and_three_point_paths_still_render() {
+        for len in [2, 3] {
+            let points = &drag()[..len];
+            assert_eq!(validate_points(points), 0);
+            let smoothed = engine::smooth_path(points, &ProcessingOptions::default()).unwrap();
+            assert!(smoothed.len() > len, "{} points gave {:?}", len, smoothed);
+        }
+    }
+
+    #[test]
+    fn steady_drag_is_valid() {
+        assert_eq!(validate_points(&drag()), 0);
+        assert!(engine::smooth_path(&drag(), &ProcessingOptions::default()).is_ok());
+    }
+}
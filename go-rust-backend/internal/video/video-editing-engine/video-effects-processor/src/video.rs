@@ -1,46 +1,187 @@
-use crate::renderer::{composite_cursor_subpixel, CursorSprite};
-use crate::smoothing::CPoint;
-use crate::VideoProcessingConfig;
-use ffmpeg::format::{input, output, Pixel};
+use crate::camera::{CameraOverlay, CameraPlan, ZoomKeyframe};
+use crate::constants::{
+    ALIGNMENT_MAX_WINDOW_MS, ALIGNMENT_SAMPLE_COUNT, HEADING_MOVING_SPEED_PX_PER_SEC,
+    HEADING_STOPPED_SPEED_PX_PER_SEC, IDLE_PIXEL_CHANGE_LEVELS, IDLE_SAMPLE_INTERVAL_MS,
+    IDLE_SAMPLE_WIDTH, PIPELINE_QUEUE_FRAMES,
+};
+use crate::engine::{
+    select_encoder, BitDepth, EncoderBackend, EngineError, FrameBounds, Heartbeat, MotionTrail,
+    ProcessingOptions, Progress, RateControl, RenderObserver, RenderWarning, ScaleFilter,
+    TailBehavior, VideoCodec, WarningCounts, WarningKind, DEFAULT_SEQUENCE_FRAME_RATE,
+    INDETERMINATE_PROGRESS, MAX_GIF_DURATION_MS,
+};
+use crate::frame_style::FrameStyleOverlay;
+use crate::orientation::{Orientation, RotationHandling};
+use crate::renderer::{
+    composite_cursor_yuv420, load_cursor_sprite_prescaled, load_font, prescale, CaptionOverlay,
+    ClickRipples, CursorShadow, CursorSprite, CursorStyle, FrameView, KeystrokeOverlay, Overlay,
+    RedactionOverlay, RedactionRegion, SpotlightOverlay, SpriteSchedule, WatermarkOverlay,
+    Yuv420Frame, YuvSample, YuvSprite,
+};
+use crate::smoothing::{CClickEvent, CPoint};
+use crate::speedup::TimeMap;
+use crate::utils;
+use crate::VideoMetadata;
+use ffmpeg::format::{sample::Type as SampleType, Pixel, Sample};
 use ffmpeg::media::Type;
+use ffmpeg::software::resampling::context::Context as ResamplerContext;
 use ffmpeg::software::scaling::{context::Context as ScalerContext, flag::Flags};
+use ffmpeg::util::color;
+use ffmpeg::util::error::EAGAIN;
+use ffmpeg::util::frame::audio::Audio as AudioFrame;
 use ffmpeg::util::frame::video::Video as VideoFrame;
-use ffmpeg::{codec, encoder, Error as FfmpegError, Packet, Rational};
+use ffmpeg::{codec, encoder, ChannelLayout, Error as FfmpegError, Packet, Rational, Rescale};
 use ffmpeg_next as ffmpeg;
-use std::error::Error;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 // ============================================================================
 // Main Video Processing Function
 // ============================================================================
 
+/// Counters collected while rendering
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RenderStats {
+    pub frames_encoded: u64,
+    /// Length of the input the output covers
+    pub source_duration: Duration,
+    pub encoder_backend: EncoderBackend,
+    pub warnings: WarningCounts,
+}
+
 pub fn process_video(
-    input_path: &str,
-    output_path: &str,
+    input_path: &Path,
+    output_path: &Path,
     cursor_points: &[CPoint],
+    clicks: &[CClickEvent],
     cursor_sprite: &CursorSprite,
-    config: &VideoProcessingConfig,
-    mut progress_callback: impl FnMut(f32),
-) -> Result<(), Box<dyn Error>> {
+    config: &ProcessingOptions,
+    observer: &mut impl RenderObserver,
+) -> Result<RenderStats, EngineError> {
     log::info!(
         "Starting video processing: {} -> {}",
-        input_path,
-        output_path
+        input_path.display(),
+        output_path.display()
     );
 
-    ffmpeg::init()?;
-    progress_callback(0.0);
+    utils::init_ffmpeg()?;
+    observer.progress(Progress {
+        fraction: 0.0,
+        frames: 0,
+    });
+
+    // Fail on a missing encoder before spending time on the input
+    let encoder_candidates = encoder_candidates(config)?;
+    let keystrokes = keystroke_overlay(config)?;
+    let captions = caption_overlay(config)?;
+    let watermark = config
+        .watermark
+        .as_ref()
+        .map(|watermark| WatermarkOverlay::new(watermark, config.gamma_correct_blend))
+        .transpose()?;
+    let (kind_sprites, sprite_schedule) = cursor_kind_sprites(config)?;
+    let ten_bit = match config.bit_depth {
+        BitDepth::Ten if config.codec == VideoCodec::Gif => {
+            log::warn!("GIF frames are paletted, ignoring bit_depth");
+            false
+        }
+        depth => depth == BitDepth::Ten,
+    };
+    // 10-bit output is composited in 10-bit YUV whenever the options allow
+    let yuv_compositing = (config.yuv_compositing || ten_bit)
+        && match rgba_requirement(config) {
+            Some(feature) => {
+                log::info!("{} needs RGBA frames, not compositing in YUV", feature);
+                false
+            }
+            None => true,
+        };
+    let (working_format, frame_format) = match (yuv_compositing, ten_bit) {
+        (true, true) => (Pixel::YUV420P10LE, "yuv420p10le"),
+        (true, false) => (Pixel::YUV420P, "yuv420p"),
+        (false, _) => (Pixel::RGBA, "rgba"),
+    };
 
     // 1. Open Input
-    let mut input_ctx = input(&input_path)?;
-    let video_stream = input_ctx
-        .streams()
-        .best(Type::Video)
-        .ok_or("No video stream found")?;
+    let mut input_ctx = utils::open_input(input_path, config.sequence_frame_rate)?;
+    let video_stream = best_video_stream(&input_ctx)?;
+    if !config.allow_still_image && is_still_image(&input_ctx, &video_stream) {
+        return Err(EngineError::StillImage);
+    }
     let video_stream_idx = video_stream.index();
+    let video_time_base = video_stream.time_base();
+    let input_time_base = f64::from(video_time_base);
+    let frame_rate = video_stream.rate();
+    let video_start = match video_stream.start_time() {
+        ffmpeg::ffi::AV_NOPTS_VALUE => 0,
+        start => start,
+    };
+    // Trim range in stream ticks (the options count from the first frame)
+    let to_ticks = |ms: f64| video_start + (ms / 1000.0 / input_time_base).round() as i64;
+    let mut trim = TrimWindow {
+        start: (config.trim_start_ms > 0.0).then(|| to_ticks(config.trim_start_ms)),
+        end: config.trim_end_ms.map(to_ticks),
+        held: None,
+        kept: false,
+    };
+    let duration_ms = stream_duration_ms(&input_ctx, &video_stream) as f64;
+    // Source length for progress, from the frame count when the container has one
+    let source_length_ms = estimate_total_frames(&video_stream, &input_ctx)
+        .zip(average_frame_rate(&video_stream))
+        .map(|(frames, fps)| frames as f64 * 1000.0 / f64::from(fps));
+    // Unknown (-1) for some inputs; those are checked once decoding ends
+    let known_duration = (duration_ms >= 0.0).then_some(duration_ms);
+    if trim.start.is_some() && known_duration.is_some_and(|d| config.trim_start_ms >= d) {
+        return Err(EngineError::TrimPastEnd {
+            start_ms: config.trim_start_ms,
+            duration_ms,
+        });
+    }
+    // GIF export holds every frame until the end, so long inputs are refused
+    // before any work (inputs of unknown length are let through)
+    if config.codec == VideoCodec::Gif {
+        let end_ms = match (known_duration, config.trim_end_ms) {
+            (Some(duration), Some(end)) => Some(duration.min(end)),
+            (duration, end) => duration.or(end),
+        };
+        if let Some(duration_ms) = end_ms.map(|end| end - config.trim_start_ms) {
+            if duration_ms > MAX_GIF_DURATION_MS {
+                return Err(EngineError::GifTooLong { duration_ms });
+            }
+        }
+    }
+    // Idle stretches to play faster. Frames are moved onto the output clock
+    // before the fps filter, which then drops what the speed-up skips.
+    let time_map = match config.idle_speedup {
+        Some(speedup) => {
+            let activity = if speedup.max_frame_change_percent > 0.0 {
+                sample_frame_activity(
+                    input_path,
+                    config.sequence_frame_rate,
+                    IDLE_SAMPLE_INTERVAL_MS,
+                )?
+            } else {
+                Vec::new()
+            };
+            TimeMap::idle(cursor_points, &speedup, &activity)
+        }
+        None => TimeMap::default(),
+    };
+    for (start, end) in time_map.stretches() {
+        log::info!("Idle from {:.0} to {:.0} ms, sped up", start, end);
+    }
+    let retime = Retime {
+        time_map: &time_map,
+        stream_start: video_start,
+        time_base: input_time_base,
+    };
 
     // 2. Create Decoder
     let decoder_context = codec::context::Context::from_parameters(video_stream.parameters())?;
-    let mut decoder = decoder_context.decoder().video()?;
+    let decoder = decoder_context.decoder().video()?;
 
     log::info!(
         "Input found: {}x{} (Codec: {:?})",
@@ -48,23 +189,90 @@ pub fn process_video(
         decoder.height(),
         decoder.id()
     );
+    let colors = Colorimetry::of_source(&decoder);
+    // Rotation metadata says how players stand the stored frames upright
+    let display_matrix = display_matrix(&video_stream);
+    let orientation = match display_matrix.map(|matrix| Orientation::from_display_matrix(&matrix)) {
+        Some(Some(orientation)) => orientation,
+        Some(None) => {
+            log::warn!("Source rotation is not a multiple of 90 degrees, ignoring it");
+            Orientation::default()
+        }
+        None => Orientation::default(),
+    };
+    if !orientation.is_upright() {
+        log::info!(
+            "Source is stored turned ({} quarter turns to stand upright{})",
+            orientation.quarter_turns,
+            if orientation.mirrored {
+                ", mirrored"
+            } else {
+                ""
+            }
+        );
+    }
+    let kept_rotation = match (display_matrix, config.rotation) {
+        (Some(_), RotationHandling::KeepMetadata) if config.codec == VideoCodec::Gif => {
+            log::warn!("GIF has no rotation metadata, the frames are turned upright");
+            None
+        }
+        (Some(matrix), RotationHandling::KeepMetadata) => Some(KeptRotation {
+            matrix,
+            orientation,
+        }),
+        _ => None,
+    };
+    // Everything past the filter graph sees the cropped, upright frame; the
+    // crop is in stored coordinates, taken before turning
+    let crop = config
+        .crop
+        .map(|crop| crop.fit(decoder.width(), decoder.height()))
+        .transpose()?;
+    let stored_size = crop.map_or((decoder.width(), decoder.height()), |crop| {
+        (crop.width, crop.height)
+    });
+    let (width, height) = orientation.upright_size(stored_size);
+    let crop_offset = crop.map_or((0.0, 0.0), |crop| (crop.x as f32, crop.y as f32));
+    // Stored source position (px) to the position in the frame drawn on
+    let to_frame =
+        |x: f32, y: f32| orientation.map_point((x - crop_offset.0, y - crop_offset.1), stored_size);
+    let reframed = crop.is_some() || !orientation.is_upright();
 
     // 3. Configure Output
-    let mut output_ctx = output(&output_path)?;
+    let mut output_ctx = utils::open_output(output_path, config.container.muxer_name())?;
     // We force the output frame rate from config (typically 60)
     let output_framerate = Rational::new(config.frame_rate, 1);
 
-    // Create Encoder (H.264)
-    let mut encoder = create_video_encoder(
-        decoder.width(),
-        decoder.height(),
+    // Create Encoder
+    let encoder = create_video_encoder(
+        &encoder_candidates,
+        config,
+        width,
+        height,
         output_framerate,
+        colors,
+        kept_rotation.as_ref(),
+        false,
         &mut output_ctx,
     )?;
+    // Source audio, copied or re-encoded (after the video stream, which stays 0).
+    // GIFs have no sound, and sound is not sped up with idle stretches.
+    let audio = if config.codec == VideoCodec::Gif {
+        None
+    } else if !time_map.is_identity() {
+        log::warn!("Idle stretches are sped up, the output will be silent");
+        None
+    } else {
+        add_audio_output(&input_ctx, &video_stream, config, &mut output_ctx)?
+    };
 
-    progress_callback(0.05);
+    observer.progress(Progress {
+        fraction: 0.05,
+        frames: 0,
+    });
 
-    // 4. Setup Filter Graph (VFR -> CFR + Pixel Format Conversion)
+    // 4. Setup Filter Graph (Crop + Orientation + VFR -> CFR + Pixel Format Conversion)
+    // (for YUV compositing the format filter passes YUV420P input through untouched)
     // We must manually add and link filters since parse() doesn't connect to existing contexts
     let mut filter_graph = ffmpeg::filter::Graph::new();
 
@@ -75,360 +283,4193 @@ pub fn process_video(
         decoder.width(),
         decoder.height(),
         decoder.format() as i32,
-        video_stream.time_base().numerator(),
-        video_stream.time_base().denominator(),
+        video_time_base.numerator(),
+        video_time_base.denominator(),
         decoder.aspect_ratio().numerator(),
         decoder.aspect_ratio().denominator(),
     );
 
     let mut filter_src_ctx = filter_graph.add(
-        &ffmpeg::filter::find("buffer").ok_or("buffer filter not found")?,
+        &ffmpeg::filter::find("buffer").ok_or(EngineError::MissingComponent("buffer filter"))?,
         "src",
         &buffer_args,
     )?;
 
-    // B. FPS Filter (VFR -> CFR conversion)
-    let fps_args = format!("fps={}:round=near", config.frame_rate);
+    // B. Crop Filter (only with `crop`), before the fps filter repeats frames
+    let crop_filter = match crop {
+        Some(crop) => Some(filter_graph.add(
+            &ffmpeg::filter::find("crop").ok_or(EngineError::MissingComponent("crop filter"))?,
+            "crop",
+            &format!(
+                "w={}:h={}:x={}:y={}",
+                crop.width, crop.height, crop.x, crop.y
+            ),
+        )?),
+        None => None,
+    };
+
+    // C. Orientation Filters, standing the frames upright
+    let orient_filters = orientation
+        .filters()
+        .iter()
+        .enumerate()
+        .map(|(i, &(name, args))| {
+            let filter = ffmpeg::filter::find(name).ok_or(EngineError::MissingComponent(name))?;
+            Ok(filter_graph.add(&filter, &format!("orient{}", i), args)?)
+        })
+        .collect::<Result<Vec<_>, EngineError>>()?;
+
+    // D. FPS Filter (VFR -> CFR conversion)
+    let mut fps_args = format!("fps={}:round=near", config.frame_rate);
+    if let Some(start) = trim.start {
+        // Output frame 0 shows whatever is on screen at the trim start
+        fps_args += &format!(
+            ":start_time={}",
+            retime.ticks(start) as f64 * input_time_base
+        );
+    }
     let mut fps_filter = filter_graph.add(
-        &ffmpeg::filter::find("fps").ok_or("fps filter not found")?,
+        &ffmpeg::filter::find("fps").ok_or(EngineError::MissingComponent("fps filter"))?,
         "fps",
         &fps_args,
     )?;
 
-    // C. Format Filter (convert to RGBA for cursor overlay)
+    // E. Scale Filter (size unchanged), told the source's YUV matrix and range
+    // so the conversion to the working format keeps its colors
+    let scale_args = |output_range: &str| {
+        format!(
+            "in_color_matrix={matrix}:out_color_matrix={matrix}:in_range={}:out_range={}",
+            colors.range_name(),
+            output_range,
+            matrix = colors.matrix_name(),
+        )
+    };
+    // swscale dithers sources deeper than 8 bits when it writes 8-bit YUV but
+    // truncates them straight into RGBA, so those go through YUV 4:4:4 first
+    let dither_to_rgba = working_format == Pixel::RGBA && bits_per_channel(decoder.format()) > 8;
+    let output_range = if yuv_compositing || dither_to_rgba {
+        colors.range_name()
+    } else {
+        "full"
+    };
+    let scale =
+        ffmpeg::filter::find("scale").ok_or(EngineError::MissingComponent("scale filter"))?;
+    let mut scale_filter = filter_graph.add(&scale, "scale", &scale_args(output_range))?;
+    let mut dither_filters = if dither_to_rgba {
+        Some((
+            filter_graph.add(
+                &ffmpeg::filter::find("format")
+                    .ok_or(EngineError::MissingComponent("format filter"))?,
+                "dither",
+                "pix_fmts=yuv444p",
+            )?,
+            filter_graph.add(&scale, "dither_scale", &scale_args("full"))?,
+        ))
+    } else {
+        None
+    };
+
+    // F. Format Filter (convert to RGBA for cursor overlay)
     let mut format_filter = filter_graph.add(
-        &ffmpeg::filter::find("format").ok_or("format filter not found")?,
+        &ffmpeg::filter::find("format").ok_or(EngineError::MissingComponent("format filter"))?,
         "format",
-        "pix_fmts=rgba",
+        &format!("pix_fmts={}", frame_format),
     )?;
 
-    // D. Sink Filter ("buffersink")
+    // G. Sink Filter ("buffersink")
     let mut filter_sink_ctx = filter_graph.add(
-        &ffmpeg::filter::find("buffersink").ok_or("buffersink filter not found")?,
+        &ffmpeg::filter::find("buffersink")
+            .ok_or(EngineError::MissingComponent("buffersink filter"))?,
         "sink",
         "",
     )?;
 
-    // E. Link the filters: buffer -> fps -> format -> buffersink
-    log::info!(
-        "Building filter graph: buffer -> fps={} -> format=rgba -> buffersink",
-        config.frame_rate
+    // H. Link the filters:
+    // buffer -> [crop ->] [orientation ->] fps -> scale -> [yuv444p -> scale ->] format -> buffersink
+    log::debug!(
+        "Building filter graph: buffer -> {}{}fps={} -> scale -> {}format={} -> buffersink",
+        crop.map_or(String::new(), |crop| format!(
+            "crop={}x{}+{}+{} -> ",
+            crop.width, crop.height, crop.x, crop.y
+        )),
+        orientation
+            .filters()
+            .iter()
+            .map(|(name, args)| match *args {
+                "" => format!("{} -> ", name),
+                args => format!("{}={} -> ", name, args),
+            })
+            .collect::<String>(),
+        config.frame_rate,
+        if dither_to_rgba {
+            "yuv444p -> scale -> "
+        } else {
+            ""
+        },
+        frame_format
     );
 
-    filter_src_ctx.link(0, &mut fps_filter, 0);
-    fps_filter.link(0, &mut format_filter, 0);
+    let mut front: Vec<_> = crop_filter.into_iter().chain(orient_filters).collect();
+    link_chain(&mut filter_src_ctx, &mut front, &mut fps_filter);
+    fps_filter.link(0, &mut scale_filter, 0);
+    match &mut dither_filters {
+        Some((dither, dither_scale)) => {
+            scale_filter.link(0, dither, 0);
+            dither.link(0, dither_scale, 0);
+            dither_scale.link(0, &mut format_filter, 0);
+        }
+        None => scale_filter.link(0, &mut format_filter, 0),
+    }
     format_filter.link(0, &mut filter_sink_ctx, 0);
 
     filter_graph.validate()?;
 
-    log::info!("Filter graph configured successfully");
+    log::debug!("Filter graph configured successfully");
 
-    // 5. Scaler for Final Output (RGBA -> YUV420P for H.264)
-    let mut reverse_scaler = ScalerContext::get(
-        Pixel::RGBA,
-        decoder.width(),
-        decoder.height(),
-        encoder.format(),
-        decoder.width(),
-        decoder.height(),
-        Flags::BILINEAR,
-    )?;
+    observer.progress(Progress {
+        fraction: 0.10,
+        frames: 0,
+    });
+
+    // 5. Pre-calculate Cursor Lookup Table and Overlays
+    // Positions given in stored source coordinates move into the crop and
+    // turn with the frame
+    let mut cursor_lookup = build_cursor_lookup(cursor_points);
+    for (_, x, y) in cursor_lookup.iter_mut() {
+        (*x, *y) = to_frame(*x, *y);
+    }
+    confine_to_frame(&mut cursor_lookup, config.frame_bounds, width, height);
+    // Kept from before any zoom or inset, for hiding the cursor outside
+    let crop_path = crop.map(|_| cursor_lookup.clone());
+    let cropped_clicks: Option<Vec<CClickEvent>> = reframed.then(|| {
+        clicks
+            .iter()
+            .map(|click| {
+                let (x, y) = to_frame(click.x, click.y);
+                CClickEvent { x, y, ..*click }
+            })
+            .collect()
+    });
+    let clicks = cropped_clicks.as_deref().unwrap_or(clicks);
+    let zoom_keyframes: Vec<ZoomKeyframe> = config
+        .zoom_keyframes
+        .iter()
+        .map(|keyframe| {
+            let (center_x, center_y) = to_frame(keyframe.center_x, keyframe.center_y);
+            ZoomKeyframe {
+                center_x,
+                center_y,
+                ..*keyframe
+            }
+        })
+        .collect();
+    // Manual zoom keyframes, else click zoom, else follow-cursor zoom; every
+    // layer after the camera draws in the zoomed frame's coordinates
+    let camera_path = || -> Vec<CPoint> {
+        cursor_lookup
+            .iter()
+            .map(|&(timestamp_ms, x, y)| CPoint { x, y, timestamp_ms })
+            .collect()
+    };
+    let camera = if !zoom_keyframes.is_empty() {
+        Some(CameraPlan::from_keyframes(&zoom_keyframes, width, height))
+    } else if let Some(zoom) = config.click_zoom.filter(|zoom| zoom.zoom > 1.0) {
+        Some(CameraPlan::from_clicks(
+            clicks,
+            &camera_path(),
+            width,
+            height,
+            &zoom,
+        ))
+    } else {
+        config
+            .auto_zoom
+            .filter(|zoom| zoom.zoom > 1.0)
+            .map(|zoom| CameraPlan::new(&camera_path(), width, height, &zoom))
+    };
+    // Styled background: the (zoomed) recording shrinks onto it
+    let frame_style = config
+        .frame_style
+        .map(|style| FrameStyleOverlay::new(&style, width, height));
+    let zoomed_clicks = camera.as_ref().map(|camera| camera.map_clicks(clicks));
+    let clicks = zoomed_clicks.as_deref().unwrap_or(clicks);
+    let styled_clicks = frame_style.as_ref().map(|style| style.map_clicks(clicks));
+    let clicks = styled_clicks.as_deref().unwrap_or(clicks);
+    if let Some(camera) = &camera {
+        camera.map_lookup(&mut cursor_lookup);
+    }
+    if let Some(style) = &frame_style {
+        style.map_lookup(&mut cursor_lookup);
+    }
+    let headings = if config.orient_to_velocity {
+        build_heading_lookup(&cursor_lookup)
+    } else {
+        Vec::new()
+    };
+    // The default sprite, then one per cursor kind in `sprite_schedule` order
+    let shadow = config.cursor_shadow.as_ref();
+    let variants: Vec<CursorVariant> = std::iter::once(CursorVariant::new(
+        cursor_sprite,
+        &config.cursor_style(),
+        shadow,
+    ))
+    .chain(
+        kind_sprites
+            .iter()
+            .map(|(sprite, style)| CursorVariant::new(sprite, style, shadow)),
+    )
+    .collect();
+    let cursor = CursorOverlay {
+        variants: &variants,
+        schedule: &sprite_schedule,
+        shadow,
+        trail: config
+            .motion_trail
+            .as_ref()
+            .map(|trail| (trail, f64::from(encoder.time_base()) * 1000.0)),
+        lookup: &cursor_lookup,
+        headings: &headings,
+        tail: config.tail_behavior,
+        crop: crop_path.as_deref().map(|path| CropBounds {
+            path,
+            width,
+            height,
+        }),
+        clipped: Cell::new(false),
+    };
+    // Overlays draw on the (cropped) source frame; the scaler to the encoder resizes
+    // the finished frame, so everything on it keeps its place
+    let source_size = (width, height);
+    let scale_flags = scaler_flags(config.scale_filter.resolve(source_size, encoder.size));
+    let to_encoder = |format: Pixel| -> Result<Option<ScalerContext>, EngineError> {
+        if encoder.format == format && encoder.size == source_size {
+            return Ok(None);
+        }
+        let mut scaler = ScalerContext::get(
+            format,
+            source_size.0,
+            source_size.1,
+            encoder.format,
+            encoder.size.0,
+            encoder.size.1,
+            scale_flags,
+        )?;
+        let full_range_input = format == Pixel::RGBA || colors.full_range();
+        colors.apply_to(&mut scaler, full_range_input, colors.full_range());
+        Ok(Some(scaler))
+    };
+    // Scaler for Final Output (RGBA -> YUV420P, or NV12 for some hardware
+    // encoders, at the output size; none for GIF, whose palette stage takes
+    // RGBA and scales itself, or for YUV frames the encoder takes as they are)
+    let to_encoder = to_encoder(if yuv_compositing {
+        working_format
+    } else {
+        Pixel::RGBA
+    })?;
+    let compositing = if yuv_compositing {
+        Compositing::Yuv420 {
+            cursor: YuvCursor::new(cursor),
+            depth: if ten_bit {
+                BitDepth::Ten
+            } else {
+                BitDepth::Eight
+            },
+        }
+    } else {
+        // Bottom to top; only the watermark goes above the cursor
+        let mut layers: Vec<Box<dyn Overlay + '_>> = Vec::new();
+        if !config.redactions.is_empty() {
+            let redactions = config
+                .redactions
+                .iter()
+                .map(|region| {
+                    // Turned by two opposite corners
+                    let (x0, y0) = to_frame(region.x as f32, region.y as f32);
+                    let (x1, y1) = to_frame(
+                        (region.x + region.width as i32) as f32,
+                        (region.y + region.height as i32) as f32,
+                    );
+                    RedactionRegion {
+                        x: x0.min(x1).round() as i32,
+                        y: y0.min(y1).round() as i32,
+                        width: (x1 - x0).abs().round() as u32,
+                        height: (y1 - y0).abs().round() as u32,
+                        ..*region
+                    }
+                })
+                .collect();
+            layers.push(Box::new(RedactionOverlay::new(redactions)));
+        }
+        if let Some(camera) = &camera {
+            layers.push(Box::new(CameraOverlay::new(camera)));
+        }
+        if let Some(style) = frame_style {
+            layers.push(Box::new(style));
+        }
+        if let Some(spotlight) = config.spotlight {
+            let (lookup, tail) = (&cursor_lookup, config.tail_behavior);
+            layers.push(Box::new(SpotlightOverlay::new(spotlight, move |t| {
+                interpolate_cursor_position(lookup, t, tail)
+            })));
+        }
+        if let Some(effect) = config.click_effect {
+            layers.push(Box::new(ClickRipples {
+                clicks,
+                effect,
+                gamma_correct_blend: config.gamma_correct_blend,
+            }));
+        }
+        if let Some(keystrokes) = keystrokes {
+            layers.push(Box::new(keystrokes));
+        }
+        if let Some(captions) = captions {
+            layers.push(Box::new(captions));
+        }
+        layers.push(Box::new(cursor));
+        if let Some(watermark) = watermark {
+            layers.push(Box::new(watermark));
+        }
+        Compositing::Rgba { layers }
+    };
 
-    progress_callback(0.10);
+    // Output frames to expect, for progress: the trimmed, retimed span of the
+    // source. Progress is indeterminate for inputs of unknown length.
+    let estimated_total_frames = source_length_ms.map(|source_ms| {
+        let end_ms = source_ms.min(config.trim_end_ms.unwrap_or(f64::INFINITY));
+        let output_span_ms = time_map.output_ms(end_ms) - time_map.output_ms(config.trim_start_ms);
+        (output_span_ms.max(0.0) / 1000.0 * config.frame_rate as f64).round() as u64
+    });
+    match estimated_total_frames {
+        Some(frames) => log::info!("Output: {} frames estimated", frames),
+        None => log::info!("Input length unknown, progress is indeterminate"),
+    }
+
+    // Write Header
+    set_container_metadata(&mut output_ctx, Some(&input_ctx), config);
+    let mut muxer_opts = muxer_options(config);
+    if config.codec == VideoCodec::Gif {
+        // Loop count: 0 = forever, -1 = play once
+        muxer_opts.set("loop", if config.gif.loop_forever { "0" } else { "-1" });
+    }
+    output_ctx.write_header_with(muxer_opts)?;
 
-    // 6. Pre-calculate Cursor Lookup Table
-    let cursor_lookup = build_cursor_lookup(cursor_points);
+    // Start decoding at the keyframe before the trim start; the frames up
+    // to the start are decoded and dropped by `trim`
+    if let Some(start) = trim.start {
+        let seek_ts = start.rescale(video_time_base, Rational::new(1, ffmpeg::ffi::AV_TIME_BASE));
+        if let Err(e) = input_ctx.seek(seek_ts, ..seek_ts) {
+            log::warn!("Seek failed ({}), decoding from the start", e);
+        }
+    }
 
-    // Calculate stats for progress
-    let start_ts = cursor_points.first().map(|p| p.timestamp_ms).unwrap_or(0.0);
-    let end_ts = cursor_points.last().map(|p| p.timestamp_ms).unwrap_or(0.0);
-    let estimated_total_frames = ((end_ts - start_ts) / 1000.0 * config.frame_rate as f64) as u64;
+    // 6. Processing: decode + filter graph -> overlays -> encoder, either
+    // frame by frame on this thread or as a pipeline (see `run_pipelined`)
+    let encoder_backend = encoder.backend;
+    let clock = OutputClock {
+        time_map: &time_map,
+        time_base: filter_sink_ctx.sink().time_base(),
+        stream_start_s: video_start as f64 * input_time_base,
+        first_pts: None,
+    };
+    let alignment = StartAlignment::new(
+        // Trimmed renders never decode the first frames, so they keep the stream start
+        (trim.start.is_none() && frame_rate.numerator() > 0 && frame_rate.denominator() > 0)
+            .then(|| 1000.0 / f64::from(frame_rate)),
+        video_start,
+        input_time_base,
+    );
+    let decode = DecodeStage {
+        input_ctx,
+        decoder,
+        video_stream_idx,
+        audio_stream_idx: audio.as_ref().map(AudioOutput::input_index),
+        skip_decode_errors: config.skip_decode_errors,
+        feed: FilterFeed {
+            _graph: filter_graph,
+            src: filter_src_ctx,
+            sink: filter_sink_ctx,
+            trim,
+            alignment,
+            clock,
+            retime,
+            input_time_base,
+            encoder_time_base: encoder.time_base(),
+            frame_count: 0,
+            trim_start_ms: config.trim_start_ms,
+            duration_ms,
+        },
+    };
+    let composite = CompositeStage {
+        compositing,
+        cursor_lookup: &cursor_lookup,
+        tail: config.tail_behavior,
+    };
+    let encode = EncodeStage {
+        to_encoder,
+        encoder,
+        output_ctx,
+        audio,
+    };
+    let mut monitor = RenderMonitor::new(
+        observer,
+        estimated_total_frames,
+        config.heartbeat_interval_frames,
+        config.frame_rate,
+    );
+    let (clock, frame_count) = if config.pipelined {
+        run_pipelined(decode, composite, encode, &mut monitor)?
+    } else {
+        run_serial(decode, composite, encode, &mut monitor)?
+    };
 
+    monitor.progress(1.0);
     log::info!(
-        "Cursor duration: {:.2}s ({} frames estimated)",
-        (end_ts - start_ts) / 1000.0,
-        estimated_total_frames
+        "Video processing complete. Total frames generated: {}",
+        frame_count
     );
 
-    // Write Header
-    output_ctx.write_header()?;
+    let mut stats = monitor.finish();
+    stats.frames_encoded = frame_count as u64;
+    // The sink counts in output frames (time base 1/fps)
+    let first_pts = clock.first_pts.unwrap_or(0);
+    let source_ms = clock.source_ms(first_pts + frame_count) - clock.source_ms(first_pts);
+    stats.source_duration = Duration::from_secs_f64(source_ms.max(0.0) / 1000.0);
+    stats.encoder_backend = encoder_backend;
+    Ok(stats)
+}
+
+/// Every frame composited and encoded as soon as the filter graph hands it
+/// over, all on the calling thread
+fn run_serial<'a>(
+    decode: DecodeStage<'a>,
+    mut composite: CompositeStage<'_>,
+    mut encode: EncodeStage,
+    monitor: &mut RenderMonitor<'_, impl RenderObserver>,
+) -> Result<(OutputClock<'a>, i64), EngineError> {
+    let decoded = decode.run(|item| match item {
+        Decoded::Source {
+            time_s,
+            next_output_frame,
+        } => {
+            monitor.source_frame(time_s, next_output_frame);
+            Ok(())
+        }
+        Decoded::Skipped {
+            packet,
+            next_output_frame,
+            error,
+        } => {
+            monitor.skipped_packet(packet, next_output_frame, error);
+            Ok(())
+        }
+        Decoded::Audio(mut packet) => encode.audio(&mut packet),
+        Decoded::Frame(mut frame) => {
+            let composite_time = composite.frame(&mut frame, monitor)?;
+            let encode_start = Instant::now();
+            encode.frame(&mut frame.frame, frame.output_pts)?;
+            monitor.frame_done(frame.index, composite_time, encode_start.elapsed());
+            Ok(())
+        }
+    })?;
+    encode.finish()?;
+    Ok(decoded)
+}
 
-    // 7. Processing Loop
-    let mut frame_count = 0i64;
+/// Decoding and encoding on threads of their own, with compositing (and the
+/// observer) on the calling thread in between, linked by queues of
+/// `PIPELINE_QUEUE_FRAMES` that stall the stage ahead when full. Each queue
+/// keeps its order, so the output is the same as `run_serial`'s. A stage
+/// that fails drops its queues, which stops the others; its error is the
+/// one returned.
+fn run_pipelined<'a>(
+    decode: DecodeStage<'a>,
+    mut composite: CompositeStage<'_>,
+    encode: EncodeStage,
+    monitor: &mut RenderMonitor<'_, impl RenderObserver>,
+) -> Result<(OutputClock<'a>, i64), EngineError> {
+    let (decoded_tx, decoded_rx) = mpsc::sync_channel(PIPELINE_QUEUE_FRAMES);
+    let (composited_tx, composited_rx) = mpsc::sync_channel(PIPELINE_QUEUE_FRAMES);
+    // Encode time of each frame, back to the monitor for heartbeats
+    let (encoded_tx, encoded_rx) = mpsc::channel();
+    let (decode, encode) = (Handoff(decode), Handoff(encode));
 
-    for (stream, packet) in input_ctx.packets() {
-        if stream.index() == video_stream_idx {
-            decoder.send_packet(&packet)?;
-
-            let mut raw_frame = VideoFrame::empty();
-            while decoder.receive_frame(&mut raw_frame).is_ok() {
-                // Push raw VFR frame into the filter graph source
-                filter_src_ctx.source().add(&raw_frame)?;
-
-                // Pull guaranteed CFR frames (60fps RGBA) from sink
-                while let Ok(mut cfr_frame) = read_frame_from_sink(&mut filter_sink_ctx) {
-                    if frame_count % 60 == 0 {
-                        // Log once per second of video
-                        let sink_view = filter_sink_ctx.sink();
-                        let time_base = sink_view.time_base();
-                        let pts = cfr_frame.pts().unwrap_or(0);
-                        let video_t = pts as f64 * f64::from(time_base);
-
-                        // Assuming 'cursor_lookup' is your Spline/Smoothing struct
-                        // You might need to expose start_time/end_time methods on it
-                        log::info!(
-                            "Frame #{}: PTS={} (Time={:.4}s) | Querying Spline...",
-                            frame_count,
-                            pts,
-                            video_t
-                        );
+    std::thread::scope(|scope| {
+        let decoding = scope.spawn(move || {
+            let stage = decode.into_inner();
+            stage.run(|item| decoded_tx.send(item).map_err(|_| stage_stopped()))
+        });
+        let encoding = scope.spawn(move || -> Result<(), EngineError> {
+            let mut stage = encode.into_inner();
+            for item in composited_rx {
+                match item {
+                    Composited::Frame(mut frame, output_pts) => {
+                        let encode_start = Instant::now();
+                        stage.frame(&mut frame, output_pts)?;
+                        // Gone only once compositing has stopped
+                        let _ = encoded_tx.send(encode_start.elapsed());
                     }
-                    process_single_frame(
-                        &mut cfr_frame,
-                        &mut encoder,
-                        &mut reverse_scaler,
-                        &mut output_ctx,
-                        cursor_sprite,
-                        &cursor_lookup,
-                        frame_count,
-                        &mut progress_callback,
-                        estimated_total_frames,
-                    )?;
-                    frame_count += 1;
+                    Composited::Audio(mut packet) => stage.audio(&mut packet)?,
+                    Composited::Finish => return stage.finish(),
                 }
             }
-        }
-    }
+            // Compositing stopped early, with its own error
+            Ok(())
+        });
 
-    // 8. Flush Decoder
-    log::info!("Flushing decoder...");
-    decoder.send_eof()?;
-    let mut raw_frame = VideoFrame::empty();
-    while decoder.receive_frame(&mut raw_frame).is_ok() {
-        filter_src_ctx.source().add(&raw_frame)?;
-        while let Ok(mut cfr_frame) = read_frame_from_sink(&mut filter_sink_ctx) {
-            process_single_frame(
-                &mut cfr_frame,
-                &mut encoder,
-                &mut reverse_scaler,
-                &mut output_ctx,
-                cursor_sprite,
-                &cursor_lookup,
-                frame_count,
-                &mut progress_callback,
-                estimated_total_frames,
-            )?;
-            frame_count += 1;
-        }
-    }
-
-    // 9. Flush Filter Graph
-    log::info!("Flushing filter graph...");
-    filter_src_ctx.source().flush()?; // Signal EOF to filter
-    while let Ok(mut cfr_frame) = read_frame_from_sink(&mut filter_sink_ctx) {
-        process_single_frame(
-            &mut cfr_frame,
-            &mut encoder,
-            &mut reverse_scaler,
-            &mut output_ctx,
-            cursor_sprite,
-            &cursor_lookup,
-            frame_count,
-            &mut progress_callback,
-            estimated_total_frames,
-        )?;
-        frame_count += 1;
+        // Ok(false): the encoder thread stopped, its queue is closed
+        let composited = (|| -> Result<bool, EngineError> {
+            for item in decoded_rx {
+                let next = match item {
+                    Decoded::Source {
+                        time_s,
+                        next_output_frame,
+                    } => {
+                        monitor.source_frame(time_s, next_output_frame);
+                        continue;
+                    }
+                    Decoded::Skipped {
+                        packet,
+                        next_output_frame,
+                        error,
+                    } => {
+                        monitor.skipped_packet(packet, next_output_frame, error);
+                        continue;
+                    }
+                    Decoded::Audio(packet) => Composited::Audio(packet),
+                    Decoded::Frame(mut frame) => {
+                        let composite_time = composite.frame(&mut frame, monitor)?;
+                        let encode_time = encoded_rx.try_iter().sum();
+                        monitor.frame_done(frame.index, composite_time, encode_time);
+                        Composited::Frame(frame.frame, frame.output_pts)
+                    }
+                };
+                if composited_tx.send(next).is_err() {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })();
+
+        // The decode thread is done, or stops at its next send now that its
+        // queue is closed
+        let decoded = join_stage(decoding);
+        let decoded = match (composited, decoded) {
+            (Ok(true), Ok(decoded)) => {
+                // Fails only if the encoder thread has failed, reported below
+                let _ = composited_tx.send(Composited::Finish);
+                Some(decoded)
+            }
+            (Err(e), _) | (Ok(true), Err(e)) => return Err(e),
+            (Ok(false), _) => None,
+        };
+        drop(composited_tx);
+        join_stage(encoding)?;
+        decoded.ok_or_else(stage_stopped)
+    })
+}
+
+/// A pipeline thread's result, with its panic, if any, passed on
+fn join_stage<T>(stage: std::thread::ScopedJoinHandle<'_, T>) -> T {
+    stage
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// A pipeline stage stopped because its neighbour did; the neighbour's error
+/// is the one reported
+fn stage_stopped() -> EngineError {
+    FfmpegError::Exit.into()
+}
+
+/// Moves a pipeline stage, and the ffmpeg handles in it that the bindings
+/// don't mark `Send` (filter contexts, scalers), to the stage's thread
+struct Handoff<T>(T);
+
+// SAFETY: FFmpeg contexts may be used from any thread, one at a time. A
+// stage moves to its thread whole and only that thread touches it from then on.
+unsafe impl<T> Send for Handoff<T> {}
+
+impl<T> Handoff<T> {
+    // A method, so closures capture the whole `Handoff` rather than its field
+    fn into_inner(self) -> T {
+        self.0
     }
+}
 
-    // 10. Flush Encoder
-    log::info!("Flushing encoder...");
-    encoder.send_eof()?;
-    encode_and_write(&mut encoder, &mut output_ctx)?;
+/// What the decode stage hands on, in stream order
+enum Decoded {
+    /// A source frame went into the filter graph (see
+    /// `RenderMonitor::source_frame`)
+    Source {
+        time_s: Option<f64>,
+        next_output_frame: i64,
+    },
+    /// A video frame could not be decoded and was left out (see
+    /// `ProcessingOptions::skip_decode_errors`); `packet` is the video
+    /// packet (counting from 1) last sent when the error came back
+    Skipped {
+        packet: u64,
+        next_output_frame: i64,
+        error: FfmpegError,
+    },
+    /// An audio packet, muxed where it falls among the video frames
+    Audio(Packet),
+    Frame(CfrFrame),
+}
 
-    // Write Trailer
-    output_ctx.write_trailer()?;
+/// What compositing hands the encoder thread, in order
+enum Composited {
+    /// A finished frame and its output PTS
+    Frame(VideoFrame, i64),
+    Audio(Packet),
+    /// Everything is in: flush the encoder and write the trailer
+    Finish,
+}
 
-    progress_callback(1.0);
-    log::info!(
-        "Video processing complete. Total frames generated: {}",
-        frame_count
-    );
+/// A frame from the filter graph and where it falls on both clocks
+struct CfrFrame {
+    frame: VideoFrame,
+    /// Output frame number
+    index: i64,
+    /// Source time (ms) it shows, on the cursor clock
+    timestamp_ms: f64,
+    /// PTS in the encoder's time base
+    output_pts: i64,
+}
 
-    Ok(())
+/// Demuxing and decoding the input, up to the frames the filter graph puts out
+struct DecodeStage<'a> {
+    input_ctx: ffmpeg::format::context::Input,
+    decoder: ffmpeg::decoder::Video,
+    video_stream_idx: usize,
+    /// Input stream whose packets go to the audio output
+    audio_stream_idx: Option<usize>,
+    skip_decode_errors: bool,
+    feed: FilterFeed<'a>,
 }
 
-// ============================================================================
-// Internal Helpers
-// ============================================================================
+/// What a decode error does to the render: fail it, or with `skip` pass it
+/// on as `Decoded::Skipped`
+#[derive(Clone, Copy)]
+struct DecodeErrors {
+    skip: bool,
+    /// Video packets sent to the decoder so far
+    packet: u64,
+}
 
-/// Reads a frame from the filter sink, handling the Result wrapper
-fn read_frame_from_sink(
-    filter_sink: &mut ffmpeg::filter::Context,
-) -> Result<VideoFrame, FfmpegError> {
-    let mut frame = VideoFrame::empty();
-    // sink().frame() pulls a filtered frame from the graph
-    filter_sink.sink().frame(&mut frame)?;
-    Ok(frame)
+impl DecodeErrors {
+    fn handle(
+        self,
+        error: FfmpegError,
+        next_output_frame: i64,
+        out: &mut impl FnMut(Decoded) -> Result<(), EngineError>,
+    ) -> Result<(), EngineError> {
+        if !self.skip {
+            return Err(EngineError::DecodeFailed {
+                packet: self.packet,
+                error,
+            });
+        }
+        out(Decoded::Skipped {
+            packet: self.packet,
+            next_output_frame,
+            error,
+        })
+    }
 }
 
-fn process_single_frame(
-    cfr_frame: &mut VideoFrame,
-    encoder: &mut encoder::Video,
-    reverse_scaler: &mut ScalerContext,
-    output_ctx: &mut ffmpeg::format::context::Output,
-    cursor_sprite: &CursorSprite,
-    cursor_lookup: &[(f64, f32, f32)],
-    frame_count: i64,
-    progress_callback: &mut impl FnMut(f32),
-    total_estimated: u64,
-) -> Result<(), Box<dyn Error>> {
-    // A. Calculate Timing based on Frame Count
-    // Since we forced CFR, Time = Frame / FPS
-    let time_base_seconds =
-        encoder.time_base().numerator() as f64 / encoder.time_base().denominator() as f64;
-    let timestamp_ms = frame_count as f64 * time_base_seconds * 1000.0;
-
-    // B. Cursor Overlay
-    let (cx, cy) = interpolate_cursor_position(cursor_lookup, timestamp_ms);
-    overlay_cursor_on_frame(cfr_frame, cursor_sprite, cx, cy)?;
-
-    // C. Convert to YUV (H.264 format)
-    let mut yuv_frame = VideoFrame::empty();
-    reverse_scaler.run(cfr_frame, &mut yuv_frame)?;
-
-    // D. Encode
-    yuv_frame.set_pts(Some(frame_count));
-    encoder.send_frame(&yuv_frame)?;
-    encode_and_write(encoder, output_ctx)?;
-
-    // E. Progress Reporting
-    if frame_count % 30 == 0 && total_estimated > 0 {
-        let p = (frame_count as f64 / total_estimated as f64) as f32;
-        progress_callback(0.10 + p * 0.85);
+impl<'a> DecodeStage<'a> {
+    /// Decode the input (up to the trim end) and hand everything to `out`;
+    /// returns the clock the frames were timed by and how many there were
+    fn run(
+        self,
+        mut out: impl FnMut(Decoded) -> Result<(), EngineError>,
+    ) -> Result<(OutputClock<'a>, i64), EngineError> {
+        let Self {
+            mut input_ctx,
+            mut decoder,
+            video_stream_idx,
+            audio_stream_idx,
+            skip_decode_errors,
+            mut feed,
+        } = self;
+        let mut errors = DecodeErrors {
+            skip: skip_decode_errors,
+            packet: 0,
+        };
+        let mut past_end = false;
+        for (stream, packet) in input_ctx.packets() {
+            if Some(stream.index()) == audio_stream_idx {
+                out(Decoded::Audio(packet))?;
+            } else if stream.index() == video_stream_idx {
+                errors.packet += 1;
+                if let Err(error) = decoder.send_packet(&packet) {
+                    errors.handle(error, feed.frame_count, &mut out)?;
+                }
+                if feed.receive(&mut decoder, errors, &mut out)? {
+                    past_end = true;
+                    break;
+                }
+            }
+        }
+
+        // 7. Flush Decoder (not needed once past the trim end)
+        log::debug!("Flushing decoder...");
+        if !past_end {
+            decoder.send_eof()?;
+            past_end = feed.receive(&mut decoder, errors, &mut out)?;
+        }
+        feed.finish(past_end, &mut out)?;
+        Ok((feed.clock, feed.frame_count))
     }
+}
 
-    Ok(())
+/// Decoded frames into the filter graph (trimmed, aligned, retimed) and the
+/// constant-rate frames out of it, timed
+struct FilterFeed<'a> {
+    // Owns the filter contexts below
+    _graph: ffmpeg::filter::Graph,
+    src: ffmpeg::filter::Context,
+    sink: ffmpeg::filter::Context,
+    trim: TrimWindow,
+    alignment: StartAlignment,
+    clock: OutputClock<'a>,
+    retime: Retime<'a>,
+    input_time_base: f64,
+    encoder_time_base: Rational,
+    /// Frames out of the graph so far
+    frame_count: i64,
+    /// For `EngineError::TrimPastEnd`
+    trim_start_ms: f64,
+    duration_ms: f64,
 }
 
-fn overlay_cursor_on_frame(
-    frame: &mut VideoFrame,
-    cursor_sprite: &CursorSprite,
-    x: f32,
-    y: f32,
-) -> Result<(), Box<dyn Error>> {
-    // Frame is guaranteed RGBA by filter graph
-    let width = frame.width();
-    let height = frame.height();
-
-    // IMPORTANT: Handle frame stride (pitch)
-    // composite_cursor_subpixel must iterate rows using stride, not just width*4
-    let _stride = frame.stride(0); // TODO: Pass stride to renderer for non-contiguous frames
-    let data = frame.data_mut(0);
-
-    // Call renderer (Update your renderer.rs to accept stride!)
-    // If renderer.rs is not updated, this assumes stride == width * 4 (Risky but common)
-    composite_cursor_subpixel(data, width, height, cursor_sprite, x, y);
+impl FilterFeed<'_> {
+    /// Filter every frame `decoder` has ready; true once past the trim end
+    fn receive(
+        &mut self,
+        decoder: &mut ffmpeg::decoder::Video,
+        errors: DecodeErrors,
+        out: &mut impl FnMut(Decoded) -> Result<(), EngineError>,
+    ) -> Result<bool, EngineError> {
+        let mut raw_frame = VideoFrame::empty();
+        loop {
+            match decoder.receive_frame(&mut raw_frame) {
+                Ok(()) => {}
+                // Waiting for the next packet, or flushed
+                Err(FfmpegError::Other { errno: EAGAIN } | FfmpegError::Eof) => return Ok(false),
+                Err(error) => {
+                    errors.handle(error, self.frame_count, out)?;
+                    continue;
+                }
+            }
+            let mut held = match self.trim.admit(&raw_frame) {
+                Admit::Skip => continue,
+                Admit::Done => return Ok(true),
+                Admit::Keep(held) => held,
+            };
+            for frame in held.iter_mut().chain([&mut raw_frame]) {
+                self.add(frame, out)?;
+            }
+            self.drain(out)?;
+        }
+    }
 
-    Ok(())
-}
+    /// Push a decoded VFR frame into the filter graph source, once the start
+    /// alignment lets it through
+    fn add(
+        &mut self,
+        frame: &mut VideoFrame,
+        out: &mut impl FnMut(Decoded) -> Result<(), EngineError>,
+    ) -> Result<(), EngineError> {
+        out(Decoded::Source {
+            time_s: frame
+                .timestamp()
+                .or(frame.pts())
+                .map(|ts| ts as f64 * self.input_time_base),
+            next_output_frame: self.frame_count,
+        })?;
+        if self.alignment.hold(frame) {
+            return Ok(());
+        }
+        self.release_held()?;
+        self.retime.frame(frame);
+        self.src.source().add(frame)?;
+        Ok(())
+    }
 
-fn create_video_encoder(
-    width: u32,
-    height: u32,
-    frame_rate: Rational,
-    output_ctx: &mut ffmpeg::format::context::Output,
-) -> Result<encoder::Video, Box<dyn Error>> {
-    let global_header = output_ctx
-        .format()
-        .flags()
-        .contains(ffmpeg::format::flag::Flags::GLOBAL_HEADER);
-    let codec = encoder::find(codec::Id::H264).ok_or("H264 encoder not found")?;
-    let mut output_stream = output_ctx.add_stream(Some(codec))?;
+    /// Push the frames the start alignment held back
+    fn release_held(&mut self) -> Result<(), EngineError> {
+        for mut early in self.alignment.release(&mut self.clock) {
+            self.retime.frame(&mut early);
+            self.src.source().add(&early)?;
+        }
+        Ok(())
+    }
 
-    let mut encoder = codec::context::Context::new_with_codec(codec)
-        .encoder()
-        .video()?;
+    /// Pull guaranteed CFR frames (RGBA or YUV420P) from the sink
+    fn drain(
+        &mut self,
+        out: &mut impl FnMut(Decoded) -> Result<(), EngineError>,
+    ) -> Result<(), EngineError> {
+        while let Ok(frame) = read_frame_from_sink(&mut self.sink) {
+            // Timing from the frame's PTS, mapped back to the source (the fps
+            // filter's first frame need not be at 0)
+            let pts = frame
+                .pts()
+                .unwrap_or_else(|| self.clock.first_pts.unwrap_or(0) + self.frame_count);
+            if self.frame_count % 60 == 0 {
+                // Log once per second of video
+                log::debug!(
+                    "Frame #{}: PTS={} (Time={:.4}s) | Querying Spline...",
+                    self.frame_count,
+                    pts,
+                    pts as f64 * f64::from(self.clock.time_base)
+                );
+            }
+            let timestamp_ms = self.clock.source_ms(pts);
+            let output_pts = self.clock.output_pts(pts, self.encoder_time_base);
+            out(Decoded::Frame(CfrFrame {
+                frame,
+                index: self.frame_count,
+                timestamp_ms,
+                output_pts,
+            }))?;
+            self.frame_count += 1;
+        }
+        Ok(())
+    }
 
-    encoder.set_width(width);
-    encoder.set_height(height);
-    encoder.set_format(Pixel::YUV420P);
-    encoder.set_frame_rate(Some(frame_rate));
-    // Set timebase to 1/FPS (standard for CFR)
-    encoder.set_time_base(Rational::new(
-        frame_rate.denominator(),
-        frame_rate.numerator(),
-    ));
+    /// Once decoding is over: the frame a range shorter than a source frame
+    /// still needs, anything held for the alignment, and the graph flushed
+    fn finish(
+        &mut self,
+        past_end: bool,
+        out: &mut impl FnMut(Decoded) -> Result<(), EngineError>,
+    ) -> Result<(), EngineError> {
+        // A range shorter than a source frame ends before any frame inside it
+        if past_end {
+            if let Some(mut held) = self.trim.leftover() {
+                self.add(&mut held, out)?;
+            }
+        }
+        if !self.trim.kept {
+            return Err(EngineError::TrimPastEnd {
+                start_ms: self.trim_start_ms,
+                duration_ms: self.duration_ms,
+            });
+        }
 
-    if global_header {
-        encoder.set_flags(codec::flag::Flags::GLOBAL_HEADER);
+        // 8. Flush Filter Graph, with any frames still held for the alignment
+        // (inputs shorter than its window)
+        log::debug!("Flushing filter graph...");
+        self.release_held()?;
+        match self.trim.end.filter(|_| past_end) {
+            // Stop the fps filter at the trim end rather than after the last frame
+            Some(end) => self.src.source().close(self.retime.ticks(end))?,
+            None => self.src.source().flush()?, // Signal EOF to filter
+        }
+        self.drain(out)
     }
+}
 
-    // Encoder Options (x264)
-    let mut opts = ffmpeg::Dictionary::new();
-    opts.set("preset", "fast");
-    opts.set("crf", "18");
+/// Drawing the overlays onto the filter graph's frames
+struct CompositeStage<'a> {
+    compositing: Compositing<'a>,
+    cursor_lookup: &'a [(f64, f32, f32)],
+    tail: TailBehavior,
+}
 
-    let opened = encoder.open_with(opts)?;
-    output_stream.set_parameters(&opened);
+impl CompositeStage<'_> {
+    /// Draw on `cfr` and return how long it took
+    fn frame(
+        &mut self,
+        cfr: &mut CfrFrame,
+        monitor: &mut RenderMonitor<'_, impl RenderObserver>,
+    ) -> Result<Duration, EngineError> {
+        let composite_start = Instant::now();
+        let (cfr_frame, timestamp_ms, frame_count) = (&mut cfr.frame, cfr.timestamp_ms, cfr.index);
+        if is_outside_cursor_path(self.cursor_lookup, timestamp_ms) {
+            monitor.warn(WarningKind::TimestampClamped, frame_count, || {
+                format!(
+                    "frame time {:.1} ms is outside the cursor path, applying {:?}",
+                    timestamp_ms, self.tail
+                )
+            });
+        }
+        let (cx, cy) = interpolate_cursor_position(self.cursor_lookup, timestamp_ms, self.tail);
+        if cx < 0.0 || cy < 0.0 || cx >= cfr_frame.width() as f32 || cy >= cfr_frame.height() as f32
+        {
+            monitor.warn(WarningKind::CursorOutsideFrame, frame_count, || {
+                format!(
+                    "cursor at ({:.1}, {:.1}) is outside the {}x{} frame",
+                    cx,
+                    cy,
+                    cfr_frame.width(),
+                    cfr_frame.height()
+                )
+            });
+        }
+        let clipped = match &mut self.compositing {
+            Compositing::Rgba { layers } => {
+                // Frame is guaranteed RGBA by filter graph; FFmpeg may pad each row past width * 4
+                let (width, height, stride) =
+                    (cfr_frame.width(), cfr_frame.height(), cfr_frame.stride(0));
+                let mut frame = FrameView::new(cfr_frame.data_mut(0), width, height, stride);
+                let mut clipped = false;
+                for layer in layers.iter() {
+                    layer.render(&mut frame, timestamp_ms);
+                    clipped |= layer.clipped();
+                }
+                clipped
+            }
+            Compositing::Yuv420 { cursor, depth } => match depth {
+                BitDepth::Eight => {
+                    cursor.render(&mut yuv420_planes::<u8>(cfr_frame, 8)?, timestamp_ms)
+                }
+                BitDepth::Ten => {
+                    cursor.render(&mut yuv420_planes::<u16>(cfr_frame, 10)?, timestamp_ms)
+                }
+            },
+        };
+        if clipped {
+            monitor.warn(WarningKind::SpriteClipped, frame_count, || {
+                "cursor sprite extends past the frame edge".to_string()
+            });
+        }
+        Ok(composite_start.elapsed())
+    }
+}
 
-    Ok(opened)
+/// Converting finished frames for the encoder, encoding and muxing them
+/// along with the audio
+struct EncodeStage {
+    /// Scaler to the encoder's format and size, unless frames are composited
+    /// in them already
+    to_encoder: Option<ScalerContext>,
+    encoder: VideoEncoder,
+    output_ctx: ffmpeg::format::context::Output,
+    audio: Option<AudioOutput>,
 }
 
-fn encode_and_write(
-    encoder: &mut encoder::Video,
-    output_ctx: &mut ffmpeg::format::context::Output,
-) -> Result<(), FfmpegError> {
-    let mut packet = Packet::empty();
-    while encoder.receive_packet(&mut packet).is_ok() {
-        packet.set_stream(0);
+impl EncodeStage {
+    fn frame(&mut self, frame: &mut VideoFrame, output_pts: i64) -> Result<(), EngineError> {
+        let mut converted = VideoFrame::empty();
+        let yuv_frame = match &mut self.to_encoder {
+            Some(to_encoder) => {
+                to_encoder.run(frame, &mut converted)?;
+                &mut converted
+            }
+            None => frame,
+        };
+        yuv_frame.set_pts(Some(output_pts));
+        self.encoder.send_frame(yuv_frame)?;
+        encode_and_write(&mut self.encoder.encoder, &mut self.output_ctx)?;
+        Ok(())
+    }
 
-        // Rescale timestamps from encoder time_base to output stream time_base
-        let encoder_tb = encoder.time_base();
-        let stream_tb = output_ctx.stream(0).map(|s| s.time_base()).unwrap_or(encoder_tb);
-        packet.rescale_ts(encoder_tb, stream_tb);
+    fn audio(&mut self, packet: &mut Packet) -> Result<(), EngineError> {
+        match &mut self.audio {
+            Some(audio) => audio.write(packet, &mut self.output_ctx),
+            None => Ok(()),
+        }
+    }
 
-        packet.write_interleaved(output_ctx)?;
+    fn finish(mut self) -> Result<(), EngineError> {
+        // 9. Flush Encoder
+        log::debug!("Flushing encoder...");
+        self.encoder.finish(&mut self.output_ctx)?;
+        if let Some(audio) = self.audio.as_mut() {
+            audio.finish(&mut self.output_ctx)?;
+        }
+
+        // Write Trailer
+        self.output_ctx.write_trailer()?;
+        Ok(())
     }
-    Ok(())
 }
 
-fn build_cursor_lookup(cursor_points: &[CPoint]) -> Vec<(f64, f32, f32)> {
-    if cursor_points.is_empty() {
-        return Vec::new();
-    }
-    let start_time = cursor_points[0].timestamp_ms;
-    cursor_points
-        .iter()
-        .map(|p| (p.timestamp_ms - start_time, p.x, p.y))
-        .collect()
+/// Moves stream ticks from the source clock onto `time_map`'s output clock
+#[derive(Clone, Copy)]
+struct Retime<'a> {
+    time_map: &'a TimeMap,
+    stream_start: i64,
+    time_base: f64,
 }
 
-fn interpolate_cursor_position(lookup: &[(f64, f32, f32)], timestamp_ms: f64) -> (f32, f32) {
-    if lookup.is_empty() {
-        return (0.0, 0.0);
+impl Retime<'_> {
+    fn ticks(&self, ticks: i64) -> i64 {
+        let source_ms = (ticks - self.stream_start) as f64 * self.time_base * 1000.0;
+        let output_s = self.time_map.output_ms(source_ms) / 1000.0;
+        self.stream_start + (output_s / self.time_base).round() as i64
     }
 
-    let idx = match lookup.binary_search_by(|p| p.0.partial_cmp(&timestamp_ms).unwrap()) {
-        Ok(i) => i,
-        Err(i) => i,
-    };
+    /// Stamp a decoded frame with its time on the output clock
+    fn frame(&self, frame: &mut VideoFrame) {
+        if !self.time_map.is_identity() {
+            let ts = frame.timestamp().or(frame.pts());
+            frame.set_pts(ts.map(|ts| self.ticks(ts)));
+        }
+    }
+}
 
-    if idx == 0 {
-        return (lookup[0].1, lookup[0].2);
+/// Times of the frames the fps filter emits, from their PTS: the source time
+/// (ms, cursor clock) each one shows, and its output PTS, with the first
+/// frame at output time 0. Sped-up stretches go by faster.
+struct OutputClock<'a> {
+    time_map: &'a TimeMap,
+    /// Time base of the filter sink's PTS
+    time_base: Rational,
+    /// The cursor clock's 0 (s on the stream clock): the stream start, moved
+    /// onto the source's frame grid by `StartAlignment`
+    stream_start_s: f64,
+    /// PTS of the first frame encoded
+    first_pts: Option<i64>,
+}
+
+impl OutputClock<'_> {
+    /// Source time (ms) of the frame with sink `pts`; the PTS are on
+    /// `time_map`'s output clock, as the frames were retimed before the fps
+    /// filter
+    fn source_ms(&self, pts: i64) -> f64 {
+        let output_ms = (pts as f64 * f64::from(self.time_base) - self.stream_start_s) * 1000.0;
+        self.time_map.source_ms(output_ms)
     }
-    if idx >= lookup.len() {
-        let last = lookup.last().unwrap();
-        return (last.1, last.2);
+
+    /// Output PTS (in `encoder_time_base`) of the frame with sink `pts`
+    fn output_pts(&mut self, pts: i64, encoder_time_base: Rational) -> i64 {
+        let first = *self.first_pts.get_or_insert(pts);
+        (pts - first).rescale(self.time_base, encoder_time_base)
     }
+}
 
-    let (t0, x0, y0) = lookup[idx - 1];
-    let (t1, x1, y1) = lookup[idx];
+/// Holds the first decoded frames back until their timestamps show where the
+/// source's frame grid starts, which becomes the cursor clock's 0. The first
+/// frame alone is often late (capture warm-up, B-frame reordering) and would
+/// offset the cursor for the whole video; the median of every held frame's
+/// offset from the grid is not.
+struct StartAlignment {
+    /// Nominal frame duration (ms); None = nothing to align (trimmed renders)
+    frame_ms: Option<f64>,
+    stream_start: i64,
+    time_base: f64,
+    /// Frames held so far, with their times (ms from the stream start)
+    queue: Vec<(VideoFrame, Option<f64>)>,
+    locked: bool,
+}
 
-    let dt = t1 - t0;
-    if dt < 1e-6 {
-        return (x1, y1);
+impl StartAlignment {
+    fn new(frame_ms: Option<f64>, stream_start: i64, time_base: f64) -> Self {
+        Self {
+            frame_ms,
+            stream_start,
+            time_base,
+            queue: Vec::new(),
+            locked: false,
+        }
     }
 
-    let t = ((timestamp_ms - t0) / dt) as f32;
-    (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t)
+    /// Hold `frame` while still sampling; false once the frames can go on
+    /// (`release` them first)
+    fn hold(&mut self, frame: &VideoFrame) -> bool {
+        if self.locked || self.frame_ms.is_none() || self.queue.len() >= ALIGNMENT_SAMPLE_COUNT {
+            return false;
+        }
+        let ms = frame
+            .timestamp()
+            .or(frame.pts())
+            .map(|ts| (ts - self.stream_start) as f64 * self.time_base * 1000.0);
+        let first_ms = self.queue.iter().find_map(|(_, ms)| *ms);
+        if let (Some(ms), Some(first_ms)) = (ms, first_ms) {
+            if ms - first_ms >= ALIGNMENT_MAX_WINDOW_MS {
+                return false;
+            }
+        }
+        self.queue.push((frame.clone(), ms));
+        true
+    }
+
+    /// Lock the alignment into `clock` (first call only) and hand back the
+    /// held frames
+    fn release(&mut self, clock: &mut OutputClock<'_>) -> Vec<VideoFrame> {
+        if !self.locked {
+            self.locked = true;
+            if let Some(offset_ms) = self.grid_offset_ms() {
+                log::info!(
+                    "Cursor clock aligned to the frame grid, {:+.1} ms from the stream start",
+                    offset_ms
+                );
+                clock.stream_start_s += offset_ms / 1000.0;
+            }
+        }
+        self.queue.drain(..).map(|(frame, _)| frame).collect()
+    }
+
+    /// Where frame 0 of the grid through the held frames falls (ms from the
+    /// stream start), unless their timestamps are too irregular (VFR) for
+    /// there to be one
+    fn grid_offset_ms(&self) -> Option<f64> {
+        let frame_ms = self.frame_ms?;
+        let deltas: Vec<f64> = self
+            .queue
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (_, ms))| ms.map(|ms| ms - i as f64 * frame_ms))
+            .collect();
+        if deltas.len() < 3 {
+            return None;
+        }
+        let offset = median(deltas.clone());
+        let spread = median(deltas.iter().map(|delta| (delta - offset).abs()).collect());
+        if spread > frame_ms / 4.0 {
+            log::debug!("Source frames are off a regular grid, keeping the stream start");
+            return None;
+        }
+        Some(offset)
+    }
+}
+
+/// Middle value of `values` (the upper one of an even count)
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    values[values.len() / 2]
+}
+
+/// Which decoded video frames fall inside `trim_start_ms`..`trim_end_ms`
+/// (in stream ticks)
+struct TrimWindow {
+    start: Option<i64>,
+    end: Option<i64>,
+    /// The latest frame before `start`: it is still on screen at the start,
+    /// so it goes to the fps filter just ahead of the first frame inside
+    held: Option<VideoFrame>,
+    /// Whether any frame was inside the range
+    kept: bool,
+}
+
+/// What to do with a decoded frame (see `TrimWindow::admit`)
+enum Admit {
+    /// Before the range
+    Skip,
+    /// Inside it; filter the held frame, if any, first
+    Keep(Option<VideoFrame>),
+    /// At or past its end, so decoding can stop
+    Done,
+}
+
+impl TrimWindow {
+    fn admit(&mut self, frame: &VideoFrame) -> Admit {
+        let ts = frame.timestamp().or(frame.pts());
+        if ts.is_some_and(|ts| self.end.is_some_and(|end| ts >= end)) {
+            return Admit::Done;
+        }
+        if ts.is_some_and(|ts| self.start.is_some_and(|start| ts < start)) {
+            self.held = Some(frame.clone());
+            return Admit::Skip;
+        }
+        self.kept = true;
+        Admit::Keep(self.held.take())
+    }
+
+    /// The held frame, when the range ended before any frame inside it
+    fn leftover(&mut self) -> Option<VideoFrame> {
+        let held = self.held.take().filter(|_| !self.kept);
+        self.kept |= held.is_some();
+        held
+    }
+}
+
+/// Progress, heartbeat timing, warnings and counters for one render
+struct RenderMonitor<'a, O: RenderObserver> {
+    observer: &'a mut O,
+    /// Output frames expected; None for an input of unknown length
+    total_estimated: Option<u64>,
+    /// Output frames done so far
+    frames_done: u64,
+    heartbeat_interval: u32,
+    interval_start: Instant,
+    interval_frames: u32,
+    composite: Duration,
+    encode: Duration,
+    output_fps: f64,
+    /// Output frame slot of the previous source frame (for drop/dup detection)
+    last_source_slot: Option<i64>,
+    stats: RenderStats,
+}
+
+impl<'a, O: RenderObserver> RenderMonitor<'a, O> {
+    fn new(
+        observer: &'a mut O,
+        total_estimated: Option<u64>,
+        heartbeat_interval: u32,
+        frame_rate: i32,
+    ) -> Self {
+        Self {
+            observer,
+            total_estimated,
+            frames_done: 0,
+            heartbeat_interval,
+            interval_start: Instant::now(),
+            interval_frames: 0,
+            composite: Duration::ZERO,
+            encode: Duration::ZERO,
+            output_fps: frame_rate as f64,
+            last_source_slot: None,
+            stats: RenderStats::default(),
+        }
+    }
+
+    fn progress(&mut self, fraction: f32) {
+        self.observer.progress(Progress {
+            fraction,
+            frames: self.frames_done,
+        });
+    }
+
+    /// Count a warning; only the first of each kind reaches the observer
+    /// (the rest are summarized by `finish`), so bad input cannot flood it
+    fn warn(&mut self, kind: WarningKind, frame: i64, message: impl FnOnce() -> String) {
+        self.warn_n(kind, frame, 1, message);
+    }
+
+    fn warn_n(&mut self, kind: WarningKind, frame: i64, n: u64, message: impl FnOnce() -> String) {
+        let count = self.stats.warnings.get_mut(kind);
+        let first = *count == 0;
+        *count += n;
+
+        if first {
+            let warning = RenderWarning {
+                kind,
+                frame: Some(frame.max(0) as u64),
+                message: message(),
+            };
+            log::warn!("Frame {}: {}", frame, warning.message);
+            self.observer.warning(&warning);
+        }
+    }
+
+    fn skipped_packet(&mut self, packet: u64, next_output_frame: i64, error: FfmpegError) {
+        self.warn(WarningKind::UndecodablePacket, next_output_frame, || {
+            format!(
+                "decoding failed at video packet {} ({}), skipped",
+                packet, error
+            )
+        });
+    }
+
+    /// Predict what the fps filter (round=near) will do with a decoded frame:
+    /// a source frame landing in the same output slot as its predecessor is
+    /// dropped, a jump of several slots duplicates the predecessor.
+    /// `time_s` is the frame's timestamp (s on the stream clock).
+    fn source_frame(&mut self, time_s: Option<f64>, next_output_frame: i64) {
+        let Some(time_s) = time_s else {
+            return;
+        };
+        let slot = (time_s * self.output_fps).round() as i64;
+
+        if let Some(previous) = self.last_source_slot.replace(slot) {
+            let gap = slot - previous;
+            if gap <= 0 {
+                self.warn(WarningKind::FrameDropped, next_output_frame, || {
+                    "source frame dropped by frame-rate conversion".to_string()
+                });
+            } else if gap > 1 {
+                self.warn_n(
+                    WarningKind::FrameDuplicated,
+                    next_output_frame,
+                    (gap - 1) as u64,
+                    || format!("{} output frames duplicated to fill a source gap", gap - 1),
+                );
+            }
+        }
+    }
+
+    /// Emit one summary per warning kind seen more than once and return the counters
+    fn finish(mut self) -> RenderStats {
+        let kinds = [
+            WarningKind::TimestampClamped,
+            WarningKind::CursorOutsideFrame,
+            WarningKind::FrameDropped,
+            WarningKind::FrameDuplicated,
+            WarningKind::SpriteClipped,
+            WarningKind::UndecodablePacket,
+        ];
+        for kind in kinds {
+            let count = self.stats.warnings.get(kind);
+            if count > 1 {
+                let warning = RenderWarning {
+                    kind,
+                    frame: None,
+                    message: format!("{:?}: {} occurrences in total", kind, count),
+                };
+                log::warn!("{}", warning.message);
+                self.observer.warning(&warning);
+            }
+        }
+        self.stats
+    }
+
+    /// Account for one encoded frame, emitting progress every 30 frames and a
+    /// heartbeat every `heartbeat_interval` frames.
+    ///
+    /// Decode time is derived as interval wall time minus composite and encode,
+    /// so it covers demuxing, decoding and the fps/format filters with no
+    /// extra clock reads in the decode loop. Pipelined, the stages overlap
+    /// and decode time is only what the other two leave of the wall time.
+    fn frame_done(&mut self, frame_index: i64, composite: Duration, encode: Duration) {
+        self.interval_frames += 1;
+        self.composite += composite;
+        self.encode += encode;
+        self.frames_done += 1;
+
+        if frame_index % 30 == 0 {
+            // The estimate can fall short, so a long render stalls at 95%
+            // rather than running past the end
+            let fraction = match self.total_estimated {
+                Some(total) => {
+                    let p = (self.frames_done as f64 / total.max(1) as f64).min(1.0) as f32;
+                    0.10 + p * 0.85
+                }
+                None => INDETERMINATE_PROGRESS,
+            };
+            self.progress(fraction);
+        }
+
+        if self.heartbeat_interval > 0 && self.interval_frames >= self.heartbeat_interval {
+            let elapsed = self.interval_start.elapsed();
+            let heartbeat = Heartbeat {
+                frame_index: frame_index as u64,
+                frames: self.interval_frames,
+                decode: elapsed.saturating_sub(self.composite + self.encode),
+                composite: self.composite,
+                encode: self.encode,
+            };
+            self.observer.heartbeat(&heartbeat);
+
+            // Restart after the callback so its own cost is not billed to decode
+            self.interval_start = Instant::now();
+            self.interval_frames = 0;
+            self.composite = Duration::ZERO;
+            self.encode = Duration::ZERO;
+        }
+    }
+}
+
+// ============================================================================
+// Overlay-Only Rendering
+// ============================================================================
+
+/// Draw the overlays alone on transparent frames and encode them with their
+/// alpha channel (see `ProcessingOptions::overlay_only`). The input is only
+/// opened for its frame size; nothing is decoded.
+pub fn render_overlay(
+    input_path: &Path,
+    output_path: &Path,
+    cursor_points: &[CPoint],
+    clicks: &[CClickEvent],
+    cursor_sprite: &CursorSprite,
+    config: &ProcessingOptions,
+    observer: &mut impl RenderObserver,
+) -> Result<RenderStats, EngineError> {
+    log::info!(
+        "Starting overlay-only render: {} -> {}",
+        input_path.display(),
+        output_path.display()
+    );
+
+    utils::init_ffmpeg()?;
+    observer.progress(Progress {
+        fraction: 0.0,
+        frames: 0,
+    });
+
+    let encoder_candidates = encoder_candidates(config)?;
+    let keystrokes = keystroke_overlay(config)?;
+    let captions = caption_overlay(config)?;
+    let watermark = config
+        .watermark
+        .as_ref()
+        .map(|watermark| WatermarkOverlay::new(watermark, config.gamma_correct_blend))
+        .transpose()?;
+    let (kind_sprites, sprite_schedule) = cursor_kind_sprites(config)?;
+    let VideoMetadata { width, height, .. } = read_metadata(input_path)?;
+
+    // From the trim start to the end of the cursor path
+    let path_end_ms = cursor_points.last().map_or(0.0, |point| point.timestamp_ms);
+    let end_ms = config
+        .trim_end_ms
+        .map_or(path_end_ms, |end| end.min(path_end_ms));
+    if end_ms < config.trim_start_ms {
+        return Err(EngineError::InvalidTrim(format!(
+            "trim starts at {:.1} s, past the end of the {:.1} s cursor path",
+            config.trim_start_ms / 1000.0,
+            path_end_ms / 1000.0
+        )));
+    }
+    let frame_interval_ms = 1000.0 / f64::from(config.frame_rate);
+    let total_frames = ((end_ms - config.trim_start_ms) / frame_interval_ms).floor() as i64 + 1;
+    log::info!(
+        "Overlay: {}x{}, {} frames from {:.0} ms",
+        width,
+        height,
+        total_frames,
+        config.trim_start_ms
+    );
+
+    let mut output_ctx = utils::open_output(output_path, config.container.muxer_name())?;
+    let colors = Colorimetry::assumed();
+    let encoder = create_video_encoder(
+        &encoder_candidates,
+        config,
+        width,
+        height,
+        Rational::new(config.frame_rate, 1),
+        colors,
+        None,
+        true,
+        &mut output_ctx,
+    )?;
+    let scale_flags = scaler_flags(config.scale_filter.resolve((width, height), encoder.size));
+    let mut to_encoder = ScalerContext::get(
+        Pixel::RGBA,
+        width,
+        height,
+        encoder.format,
+        encoder.size.0,
+        encoder.size.1,
+        scale_flags,
+    )?;
+    colors.apply_to(&mut to_encoder, true, colors.full_range());
+
+    let mut cursor_lookup = build_cursor_lookup(cursor_points);
+    confine_to_frame(&mut cursor_lookup, config.frame_bounds, width, height);
+    let headings = if config.orient_to_velocity {
+        build_heading_lookup(&cursor_lookup)
+    } else {
+        Vec::new()
+    };
+    let shadow = config.cursor_shadow.as_ref();
+    let variants: Vec<CursorVariant> = std::iter::once(CursorVariant::new(
+        cursor_sprite,
+        &config.cursor_style(),
+        shadow,
+    ))
+    .chain(
+        kind_sprites
+            .iter()
+            .map(|(sprite, style)| CursorVariant::new(sprite, style, shadow)),
+    )
+    .collect();
+    let cursor = CursorOverlay {
+        variants: &variants,
+        schedule: &sprite_schedule,
+        shadow,
+        trail: config
+            .motion_trail
+            .as_ref()
+            .map(|trail| (trail, frame_interval_ms)),
+        lookup: &cursor_lookup,
+        headings: &headings,
+        tail: config.tail_behavior,
+        crop: None,
+        clipped: Cell::new(false),
+    };
+    // The layers of a full render that draw without the source, in its order
+    let mut layers: Vec<Box<dyn Overlay + '_>> = Vec::new();
+    if let Some(effect) = config.click_effect {
+        layers.push(Box::new(ClickRipples {
+            clicks,
+            effect,
+            gamma_correct_blend: config.gamma_correct_blend,
+        }));
+    }
+    if let Some(keystrokes) = keystrokes {
+        layers.push(Box::new(keystrokes));
+    }
+    if let Some(captions) = captions {
+        layers.push(Box::new(captions));
+    }
+    layers.push(Box::new(cursor));
+    if let Some(watermark) = watermark {
+        layers.push(Box::new(watermark));
+    }
+
+    // The input is only opened again for its tags
+    let input_ctx = config
+        .copy_metadata
+        .then(|| utils::open_input(input_path, config.sequence_frame_rate))
+        .transpose()?;
+    set_container_metadata(&mut output_ctx, input_ctx.as_ref(), config);
+    output_ctx.write_header_with(muxer_options(config))?;
+
+    let encoder_backend = encoder.backend;
+    let mut composite = CompositeStage {
+        compositing: Compositing::Rgba { layers },
+        cursor_lookup: &cursor_lookup,
+        tail: config.tail_behavior,
+    };
+    let mut encode = EncodeStage {
+        to_encoder: Some(to_encoder),
+        encoder,
+        output_ctx,
+        audio: None,
+    };
+    let mut monitor = RenderMonitor::new(
+        observer,
+        Some(total_frames as u64),
+        config.heartbeat_interval_frames,
+        config.frame_rate,
+    );
+    // Cleared for every frame; the encoder only ever sees the converted copy
+    let mut cfr = CfrFrame {
+        frame: VideoFrame::new(Pixel::RGBA, width, height),
+        index: 0,
+        timestamp_ms: 0.0,
+        output_pts: 0,
+    };
+    for index in 0..total_frames {
+        cfr.frame.data_mut(0).fill(0);
+        cfr.index = index;
+        cfr.timestamp_ms = config.trim_start_ms + index as f64 * frame_interval_ms;
+        cfr.output_pts = index;
+        let composite_time = composite.frame(&mut cfr, &mut monitor)?;
+        let stride = cfr.frame.stride(0);
+        FrameView::new(cfr.frame.data_mut(0), width, height, stride).unpremultiply();
+
+        let encode_start = Instant::now();
+        encode.frame(&mut cfr.frame, cfr.output_pts)?;
+        monitor.frame_done(index, composite_time, encode_start.elapsed());
+    }
+    encode.finish()?;
+
+    monitor.progress(1.0);
+    log::info!("Overlay render complete, {} frames", total_frames);
+
+    let mut stats = monitor.finish();
+    stats.frames_encoded = total_frames as u64;
+    stats.source_duration =
+        Duration::from_secs_f64(total_frames as f64 * frame_interval_ms / 1000.0);
+    stats.encoder_backend = encoder_backend;
+    Ok(stats)
+}
+
+// ============================================================================
+// Single-Frame Preview
+// ============================================================================
+
+/// Decode the frame shown at `timestamp_ms`, overlay the cursor and save it as PNG.
+///
+/// Seeks to the nearest preceding keyframe, then decodes forward until the
+/// target so the result is frame-accurate. Timestamps past EOF clamp to the
+/// last decodable frame.
+#[allow(clippy::too_many_arguments)]
+pub fn render_preview_frame(
+    input_path: &Path,
+    output_png_path: &Path,
+    cursor_points: &[CPoint],
+    cursor_sprite: &CursorSprite,
+    cursor_style: &CursorStyle,
+    frame_bounds: FrameBounds,
+    tail: TailBehavior,
+    orient_to_velocity: bool,
+    cursor_shadow: Option<&CursorShadow>,
+    sequence_frame_rate: i32,
+    timestamp_ms: f64,
+) -> Result<(), EngineError> {
+    let StillFrame {
+        mut pixels,
+        width,
+        height,
+        time_ms: frame_ms,
+    } = decode_still(input_path, sequence_frame_rate, timestamp_ms, 0)?;
+    let row_bytes = width as usize * 4;
+
+    let mut cursor_lookup = build_cursor_lookup(cursor_points);
+    confine_to_frame(&mut cursor_lookup, frame_bounds, width, height);
+    let headings = if orient_to_velocity {
+        build_heading_lookup(&cursor_lookup)
+    } else {
+        Vec::new()
+    };
+    let variants = [CursorVariant::new(
+        cursor_sprite.frame_at(frame_ms),
+        cursor_style,
+        cursor_shadow,
+    )];
+    let cursor = CursorOverlay {
+        variants: &variants,
+        schedule: &SpriteSchedule::default(),
+        shadow: cursor_shadow,
+        trail: None,
+        lookup: &cursor_lookup,
+        headings: &headings,
+        tail,
+        crop: None,
+        clipped: Cell::new(false),
+    };
+    cursor.render(
+        &mut FrameView::new(&mut pixels, width, height, row_bytes),
+        frame_ms,
+    );
+
+    // `pixels` is exactly width * height * 4 bytes, so from_raw cannot fail
+    let image = image::RgbaImage::from_raw(width, height, pixels)
+        .expect("preview buffer matches frame dimensions");
+    image.save(output_png_path)?;
+
+    log::info!(
+        "Preview frame at {:.1}ms (requested {:.1}ms) written to {}",
+        frame_ms,
+        timestamp_ms,
+        output_png_path.display()
+    );
+
+    Ok(())
+}
+
+/// A decoded frame as tightly packed RGBA
+struct StillFrame {
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    /// Video time of the frame, which may be before the one asked for
+    time_ms: f64,
+}
+
+/// Decode the frame shown at `timestamp_ms` as RGBA, no wider than
+/// `max_width` (0 = the source size). The time is clamped to the stream's
+/// length when it is known.
+fn decode_still(
+    input_path: &Path,
+    sequence_frame_rate: i32,
+    timestamp_ms: f64,
+    max_width: u32,
+) -> Result<StillFrame, EngineError> {
+    utils::init_ffmpeg()?;
+
+    let mut input_ctx = utils::open_input(input_path, sequence_frame_rate)?;
+    let video_stream = best_video_stream(&input_ctx)?;
+    let video_stream_idx = video_stream.index();
+    let time_base = f64::from(video_stream.time_base());
+    let stream_start = match video_stream.start_time() {
+        ffmpeg::ffi::AV_NOPTS_VALUE => 0,
+        start => start,
+    };
+    let duration_ms = stream_duration_ms(&input_ctx, &video_stream);
+
+    let decoder_context = codec::context::Context::from_parameters(video_stream.parameters())?;
+    let mut decoder = decoder_context.decoder().video()?;
+
+    // Seek in AV_TIME_BASE units to the last keyframe at or before the target
+    let target_ms = match duration_ms {
+        d if d > 0 => timestamp_ms.clamp(0.0, d as f64),
+        _ => timestamp_ms.max(0.0),
+    };
+    let format_start = match unsafe { (*input_ctx.as_ptr()).start_time } {
+        ffmpeg::ffi::AV_NOPTS_VALUE => 0,
+        start => start,
+    };
+    let seek_ts = (target_ms * 1000.0) as i64 + format_start;
+    if let Err(e) = input_ctx.seek(seek_ts, ..seek_ts) {
+        log::warn!("Seek failed ({}), decoding from the start", e);
+    }
+    decoder.flush();
+
+    let frame_time_ms = |frame: &VideoFrame| -> Option<f64> {
+        frame
+            .timestamp()
+            .or(frame.pts())
+            .map(|ts| (ts - stream_start) as f64 * time_base * 1000.0)
+    };
+
+    // Keep the latest frame whose time does not exceed the target
+    let mut selected: Option<VideoFrame> = None;
+    let mut reached_target = false;
+    let mut raw_frame = VideoFrame::empty();
+
+    for (stream, packet) in input_ctx.packets() {
+        if stream.index() != video_stream_idx {
+            continue;
+        }
+        decoder.send_packet(&packet)?;
+        while decoder.receive_frame(&mut raw_frame).is_ok() {
+            let t = frame_time_ms(&raw_frame).unwrap_or(0.0);
+            if t > target_ms && selected.is_some() {
+                reached_target = true;
+                break;
+            }
+            selected = Some(raw_frame.clone());
+            if t >= target_ms {
+                reached_target = true;
+                break;
+            }
+        }
+        if reached_target {
+            break;
+        }
+    }
+
+    // Past EOF: drain the decoder and keep the very last frame
+    if !reached_target {
+        decoder.send_eof()?;
+        while decoder.receive_frame(&mut raw_frame).is_ok() {
+            let t = frame_time_ms(&raw_frame).unwrap_or(0.0);
+            if t > target_ms && selected.is_some() {
+                break;
+            }
+            selected = Some(raw_frame.clone());
+        }
+    }
+
+    let decoded = selected.ok_or(EngineError::Undecodable)?;
+    let frame_ms = frame_time_ms(&decoded).unwrap_or(target_ms);
+
+    // Convert to RGBA (same format the export pipeline composites on),
+    // shrinking to `max_width` with the aspect ratio kept
+    let (source_width, source_height) = (decoded.width(), decoded.height());
+    let width = match max_width {
+        0 => source_width,
+        max => source_width.min(max),
+    };
+    let height = ((f64::from(source_height) * f64::from(width) / f64::from(source_width)).round()
+        as u32)
+        .max(1);
+    let flags = if width < source_width {
+        Flags::AREA
+    } else {
+        Flags::BILINEAR
+    };
+    let mut scaler = ScalerContext::get(
+        decoded.format(),
+        source_width,
+        source_height,
+        Pixel::RGBA,
+        width,
+        height,
+        flags,
+    )?;
+    let colors = Colorimetry::of_source(&decoder);
+    colors.apply_to(&mut scaler, colors.full_range(), true);
+    let mut rgba_frame = VideoFrame::empty();
+    scaler.run(&decoded, &mut rgba_frame)?;
+
+    // Copy into a tightly packed buffer (the RGBA frame may carry row padding)
+    let stride = rgba_frame.stride(0);
+    let row_bytes = width as usize * 4;
+    let mut pixels = Vec::with_capacity(row_bytes * height as usize);
+    for row in rgba_frame.data(0).chunks(stride).take(height as usize) {
+        pixels.extend_from_slice(&row[..row_bytes]);
+    }
+
+    Ok(StillFrame {
+        pixels,
+        width,
+        height,
+        time_ms: frame_ms,
+    })
+}
+
+/// Save the frame shown at `timestamp_ms` as a PNG no wider than `max_width`
+/// (0 = the source width), for a thumbnail or poster frame. Times past the
+/// end give the last frame.
+pub fn generate_thumbnail(
+    input_path: &Path,
+    output_png_path: &Path,
+    timestamp_ms: f64,
+    max_width: u32,
+) -> Result<(), EngineError> {
+    let frame = decode_still(
+        input_path,
+        DEFAULT_SEQUENCE_FRAME_RATE,
+        timestamp_ms,
+        max_width,
+    )?;
+    // `pixels` is exactly width * height * 4 bytes, so from_raw cannot fail
+    let image = image::RgbaImage::from_raw(frame.width, frame.height, frame.pixels)
+        .expect("thumbnail buffer matches frame dimensions");
+    // A video frame is opaque, so the alpha channel would only add bytes
+    image::DynamicImage::ImageRgba8(image)
+        .to_rgb8()
+        .save(output_png_path)?;
+
+    log::info!(
+        "{}x{} thumbnail at {:.1}ms (requested {:.1}ms) written to {}",
+        frame.width,
+        frame.height,
+        frame.time_ms,
+        timestamp_ms,
+        output_png_path.display()
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Input Probing
+// ============================================================================
+
+/// Maximum number of video packets fed to the decoder before giving up.
+/// Codecs with frame reordering may need a few packets before the first frame.
+const PROBE_MAX_PACKETS: usize = 32;
+
+/// Open the input and decode the first video frame without rendering anything
+pub fn probe_video(input_path: &Path) -> Result<(), EngineError> {
+    utils::init_ffmpeg().map_err(EngineError::InputUnreadable)?;
+
+    let mut input_ctx = utils::open_input(input_path, DEFAULT_SEQUENCE_FRAME_RATE)?;
+    let video_stream = best_video_stream(&input_ctx)?;
+    let video_stream_idx = video_stream.index();
+
+    let mut decoder = codec::context::Context::from_parameters(video_stream.parameters())
+        .and_then(|ctx| ctx.decoder().video())
+        .map_err(|_| EngineError::Undecodable)?;
+
+    let mut frame = VideoFrame::empty();
+    let mut packets_sent = 0;
+
+    for (stream, packet) in input_ctx.packets() {
+        if stream.index() != video_stream_idx {
+            continue;
+        }
+        if decoder.send_packet(&packet).is_ok() && decoder.receive_frame(&mut frame).is_ok() {
+            return Ok(());
+        }
+        packets_sent += 1;
+        if packets_sent >= PROBE_MAX_PACKETS {
+            break;
+        }
+    }
+
+    // Short clips may only produce their first frame once drained
+    if decoder.send_eof().is_ok() && decoder.receive_frame(&mut frame).is_ok() {
+        return Ok(());
+    }
+
+    Err(EngineError::Undecodable)
+}
+
+// ============================================================================
+// Idle Detection
+// ============================================================================
+
+/// `(ms, % of the picture changed since the previous sample)` for one frame
+/// every `interval_ms`, compared at `IDLE_SAMPLE_WIDTH` (see `IdleSpeedup`)
+fn sample_frame_activity(
+    input_path: &Path,
+    sequence_frame_rate: i32,
+    interval_ms: f64,
+) -> Result<Vec<(f64, f32)>, EngineError> {
+    let mut input_ctx = utils::open_input(input_path, sequence_frame_rate)?;
+    let video_stream = best_video_stream(&input_ctx)?;
+    let video_stream_idx = video_stream.index();
+    let time_base = f64::from(video_stream.time_base());
+    let video_start = match video_stream.start_time() {
+        ffmpeg::ffi::AV_NOPTS_VALUE => 0,
+        start => start,
+    };
+    let mut decoder = codec::context::Context::from_parameters(video_stream.parameters())?
+        .decoder()
+        .video()?;
+    let width = IDLE_SAMPLE_WIDTH.min(decoder.width());
+    let height = (decoder.height() * width / decoder.width().max(1)).max(1);
+    let mut scaler = ScalerContext::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        Pixel::GRAY8,
+        width,
+        height,
+        Flags::AREA,
+    )?;
+
+    let mut samples = Vec::new();
+    let mut previous: Option<Vec<u8>> = None;
+    let mut next_ms = 0.0;
+    let mut sample = |frame: &VideoFrame| -> Result<(), EngineError> {
+        let Some(ts) = frame.timestamp().or(frame.pts()) else {
+            return Ok(());
+        };
+        let ms = (ts - video_start) as f64 * time_base * 1000.0;
+        if ms < next_ms {
+            return Ok(());
+        }
+        next_ms = ms + interval_ms;
+        let mut gray = VideoFrame::empty();
+        scaler.run(frame, &mut gray)?;
+        let luma: Vec<u8> = gray
+            .data(0)
+            .chunks(gray.stride(0))
+            .take(height as usize)
+            .flat_map(|row| &row[..width as usize])
+            .copied()
+            .collect();
+        if let Some(previous) = &previous {
+            let changed = previous
+                .iter()
+                .zip(&luma)
+                .filter(|&(a, b)| a.abs_diff(*b) > IDLE_PIXEL_CHANGE_LEVELS)
+                .count();
+            samples.push((ms, changed as f32 * 100.0 / luma.len() as f32));
+        }
+        previous = Some(luma);
+        Ok(())
+    };
+
+    let mut frame = VideoFrame::empty();
+    for (stream, packet) in input_ctx.packets() {
+        if stream.index() != video_stream_idx {
+            continue;
+        }
+        decoder.send_packet(&packet)?;
+        while decoder.receive_frame(&mut frame).is_ok() {
+            sample(&frame)?;
+        }
+    }
+    decoder.send_eof()?;
+    while decoder.receive_frame(&mut frame).is_ok() {
+        sample(&frame)?;
+    }
+    Ok(samples)
+}
+
+// ============================================================================
+// Stream Copy
+// ============================================================================
+
+/// Outcome of `remux_video`
+pub enum StreamCopy {
+    Copied(RenderStats),
+    /// The input cannot be copied as asked; nothing was muxed
+    Unavailable(String),
+}
+
+/// An input stream `remux_video` copies, with the cut on its clock
+struct CopiedStream {
+    input_index: usize,
+    output_index: usize,
+    input_time_base: Rational,
+    /// The input's until write_header, which may change it
+    output_time_base: Rational,
+    /// Input timestamp of the cut, which becomes 0
+    origin: i64,
+    /// Trim end on the output clock
+    end: Option<i64>,
+    /// A packet at or past `end` has been read
+    done: bool,
+}
+
+impl CopiedStream {
+    /// Move `packet` onto the output clock and mux it. Returns where the
+    /// packet ends (output clock, input ticks), or None if it falls outside
+    /// the cut.
+    fn write(
+        &mut self,
+        packet: &mut Packet,
+        output_ctx: &mut ffmpeg::format::context::Output,
+    ) -> Result<Option<i64>, FfmpegError> {
+        let shift = |ts: Option<i64>| ts.map(|ts| ts - self.origin);
+        let (pts, dts) = (shift(packet.pts()), shift(packet.dts()));
+        if pts.or(dts).is_some_and(|ts| ts < 0) {
+            return Ok(None);
+        }
+        // By decode time, so the frames shown before the end keep the
+        // frames they reference
+        if dts.or(pts).zip(self.end).is_some_and(|(ts, end)| ts >= end) {
+            self.done = true;
+            return Ok(None);
+        }
+        let packet_end = pts.or(dts).unwrap_or(0) + packet.duration();
+        packet.set_pts(pts);
+        packet.set_dts(dts);
+        packet.set_position(-1);
+        packet.set_stream(self.output_index);
+        packet.rescale_ts(self.input_time_base, self.output_time_base);
+        packet.write_interleaved(output_ctx)?;
+        Ok(Some(packet_end))
+    }
+}
+
+/// Copy the streams of `input_path` into `output_path` without decoding
+/// them, cut to the trim range (see `ProcessingOptions::stream_copy`).
+/// Progress follows the packets' position in the video.
+pub fn remux_video(
+    input_path: &Path,
+    output_path: &Path,
+    config: &ProcessingOptions,
+    observer: &mut impl RenderObserver,
+) -> Result<StreamCopy, EngineError> {
+    log::info!(
+        "Copying streams: {} -> {}",
+        input_path.display(),
+        output_path.display()
+    );
+    utils::init_ffmpeg()?;
+    observer.progress(Progress {
+        fraction: 0.0,
+        frames: 0,
+    });
+
+    let mut input_ctx = utils::open_input(input_path, config.sequence_frame_rate)?;
+    let video_stream = best_video_stream(&input_ctx)?;
+    let video_stream_idx = video_stream.index();
+    let video_time_base = video_stream.time_base();
+    let tick_ms = f64::from(video_time_base) * 1000.0;
+    let video_start = match video_stream.start_time() {
+        ffmpeg::ffi::AV_NOPTS_VALUE => 0,
+        start => start,
+    };
+    let duration_ms = stream_duration_ms(&input_ctx, &video_stream) as f64;
+    let known_duration = (duration_ms >= 0.0).then_some(duration_ms);
+    if config.trim_start_ms > 0.0 && known_duration.is_some_and(|d| config.trim_start_ms >= d) {
+        return Err(EngineError::TrimPastEnd {
+            start_ms: config.trim_start_ms,
+            duration_ms,
+        });
+    }
+    // Packets can only be cut at a keyframe: probe the keyframe the seek
+    // lands on (the one at or before the trim start), then seek back to copy
+    // from it
+    let mut origin = video_start;
+    let seek_ts = (config.trim_start_ms > 0.0).then(|| {
+        let start = video_start + (config.trim_start_ms / tick_ms).round() as i64;
+        start.rescale(video_time_base, Rational::new(1, ffmpeg::ffi::AV_TIME_BASE))
+    });
+    if let Some(seek_ts) = seek_ts {
+        input_ctx.seek(seek_ts, ..seek_ts)?;
+        let keyframe = input_ctx
+            .packets()
+            .find(|(stream, _)| stream.index() == video_stream_idx)
+            .map(|(_, packet)| packet);
+        let Some(keyframe) = keyframe else {
+            return Err(EngineError::TrimPastEnd {
+                start_ms: config.trim_start_ms,
+                duration_ms,
+            });
+        };
+        let Some(pts) = keyframe.pts().filter(|_| keyframe.is_key()) else {
+            return Ok(StreamCopy::Unavailable(format!(
+                "no keyframe to cut at before {:.0} ms",
+                config.trim_start_ms
+            )));
+        };
+        origin = pts;
+        log::info!(
+            "Cutting at the keyframe at {:.0} ms (trim start {:.0} ms)",
+            (origin - video_start) as f64 * tick_ms,
+            config.trim_start_ms
+        );
+    }
+    let cut_ms = (origin - video_start) as f64 * tick_ms;
+
+    // Every video, audio and subtitle stream the container can hold
+    let mut output_ctx = utils::open_output(output_path, config.container.muxer_name())?;
+    let mut copies = Vec::new();
+    for stream in input_ctx.streams() {
+        let parameters = stream.parameters();
+        if !matches!(
+            parameters.medium(),
+            Type::Video | Type::Audio | Type::Subtitle
+        ) {
+            continue;
+        }
+        if !container_accepts(&output_ctx, parameters.id()) {
+            if stream.index() == video_stream_idx {
+                return Ok(StreamCopy::Unavailable(format!(
+                    "{:?} video cannot be stored in a {} file",
+                    parameters.id(),
+                    output_ctx.format().name()
+                )));
+            }
+            log::warn!(
+                "{:?} (stream {}) cannot be stored in a {} file, leaving it out",
+                parameters.id(),
+                stream.index(),
+                output_ctx.format().name()
+            );
+            continue;
+        }
+        let time_base = stream.time_base();
+        let mut output_stream = output_ctx.add_stream(encoder::find(codec::Id::None))?;
+        output_stream.set_parameters(parameters);
+        output_stream.set_time_base(time_base);
+        // The input container's codec tag may mean nothing in the output's
+        unsafe {
+            (*output_stream.parameters().as_mut_ptr()).codec_tag = 0;
+        }
+        copy_stream_metadata(&stream, &mut output_stream, config);
+        copies.push(CopiedStream {
+            input_index: stream.index(),
+            output_index: output_stream.index(),
+            input_time_base: time_base,
+            output_time_base: time_base,
+            origin: origin.rescale(video_time_base, time_base),
+            end: config
+                .trim_end_ms
+                .map(|end_ms| ((end_ms - cut_ms) / 1000.0 / f64::from(time_base)).round() as i64),
+            done: false,
+        });
+    }
+
+    set_container_metadata(&mut output_ctx, Some(&input_ctx), config);
+    output_ctx.write_header_with(muxer_options(config))?;
+    for copy in &mut copies {
+        if let Some(stream) = output_ctx.stream(copy.output_index) {
+            copy.output_time_base = stream.time_base();
+        }
+    }
+
+    if let Some(seek_ts) = seek_ts {
+        input_ctx.seek(seek_ts, ..seek_ts)?;
+    }
+    let span_ms = config
+        .trim_end_ms
+        .or(known_duration)
+        .map(|end_ms| end_ms - cut_ms);
+    let (mut frames, mut covered) = (0u64, 0i64);
+    for (stream, mut packet) in input_ctx.packets() {
+        let Some(copy) = copies.iter_mut().find(|c| c.input_index == stream.index()) else {
+            continue;
+        };
+        let Some(packet_end) = copy.write(&mut packet, &mut output_ctx)? else {
+            if copies.iter().all(|c| c.done) {
+                break;
+            }
+            continue;
+        };
+        if stream.index() != video_stream_idx {
+            continue;
+        }
+        frames += 1;
+        covered = covered.max(packet_end);
+        if frames.is_multiple_of(30) {
+            let fraction = span_ms.map_or(INDETERMINATE_PROGRESS, |span_ms| {
+                (covered as f64 * tick_ms / span_ms).clamp(0.0, 1.0) as f32
+            });
+            observer.progress(Progress { fraction, frames });
+        }
+    }
+    output_ctx.write_trailer()?;
+
+    observer.progress(Progress {
+        fraction: 1.0,
+        frames,
+    });
+    log::info!("Stream copy complete: {} video packets", frames);
+    Ok(StreamCopy::Copied(RenderStats {
+        frames_encoded: frames,
+        source_duration: Duration::from_secs_f64(covered.max(0) as f64 * tick_ms / 1000.0),
+        encoder_backend: EncoderBackend::Software,
+        warnings: WarningCounts::default(),
+    }))
+}
+
+// ============================================================================
+// Metadata Query
+// ============================================================================
+
+/// Read dimensions, frame rate, duration and codec of the best video stream
+pub fn read_metadata(input_path: &Path) -> Result<VideoMetadata, EngineError> {
+    utils::init_ffmpeg().map_err(EngineError::InputUnreadable)?;
+
+    let input_ctx = utils::open_input(input_path, DEFAULT_SEQUENCE_FRAME_RATE)?;
+    let video_stream = best_video_stream(&input_ctx)?;
+
+    let decoder = codec::context::Context::from_parameters(video_stream.parameters())
+        .and_then(|ctx| ctx.decoder().video())
+        .map_err(|_| EngineError::Undecodable)?;
+
+    let fps = average_frame_rate(&video_stream).unwrap_or_else(|| video_stream.rate());
+
+    let duration_ms = stream_duration_ms(&input_ctx, &video_stream);
+
+    let codec_id: ffmpeg::ffi::AVCodecID = decoder.id().into();
+
+    Ok(VideoMetadata {
+        width: decoder.width(),
+        height: decoder.height(),
+        fps_num: fps.numerator(),
+        fps_den: fps.denominator(),
+        duration_ms,
+        codec_id: codec_id as i32,
+        nb_frames: estimate_total_frames(&video_stream, &input_ctx).map_or(0, |n| n as i64),
+    })
+}
+
+// ============================================================================
+// Internal Helpers
+// ============================================================================
+
+/// Reads a frame from the filter sink, handling the Result wrapper
+fn read_frame_from_sink(
+    filter_sink: &mut ffmpeg::filter::Context,
+) -> Result<VideoFrame, FfmpegError> {
+    let mut frame = VideoFrame::empty();
+    // sink().frame() pulls a filtered frame from the graph
+    filter_sink.sink().frame(&mut frame)?;
+    Ok(frame)
+}
+
+/// The display matrix in `stream`'s side data, if it has one
+fn display_matrix(stream: &ffmpeg::Stream) -> Option<[i32; 9]> {
+    let side_data = stream
+        .side_data()
+        .find(|data| data.kind() == codec::packet::side_data::Type::DisplayMatrix)?;
+    let bytes = side_data.data().get(..36)?;
+    let mut matrix = [0; 9];
+    for (value, chunk) in matrix.iter_mut().zip(bytes.chunks_exact(4)) {
+        *value = i32::from_ne_bytes(chunk.try_into().unwrap());
+    }
+    Some(matrix)
+}
+
+/// Link `first` -> `middle`... -> `last`, each on pad 0
+fn link_chain(
+    first: &mut ffmpeg::filter::Context,
+    middle: &mut [ffmpeg::filter::Context],
+    last: &mut ffmpeg::filter::Context,
+) {
+    match middle.split_first_mut() {
+        Some((next, rest)) => {
+            first.link(0, next, 0);
+            link_chain(next, rest, last);
+        }
+        None => first.link(0, last, 0),
+    }
+}
+
+/// The stream to render from `input_ctx`: its best video stream, leaving out
+/// cover art (an attached picture is not a video). Without one, the error
+/// lists what the input does have.
+fn best_video_stream(
+    input_ctx: &ffmpeg::format::context::Input,
+) -> Result<ffmpeg::Stream<'_>, EngineError> {
+    let is_video = |stream: &ffmpeg::Stream| {
+        stream.parameters().medium() == Type::Video
+            && !stream
+                .disposition()
+                .contains(ffmpeg::format::stream::Disposition::ATTACHED_PIC)
+    };
+    if let Some(stream) = input_ctx.streams().best(Type::Video).filter(is_video) {
+        return Ok(stream);
+    }
+    if let Some(stream) = input_ctx.streams().find(is_video) {
+        return Ok(stream);
+    }
+    let found: Vec<String> = input_ctx
+        .streams()
+        .map(|stream| {
+            let parameters = stream.parameters();
+            let kind = match parameters.medium() {
+                Type::Video => "cover art",
+                Type::Audio => "audio",
+                Type::Subtitle => "subtitles",
+                Type::Data => "data",
+                Type::Attachment => "attachment",
+                Type::Unknown => "unknown",
+            };
+            match parameters.id() {
+                codec::Id::None => kind.to_string(),
+                id => format!("{} ({})", kind, id.name()),
+            }
+        })
+        .collect();
+    Err(EngineError::NoVideoStream(if found.is_empty() {
+        "no streams".to_string()
+    } else {
+        found.join(", ")
+    }))
+}
+
+/// A still image rather than a video: read by a single-image demuxer
+/// (png_pipe, jpeg_pipe, ...), or a stream of at most one frame. Image
+/// sequences (image2) count their frames, so they are videos.
+fn is_still_image(input_ctx: &ffmpeg::format::context::Input, stream: &ffmpeg::Stream) -> bool {
+    input_ctx.format().name().ends_with("_pipe")
+        || matches!(estimate_total_frames(stream, input_ctx), Some(0 | 1))
+}
+
+/// Length of `stream` in ms: the stream duration first, then the container
+/// duration, otherwise unknown (-1)
+fn stream_duration_ms(input_ctx: &ffmpeg::format::context::Input, stream: &ffmpeg::Stream) -> i64 {
+    let stream_duration = stream.duration();
+    if stream_duration != ffmpeg::ffi::AV_NOPTS_VALUE && stream_duration > 0 {
+        (stream_duration as f64 * f64::from(stream.time_base()) * 1000.0).round() as i64
+    } else if input_ctx.duration() > 0 {
+        input_ctx.duration() * 1000 / i64::from(ffmpeg::ffi::AV_TIME_BASE)
+    } else {
+        -1
+    }
+}
+
+/// Frames in `stream`: the count the container stores (`nb_frames`), else
+/// its length (see `stream_duration_ms`) at the average frame rate; None when
+/// neither is known
+fn estimate_total_frames(
+    stream: &ffmpeg::Stream,
+    input_ctx: &ffmpeg::format::context::Input,
+) -> Option<u64> {
+    if stream.frames() > 0 {
+        return Some(stream.frames() as u64);
+    }
+    let fps = average_frame_rate(stream)?;
+    let duration_ms = stream_duration_ms(input_ctx, stream);
+    (duration_ms > 0).then(|| (duration_ms as f64 / 1000.0 * f64::from(fps)).round() as u64)
+}
+
+/// The average frame rate of `stream`, else its base rate (r_frame_rate);
+/// None when neither is set
+fn average_frame_rate(stream: &ffmpeg::Stream) -> Option<Rational> {
+    [stream.avg_frame_rate(), stream.rate()]
+        .into_iter()
+        .find(|rate| rate.numerator() > 0 && rate.denominator() > 0)
+}
+
+/// A cursor sprite ready to draw: resampled by `prescale`, with the style to
+/// draw it at and its shadow silhouette
+struct CursorVariant<'a> {
+    sprite: Cow<'a, CursorSprite>,
+    style: CursorStyle,
+    silhouette: Option<CursorSprite>,
+}
+
+impl<'a> CursorVariant<'a> {
+    fn new(sprite: &'a CursorSprite, style: &CursorStyle, shadow: Option<&CursorShadow>) -> Self {
+        // Resample the sprite once for the whole render, not per frame
+        let (sprite, style) = prescale(sprite, style);
+        // Blurred from the prescaled sprite, so the blur ignores the cursor scale
+        let silhouette = shadow.map(|shadow| shadow.silhouette(&sprite));
+        Self {
+            sprite,
+            style,
+            silhouette,
+        }
+    }
+}
+
+/// The cursor sprite following the smoothed path, with its shadow and motion trail
+struct CursorOverlay<'a> {
+    /// Indexed by `schedule`
+    variants: &'a [CursorVariant<'a>],
+    schedule: &'a SpriteSchedule,
+    /// Drawn with each variant's silhouette
+    shadow: Option<&'a CursorShadow>,
+    /// Trail settings and the output frame interval in ms
+    trail: Option<(&'a MotionTrail, f64)>,
+    lookup: &'a [(f64, f32, f32)],
+    headings: &'a [f32],
+    tail: TailBehavior,
+    /// Cropped renders don't draw the cursor while it is outside the crop
+    crop: Option<CropBounds<'a>>,
+    /// Whether the last render cut the sprite at a frame edge
+    clipped: Cell<bool>,
+}
+
+/// The crop a cropped render's cursor has to be inside to be drawn
+struct CropBounds<'a> {
+    /// Cursor path in crop coordinates, before any zoom or inset moves it
+    path: &'a [(f64, f32, f32)],
+    width: u32,
+    height: u32,
+}
+
+/// Which of `CursorOverlay`'s sprites a draw uses
+#[derive(Debug, Clone, Copy)]
+enum CursorPart {
+    Shadow,
+    Sprite,
+}
+
+impl CursorOverlay<'_> {
+    /// Index into `variants` of the sprite showing at `timestamp_ms`
+    fn variant_at(&self, timestamp_ms: f64) -> usize {
+        self.schedule
+            .index_at(timestamp_ms)
+            .min(self.variants.len() - 1)
+    }
+
+    /// Sprite placements at `timestamp_ms`, bottom to top: shadow, trail
+    /// ghosts, then the cursor itself
+    fn draws(&self, timestamp_ms: f64) -> Vec<(CursorPart, f32, f32, CursorStyle)> {
+        if let Some(crop) = &self.crop {
+            let (x, y) = interpolate_cursor_position(crop.path, timestamp_ms, self.tail);
+            if !(0.0..crop.width as f32).contains(&x) || !(0.0..crop.height as f32).contains(&y) {
+                return Vec::new();
+            }
+        }
+        let (cx, cy) = interpolate_cursor_position(self.lookup, timestamp_ms, self.tail);
+        let style = CursorStyle {
+            rotation: heading_at(self.lookup, self.headings, timestamp_ms),
+            ..self.variants[self.variant_at(timestamp_ms)].style
+        };
+        let mut draws = Vec::new();
+        if let Some(shadow) = self.shadow {
+            let (sx, sy, shadow_style) = shadow.placement(cx, cy, &style);
+            draws.push((CursorPart::Shadow, sx, sy, shadow_style));
+        }
+        if let Some((trail, frame_interval_ms)) = self.trail {
+            let ghosts = trail.ghosts(
+                |t| interpolate_cursor_position(self.lookup, t, self.tail),
+                timestamp_ms,
+                frame_interval_ms,
+            );
+            for (gx, gy, opacity) in ghosts {
+                let ghost_style = CursorStyle {
+                    opacity: style.opacity * opacity,
+                    ..style
+                };
+                draws.push((CursorPart::Sprite, gx, gy, ghost_style));
+            }
+        }
+        draws.push((CursorPart::Sprite, cx, cy, style));
+        draws
+    }
+}
+
+impl Overlay for CursorOverlay<'_> {
+    fn render(&self, frame: &mut FrameView<'_>, timestamp_ms: f64) {
+        let variant = &self.variants[self.variant_at(timestamp_ms)];
+        // Only the last draw (the cursor itself) decides `clipped`
+        let mut clipped = false;
+        for (part, x, y, style) in self.draws(timestamp_ms) {
+            let sprite = match (part, &variant.silhouette) {
+                (CursorPart::Shadow, Some(silhouette)) => silhouette,
+                _ => &variant.sprite,
+            };
+            clipped = frame.composite_sprite(sprite.frame_at(timestamp_ms), x, y, &style);
+        }
+        self.clipped.set(clipped);
+    }
+
+    fn clipped(&self) -> bool {
+        self.clipped.get()
+    }
+}
+
+/// `CursorOverlay` blended straight into 4:2:0 frames (`yuv_compositing`)
+struct YuvCursor<'a> {
+    cursor: CursorOverlay<'a>,
+    /// Converted animation frames of each variant's sprite and silhouette
+    sprites: Vec<Vec<YuvSprite>>,
+    silhouettes: Vec<Vec<YuvSprite>>,
+}
+
+impl<'a> YuvCursor<'a> {
+    fn new(cursor: CursorOverlay<'a>) -> Self {
+        Self {
+            sprites: cursor
+                .variants
+                .iter()
+                .map(|variant| YuvSprite::frames(&variant.sprite))
+                .collect(),
+            silhouettes: cursor
+                .variants
+                .iter()
+                .map(|variant| {
+                    variant
+                        .silhouette
+                        .as_ref()
+                        .map_or_else(Vec::new, YuvSprite::frames)
+                })
+                .collect(),
+            cursor,
+        }
+    }
+
+    /// Draw onto `frame`; true when the cursor was clipped at a frame edge
+    fn render<S: YuvSample>(&self, frame: &mut Yuv420Frame<'_, S>, timestamp_ms: f64) -> bool {
+        let variant = self.cursor.variant_at(timestamp_ms);
+        // The silhouette keeps the sprite's frame timing
+        let index = self.cursor.variants[variant]
+            .sprite
+            .frame_index(timestamp_ms);
+        let mut clipped = false;
+        for (part, x, y, style) in self.cursor.draws(timestamp_ms) {
+            let frames = match part {
+                CursorPart::Shadow => &self.silhouettes[variant],
+                CursorPart::Sprite => &self.sprites[variant],
+            };
+            if let Some(sprite) = frames.get(index) {
+                clipped = composite_cursor_yuv420(frame, sprite, x, y, &style);
+            }
+        }
+        clipped
+    }
+}
+
+/// How frames from the filter graph get their overlays
+enum Compositing<'a> {
+    /// RGBA frames drawn on by `layers`, then converted for the encoder
+    /// unless it takes RGBA (GIF)
+    Rgba { layers: Vec<Box<dyn Overlay + 'a>> },
+    /// YUV420P (or YUV420P10LE for 10-bit output) frames the cursor is
+    /// blended into, sent to the encoder as-is or, for encoders that want
+    /// another layout (NV12, P010) or another size, converted
+    Yuv420 {
+        cursor: YuvCursor<'a>,
+        depth: BitDepth,
+    },
+}
+
+/// The first enabled option that needs RGBA frames, if any (see
+/// `ProcessingOptions::yuv_compositing`)
+fn rgba_requirement(config: &ProcessingOptions) -> Option<&'static str> {
+    if config.click_effect.is_some() {
+        Some("click_effect")
+    } else if config.spotlight.is_some() {
+        Some("spotlight")
+    } else if config.auto_zoom.is_some_and(|zoom| zoom.zoom > 1.0) {
+        Some("auto_zoom")
+    } else if config.click_zoom.is_some_and(|zoom| zoom.zoom > 1.0) {
+        Some("click_zoom")
+    } else if config.frame_style.is_some() {
+        Some("frame_style")
+    } else if config.watermark.is_some() {
+        Some("watermark")
+    } else if !config.zoom_keyframes.is_empty() {
+        Some("zoom_keyframes")
+    } else if !config.redactions.is_empty() {
+        Some("redactions")
+    } else if !config.captions.is_empty() {
+        Some("captions")
+    } else if config.keystrokes.is_some() && !config.key_events.is_empty() {
+        Some("keystrokes")
+    } else if config.orient_to_velocity {
+        Some("orient_to_velocity")
+    } else if config.gamma_correct_blend {
+        Some("gamma_correct_blend")
+    } else {
+        None
+    }
+}
+
+/// Borrow the three planes of a YUV420P frame (`u8` samples) or a 9-16 bit
+/// one like YUV420P10LE (`u16`) at once
+fn yuv420_planes<S: YuvSample>(
+    frame: &mut VideoFrame,
+    bit_depth: u32,
+) -> Result<Yuv420Frame<'_, S>, EngineError> {
+    let ptr = unsafe { frame.as_mut_ptr() };
+    // The fps filter repeats frames by reference; give this one its own
+    // buffer so the cursor isn't drawn into the repeats as well
+    let ret = unsafe { ffmpeg::ffi::av_frame_make_writable(ptr) };
+    if ret < 0 {
+        return Err(FfmpegError::from(ret).into());
+    }
+    let (width, height) = (frame.width(), frame.height());
+    let sample_size = std::mem::size_of::<S>();
+    let strides = std::array::from_fn(|i| frame.stride(i) / sample_size);
+    let rows = [height, height.div_ceil(2), height.div_ceil(2)];
+    // SAFETY: each plane is its own buffer of at least stride * rows
+    // samples, aligned well past a `u16`, and `frame` stays mutably borrowed
+    // for as long as the slices live
+    let planes = std::array::from_fn(|i| unsafe {
+        std::slice::from_raw_parts_mut((*ptr).data[i] as *mut S, strides[i] * rows[i] as usize)
+    });
+    Ok(Yuv420Frame {
+        planes,
+        strides,
+        width,
+        height,
+        bit_depth,
+    })
+}
+
+/// The keystroke badge layer, if enabled and there are presses to show
+fn keystroke_overlay(config: &ProcessingOptions) -> Result<Option<KeystrokeOverlay>, EngineError> {
+    let Some(display) = &config.keystrokes else {
+        return Ok(None);
+    };
+    if config.key_events.is_empty() {
+        return Ok(None);
+    }
+    let font = load_font(&display.font_path)
+        .map_err(|e| EngineError::Font(format!("{}: {}", display.font_path.display(), e)))?;
+    Ok(Some(KeystrokeOverlay::new(
+        &config.key_events,
+        display,
+        font,
+        config.gamma_correct_blend,
+    )))
+}
+
+/// The caption layer, if there are captions to show
+fn caption_overlay(config: &ProcessingOptions) -> Result<Option<CaptionOverlay>, EngineError> {
+    if config.captions.is_empty() {
+        return Ok(None);
+    }
+    let style = &config.caption_style;
+    let font = load_font(&style.font_path)
+        .map_err(|e| EngineError::Font(format!("{}: {}", style.font_path.display(), e)))?;
+    Ok(Some(CaptionOverlay::new(
+        &config.captions,
+        style,
+        font,
+        config.gamma_correct_blend,
+    )))
+}
+
+/// Load the sprites of the cursor kinds that `config.cursor_kind_events`
+/// uses, with the style for each hotspot, and the schedule switching between
+/// them
+fn cursor_kind_sprites(
+    config: &ProcessingOptions,
+) -> Result<(Vec<(CursorSprite, CursorStyle)>, SpriteSchedule), EngineError> {
+    let mut seen: Vec<&str> = Vec::new();
+    let mut kinds: Vec<&str> = Vec::new();
+    let mut sprites = Vec::new();
+    for event in &config.cursor_kind_events {
+        let kind = event.kind.as_str();
+        if seen.contains(&kind) {
+            continue;
+        }
+        seen.push(kind);
+        let Some(entry) = config.cursor_kind_sprites.get(kind) else {
+            if !kind.is_empty() {
+                log::warn!(
+                    "No sprite for cursor kind {:?}, drawing the default sprite",
+                    kind
+                );
+            }
+            continue;
+        };
+        let style = CursorStyle {
+            hotspot_x: entry.hotspot_x,
+            hotspot_y: entry.hotspot_y,
+            ..config.cursor_style()
+        };
+        let (sprite, style) = load_cursor_sprite_prescaled(&entry.sprite_path, &style)
+            .map_err(EngineError::Sprite)?;
+        kinds.push(kind);
+        sprites.push((sprite, style));
+    }
+    let schedule = SpriteSchedule::new(&config.cursor_kind_events, &kinds);
+    Ok((sprites, schedule))
+}
+
+/// How the input's audio reaches the output
+enum AudioOutput {
+    /// Packets copied untouched
+    Copy(AudioPassthrough),
+    /// Decoded and re-encoded to AAC, for codecs the container cannot hold
+    Transcode(Box<AudioTranscode>),
+}
+
+impl AudioOutput {
+    fn input_index(&self) -> usize {
+        match self {
+            Self::Copy(copy) => copy.input_index,
+            Self::Transcode(transcode) => transcode.input_index,
+        }
+    }
+
+    fn write(
+        &mut self,
+        packet: &mut Packet,
+        output_ctx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(), EngineError> {
+        match self {
+            Self::Copy(copy) => Ok(copy.write(packet, output_ctx)?),
+            Self::Transcode(transcode) => transcode.write(packet, output_ctx),
+        }
+    }
+
+    /// Drain everything still buffered once the input is exhausted
+    fn finish(
+        &mut self,
+        output_ctx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(), EngineError> {
+        match self {
+            Self::Copy(_) => Ok(()),
+            Self::Transcode(transcode) => transcode.finish(output_ctx),
+        }
+    }
+}
+
+/// The input's audio stream copied into the output without re-encoding
+struct AudioPassthrough {
+    input_index: usize,
+    output_index: usize,
+    input_time_base: Rational,
+    /// Input audio time of the first video frame, output time 0
+    origin: i64,
+    /// Output time of the trim end, from which packets are dropped
+    end: Option<i64>,
+}
+
+impl AudioPassthrough {
+    /// Move `packet` onto the output's timeline and mux it with the video
+    fn write(
+        &self,
+        packet: &mut Packet,
+        output_ctx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(), FfmpegError> {
+        let shift = |ts: Option<i64>| ts.map(|ts| ts - self.origin);
+        let (pts, dts) = (shift(packet.pts()), shift(packet.dts()));
+        // Sound from before the first video frame has no picture to go with;
+        // the rest keeps its timestamps, so sync holds
+        if pts.or(dts).is_some_and(|ts| ts < 0) {
+            return Ok(());
+        }
+        if pts.is_some_and(|pts| self.end.is_some_and(|end| pts >= end)) {
+            return Ok(());
+        }
+        packet.set_pts(pts);
+        packet.set_dts(dts);
+        packet.set_position(-1);
+        packet.set_stream(self.output_index);
+        // The muxer may have changed the stream's time base in write_header
+        let stream_tb = output_ctx
+            .stream(self.output_index)
+            .map(|s| s.time_base())
+            .unwrap_or(self.input_time_base);
+        packet.rescale_ts(self.input_time_base, stream_tb);
+        packet.write_interleaved(output_ctx)
+    }
+}
+
+/// The input's audio stream decoded, converted to the encoder's sample
+/// format, stereo layout and rate, and re-encoded to AAC
+struct AudioTranscode {
+    input_index: usize,
+    output_index: usize,
+    input_time_base: Rational,
+    /// Input audio time of the first video frame, output time 0
+    origin: i64,
+    decoder: ffmpeg::decoder::Audio,
+    /// Layout of the decoded frames (raw PCM often only has a channel count)
+    input_layout: ChannelLayout,
+    resampler: ResamplerContext,
+    encoder: encoder::audio::Encoder,
+    fifo: SampleFifo,
+    /// Output sample index of the trim end; nothing from there on is encoded
+    end: Option<i64>,
+    /// Output sample index of the next encoded frame (None until the first
+    /// decoded frame places the audio on the output timeline)
+    next_pts: Option<i64>,
+}
+
+impl AudioTranscode {
+    fn new(
+        audio_stream: &ffmpeg::Stream,
+        aac: ffmpeg::Codec,
+        origin: i64,
+        end: Option<i64>,
+        config: &ProcessingOptions,
+        output_ctx: &mut ffmpeg::format::context::Output,
+    ) -> Result<Self, EngineError> {
+        let decoder = codec::context::Context::from_parameters(audio_stream.parameters())?
+            .decoder()
+            .audio()?;
+        let input_layout = match decoder.channel_layout() {
+            layout if layout.is_empty() => ChannelLayout::default(decoder.channels() as i32),
+            layout => layout,
+        };
+        let aac_audio = aac.audio()?;
+        // Keep the source rate unless the encoder cannot take it
+        let source_rate = decoder.rate() as i32;
+        let rate = if aac_audio
+            .rates()
+            .is_none_or(|mut rates| rates.any(|rate| rate == source_rate))
+        {
+            source_rate
+        } else {
+            48_000
+        };
+        let format = aac_audio
+            .formats()
+            .and_then(|mut formats| formats.next())
+            .unwrap_or(Sample::F32(SampleType::Planar));
+
+        let global_header = output_ctx
+            .format()
+            .flags()
+            .contains(ffmpeg::format::flag::Flags::GLOBAL_HEADER);
+        let mut output_stream = output_ctx.add_stream(Some(aac))?;
+        let mut encoder = codec::context::Context::new_with_codec(aac)
+            .encoder()
+            .audio()?;
+        encoder.set_rate(rate);
+        // Stereo, so a mono microphone plays from both speakers
+        encoder.set_channel_layout(ChannelLayout::STEREO);
+        encoder.set_format(format);
+        encoder.set_bit_rate(config.audio_bitrate_kbps as usize * 1000);
+        encoder.set_time_base(Rational::new(1, rate));
+        if global_header {
+            encoder.set_flags(codec::flag::Flags::GLOBAL_HEADER);
+        }
+        let mut opts = ffmpeg::Dictionary::new();
+        if config.deterministic {
+            opts.set("flags", "+bitexact");
+        }
+        let encoder = encoder.open_with(opts)?;
+        output_stream.set_parameters(&encoder);
+        let output_index = output_stream.index();
+
+        let resampler = ResamplerContext::get(
+            decoder.format(),
+            input_layout,
+            decoder.rate(),
+            encoder.format(),
+            encoder.channel_layout(),
+            encoder.rate(),
+        )?;
+        Ok(Self {
+            input_index: audio_stream.index(),
+            output_index,
+            input_time_base: audio_stream.time_base(),
+            origin,
+            fifo: SampleFifo::new(encoder.format(), encoder.channels() as usize),
+            end: end.map(|end| end.rescale(audio_stream.time_base(), encoder.time_base())),
+            decoder,
+            input_layout,
+            resampler,
+            encoder,
+            next_pts: None,
+        })
+    }
+
+    fn write(
+        &mut self,
+        packet: &Packet,
+        output_ctx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(), EngineError> {
+        self.decoder.send_packet(packet)?;
+        self.convert_decoded(output_ctx)
+    }
+
+    /// Flush the decoder, resampler and encoder, in that order
+    fn finish(
+        &mut self,
+        output_ctx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(), EngineError> {
+        self.decoder.send_eof()?;
+        self.convert_decoded(output_ctx)?;
+        loop {
+            let mut resampled = self.resampled_frame(0);
+            if resampled.samples() == 0 {
+                break;
+            }
+            self.resampler.flush(&mut resampled)?;
+            if resampled.samples() == 0 {
+                break;
+            }
+            self.buffer(&resampled);
+        }
+        self.encode_buffered(output_ctx, true)?;
+        self.encoder.send_eof()?;
+        self.write_packets(output_ctx)?;
+        Ok(())
+    }
+
+    /// Resample every frame the decoder has ready into the FIFO and encode
+    /// each whole encoder frame
+    fn convert_decoded(
+        &mut self,
+        output_ctx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(), EngineError> {
+        let mut decoded = AudioFrame::empty();
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            if decoded.channel_layout().is_empty() {
+                decoded.set_channel_layout(self.input_layout);
+            }
+            self.place_first_samples(decoded.pts());
+            let mut resampled = self.resampled_frame(decoded.samples());
+            self.resampler.run(&decoded, &mut resampled)?;
+            self.buffer(&resampled);
+            self.encode_buffered(output_ctx, false)?;
+        }
+        Ok(())
+    }
+
+    /// Start the output audio where the first decoded frame sits relative to
+    /// the first video frame, as copied audio does. Later frames follow on
+    /// sample by sample.
+    fn place_first_samples(&mut self, pts: Option<i64>) {
+        if self.next_pts.is_some() {
+            return;
+        }
+        let sample_tb = Rational::new(1, self.encoder.rate() as i32);
+        let start = pts.map_or(0, |pts| {
+            (pts - self.origin).rescale(self.input_time_base, sample_tb)
+        });
+        // Sound from before the first video frame is dropped
+        self.fifo.skip = (-start).max(0) as usize;
+        self.next_pts = Some(start.max(0));
+    }
+
+    /// Add resampled samples to the FIFO, up to the trim end
+    fn buffer(&mut self, resampled: &AudioFrame) {
+        self.fifo.push(resampled);
+        if let (Some(end), Some(next_pts)) = (self.end, self.next_pts) {
+            self.fifo.truncate((end - next_pts).max(0) as usize);
+        }
+    }
+
+    /// Empty frame in the encoder's format with room for everything the
+    /// resampler can return for `input_samples` more samples
+    fn resampled_frame(&mut self, input_samples: usize) -> AudioFrame {
+        let capacity = unsafe {
+            ffmpeg::ffi::swr_get_out_samples(self.resampler.as_mut_ptr(), input_samples as i32)
+        };
+        AudioFrame::new(
+            self.encoder.format(),
+            capacity.max(0) as usize,
+            self.encoder.channel_layout(),
+        )
+    }
+
+    /// Encode the FIFO in encoder-sized frames, including the short final
+    /// frame `at_eof`
+    fn encode_buffered(
+        &mut self,
+        output_ctx: &mut ffmpeg::format::context::Output,
+        at_eof: bool,
+    ) -> Result<(), FfmpegError> {
+        // 0 = the encoder takes frames of any size
+        let frame_size = match self.encoder.frame_size() as usize {
+            0 => self.fifo.len(),
+            size => size,
+        }
+        .max(1);
+        while self.fifo.len() >= frame_size || (at_eof && !self.fifo.is_empty()) {
+            let samples = self.fifo.len().min(frame_size);
+            let mut frame = self.fifo.pop(
+                samples,
+                self.encoder.format(),
+                self.encoder.channel_layout(),
+            );
+            frame.set_rate(self.encoder.rate());
+            let pts = self.next_pts.get_or_insert(0);
+            frame.set_pts(Some(*pts));
+            *pts += samples as i64;
+            self.encoder.send_frame(&frame)?;
+            self.write_packets(output_ctx)?;
+        }
+        Ok(())
+    }
+
+    fn write_packets(
+        &mut self,
+        output_ctx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(), FfmpegError> {
+        let encoder_tb = self.encoder.time_base();
+        let mut packet = Packet::empty();
+        while self.encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(self.output_index);
+            let stream_tb = output_ctx
+                .stream(self.output_index)
+                .map(|s| s.time_base())
+                .unwrap_or(encoder_tb);
+            packet.rescale_ts(encoder_tb, stream_tb);
+            packet.write_interleaved(output_ctx)?;
+        }
+        Ok(())
+    }
+}
+
+/// Resampled audio waiting to fill whole encoder frames (AAC takes exactly
+/// 1024 samples per frame, decoders hand out whatever the source packets held)
+struct SampleFifo {
+    /// One buffer per channel for planar formats, one for all when packed
+    planes: Vec<Vec<u8>>,
+    /// Bytes of one sample in one plane
+    sample_bytes: usize,
+    /// Leading samples still to be dropped on push
+    skip: usize,
+}
+
+impl SampleFifo {
+    fn new(format: Sample, channels: usize) -> Self {
+        let (planes, sample_bytes) = if format.is_planar() {
+            (channels, format.bytes())
+        } else {
+            (1, format.bytes() * channels)
+        };
+        Self {
+            planes: vec![Vec::new(); planes],
+            sample_bytes,
+            skip: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.planes[0].len() / self.sample_bytes
+    }
+
+    fn is_empty(&self) -> bool {
+        self.planes[0].is_empty()
+    }
+
+    fn push(&mut self, frame: &AudioFrame) {
+        let skipped = self.skip.min(frame.samples());
+        self.skip -= skipped;
+        if skipped == frame.samples() {
+            return;
+        }
+        let range = skipped * self.sample_bytes..frame.samples() * self.sample_bytes;
+        for (index, plane) in self.planes.iter_mut().enumerate() {
+            plane.extend_from_slice(&audio_plane(frame, index, range.end)[range.clone()]);
+        }
+    }
+
+    /// Drop everything after the first `samples` samples
+    fn truncate(&mut self, samples: usize) {
+        for plane in &mut self.planes {
+            plane.truncate(samples * self.sample_bytes);
+        }
+    }
+
+    /// The oldest `samples` samples as a frame
+    fn pop(&mut self, samples: usize, format: Sample, layout: ChannelLayout) -> AudioFrame {
+        let mut frame = AudioFrame::new(format, samples, layout);
+        let len = samples * self.sample_bytes;
+        for (index, plane) in self.planes.iter_mut().enumerate() {
+            audio_plane_mut(&mut frame, index, len).copy_from_slice(&plane[..len]);
+            plane.drain(..len);
+        }
+        frame
+    }
+}
+
+/// The first `len` bytes of plane `index` of `frame`. `Audio::data` reads
+/// each plane's own linesize, but FFmpeg only sets the first one for audio.
+fn audio_plane(frame: &AudioFrame, index: usize, len: usize) -> &[u8] {
+    assert!(index < frame.planes());
+    unsafe { std::slice::from_raw_parts((*frame.as_ptr()).data[index], len) }
+}
+
+fn audio_plane_mut(frame: &mut AudioFrame, index: usize, len: usize) -> &mut [u8] {
+    assert!(index < frame.planes());
+    unsafe { std::slice::from_raw_parts_mut((*frame.as_mut_ptr()).data[index], len) }
+}
+
+/// Add an output stream for the input's best audio stream: copied if the
+/// output container can hold its codec, otherwise re-encoded to AAC. Audio
+/// shares the video's origin: the fps filter starts at the first video frame
+/// (or the trim start), which is encoded at time 0, and ends with the video
+/// at the trim end.
+fn add_audio_output(
+    input_ctx: &ffmpeg::format::context::Input,
+    video_stream: &ffmpeg::Stream,
+    config: &ProcessingOptions,
+    output_ctx: &mut ffmpeg::format::context::Output,
+) -> Result<Option<AudioOutput>, EngineError> {
+    let Some(audio_stream) = input_ctx.streams().best(Type::Audio) else {
+        return Ok(None);
+    };
+    let parameters = audio_stream.parameters();
+    let time_base = audio_stream.time_base();
+    let video_start = match video_stream.start_time() {
+        ffmpeg::ffi::AV_NOPTS_VALUE => 0,
+        start => start,
+    };
+    let to_ticks = |ms: f64| (ms / 1000.0 / f64::from(time_base)).round() as i64;
+    let origin =
+        video_start.rescale(video_stream.time_base(), time_base) + to_ticks(config.trim_start_ms);
+    let end = config
+        .trim_end_ms
+        .map(|end_ms| to_ticks(end_ms - config.trim_start_ms));
+
+    if !container_accepts(output_ctx, parameters.id()) {
+        let aac =
+            encoder::find(codec::Id::AAC).filter(|_| container_accepts(output_ctx, codec::Id::AAC));
+        let Some(aac) = aac else {
+            log::warn!(
+                "{:?} audio cannot be stored in a {} file, the output will be silent",
+                parameters.id(),
+                output_ctx.format().name()
+            );
+            return Ok(None);
+        };
+        log::info!(
+            "Re-encoding {:?} audio (stream {}) to AAC at {} kbit/s",
+            parameters.id(),
+            audio_stream.index(),
+            config.audio_bitrate_kbps
+        );
+        let transcode = AudioTranscode::new(&audio_stream, aac, origin, end, config, output_ctx)?;
+        if let Some(mut output_stream) = output_ctx.stream_mut(transcode.output_index) {
+            copy_stream_metadata(&audio_stream, &mut output_stream, config);
+        }
+        return Ok(Some(AudioOutput::Transcode(Box::new(transcode))));
+    }
+
+    let mut output_stream = output_ctx.add_stream(encoder::find(codec::Id::None))?;
+    output_stream.set_parameters(parameters);
+    output_stream.set_time_base(time_base);
+    // The input container's codec tag may mean nothing in the output's
+    unsafe {
+        (*output_stream.parameters().as_mut_ptr()).codec_tag = 0;
+    }
+    copy_stream_metadata(&audio_stream, &mut output_stream, config);
+    log::info!(
+        "Copying {:?} audio (stream {})",
+        audio_stream.parameters().id(),
+        audio_stream.index()
+    );
+    Ok(Some(AudioOutput::Copy(AudioPassthrough {
+        input_index: audio_stream.index(),
+        output_index: output_stream.index(),
+        input_time_base: time_base,
+        origin,
+        end,
+    })))
+}
+
+/// The output's container tags: the input's with `copy_metadata`, then
+/// `metadata` over them
+fn set_container_metadata(
+    output_ctx: &mut ffmpeg::format::context::Output,
+    input_ctx: Option<&ffmpeg::format::context::Input>,
+    config: &ProcessingOptions,
+) {
+    let mut metadata = match input_ctx.filter(|_| config.copy_metadata) {
+        Some(input_ctx) => input_ctx.metadata().to_owned(),
+        None => ffmpeg::Dictionary::new(),
+    };
+    for (key, value) in &config.metadata {
+        metadata.set(key, value);
+    }
+    output_ctx.set_metadata(metadata);
+}
+
+/// With `copy_metadata`, the tags of `input` (e.g. its language) on `output`
+fn copy_stream_metadata(
+    input: &ffmpeg::Stream,
+    output: &mut ffmpeg::StreamMut,
+    config: &ProcessingOptions,
+) {
+    if config.copy_metadata {
+        output.set_metadata(input.metadata().to_owned());
+    }
+}
+
+/// Whether the output container can hold a `codec` stream
+fn container_accepts(output_ctx: &ffmpeg::format::context::Output, codec: codec::Id) -> bool {
+    let supported = unsafe {
+        ffmpeg::ffi::avformat_query_codec(
+            output_ctx.format().as_ptr(),
+            codec.into(),
+            ffmpeg::ffi::FF_COMPLIANCE_NORMAL as _,
+        )
+    };
+    supported == 1
+}
+
+/// Bits per pixel per frame for hardware encoders that only take a bitrate,
+/// around what x264 spends at CRF 18 on screen content
+const HARDWARE_BITS_PER_PIXEL: f64 = 0.1;
+
+/// `hvc1` as an FFmpeg codec tag (MKTAG, little-endian): parameter sets live
+/// in the sample description only, as Apple's players require
+const HVC1_TAG: u32 = u32::from_le_bytes(*b"hvc1");
+
+/// Encoders to try for `config`, failing early if the linked FFmpeg has none
+/// of them (e.g. libx265 is an optional FFmpeg dependency)
+fn encoder_candidates(
+    config: &ProcessingOptions,
+) -> Result<Vec<(EncoderBackend, &'static str)>, EngineError> {
+    let backend = if config.deterministic && config.encoder_backend != EncoderBackend::Software {
+        log::warn!(
+            "{:?} encoding is not bit-identical across runs, using software for a deterministic render",
+            config.encoder_backend
+        );
+        EncoderBackend::Software
+    } else {
+        config.encoder_backend
+    };
+    let candidates = backend.candidates(config.codec);
+    if candidates
+        .iter()
+        .all(|(_, name)| encoder::find_by_name(name).is_none())
+    {
+        return Err(EngineError::EncoderUnavailable(config.codec.encoder_name()));
+    }
+    Ok(candidates)
+}
+
+/// The opened video encoder and what frames need on the way in
+struct VideoEncoder {
+    encoder: encoder::Video,
+    backend: EncoderBackend,
+    /// Pixel format frames are converted to (NV12 for some hardware encoders)
+    format: Pixel,
+    /// Size frames are scaled to: the output size, or the source size for
+    /// GIF, whose palette stage scales
+    size: (u32, u32),
+    /// VA-API surfaces frames are copied to, since VA-API encoders only read
+    /// GPU memory
+    upload: Option<HwFrames>,
+    /// Quantizer RGBA frames go through before the GIF encoder
+    palette: Option<GifPalette>,
+    /// Turns upright frames back to the source's stored orientation when its
+    /// rotation metadata is kept
+    turn: Option<FrameTurn>,
+}
+
+impl VideoEncoder {
+    fn time_base(&self) -> Rational {
+        self.encoder.time_base()
+    }
+
+    fn send_frame(&mut self, frame: &VideoFrame) -> Result<(), EngineError> {
+        let turned;
+        let frame = match &mut self.turn {
+            Some(turn) => {
+                turned = turn.run(frame)?;
+                &turned
+            }
+            None => frame,
+        };
+        match (&mut self.palette, &self.upload) {
+            // Held until `finish`: the palette needs every frame first
+            (Some(palette), _) => palette.src.source().add(frame)?,
+            (None, Some(upload)) => self.encoder.send_frame(&upload.upload(frame)?)?,
+            (None, None) => self.encoder.send_frame(frame)?,
+        }
+        Ok(())
+    }
+
+    /// Encode whatever is still buffered and write the remaining packets
+    fn finish(
+        &mut self,
+        output_ctx: &mut ffmpeg::format::context::Output,
+    ) -> Result<(), EngineError> {
+        if let Some(palette) = &mut self.palette {
+            palette.src.source().flush()?;
+            while let Ok(quantized) = read_frame_from_sink(&mut palette.sink) {
+                self.encoder.send_frame(&quantized)?;
+                encode_and_write(&mut self.encoder, output_ctx)?;
+            }
+        }
+        self.encoder.send_eof()?;
+        encode_and_write(&mut self.encoder, output_ctx)?;
+        Ok(())
+    }
+}
+
+/// Rotation metadata copied from the source onto the output stream
+struct KeptRotation {
+    /// Display matrix as stored in the source's side data
+    matrix: [i32; 9],
+    /// What the matrix does, so the upright frames can be turned back
+    orientation: Orientation,
+}
+
+/// Applies an orientation to encoder-ready frames, one frame in, one out
+struct FrameTurn {
+    // Owns the filter contexts below
+    _graph: ffmpeg::filter::Graph,
+    src: ffmpeg::filter::Context,
+    sink: ffmpeg::filter::Context,
+}
+
+impl FrameTurn {
+    /// Turner for `width` x `height` frames in `format` by `orientation`
+    fn new(
+        orientation: Orientation,
+        (width, height): (u32, u32),
+        format: Pixel,
+        frame_rate: Rational,
+    ) -> Result<Self, EngineError> {
+        let mut graph = ffmpeg::filter::Graph::new();
+        let mut add = |filter: &'static str,
+                       name: &str,
+                       args: &str|
+         -> Result<ffmpeg::filter::Context, EngineError> {
+            let found =
+                ffmpeg::filter::find(filter).ok_or(EngineError::MissingComponent(filter))?;
+            Ok(graph.add(&found, name, args)?)
+        };
+
+        let mut src = add(
+            "buffer",
+            "buffer",
+            &format!(
+                "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect=1/1",
+                width,
+                height,
+                ffmpeg::ffi::AVPixelFormat::from(format) as i32,
+                frame_rate.denominator(),
+                frame_rate.numerator(),
+            ),
+        )?;
+        let mut turns = orientation
+            .filters()
+            .iter()
+            .enumerate()
+            .map(|(i, &(filter, args))| add(filter, &format!("turn{}", i), args))
+            .collect::<Result<Vec<_>, EngineError>>()?;
+        let mut sink = add("buffersink", "buffersink", "")?;
+        link_chain(&mut src, &mut turns, &mut sink);
+        graph.validate()?;
+
+        Ok(Self {
+            _graph: graph,
+            src,
+            sink,
+        })
+    }
+
+    fn run(&mut self, frame: &VideoFrame) -> Result<VideoFrame, EngineError> {
+        self.src.source().add(frame)?;
+        Ok(read_frame_from_sink(&mut self.sink)?)
+    }
+}
+
+/// Turns RGBA frames into paletted ones for the GIF encoder: scaled to
+/// `size`, then dithered (Floyd-Steinberg) onto one 256-color palette built
+/// from the whole clip, which avoids the flicker of per-frame palettes.
+/// palettegen only has that palette after the last frame, so all frames stay
+/// buffered until the source is flushed.
+struct GifPalette {
+    // Owns the filter contexts below
+    _graph: ffmpeg::filter::Graph,
+    src: ffmpeg::filter::Context,
+    sink: ffmpeg::filter::Context,
+    /// Output frame size
+    size: (u32, u32),
+}
+
+impl GifPalette {
+    /// Quantizer for `width` x `height` RGBA frames, scaled to `size`
+    fn new(
+        (width, height): (u32, u32),
+        size: (u32, u32),
+        frame_rate: Rational,
+    ) -> Result<Self, EngineError> {
+        let mut graph = ffmpeg::filter::Graph::new();
+        let mut add =
+            |filter: &'static str, args: &str| -> Result<ffmpeg::filter::Context, EngineError> {
+                let found =
+                    ffmpeg::filter::find(filter).ok_or(EngineError::MissingComponent(filter))?;
+                Ok(graph.add(&found, filter, args)?)
+            };
+
+        let mut src = add(
+            "buffer",
+            &format!(
+                "video_size={}x{}:pix_fmt=rgba:time_base={}/{}:pixel_aspect=1/1",
+                width,
+                height,
+                frame_rate.denominator(),
+                frame_rate.numerator(),
+            ),
+        )?;
+        let mut scale = add("scale", &format!("{}:{}:flags=lanczos", size.0, size.1))?;
+        let mut split = add("split", "2")?;
+        // The encoder has no use for a transparent entry, so all 256 are colors
+        let mut palettegen = add("palettegen", "reserve_transparent=0")?;
+        let mut paletteuse = add("paletteuse", "dither=floyd_steinberg")?;
+        let mut sink = add("buffersink", "")?;
+
+        // buffer -> scale -> split -> paletteuse -> buffersink, with the
+        // split's second copy feeding palettegen -> paletteuse's palette input
+        src.link(0, &mut scale, 0);
+        scale.link(0, &mut split, 0);
+        split.link(0, &mut paletteuse, 0);
+        split.link(1, &mut palettegen, 0);
+        palettegen.link(0, &mut paletteuse, 1);
+        paletteuse.link(0, &mut sink, 0);
+        graph.validate()?;
+
+        Ok(Self {
+            _graph: graph,
+            src,
+            sink,
+            size,
+        })
+    }
+}
+
+/// GIF frame size: the source size, or `max_width` wide with the same aspect
+/// ratio when the source is wider (0 = no limit)
+fn gif_size(width: u32, height: u32, max_width: u32) -> (u32, u32) {
+    if max_width == 0 || width <= max_width {
+        return (width, height);
+    }
+    let scaled =
+        (u64::from(height) * u64::from(max_width) + u64::from(width) / 2) / u64::from(width);
+    (max_width, (scaled as u32).max(1))
+}
+
+/// Options for the muxer's write_header that every pipeline shares
+fn muxer_options(config: &ProcessingOptions) -> ffmpeg::Dictionary<'static> {
+    let mut muxer_opts = ffmpeg::Dictionary::new();
+    if config.deterministic {
+        // Keep the muxer from stamping its version string into the file
+        muxer_opts.set("fflags", "+bitexact");
+    }
+    // Only ever set for MP4/MOV, which render_cursor_video checks
+    if let Some((_, flags)) = config.movflags() {
+        muxer_opts.set("movflags", flags);
+    }
+    muxer_opts
+}
+
+/// Open the first of `candidates` that works and add its output stream,
+/// falling back to the software encoder when hardware encoding is missing or
+/// fails to open. With `alpha` the encoder takes frames with an alpha
+/// channel (see `encoder_pixel_format`), or fails with `AlphaUnavailable`.
+#[allow(clippy::too_many_arguments)]
+fn create_video_encoder(
+    candidates: &[(EncoderBackend, &'static str)],
+    config: &ProcessingOptions,
+    width: u32,
+    height: u32,
+    frame_rate: Rational,
+    colors: Colorimetry,
+    kept_rotation: Option<&KeptRotation>,
+    alpha: bool,
+    output_ctx: &mut ffmpeg::format::context::Output,
+) -> Result<VideoEncoder, EngineError> {
+    let global_header = output_ctx
+        .format()
+        .flags()
+        .contains(ffmpeg::format::flag::Flags::GLOBAL_HEADER);
+    let output_size = config.output_size(width, height);
+    let palette = (config.codec == VideoCodec::Gif)
+        .then(|| {
+            let size = gif_size(output_size.0, output_size.1, config.gif.max_width);
+            GifPalette::new((width, height), size, frame_rate)
+        })
+        .transpose()?;
+    let size = palette.as_ref().map_or(output_size, |palette| palette.size);
+    let ten_bit = palette.is_none() && config.bit_depth == BitDepth::Ten;
+    // Frames are encoded as the source stores them when its rotation is kept
+    let turn_back = kept_rotation
+        .map(|kept| kept.orientation.inverse())
+        .filter(|turn| !turn.is_upright());
+    let encoded_size = turn_back.map_or(size, |turn| turn.upright_size(size));
+    let (backend, (codec, opened, format, upload)) =
+        select_encoder(candidates, |backend, name| {
+            let codec = encoder::find_by_name(name).ok_or(EngineError::EncoderUnavailable(name))?;
+            let (format, upload) = if palette.is_some() {
+                (Pixel::PAL8, None)
+            } else {
+                encoder_pixel_format(codec, encoded_size.0, encoded_size.1, ten_bit, alpha)?
+            };
+            if ten_bit && bits_per_channel(format) < 10 {
+                return Err(EngineError::TenBitUnavailable(name));
+            }
+            if alpha && !ALPHA_FORMATS.contains(&format) {
+                return Err(EngineError::AlphaUnavailable(name));
+            }
+            let opened = open_video_encoder(
+                codec,
+                backend,
+                config,
+                encoded_size,
+                upload.as_ref().map_or(format, |_| Pixel::VAAPI),
+                frame_rate,
+                colors,
+                global_header,
+                upload.as_ref(),
+            )?;
+            Ok((codec, opened, format, upload))
+        })?;
+    log::info!("Encoding with {} ({:?})", codec.name(), backend);
+
+    let hvc1 =
+        config.codec == VideoCodec::Hevc && matches!(output_ctx.format().name(), "mp4" | "mov");
+    // Added only now, so an encoder that failed to open leaves no stream behind
+    let mut output_stream = output_ctx.add_stream(Some(codec))?;
+    output_stream.set_parameters(&opened);
+    // MP4/MOV default HEVC to `hev1`, which QuickTime and iOS refuse to play
+    if hvc1 {
+        unsafe {
+            (*output_stream.parameters().as_mut_ptr()).codec_tag = HVC1_TAG;
+        }
+    }
+    if let Some(kept) = kept_rotation {
+        add_display_matrix(&mut output_stream, &kept.matrix)?;
+    }
+    // Matroska only reads the alpha side data of blocks when the track says so
+    if alpha && matches!(output_ctx.format().name(), "matroska" | "webm") {
+        let mut metadata = ffmpeg::Dictionary::new();
+        metadata.set("alpha_mode", "1");
+        output_stream.set_metadata(metadata);
+    }
+    let turn = turn_back
+        .map(|turn| FrameTurn::new(turn, size, format, frame_rate))
+        .transpose()?;
+
+    Ok(VideoEncoder {
+        encoder: opened,
+        backend,
+        // The palette stage takes the composited frames as they are
+        format: if palette.is_some() {
+            Pixel::RGBA
+        } else {
+            format
+        },
+        size: if palette.is_some() {
+            (width, height)
+        } else {
+            size
+        },
+        upload,
+        palette,
+        turn,
+    })
+}
+
+/// Attach `matrix` to `stream` as its display matrix side data
+fn add_display_matrix(
+    stream: &mut ffmpeg::format::stream::StreamMut,
+    matrix: &[i32; 9],
+) -> Result<(), EngineError> {
+    let bytes: Vec<u8> = matrix
+        .iter()
+        .flat_map(|value| value.to_ne_bytes())
+        .collect();
+    unsafe {
+        let par = stream.parameters().as_mut_ptr();
+        let side_data = ffmpeg::ffi::av_packet_side_data_new(
+            &mut (*par).coded_side_data,
+            &mut (*par).nb_coded_side_data,
+            ffmpeg::ffi::AVPacketSideDataType::AV_PKT_DATA_DISPLAYMATRIX,
+            bytes.len(),
+            0,
+        );
+        if side_data.is_null() {
+            return Err(FfmpegError::Other {
+                errno: ffmpeg::util::error::ENOMEM,
+            }
+            .into());
+        }
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), (*side_data).data, bytes.len());
+    }
+    Ok(())
+}
+
+/// YUV matrix, range, primaries and transfer of the source, carried through
+/// every conversion and onto the output stream
+#[derive(Debug, Clone, Copy)]
+struct Colorimetry {
+    space: color::Space,
+    range: color::Range,
+    primaries: color::Primaries,
+    transfer: color::TransferCharacteristic,
+}
+
+impl Colorimetry {
+    /// BT.709 limited range, what is assumed for untagged sources
+    fn assumed() -> Self {
+        Self {
+            space: color::Space::BT709,
+            range: color::Range::MPEG,
+            primaries: color::Primaries::BT709,
+            transfer: color::TransferCharacteristic::BT709,
+        }
+    }
+
+    /// What `decoder` reports; anything unspecified is taken as BT.709
+    /// limited range, what screen recorders and players assume for HD
+    fn of_source(decoder: &ffmpeg::decoder::Video) -> Self {
+        let mut assumed = Vec::new();
+        let space = match decoder.color_space() {
+            // RGB sources have no matrix; theirs is only needed for the output
+            color::Space::RGB => color::Space::BT709,
+            color::Space::Unspecified | color::Space::Reserved => {
+                assumed.push("matrix");
+                color::Space::BT709
+            }
+            space => space,
+        };
+        let range = match decoder.color_range() {
+            color::Range::Unspecified => match decoder.format() {
+                // The deprecated J formats are full range by definition
+                Pixel::YUVJ420P | Pixel::YUVJ422P | Pixel::YUVJ444P => color::Range::JPEG,
+                _ => {
+                    assumed.push("range");
+                    color::Range::MPEG
+                }
+            },
+            range => range,
+        };
+        let primaries = match decoder.color_primaries() {
+            color::Primaries::Unspecified
+            | color::Primaries::Reserved
+            | color::Primaries::Reserved0 => {
+                assumed.push("primaries");
+                color::Primaries::BT709
+            }
+            primaries => primaries,
+        };
+        let transfer = match decoder.color_transfer_characteristic() {
+            color::TransferCharacteristic::Unspecified
+            | color::TransferCharacteristic::Reserved
+            | color::TransferCharacteristic::Reserved0 => {
+                assumed.push("transfer");
+                color::TransferCharacteristic::BT709
+            }
+            transfer => transfer,
+        };
+        if !assumed.is_empty() {
+            log::info!(
+                "Source {} not tagged, assuming BT.709 limited range",
+                assumed.join("/")
+            );
+        }
+        Self {
+            space,
+            range,
+            primaries,
+            transfer,
+        }
+    }
+
+    fn full_range(&self) -> bool {
+        self.range == color::Range::JPEG
+    }
+
+    /// `in_color_matrix`/`out_color_matrix` value of the scale filter
+    fn matrix_name(&self) -> &'static str {
+        match self.space {
+            color::Space::FCC => "fcc",
+            color::Space::BT470BG => "bt470",
+            color::Space::SMPTE170M => "smpte170m",
+            color::Space::SMPTE240M => "smpte240m",
+            color::Space::BT2020NCL | color::Space::BT2020CL => "bt2020",
+            _ => "bt709",
+        }
+    }
+
+    /// `in_range`/`out_range` value of the scale filter
+    fn range_name(&self) -> &'static str {
+        if self.full_range() {
+            "full"
+        } else {
+            "limited"
+        }
+    }
+
+    /// Make `scaler` use this matrix on both sides, with the given ranges
+    /// (swscale assumes BT.601 limited range otherwise; RGB sides are full)
+    fn apply_to(
+        &self,
+        scaler: &mut ScalerContext,
+        full_range_input: bool,
+        full_range_output: bool,
+    ) {
+        let space: ffmpeg::ffi::AVColorSpace = self.space.into();
+        unsafe {
+            // Indexed like AVColorSpace; unknown values fall back to BT.601
+            let coefficients = ffmpeg::ffi::sws_getCoefficients(space as i32);
+            ffmpeg::ffi::sws_setColorspaceDetails(
+                scaler.as_mut_ptr(),
+                coefficients,
+                i32::from(full_range_input),
+                coefficients,
+                i32::from(full_range_output),
+                0,
+                1 << 16,
+                1 << 16,
+            );
+        }
+    }
+}
+
+/// swscale flag for `filter` (`Auto` should be resolved first)
+fn scaler_flags(filter: ScaleFilter) -> Flags {
+    match filter {
+        ScaleFilter::Auto | ScaleFilter::Bilinear => Flags::BILINEAR,
+        ScaleFilter::Bicubic => Flags::BICUBIC,
+        ScaleFilter::Lanczos => Flags::LANCZOS,
+        ScaleFilter::Area => Flags::AREA,
+    }
+}
+
+/// Formats with an alpha channel, best first: ProRes 4444's, then VP9's
+const ALPHA_FORMATS: [Pixel; 2] = [Pixel::YUVA444P10LE, Pixel::YUVA420P];
+
+/// Pixel format to convert frames to for `codec`: YUV420P where it is
+/// accepted, else NV12 (YUV420P10LE, else P010LE, for `ten_bit`). Encoders
+/// that only take VA-API surfaces get a surface pool to upload those to.
+/// For `alpha`, the first of `ALPHA_FORMATS` the encoder takes.
+fn encoder_pixel_format(
+    codec: ffmpeg::Codec,
+    width: u32,
+    height: u32,
+    ten_bit: bool,
+    alpha: bool,
+) -> Result<(Pixel, Option<HwFrames>), EngineError> {
+    let formats: Vec<Pixel> = codec
+        .video()?
+        .formats()
+        .map(|formats| formats.collect())
+        .unwrap_or_default();
+    // Encoders without one fall through, for the caller to refuse
+    if let Some(format) = ALPHA_FORMATS
+        .into_iter()
+        .find(|format| alpha && formats.contains(format))
+    {
+        return Ok((format, None));
+    }
+    let (planar, semi_planar) = if ten_bit {
+        (Pixel::YUV420P10LE, Pixel::P010LE)
+    } else {
+        (Pixel::YUV420P, Pixel::NV12)
+    };
+    if formats.is_empty() || formats.contains(&planar) {
+        Ok((planar, None))
+    } else if formats.contains(&semi_planar) {
+        Ok((semi_planar, None))
+    } else if formats.contains(&Pixel::VAAPI) {
+        Ok((
+            semi_planar,
+            Some(HwFrames::vaapi(width, height, semi_planar)?),
+        ))
+    } else {
+        Ok((formats[0], None))
+    }
+}
+
+/// Bits per channel of `format` (8 when unknown)
+fn bits_per_channel(format: Pixel) -> i32 {
+    format.descriptor().map_or(8, |descriptor| unsafe {
+        (*descriptor.as_ptr()).comp[0].depth
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn open_video_encoder(
+    codec: ffmpeg::Codec,
+    backend: EncoderBackend,
+    config: &ProcessingOptions,
+    (width, height): (u32, u32),
+    format: Pixel,
+    frame_rate: Rational,
+    colors: Colorimetry,
+    global_header: bool,
+    upload: Option<&HwFrames>,
+) -> Result<encoder::Video, EngineError> {
+    let mut encoder = codec::context::Context::new_with_codec(codec)
+        .encoder()
+        .video()?;
+
+    encoder.set_width(width);
+    encoder.set_height(height);
+    encoder.set_format(format);
+    encoder.set_frame_rate(Some(frame_rate));
+    // Set timebase to 1/FPS (standard for CFR)
+    encoder.set_time_base(Rational::new(
+        frame_rate.denominator(),
+        frame_rate.numerator(),
+    ));
+    // Tagged like the source, so players decode the colors the same way
+    encoder.set_colorspace(colors.space);
+    encoder.set_color_range(colors.range);
+    unsafe {
+        (*encoder.as_mut_ptr()).color_primaries = colors.primaries.into();
+        (*encoder.as_mut_ptr()).color_trc = colors.transfer.into();
+    }
+
+    if global_header {
+        encoder.set_flags(codec::flag::Flags::GLOBAL_HEADER);
+    }
+    if let Some(upload) = upload {
+        upload.attach(&mut encoder);
+    }
+
+    // Encoder Options (by default visually lossless for screen content)
+    let mut opts = ffmpeg::Dictionary::new();
+    let quality = &config.quality;
+    let kbps = |rate: u32| format!("{}k", rate);
+    let target = kbps(quality.bitrate_kbps);
+    let heuristic =
+        (width as f64 * height as f64 * f64::from(frame_rate) * HARDWARE_BITS_PER_PIXEL)
+            .round()
+            .to_string();
+    match (backend, config.codec) {
+        // Quality is settled by the palette stage; the encoder has no knobs
+        (_, VideoCodec::Gif) => {}
+        // Intra-only at the fixed quality of the profile, which prores_ks
+        // picks from the pixel format (HQ, or 4444 with alpha)
+        (_, VideoCodec::ProRes) => {}
+        (
+            EncoderBackend::Software | EncoderBackend::Auto,
+            codec @ (VideoCodec::H264 | VideoCodec::Hevc),
+        ) => {
+            opts.set("preset", quality.preset.as_str());
+            match quality.tune {
+                Some(tune) if codec == VideoCodec::H264 || tune.in_x265() => {
+                    opts.set("tune", tune.as_str())
+                }
+                Some(tune) => log::warn!("x265 has no {} tune, ignoring it", tune.as_str()),
+                None => {}
+            }
+            match quality.rate_control {
+                RateControl::Crf => {
+                    let default = if codec == VideoCodec::H264 { 18 } else { 20 };
+                    opts.set("crf", &quality.crf.unwrap_or(default).to_string());
+                }
+                RateControl::Abr => opts.set("b", &target),
+                RateControl::Cbr => {
+                    opts.set("b", &target);
+                    if codec == VideoCodec::H264 {
+                        opts.set("nal-hrd", "cbr");
+                    }
+                }
+            }
+        }
+        (EncoderBackend::Software | EncoderBackend::Auto, VideoCodec::Vp9) => {
+            match quality.rate_control {
+                RateControl::Crf => {
+                    opts.set("crf", &quality.crf.unwrap_or(30).to_string());
+                    // b 0 = pure constant quality; a bitrate makes it the cap
+                    // (constrained quality)
+                    match quality.vbv() {
+                        Some((peak, _)) => opts.set("b", &kbps(peak)),
+                        None => opts.set("b", "0"),
+                    }
+                }
+                RateControl::Abr | RateControl::Cbr => opts.set("b", &target),
+            }
+            opts.set("deadline", config.vp9_deadline.as_str());
+            opts.set("cpu-used", &config.vp9_cpu_used.to_string());
+            opts.set("row-mt", "1");
+        }
+        // Constant quality on NVENC's scale, by default close to CRF 18
+        (EncoderBackend::Nvenc, _) => {
+            opts.set("preset", "p5");
+            match quality.rate_control {
+                RateControl::Crf => {
+                    opts.set("rc", "vbr");
+                    opts.set("cq", &quality.crf.unwrap_or(19).to_string());
+                    opts.set("b", "0");
+                }
+                RateControl::Abr => {
+                    opts.set("rc", "vbr");
+                    opts.set("b", &target);
+                }
+                RateControl::Cbr => {
+                    opts.set("rc", "cbr");
+                    opts.set("b", &target);
+                }
+            }
+        }
+        // No CRF equivalent that every device supports, so a bitrate
+        (EncoderBackend::VideoToolbox, _) => {
+            match quality.rate_control {
+                RateControl::Crf => opts.set("b", &heuristic),
+                RateControl::Abr | RateControl::Cbr => opts.set("b", &target),
+            }
+            opts.set("realtime", "0");
+        }
+        (EncoderBackend::Vaapi, _) => match (quality.rate_control, quality.crf) {
+            (RateControl::Crf, Some(qp)) => {
+                opts.set("rc_mode", "CQP");
+                opts.set("qp", &qp.to_string());
+            }
+            (RateControl::Crf, None) => {
+                opts.set("rc_mode", "VBR");
+                opts.set("b", &heuristic);
+            }
+            (RateControl::Abr, _) => {
+                opts.set("rc_mode", "VBR");
+                opts.set("b", &target);
+            }
+            (RateControl::Cbr, _) => {
+                opts.set("rc_mode", "CBR");
+                opts.set("b", &target);
+            }
+        },
+    }
+    // 10-bit frames need the codec's 10-bit profile (VP9 and VA-API, whose
+    // frames arrive as surfaces, pick theirs from the frames)
+    if bits_per_channel(format) == 10 {
+        match config.codec {
+            VideoCodec::H264 => opts.set("profile", "high10"),
+            VideoCodec::Hevc => opts.set("profile", "main10"),
+            _ => {}
+        }
+    }
+    // Peak rate: the cap for CRF/ABR, or the fixed rate for CBR
+    let rate_controlled = !matches!(config.codec, VideoCodec::Gif | VideoCodec::ProRes);
+    if let Some((peak, bufsize)) = quality.vbv().filter(|_| rate_controlled) {
+        opts.set("maxrate", &kbps(peak));
+        opts.set("bufsize", &kbps(bufsize));
+        if quality.rate_control == RateControl::Cbr {
+            opts.set("minrate", &kbps(peak));
+        }
+    }
+
+    // Deterministic mode: threaded encoders split work depending on core
+    // count and scheduling, so pin everything to one thread and drop the
+    // libavcodec version tag. No creation_time is set on the output, so the
+    // muxers write none. (Hardware encoders are never picked for it.)
+    //
+    // Cost: the encoder gives up frame and slice threading, which is most of
+    // its speed on a multi-core machine (libx264 and libx265 otherwise run
+    // about one thread per core, libvpx-vp9 one per tile row). Encoding time
+    // grows roughly with the core count: expect 4-8x slower exports on an
+    // 8-core machine, while decoding and compositing keep their threads. The
+    // bit-exact flags and the missing metadata cost nothing.
+    if config.deterministic {
+        opts.set("threads", "1");
+        opts.set("flags", "+bitexact");
+        match config.codec {
+            VideoCodec::H264 => opts.set("x264-params", "threads=1:sliced-threads=0"),
+            VideoCodec::Hevc => opts.set("x265-params", "pools=none:frame-threads=1"),
+            VideoCodec::Vp9 => opts.set("row-mt", "0"),
+            VideoCodec::Gif | VideoCodec::ProRes => {}
+        }
+    }
+
+    Ok(encoder.open_with(opts)?)
+}
+
+/// A VA-API device and a pool of surfaces on it
+struct HwFrames {
+    device: *mut ffmpeg::ffi::AVBufferRef,
+    frames: *mut ffmpeg::ffi::AVBufferRef,
+}
+
+impl HwFrames {
+    /// Surfaces of `width` x `height` `sw_format` pixels on the default
+    /// VA-API device
+    fn vaapi(width: u32, height: u32, sw_format: Pixel) -> Result<Self, EngineError> {
+        use ffmpeg::ffi::*;
+        // SAFETY: every pointer is checked before use, and `Drop` releases
+        // whatever was allocated when an early return leaves `pool` behind
+        unsafe {
+            let mut pool = Self {
+                device: std::ptr::null_mut(),
+                frames: std::ptr::null_mut(),
+            };
+            let ret = av_hwdevice_ctx_create(
+                &mut pool.device,
+                AVHWDeviceType::AV_HWDEVICE_TYPE_VAAPI,
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                0,
+            );
+            if ret < 0 {
+                return Err(FfmpegError::from(ret).into());
+            }
+            pool.frames = av_hwframe_ctx_alloc(pool.device);
+            if pool.frames.is_null() {
+                return Err(EngineError::MissingComponent("VA-API frame pool"));
+            }
+            let ctx = (*pool.frames).data as *mut AVHWFramesContext;
+            (*ctx).format = AVPixelFormat::AV_PIX_FMT_VAAPI;
+            (*ctx).sw_format = sw_format.into();
+            (*ctx).width = width as i32;
+            (*ctx).height = height as i32;
+            (*ctx).initial_pool_size = 20;
+            let ret = av_hwframe_ctx_init(pool.frames);
+            if ret < 0 {
+                return Err(FfmpegError::from(ret).into());
+            }
+            Ok(pool)
+        }
+    }
+
+    /// Have `encoder` take its frames from this pool
+    fn attach(&self, encoder: &mut encoder::video::Video) {
+        // SAFETY: the encoder gets its own reference, released when it is freed
+        unsafe {
+            (*encoder.as_mut_ptr()).hw_frames_ctx = ffmpeg::ffi::av_buffer_ref(self.frames);
+        }
+    }
+
+    /// Copy `frame` into a surface from the pool
+    fn upload(&self, frame: &VideoFrame) -> Result<VideoFrame, FfmpegError> {
+        let mut surface = VideoFrame::empty();
+        // SAFETY: `surface` is a fresh frame, filled by the pool, and
+        // `frame` has the pool's software format and size
+        unsafe {
+            let ret = ffmpeg::ffi::av_hwframe_get_buffer(self.frames, surface.as_mut_ptr(), 0);
+            if ret < 0 {
+                return Err(FfmpegError::from(ret));
+            }
+            let ret =
+                ffmpeg::ffi::av_hwframe_transfer_data(surface.as_mut_ptr(), frame.as_ptr(), 0);
+            if ret < 0 {
+                return Err(FfmpegError::from(ret));
+            }
+        }
+        surface.set_pts(frame.pts());
+        Ok(surface)
+    }
+}
+
+impl Drop for HwFrames {
+    fn drop(&mut self) {
+        // SAFETY: both are null or references this struct owns
+        unsafe {
+            ffmpeg::ffi::av_buffer_unref(&mut self.frames);
+            ffmpeg::ffi::av_buffer_unref(&mut self.device);
+        }
+    }
+}
+
+fn encode_and_write(
+    encoder: &mut encoder::Video,
+    output_ctx: &mut ffmpeg::format::context::Output,
+) -> Result<(), FfmpegError> {
+    let mut packet = Packet::empty();
+    while encoder.receive_packet(&mut packet).is_ok() {
+        packet.set_stream(0);
+
+        // Rescale timestamps from encoder time_base to output stream time_base
+        let encoder_tb = encoder.time_base();
+        let stream_tb = output_ctx
+            .stream(0)
+            .map(|s| s.time_base())
+            .unwrap_or(encoder_tb);
+        packet.rescale_ts(encoder_tb, stream_tb);
+
+        packet.write_interleaved(output_ctx)?;
+    }
+    Ok(())
+}
+
+fn build_cursor_lookup(cursor_points: &[CPoint]) -> Vec<(f64, f32, f32)> {
+    // Smoothed timestamps are already video time (frame k at k * 1000 / fps);
+    // rebasing them onto the first point would shift the frame grid
+    cursor_points
+        .iter()
+        .map(|p| (p.timestamp_ms, p.x, p.y))
+        .collect()
+}
+
+/// Pull spline overshoot at the screen edges back inside the frame
+fn confine_to_frame(lookup: &mut [(f64, f32, f32)], bounds: FrameBounds, width: u32, height: u32) {
+    if bounds == FrameBounds::Off {
+        return;
+    }
+    for (_, x, y) in lookup.iter_mut() {
+        (*x, *y) = bounds.apply(*x, *y, width, height);
+    }
+}
+
+/// Direction of travel (radians, clockwise from +x) over each lookup segment.
+///
+/// The heading only follows the path while the cursor is moving; below the
+/// stopped speed it holds, and it resumes only past the (higher) moving speed,
+/// so jitter at rest can't spin the sprite.
+fn build_heading_lookup(lookup: &[(f64, f32, f32)]) -> Vec<f32> {
+    let mut heading = 0.0;
+    let mut moving = false;
+    lookup
+        .windows(2)
+        .map(|w| {
+            let (t0, x0, y0) = w[0];
+            let (t1, x1, y1) = w[1];
+            let (dx, dy) = (x1 - x0, y1 - y0);
+            let dt_sec = ((t1 - t0) / 1000.0) as f32;
+            if dt_sec > 0.0 {
+                let speed = dx.hypot(dy) / dt_sec;
+                moving = if moving {
+                    speed >= HEADING_STOPPED_SPEED_PX_PER_SEC
+                } else {
+                    speed > HEADING_MOVING_SPEED_PX_PER_SEC
+                };
+            }
+            if moving {
+                heading = dy.atan2(dx);
+            }
+            heading
+        })
+        .collect()
+}
+
+/// Sprite rotation at `timestamp_ms` (0 without headings); held past the path ends
+fn heading_at(lookup: &[(f64, f32, f32)], headings: &[f32], timestamp_ms: f64) -> f32 {
+    if headings.is_empty() {
+        return 0.0;
+    }
+    // Segment i runs from lookup[i] to lookup[i + 1]
+    let segment = lookup.partition_point(|p| p.0 <= timestamp_ms);
+    headings[segment.saturating_sub(1).min(headings.len() - 1)]
+}
+
+/// True when the lookup has to hold an endpoint because `timestamp_ms` is out of range
+fn is_outside_cursor_path(lookup: &[(f64, f32, f32)], timestamp_ms: f64) -> bool {
+    match (lookup.first(), lookup.last()) {
+        (Some(first), Some(last)) => timestamp_ms < first.0 || timestamp_ms > last.0,
+        _ => true,
+    }
+}
+
+/// Cursor position at `timestamp_ms`; NaN when `tail` hides the cursor
+fn interpolate_cursor_position(
+    lookup: &[(f64, f32, f32)],
+    timestamp_ms: f64,
+    tail: TailBehavior,
+) -> (f32, f32) {
+    if lookup.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    // Smoothed paths sit on the output frame grid, so a frame's sample is
+    // found by index; anything off the grid falls back to a search
+    if let [(t0, ..), (t1, ..), ..] = lookup {
+        let step = t1 - t0;
+        if step > 0.0 {
+            let k = ((timestamp_ms - t0) / step).round();
+            if k >= 0.0 && (k as usize) < lookup.len() {
+                let (t, x, y) = lookup[k as usize];
+                if (t - timestamp_ms).abs() < 1e-6 {
+                    return (x, y);
+                }
+            }
+        }
+    }
+
+    let idx = match lookup.binary_search_by(|p| p.0.total_cmp(&timestamp_ms)) {
+        Ok(i) => i,
+        Err(i) => i,
+    };
+
+    if idx == 0 || idx >= lookup.len() {
+        return position_past_end(lookup, timestamp_ms, tail);
+    }
+
+    let (t0, x0, y0) = lookup[idx - 1];
+    let (t1, x1, y1) = lookup[idx];
+
+    let dt = t1 - t0;
+    if dt < 1e-6 {
+        return (x1, y1);
+    }
+
+    let t = ((timestamp_ms - t0) / dt) as f32;
+    (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t)
+}
+
+/// Position at a time before the first or after the last lookup entry
+fn position_past_end(
+    lookup: &[(f64, f32, f32)],
+    timestamp_ms: f64,
+    tail: TailBehavior,
+) -> (f32, f32) {
+    // `inner` is the neighbour of the end point, giving the end velocity
+    let (end, inner) = if timestamp_ms <= lookup[0].0 {
+        (lookup[0], lookup.get(1))
+    } else {
+        (
+            lookup[lookup.len() - 1],
+            lookup.len().checked_sub(2).map(|i| &lookup[i]),
+        )
+    };
+    let overshoot_ms = timestamp_ms - end.0;
+
+    match tail {
+        TailBehavior::HideAfterMs(ms) if overshoot_ms.abs() > ms as f64 => (f32::NAN, f32::NAN),
+        TailBehavior::ExtrapolateMs(ms) => {
+            let Some(&(t_inner, x_inner, y_inner)) = inner else {
+                return (end.1, end.2);
+            };
+            let dt = end.0 - t_inner;
+            if dt.abs() < 1e-6 {
+                return (end.1, end.2);
+            }
+            let s = (overshoot_ms.clamp(-(ms as f64), ms as f64) / dt) as f32;
+            (end.1 + (end.1 - x_inner) * s, end.2 + (end.2 - y_inner) * s)
+        }
+        _ => (end.1, end.2),
+    }
 }
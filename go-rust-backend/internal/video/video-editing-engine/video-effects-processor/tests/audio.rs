@@ -0,0 +1,116 @@
+// audio.rs - Source audio the container cannot hold is re-encoded to AAC,
+// whole and in step with the video (audio_passthrough.rs covers the copy)
+//
+// Inputs are generated with the ffmpeg CLI and outputs are read back with
+// ffprobe; the tests are skipped when either is missing.
+mod common;
+
+use common::{
+    drag, ffmpeg_command, generate, probe_lines, tools_available, white_sprite, work_dir,
+};
+use std::path::Path;
+use video_effects_processor::engine::{self, ProcessingOptions, Progress};
+
+const DURATION_S: f64 = 2.0;
+/// Largest accepted difference between input and output audio durations
+/// (one AAC frame at 48 kHz is ~21 ms)
+const TOLERANCE_S: f64 = 0.05;
+
+/// One audio stream as ffprobe reports it
+#[derive(Debug)]
+struct AudioStream {
+    codec: String,
+    channels: u32,
+    duration_s: f64,
+}
+
+/// A 320x240 test pattern with a 440 Hz tone, both `DURATION_S` long, the
+/// tone encoded with `audio_args`
+fn generate_input(path: &Path, audio_args: &[&str]) {
+    let duration = DURATION_S.to_string();
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=320x240:rate=30"])
+            .args(["-f", "lavfi", "-i", "sine=frequency=440:sample_rate=48000"])
+            .args(["-t", &duration])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+            .args(audio_args),
+        path,
+    );
+}
+
+fn audio_streams(path: &Path) -> Vec<AudioStream> {
+    probe_lines(path, "a", "stream=codec_name,channels,duration")
+        .iter()
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            AudioStream {
+                codec: fields[0].to_string(),
+                channels: fields[1].parse().unwrap(),
+                duration_s: fields[2].parse().unwrap(),
+            }
+        })
+        .collect()
+}
+
+/// Render `input` into `output` with a cursor crossing the frame
+fn render(input: &Path, output: &Path) {
+    let sprite = white_sprite(8);
+    let points = drag((40.0, 40.0), (280.0, 200.0), DURATION_S * 1000.0);
+    engine::process_video(
+        input,
+        output,
+        &sprite,
+        &points,
+        &ProcessingOptions::default(),
+        |_: Progress| {},
+    )
+    .unwrap();
+}
+
+#[test]
+fn pcm_audio_is_reencoded_to_stereo_aac() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("audio-transcode");
+    let (input, output) = (dir.join("input.mkv"), dir.join("output.mp4"));
+    // A mono microphone track, as screen recorders write it
+    generate_input(&input, &["-c:a", "pcm_s16le", "-ac", "1"]);
+    render(&input, &output);
+
+    let actual = audio_streams(&output);
+    assert_eq!(actual.len(), 1, "output audio streams: {:?}", actual);
+    assert_eq!(actual[0].codec, "aac");
+    assert_eq!(actual[0].channels, 2);
+    assert!(
+        (actual[0].duration_s - DURATION_S).abs() <= TOLERANCE_S,
+        "audio lasts {} s, the source's {} s",
+        actual[0].duration_s,
+        DURATION_S
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn surround_pcm_is_downmixed_to_stereo_aac() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("audio-downmix");
+    let (input, output) = (dir.join("input.mkv"), dir.join("output.mp4"));
+    generate_input(&input, &["-c:a", "pcm_s24le", "-ac", "6"]);
+    render(&input, &output);
+
+    let actual = audio_streams(&output);
+    assert_eq!(actual.len(), 1, "output audio streams: {:?}", actual);
+    assert_eq!(actual[0].codec, "aac");
+    assert_eq!(actual[0].channels, 2);
+    assert!(
+        (actual[0].duration_s - DURATION_S).abs() <= TOLERANCE_S,
+        "audio lasts {} s, the source's {} s",
+        actual[0].duration_s,
+        DURATION_S
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,71 @@
+// audio_passthrough.rs - The source's audio comes through a render whole and
+// in step with the video
+//
+// The input is generated with the ffmpeg CLI and the output is read back with
+// ffprobe; the test is skipped when either is missing.
+mod common;
+
+use common::{
+    drag, ffmpeg_command, generate, probe_lines, tools_available, white_sprite, work_dir,
+};
+use std::path::Path;
+use video_effects_processor::engine::{self, ProcessingOptions, Progress};
+
+const DURATION_S: f64 = 2.0;
+/// Largest accepted difference between input and output audio durations
+/// (one AAC frame at 48 kHz is ~21 ms)
+const TOLERANCE_S: f64 = 0.05;
+
+/// A 320x240 test pattern with a 440 Hz tone, both `DURATION_S` long
+fn generate_input(path: &Path) {
+    let duration = DURATION_S.to_string();
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=320x240:rate=30"])
+            .args(["-f", "lavfi", "-i", "sine=frequency=440:sample_rate=48000"])
+            .args(["-t", &duration])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-c:a", "aac"]),
+        path,
+    );
+}
+
+/// Duration in seconds of each audio stream of `path`
+fn audio_durations(path: &Path) -> Vec<f64> {
+    probe_lines(path, "a", "stream=duration")
+        .iter()
+        .map(|line| line.parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn output_keeps_the_source_audio() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("audio");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+
+    let sprite = white_sprite(8);
+    let points = drag((40.0, 40.0), (280.0, 200.0), DURATION_S * 1000.0);
+    engine::process_video(
+        &input,
+        &output,
+        &sprite,
+        &points,
+        &ProcessingOptions::default(),
+        |_: Progress| {},
+    )
+    .unwrap();
+
+    let expected = audio_durations(&input);
+    let actual = audio_durations(&output);
+    assert_eq!(actual.len(), 1, "output audio streams: {:?}", actual);
+    assert!(
+        (actual[0] - expected[0]).abs() <= TOLERANCE_S,
+        "audio lasts {} s, the source's {} s",
+        actual[0],
+        expected[0]
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
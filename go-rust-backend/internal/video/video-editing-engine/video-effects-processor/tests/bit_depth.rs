@@ -0,0 +1,173 @@
+// bit_depth.rs - The cursor blends the same at 8 and 10 bits, and 10-bit
+// sources export at either depth
+//
+// The export tests generate a 10-bit input with the ffmpeg CLI and read the
+// output back with ffprobe and ffmpeg; they are skipped when either is
+// missing or the CLI's libx264 has no 10-bit support.
+mod common;
+
+use common::{ffmpeg_command, probe_fields, resting, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use std::process::Command;
+use video_effects_processor::engine::{
+    self, composite_cursor_yuv420, BitDepth, CursorStyle, ProcessingOptions, Progress, Yuv420Frame,
+    YuvSample, YuvSprite,
+};
+
+const SIZE: u32 = 16;
+
+/// Planes of a flat `SIZE` x `SIZE` 4:2:0 frame
+fn flat_planes<S: YuvSample>(luma: S, chroma: S) -> [Vec<S>; 3] {
+    let (full, half) = ((SIZE * SIZE) as usize, (SIZE * SIZE / 4) as usize);
+    [vec![luma; full], vec![chroma; half], vec![chroma; half]]
+}
+
+/// A 4x4 white sprite at (4, 4) with `opacity`, blended onto `planes`
+fn blend_white<S: YuvSample>(planes: &mut [Vec<S>; 3], bit_depth: u32, opacity: f32) {
+    let sprite = YuvSprite::new(&white_sprite(4));
+    let [y, u, v] = planes;
+    let mut frame = Yuv420Frame {
+        planes: [y.as_mut_slice(), u.as_mut_slice(), v.as_mut_slice()],
+        strides: [SIZE as usize, SIZE as usize / 2, SIZE as usize / 2],
+        width: SIZE,
+        height: SIZE,
+        bit_depth,
+    };
+    let style = CursorStyle {
+        opacity,
+        ..CursorStyle::default()
+    };
+    let clipped = composite_cursor_yuv420(&mut frame, &sprite, 4.0, 4.0, &style);
+    assert!(!clipped);
+}
+
+#[test]
+fn both_depths_blend_the_same_cursor() {
+    let mut eight = flat_planes(128u8, 128u8);
+    let mut ten = flat_planes(512u16, 512u16);
+    blend_white(&mut eight, 8, 0.5);
+    blend_white(&mut ten, 10, 0.5);
+
+    // White lands on the luma it has at each depth
+    assert_eq!(eight[0][4 * SIZE as usize + 4], 181);
+    assert_eq!(ten[0][4 * SIZE as usize + 4], 726);
+    for (plane, (a, b)) in eight.iter().zip(&ten).enumerate() {
+        for (i, (&a, &b)) in a.iter().zip(b).enumerate() {
+            assert!(
+                (f32::from(b) - 4.0 * f32::from(a)).abs() <= 2.0,
+                "plane {} sample {}: {} at 8 bits, {} at 10",
+                plane,
+                i,
+                a,
+                b
+            );
+        }
+    }
+}
+
+#[test]
+fn ten_bit_blend_keeps_the_low_bits() {
+    // 601 and the blends with it fall between 8-bit steps
+    let mut ten = flat_planes(601u16, 601u16);
+    blend_white(&mut ten, 10, 0.25);
+    let [y, u, v] = &ten;
+    assert_eq!(y[4 * SIZE as usize + 4], 686);
+    assert_eq!(u[2 * SIZE as usize / 2 + 2], 579);
+    assert_eq!(v[2 * SIZE as usize / 2 + 2], 579);
+    // Outside the sprite nothing moves
+    assert_eq!(y[0], 601);
+    assert_eq!(u[0], 601);
+}
+
+/// One second of a flat blue-green 320x240 frame in 10-bit H.264, or false
+/// when the CLI's libx264 cannot encode 10-bit
+fn generate_input(path: &Path) -> bool {
+    let status = ffmpeg_command()
+        .args(["-f", "lavfi", "-i", "color=c=0x20a0e0:size=320x240:rate=30"])
+        .args(["-t", "1"])
+        .args(["-c:v", "libx264", "-pix_fmt", "yuv420p10le"])
+        .arg(path)
+        .status()
+        .unwrap();
+    if !status.success() {
+        eprintln!("ffmpeg cannot encode 10-bit H.264, skipping");
+    }
+    status.success()
+}
+
+fn render(input: &Path, output: &Path, opts: &ProcessingOptions) {
+    let sprite = white_sprite(8);
+    // Resting in a corner, away from the sampled center
+    let points = resting(4.0, 4.0);
+    engine::process_video(input, output, &sprite, &points, opts, |_: Progress| {}).unwrap();
+}
+
+/// RGB of the center pixel of the first frame, as ffmpeg decodes it
+fn center_pixel(path: &Path) -> [u8; 3] {
+    let out = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "ffmpeg could not decode {}",
+        path.display()
+    );
+    let at = (120 * 320 + 160) * 3;
+    [out.stdout[at], out.stdout[at + 1], out.stdout[at + 2]]
+}
+
+#[test]
+fn ten_bit_export_uses_a_ten_bit_profile() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("bit-depth-10");
+    let input = dir.join("input.mp4");
+    if !generate_input(&input) {
+        return;
+    }
+    let ten_bit = ProcessingOptions {
+        bit_depth: BitDepth::Ten,
+        ..ProcessingOptions::default()
+    };
+    // Composited in 10-bit YUV, then in RGBA for an option that needs it
+    let rgba = ProcessingOptions {
+        gamma_correct_blend: true,
+        ..ten_bit.clone()
+    };
+    for (name, opts) in [("yuv", ten_bit), ("rgba", rgba)] {
+        let output = dir.join(format!("{}.mp4", name));
+        render(&input, &output, &opts);
+        let format = probe_fields(&output, "v:0", "stream=pix_fmt,profile");
+        assert_eq!(format["pix_fmt"], "yuv420p10le", "{}: {:?}", name, format);
+        assert_eq!(format["profile"], "High 10", "{}: {:?}", name, format);
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn ten_bit_source_exports_at_eight_bits_by_default() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("bit-depth-8");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    if !generate_input(&input) {
+        return;
+    }
+    render(&input, &output, &ProcessingOptions::default());
+
+    let format = probe_fields(&output, "v:0", "stream=pix_fmt,profile");
+    assert_eq!(format["pix_fmt"], "yuv420p", "{:?}", format);
+    let (before, after) = (center_pixel(&input), center_pixel(&output));
+    assert!(
+        before.iter().zip(after).all(|(&b, a)| b.abs_diff(a) <= 6),
+        "{:?} became {:?}",
+        before,
+        after
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,88 @@
+// builder.rs - Points appended to a CursorPathBuilder in chunks render like
+// an array, and freeing the builder mid-render leaves the render its points
+//
+// The input is generated with the ffmpeg CLI and the output is read back
+// with ffprobe; the test is skipped when either is missing.
+#![cfg(unix)]
+
+mod common;
+
+use common::{drag, ffmpeg_command, generate, probe, tools_available, work_dir};
+use std::ffi::{c_void, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use video_effects_processor::{
+    cursor_path_builder_append, cursor_path_builder_free, cursor_path_builder_len,
+    cursor_path_builder_new, process_video_with_cursor_builder, CursorPathBuilder,
+    ProcessingCallbacks, ProcessingResult, VideoProcessingConfigV2,
+};
+
+fn c_path(path: &Path) -> CString {
+    CString::new(path.as_os_str().as_bytes()).unwrap()
+}
+
+/// Frees the builder in `user_data` on the first progress report
+extern "C" fn free_on_progress(user_data: *mut c_void, _fraction: f32) {
+    let builder = unsafe { &*(user_data as *const AtomicPtr<CursorPathBuilder>) };
+    let builder = builder.swap(std::ptr::null_mut(), Ordering::AcqRel);
+    unsafe { cursor_path_builder_free(builder) };
+}
+
+#[test]
+fn chunked_points_render_after_the_builder_is_freed() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("builder");
+    let input = dir.join("input.mp4");
+    let output = dir.join("output.mp4");
+    let sprite = dir.join("cursor.png");
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=160x120:rate=10"])
+            .args(["-t", "1"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        &input,
+    );
+    image::RgbaImage::from_pixel(8, 8, image::Rgba([255; 4]))
+        .save(&sprite)
+        .unwrap();
+
+    let points = drag((20.0, 20.0), (140.0, 100.0), 1000.0);
+    let builder = cursor_path_builder_new();
+    for chunk in points.chunks(2) {
+        let code = unsafe { cursor_path_builder_append(builder, chunk.as_ptr(), chunk.len()) };
+        assert_eq!(code, 0);
+    }
+    assert_eq!(unsafe { cursor_path_builder_len(builder) }, points.len());
+
+    let shared = AtomicPtr::new(builder as *mut CursorPathBuilder);
+    let callbacks = ProcessingCallbacks {
+        progress: Some(free_on_progress),
+        user_data: &shared as *const _ as *mut c_void,
+        ..ProcessingCallbacks::default()
+    };
+    let config = VideoProcessingConfigV2 {
+        frame_rate: 10,
+        ..VideoProcessingConfigV2::default()
+    };
+    let mut result = ProcessingResult::default();
+    let code = unsafe {
+        process_video_with_cursor_builder(
+            c_path(&input).as_ptr(),
+            c_path(&output).as_ptr(),
+            c_path(&sprite).as_ptr(),
+            builder,
+            &config,
+            &callbacks,
+            &mut result,
+        )
+    };
+    assert_eq!(code, 0);
+    // Freed during the render
+    assert!(shared.load(Ordering::Acquire).is_null());
+    assert_eq!(result.frames_encoded, 10);
+    assert_eq!(probe(&output, "v:0", "stream=codec_name"), "h264");
+    std::fs::remove_dir_all(&dir).ok();
+}
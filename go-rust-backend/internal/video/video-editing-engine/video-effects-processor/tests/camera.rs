@@ -0,0 +1,100 @@
+// camera.rs - Follow-cursor zoom keeps the cursor inside the rendered frame's margins
+use video_effects_processor::engine::{AutoZoom, CPoint, CameraPlan};
+
+const WIDTH: u32 = 1920;
+const HEIGHT: u32 = 1080;
+const FPS: f64 = 60.0;
+
+/// 8 s on the 60 fps grid: slow circles, a fast flick across the screen and
+/// back, then a short rest, all at least 200 px from the frame edges
+fn sweeping_path() -> Vec<CPoint> {
+    (0..(8.0 * FPS) as usize)
+        .map(|k| {
+            let t = k as f64 / FPS;
+            let (x, y) = if t < 4.0 {
+                let a = t * std::f64::consts::PI;
+                (960.0 + 600.0 * a.cos(), 540.0 + 300.0 * (1.3 * a).sin())
+            } else if t < 6.0 {
+                // 1500 px in a quarter second, each way
+                let s = ((t - 4.0) * 4.0).min(1.0) - ((t - 5.0) * 4.0).clamp(0.0, 1.0);
+                (210.0 + 1500.0 * s, 220.0 + 640.0 * s)
+            } else {
+                (210.0, 220.0)
+            };
+            CPoint {
+                x: x as f32,
+                y: y as f32,
+                timestamp_ms: k as f64 * 1000.0 / FPS,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn cursor_stays_inside_margins() {
+    let path = sweeping_path();
+    for zoom in [1.5, 2.0, 3.0] {
+        let settings = AutoZoom {
+            zoom,
+            margin_x_px: 240.0,
+            margin_y_px: 160.0,
+        };
+        let plan = CameraPlan::new(&path, WIDTH, HEIGHT, &settings);
+        for p in &path {
+            let crop = plan.crop_at(p.timestamp_ms);
+            assert!(crop.x >= 0.0 && crop.x + crop.width <= WIDTH as f32 + 1e-3);
+            assert!(crop.y >= 0.0 && crop.y + crop.height <= HEIGHT as f32 + 1e-3);
+
+            let (x, y) = plan.to_output(p.timestamp_ms, p.x, p.y);
+            let inside = (settings.margin_x_px - 1e-2..=WIDTH as f32 - settings.margin_x_px + 1e-2)
+                .contains(&x)
+                && (settings.margin_y_px - 1e-2..=HEIGHT as f32 - settings.margin_y_px + 1e-2)
+                    .contains(&y);
+            assert!(
+                inside,
+                "zoom {}: cursor at ({}, {}) drawn at ({}, {}) at {} ms",
+                zoom, p.x, p.y, x, y, p.timestamp_ms
+            );
+        }
+    }
+}
+
+#[test]
+fn camera_glides_and_settles() {
+    let path = sweeping_path();
+    let plan = CameraPlan::new(&path, WIDTH, HEIGHT, &AutoZoom::default());
+    let step = |a: (f32, f32), b: (f32, f32)| (b.0 - a.0).hypot(b.1 - a.1);
+    let crop = |p: &CPoint| {
+        let crop = plan.crop_at(p.timestamp_ms);
+        (crop.x, crop.y)
+    };
+    // No pan outruns the cursor's fastest move
+    let fastest_cursor = path
+        .windows(2)
+        .map(|w| step((w[0].x, w[0].y), (w[1].x, w[1].y)))
+        .fold(0.0, f32::max);
+    for w in path.windows(2) {
+        assert!(step(crop(&w[0]), crop(&w[1])) <= fastest_cursor + 1e-2);
+    }
+    // At rest the camera comes to a stop within its settling time
+    let rest = &path[path.len() - 60..];
+    for w in rest.windows(2) {
+        assert!(step(crop(&w[0]), crop(&w[1])) < 0.01);
+    }
+}
+
+#[test]
+fn zoom_one_is_the_whole_frame() {
+    let path = sweeping_path();
+    let settings = AutoZoom {
+        zoom: 1.0,
+        ..AutoZoom::default()
+    };
+    let plan = CameraPlan::new(&path, WIDTH, HEIGHT, &settings);
+    for p in &path {
+        let crop = plan.crop_at(p.timestamp_ms);
+        assert_eq!((crop.x, crop.y), (0.0, 0.0));
+        assert_eq!((crop.width, crop.height), (WIDTH as f32, HEIGHT as f32));
+        assert_eq!(plan.to_output(p.timestamp_ms, p.x, p.y), (p.x, p.y));
+    }
+}
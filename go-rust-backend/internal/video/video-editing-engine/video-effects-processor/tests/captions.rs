@@ -0,0 +1,260 @@
+// captions.rs - Captions show only within their time range, stack when they
+// overlap and wrap to the configured width
+use ab_glyph::FontArc;
+use video_effects_processor::engine::{
+    parse_captions, Caption, CaptionOverlay, CaptionPosition, CaptionStyle, EngineError, FrameView,
+    Overlay, ProcessingOptions,
+};
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+const BACKGROUND: [u8; 4] = [90, 90, 90, 255];
+
+/// A font to draw with: $FOCUSFRAME_TEST_FONT, else a common system font.
+/// None (and the test is skipped) when there is none.
+fn test_font() -> Option<FontArc> {
+    let candidates = [
+        std::env::var("FOCUSFRAME_TEST_FONT").unwrap_or_default(),
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf".to_string(),
+        "/usr/share/fonts/TTF/DejaVuSans.ttf".to_string(),
+        "/System/Library/Fonts/Supplemental/Arial.ttf".to_string(),
+        "C:\\Windows\\Fonts\\arial.ttf".to_string(),
+    ];
+    let font = candidates
+        .iter()
+        .filter(|path| !path.is_empty())
+        .find_map(|path| FontArc::try_from_vec(std::fs::read(path).ok()?).ok());
+    if font.is_none() {
+        eprintln!("no font found, set FOCUSFRAME_TEST_FONT to run this test");
+    }
+    font
+}
+
+fn caption(text: &str, start_ms: f64, end_ms: f64) -> Caption {
+    Caption {
+        text: text.to_string(),
+        start_ms,
+        end_ms,
+        background: Some([0, 0, 0, 255]),
+        ..Caption::default()
+    }
+}
+
+/// Rows and columns `overlay` drew on at `timestamp_ms`, as (rows, columns)
+/// lists of changed indices
+fn drawn(overlay: &CaptionOverlay, timestamp_ms: f64) -> (Vec<u32>, Vec<u32>) {
+    let stride = WIDTH as usize * 4;
+    let mut data = BACKGROUND.repeat((WIDTH * HEIGHT) as usize);
+    overlay.render(
+        &mut FrameView::new(&mut data, WIDTH, HEIGHT, stride),
+        timestamp_ms,
+    );
+    let mut rows = Vec::new();
+    let mut columns = vec![false; WIDTH as usize];
+    for (y, row) in data.chunks_exact(stride).enumerate() {
+        let mut any = false;
+        for (x, px) in row.chunks_exact(4).enumerate() {
+            if px != BACKGROUND {
+                columns[x] = true;
+                any = true;
+            }
+        }
+        if any {
+            rows.push(y as u32);
+        }
+    }
+    let columns = (0..WIDTH).filter(|&x| columns[x as usize]).collect();
+    (rows, columns)
+}
+
+/// Consecutive runs in sorted `indices`, as (first, last)
+fn runs(indices: &[u32]) -> Vec<(u32, u32)> {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for &i in indices {
+        match runs.last_mut() {
+            Some(run) if run.1 + 1 == i => run.1 = i,
+            _ => runs.push((i, i)),
+        }
+    }
+    runs
+}
+
+#[test]
+fn caption_shows_only_within_its_range() {
+    let Some(font) = test_font() else {
+        return;
+    };
+    let style = CaptionStyle {
+        fade_in_ms: 0.0,
+        fade_out_ms: 0.0,
+        ..CaptionStyle::default()
+    };
+    let overlay = CaptionOverlay::new(
+        &[caption("Step 2: open Settings", 1000.0, 3000.0)],
+        &style,
+        font,
+        false,
+    );
+    let frame_ms = 1000.0 / 30.0;
+    assert!(drawn(&overlay, 1000.0 - frame_ms).0.is_empty());
+    assert!(!drawn(&overlay, 1000.0).0.is_empty());
+    assert!(!drawn(&overlay, 3000.0 - frame_ms).0.is_empty());
+    assert!(drawn(&overlay, 3000.0).0.is_empty());
+
+    // Centered at the bottom margin
+    let (rows, columns) = drawn(&overlay, 2000.0);
+    assert_eq!(*rows.last().unwrap(), HEIGHT - 48 - 1);
+    let (left, right) = (columns[0], *columns.last().unwrap());
+    assert!((left as i32 - (WIDTH - 1 - right) as i32).abs() <= 1);
+}
+
+#[test]
+fn caption_fades_in_and_out() {
+    let Some(font) = test_font() else {
+        return;
+    };
+    let style = CaptionStyle::default();
+    let overlay = CaptionOverlay::new(&[caption("Fading", 1000.0, 3000.0)], &style, font, false);
+    let box_pixel = |timestamp_ms: f64| {
+        let stride = WIDTH as usize * 4;
+        let mut data = BACKGROUND.repeat((WIDTH * HEIGHT) as usize);
+        overlay.render(
+            &mut FrameView::new(&mut data, WIDTH, HEIGHT, stride),
+            timestamp_ms,
+        );
+        // Inside the box's padding, left of the text, halfway down
+        let (rows, columns) = drawn(&overlay, 2000.0);
+        let (x, y) = (columns[0] + 2, (rows[0] + rows[rows.len() - 1]) / 2);
+        data[y as usize * stride + x as usize * 4]
+    };
+    // Invisible at the start, halfway dark mid-fade, black once faded in
+    assert_eq!(box_pixel(1000.0), BACKGROUND[0]);
+    assert!((box_pixel(1100.0) as i32 - 45).abs() <= 2);
+    assert_eq!(box_pixel(2000.0), 0);
+    assert!((box_pixel(2900.0) as i32 - 45).abs() <= 2);
+}
+
+#[test]
+fn overlapping_captions_stack_vertically() {
+    let Some(font) = test_font() else {
+        return;
+    };
+    let style = CaptionStyle {
+        fade_in_ms: 0.0,
+        fade_out_ms: 0.0,
+        ..CaptionStyle::default()
+    };
+    let overlay = CaptionOverlay::new(
+        &[
+            caption("First", 1000.0, 4000.0),
+            caption("Second, overlapping", 2000.0, 5000.0),
+        ],
+        &style,
+        font,
+        false,
+    );
+    // Alone, each sits on the bottom margin
+    let alone = runs(&drawn(&overlay, 1500.0).0);
+    assert_eq!(alone.len(), 1);
+    assert_eq!(alone[0].1, HEIGHT - 48 - 1);
+
+    // Together, the first moves up, one spacing above the second
+    let both = runs(&drawn(&overlay, 3000.0).0);
+    assert_eq!(both.len(), 2, "{:?}", both);
+    let (upper, lower) = (both[0], both[1]);
+    assert_eq!(lower.1, HEIGHT - 48 - 1);
+    assert_eq!(lower.0 - upper.1 - 1, 12);
+    assert_eq!(upper.1 - upper.0, alone[0].1 - alone[0].0);
+
+    let later = runs(&drawn(&overlay, 4500.0).0);
+    assert_eq!(later, vec![lower]);
+}
+
+#[test]
+fn long_captions_wrap_to_the_max_width() {
+    let Some(font) = test_font() else {
+        return;
+    };
+    let style = CaptionStyle {
+        max_width_percent: 50.0,
+        fade_in_ms: 0.0,
+        fade_out_ms: 0.0,
+        ..CaptionStyle::default()
+    };
+    let text = "Open the Settings window, then pick Privacy and Security from the list";
+    let one_line =
+        CaptionOverlay::new(&[caption("Open", 0.0, 1000.0)], &style, font.clone(), false);
+    let wrapped = CaptionOverlay::new(&[caption(text, 0.0, 1000.0)], &style, font.clone(), false);
+    let broken = CaptionOverlay::new(
+        &[caption("Line one\nLine two", 0.0, 1000.0)],
+        &style,
+        font,
+        false,
+    );
+
+    let line = drawn(&one_line, 500.0).0.len();
+    let (rows, columns) = drawn(&wrapped, 500.0);
+    assert!(
+        columns.len() <= WIDTH as usize / 2,
+        "{} px wide",
+        columns.len()
+    );
+    assert!(
+        rows.len() > 2 * line,
+        "{} rows for one {}-row line",
+        rows.len(),
+        line
+    );
+    // A line break adds one line height (the font size, plus the font's
+    // line gap)
+    let (rows, _) = drawn(&broken, 500.0);
+    let added = rows.len() as f32 - line as f32;
+    assert!(
+        (36.0..=40.0).contains(&added),
+        "{} rows for one {}-row line",
+        rows.len(),
+        line
+    );
+}
+
+#[test]
+fn invalid_captions_are_rejected() {
+    for bad in [
+        caption("Backwards", 2000.0, 1000.0),
+        caption("   ", 0.0, 1000.0),
+        caption("שלום", 0.0, 1000.0),
+        caption("مرحبا", 0.0, 1000.0),
+    ] {
+        let options = ProcessingOptions {
+            captions: vec![bad],
+            ..ProcessingOptions::default()
+        };
+        assert!(matches!(
+            options.validated(),
+            Err(EngineError::InvalidCaption(_))
+        ));
+    }
+}
+
+#[test]
+fn captions_parse_with_defaults() {
+    let captions = parse_captions(
+        r#"[{"text": "Hi", "start_ms": 0, "end_ms": 1000},
+            {"text": "Top", "start_ms": 0, "end_ms": 1000, "position": "top",
+             "font_size_px": 20, "background": null}]"#,
+    )
+    .unwrap();
+    let plain = Caption {
+        text: "Hi".to_string(),
+        start_ms: 0.0,
+        end_ms: 1000.0,
+        ..Caption::default()
+    };
+    assert_eq!(captions[0], plain);
+    assert_eq!(captions[1].position, CaptionPosition::Top);
+    assert_eq!(captions[1].background, None);
+    assert!(matches!(
+        parse_captions(r#"[{"text": 5}]"#),
+        Err(EngineError::InvalidCaption(_))
+    ));
+}
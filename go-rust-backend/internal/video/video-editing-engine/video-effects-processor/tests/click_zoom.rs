@@ -0,0 +1,125 @@
+// click_zoom.rs - Zooming in around clicks and back out once things go quiet
+use video_effects_processor::engine::{CClickEvent, CPoint, CameraPlan, ClickZoom};
+
+const WIDTH: u32 = 1920;
+const HEIGHT: u32 = 1080;
+const FPS: f64 = 60.0;
+
+/// `seconds` of a cursor parked at (600, 400)
+fn resting_path(seconds: f64) -> Vec<CPoint> {
+    (0..(seconds * FPS) as usize)
+        .map(|k| CPoint {
+            x: 600.0,
+            y: 400.0,
+            timestamp_ms: k as f64 * 1000.0 / FPS,
+        })
+        .collect()
+}
+
+fn click(x: f32, y: f32, timestamp_ms: f64) -> CClickEvent {
+    CClickEvent {
+        x,
+        y,
+        timestamp_ms,
+        button: 0,
+    }
+}
+
+/// Crop width at each path sample from `from_ms` up to (excluding) `to_ms`
+fn widths(plan: &CameraPlan, path: &[CPoint], from_ms: f64, to_ms: f64) -> Vec<f32> {
+    path.iter()
+        .filter(|p| (from_ms..to_ms).contains(&p.timestamp_ms))
+        .map(|p| plan.crop_at(p.timestamp_ms).width)
+        .collect()
+}
+
+#[test]
+fn rapid_clicks_share_one_zoom() {
+    let path = resting_path(6.0);
+    let clicks = [click(600.0, 400.0, 1000.0), click(640.0, 420.0, 1600.0)];
+    let settings = ClickZoom::default();
+    let plan = CameraPlan::from_clicks(&clicks, &path, WIDTH, HEIGHT, &settings);
+
+    // Full frame until the first click, then one continuous zoom in: the
+    // crop never widens again before the dwell after the second click ends
+    assert!(widths(&plan, &path, 0.0, 1000.0)
+        .iter()
+        .all(|&w| w == WIDTH as f32));
+    let zooming = widths(&plan, &path, 1000.0, 1600.0 + settings.dwell_ms as f64);
+    assert!(zooming.windows(2).all(|w| w[1] <= w[0] + 1e-3));
+    let zoomed = WIDTH as f32 / settings.zoom;
+    assert!((zooming.last().unwrap() - zoomed).abs() < 1.0);
+
+    // ... and back out to the full frame afterwards
+    let release = widths(&plan, &path, 1600.0 + settings.dwell_ms as f64, 6000.0);
+    assert!(release.windows(2).all(|w| w[1] >= w[0] - 1e-3));
+    assert!((release.last().unwrap() - WIDTH as f32).abs() < 1.0);
+}
+
+#[test]
+fn short_dwell_zooms_out_between_clicks() {
+    let path = resting_path(6.0);
+    let clicks = [click(600.0, 400.0, 1000.0), click(640.0, 420.0, 3000.0)];
+    let settings = ClickZoom {
+        dwell_ms: 500.0,
+        min_spacing_ms: 0.0,
+        ..ClickZoom::default()
+    };
+    let plan = CameraPlan::from_clicks(&clicks, &path, WIDTH, HEIGHT, &settings);
+    // Zoomed in after the first click, out again well before the second
+    let between = widths(&plan, &path, 1000.0, 3000.0);
+    let narrowest = between.iter().cloned().fold(f32::MAX, f32::min);
+    assert!(
+        narrowest < WIDTH as f32 / 1.7,
+        "never zoomed in: {}",
+        narrowest
+    );
+    assert!(widths(&plan, &path, 2500.0, 3000.0)
+        .iter()
+        .all(|&w| w > WIDTH as f32 - 1.0));
+}
+
+#[test]
+fn moving_cursor_holds_the_zoom() {
+    // Circles 40 px around the click until 4 s, then rests
+    let path: Vec<CPoint> = resting_path(8.0)
+        .into_iter()
+        .map(|p| {
+            let a = p.timestamp_ms.min(4000.0) / 300.0;
+            CPoint {
+                x: p.x + 40.0 * a.cos() as f32,
+                y: p.y + 40.0 * a.sin() as f32,
+                ..p
+            }
+        })
+        .collect();
+    let settings = ClickZoom::default();
+    let plan = CameraPlan::from_clicks(
+        &[click(600.0, 400.0, 1000.0)],
+        &path,
+        WIDTH,
+        HEIGHT,
+        &settings,
+    );
+    let zoomed = WIDTH as f32 / settings.zoom;
+    let held = widths(&plan, &path, 2000.0, 4000.0 + settings.dwell_ms as f64);
+    assert!(held.iter().all(|&w| (w - zoomed).abs() < 1.0));
+    assert!((plan.crop_at(8000.0).width - WIDTH as f32).abs() < 1.0);
+}
+
+#[test]
+fn clicks_elsewhere_wait_for_the_spacing() {
+    let path = resting_path(8.0);
+    let settings = ClickZoom {
+        dwell_ms: 500.0,
+        min_spacing_ms: 2000.0,
+        ..ClickZoom::default()
+    };
+    // The second click is across the screen, 1 s after the first zoom ends
+    let clicks = [click(600.0, 400.0, 1000.0), click(1700.0, 900.0, 2500.0)];
+    let plan = CameraPlan::from_clicks(&clicks, &path, WIDTH, HEIGHT, &settings);
+    // Ignored: no second zoom after the first one settles out
+    assert!(widths(&plan, &path, 2500.0, 8000.0)
+        .iter()
+        .all(|&w| w > WIDTH as f32 - 1.0));
+}
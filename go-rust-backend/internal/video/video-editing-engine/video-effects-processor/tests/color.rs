@@ -0,0 +1,110 @@
+// color.rs - Exports keep the source's color tags and colors
+//
+// Inputs are generated with the ffmpeg CLI and outputs are read back with
+// ffprobe and ffmpeg; the tests are skipped when either is missing.
+mod common;
+
+use common::{
+    ffmpeg_command, generate, probe_fields, resting, tools_available, white_sprite, work_dir,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use video_effects_processor::engine::{self, ProcessingOptions, Progress};
+
+/// One second of a flat blue-green 320x240 frame, converted with BT.601 and
+/// tagged as such (`tagged`), or converted the default way and left untagged
+fn generate_input(path: &Path, tagged: bool) {
+    let mut ffmpeg = ffmpeg_command();
+    ffmpeg
+        .args(["-f", "lavfi", "-i", "color=c=0x20a0e0:size=320x240:rate=30"])
+        .args(["-t", "1"]);
+    if tagged {
+        ffmpeg
+            .args(["-vf", "scale=out_color_matrix=bt601:out_range=tv"])
+            .args(["-colorspace", "smpte170m", "-color_primaries", "smpte170m"])
+            .args(["-color_trc", "smpte170m", "-color_range", "tv"]);
+    }
+    generate(
+        ffmpeg.args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+fn render(input: &Path, output: &Path) {
+    let sprite = white_sprite(8);
+    // Resting in a corner, away from the sampled center
+    let points = resting(4.0, 4.0);
+    let opts = ProcessingOptions::default();
+    engine::process_video(input, output, &sprite, &points, &opts, |_: Progress| {}).unwrap();
+}
+
+/// Color tags of the first video stream, by ffprobe field name
+fn color_tags(path: &Path) -> HashMap<String, String> {
+    probe_fields(
+        path,
+        "v:0",
+        "stream=color_range,color_space,color_transfer,color_primaries",
+    )
+}
+
+/// RGB of the center pixel of the first frame, as ffmpeg decodes it
+fn center_pixel(path: &Path) -> [u8; 3] {
+    let out = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "ffmpeg could not decode {}",
+        path.display()
+    );
+    let at = (120 * 320 + 160) * 3;
+    [out.stdout[at], out.stdout[at + 1], out.stdout[at + 2]]
+}
+
+#[test]
+fn bt601_input_keeps_its_tags_and_colors() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("color-601");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input, true);
+    render(&input, &output);
+
+    let tags = color_tags(&output);
+    assert_eq!(tags["color_range"], "tv", "{:?}", tags);
+    assert_eq!(tags["color_space"], "smpte170m", "{:?}", tags);
+    assert_eq!(tags["color_transfer"], "smpte170m", "{:?}", tags);
+    assert_eq!(tags["color_primaries"], "smpte170m", "{:?}", tags);
+
+    let (before, after) = (center_pixel(&input), center_pixel(&output));
+    assert!(
+        before.iter().zip(after).all(|(&b, a)| b.abs_diff(a) <= 6),
+        "{:?} became {:?}",
+        before,
+        after
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn untagged_input_is_exported_as_bt709_limited() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("color-untagged");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input, false);
+    render(&input, &output);
+
+    let tags = color_tags(&output);
+    assert_eq!(tags["color_range"], "tv", "{:?}", tags);
+    assert_eq!(tags["color_space"], "bt709", "{:?}", tags);
+    assert_eq!(tags["color_transfer"], "bt709", "{:?}", tags);
+    assert_eq!(tags["color_primaries"], "bt709", "{:?}", tags);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,195 @@
+// common/mod.rs - Helpers shared by the integration tests that drive the
+// ffmpeg CLI: tool checks, scratch directories, inputs and probes
+//
+// Tests that need ffmpeg or ffprobe skip when they are missing. Set
+// REQUIRE_FFMPEG=1 (as CI should) to make a missing tool fail the test
+// instead of passing it silently.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use video_effects_processor::engine::{CPoint, CursorSprite};
+
+/// Whether REQUIRE_FFMPEG=1 turns a missing tool into a failure
+fn tools_required() -> bool {
+    std::env::var_os("REQUIRE_FFMPEG").is_some_and(|value| value == "1")
+}
+
+/// Report `what` as missing: a panic under REQUIRE_FFMPEG=1, a skip otherwise
+fn missing(what: &str) -> bool {
+    if tools_required() {
+        panic!("{} not found and REQUIRE_FFMPEG=1", what);
+    }
+    eprintln!("{} not found, skipping", what);
+    false
+}
+
+/// Standard output of `tool arg`, if it ran and succeeded
+fn tool_output(tool: &str, arg: &str) -> Option<String> {
+    Command::new(tool)
+        .args(["-hide_banner", arg])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// ffmpeg is on the PATH
+pub fn ffmpeg_available() -> bool {
+    tool_output("ffmpeg", "-version").is_some() || missing("ffmpeg")
+}
+
+/// ffmpeg and ffprobe are both on the PATH
+pub fn tools_available() -> bool {
+    let available =
+        tool_output("ffmpeg", "-version").is_some() && tool_output("ffprobe", "-version").is_some();
+    available || missing("ffmpeg/ffprobe")
+}
+
+/// ffmpeg and ffprobe are on the PATH and ffmpeg was built with `encoder`
+pub fn encoder_available(encoder: &str) -> bool {
+    let available = tool_output("ffprobe", "-version").is_some()
+        && tool_output("ffmpeg", "-encoders").is_some_and(|encoders| encoders.contains(encoder));
+    available || missing(&format!("ffmpeg/ffprobe with {}", encoder))
+}
+
+/// A fresh scratch directory for the test `name`
+pub fn work_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("focusframe-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// `ffmpeg -v error -y`, for the caller to add inputs and options to
+pub fn ffmpeg_command() -> Command {
+    let mut command = Command::new("ffmpeg");
+    command.args(["-v", "error", "-y"]);
+    command
+}
+
+/// Run `command` writing `output`, failing the test if it fails
+pub fn generate(command: &mut Command, output: &Path) {
+    let status = command.arg(output).status().unwrap();
+    assert!(
+        status.success(),
+        "ffmpeg could not generate {}",
+        output.display()
+    );
+}
+
+/// Run `ffmpeg -v error -y <args> <output>`, failing the test if it fails
+pub fn ffmpeg(args: &[&str], output: &Path) {
+    generate(ffmpeg_command().args(args), output);
+}
+
+/// Run ffprobe showing `entry` of the streams `streams` selects ("" = the
+/// container) in the output `format`, failing the test if it fails
+fn run_probe(
+    path: &Path,
+    streams: &str,
+    entry: &str,
+    format: &str,
+    count_frames: bool,
+) -> std::process::Output {
+    let mut command = Command::new("ffprobe");
+    command.args(["-v", "error"]);
+    if count_frames {
+        command.arg("-count_frames");
+    }
+    if !streams.is_empty() {
+        command.args(["-select_streams", streams]);
+    }
+    let out = command
+        .args(["-show_entries", entry, "-of", format])
+        .arg(path)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "ffprobe failed on {}", path.display());
+    out
+}
+
+/// `entry` of the first stream `streams` selects ("" = the container)
+pub fn probe(path: &Path, streams: &str, entry: &str) -> String {
+    let out = run_probe(path, streams, entry, "csv=p=0", false);
+    String::from_utf8(out.stdout).unwrap().trim().to_string()
+}
+
+/// `probe` with one line per selected stream, or per packet for
+/// `packet=...` entries
+pub fn probe_lines(path: &Path, streams: &str, entry: &str) -> Vec<String> {
+    probe(path, streams, entry)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .collect()
+}
+
+/// `entry` of the first stream `streams` selects, by ffprobe field name
+pub fn probe_fields(path: &Path, streams: &str, entry: &str) -> HashMap<String, String> {
+    let out = run_probe(path, streams, entry, "default=noprint_wrappers=1", false);
+    String::from_utf8(out.stdout)
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Frames ffmpeg decodes from the first video stream of `path`, failing the
+/// test on any decode error
+pub fn decoded_frames(path: &Path) -> u32 {
+    let out = run_probe(path, "v:0", "stream=nb_read_frames", "csv=p=0", true);
+    assert!(
+        out.stderr.is_empty(),
+        "{} did not decode cleanly: {}",
+        path.display(),
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout)
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+/// An opaque white `size`x`size` cursor
+pub fn white_sprite(size: u32) -> CursorSprite {
+    CursorSprite::from_rgba8(vec![255; (size * size * 4) as usize], size, size, false)
+}
+
+/// A straight drag from `from` to `to` over `end_ms`, sampled often enough
+/// to pass point validation
+pub fn drag(from: (f32, f32), to: (f32, f32), end_ms: f64) -> Vec<CPoint> {
+    (0..=4)
+        .map(|i| {
+            let t = i as f32 / 4.0;
+            CPoint {
+                x: from.0 + (to.0 - from.0) * t,
+                y: from.1 + (to.1 - from.1) * t,
+                timestamp_ms: end_ms * f64::from(t),
+            }
+        })
+        .collect()
+}
+
+/// The cursor resting at `(x, y)` for a second
+pub fn resting(x: f32, y: f32) -> Vec<CPoint> {
+    (0..=4)
+        .map(|i| CPoint {
+            x,
+            y,
+            timestamp_ms: f64::from(i) * 250.0,
+        })
+        .collect()
+}
+
+/// Left to right along y = 360 over three seconds, across a 1280x720 frame
+pub fn sweep_720p() -> Vec<CPoint> {
+    (0..=30)
+        .map(|i| CPoint {
+            x: 100.0 + i as f32 * 30.0,
+            y: 360.0,
+            timestamp_ms: f64::from(i) * 100.0,
+        })
+        .collect()
+}
@@ -0,0 +1,90 @@
+// concurrent.rs - Two renders running at once on different threads, inputs
+// and outputs both finish and produce files that decode
+//
+// The inputs are generated with the ffmpeg CLI and the outputs are read back
+// with ffprobe and decoded with ffmpeg; the test is skipped when either is
+// missing.
+mod common;
+
+use common::{drag, ffmpeg_command, generate, probe, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{self, ProcessingOptions, Progress};
+
+/// Frames in each input (one second at 10 fps)
+const FRAMES: u32 = 10;
+
+/// A one second test pattern of `size`
+fn generate_input(path: &Path, size: &str) {
+    generate(
+        ffmpeg_command()
+            .args([
+                "-f",
+                "lavfi",
+                "-i",
+                &format!("testsrc=size={}:rate=10", size),
+            ])
+            .args(["-frames:v", &FRAMES.to_string()])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+/// Decode all of `path`, failing the test on the first decode error
+fn assert_decodes(path: &Path) {
+    let status = ffmpeg_command()
+        .args(["-xerror", "-i"])
+        .arg(path)
+        .args(["-f", "null", "-"])
+        .status()
+        .unwrap();
+    assert!(status.success(), "{} does not decode", path.display());
+}
+
+#[test]
+fn two_renders_at_once_both_decode() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("concurrent");
+    // Different sizes, so outputs swapped between the renders would show
+    let jobs = [("160x120", (160, 120)), ("240x160", (240, 160))].map(|(size, dims)| {
+        let input = dir.join(format!("input-{}.mp4", size));
+        generate_input(&input, size);
+        (input, dir.join(format!("output-{}.mp4", size)), dims)
+    });
+
+    let sprite = white_sprite(8);
+    let opts = ProcessingOptions {
+        frame_rate: 10,
+        ..ProcessingOptions::default()
+    };
+    std::thread::scope(|scope| {
+        let renders: Vec<_> = jobs
+            .iter()
+            .map(|(input, output, (width, height))| {
+                let points = drag(
+                    (10.0, 10.0),
+                    (*width as f32 - 10.0, *height as f32 - 10.0),
+                    1000.0,
+                );
+                let (sprite, opts) = (&sprite, &opts);
+                scope.spawn(move || {
+                    engine::process_video(input, output, sprite, &points, opts, |_: Progress| {})
+                })
+            })
+            .collect();
+        for render in renders {
+            render.join().unwrap().unwrap();
+        }
+    });
+
+    for (_, output, (width, height)) in &jobs {
+        assert_eq!(
+            probe(output, "v:0", "stream=codec_name,width,height"),
+            format!("h264,{},{}", width, height)
+        );
+        assert_eq!(probe(output, "v:0", "stream=nb_frames"), FRAMES.to_string());
+        assert_decodes(output);
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,86 @@
+// container.rs - H.264 exports are written as MP4 or Matroska, picked from
+// the output extension or the config, and reopen as such
+//
+// The input is generated with the ffmpeg CLI and the outputs are read back
+// with ffprobe and decoded with ffmpeg; the export test is skipped when
+// either is missing.
+mod common;
+
+use common::{
+    decoded_frames, drag, ffmpeg_command, generate, probe, tools_available, white_sprite, work_dir,
+};
+use std::path::Path;
+use video_effects_processor::engine::{self, Container, ProcessingOptions, Progress};
+
+/// Frames in the input (one second at 10 fps)
+const FRAMES: u32 = 10;
+
+/// A one second, 160x120 test pattern
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=160x120:rate=10"])
+            .args(["-frames:v", &FRAMES.to_string()])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+#[test]
+fn container_follows_the_extension_unless_set() {
+    let cases = [
+        (Container::Auto, "out.mp4", Container::Mp4),
+        (Container::Auto, "out.M4V", Container::Mp4),
+        (Container::Auto, "out.mkv", Container::Mkv),
+        (Container::Auto, "out.bin", Container::Auto),
+        (Container::Mkv, "out.mp4", Container::Mkv),
+    ];
+    for (container, path, expected) in cases {
+        assert_eq!(container.resolve(Path::new(path)), expected, "{}", path);
+    }
+}
+
+#[test]
+fn mp4_and_mkv_outputs_reopen() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("container");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    // (output, container set in the config, a demuxer name ffprobe reports)
+    let cases = [
+        ("auto.mp4", Container::Auto, "mp4"),
+        ("auto.mkv", Container::Auto, "matroska"),
+        ("forced.mp4", Container::Mp4, "mp4"),
+        ("forced.bin", Container::Mkv, "matroska"),
+    ];
+    let sprite = white_sprite(8);
+    let points = drag((20.0, 20.0), (140.0, 100.0), 1000.0);
+    for (name, container, format) in cases {
+        let output = dir.join(name);
+        let opts = ProcessingOptions {
+            frame_rate: 10,
+            container,
+            ..ProcessingOptions::default()
+        };
+        engine::process_video(&input, &output, &sprite, &points, &opts, |_: Progress| {}).unwrap();
+
+        let demuxers = probe(&output, "", "format=format_name");
+        assert!(
+            demuxers.contains(format),
+            "{}: opened as {}",
+            name,
+            demuxers
+        );
+        assert_eq!(
+            probe(&output, "v:0", "stream=codec_name"),
+            "h264",
+            "{}",
+            name
+        );
+        assert_eq!(decoded_frames(&output), FRAMES, "{}", name);
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,158 @@
+// crop.rs - Crop-region exports keep only the chosen part of the recording,
+// with the cursor moved into it
+//
+// The input is generated with the ffmpeg CLI and the output is decoded with
+// it again; the export tests are skipped when it is missing.
+mod common;
+
+use common::{ffmpeg_available, ffmpeg_command, generate, resting, white_sprite, work_dir};
+use std::path::Path;
+use std::process::Command;
+use video_effects_processor::engine::{self, CropRegion, EngineError, ProcessingOptions, Progress};
+
+const RED: [u8; 3] = [255, 0, 0];
+const GREEN: [u8; 3] = [0, 255, 0];
+const BLUE: [u8; 3] = [0, 0, 255];
+const WHITE: [u8; 3] = [255, 255, 255];
+
+/// The bottom-right quadrant of the input
+const QUADRANT: CropRegion = CropRegion {
+    x: 320,
+    y: 240,
+    width: 320,
+    height: 240,
+};
+
+/// One second of 640x480 frames: red on top, green bottom-left and blue
+/// bottom-right
+fn generate_input(path: &Path) {
+    let pattern = "color=c=red:size=640x480:rate=30,\
+        drawbox=x=0:y=240:w=320:h=240:c=green:t=fill,\
+        drawbox=x=320:y=240:w=320:h=240:c=blue:t=fill";
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", pattern])
+            .args(["-t", "1"])
+            .args(["-c:v", "libx264", "-crf", "0", "-pix_fmt", "yuv444p"]),
+        path,
+    );
+}
+
+/// Render `input` cropped to `QUADRANT`, a white 16x16 sprite resting at
+/// the source position `(x, y)`
+fn render(input: &Path, output: &Path, (x, y): (f32, f32)) -> Result<(), EngineError> {
+    let sprite = white_sprite(16);
+    let points = resting(x, y);
+    let opts = ProcessingOptions {
+        crop: Some(QUADRANT),
+        ..ProcessingOptions::default()
+    };
+    engine::process_video(input, output, &sprite, &points, &opts, |_: Progress| {}).map(|_| ())
+}
+
+/// RGB pixels of the first frame, which must be `width` x `height`
+fn first_frame(path: &Path, width: usize, height: usize) -> Vec<[u8; 3]> {
+    let out = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "ffmpeg could not decode {}",
+        path.display()
+    );
+    assert_eq!(
+        out.stdout.len(),
+        width * height * 3,
+        "unexpected frame size"
+    );
+    out.stdout
+        .chunks_exact(3)
+        .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+        .collect()
+}
+
+/// Whether `pixel` is `color` give or take encoding error
+fn is(pixel: [u8; 3], color: [u8; 3]) -> bool {
+    pixel.iter().zip(color).all(|(&p, c)| p.abs_diff(c) < 48)
+}
+
+#[test]
+fn cropped_export_keeps_the_quadrant_and_moves_the_cursor() {
+    if !ffmpeg_available() {
+        return;
+    }
+    let dir = work_dir("crop");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+    render(&input, &output, (400.0, 300.0)).unwrap();
+
+    let frame = first_frame(&output, 320, 240);
+    let at = |x: usize, y: usize| frame[y * 320 + x];
+    // Only the blue quadrant is left, corner to corner
+    for (x, y) in [(2, 2), (317, 2), (2, 237), (317, 237), (200, 150)] {
+        assert!(is(at(x, y), BLUE), "({}, {}) is {:?}", x, y, at(x, y));
+    }
+    assert!(!frame.iter().any(|&p| is(p, RED) || is(p, GREEN)));
+    // The sprite's top-left corner moves from (400, 300) to (80, 60)
+    let white: Vec<(usize, usize)> = (0..frame.len())
+        .filter(|&i| is(frame[i], WHITE))
+        .map(|i| (i % 320, i / 320))
+        .collect();
+    let centroid = |axis: fn(&(usize, usize)) -> usize| {
+        white.iter().map(axis).sum::<usize>() as f64 / white.len() as f64 + 0.5
+    };
+    let (x, y) = (centroid(|p| p.0), centroid(|p| p.1));
+    assert!(
+        (x - 88.0).abs() < 1.0 && (y - 68.0).abs() < 1.0,
+        "cursor centered at ({:.1}, {:.1})",
+        x,
+        y
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn cursor_outside_the_crop_is_not_drawn() {
+    if !ffmpeg_available() {
+        return;
+    }
+    let dir = work_dir("crop-outside");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+    // In the red area, with the sprite reaching into the crop
+    render(&input, &output, (312.0, 232.0)).unwrap();
+
+    let frame = first_frame(&output, 320, 240);
+    assert!(frame.iter().all(|&p| is(p, BLUE)));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn crop_is_rounded_to_even_and_checked_against_the_input() {
+    let crop = |x, y, width, height| CropRegion {
+        x,
+        y,
+        width,
+        height,
+    };
+    assert_eq!(
+        crop(101, 51, 321, 241).fit(640, 480).unwrap(),
+        crop(100, 50, 320, 240)
+    );
+    assert_eq!(QUADRANT.fit(640, 480).unwrap(), QUADRANT);
+    for outside in [
+        crop(400, 0, 320, 240),
+        crop(0, 0, 640, 482),
+        crop(0, 0, 1, 240),
+        crop(u32::MAX - 1, 0, 4, 4),
+    ] {
+        assert!(
+            matches!(outside.fit(640, 480), Err(EngineError::InvalidCrop(_))),
+            "{:?}",
+            outside
+        );
+    }
+}
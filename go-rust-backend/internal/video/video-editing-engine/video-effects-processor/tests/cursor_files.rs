@@ -0,0 +1,95 @@
+// cursor_files.rs - Windows .cur and .ani cursors load with their own
+// hotspots, frames and timing, and broken ones are load errors
+//
+// The fixtures in tests/fixtures are small hand-built files: arrow.cur holds
+// a 16x16 PNG and a 32x32 BMP cursor, busy.ani three 16x16 PNG cursors
+// played in a custom order at custom rates.
+mod common;
+
+use common::work_dir;
+use std::path::PathBuf;
+use video_effects_processor::engine::{self, EngineError};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+/// Alpha of the sprite pixel at (`x`, `y`)
+fn alpha_at(sprite: &engine::CursorSprite, x: u32, y: u32) -> u8 {
+    sprite.data[((y * sprite.width + x) * 4 + 3) as usize]
+}
+
+#[test]
+fn cur_loads_its_largest_cursor_and_hotspot() {
+    let sprite = engine::load_cursor_sprite(&fixture("arrow.cur")).unwrap();
+    // The 32x32 BMP entry, not the 16x16 PNG listed first
+    assert_eq!((sprite.width, sprite.height), (32, 32));
+    assert_eq!((sprite.hotspot_x, sprite.hotspot_y), (3.0, 2.0));
+    assert!(sprite.animation.is_empty());
+    // The BMP rows are stored bottom-up: the arrow tip is at the top left
+    assert_eq!(alpha_at(&sprite, 0, 0), 255);
+    assert_eq!(alpha_at(&sprite, 31, 0), 0);
+}
+
+#[test]
+fn ani_loads_each_step_with_its_hotspot_and_rate() {
+    let sprite = engine::load_cursor_sprite(&fixture("busy.ani")).unwrap();
+    // seq plays frames 0 1 2 1; rate gives 6, 12, 6 and 3 jiffies (1/60 s)
+    let steps: Vec<(u32, u32, f32, f32, u32)> = sprite
+        .animation
+        .iter()
+        .map(|frame| {
+            let s = &frame.sprite;
+            (
+                s.width,
+                s.height,
+                s.hotspot_x,
+                s.hotspot_y,
+                frame.duration_ms,
+            )
+        })
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            (16, 16, 8.0, 8.0, 100),
+            (16, 16, 7.0, 8.0, 200),
+            (16, 16, 8.0, 7.0, 100),
+            (16, 16, 7.0, 8.0, 50),
+        ]
+    );
+    // Red, green, blue, green at the center
+    let centers: Vec<[u8; 3]> = sprite
+        .animation
+        .iter()
+        .map(|frame| {
+            let i = ((8 * frame.sprite.width + 8) * 4) as usize;
+            frame.sprite.data[i..i + 3].try_into().unwrap()
+        })
+        .collect();
+    assert_eq!(
+        centers,
+        [[255, 0, 0], [0, 200, 0], [0, 0, 255], [0, 200, 0]]
+    );
+    assert_eq!(sprite.frame_index(320.0), 2);
+}
+
+#[test]
+fn truncated_cursor_files_are_load_errors() {
+    let dir = work_dir("truncated-cursors");
+    for (name, keep) in [("arrow.cur", 40), ("busy.ani", 100)] {
+        let bytes = std::fs::read(fixture(name)).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, &bytes[..keep]).unwrap();
+        let result = engine::load_cursor_sprite(&path);
+        assert!(
+            matches!(result, Err(EngineError::Sprite(_))),
+            "{}: {:?}",
+            name,
+            result.map(|sprite| sprite.width)
+        );
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,57 @@
+// deterministic.rs - A deterministic render of the same input twice gives
+// byte-identical files
+//
+// The input is generated with the ffmpeg CLI; the test is skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{drag, ffmpeg_command, generate, tools_available, white_sprite, work_dir};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use video_effects_processor::engine::{self, ProcessingOptions, Progress};
+
+/// Half a second of a 160x120 test pattern with a tone
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=160x120:rate=30"])
+            .args(["-f", "lavfi", "-i", "sine=frequency=440:sample_rate=48000"])
+            .args(["-t", "0.5"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-c:a", "aac"]),
+        path,
+    );
+}
+
+fn sha256(path: &Path) -> String {
+    let digest = Sha256::digest(std::fs::read(path).unwrap());
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[test]
+fn deterministic_renders_hash_the_same() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("deterministic");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    let sprite = white_sprite(8);
+    let points = drag((20.0, 20.0), (140.0, 100.0), 500.0);
+    let opts = ProcessingOptions {
+        frame_rate: 30,
+        deterministic: true,
+        ..ProcessingOptions::default()
+    };
+    let hashes: Vec<String> = ["first.mp4", "second.mp4"]
+        .iter()
+        .map(|name| {
+            let output = dir.join(name);
+            engine::process_video(&input, &output, &sprite, &points, &opts, |_: Progress| {})
+                .unwrap();
+            sha256(&output)
+        })
+        .collect();
+    assert_eq!(hashes[0], hashes[1]);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,103 @@
+// encoder_backend.rs - Hardware encoders are tried first and fall back to
+// software when they are missing or fail to open
+use video_effects_processor::engine::{select_encoder, EncoderBackend, EngineError, VideoCodec};
+
+/// An opener that only knows the encoders in `available`, like an FFmpeg
+/// build without the hardware ones
+fn open_from(
+    available: &'static [&'static str],
+) -> impl FnMut(EncoderBackend, &'static str) -> Result<&'static str, EngineError> {
+    move |_, name| {
+        if available.contains(&name) {
+            Ok(name)
+        } else {
+            Err(EngineError::EncoderUnavailable(name))
+        }
+    }
+}
+
+#[test]
+fn hardware_backends_fall_back_to_software() {
+    for backend in [
+        EncoderBackend::Auto,
+        EncoderBackend::VideoToolbox,
+        EncoderBackend::Nvenc,
+        EncoderBackend::Vaapi,
+    ] {
+        let candidates = backend.candidates(VideoCodec::H264);
+        assert_eq!(
+            candidates.last(),
+            Some(&(EncoderBackend::Software, "libx264"))
+        );
+        let (chosen, name) = select_encoder(&candidates, open_from(&["libx264"])).unwrap();
+        assert_eq!((chosen, name), (EncoderBackend::Software, "libx264"));
+    }
+}
+
+#[test]
+fn available_hardware_encoder_is_preferred() {
+    let candidates = EncoderBackend::Nvenc.candidates(VideoCodec::Hevc);
+    assert_eq!(
+        candidates,
+        vec![
+            (EncoderBackend::Nvenc, "hevc_nvenc"),
+            (EncoderBackend::Software, "libx265"),
+        ]
+    );
+    let (chosen, name) =
+        select_encoder(&candidates, open_from(&["hevc_nvenc", "libx265"])).unwrap();
+    assert_eq!((chosen, name), (EncoderBackend::Nvenc, "hevc_nvenc"));
+}
+
+#[test]
+fn encoder_that_fails_to_open_is_skipped() {
+    // Present in the build but without a usable GPU, e.g. no /dev/dri
+    let candidates = [
+        (EncoderBackend::Vaapi, "h264_vaapi_without_device"),
+        (EncoderBackend::Software, "libx264"),
+    ];
+    let mut tried = Vec::new();
+    let (chosen, _) = select_encoder(&candidates, |backend, name| {
+        tried.push(name);
+        match backend {
+            EncoderBackend::Vaapi => Err(EngineError::MissingComponent("VA-API device")),
+            _ => Ok(()),
+        }
+    })
+    .unwrap();
+    assert_eq!(chosen, EncoderBackend::Software);
+    assert_eq!(tried, ["h264_vaapi_without_device", "libx264"]);
+}
+
+#[test]
+fn software_only_never_tries_hardware() {
+    for codec in [VideoCodec::H264, VideoCodec::Hevc, VideoCodec::Vp9] {
+        assert_eq!(
+            EncoderBackend::Software.candidates(codec),
+            vec![(EncoderBackend::Software, codec.encoder_name())]
+        );
+    }
+    // Neither VideoToolbox nor NVENC encodes VP9
+    assert_eq!(
+        EncoderBackend::Nvenc.candidates(VideoCodec::Vp9),
+        vec![(EncoderBackend::Software, "libvpx-vp9")]
+    );
+}
+
+#[test]
+fn last_error_is_returned_when_nothing_opens() {
+    let candidates = EncoderBackend::VideoToolbox.candidates(VideoCodec::H264);
+    assert!(matches!(
+        select_encoder(&candidates, open_from(&[])),
+        Err(EngineError::EncoderUnavailable("libx264"))
+    ));
+}
+
+#[test]
+fn backend_raw_values_round_trip() {
+    for raw in 0..5 {
+        assert_eq!(EncoderBackend::from_raw(raw).unwrap().to_raw(), raw);
+    }
+    assert_eq!(EncoderBackend::from_raw(0), Some(EncoderBackend::Software));
+    assert_eq!(EncoderBackend::from_raw(5), None);
+}
@@ -0,0 +1,64 @@
+// frame_bounds.rs - Smoothed positions outside the frame are left alone,
+// clamped to the edge or mirrored back in
+//
+// The preview test generates its input with the ffmpeg CLI and is skipped
+// when ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{ffmpeg_command, generate, resting, tools_available, white_sprite, work_dir};
+use video_effects_processor::engine::{self, FrameBounds, ProcessingOptions};
+
+#[test]
+fn positions_map_into_the_frame() {
+    let (width, height) = (320, 240);
+    // (position, clamped, reflected)
+    let cases = [
+        ((100.0, 80.0), (100.0, 80.0), (100.0, 80.0)),
+        ((-12.5, 80.0), (0.0, 80.0), (12.5, 80.0)),
+        ((329.0, -3.0), (319.0, 0.0), (309.0, 3.0)),
+        ((100.0, 250.0), (100.0, 239.0), (100.0, 228.0)),
+        // Further out than the frame is wide: the mirror image is clamped
+        ((-900.0, 1000.0), (0.0, 239.0), (319.0, 0.0)),
+    ];
+    for ((x, y), clamped, reflected) in cases {
+        assert_eq!(FrameBounds::Off.apply(x, y, width, height), (x, y));
+        assert_eq!(FrameBounds::Clamp.apply(x, y, width, height), clamped);
+        assert_eq!(FrameBounds::Reflect.apply(x, y, width, height), reflected);
+    }
+    assert_eq!(FrameBounds::from_raw(2), Some(FrameBounds::Reflect));
+    assert_eq!(FrameBounds::from_raw(3), None);
+}
+
+#[test]
+fn clamped_cursor_stays_visible_in_the_preview() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("frame-bounds");
+    let input = dir.join("input.mp4");
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "color=c=black:size=320x240:rate=30"])
+            .args(["-t", "1"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv444p"]),
+        &input,
+    );
+
+    // Resting 40 px left of the frame
+    let points = resting(-40.0, 120.0);
+    let sprite = white_sprite(8);
+    let lit_pixels = |frame_bounds: FrameBounds| {
+        let png = dir.join(format!("{:?}.png", frame_bounds));
+        let opts = ProcessingOptions {
+            frame_bounds,
+            ..ProcessingOptions::default()
+        };
+        engine::render_preview_frame(&input, &png, &sprite, &points, &opts, 500.0).unwrap();
+        let image = image::open(&png).unwrap().to_luma8();
+        image.pixels().filter(|pixel| pixel.0[0] > 200).count()
+    };
+    assert_eq!(lit_pixels(FrameBounds::Off), 0);
+    // The whole sprite, its hotspot on the left edge
+    assert!(lit_pixels(FrameBounds::Clamp) >= 7 * 7);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,130 @@
+// frame_estimate.rs - Frame counts are estimated for inputs that do not
+// store one, and progress goes indeterminate when even the length is unknown
+//
+// The inputs are generated with the ffmpeg CLI; the tests are skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{ffmpeg_command, tools_available, white_sprite, work_dir};
+use std::fs::File;
+use std::path::Path;
+use std::process::Stdio;
+use video_effects_processor::engine::{
+    self, CPoint, ProcessingOptions, Progress, INDETERMINATE_PROGRESS,
+};
+
+/// Frames in every input (two seconds at 30 fps)
+const FRAMES: i64 = 60;
+
+/// Two seconds of a 320x240 test pattern in `format`. Piped, the muxer
+/// cannot seek back to write the duration, so the file has none.
+fn generate_input(path: &Path, format: &str, piped: bool) {
+    let mut command = ffmpeg_command();
+    command
+        .args(["-f", "lavfi", "-i", "testsrc=size=320x240:rate=30"])
+        .args(["-frames:v", &FRAMES.to_string()])
+        .args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-f", format]);
+    if piped {
+        command
+            .arg("pipe:1")
+            .stdout(Stdio::from(File::create(path).unwrap()));
+    } else {
+        command.arg(path);
+    }
+    let status = command.status().unwrap();
+    assert!(
+        status.success(),
+        "ffmpeg could not generate {}",
+        path.display()
+    );
+}
+
+#[test]
+fn frame_count_is_estimated_from_what_the_input_has() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("frame-estimate");
+    // (file, muxer, piped, frames expected); MP4 stores the count, Matroska
+    // and MPEG-TS only a duration, and piped Matroska neither
+    let fixtures = [
+        ("stored.mp4", "mp4", false, Some(FRAMES)),
+        ("duration.mkv", "matroska", false, Some(FRAMES)),
+        ("timestamps.ts", "mpegts", false, Some(FRAMES)),
+        ("unknown.mkv", "matroska", true, None),
+    ];
+    for (name, format, piped, expected) in fixtures {
+        let path = dir.join(name);
+        generate_input(&path, format, piped);
+        let metadata = engine::read_metadata(&path).unwrap();
+        match expected {
+            Some(frames) => assert!(
+                (metadata.nb_frames - frames).abs() <= 1,
+                "{}: {} frames, expected {}",
+                name,
+                metadata.nb_frames,
+                frames
+            ),
+            None => assert_eq!(metadata.nb_frames, 0, "{}", name),
+        }
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+/// Every progress update of a render of `input`
+fn render(input: &Path, output: &Path) -> Vec<Progress> {
+    let sprite = white_sprite(8);
+    let points: Vec<CPoint> = (0..=20)
+        .map(|i| CPoint {
+            x: 20.0 + i as f32 * 10.0,
+            y: 120.0,
+            timestamp_ms: f64::from(i) * 50.0,
+        })
+        .collect();
+    let opts = ProcessingOptions {
+        frame_rate: 30,
+        ..ProcessingOptions::default()
+    };
+    let mut updates = Vec::new();
+    engine::process_video(input, output, &sprite, &points, &opts, |p: Progress| {
+        updates.push(p)
+    })
+    .unwrap();
+    updates
+}
+
+#[test]
+fn progress_is_indeterminate_for_an_input_of_unknown_length() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("indeterminate-progress");
+    let (known, unknown) = (dir.join("known.mkv"), dir.join("unknown.mkv"));
+    generate_input(&known, "matroska", false);
+    generate_input(&unknown, "matroska", true);
+
+    // Known length: the fraction climbs to 1.0 and never goes indeterminate
+    let updates = render(&known, &dir.join("known.mp4"));
+    assert!(updates
+        .iter()
+        .any(|p| p.fraction > 0.15 && p.fraction < 1.0));
+    assert!(updates
+        .windows(2)
+        .all(|pair| pair[0].fraction <= pair[1].fraction));
+    assert_eq!(updates.last().unwrap().fraction, 1.0);
+
+    // Unknown length: indeterminate while rendering, with the frame count
+    // still going up, and done at the end
+    let updates = render(&unknown, &dir.join("unknown.mp4"));
+    let indeterminate: Vec<u64> = updates
+        .iter()
+        .filter(|p| p.fraction == INDETERMINATE_PROGRESS)
+        .map(|p| p.frames)
+        .collect();
+    assert!(!indeterminate.is_empty());
+    assert!(indeterminate.windows(2).all(|pair| pair[0] < pair[1]));
+    let last = updates.last().unwrap();
+    assert_eq!(last.fraction, 1.0);
+    assert_eq!(last.frames, FRAMES as u64);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,116 @@
+// gif.rs - Animated GIF export renders at the GIF's own frame rate and size
+// and refuses inputs too long to hold in memory
+//
+// Inputs are generated with the ffmpeg CLI and outputs are read back with
+// ffprobe; those tests are skipped when either is missing.
+mod common;
+
+use common::{
+    decoded_frames, drag, ffmpeg_command, generate, probe, tools_available, white_sprite, work_dir,
+};
+use std::path::Path;
+use video_effects_processor::engine::{
+    self, Container, EngineError, GifExport, ProcessingOptions, Progress, VideoCodec,
+    MAX_GIF_FRAME_RATE,
+};
+
+/// A `duration_s` long, 320x240 test pattern at 30 fps
+fn generate_input(path: &Path, duration_s: u32) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=320x240:rate=30"])
+            .args(["-t", &duration_s.to_string()])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+fn gif_options(gif: GifExport) -> ProcessingOptions {
+    ProcessingOptions {
+        codec: VideoCodec::Gif,
+        gif,
+        ..ProcessingOptions::default()
+    }
+}
+
+fn render(
+    input: &Path,
+    output: &Path,
+    duration_s: u32,
+    opts: &ProcessingOptions,
+) -> Result<(), EngineError> {
+    let sprite = white_sprite(8);
+    let points = drag((40.0, 40.0), (280.0, 200.0), f64::from(duration_s) * 1000.0);
+    engine::process_video(input, output, &sprite, &points, opts, |_: Progress| {}).map(|_| ())
+}
+
+#[test]
+fn two_second_clip_exports_at_the_gif_rate_and_size() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("gif");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.gif"));
+    generate_input(&input, 2);
+    let opts = gif_options(GifExport {
+        frame_rate: 15,
+        max_width: 160,
+        loop_forever: true,
+    });
+    render(&input, &output, 2, &opts).unwrap();
+
+    assert_eq!(
+        probe(&output, "v:0", "stream=codec_name,width,height"),
+        "gif,160,120"
+    );
+    assert_eq!(decoded_frames(&output), 30);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn inputs_over_thirty_seconds_are_refused() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("gif-long");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.gif"));
+    generate_input(&input, 31);
+    let result = render(&input, &output, 31, &gif_options(GifExport::default()));
+    assert!(
+        matches!(result, Err(EngineError::GifTooLong { duration_ms }) if duration_ms > 30_000.0),
+        "{:?}",
+        result
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn gif_only_goes_into_gif_files() {
+    // Checked before the input is opened, so no input is needed
+    let dir = work_dir("gif-mp4");
+    let opts = gif_options(GifExport::default());
+    let result = render(&dir.join("missing.mp4"), &dir.join("output.mp4"), 1, &opts);
+    assert!(matches!(
+        result,
+        Err(EngineError::UnsupportedOutput {
+            codec: VideoCodec::Gif,
+            container: Container::Mp4,
+        })
+    ));
+    assert!(!Container::Gif.supports(VideoCodec::H264));
+    assert!(!Container::Mkv.supports(VideoCodec::Gif));
+    assert_eq!(
+        Container::Auto.resolve(Path::new("clip.GIF")),
+        Container::Gif
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn gif_frame_rate_is_clamped() {
+    let opts = gif_options(GifExport {
+        frame_rate: 120,
+        ..GifExport::default()
+    });
+    assert_eq!(opts.validated().unwrap().gif.frame_rate, MAX_GIF_FRAME_RATE);
+}
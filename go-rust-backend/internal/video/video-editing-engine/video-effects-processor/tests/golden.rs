@@ -0,0 +1,934 @@
+// golden.rs - Full-buffer regression tests for the cursor compositor and the
+// styled background
+//
+// Each scenario composites a synthetic sprite onto a gradient (or flat gray)
+// background through the public `composite_cursor_on_buffer` API, draws
+// overlay layers (ripples, trail, badges, ...) onto the gradient, or insets
+// the gradient onto a styled background with `FrameStyleOverlay`, and
+// compares every pixel against tests/golden/<scenario>.png.
+//
+//   UPDATE_GOLDENS=1      rewrite the goldens from the current output
+//   GOLDEN_TOLERANCE=<n>  largest accepted per-channel difference (default 1)
+//
+// Failing scenarios write <scenario>.actual.png and <scenario>.diff.png (the
+// difference amplified 8x) to go-rust-backend/output/golden.
+use ab_glyph::FontArc;
+use image::RgbaImage;
+use std::path::PathBuf;
+use video_effects_processor::engine::{
+    composite_cursor_on_buffer, composite_cursor_yuv420, load_cursor_sprite, AnimationFrame,
+    BadgeCorner, CClickEvent, ClickEffect, ClickRipples, CursorKindEvent, CursorShadow,
+    CursorSprite, CursorStyle, FrameBackground, FrameStyle, FrameStyleOverlay, FrameView, KeyEvent,
+    KeystrokeDisplay, KeystrokeOverlay, MotionTrail, Overlay, RgbaBufferMut, SamplingQuality,
+    Spotlight, SpotlightOverlay, SpriteSchedule, Yuv420Frame, YuvSprite, BUTTON_LEFT, BUTTON_RIGHT,
+};
+
+const WIDTH: u32 = 96;
+const HEIGHT: u32 = 64;
+/// Row padding past the pixels, which must come back untouched
+const PADDING: usize = 12;
+const STRIDE: usize = WIDTH as usize * 4 + PADDING;
+const DEFAULT_TOLERANCE: u8 = 1;
+/// Diff images scale each channel's difference by this
+const DIFF_GAIN: u8 = 8;
+
+struct Scenario {
+    name: &'static str,
+    sprite: fn() -> CursorSprite,
+    x: f32,
+    y: f32,
+    style: CursorStyle,
+}
+
+fn scenarios() -> Vec<Scenario> {
+    vec![
+        Scenario {
+            name: "opaque_sprite",
+            sprite: opaque_arrow,
+            x: 40.0,
+            y: 20.0,
+            style: CursorStyle::default(),
+        },
+        Scenario {
+            name: "feathered_sprite",
+            sprite: feathered_disc,
+            x: 48.0,
+            y: 32.0,
+            style: CursorStyle {
+                hotspot_x: 12.0,
+                hotspot_y: 12.0,
+                ..CursorStyle::default()
+            },
+        },
+        Scenario {
+            name: "subpixel_position",
+            sprite: feathered_disc,
+            x: 48.3,
+            y: 31.7,
+            style: CursorStyle {
+                hotspot_x: 12.0,
+                hotspot_y: 12.0,
+                ..CursorStyle::default()
+            },
+        },
+        Scenario {
+            name: "edge_clipping",
+            sprite: opaque_arrow,
+            x: WIDTH as f32 - 6.5,
+            y: HEIGHT as f32 - 9.25,
+            style: CursorStyle {
+                opacity: 0.8,
+                ..CursorStyle::default()
+            },
+        },
+        Scenario {
+            name: "scaled_sprite",
+            sprite: feathered_disc,
+            x: 10.4,
+            y: 6.6,
+            style: CursorStyle {
+                scale: 2.5,
+                ..CursorStyle::default()
+            },
+        },
+        Scenario {
+            name: "scaled_bicubic",
+            sprite: feathered_disc,
+            x: 10.4,
+            y: 6.6,
+            style: CursorStyle {
+                scale: 2.5,
+                sampling: SamplingQuality::Bicubic,
+                ..CursorStyle::default()
+            },
+        },
+        // A 1 px outline half a pixel off the grid, softened by bilinear
+        // and kept sharper by bicubic
+        Scenario {
+            name: "subpixel_arrow_bilinear",
+            sprite: opaque_arrow,
+            x: 40.5,
+            y: 20.25,
+            style: CursorStyle::default(),
+        },
+        Scenario {
+            name: "subpixel_arrow_bicubic",
+            sprite: opaque_arrow,
+            x: 40.5,
+            y: 20.25,
+            style: CursorStyle {
+                sampling: SamplingQuality::Bicubic,
+                ..CursorStyle::default()
+            },
+        },
+    ]
+}
+
+/// Styled backgrounds around the gradient frame: an obvious two-stop
+/// gradient with a soft offset shadow, and a plain square inset
+fn frame_styles() -> Vec<(&'static str, FrameStyle)> {
+    vec![
+        (
+            "frame_style_gradient",
+            FrameStyle {
+                padding_percent: 12.0,
+                corner_radius_px: 10.0,
+                background: FrameBackground::Gradient {
+                    top: [30, 30, 90],
+                    bottom: [240, 200, 40],
+                },
+                shadow_blur_px: 6.0,
+                shadow_opacity: 0.6,
+                shadow_offset_x: 2.0,
+                shadow_offset_y: 3.0,
+            },
+        ),
+        (
+            "frame_style_solid",
+            FrameStyle {
+                padding_percent: 20.0,
+                corner_radius_px: 0.0,
+                background: FrameBackground::Solid([20, 120, 60]),
+                shadow_opacity: 0.0,
+                ..FrameStyle::default()
+            },
+        ),
+    ]
+}
+
+#[test]
+fn compositor_matches_goldens() {
+    let failures: Vec<String> = scenarios()
+        .iter()
+        .filter_map(|scenario| {
+            let mut data = gradient_buffer();
+            let buffer = RgbaBufferMut {
+                data: &mut data,
+                width: WIDTH,
+                height: HEIGHT,
+                stride: STRIDE,
+            };
+            composite_cursor_on_buffer(
+                buffer,
+                &(scenario.sprite)(),
+                scenario.x,
+                scenario.y,
+                &scenario.style,
+            )
+            .unwrap();
+            check_golden(scenario.name, &unpadded(scenario.name, &data))
+        })
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "golden mismatches:\n{}",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn frame_style_matches_goldens() {
+    let failures: Vec<String> = frame_styles()
+        .iter()
+        .filter_map(|(name, style)| {
+            let mut data = gradient_buffer();
+            FrameStyleOverlay::new(style, WIDTH, HEIGHT)
+                .render(&mut FrameView::new(&mut data, WIDTH, HEIGHT, STRIDE), 0.0);
+            check_golden(name, &unpadded(name, &data))
+        })
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "golden mismatches:\n{}",
+        failures.join("\n")
+    );
+}
+
+/// A white disc feathered into a mid-gray frame: premultiplied blending
+/// keeps every edge pixel between the two, where straight-alpha sampling
+/// pulled the transparent texels' black in as a dark ring
+#[test]
+fn feathered_edge_has_no_dark_fringe() {
+    const GRAY: u8 = 128;
+    let mut data = padded_buffer(|_, _| [GRAY, GRAY, GRAY, 255]);
+    let buffer = RgbaBufferMut {
+        data: &mut data,
+        width: WIDTH,
+        height: HEIGHT,
+        stride: STRIDE,
+    };
+    let style = CursorStyle {
+        hotspot_x: 12.0,
+        hotspot_y: 12.0,
+        ..CursorStyle::default()
+    };
+    composite_cursor_on_buffer(buffer, &feathered_white_disc(), 48.3, 31.7, &style).unwrap();
+    let actual = unpadded("feathered_white_on_gray", &data);
+
+    let darkest = actual
+        .pixels()
+        .flat_map(|p| p.0[..3].to_vec())
+        .min()
+        .unwrap();
+    assert!(
+        darkest >= GRAY,
+        "edge pixel at {}, darker than the gray",
+        darkest
+    );
+    if let Some(failure) = check_golden("feathered_white_on_gray", &actual) {
+        panic!("golden mismatch:\n{}", failure);
+    }
+}
+
+/// A 4-frame, 100 ms/frame animation drawn at five times, left to right:
+/// 0 ms, 150 ms, 250 ms, 399 ms and 450 ms (wrapped round to the first frame)
+#[test]
+fn animation_frame_matches_golden() {
+    let frames = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]]
+        .into_iter()
+        .map(|color| AnimationFrame {
+            sprite: solid_square(color),
+            duration_ms: 100,
+        })
+        .collect();
+    let sprite = CursorSprite::animated(frames).unwrap();
+    let times = [0.0, 150.0, 250.0, 399.0, 450.0];
+    let indices: Vec<usize> = times.iter().map(|&t| sprite.frame_index(t)).collect();
+    assert_eq!(indices, [0, 1, 2, 3, 0]);
+
+    check_frame_golden("animation_frame", |frame| {
+        for (i, &t) in times.iter().enumerate() {
+            let x = 4.0 + 18.0 * i as f32;
+            frame.composite_sprite(sprite.frame_at(t), x, 26.0, &CursorStyle::default());
+        }
+    });
+}
+
+/// The arrow turned about its tip by 0, 45, 90 and 180 degrees (clockwise),
+/// as `orient_to_velocity` turns it toward the heading
+#[test]
+fn rotation_matches_golden() {
+    check_frame_golden("rotation", |frame| {
+        for (i, degrees) in [0.0f32, 45.0, 90.0, 180.0].into_iter().enumerate() {
+            let style = CursorStyle {
+                rotation: degrees.to_radians(),
+                ..CursorStyle::default()
+            };
+            frame.composite_sprite(&opaque_arrow(), 12.0 + 22.0 * i as f32, 24.5, &style);
+        }
+    });
+}
+
+/// Left: one left click halfway through its ripple. Right: a double click,
+/// the second press a right click 100 ms after the first, its ring (just
+/// starting) drawn over the older one.
+#[test]
+fn click_ripples_match_goldens() {
+    let click = |x, timestamp_ms, button| CClickEvent {
+        x,
+        y: 32.0,
+        timestamp_ms,
+        button,
+    };
+    let clicks = [
+        click(24.0, 800.0, BUTTON_LEFT),
+        click(70.0, 900.0, BUTTON_LEFT),
+        click(70.0, 1000.0, BUTTON_RIGHT),
+    ];
+    let effect = ClickEffect {
+        end_radius_px: 20.0,
+        ..ClickEffect::default()
+    };
+    let ripples = ClickRipples {
+        clicks: &clicks,
+        effect,
+        gamma_correct_blend: false,
+    };
+    check_frame_golden("click_ripple", |frame| ripples.render(frame, 1000.0));
+
+    // Every ring is gone at the end of its lifetime
+    let mut data = gradient_buffer();
+    ripples.render(
+        &mut FrameView::new(&mut data, WIDTH, HEIGHT, STRIDE),
+        1400.0,
+    );
+    assert!(data == gradient_buffer());
+}
+
+/// The arrow over its shadow: the default soft shadow, a hard one (no blur)
+/// and a soft one cut off at the bottom-right corner
+#[test]
+fn cursor_shadow_matches_golden() {
+    let sprite = opaque_arrow();
+    let hard = CursorShadow {
+        blur_radius_px: 0.0,
+        opacity: 0.6,
+        ..CursorShadow::default()
+    };
+    let shadows = [
+        (CursorShadow::default(), 12.0, 14.0),
+        (hard, 44.0, 14.0),
+        (CursorShadow::default(), 86.5, 52.5),
+    ];
+    check_frame_golden("cursor_shadow", |frame| {
+        let style = CursorStyle::default();
+        for (shadow, x, y) in shadows {
+            let (shadow_x, shadow_y, shadow_style) = shadow.placement(x, y, &style);
+            frame.composite_sprite(
+                &shadow.silhouette(&sprite),
+                shadow_x,
+                shadow_y,
+                &shadow_style,
+            );
+            frame.composite_sprite(&sprite, x, y, &style);
+        }
+    });
+}
+
+#[test]
+fn motion_trail_matches_golden() {
+    let sprite = opaque_arrow();
+    let trail = MotionTrail::default();
+    // Rightward motions reaching (x, y) at the frame: px per ms and the time
+    // the cursor is shown from
+    let motions = [
+        // Fast: a full trail
+        ((50.0, 6.0), 1.2, f64::NEG_INFINITY),
+        // Under a pixel over the whole frame: none
+        ((80.0, 6.0), 0.02, f64::NEG_INFINITY),
+        // Shown only 10 ms before the frame: cut short
+        ((50.0, 38.0), 1.2, -10.0),
+    ];
+    let position_at = |((x, y), speed, shown_from): ((f32, f32), f32, f64), t: f64| {
+        if t < shown_from {
+            (f32::NAN, f32::NAN)
+        } else {
+            (x + speed * t as f32, y)
+        }
+    };
+    // 33 ms frames
+    let ghosts: Vec<Vec<(f32, f32, f32)>> = motions
+        .iter()
+        .map(|&motion| trail.ghosts(|t| position_at(motion, t), 0.0, 33.0))
+        .collect();
+    let counts: Vec<usize> = ghosts.iter().map(Vec::len).collect();
+    assert_eq!(counts, [4, 0, 1]);
+
+    check_frame_golden("motion_trail", |frame| {
+        let style = CursorStyle::default();
+        for (((x, y), _, _), ghosts) in motions.iter().zip(&ghosts) {
+            for &(gx, gy, opacity) in ghosts {
+                frame.composite_sprite(&sprite, gx, gy, &CursorStyle { opacity, ..style });
+            }
+            frame.composite_sprite(&sprite, *x, *y, &style);
+        }
+    });
+}
+
+#[test]
+fn overlay_layers_match_golden() {
+    let stamp = |sprite, x, y, opacity| -> Box<dyn Overlay> {
+        Box::new(Stamp {
+            sprite,
+            x,
+            y,
+            opacity,
+        })
+    };
+    // The same red, green and cursor layers, listed in opposite orders
+    let left = [
+        stamp(solid_square([255, 0, 0]), 10.0, 20.0, 0.7),
+        stamp(solid_square([0, 255, 0]), 16.0, 26.0, 0.7),
+        stamp(opaque_arrow(), 20.0, 24.0, 1.0),
+    ];
+    let right = [
+        stamp(opaque_arrow(), 68.0, 24.0, 1.0),
+        stamp(solid_square([0, 255, 0]), 64.0, 26.0, 0.7),
+        stamp(solid_square([255, 0, 0]), 58.0, 20.0, 0.7),
+    ];
+    check_frame_golden("overlay_layers", |frame| {
+        for layer in left.iter().chain(&right) {
+            layer.render(frame, 0.0);
+        }
+    });
+}
+
+/// A sprite at a fixed spot, as a stand-in for a real layer
+struct Stamp {
+    sprite: CursorSprite,
+    x: f32,
+    y: f32,
+    opacity: f32,
+}
+
+impl Overlay for Stamp {
+    fn render(&self, frame: &mut FrameView<'_>, _timestamp_ms: f64) {
+        let style = CursorStyle {
+            opacity: self.opacity,
+            ..CursorStyle::default()
+        };
+        frame.composite_sprite(&self.sprite, self.x, self.y, &style);
+    }
+}
+
+#[test]
+fn keystroke_badges_match_golden() {
+    let Some(font) = dejavu_sans() else {
+        eprintln!("DejaVu Sans not found, skipping the keystroke badge golden");
+        return;
+    };
+    let key = |timestamp_ms, text: &str| KeyEvent {
+        timestamp_ms,
+        text: text.to_string(),
+    };
+    let display = KeystrokeDisplay {
+        font_size_px: 12.0,
+        margin_px: 4.0,
+        hold_ms: 1000.0,
+        fade_ms: 200.0,
+        ..KeystrokeDisplay::default()
+    };
+    // Two presses merged into one badge, drawn at full opacity and halfway
+    // through the fade
+    let keys = [key(0.0, "⌘"), key(300.0, "Shift+4")];
+    let bottom_left = KeystrokeOverlay::new(&keys, &display, font.clone(), false);
+    let top_right = KeystrokeOverlay::new(
+        &keys,
+        &KeystrokeDisplay {
+            corner: BadgeCorner::TopRight,
+            ..display.clone()
+        },
+        font,
+        false,
+    );
+    check_frame_golden("keystroke_badge", |frame| {
+        bottom_left.render(frame, 500.0);
+        top_right.render(frame, 1200.0);
+    });
+
+    // Gone once the hold runs out
+    let mut data = gradient_buffer();
+    top_right.render(
+        &mut FrameView::new(&mut data, WIDTH, HEIGHT, STRIDE),
+        1300.0,
+    );
+    assert!(data == gradient_buffer());
+}
+
+/// DejaVu Sans, which the keystroke badge golden was drawn with, if installed
+fn dejavu_sans() -> Option<FontArc> {
+    [
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    ]
+    .iter()
+    .find_map(|path| FontArc::try_from_vec(std::fs::read(path).ok()?).ok())
+}
+
+#[test]
+fn yuv420_compositing_matches_rgba_and_golden() {
+    let disc = CursorStyle {
+        hotspot_x: 12.0,
+        hotspot_y: 12.0,
+        ..CursorStyle::default()
+    };
+    let faded = CursorStyle {
+        opacity: 0.5,
+        ..CursorStyle::default()
+    };
+    // Whole-pixel positions, even and odd so sprites straddle chroma blocks
+    let draws = [
+        (opaque_arrow(), 20.0, 10.0, CursorStyle::default()),
+        (opaque_arrow(), 41.0, 33.0, faded),
+        (feathered_disc(), 70.0, 25.0, disc),
+    ];
+
+    let mut rgba = gradient_buffer();
+    let mut frame = FrameView::new(&mut rgba, WIDTH, HEIGHT, STRIDE);
+    for (sprite, x, y, style) in &draws {
+        frame.composite_sprite(sprite, *x, *y, style);
+    }
+    let expected = bt601_420(&rgba);
+
+    let mut planes = bt601_420(&gradient_buffer());
+    let [y_plane, u_plane, v_plane] = &mut planes;
+    let mut frame = Yuv420Frame {
+        planes: [y_plane, u_plane, v_plane],
+        strides: [WIDTH as usize, WIDTH as usize / 2, WIDTH as usize / 2],
+        width: WIDTH,
+        height: HEIGHT,
+        bit_depth: 8,
+    };
+    for (sprite, x, y, style) in &draws {
+        composite_cursor_yuv420(&mut frame, &YuvSprite::new(sprite), *x, *y, style);
+    }
+    // Chroma is subsampled after blending on one path and before on the other
+    for (plane, (actual, expected)) in ["Y", "U", "V"].iter().zip(planes.iter().zip(&expected)) {
+        let worst = actual
+            .iter()
+            .zip(expected)
+            .map(|(a, e)| a.abs_diff(*e))
+            .max()
+            .unwrap();
+        assert!(
+            worst <= 2,
+            "{}: off by up to {} from the RGBA path",
+            plane,
+            worst
+        );
+    }
+
+    if let Some(failure) = check_golden("yuv420_cursor", &bt601_to_rgba(&planes)) {
+        panic!("golden mismatch:\n{}", failure);
+    }
+}
+
+/// 8-bit limited-range BT.601 4:2:0 planes of a padded RGBA buffer, each
+/// chroma sample averaged over its 2x2 block
+fn bt601_420(data: &[u8]) -> [Vec<u8>; 3] {
+    let rgb = |x: u32, y: u32| {
+        let i = y as usize * STRIDE + x as usize * 4;
+        [data[i], data[i + 1], data[i + 2]].map(f32::from)
+    };
+    let luma = |[r, g, b]: [f32; 3]| 16.0 + 0.256_788 * r + 0.504_129 * g + 0.097_906 * b;
+    let cb = |[r, g, b]: [f32; 3]| 128.0 - 0.148_223 * r - 0.290_993 * g + 0.439_216 * b;
+    let cr = |[r, g, b]: [f32; 3]| 128.0 + 0.439_216 * r - 0.367_788 * g - 0.071_427 * b;
+    let y_plane = (0..HEIGHT)
+        .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+        .map(|(x, y)| luma(rgb(x, y)).round() as u8)
+        .collect();
+    let block = |x: u32, y: u32, channel: &dyn Fn([f32; 3]) -> f32| {
+        let sum: f32 = [(0, 0), (1, 0), (0, 1), (1, 1)]
+            .iter()
+            .map(|(dx, dy)| channel(rgb(2 * x + dx, 2 * y + dy)))
+            .sum();
+        (sum / 4.0).round() as u8
+    };
+    let chroma = |channel: &dyn Fn([f32; 3]) -> f32| {
+        (0..HEIGHT / 2)
+            .flat_map(|y| (0..WIDTH / 2).map(move |x| (x, y)))
+            .map(|(x, y)| block(x, y, channel))
+            .collect()
+    };
+    [y_plane, chroma(&cb), chroma(&cr)]
+}
+
+/// RGBA image of limited-range BT.601 4:2:0 planes (chroma upsampled by
+/// repetition)
+fn bt601_to_rgba([y_plane, u_plane, v_plane]: &[Vec<u8>; 3]) -> RgbaImage {
+    RgbaImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        let luma = 1.164_383 * (f32::from(y_plane[(y * WIDTH + x) as usize]) - 16.0);
+        let c = ((y / 2) * (WIDTH / 2) + x / 2) as usize;
+        let (u, v) = (f32::from(u_plane[c]) - 128.0, f32::from(v_plane[c]) - 128.0);
+        let channel = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+        image::Rgba([
+            channel(luma + 1.596_027 * v),
+            channel(luma - 0.391_762 * u - 0.812_968 * v),
+            channel(luma + 2.017_232 * u),
+            255,
+        ])
+    })
+}
+
+#[test]
+fn cursor_kind_switches_match_golden() {
+    let change = |timestamp_ms, kind: &str| CursorKindEvent {
+        timestamp_ms,
+        kind: kind.to_string(),
+    };
+    let events = [
+        change(100.0, "ibeam"),
+        change(200.0, "pointing_hand"),
+        change(300.0, "zoom_in"),
+    ];
+    let schedule = SpriteSchedule::new(&events, &["ibeam", "pointing_hand"]);
+    // Each sprite carries its own hotspot: the arrow tip, the I-beam's
+    // middle and the top middle of a green square
+    let ibeam = CursorSprite {
+        hotspot_x: 1.0,
+        hotspot_y: 7.0,
+        ..CursorSprite::from_rgba8([0, 0, 0, 255].repeat(3 * 14), 3, 14, false)
+    };
+    let hand = CursorSprite {
+        hotspot_x: 6.0,
+        hotspot_y: 0.0,
+        ..solid_square([0, 200, 0])
+    };
+    let sprites = [opaque_arrow(), ibeam, hand];
+
+    // Just before and at each change; the unknown kind falls back to the arrow
+    let times = [99.9, 100.0, 199.9, 200.0, 300.0];
+    let indices: Vec<usize> = times.iter().map(|&t| schedule.index_at(t)).collect();
+    assert_eq!(indices, [0, 1, 1, 2, 0]);
+
+    check_frame_golden("cursor_kind", |frame| {
+        for (i, index) in indices.into_iter().enumerate() {
+            let x = 8.0 + 18.0 * i as f32;
+            frame.composite_sprite(&sprites[index], x, 30.0, &CursorStyle::default());
+        }
+    });
+}
+
+#[test]
+fn cur_and_ani_fixtures_match_golden() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let cur = load_cursor_sprite(&fixtures.join("arrow.cur")).unwrap();
+    let ani = load_cursor_sprite(&fixtures.join("busy.ani")).unwrap();
+    // Each on its own hotspot: the .cur once, then every .ani step in a row
+    check_frame_golden("cur_ani_fixtures", |frame| {
+        let style = CursorStyle::default();
+        frame.composite_sprite(&cur, 10.0, 10.0, &style);
+        for (i, step) in ani.animation.iter().enumerate() {
+            let x = 48.0 + 12.0 * i as f32;
+            frame.composite_sprite(&step.sprite, x, 50.0, &style);
+        }
+    });
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn svg_sprite_matches_golden() {
+    use video_effects_processor::engine::{load_cursor_sprite_for, ProcessingOptions};
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/triangle.svg");
+    let native = load_cursor_sprite(&path).unwrap();
+    let mut opts = ProcessingOptions {
+        cursor_scale: 3.0,
+        ..ProcessingOptions::default()
+    };
+    let rasterized = load_cursor_sprite_for(&path, &mut opts).unwrap();
+    assert_eq!(opts.cursor_scale, 1.0);
+    let tripled = CursorStyle {
+        scale: 3.0,
+        ..CursorStyle::default()
+    };
+    // Native size, rasterized at 3x, and the native raster upscaled 3x
+    check_frame_golden("svg_sprite", |frame| {
+        let style = CursorStyle::default();
+        frame.composite_sprite(&native, 4.0, 27.0, &style);
+        frame.composite_sprite(&rasterized, 20.0, 17.0, &style);
+        frame.composite_sprite(&native, 58.0, 17.0, &tripled);
+    });
+}
+
+#[test]
+fn spotlight_matches_golden() {
+    let spotlight = Spotlight {
+        radius_px: 14.0,
+        feather_px: 10.0,
+        dim_opacity: 0.6,
+    };
+    // Following a cursor that runs off the right edge: the circle is cut there
+    let center = |t: f64| (60.0 + 0.1 * t as f32, 24.0);
+    let overlay = SpotlightOverlay::new(spotlight, center);
+
+    let mut data = gradient_buffer();
+    overlay.render(&mut FrameView::new(&mut data, WIDTH, HEIGHT, STRIDE), 300.0);
+    let pixel = |data: &[u8], x: usize, y: usize| {
+        let i = y * STRIDE + x * 4;
+        [data[i], data[i + 1], data[i + 2], data[i + 3]]
+    };
+    // Dimmed by exactly the configured amount far away, untouched at the center
+    let [r, g, b, a] = gradient(0, HEIGHT - 1);
+    let dim = |v: u8| (f32::from(v) * 0.4).round() as u8;
+    assert_eq!(
+        pixel(&data, 0, HEIGHT as usize - 1),
+        [dim(r), dim(g), dim(b), a]
+    );
+    assert_eq!(pixel(&data, 90, 24), gradient(90, 24));
+
+    check_frame_golden("spotlight", |frame| {
+        overlay.render(frame, 300.0);
+        let (x, y) = center(300.0);
+        frame.composite_sprite(&opaque_arrow(), x, y, &CursorStyle::default());
+    });
+}
+
+#[test]
+fn integer_positions_copy_the_sprite_under_both_samplers() {
+    let draw = |sprite: &CursorSprite, sampling| {
+        let mut data = gradient_buffer();
+        let style = CursorStyle {
+            sampling,
+            ..CursorStyle::default()
+        };
+        FrameView::new(&mut data, WIDTH, HEIGHT, STRIDE)
+            .composite_sprite(sprite, 30.0, 20.0, &style);
+        data
+    };
+    // The opaque arrow lands texel for texel; transparent texels keep the gradient
+    let arrow = opaque_arrow();
+    for sampling in [SamplingQuality::Bilinear, SamplingQuality::Bicubic] {
+        let data = draw(&arrow, sampling);
+        for (i, texel) in arrow.data.chunks_exact(4).enumerate() {
+            let (x, y) = (30 + i as u32 % arrow.width, 20 + i as u32 / arrow.width);
+            let at = y as usize * STRIDE + x as usize * 4;
+            let expected = if texel[3] == 255 {
+                [texel[0], texel[1], texel[2], 255]
+            } else {
+                gradient(x, y)
+            };
+            assert_eq!(
+                data[at..at + 4],
+                expected,
+                "{:?} at ({}, {})",
+                sampling,
+                x,
+                y
+            );
+        }
+    }
+    // Translucent texels blend the same whichever sampler reads them
+    let disc = feathered_disc();
+    assert!(draw(&disc, SamplingQuality::Bilinear) == draw(&disc, SamplingQuality::Bicubic));
+}
+
+/// Draw onto a fresh gradient buffer and compare it with the golden `name`,
+/// panicking on a mismatch
+fn check_frame_golden(name: &str, draw: impl FnOnce(&mut FrameView<'_>)) {
+    let mut data = gradient_buffer();
+    draw(&mut FrameView::new(&mut data, WIDTH, HEIGHT, STRIDE));
+    if let Some(failure) = check_golden(name, &unpadded(name, &data)) {
+        panic!("golden mismatch:\n{}", failure);
+    }
+}
+
+/// Compare `actual` against tests/golden/`name`.png, or rewrite the golden
+/// under UPDATE_GOLDENS. Describes the failure, if any.
+fn check_golden(name: &str, actual: &RgbaImage) -> Option<String> {
+    let golden_path = golden_dir().join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_GOLDENS").is_some_and(|v| v != "0") {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        actual.save(&golden_path).unwrap();
+        return None;
+    }
+    let tolerance = std::env::var("GOLDEN_TOLERANCE")
+        .ok()
+        .map(|v| v.parse().expect("GOLDEN_TOLERANCE must be 0-255"))
+        .unwrap_or(DEFAULT_TOLERANCE);
+    let golden = match image::open(&golden_path) {
+        Ok(golden) => golden.to_rgba8(),
+        Err(e) => return Some(format!("{}: {} ({})", name, e, golden_path.display())),
+    };
+    let mismatch = compare(actual, &golden, tolerance)?;
+    let dir = ensure_repo_output_dir().join("golden");
+    std::fs::create_dir_all(&dir).unwrap();
+    actual
+        .save(dir.join(format!("{}.actual.png", name)))
+        .unwrap();
+    if golden.dimensions() == actual.dimensions() {
+        diff_image(actual, &golden)
+            .save(dir.join(format!("{}.diff.png", name)))
+            .unwrap();
+    }
+    Some(format!("{}: {} (see {})", name, mismatch, dir.display()))
+}
+
+/// The gradient in a buffer whose rows are padded with 0xA5
+fn gradient_buffer() -> Vec<u8> {
+    padded_buffer(gradient)
+}
+
+/// A buffer of `pixel(x, y)` whose rows are padded with 0xA5
+fn padded_buffer(pixel: impl Fn(u32, u32) -> [u8; 4]) -> Vec<u8> {
+    let mut data = vec![0xA5; STRIDE * HEIGHT as usize];
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let i = y as usize * STRIDE + x as usize * 4;
+            data[i..i + 4].copy_from_slice(&pixel(x, y));
+        }
+    }
+    data
+}
+
+/// The pixels of a `gradient_buffer`, checking its padding came back untouched
+fn unpadded(name: &str, data: &[u8]) -> RgbaImage {
+    let mut image = RgbaImage::new(WIDTH, HEIGHT);
+    for (y, row) in data.chunks_exact(STRIDE).enumerate() {
+        let (pixels, padding) = row.split_at(WIDTH as usize * 4);
+        assert!(
+            padding.iter().all(|&b| b == 0xA5),
+            "{}: row {} padding was written",
+            name,
+            y
+        );
+        let start = y * WIDTH as usize * 4;
+        image.as_mut()[start..start + pixels.len()].copy_from_slice(pixels);
+    }
+    image
+}
+
+/// Opaque background with every channel varying, so misplaced or
+/// mis-weighted samples show up as colour shifts
+fn gradient(x: u32, y: u32) -> [u8; 4] {
+    [
+        (x * 255 / (WIDTH - 1)) as u8,
+        (y * 255 / (HEIGHT - 1)) as u8,
+        ((x + y) * 4 % 256) as u8,
+        255,
+    ]
+}
+
+/// 12x16 opaque arrow: white fill, black outline, transparent elsewhere
+fn opaque_arrow() -> CursorSprite {
+    let (w, h) = (12u32, 16u32);
+    let mut data = Vec::with_capacity((w * h * 4) as usize);
+    for y in 0..h {
+        for x in 0..w {
+            let inside = x <= y && x + y / 2 < w;
+            let edge = inside && (x == 0 || x == y || x + y / 2 + 1 >= w || y == h - 1);
+            data.extend_from_slice(match (inside, edge) {
+                (true, true) => &[0, 0, 0, 255],
+                (true, false) => &[255, 255, 255, 255],
+                _ => &[0, 0, 0, 0],
+            });
+        }
+    }
+    CursorSprite::from_rgba8(data, w, h, false)
+}
+
+/// Opaque 12x12 square of `color`
+fn solid_square([r, g, b]: [u8; 3]) -> CursorSprite {
+    CursorSprite::from_rgba8([r, g, b, 255].repeat(12 * 12), 12, 12, false)
+}
+
+/// 24x24 orange disc whose alpha ramps to zero over its outer ~4 px
+fn feathered_disc() -> CursorSprite {
+    feathered([255, 140, 20])
+}
+
+/// `feathered_disc` in white; its transparent texels are black
+fn feathered_white_disc() -> CursorSprite {
+    feathered([255, 255, 255])
+}
+
+fn feathered(color: [u8; 3]) -> CursorSprite {
+    let size = 24u32;
+    let center = size as f32 / 2.0;
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let r = (x as f32 + 0.5 - center).hypot(y as f32 + 0.5 - center);
+            let a = ((center - 1.0 - r) * 64.0).clamp(0.0, 255.0) as u8;
+            let rgb = if a == 0 { [0, 0, 0] } else { color };
+            data.extend_from_slice(&[rgb[0], rgb[1], rgb[2], a]);
+        }
+    }
+    CursorSprite::from_rgba8(data, size, size, false)
+}
+
+/// Describe the first difference above `tolerance`, if any
+fn compare(actual: &RgbaImage, golden: &RgbaImage, tolerance: u8) -> Option<String> {
+    if actual.dimensions() != golden.dimensions() {
+        return Some(format!(
+            "size {:?}, golden is {:?}",
+            actual.dimensions(),
+            golden.dimensions()
+        ));
+    }
+    let mut worst: Option<(u8, u32, u32)> = None;
+    let mut over = 0;
+    for (x, y, a) in actual.enumerate_pixels() {
+        let g = golden.get_pixel(x, y);
+        let diff = (0..4).map(|i| a[i].abs_diff(g[i])).max().unwrap();
+        if diff > tolerance {
+            over += 1;
+            if worst.is_none_or(|(d, ..)| diff > d) {
+                worst = Some((diff, x, y));
+            }
+        }
+    }
+    worst.map(|(diff, x, y)| {
+        format!(
+            "{} pixels differ by more than {}, worst {} at ({}, {}): {:?} vs golden {:?}",
+            over,
+            tolerance,
+            diff,
+            x,
+            y,
+            actual.get_pixel(x, y).0,
+            golden.get_pixel(x, y).0
+        )
+    })
+}
+
+/// Per-channel |actual - golden| amplified by `DIFF_GAIN`, opaque
+fn diff_image(actual: &RgbaImage, golden: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let (a, g) = (actual.get_pixel(x, y), golden.get_pixel(x, y));
+        let channel = |i: usize| a[i].abs_diff(g[i]).saturating_mul(DIFF_GAIN);
+        image::Rgba([channel(0), channel(1), channel(2), 255])
+    })
+}
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+/// The `output/` directory the recorder writes to (go-rust-backend/output),
+/// created if missing
+fn ensure_repo_output_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../../../output");
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.canonicalize().unwrap_or(dir)
+}
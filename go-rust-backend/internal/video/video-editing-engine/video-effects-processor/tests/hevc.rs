@@ -0,0 +1,70 @@
+// hevc.rs - HEVC exports land in containers that can carry them, tagged
+// `hvc1` in MP4 so QuickTime plays them
+//
+// The input is generated with the ffmpeg CLI and the output is read back
+// with ffprobe; the export test is skipped when either is missing or the
+// build has no libx265.
+mod common;
+
+use common::{drag, encoder_available, ffmpeg_command, generate, probe, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{
+    self, Container, EngineError, ProcessingOptions, Progress, VideoCodec,
+};
+
+/// A one second, 160x120 test pattern
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=160x120:rate=10"])
+            .args(["-t", "1"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+fn render(input: &Path, output: &Path, opts: &ProcessingOptions) -> Result<(), EngineError> {
+    let sprite = white_sprite(8);
+    let points = drag((20.0, 20.0), (140.0, 100.0), 1000.0);
+    engine::process_video(input, output, &sprite, &points, opts, |_: Progress| {}).map(|_| ())
+}
+
+#[test]
+fn hevc_export_is_tagged_hvc1_in_mp4() {
+    if !encoder_available("libx265") {
+        return;
+    }
+    let dir = work_dir("hevc");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+    let opts = ProcessingOptions {
+        codec: VideoCodec::Hevc,
+        ..ProcessingOptions::default()
+    };
+    render(&input, &output, &opts).unwrap();
+
+    assert_eq!(
+        probe(&output, "v:0", "stream=codec_name,codec_tag_string"),
+        "hevc,hvc1"
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn hevc_is_rejected_in_webm() {
+    // Checked before the input is opened, so no input is needed
+    let dir = work_dir("hevc-webm");
+    let opts = ProcessingOptions {
+        codec: VideoCodec::Hevc,
+        ..ProcessingOptions::default()
+    };
+    let result = render(&dir.join("missing.mp4"), &dir.join("output.webm"), &opts);
+    assert!(matches!(
+        result,
+        Err(EngineError::UnsupportedOutput {
+            codec: VideoCodec::Hevc,
+            container: Container::WebM,
+        })
+    ));
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,129 @@
+// image_sequence.rs - A directory of numbered PNG frames, or a frame_%05d.png
+// pattern, renders like a video, timed by the sequence frame rate
+//
+// The frames are written with the image crate; the tests are skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{probe, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{
+    self, CPoint, ProcessingOptions, Progress, RenderObserver, RenderWarning, SmoothingMode,
+    WarningKind,
+};
+
+const FRAMES: u64 = 30;
+
+/// `FRAMES` dark 320x240 frames, frame_00000.png onwards
+fn write_frames(dir: &Path) {
+    for i in 0..FRAMES {
+        let frame = image::RgbImage::from_pixel(320, 240, image::Rgb([32, 32, 32]));
+        frame.save(dir.join(format!("frame_{:05}.png", i))).unwrap();
+    }
+}
+
+/// Left to right across the frame over the second
+fn cursor_points() -> Vec<CPoint> {
+    (0..=60)
+        .map(|i| {
+            let ms = f64::from(i) * 1000.0 / 60.0;
+            CPoint {
+                x: 20.0 + 0.2 * ms as f32,
+                y: 120.0,
+                timestamp_ms: ms,
+            }
+        })
+        .collect()
+}
+
+/// Records the kind of each warning
+struct Warnings<'a>(&'a mut Vec<WarningKind>);
+
+impl RenderObserver for Warnings<'_> {
+    fn warning(&mut self, warning: &RenderWarning) {
+        self.0.push(warning.kind);
+    }
+}
+
+#[test]
+fn directory_of_frames_renders_a_second_with_the_cursor() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("image-sequence");
+    let frames = dir.join("frames");
+    std::fs::create_dir_all(&frames).unwrap();
+    write_frames(&frames);
+
+    let sprite = white_sprite(8);
+    let opts = ProcessingOptions {
+        frame_rate: 30,
+        sequence_frame_rate: 30,
+        smoothing_mode: SmoothingMode::Passthrough,
+        ..ProcessingOptions::default()
+    };
+    let output = dir.join("output.mp4");
+    let report = engine::process_video(
+        &frames,
+        &output,
+        &sprite,
+        &cursor_points(),
+        &opts,
+        |_: Progress| {},
+    )
+    .unwrap();
+    assert_eq!(report.frames_encoded, FRAMES);
+    assert_eq!(report.warnings.sequence_frames_missing, 0);
+    let duration_s: f64 = probe(&output, "", "format=duration").parse().unwrap();
+    assert!((duration_s - 1.0).abs() < 0.05);
+
+    // Halfway through, the cursor is halfway along its path
+    let png = dir.join("preview.png");
+    engine::render_preview_frame(&frames, &png, &sprite, &cursor_points(), &opts, 500.0).unwrap();
+    let image = image::open(&png).unwrap().to_rgba8();
+    let (mut sum, mut count) = (0.0, 0.0);
+    for (x, _, pixel) in image.enumerate_pixels() {
+        if pixel.0[..3].iter().all(|&c| c > 200) {
+            sum += x as f32;
+            count += 1.0;
+        }
+    }
+    assert!(count > 0.0, "no cursor in the preview");
+    assert!((sum / count - (20.0 + 100.0 + 4.0)).abs() < 2.0);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn missing_frames_in_a_pattern_are_held_with_a_warning() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("image-sequence-gaps");
+    write_frames(&dir);
+    for i in 10..13 {
+        std::fs::remove_file(dir.join(format!("frame_{:05}.png", i))).unwrap();
+    }
+    // Not a frame, whatever its name
+    std::fs::write(dir.join("frame_notes.png"), b"").unwrap();
+
+    let sprite = white_sprite(8);
+    let opts = ProcessingOptions {
+        frame_rate: 30,
+        sequence_frame_rate: 30,
+        ..ProcessingOptions::default()
+    };
+    let mut warnings = Vec::new();
+    let report = engine::process_video(
+        &dir.join("frame_%05d.png"),
+        &dir.join("output.mp4"),
+        &sprite,
+        &cursor_points(),
+        &opts,
+        Warnings(&mut warnings),
+    )
+    .unwrap();
+    assert_eq!(report.frames_encoded, FRAMES);
+    assert_eq!(report.warnings.sequence_frames_missing, 3);
+    assert!(warnings.contains(&WarningKind::SequenceFramesMissing));
+    std::fs::remove_dir_all(&dir).ok();
+}
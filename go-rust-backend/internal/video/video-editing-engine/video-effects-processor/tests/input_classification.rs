@@ -0,0 +1,195 @@
+// input_classification.rs - Inputs that are not a decodable video fail with
+// an error of their own: no video stream, a still image, or a packet that
+// will not decode (unless the options ask for a render anyway)
+//
+// The inputs are generated with the ffmpeg CLI; the tests are skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{ffmpeg, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{
+    self, CPoint, EngineError, ProcessingOptions, ProcessingReport, RenderObserver, RenderWarning,
+    WarningKind,
+};
+
+/// Frames in the corrupt input (one second at 30 fps)
+const FRAMES: u64 = 30;
+
+/// Records the kind of each warning
+struct Warnings<'a>(&'a mut Vec<WarningKind>);
+
+impl RenderObserver for Warnings<'_> {
+    fn warning(&mut self, warning: &RenderWarning) {
+        self.0.push(warning.kind);
+    }
+}
+
+fn render(
+    input: &Path,
+    output: &Path,
+    opts: &ProcessingOptions,
+) -> (Result<ProcessingReport, EngineError>, Vec<WarningKind>) {
+    let sprite = white_sprite(8);
+    let points: Vec<CPoint> = (0..=10)
+        .map(|i| CPoint {
+            x: 20.0 + i as f32 * 20.0,
+            y: 120.0,
+            timestamp_ms: f64::from(i) * 100.0,
+        })
+        .collect();
+    let mut warnings = Vec::new();
+    let result = engine::process_video(
+        input,
+        output,
+        &sprite,
+        &points,
+        opts,
+        Warnings(&mut warnings),
+    );
+    (result, warnings)
+}
+
+#[test]
+fn audio_only_input_names_the_streams_it_has() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("audio-only");
+    let input = dir.join("input.m4a");
+    ffmpeg(
+        &[
+            "-f",
+            "lavfi",
+            "-i",
+            "sine=frequency=440:sample_rate=48000",
+            "-t",
+            "1",
+            "-c:a",
+            "aac",
+        ],
+        &input,
+    );
+
+    let (result, _) = render(
+        &input,
+        &dir.join("output.mp4"),
+        &ProcessingOptions::default(),
+    );
+    match result {
+        Err(EngineError::NoVideoStream(found)) => assert_eq!(found, "audio (aac)"),
+        other => panic!("expected NoVideoStream, got {:?}", other.map(|_| ())),
+    }
+    assert!(matches!(
+        engine::read_metadata(&input),
+        Err(EngineError::NoVideoStream(_))
+    ));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn still_images_fail_unless_allowed() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("still-image");
+    let (image, single) = (dir.join("image.png"), dir.join("single.mp4"));
+    let source = ["-f", "lavfi", "-i", "testsrc=size=320x240:rate=30"];
+    ffmpeg(&[&source[..], &["-frames:v", "1"]].concat(), &image);
+    ffmpeg(
+        &[
+            &source[..],
+            &["-frames:v", "1", "-c:v", "libx264", "-pix_fmt", "yuv420p"],
+        ]
+        .concat(),
+        &single,
+    );
+
+    for input in [&image, &single] {
+        let output = dir.join("output.mp4");
+        let (result, _) = render(input, &output, &ProcessingOptions::default());
+        assert!(
+            matches!(result, Err(EngineError::StillImage)),
+            "{}: {:?}",
+            input.display(),
+            result.map(|_| ())
+        );
+
+        let opts = ProcessingOptions {
+            allow_still_image: true,
+            ..ProcessingOptions::default()
+        };
+        let (result, _) = render(input, &output, &opts);
+        let report = result.unwrap_or_else(|e| panic!("{}: {}", input.display(), e));
+        assert!(
+            (1..=2).contains(&report.frames_encoded),
+            "{}: {} frames",
+            input.display(),
+            report.frames_encoded
+        );
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+/// A second of PNG frames in Matroska with the image size of frame 10
+/// zeroed, which the PNG decoder rejects
+fn generate_corrupt_input(path: &Path) {
+    ffmpeg(
+        &[
+            "-f",
+            "lavfi",
+            "-i",
+            "testsrc=size=320x240:rate=30",
+            "-frames:v",
+            &FRAMES.to_string(),
+            "-c:v",
+            "png",
+        ],
+        path,
+    );
+    let mut bytes = std::fs::read(path).unwrap();
+    let signature = b"\x89PNG\r\n\x1a\n";
+    let frame = bytes
+        .windows(signature.len())
+        .enumerate()
+        .filter(|(_, window)| window == signature)
+        .map(|(at, _)| at)
+        .nth(10)
+        .expect("fewer than 11 PNG frames");
+    // Signature, IHDR length and type, then width and height
+    bytes[frame + 16..frame + 24].fill(0);
+    std::fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn decode_errors_abort_or_are_skipped_with_a_warning() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("corrupt-input");
+    let (input, output) = (dir.join("input.mkv"), dir.join("output.mp4"));
+    generate_corrupt_input(&input);
+
+    let opts = ProcessingOptions {
+        frame_rate: 30,
+        ..ProcessingOptions::default()
+    };
+    let (result, _) = render(&input, &output, &opts);
+    match result {
+        // Frame-threaded decoders report an error a few packets late
+        Err(EngineError::DecodeFailed { packet, .. }) => assert!(packet >= 11, "{}", packet),
+        other => panic!("expected DecodeFailed, got {:?}", other.map(|_| ())),
+    }
+
+    let opts = ProcessingOptions {
+        skip_decode_errors: true,
+        ..opts
+    };
+    let (result, warnings) = render(&input, &output, &opts);
+    let report = result.unwrap();
+    assert_eq!(report.warnings.undecodable_packets, 1);
+    assert!(warnings.contains(&WarningKind::UndecodablePacket));
+    // The frame before the gap is held in its place
+    assert_eq!(report.frames_encoded, FRAMES);
+    std::fs::remove_dir_all(&dir).ok();
+}
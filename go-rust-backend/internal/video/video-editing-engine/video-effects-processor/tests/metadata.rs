@@ -0,0 +1,93 @@
+// metadata.rs - Container tags on the output: the input's copied with
+// copy_metadata (its audio stream's too), and the caller's set over them
+//
+// The input is generated with the ffmpeg CLI; those tests are skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{ffmpeg_command, generate, probe, tools_available, white_sprite, work_dir};
+use std::collections::BTreeMap;
+use std::path::Path;
+use video_effects_processor::engine::{self, EngineError, ProcessingOptions, Progress};
+
+/// A second of video and French-tagged audio, titled and dated
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=320x240:rate=30"])
+            .args(["-f", "lavfi", "-i", "sine=frequency=440:sample_rate=48000"])
+            .args(["-t", "1", "-c:v", "libx264", "-pix_fmt", "yuv420p"])
+            .args(["-c:a", "aac"])
+            .args(["-metadata", "title=Raw capture"])
+            .args(["-metadata", "creation_time=2024-03-01T09:30:00.000000Z"])
+            .args(["-metadata:s:a:0", "language=fra"]),
+        path,
+    );
+}
+
+fn export(input: &Path, output: &Path, opts: &ProcessingOptions) -> Result<(), EngineError> {
+    let sprite = white_sprite(8);
+    engine::process_video(input, output, &sprite, &[], opts, |_: Progress| {}).map(|_| ())
+}
+
+fn tags(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn title_round_trips_and_input_tags_are_copied() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("metadata");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    // Nothing is carried over unless asked
+    let plain = dir.join("plain.mp4");
+    export(&input, &plain, &ProcessingOptions::default()).unwrap();
+    assert_eq!(probe(&plain, "", "format_tags=title"), "");
+    assert_eq!(probe(&plain, "", "format_tags=creation_time"), "");
+
+    let output = dir.join("output.mp4");
+    let opts = ProcessingOptions {
+        copy_metadata: true,
+        metadata: tags(&[
+            ("title", "Onboarding walkthrough"),
+            ("comment", "FocusFrame with spring smoothing"),
+        ]),
+        ..ProcessingOptions::default()
+    };
+    export(&input, &output, &opts).unwrap();
+    assert_eq!(
+        probe(&output, "", "format_tags=title"),
+        "Onboarding walkthrough"
+    );
+    assert_eq!(
+        probe(&output, "", "format_tags=comment"),
+        "FocusFrame with spring smoothing"
+    );
+    assert_eq!(
+        probe(&output, "", "format_tags=creation_time"),
+        "2024-03-01T09:30:00.000000Z"
+    );
+    assert_eq!(probe(&output, "a:0", "stream_tags=language"), "fra");
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn empty_metadata_keys_are_refused() {
+    let dir = work_dir("metadata-refused");
+    let opts = ProcessingOptions {
+        metadata: tags(&[("", "untitled")]),
+        ..ProcessingOptions::default()
+    };
+    match export(&dir.join("input.mp4"), &dir.join("output.mp4"), &opts) {
+        Err(EngineError::InvalidMetadata(_)) => {}
+        other => panic!("expected InvalidMetadata, got {:?}", other),
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
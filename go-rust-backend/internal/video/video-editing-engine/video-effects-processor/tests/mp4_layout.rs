@@ -0,0 +1,143 @@
+// mp4_layout.rs - MP4 muxer options: faststart moves the index (moov) ahead
+// of the media data, fragmented writes it empty up front and the media in
+// fragments; other containers refuse both
+//
+// The input is generated with the ffmpeg CLI; those tests are skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{decoded_frames, ffmpeg_command, generate, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{self, Container, EngineError, ProcessingOptions, Progress};
+
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=320x240:rate=30"])
+            .args(["-t", "1", "-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+fn export(input: &Path, output: &Path, opts: &ProcessingOptions) -> Result<(), EngineError> {
+    let sprite = white_sprite(8);
+    engine::process_video(input, output, &sprite, &[], opts, |_: Progress| {}).map(|_| ())
+}
+
+/// Top-level boxes of `path`, as (type, offset) in file order
+fn top_level_boxes(path: &Path) -> Vec<(String, usize)> {
+    let data = std::fs::read(path).unwrap();
+    let mut boxes = Vec::new();
+    let mut at = 0;
+    while at + 8 <= data.len() {
+        let size = u32::from_be_bytes(data[at..at + 4].try_into().unwrap()) as usize;
+        boxes.push((
+            String::from_utf8_lossy(&data[at + 4..at + 8]).into_owned(),
+            at,
+        ));
+        let size = match size {
+            // 64-bit size after the type
+            1 if at + 16 <= data.len() => {
+                u64::from_be_bytes(data[at + 8..at + 16].try_into().unwrap()) as usize
+            }
+            // To the end of the file
+            0 | 1 => break,
+            size => size,
+        };
+        at += size;
+    }
+    boxes
+}
+
+/// Offset of the first top-level box of type `kind`
+fn offset(boxes: &[(String, usize)], kind: &str) -> usize {
+    boxes
+        .iter()
+        .find(|(found, _)| found == kind)
+        .unwrap_or_else(|| panic!("no {} box in {:?}", kind, boxes))
+        .1
+}
+
+#[test]
+fn faststart_puts_the_index_before_the_media() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("mp4-faststart");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    // By default the index is written last, after the media
+    let plain = dir.join("plain.mp4");
+    export(&input, &plain, &ProcessingOptions::default()).unwrap();
+    let boxes = top_level_boxes(&plain);
+    assert!(
+        offset(&boxes, "mdat") < offset(&boxes, "moov"),
+        "{:?}",
+        boxes
+    );
+
+    let output = dir.join("faststart.mp4");
+    let opts = ProcessingOptions {
+        faststart: true,
+        ..ProcessingOptions::default()
+    };
+    export(&input, &output, &opts).unwrap();
+    // Within the first kilobytes, where a player reads it before any media
+    let boxes = top_level_boxes(&output);
+    let moov = offset(&boxes, "moov");
+    assert!(moov < 4096, "{:?}", boxes);
+    assert!(moov < offset(&boxes, "mdat"), "{:?}", boxes);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn fragmented_mp4_starts_with_an_empty_index_and_fragments() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("mp4-fragmented");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    let output = dir.join("fragmented.mp4");
+    let opts = ProcessingOptions {
+        fragmented: true,
+        ..ProcessingOptions::default()
+    };
+    export(&input, &output, &opts).unwrap();
+    let boxes = top_level_boxes(&output);
+    assert!(
+        offset(&boxes, "moov") < offset(&boxes, "moof"),
+        "{:?}",
+        boxes
+    );
+
+    assert_eq!(decoded_frames(&output), 30);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn muxer_flags_are_refused_outside_mp4_and_mov() {
+    let dir = work_dir("mp4-flags-refused");
+    let input = dir.join("input.mp4");
+    for (faststart, fragmented, option) in [(true, false, "faststart"), (true, true, "fragmented")]
+    {
+        let opts = ProcessingOptions {
+            faststart,
+            fragmented,
+            ..ProcessingOptions::default()
+        };
+        match export(&input, &dir.join("output.mkv"), &opts) {
+            Err(EngineError::MuxerOptionUnsupported {
+                option: refused,
+                container,
+            }) => {
+                assert_eq!(refused, option);
+                assert_eq!(container, Container::Mkv);
+            }
+            other => panic!("expected MuxerOptionUnsupported, got {:?}", other),
+        }
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
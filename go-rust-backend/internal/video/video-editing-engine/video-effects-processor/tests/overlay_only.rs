@@ -0,0 +1,168 @@
+// overlay_only.rs - Overlay-only renders: the cursor alone on transparent
+// frames, encoded with an alpha channel for compositing elsewhere
+//
+// The input (only read for its size) is generated with the ffmpeg CLI, and
+// frames are decoded back with it; those tests are skipped when ffmpeg or
+// ffprobe is missing.
+mod common;
+
+use common::{ffmpeg_command, generate, probe, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use std::process::Command;
+use video_effects_processor::engine::{
+    self, CPoint, Container, EngineError, ProcessingOptions, Progress, SmoothingMode, Spotlight,
+    VideoCodec,
+};
+
+const WIDTH: usize = 320;
+const HEIGHT: usize = 240;
+
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=320x240:rate=30"])
+            .args(["-t", "1", "-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+/// Left to right along y = 120 over the second
+fn cursor_points() -> Vec<CPoint> {
+    (0..=10)
+        .map(|i| CPoint {
+            x: 20.0 + i as f32 * 20.0,
+            y: 120.0,
+            timestamp_ms: f64::from(i) * 100.0,
+        })
+        .collect()
+}
+
+fn render(
+    input: &Path,
+    output: &Path,
+    opts: &ProcessingOptions,
+) -> Result<engine::ProcessingReport, EngineError> {
+    let sprite = white_sprite(8);
+    engine::process_video(
+        input,
+        output,
+        &sprite,
+        &cursor_points(),
+        opts,
+        |_: Progress| {},
+    )
+}
+
+fn overlay_options(codec: VideoCodec, container: Container) -> ProcessingOptions {
+    ProcessingOptions {
+        overlay_only: true,
+        codec,
+        container,
+        frame_rate: 30,
+        smoothing_mode: SmoothingMode::Passthrough,
+        ..ProcessingOptions::default()
+    }
+}
+
+/// Frame `index` of `path` as RGBA, decoded with `decoder` if given
+fn decode_frame(path: &Path, decoder: Option<&str>, index: u32) -> Vec<u8> {
+    let mut command = Command::new("ffmpeg");
+    command.args(["-v", "error"]);
+    if let Some(decoder) = decoder {
+        command.args(["-c:v", decoder]);
+    }
+    let out = command
+        .arg("-i")
+        .arg(path)
+        .args(["-vf", &format!("select=eq(n\\,{})", index)])
+        .args(["-frames:v", "1", "-f", "rawvideo", "-pix_fmt", "rgba", "-"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "ffmpeg could not decode {}",
+        path.display()
+    );
+    assert_eq!(out.stdout.len(), WIDTH * HEIGHT * 4);
+    out.stdout
+}
+
+/// Transparent everywhere but around the cursor, which is opaque white
+fn assert_cursor_on_transparency(rgba: &[u8], cursor_x: usize) {
+    let alpha = |x: usize, y: usize| rgba[(y * WIDTH + x) * 4 + 3];
+    assert_eq!(alpha(10, 10), 0);
+    assert_eq!(alpha(WIDTH - 10, HEIGHT - 10), 0);
+    assert_eq!(alpha(cursor_x, 60), 0);
+    let pixel = &rgba[(124 * WIDTH + cursor_x + 4) * 4..][..4];
+    assert!(pixel.iter().all(|&c| c > 240), "cursor pixel {:?}", pixel);
+}
+
+#[test]
+fn prores_overlay_keeps_its_alpha_channel() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("overlay-prores");
+    let (input, output) = (dir.join("input.mp4"), dir.join("overlay.mov"));
+    generate_input(&input);
+
+    let report = render(
+        &input,
+        &output,
+        &overlay_options(VideoCodec::ProRes, Container::Auto),
+    )
+    .unwrap();
+    // One frame per 1/30 s of the cursor path, both ends included
+    assert_eq!(report.frames_encoded, 31);
+    assert!(probe(&output, "v:0", "stream=codec_name").starts_with("prores"));
+    let pix_fmt = probe(&output, "v:0", "stream=pix_fmt");
+    assert!(pix_fmt.starts_with("yuva"), "{}", pix_fmt);
+
+    // Half a second in, the cursor is at x = 120
+    assert_cursor_on_transparency(&decode_frame(&output, None, 15), 120);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn vp9_overlay_marks_its_alpha_for_webm() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("overlay-vp9");
+    let (input, output) = (dir.join("input.mp4"), dir.join("overlay.webm"));
+    generate_input(&input);
+
+    let report = render(
+        &input,
+        &output,
+        &overlay_options(VideoCodec::Vp9, Container::Auto),
+    )
+    .unwrap();
+    assert_eq!(report.frames_encoded, 31);
+    assert_eq!(probe(&output, "v:0", "stream_tags=alpha_mode"), "1");
+    // FFmpeg's own VP9 decoder drops the alpha; libvpx reads it
+    assert_cursor_on_transparency(&decode_frame(&output, Some("libvpx-vp9"), 15), 120);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn overlay_needs_an_alpha_codec_and_no_source_effects() {
+    let dir = work_dir("overlay-refused");
+    let (input, output) = (dir.join("input.mp4"), dir.join("overlay.mp4"));
+
+    let opts = overlay_options(VideoCodec::H264, Container::Mp4);
+    match render(&input, &output, &opts) {
+        Err(EngineError::AlphaUnavailable(encoder)) => assert_eq!(encoder, "libx264"),
+        other => panic!("expected AlphaUnavailable, got {:?}", other.map(|_| ())),
+    }
+
+    let opts = ProcessingOptions {
+        spotlight: Some(Spotlight::default()),
+        ..overlay_options(VideoCodec::ProRes, Container::Mov)
+    };
+    match render(&input, &dir.join("overlay.mov"), &opts) {
+        Err(EngineError::OverlayUnsupported(blocker)) => assert_eq!(blocker, "spotlights"),
+        other => panic!("expected OverlayUnsupported, got {:?}", other.map(|_| ())),
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
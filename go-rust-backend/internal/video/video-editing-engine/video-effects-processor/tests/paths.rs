@@ -0,0 +1,60 @@
+// paths.rs - File names that are not UTF-8 are opened and written byte for
+// byte, and a missing one is an error rather than a panic
+//
+// Unix only (Windows narrow paths must be UTF-8). The input is generated
+// with the ffmpeg CLI; the export test is skipped when ffmpeg or ffprobe is
+// missing.
+#![cfg(unix)]
+
+mod common;
+
+use common::{drag, ffmpeg_command, generate, probe, tools_available, white_sprite, work_dir};
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use video_effects_processor::engine::{self, EngineError, ProcessingOptions, Progress};
+
+#[test]
+fn non_utf8_input_and_output_names_work() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("non-utf8-paths");
+    // Latin-1 "capture-é.mp4" and "export-é.mp4"
+    let input = dir.join(OsStr::from_bytes(b"capture-\xe9.mp4"));
+    let output = dir.join(OsStr::from_bytes(b"export-\xe9.mp4"));
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=160x120:rate=10"])
+            .args(["-t", "1"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        &input,
+    );
+
+    let metadata = engine::read_metadata(&input).unwrap();
+    assert_eq!((metadata.width, metadata.height), (160, 120));
+    let points = drag((20.0, 20.0), (140.0, 100.0), 1000.0);
+    engine::process_video(
+        &input,
+        &output,
+        &white_sprite(8),
+        &points,
+        &ProcessingOptions::default(),
+        |_: Progress| {},
+    )
+    .unwrap();
+    assert_eq!(probe(&output, "v:0", "stream=codec_name"), "h264");
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn missing_non_utf8_input_is_an_error() {
+    let dir = work_dir("non-utf8-missing");
+    let input = dir.join(OsStr::from_bytes(b"absent-\xff.mp4"));
+    let result = engine::read_metadata(&input);
+    assert!(
+        matches!(result, Err(EngineError::InputUnreadable(_))),
+        "{:?}",
+        result
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
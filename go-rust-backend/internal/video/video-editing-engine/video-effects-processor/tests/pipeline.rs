@@ -0,0 +1,133 @@
+// pipeline.rs - Pipelined renders (decode, composite and encode on threads
+// of their own) write the same file as serial ones, and still call the
+// observer on the calling thread
+//
+// The input is generated with the ffmpeg CLI; the tests are skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{ffmpeg_command, generate, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use std::thread::{self, ThreadId};
+use video_effects_processor::engine::{
+    self, CPoint, Heartbeat, ProcessingOptions, ProcessingReport, Progress, RenderObserver,
+    RenderWarning,
+};
+
+/// Two seconds of a 320x240 test pattern with a 440 Hz tone
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=320x240:rate=30"])
+            .args(["-f", "lavfi", "-i", "sine=frequency=440:sample_rate=48000"])
+            .args(["-t", "2"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-c:a", "aac"]),
+        path,
+    );
+}
+
+/// Records the thread each callback was made on
+struct Threads<'a>(&'a mut Vec<ThreadId>);
+
+impl RenderObserver for Threads<'_> {
+    fn progress(&mut self, _progress: Progress) {
+        self.0.push(thread::current().id());
+    }
+
+    fn heartbeat(&mut self, _heartbeat: &Heartbeat) {
+        self.0.push(thread::current().id());
+    }
+
+    fn warning(&mut self, _warning: &RenderWarning) {
+        self.0.push(thread::current().id());
+    }
+}
+
+fn render(input: &Path, output: &Path, opts: &ProcessingOptions) -> ProcessingReport {
+    let sprite = white_sprite(8);
+    // Left to right and out past the right edge, for a warning or two
+    let points: Vec<CPoint> = (0..=40)
+        .map(|i| CPoint {
+            x: 20.0 + i as f32 * 9.0,
+            y: 120.0,
+            timestamp_ms: f64::from(i) * 50.0,
+        })
+        .collect();
+    let mut threads = Vec::new();
+    let report =
+        engine::process_video(input, output, &sprite, &points, opts, Threads(&mut threads))
+            .unwrap_or_else(|e| panic!("{} failed: {}", output.display(), e));
+    let caller = thread::current().id();
+    assert!(!threads.is_empty());
+    assert!(
+        threads.iter().all(|&id| id == caller),
+        "{}: observer called off the calling thread",
+        output.display()
+    );
+    report
+}
+
+#[test]
+fn pipelined_render_matches_serial() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("pipeline");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    let base = ProcessingOptions {
+        frame_rate: 30,
+        heartbeat_interval_frames: 10,
+        deterministic: true,
+        ..ProcessingOptions::default()
+    };
+    let cases = [
+        ("rgba", base.clone()),
+        (
+            "yuv",
+            ProcessingOptions {
+                yuv_compositing: true,
+                ..base.clone()
+            },
+        ),
+        (
+            "trimmed",
+            ProcessingOptions {
+                trim_start_ms: 400.0,
+                trim_end_ms: Some(1400.0),
+                ..base.clone()
+            },
+        ),
+    ];
+    for (name, opts) in cases {
+        let (serial_path, pipelined_path) = (
+            dir.join(format!("{}-serial.mp4", name)),
+            dir.join(format!("{}-pipelined.mp4", name)),
+        );
+        let serial = render(
+            &input,
+            &serial_path,
+            &ProcessingOptions {
+                pipelined: false,
+                ..opts.clone()
+            },
+        );
+        let pipelined = render(
+            &input,
+            &pipelined_path,
+            &ProcessingOptions {
+                pipelined: true,
+                ..opts
+            },
+        );
+        assert_eq!(serial.frames_encoded, pipelined.frames_encoded, "{}", name);
+        assert_eq!(serial.warnings, pipelined.warnings, "{}", name);
+        assert!(
+            std::fs::read(&serial_path).unwrap() == std::fs::read(&pipelined_path).unwrap(),
+            "{}: pipelined output differs from serial",
+            name
+        );
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
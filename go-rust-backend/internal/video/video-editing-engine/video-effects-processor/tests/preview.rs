@@ -0,0 +1,143 @@
+// preview.rs - preview_mode renders a quick proxy of the export: a quarter
+// of each side, at most 30 fps, with the cursor where the export has it
+//
+// The inputs are generated with the ffmpeg CLI, and frames are decoded back
+// with it; the tests are skipped when ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{
+    ffmpeg_command, generate, probe, sweep_720p, tools_available, white_sprite, work_dir,
+};
+use std::path::Path;
+use std::process::Command;
+use video_effects_processor::engine::{
+    self, ProcessingOptions, ProcessingReport, Progress, SmoothingMode,
+};
+
+/// Three seconds of 1280x720 `source` (a lavfi source without its size)
+fn generate_input(path: &Path, source: &str) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i"])
+            .arg(format!("{}:size=1280x720:rate=30", source))
+            .args(["-t", "3", "-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+fn render(input: &Path, output: &Path, opts: &ProcessingOptions) -> ProcessingReport {
+    let sprite = white_sprite(32);
+    engine::process_video(
+        input,
+        output,
+        &sprite,
+        &sweep_720p(),
+        opts,
+        |_: Progress| {},
+    )
+    .unwrap_or_else(|e| panic!("{} failed: {}", output.display(), e))
+}
+
+/// Mean x of the near-white pixels in the frame shown at `time_s`
+fn cursor_x(path: &Path, width: usize, height: usize, time_s: f64) -> f64 {
+    let out = Command::new("ffmpeg")
+        .args(["-v", "error", "-ss", &time_s.to_string(), "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-f", "rawvideo", "-pix_fmt", "gray", "-"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "ffmpeg could not decode {}",
+        path.display()
+    );
+    assert_eq!(out.stdout.len(), width * height);
+    let (mut sum, mut count) = (0.0, 0.0);
+    for (i, &luma) in out.stdout.iter().enumerate() {
+        if luma > 200 {
+            sum += (i % width) as f64;
+            count += 1.0;
+        }
+    }
+    assert!(count > 0.0, "no cursor in {}", path.display());
+    sum / count
+}
+
+fn options(preview_mode: bool) -> ProcessingOptions {
+    ProcessingOptions {
+        preview_mode,
+        smoothing_mode: SmoothingMode::Passthrough,
+        ..ProcessingOptions::default()
+    }
+}
+
+#[test]
+fn preview_is_a_quarter_of_each_side_with_the_cursor_in_place() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("preview");
+    let input = dir.join("input.mp4");
+    generate_input(&input, "color=c=0x202020");
+
+    let full = dir.join("full.mp4");
+    let full_report = render(&input, &full, &options(false));
+    assert!(!full_report.proxy);
+    let proxy = dir.join("proxy.mp4");
+    let report = render(&input, &proxy, &options(true));
+    assert!(report.proxy);
+
+    assert_eq!(probe(&proxy, "v:0", "stream=width,height"), "320,180");
+    assert_eq!(probe(&proxy, "v:0", "stream=r_frame_rate"), "30/1");
+    assert_eq!(probe(&full, "v:0", "stream=r_frame_rate"), "60/1");
+
+    // Halfway through, the cursor sits at a quarter of the export's x
+    let full_x = cursor_x(&full, 1280, 720, 1.5);
+    let proxy_x = cursor_x(&proxy, 320, 180, 1.5);
+    assert!(
+        (proxy_x * 4.0 - full_x).abs() < 8.0,
+        "proxy {} vs full {}",
+        proxy_x,
+        full_x
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn preview_encodes_half_the_frames() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("preview-frames");
+    let input = dir.join("input.mp4");
+    generate_input(&input, "testsrc2");
+
+    let full = render(&input, &dir.join("full.mp4"), &options(false));
+    let proxy = render(&input, &dir.join("proxy.mp4"), &options(true));
+    // At 30 fps instead of 60
+    assert!((proxy.frames_encoded * 2).abs_diff(full.frames_encoded) <= 2);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+// Wall-clock ratios depend on the machine and its load, so this only runs
+// when asked for: cargo test --test preview -- --ignored
+#[test]
+#[ignore = "timing comparison; flaky on loaded machines"]
+fn preview_renders_much_faster_than_the_export() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("preview-speed");
+    let input = dir.join("input.mp4");
+    generate_input(&input, "testsrc2");
+
+    let full = render(&input, &dir.join("full.mp4"), &options(false));
+    let proxy = render(&input, &dir.join("proxy.mp4"), &options(true));
+    assert!(
+        proxy.wall_clock.as_secs_f64() < full.wall_clock.as_secs_f64() * 0.6,
+        "proxy took {:?}, the export {:?}",
+        proxy.wall_clock,
+        full.wall_clock
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
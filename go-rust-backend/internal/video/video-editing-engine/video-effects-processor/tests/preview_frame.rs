@@ -0,0 +1,144 @@
+// preview_frame.rs - render_preview_frame writes one composited frame as a
+// PNG, with the cursor drawn where the export draws it in the same frame
+//
+// The input is generated with the ffmpeg CLI; the tests are skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{ffmpeg_command, generate, resting, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{self, CPoint, ProcessingOptions, Progress};
+
+/// One second of a dark 320x240 frame
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "color=c=0x202020:size=320x240:rate=30"])
+            .args(["-t", "1"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv444p"]),
+        path,
+    );
+}
+
+/// Bounding box `(x0, y0, x1, y1)` of the near-white pixels of `image`
+fn bright_box(image: &image::RgbImage) -> Option<(u32, u32, u32, u32)> {
+    image
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel.0.iter().all(|&c| c > 200))
+        .fold(None, |found, (x, y, _)| {
+            let (x0, y0, x1, y1) = found.unwrap_or((x, y, x, y));
+            Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y)))
+        })
+}
+
+#[test]
+fn preview_png_has_the_cursor_at_its_position() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("preview-frame");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    let png = dir.join("preview.png");
+    engine::render_preview_frame(
+        &input,
+        &png,
+        &white_sprite(16),
+        &resting(100.0, 80.0),
+        &ProcessingOptions::default(),
+        500.0,
+    )
+    .unwrap();
+
+    let image = image::open(&png).unwrap().to_rgb8();
+    assert_eq!(image.dimensions(), (320, 240));
+    // The sprite's top-left corner (its default hotspot) on the point
+    let (x0, y0, x1, y1) = bright_box(&image).expect("no cursor in the preview");
+    assert!(
+        x0.abs_diff(100) <= 1 && y0.abs_diff(80) <= 1,
+        "{:?}",
+        (x0, y0)
+    );
+    assert!(
+        x1.abs_diff(115) <= 1 && y1.abs_diff(95) <= 1,
+        "{:?}",
+        (x1, y1)
+    );
+    // The rest of the frame is the source's
+    assert_eq!(image.get_pixel(20, 200).0, image.get_pixel(300, 20).0);
+    assert!(image.get_pixel(20, 200).0.iter().all(|&c| c < 64));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+/// Mean x of the near-white pixels of frame `index` of `video`
+fn frame_cursor_x(video: &Path, index: u32, width: usize) -> f64 {
+    let out = std::process::Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(video)
+        .args(["-vf", &format!("select=eq(n\\,{})", index)])
+        .args(["-frames:v", "1", "-f", "rawvideo", "-pix_fmt", "gray", "-"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "ffmpeg could not decode {}",
+        video.display()
+    );
+    mean_bright_x(out.stdout.iter().copied(), width)
+}
+
+fn mean_bright_x(luma: impl Iterator<Item = u8>, width: usize) -> f64 {
+    let (mut sum, mut count) = (0.0, 0.0);
+    for (i, value) in luma.enumerate() {
+        if value > 200 {
+            sum += (i % width) as f64;
+            count += 1.0;
+        }
+    }
+    assert!(count > 0.0, "no cursor");
+    sum / count
+}
+
+#[test]
+fn preview_at_a_frame_time_matches_that_frame_of_the_export() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("preview-frame-sync");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    // Left to right at 200 px/s, ~7 px a frame at 30 fps
+    let points: Vec<CPoint> = (0..=20)
+        .map(|i| CPoint {
+            x: 40.0 + i as f32 * 10.0,
+            y: 120.0,
+            timestamp_ms: f64::from(i) * 50.0,
+        })
+        .collect();
+    let opts = ProcessingOptions {
+        frame_rate: 30,
+        ..ProcessingOptions::default()
+    };
+    let sprite = white_sprite(8);
+    let output = dir.join("output.mp4");
+    engine::process_video(&input, &output, &sprite, &points, &opts, |_: Progress| {}).unwrap();
+
+    for frame in [6, 15, 24] {
+        let timestamp_ms = f64::from(frame) * 1000.0 / f64::from(opts.frame_rate);
+        let png = dir.join(format!("frame-{}.png", frame));
+        engine::render_preview_frame(&input, &png, &sprite, &points, &opts, timestamp_ms).unwrap();
+        let preview = image::open(&png).unwrap().to_luma8();
+        let preview_x = mean_bright_x(preview.into_raw().into_iter(), 320);
+        let export_x = frame_cursor_x(&output, frame, 320);
+        assert!(
+            (preview_x - export_x).abs() < 1.0,
+            "frame {}: preview {} vs export {}",
+            frame,
+            preview_x,
+            export_x
+        );
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
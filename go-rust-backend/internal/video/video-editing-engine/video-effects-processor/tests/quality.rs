@@ -0,0 +1,128 @@
+// quality.rs - Encoder quality settings reach the encoder and stay in range
+//
+// The size comparison generates its input with the ffmpeg CLI and is skipped
+// when it is missing.
+mod common;
+
+use common::{drag, ffmpeg_available, ffmpeg_command, generate, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{
+    self, EncoderPreset, EncoderQuality, EncoderTune, ProcessingOptions, Progress, RateControl,
+    VideoCodec, MAX_VIDEO_BITRATE_KBPS, MAX_VP9_CRF, MAX_X264_CRF, MIN_VIDEO_BITRATE_KBPS,
+};
+
+/// Two seconds of a detailed, moving 640x360 pattern, so bitrate matters
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc2=size=640x360:rate=30"])
+            .args(["-t", "2"])
+            .args(["-c:v", "libx264", "-crf", "10", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+/// Size in bytes of `input` rendered at `crf`
+fn rendered_size(input: &Path, output: &Path, crf: u32) -> u64 {
+    let sprite = white_sprite(8);
+    let points = drag((40.0, 40.0), (600.0, 320.0), 2000.0);
+    let opts = ProcessingOptions {
+        quality: EncoderQuality {
+            crf: Some(crf),
+            ..EncoderQuality::default()
+        },
+        ..ProcessingOptions::default()
+    };
+    engine::process_video(input, output, &sprite, &points, &opts, |_: Progress| {})
+        .unwrap()
+        .output_file_size_bytes
+}
+
+#[test]
+fn high_crf_gives_a_much_smaller_file() {
+    if !ffmpeg_available() {
+        return;
+    }
+    let dir = work_dir("quality");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    let fine = rendered_size(&input, &dir.join("crf18.mp4"), 18);
+    let coarse = rendered_size(&input, &dir.join("crf40.mp4"), 40);
+    assert!(
+        coarse * 3 < fine,
+        "crf 40 gave {} bytes, crf 18 {} bytes",
+        coarse,
+        fine
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn quality_values_are_clamped_per_codec() {
+    let quality = EncoderQuality {
+        crf: Some(70),
+        bitrate_kbps: 1,
+        max_bitrate_kbps: u32::MAX,
+        bufsize_kbits: 0,
+        ..EncoderQuality::default()
+    };
+    let validated = |codec| {
+        ProcessingOptions {
+            codec,
+            quality,
+            ..ProcessingOptions::default()
+        }
+        .validated()
+        .unwrap()
+        .quality
+    };
+
+    let h264 = validated(VideoCodec::H264);
+    assert_eq!(h264.crf, Some(MAX_X264_CRF));
+    assert_eq!(h264.bitrate_kbps, MIN_VIDEO_BITRATE_KBPS);
+    assert_eq!(h264.max_bitrate_kbps, MAX_VIDEO_BITRATE_KBPS);
+    // 0 means "twice the peak", not out of range
+    assert_eq!(h264.bufsize_kbits, 0);
+    assert_eq!(validated(VideoCodec::Vp9).crf, Some(MAX_VP9_CRF));
+}
+
+#[test]
+fn peak_rate_applies_to_capped_and_constant_bitrates() {
+    let crf = EncoderQuality::default();
+    assert_eq!(crf.vbv(), None);
+
+    let capped = EncoderQuality {
+        max_bitrate_kbps: 6000,
+        ..EncoderQuality::default()
+    };
+    assert_eq!(capped.vbv(), Some((6000, 12_000)));
+
+    let cbr = EncoderQuality {
+        rate_control: RateControl::Cbr,
+        bitrate_kbps: 4000,
+        bufsize_kbits: 2000,
+        ..EncoderQuality::default()
+    };
+    assert_eq!(cbr.vbv(), Some((4000, 2000)));
+}
+
+#[test]
+fn raw_values_map_to_x264_names() {
+    assert_eq!(EncoderPreset::from_raw(0).unwrap().as_str(), "ultrafast");
+    assert_eq!(EncoderPreset::from_raw(4), Some(EncoderPreset::default()));
+    assert_eq!(EncoderPreset::from_raw(9).unwrap().as_str(), "placebo");
+    assert_eq!(EncoderPreset::from_raw(10), None);
+    assert_eq!(EncoderPreset::from_raw(-1), None);
+
+    assert_eq!(EncoderTune::from_raw(0), Some(None));
+    assert_eq!(
+        EncoderTune::from_raw(2).flatten().map(EncoderTune::as_str),
+        Some("animation")
+    );
+    assert_eq!(EncoderTune::from_raw(7), None);
+    assert!(!EncoderTune::Film.in_x265());
+
+    assert_eq!(RateControl::from_raw(1), Some(RateControl::Abr));
+    assert_eq!(RateControl::from_raw(3), None);
+}
@@ -0,0 +1,173 @@
+// redaction.rs - Blurred and pixelated regions change only their own pixels,
+// and only while they are active
+use video_effects_processor::engine::{
+    EngineError, FrameView, Overlay, ProcessingOptions, RedactionMode, RedactionOverlay,
+    RedactionRegion,
+};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 48;
+/// Row padding past the pixels, which must come back untouched
+const PADDING: usize = 8;
+const STRIDE: usize = WIDTH as usize * 4 + PADDING;
+
+/// Fine checkerboard (every blur or pixelate changes it) with padding bytes
+fn checkerboard() -> Vec<u8> {
+    let mut data = vec![0xA5; STRIDE * HEIGHT as usize];
+    for (y, row) in data.chunks_exact_mut(STRIDE).enumerate() {
+        for (x, px) in row[..WIDTH as usize * 4].chunks_exact_mut(4).enumerate() {
+            let v = if (x + y) % 2 == 0 { 230 } else { 20 };
+            px.copy_from_slice(&[v, v / 2, 255 - v, 255]);
+        }
+    }
+    data
+}
+
+fn region(x: i32, y: i32, mode: RedactionMode) -> RedactionRegion {
+    RedactionRegion {
+        x,
+        y,
+        width: 20,
+        height: 12,
+        start_ms: 1000.0,
+        end_ms: Some(2000.0),
+        mode,
+    }
+}
+
+fn render(overlay: &RedactionOverlay, timestamp_ms: f64) -> Vec<u8> {
+    let mut data = checkerboard();
+    overlay.render(
+        &mut FrameView::new(&mut data, WIDTH, HEIGHT, STRIDE),
+        timestamp_ms,
+    );
+    data
+}
+
+/// Pixels of `data` that differ from the untouched checkerboard
+fn changed(data: &[u8]) -> Vec<(u32, u32)> {
+    let original = checkerboard();
+    let mut changed = Vec::new();
+    for (y, (row, before)) in data
+        .chunks_exact(STRIDE)
+        .zip(original.chunks_exact(STRIDE))
+        .enumerate()
+    {
+        let (pixels, padding) = row.split_at(WIDTH as usize * 4);
+        assert!(padding.iter().all(|&b| b == 0xA5), "row {} padding", y);
+        for (x, (px, old)) in pixels
+            .chunks_exact(4)
+            .zip(before.chunks_exact(4))
+            .enumerate()
+        {
+            if px != old {
+                changed.push((x as u32, y as u32));
+            }
+        }
+    }
+    changed
+}
+
+/// Every pixel of the `x0..x1` x `y0..y1` rectangle
+fn rect(x0: u32, x1: u32, y0: u32, y1: u32) -> Vec<(u32, u32)> {
+    (y0..y1)
+        .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+        .collect()
+}
+
+#[test]
+fn blur_changes_the_region_only() {
+    let overlay = RedactionOverlay::new(vec![region(10, 8, RedactionMode::Blur { radius_px: 3 })]);
+    let data = render(&overlay, 1500.0);
+    // Every pixel inside is now a mix of both checker colors; the apron was
+    // read but nothing just outside was written
+    assert_eq!(changed(&data), rect(10, 30, 8, 20));
+    // Alpha is kept
+    for row in data.chunks_exact(STRIDE) {
+        assert!(row[..WIDTH as usize * 4]
+            .chunks_exact(4)
+            .all(|px| px[3] == 255));
+    }
+}
+
+#[test]
+fn pixelate_fills_blocks_with_their_average() {
+    let overlay =
+        RedactionOverlay::new(vec![region(10, 8, RedactionMode::Pixelate { block_px: 4 })]);
+    let data = render(&overlay, 1500.0);
+    assert_eq!(changed(&data), rect(10, 30, 8, 20));
+    // Even-sized blocks hold as many light as dark squares
+    let pixel = |x: u32, y: u32| {
+        let i = y as usize * STRIDE + x as usize * 4;
+        [data[i], data[i + 1], data[i + 2], data[i + 3]]
+    };
+    for (x, y) in rect(10, 30, 8, 20) {
+        assert_eq!(pixel(x, y), [125, 63, 130, 255], "({}, {})", x, y);
+    }
+}
+
+#[test]
+fn region_starts_and_stops_on_its_frames() {
+    let overlay = RedactionOverlay::new(vec![region(10, 8, RedactionMode::Blur { radius_px: 2 })]);
+    let frame_ms = 1000.0 / 60.0;
+    // Frames just before the start and from the end on are untouched
+    assert!(changed(&render(&overlay, 1000.0 - frame_ms)).is_empty());
+    assert!(!changed(&render(&overlay, 1000.0)).is_empty());
+    assert!(!changed(&render(&overlay, 2000.0 - frame_ms)).is_empty());
+    assert!(changed(&render(&overlay, 2000.0)).is_empty());
+
+    // Without an end it lasts the rest of the video
+    let open = RedactionOverlay::new(vec![RedactionRegion {
+        end_ms: None,
+        ..region(10, 8, RedactionMode::Blur { radius_px: 2 })
+    }]);
+    assert!(changed(&render(&open, 999.0)).is_empty());
+    assert!(!changed(&render(&open, 3_600_000.0)).is_empty());
+}
+
+#[test]
+fn regions_clamp_at_frame_edges_and_overlap() {
+    // Hanging off the top-left and bottom-right corners, plus a pixelated
+    // region over part of a blurred one
+    let overlay = RedactionOverlay::new(vec![
+        region(-6, -4, RedactionMode::Blur { radius_px: 5 }),
+        region(50, 40, RedactionMode::Pixelate { block_px: 6 }),
+        region(20, 20, RedactionMode::Blur { radius_px: 4 }),
+        region(30, 24, RedactionMode::Pixelate { block_px: 5 }),
+    ]);
+    let data = render(&overlay, 1500.0);
+    let mut expected = rect(0, 14, 0, 8);
+    expected.extend(rect(50, 64, 40, 48));
+    expected.extend(rect(20, 40, 20, 32));
+    expected.extend(rect(30, 50, 24, 36));
+    expected.sort_by_key(|&(x, y)| (y, x));
+    expected.dedup();
+    assert_eq!(changed(&data), expected);
+}
+
+#[test]
+fn empty_regions_are_rejected() {
+    for bad in [
+        RedactionRegion {
+            width: 0,
+            ..region(0, 0, RedactionMode::Blur { radius_px: 4 })
+        },
+        RedactionRegion {
+            end_ms: Some(1000.0),
+            ..region(0, 0, RedactionMode::Blur { radius_px: 4 })
+        },
+        RedactionRegion {
+            start_ms: f64::NAN,
+            ..region(0, 0, RedactionMode::Blur { radius_px: 4 })
+        },
+    ] {
+        let options = ProcessingOptions {
+            redactions: vec![bad],
+            ..ProcessingOptions::default()
+        };
+        assert!(matches!(
+            options.validated(),
+            Err(EngineError::InvalidRedaction(_))
+        ));
+    }
+}
@@ -0,0 +1,293 @@
+// rotation.rs - Sources stored turned or mirrored export upright, with the
+// cursor turned along with the frame, or keep their rotation metadata
+//
+// The fixtures are generated with the ffmpeg CLI, which writes the display
+// matrix (-display_rotation, -display_hflip, -display_vflip) and is also the
+// reference for what upright looks like; the export tests are skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{ffmpeg_command, generate, resting, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use std::process::Command;
+use video_effects_processor::engine::{
+    self, Orientation, ProcessingOptions, Progress, RotationHandling,
+};
+
+/// Display matrices as ffmpeg writes them, 16.16 fixed point
+const UNIT: i32 = 1 << 16;
+const W: i32 = 1 << 30;
+
+#[test]
+fn display_matrices_map_to_turns_and_mirrors() {
+    let cases = [
+        ([UNIT, 0, 0, 0, UNIT, 0, 0, 0, W], 0, false),
+        ([0, UNIT, 0, -UNIT, 0, 0, 0, 0, W], 1, false),
+        ([-UNIT, 0, 0, 0, -UNIT, 0, 0, 0, W], 2, false),
+        ([0, -UNIT, 0, UNIT, 0, 0, 0, 0, W], 3, false),
+        ([-UNIT, 0, 0, 0, UNIT, 0, 0, 0, W], 0, true),
+        ([UNIT, 0, 0, 0, -UNIT, 0, 0, 0, W], 2, true),
+    ];
+    for (matrix, quarter_turns, mirrored) in cases {
+        assert_eq!(
+            Orientation::from_display_matrix(&matrix),
+            Some(Orientation {
+                quarter_turns,
+                mirrored
+            }),
+            "{:?}",
+            matrix
+        );
+    }
+    // 45 degrees
+    assert_eq!(
+        Orientation::from_display_matrix(&[46341, 46341, 0, -46341, 46341, 0, 0, 0, W]),
+        None
+    );
+}
+
+#[test]
+fn points_turn_with_the_frame_and_back() {
+    let stored = (320, 240);
+    for quarter_turns in 0..4 {
+        for mirrored in [false, true] {
+            let turn = Orientation {
+                quarter_turns,
+                mirrored,
+            };
+            let upright = turn.upright_size(stored);
+            assert_eq!(turn.inverse().upright_size(upright), stored);
+            for point in [(0.0, 0.0), (40.0, 30.0), (320.0, 0.0), (100.0, 240.0)] {
+                let turned = turn.map_point(point, stored);
+                assert!(
+                    (0.0..=upright.0 as f32).contains(&turned.0)
+                        && (0.0..=upright.1 as f32).contains(&turned.1),
+                    "{:?} left the frame: {:?}",
+                    turn,
+                    turned
+                );
+                assert_eq!(
+                    turn.inverse().map_point(turned, upright),
+                    point,
+                    "{:?}",
+                    turn
+                );
+            }
+        }
+    }
+    // A quarter turn clockwise takes the top-left corner to the top right
+    let clockwise = Orientation {
+        quarter_turns: 1,
+        mirrored: false,
+    };
+    assert_eq!(clockwise.map_point((0.0, 0.0), stored), (240.0, 0.0));
+}
+
+/// Where the cursor rests, in stored coordinates: the middle of the green box
+const CURSOR: (f32, f32) = (290.0, 200.0);
+
+/// One second of a blue 320x240 frame with a red box near the top-left
+/// corner and a green one around `CURSOR`, without rotation metadata
+fn generate_base(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "color=c=blue:size=320x240:rate=30"])
+            .args([
+                "-vf",
+                "drawbox=x=20:y=20:w=60:h=40:c=red:t=fill,drawbox=x=278:y=188:w=24:h=24:c=lime:t=fill",
+            ])
+            .args(["-t", "1"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv444p"]),
+        path,
+    );
+}
+
+/// `base` with a display matrix that turns it `degrees` counterclockwise
+/// and mirrors it per the `flips` (-display_hflip / -display_vflip)
+fn with_rotation(base: &Path, path: &Path, degrees: i32, flips: &[&str]) {
+    generate(
+        ffmpeg_command()
+            .args(["-display_rotation", &degrees.to_string()])
+            .args(flips)
+            .arg("-i")
+            .arg(base)
+            .args(["-c", "copy"]),
+        path,
+    );
+}
+
+fn render(input: &Path, output: &Path, rotation: RotationHandling) {
+    // 6x6 so it stays inside the green box however the box turns
+    let sprite = white_sprite(6);
+    let points = resting(CURSOR.0, CURSOR.1);
+    let opts = ProcessingOptions {
+        frame_rate: 30,
+        rotation,
+        ..ProcessingOptions::default()
+    };
+    engine::process_video(input, output, &sprite, &points, &opts, |_: Progress| {}).unwrap();
+}
+
+/// First frame of `path` as ffmpeg decodes it: size and RGB rows
+struct Picture {
+    width: usize,
+    height: usize,
+    rgb: Vec<u8>,
+}
+
+impl Picture {
+    /// Decoded turned upright per the metadata (`autorotate`) or as stored
+    fn decode(path: &Path, autorotate: bool) -> Self {
+        let mut ffmpeg = Command::new("ffmpeg");
+        ffmpeg.args(["-v", "error"]);
+        if !autorotate {
+            ffmpeg.arg("-noautorotate");
+        }
+        let out = ffmpeg
+            .arg("-i")
+            .arg(path)
+            .args(["-frames:v", "1", "-f", "image2pipe", "-c:v", "ppm", "-"])
+            .output()
+            .unwrap();
+        assert!(
+            out.status.success(),
+            "ffmpeg could not decode {}",
+            path.display()
+        );
+        // P6 header: magic, width, height, max value, one whitespace byte
+        let mut fields = Vec::new();
+        let mut at = 0;
+        while fields.len() < 4 {
+            let end = at
+                + out.stdout[at..]
+                    .iter()
+                    .position(u8::is_ascii_whitespace)
+                    .unwrap();
+            if end > at {
+                fields.push(String::from_utf8_lossy(&out.stdout[at..end]).into_owned());
+            }
+            at = end + 1;
+        }
+        Self {
+            width: fields[1].parse().unwrap(),
+            height: fields[2].parse().unwrap(),
+            rgb: out.stdout[at..].to_vec(),
+        }
+    }
+
+    /// Mean position of the pixels `matches` picks out
+    fn centroid(&self, matches: impl Fn([u8; 3]) -> bool) -> (f32, f32) {
+        let (mut sum, mut count) = ((0.0, 0.0), 0.0);
+        for (i, pixel) in self.rgb.chunks_exact(3).enumerate() {
+            if matches([pixel[0], pixel[1], pixel[2]]) {
+                sum.0 += (i % self.width) as f32;
+                sum.1 += (i / self.width) as f32;
+                count += 1.0;
+            }
+        }
+        assert!(count > 0.0, "no matching pixels");
+        (sum.0 / count, sum.1 / count)
+    }
+}
+
+fn red([r, g, b]: [u8; 3]) -> bool {
+    r > 160 && g < 90 && b < 90
+}
+
+fn green([r, g, b]: [u8; 3]) -> bool {
+    g > 160 && r < 90 && b < 90
+}
+
+fn white(pixel: [u8; 3]) -> bool {
+    pixel.iter().all(|&c| c > 200)
+}
+
+fn near((ax, ay): (f32, f32), (bx, by): (f32, f32)) -> bool {
+    (ax - bx).abs() <= 2.0 && (ay - by).abs() <= 2.0
+}
+
+#[test]
+fn turned_and_mirrored_sources_export_upright() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("rotation");
+    let base = dir.join("base.mp4");
+    generate_base(&base);
+
+    let cases: [(i32, &[&str]); 6] = [
+        (90, &[]),
+        (180, &[]),
+        (270, &[]),
+        (0, &["-display_hflip"]),
+        (0, &["-display_vflip"]),
+        (90, &["-display_hflip"]),
+    ];
+    for (degrees, flips) in cases {
+        let name = format!("{}{}", degrees, flips.concat());
+        let (input, output) = (
+            dir.join(format!("in-{}.mp4", name)),
+            dir.join(format!("out-{}.mp4", name)),
+        );
+        with_rotation(&base, &input, degrees, flips);
+        render(&input, &output, RotationHandling::Rotate);
+
+        let expected = Picture::decode(&input, true);
+        // Nothing left for a player to turn
+        let exported = Picture::decode(&output, false);
+        assert_eq!(
+            (exported.width, exported.height),
+            (expected.width, expected.height),
+            "{}",
+            name
+        );
+        let (want, got) = (expected.centroid(red), exported.centroid(red));
+        assert!(
+            near(want, got),
+            "{}: red box at {:?}, not {:?}",
+            name,
+            got,
+            want
+        );
+        // The cursor turned with the frame, so it still sits in the green box
+        let cursor = exported.centroid(white);
+        let green_box = expected.centroid(green);
+        assert!(
+            (cursor.0 - green_box.0).abs() < 12.0 && (cursor.1 - green_box.1).abs() < 12.0,
+            "{}: cursor at {:?}, green box at {:?}",
+            name,
+            cursor,
+            green_box
+        );
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn kept_metadata_leaves_frames_as_stored() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("rotation-keep");
+    let (base, input, output) = (
+        dir.join("base.mp4"),
+        dir.join("input.mp4"),
+        dir.join("output.mp4"),
+    );
+    generate_base(&base);
+    with_rotation(&base, &input, 90, &["-display_hflip"]);
+    render(&input, &output, RotationHandling::KeepMetadata);
+
+    // Stored the same way as the source...
+    let stored = Picture::decode(&output, false);
+    assert_eq!((stored.width, stored.height), (320, 240));
+    let source = Picture::decode(&input, false);
+    assert!(near(source.centroid(red), stored.centroid(red)));
+    // ...and turned by players the same way too
+    let (want, got) = (
+        Picture::decode(&input, true).centroid(red),
+        Picture::decode(&output, true).centroid(red),
+    );
+    assert!(near(want, got), "red box at {:?}, not {:?}", got, want);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,124 @@
+// scaling.rs - Exports scaled to an output size keep the cursor where it was
+// in the picture
+//
+// The input is generated with the ffmpeg CLI and the output is read back
+// with ffprobe and ffmpeg; the export test is skipped when either is missing.
+mod common;
+
+use common::{ffmpeg_command, generate, probe, resting, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use std::process::Command;
+use video_effects_processor::engine::{
+    self, ProcessingOptions, Progress, ScaleFilter, MAX_OUTPUT_DIMENSION,
+};
+
+/// One second of black 1920x1080 frames
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "color=c=black:size=1920x1080:rate=30"])
+            .args(["-t", "1"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+/// Centroid of the bright pixels in the first frame of a `width` wide video
+fn bright_centroid(path: &Path, width: usize) -> (f64, f64) {
+    let out = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-f", "rawvideo", "-pix_fmt", "gray", "-"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "ffmpeg could not decode {}",
+        path.display()
+    );
+    let (mut sum_x, mut sum_y, mut count) = (0.0, 0.0, 0.0);
+    for (i, &luma) in out.stdout.iter().enumerate() {
+        if luma > 128 {
+            sum_x += (i % width) as f64 + 0.5;
+            sum_y += (i / width) as f64 + 0.5;
+            count += 1.0;
+        }
+    }
+    assert!(count > 0.0, "no cursor in {}", path.display());
+    (sum_x / count, sum_y / count)
+}
+
+#[test]
+fn downscaled_export_keeps_the_cursor_in_place() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("scaling");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+
+    // A white 24x24 square resting with its top-left corner at (600, 300)
+    let sprite = white_sprite(24);
+    let points = resting(600.0, 300.0);
+    let opts = ProcessingOptions {
+        output_width: 1280,
+        output_height: 720,
+        ..ProcessingOptions::default()
+    };
+    engine::process_video(&input, &output, &sprite, &points, &opts, |_: Progress| {}).unwrap();
+
+    assert_eq!(probe(&output, "v:0", "stream=width,height"), "1280,720");
+    let (x, y) = bright_centroid(&output, 1280);
+    let (expected_x, expected_y) = (612.0 * 2.0 / 3.0, 312.0 * 2.0 / 3.0);
+    assert!(
+        (x - expected_x).abs() < 1.5 && (y - expected_y).abs() < 1.5,
+        "cursor centered at ({:.1}, {:.1}), expected ({:.1}, {:.1})",
+        x,
+        y,
+        expected_x,
+        expected_y
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn output_size_keeps_the_aspect_ratio_and_even_sizes() {
+    let size = |output_width, output_height| {
+        ProcessingOptions {
+            output_width,
+            output_height,
+            ..ProcessingOptions::default()
+        }
+        .output_size(1920, 1080)
+    };
+    assert_eq!(size(0, 0), (1920, 1080));
+    assert_eq!(size(1280, 720), (1280, 720));
+    assert_eq!(size(0, 720), (1280, 720));
+    assert_eq!(size(1280, 0), (1280, 720));
+    // 853 wide would be 479.8 high; both round down to even
+    assert_eq!(size(853, 0), (852, 480));
+    assert_eq!(size(1281, 721), (1280, 720));
+    assert_eq!(size(1, 0), (2, 2));
+
+    let clamped = ProcessingOptions {
+        output_width: 100_000,
+        ..ProcessingOptions::default()
+    }
+    .validated()
+    .unwrap();
+    assert_eq!(clamped.output_width, MAX_OUTPUT_DIMENSION);
+}
+
+#[test]
+fn auto_filter_sharpens_large_downscales_only() {
+    let auto = |to| ScaleFilter::Auto.resolve((3840, 2160), to);
+    assert_eq!(auto((1920, 1080)), ScaleFilter::Bilinear);
+    assert_eq!(auto((1280, 720)), ScaleFilter::Lanczos);
+    assert_eq!(auto((7680, 4320)), ScaleFilter::Bilinear);
+    assert_eq!(
+        ScaleFilter::Area.resolve((3840, 2160), (640, 360)),
+        ScaleFilter::Area
+    );
+    assert_eq!(ScaleFilter::from_raw(3), Some(ScaleFilter::Lanczos));
+    assert_eq!(ScaleFilter::from_raw(5), None);
+}
@@ -0,0 +1,142 @@
+// speedup.rs - Idle stretches play faster, and the cursor keeps up with the
+// sped-up picture
+//
+// The export test generates its input with the ffmpeg CLI and reads the
+// output back with ffprobe; it is skipped when either is missing.
+mod common;
+
+use common::{decoded_frames, ffmpeg_command, generate, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{
+    self, CPoint, IdleSpeedup, ProcessingOptions, Progress, TimeMap,
+};
+
+/// Fourteen seconds of a 160x120 test pattern at 30 fps
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=160x120:rate=30"])
+            .args(["-t", "14"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+/// 60 Hz samples: a 2 s drag right, 10 s still, a 2 s drag down
+fn still_in_the_middle() -> Vec<CPoint> {
+    (0..=14 * 60)
+        .map(|i| {
+            let ms = f64::from(i) * 1000.0 / 60.0;
+            let (x, y) = match ms {
+                ms if ms < 2000.0 => (20.0 + 100.0 * ms as f32 / 2000.0, 20.0),
+                ms if ms < 12_000.0 => (120.0, 20.0),
+                ms => (120.0, 20.0 + 80.0 * (ms - 12_000.0) as f32 / 2000.0),
+            };
+            CPoint {
+                x,
+                y,
+                timestamp_ms: ms,
+            }
+        })
+        .collect()
+}
+
+fn four_times() -> IdleSpeedup {
+    IdleSpeedup {
+        factor: 4.0,
+        ..IdleSpeedup::default()
+    }
+}
+
+#[test]
+fn ten_still_seconds_at_4x_save_seven_and_a_half() {
+    let map = TimeMap::idle(&still_in_the_middle(), &four_times(), &[]);
+    let stretches: Vec<(f64, f64)> = map.stretches().collect();
+    assert_eq!(stretches.len(), 1);
+    // Give or take the last and first few px of the drags
+    let (start, end) = stretches[0];
+    assert!((start - 2000.0).abs() < 150.0 && (end - 12_000.0).abs() < 150.0);
+
+    let saved = 14_000.0 - map.output_ms(14_000.0);
+    assert!((saved - 7500.0).abs() < 150.0, "saved {:.0} ms", saved);
+    // Normal speed before, a quarter inside, and the round trip holds
+    assert_eq!(map.output_ms(1000.0), 1000.0);
+    assert!((map.output_ms(6000.0) - (start + (6000.0 - start) / 4.0)).abs() < 1e-6);
+    for output_ms in [500.0, 3000.0, 4400.0, 6000.0] {
+        assert!((map.output_ms(map.source_ms(output_ms)) - output_ms).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn short_pauses_and_changing_frames_are_not_sped_up() {
+    let path = still_in_the_middle();
+    let patient = IdleSpeedup {
+        min_idle_ms: 11_000.0,
+        ..four_times()
+    };
+    assert!(TimeMap::idle(&path, &patient, &[]).is_identity());
+
+    // The picture changes at 7 s, splitting the stretch in two
+    let activity: Vec<(f64, f32)> = (1..56)
+        .map(|i| f64::from(i) * 250.0)
+        .map(|ms| (ms, if ms == 7000.0 { 5.0 } else { 0.0 }))
+        .collect();
+    let watching = IdleSpeedup {
+        max_frame_change_percent: 1.0,
+        ..four_times()
+    };
+    let stretches: Vec<(f64, f64)> = TimeMap::idle(&path, &watching, &activity)
+        .stretches()
+        .collect();
+    assert_eq!(stretches.len(), 2);
+    assert_eq!(stretches[0].1, 6750.0);
+    assert_eq!(stretches[1].0, 7000.0);
+
+    let off = IdleSpeedup {
+        factor: 1.0,
+        ..IdleSpeedup::default()
+    };
+    assert!(TimeMap::idle(&path, &off, &[]).is_identity());
+}
+
+#[test]
+fn sped_up_export_reports_both_durations() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("speedup");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+
+    let sprite = white_sprite(8);
+    let opts = ProcessingOptions {
+        frame_rate: 30,
+        idle_speedup: Some(four_times()),
+        ..ProcessingOptions::default()
+    };
+    let report = engine::process_video(
+        &input,
+        &output,
+        &sprite,
+        &still_in_the_middle(),
+        &opts,
+        |_: Progress| {},
+    )
+    .unwrap();
+
+    let (source, output_s) = (
+        report.source_duration.as_secs_f64(),
+        report.output_duration.as_secs_f64(),
+    );
+    assert!((source - 14.0).abs() < 0.1, "source {:.2} s", source);
+    assert!(
+        (source - output_s - 7.5).abs() < 0.5,
+        "{:.2} s became {:.2} s",
+        source,
+        output_s
+    );
+    // Decoded frames at 30 fps
+    let decoded_s = f64::from(decoded_frames(&output)) / 30.0;
+    assert!((decoded_s - output_s).abs() < 0.1);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,106 @@
+// start_alignment.rs - A late first frame does not shift the cursor for the
+// whole video
+//
+// The input is generated with the ffmpeg CLI: a green box moves along the
+// same path as the cursor, so each output frame shows where the cursor
+// belongs. The test is skipped when ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{ffmpeg_command, generate, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use std::process::Command;
+use video_effects_processor::engine::{self, CPoint, ProcessingOptions, Progress, SmoothingMode};
+
+const FRAME_RATE: i32 = 30;
+/// Box and cursor speed (px/s); half a frame at 30 fps is ~4 px
+const SPEED: f32 = 250.0;
+
+/// One second at 30 fps of a 12x12 green box moving right at `SPEED` from
+/// x=20 on a dark 320x240 frame, with the first frame stamped 25 ms late
+/// (on the grid it belongs at 0) and the rest on time
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "color=c=0x202020:size=320x240:rate=30"])
+            .args(["-f", "lavfi", "-i", "color=c=lime:size=12x12:rate=30"])
+            .args([
+                "-filter_complex",
+                &format!(
+                    "[0][1]overlay=x=20+{}*t:y=60:shortest=1,setpts=if(eq(N\\,0)\\,0.025/TB\\,PTS)",
+                    SPEED
+                ),
+            ])
+            .args(["-frames:v", "30", "-fps_mode", "passthrough"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv444p"]),
+        path,
+    );
+}
+
+/// Mean x of the pixels `matches` picks out in output frame `index`
+fn centroid_x(path: &Path, index: u32, matches: impl Fn(&[u8]) -> bool) -> f32 {
+    let out = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-vf", &format!("select=eq(n\\,{})", index)])
+        .args(["-frames:v", "1", "-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "ffmpeg could not decode {}",
+        path.display()
+    );
+    let (mut sum, mut count) = (0.0, 0.0);
+    for (i, pixel) in out.stdout.chunks_exact(3).enumerate() {
+        if matches(pixel) {
+            sum += (i % 320) as f32;
+            count += 1.0;
+        }
+    }
+    assert!(count > 0.0, "nothing found in frame {}", index);
+    sum / count
+}
+
+#[test]
+fn late_first_frame_keeps_the_cursor_in_sync() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("start-alignment");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+
+    // 12x12 like the box, the cursor's hotspot on the box's left edge
+    let sprite = white_sprite(12);
+    let points: Vec<CPoint> = (0..=60)
+        .map(|i| {
+            let ms = f64::from(i) * 1000.0 / 60.0;
+            CPoint {
+                x: 20.0 + SPEED * ms as f32 / 1000.0,
+                y: 160.0,
+                timestamp_ms: ms,
+            }
+        })
+        .collect();
+    // Unfiltered, so the cursor is exactly on the path
+    let opts = ProcessingOptions {
+        frame_rate: FRAME_RATE,
+        smoothing_mode: SmoothingMode::Passthrough,
+        ..ProcessingOptions::default()
+    };
+    engine::process_video(&input, &output, &sprite, &points, &opts, |_: Progress| {}).unwrap();
+
+    let half_frame_px = SPEED / FRAME_RATE as f32 / 2.0;
+    for index in [5, 15, 25] {
+        let cursor = centroid_x(&output, index, |p| p.iter().all(|&c| c > 200));
+        let green_box = centroid_x(&output, index, |p| p[1] > 150 && p[0] < 100 && p[2] < 100);
+        assert!(
+            (cursor - green_box).abs() < half_frame_px,
+            "frame {}: cursor at x={:.1}, box at x={:.1}",
+            index,
+            cursor,
+            green_box
+        );
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
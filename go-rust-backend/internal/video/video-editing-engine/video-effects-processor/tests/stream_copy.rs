@@ -0,0 +1,178 @@
+// stream_copy.rs - With nothing to draw, the input's packets are copied
+// into the output untouched, cut at keyframes, and far faster than a render
+//
+// The input is generated with the ffmpeg CLI and packets are listed with
+// ffprobe; the tests are skipped when either is missing.
+mod common;
+
+use common::{
+    ffmpeg_command, generate, probe_lines, sweep_720p, tools_available, white_sprite, work_dir,
+};
+use std::path::Path;
+use video_effects_processor::engine::{
+    self, CPoint, ProcessingOptions, ProcessingReport, RenderObserver, RenderWarning, WarningKind,
+};
+
+/// Frames in the input (three seconds at 30 fps, a keyframe every second)
+const FRAMES: u64 = 90;
+
+/// Three seconds of a 1280x720 test pattern with a 440 Hz tone, with a
+/// keyframe on every whole second
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc2=size=1280x720:rate=30"])
+            .args(["-f", "lavfi", "-i", "sine=frequency=440:sample_rate=48000"])
+            .args(["-t", "3"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+            .args(["-g", "30", "-keyint_min", "30", "-sc_threshold", "0"])
+            .args(["-c:a", "aac"]),
+        path,
+    );
+}
+
+/// Sizes of the video packets of `path`, in decode order
+fn video_packet_sizes(path: &Path) -> Vec<u64> {
+    probe_lines(path, "v:0", "packet=size")
+        .iter()
+        .map(|line| line.parse().unwrap())
+        .collect()
+}
+
+/// Records the kind of each warning
+struct Warnings<'a>(&'a mut Vec<WarningKind>);
+
+impl RenderObserver for Warnings<'_> {
+    fn warning(&mut self, warning: &RenderWarning) {
+        self.0.push(warning.kind);
+    }
+}
+
+fn render(
+    input: &Path,
+    output: &Path,
+    points: &[CPoint],
+    opts: &ProcessingOptions,
+) -> (ProcessingReport, Vec<WarningKind>) {
+    let sprite = white_sprite(8);
+    let mut warnings = Vec::new();
+    let report = engine::process_video(
+        input,
+        output,
+        &sprite,
+        points,
+        opts,
+        Warnings(&mut warnings),
+    )
+    .unwrap_or_else(|e| panic!("{} failed: {}", output.display(), e));
+    (report, warnings)
+}
+
+#[test]
+fn copy_keeps_every_video_packet() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("stream-copy");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+
+    let opts = ProcessingOptions {
+        stream_copy: true,
+        ..ProcessingOptions::default()
+    };
+    let (report, warnings) = render(&input, &output, &[], &opts);
+    assert!(report.stream_copied);
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    assert_eq!(report.frames_encoded, FRAMES);
+    assert_eq!(video_packet_sizes(&output), video_packet_sizes(&input));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+// Wall-clock ratios depend on the machine and its load, so this only runs
+// when asked for: cargo test --test stream_copy -- --ignored
+#[test]
+#[ignore = "timing comparison; flaky on loaded machines"]
+fn copy_beats_encoding() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("stream-copy-timing");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    let (copied, output) = (dir.join("copied.mp4"), dir.join("encoded.mp4"));
+    let copy_opts = ProcessingOptions {
+        stream_copy: true,
+        ..ProcessingOptions::default()
+    };
+    let (copy, _) = render(&input, &copied, &[], &copy_opts);
+    assert!(copy.stream_copied);
+
+    let (encode, _) = render(
+        &input,
+        &output,
+        &sweep_720p(),
+        &ProcessingOptions::default(),
+    );
+    assert!(!encode.stream_copied);
+    assert!(
+        copy.wall_clock * 10 < encode.wall_clock,
+        "copy took {:?}, encoding {:?}",
+        copy.wall_clock,
+        encode.wall_clock
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn trimmed_copy_starts_at_the_keyframe_before_the_trim() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("stream-copy-trim");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+
+    let opts = ProcessingOptions {
+        stream_copy: true,
+        trim_start_ms: 1200.0,
+        trim_end_ms: Some(2500.0),
+        ..ProcessingOptions::default()
+    };
+    let (report, _) = render(&input, &output, &[], &opts);
+    assert!(report.stream_copied);
+
+    // From the keyframe at 1 s to the trim end, 1.5 s later
+    let (source, copied) = (video_packet_sizes(&input), video_packet_sizes(&output));
+    assert!(
+        (43..=48).contains(&copied.len()),
+        "{} packets copied",
+        copied.len()
+    );
+    assert_eq!(copied[..], source[30..30 + copied.len()]);
+    assert!((report.source_duration.as_secs_f64() - 1.5).abs() < 0.1);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn copy_falls_back_to_encoding_when_there_is_a_cursor_to_draw() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("stream-copy-fallback");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+
+    let opts = ProcessingOptions {
+        stream_copy: true,
+        frame_rate: 30,
+        ..ProcessingOptions::default()
+    };
+    let (report, warnings) = render(&input, &output, &sweep_720p(), &opts);
+    assert!(!report.stream_copied);
+    assert!(warnings.contains(&WarningKind::StreamCopyUnavailable));
+    assert_eq!(report.warnings.stream_copy_unavailable, 1);
+    assert_eq!(report.frames_encoded, FRAMES);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,56 @@
+// svg.rs - SVG sprites are rasterized at the cursor scale rather than
+// resampled, and without the `svg` feature loading one is an error
+//
+// tests/fixtures/triangle.svg is a 10x10 outlined triangle pointing right.
+mod common;
+
+use std::path::PathBuf;
+use video_effects_processor::engine::{self, EngineError};
+
+fn triangle() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/triangle.svg")
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn svg_rasterizes_at_the_cursor_scale() {
+    let native = engine::load_cursor_sprite(&triangle()).unwrap();
+    assert_eq!((native.width, native.height), (10, 10));
+    assert!(native.data.chunks_exact(4).any(|px| px[3] == 255));
+
+    let mut opts = engine::ProcessingOptions {
+        cursor_scale: 3.0,
+        hotspot_x: 1.0,
+        hotspot_y: 5.0,
+        ..engine::ProcessingOptions::default()
+    };
+    let sprite = engine::load_cursor_sprite_for(&triangle(), &mut opts).unwrap();
+    assert_eq!((sprite.width, sprite.height), (30, 30));
+    assert!(sprite.data.chunks_exact(4).any(|px| px[3] == 255));
+    // Drawn as is, with the hotspot moved onto the rasterized pixels
+    assert_eq!(opts.cursor_scale, 1.0);
+    assert_eq!((opts.hotspot_x, opts.hotspot_y), (3.0, 15.0));
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn invalid_svg_is_a_load_error() {
+    let dir = common::work_dir("invalid-svg");
+    let path = dir.join("broken.svg");
+    std::fs::write(&path, "<svg xmlns=\"http://www.w3.org/2000/svg\"><path").unwrap();
+    let result = engine::load_cursor_sprite(&path);
+    match result {
+        Err(EngineError::Sprite(e)) => assert!(e.to_string().contains("SVG"), "{}", e),
+        other => panic!("{:?}", other.map(|sprite| sprite.width)),
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[cfg(not(feature = "svg"))]
+#[test]
+fn svg_without_the_feature_is_a_load_error() {
+    match engine::load_cursor_sprite(&triangle()) {
+        Err(EngineError::Sprite(e)) => assert!(e.to_string().contains("not compiled in"), "{}", e),
+        other => panic!("{:?}", other.map(|sprite| sprite.width)),
+    }
+}
@@ -0,0 +1,91 @@
+// sync_offset.rs - The manual sync offset moves the cursor along its path
+//
+// The input is generated with the ffmpeg CLI; the test is skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{ffmpeg_command, generate, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{self, CPoint, ProcessingOptions, SmoothingMode};
+
+/// Cursor speed (px/ms) along the path
+const SPEED: f32 = 0.1;
+
+/// Two seconds of a dark 320x240 frame
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "color=c=0x202020:size=320x240:rate=30"])
+            .args(["-t", "2"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+/// Mean x of the white pixels in `png`
+fn cursor_x(png: &Path) -> f32 {
+    let image = image::open(png).unwrap().to_rgba8();
+    let (mut sum, mut count) = (0.0, 0.0);
+    for (x, _, pixel) in image.enumerate_pixels() {
+        if pixel.0[..3].iter().all(|&c| c > 200) {
+            sum += x as f32;
+            count += 1.0;
+        }
+    }
+    assert!(count > 0.0, "no cursor in {}", png.display());
+    sum / count
+}
+
+#[test]
+fn half_second_offset_moves_the_cursor_half_a_second_along() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("sync-offset");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    let sprite = white_sprite(8);
+    // Left to right across the frame over the two seconds
+    let points: Vec<CPoint> = (0..=120)
+        .map(|i| {
+            let ms = f64::from(i) * 1000.0 / 60.0;
+            CPoint {
+                x: 20.0 + SPEED * ms as f32,
+                y: 120.0,
+                timestamp_ms: ms,
+            }
+        })
+        .collect();
+    let render = |offset_ms: f64| {
+        let png = dir.join(format!("offset-{}.png", offset_ms));
+        let opts = ProcessingOptions {
+            smoothing_mode: SmoothingMode::Passthrough,
+            cursor_sync_offset_ms: offset_ms,
+            ..ProcessingOptions::default()
+        };
+        engine::render_preview_frame(&input, &png, &sprite, &points, &opts, 1000.0).unwrap();
+        cursor_x(&png)
+    };
+
+    let (on_time, later) = (render(0.0), render(500.0));
+    assert!(
+        (on_time - (20.0 + SPEED * 1000.0 + 4.0)).abs() < 1.0,
+        "{}",
+        on_time
+    );
+    assert!(
+        (later - on_time - SPEED * 500.0).abs() < 1.0,
+        "+500 ms moved the cursor from x={:.1} to x={:.1}",
+        on_time,
+        later
+    );
+    // And back the other way
+    let earlier = render(-500.0);
+    assert!(
+        (on_time - earlier - SPEED * 500.0).abs() < 1.0,
+        "{}",
+        earlier
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,94 @@
+// tail.rs - Past either end of the cursor path the cursor is held, hidden
+// or carried on at its end velocity, as the tail behavior says
+//
+// The input is generated with the ffmpeg CLI; the tests are skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{drag, ffmpeg_command, generate, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{self, ProcessingOptions, TailBehavior};
+
+/// One second of black 160x120
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "color=c=black:size=160x120:rate=30"])
+            .args(["-t", "1"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv444p"]),
+        path,
+    );
+}
+
+/// Columns of the bright (sprite) pixels in the preview at `timestamp_ms`
+fn lit_columns(
+    input: &Path,
+    png: &Path,
+    tail_behavior: TailBehavior,
+    cursor_sync_offset_ms: f64,
+    timestamp_ms: f64,
+) -> Vec<u32> {
+    // Left to right over the first half of the video
+    let points = drag((20.0, 60.0), (80.0, 60.0), 500.0);
+    let opts = ProcessingOptions {
+        tail_behavior,
+        cursor_sync_offset_ms,
+        ..ProcessingOptions::default()
+    };
+    engine::render_preview_frame(input, png, &white_sprite(8), &points, &opts, timestamp_ms)
+        .unwrap();
+    let image = image::open(png).unwrap().to_luma8();
+    image
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel.0[0] > 200)
+        .map(|(x, _, _)| x)
+        .collect()
+}
+
+fn mean(columns: &[u32]) -> f64 {
+    assert!(!columns.is_empty(), "no cursor");
+    columns.iter().map(|&x| f64::from(x)).sum::<f64>() / columns.len() as f64
+}
+
+#[test]
+fn cursor_after_the_path_ends_is_held_hidden_or_extrapolated() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("tail-after");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+    let png = dir.join("frame.png");
+
+    // 400 ms after the last sample
+    let held = lit_columns(&input, &png, TailBehavior::HoldLastPosition, 0.0, 900.0);
+    assert!(!held.is_empty());
+    let hidden = lit_columns(&input, &png, TailBehavior::HideAfterMs(200), 0.0, 900.0);
+    assert!(hidden.is_empty(), "{} sprite pixels", hidden.len());
+    // Still within the hold time
+    let holding = lit_columns(&input, &png, TailBehavior::HideAfterMs(200), 0.0, 600.0);
+    assert_eq!(mean(&holding), mean(&held));
+
+    // Carried on to the right, then held
+    let extrapolated = lit_columns(&input, &png, TailBehavior::ExtrapolateMs(200), 0.0, 900.0);
+    assert!(mean(&extrapolated) > mean(&held));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn cursor_before_the_path_starts_is_hidden_too() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("tail-before");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+    let png = dir.join("frame.png");
+
+    // The path starts 300 ms into the video
+    let before = |tail_behavior| lit_columns(&input, &png, tail_behavior, -300.0, 0.0);
+    assert!(!before(TailBehavior::HoldLastPosition).is_empty());
+    assert!(before(TailBehavior::HideAfterMs(200)).is_empty());
+    assert!(!before(TailBehavior::HideAfterMs(400)).is_empty());
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,89 @@
+// thumbnail.rs - Poster frames: one frame of a video saved as a PNG shrunk
+// to a maximum width, either on request or at the end of an export
+//
+// The input is generated with the ffmpeg CLI; the tests are skipped when
+// ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{ffmpeg_command, generate, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{self, ProcessingOptions, Progress};
+
+/// Two seconds of a moving 640x360 test pattern
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc2=size=640x360:rate=30"])
+            .args(["-t", "2", "-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+fn thumbnail(input: &Path, png: &Path, timestamp_ms: f64, max_width: u32) -> image::RgbImage {
+    engine::generate_thumbnail(input, png, timestamp_ms, max_width)
+        .unwrap_or_else(|e| panic!("thumbnail at {} ms failed: {}", timestamp_ms, e));
+    image::open(png).unwrap().to_rgb8()
+}
+
+#[test]
+fn thumbnails_keep_the_aspect_ratio_and_follow_the_timestamp() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("thumbnail");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    let png = dir.join("thumbnail.png");
+    let early = thumbnail(&input, &png, 200.0, 160);
+    assert_eq!(early.dimensions(), (160, 90));
+    let late = thumbnail(&input, &png, 1500.0, 160);
+    assert_eq!(late.dimensions(), (160, 90));
+    let changed = early
+        .pixels()
+        .zip(late.pixels())
+        .filter(|(a, b)| a != b)
+        .count();
+    assert!(changed > 160 * 90 / 10, "only {} pixels changed", changed);
+
+    // 0 keeps the source size, and a wider limit does not enlarge it
+    assert_eq!(thumbnail(&input, &png, 200.0, 0).dimensions(), (640, 360));
+    assert_eq!(
+        thumbnail(&input, &png, 200.0, 1280).dimensions(),
+        (640, 360)
+    );
+
+    // Past the end, the last frame
+    let past_end = thumbnail(&input, &png, 60_000.0, 160);
+    assert_eq!(past_end, thumbnail(&input, &png, 1990.0, 160));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn export_writes_a_thumbnail_when_asked() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("thumbnail-export");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    let png = dir.join("poster.png");
+    let opts = ProcessingOptions {
+        thumbnail_path: Some(png.clone()),
+        thumbnail_max_width: 320,
+        ..ProcessingOptions::default()
+    };
+    let sprite = white_sprite(8);
+    let output = dir.join("output.mp4");
+    engine::process_video(&input, &output, &sprite, &[], &opts, |_: Progress| {}).unwrap();
+
+    let poster = image::open(&png).unwrap().to_rgb8();
+    assert_eq!(poster.dimensions(), (320, 180));
+    // 10% of the way into the two seconds
+    assert_eq!(
+        poster,
+        thumbnail(&output, &dir.join("check.png"), 200.0, 320)
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,114 @@
+// trim.rs - Trimmed exports cover exactly the requested input range
+//
+// The input is generated with the ffmpeg CLI and the output is read back
+// with ffprobe; the export tests are skipped when either is missing.
+mod common;
+
+use common::{
+    decoded_frames, drag, ffmpeg_command, generate, tools_available, white_sprite, work_dir,
+};
+use std::path::Path;
+use video_effects_processor::engine::{self, EngineError, ProcessingOptions, Progress};
+
+const FRAME_RATE: i32 = 30;
+
+/// Three seconds of a 320x240 test pattern at 30 fps, with a keyframe only
+/// every 2 s so the trim start falls between keyframes
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=320x240:rate=30"])
+            .args(["-t", "3"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-g", "60"]),
+        path,
+    );
+}
+
+fn render(
+    input: &Path,
+    output: &Path,
+    trim_start_ms: f64,
+    trim_end_ms: Option<f64>,
+) -> Result<(), EngineError> {
+    let sprite = white_sprite(8);
+    // Across the whole input, on the input's clock
+    let points = drag((40.0, 40.0), (280.0, 200.0), 3000.0);
+    let opts = ProcessingOptions {
+        frame_rate: FRAME_RATE,
+        trim_start_ms,
+        trim_end_ms,
+        ..ProcessingOptions::default()
+    };
+    engine::process_video(input, output, &sprite, &points, &opts, |_: Progress| {}).map(|_| ())
+}
+
+#[test]
+fn trimmed_export_lasts_the_requested_span() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("trim");
+    let input = dir.join("input.mp4");
+    generate_input(&input);
+
+    for (start_ms, end_ms) in [(1000.0, 2000.0), (1250.0, 2500.0)] {
+        let output = dir.join(format!("trim-{}.mp4", start_ms));
+        render(&input, &output, start_ms, Some(end_ms)).unwrap();
+        let expected = (end_ms - start_ms) / 1000.0 * f64::from(FRAME_RATE);
+        let frames = f64::from(decoded_frames(&output));
+        assert!(
+            (frames - expected).abs() <= 1.0,
+            "{}-{} ms gave {} frames, expected {}",
+            start_ms,
+            end_ms,
+            frames,
+            expected
+        );
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn start_past_the_end_of_the_input_is_refused() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("trim-past-end");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+    let result = render(&input, &output, 5000.0, None);
+    assert!(
+        matches!(result, Err(EngineError::TrimPastEnd { start_ms, .. }) if start_ms == 5000.0),
+        "{:?}",
+        result
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn trim_end_must_follow_the_start() {
+    let validated = |trim_start_ms, trim_end_ms| {
+        ProcessingOptions {
+            trim_start_ms,
+            trim_end_ms,
+            ..ProcessingOptions::default()
+        }
+        .validated()
+    };
+    assert!(validated(1000.0, Some(3000.0)).is_ok());
+    assert!(validated(0.0, None).is_ok());
+    for (start, end) in [
+        (2000.0, Some(1000.0)),
+        (1000.0, Some(1000.0)),
+        (-1.0, None),
+        (f64::NAN, None),
+        (0.0, Some(f64::INFINITY)),
+    ] {
+        assert!(
+            matches!(validated(start, end), Err(EngineError::InvalidTrim(_))),
+            "{} to {:?}",
+            start,
+            end
+        );
+    }
+}
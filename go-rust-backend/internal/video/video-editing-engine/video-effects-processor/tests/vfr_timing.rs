@@ -0,0 +1,104 @@
+// vfr_timing.rs - On a variable frame rate source the cursor is drawn where
+// the path has it at each output frame's time
+//
+// The input is generated with the ffmpeg CLI and output frames are read
+// back with it; the test is skipped when ffmpeg or ffprobe is missing.
+mod common;
+
+use common::{ffmpeg_command, generate, tools_available, white_sprite, work_dir};
+use std::path::Path;
+use std::process::Command;
+use video_effects_processor::engine::{self, CPoint, ProcessingOptions, Progress};
+
+const FRAME_RATE: i32 = 30;
+
+/// About three seconds of a dark 320x240 frame whose frames alternately last
+/// 1/30 s and 3/30 s, with timestamps starting at 0.4 s
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "color=c=0x202020:size=320x240:rate=30"])
+            .args(["-vf", "setpts=(N+2*floor(N/2))/30/TB"])
+            .args(["-frames:v", "46", "-fps_mode", "passthrough"])
+            .args(["-output_ts_offset", "0.4"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+/// Cursor x (px) at `ms`: resting at 60 until 1.2 s, then a quick move to
+/// rest at 240 from 1.4 s
+fn path_x(ms: f64) -> f32 {
+    match ms {
+        ms if ms < 1200.0 => 60.0,
+        ms if ms < 1400.0 => 60.0 + 180.0 * ((ms - 1200.0) / 200.0) as f32,
+        _ => 240.0,
+    }
+}
+
+/// Mean x of the white pixels of output frame `index`
+fn cursor_x(path: &Path, index: u32) -> f32 {
+    let out = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-vf", &format!("select=eq(n\\,{})", index)])
+        .args(["-frames:v", "1", "-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "ffmpeg could not decode {}",
+        path.display()
+    );
+    let (mut sum, mut count) = (0.0, 0.0);
+    for (i, pixel) in out.stdout.chunks_exact(3).enumerate() {
+        if pixel.iter().all(|&c| c > 200) {
+            sum += (i % 320) as f32;
+            count += 1.0;
+        }
+    }
+    assert!(count > 0.0, "no cursor in frame {}", index);
+    sum / count
+}
+
+#[test]
+fn cursor_follows_the_path_on_a_vfr_source() {
+    if !tools_available() {
+        return;
+    }
+    let dir = work_dir("vfr-timing");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.mp4"));
+    generate_input(&input);
+
+    let sprite = white_sprite(6);
+    let points: Vec<CPoint> = (0..=60)
+        .map(|i| {
+            let ms = f64::from(i) * 50.0;
+            CPoint {
+                x: path_x(ms),
+                y: 120.0,
+                timestamp_ms: ms,
+            }
+        })
+        .collect();
+    let opts = ProcessingOptions {
+        frame_rate: FRAME_RATE,
+        ..ProcessingOptions::default()
+    };
+    engine::process_video(&input, &output, &sprite, &points, &opts, |_: Progress| {}).unwrap();
+
+    // Half a second early or late would land either frame mid-move
+    for ms in [900.0, 1700.0] {
+        let index = (ms / 1000.0 * f64::from(FRAME_RATE)).round() as u32;
+        // The sprite hangs 6 px right of the hotspot
+        let (expected, got) = (path_x(ms) + 3.0, cursor_x(&output, index));
+        assert!(
+            (got - expected).abs() <= 6.0,
+            "at {} ms the cursor is at x={:.1}, expected {:.1}",
+            ms,
+            got,
+            expected
+        );
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,104 @@
+// watermark.rs - The logo lands at its corner offset, and nowhere else
+use video_effects_processor::engine::{
+    BadgeCorner, EngineError, FrameView, Overlay, ProcessingOptions, Watermark, WatermarkImage,
+    WatermarkOverlay,
+};
+
+const LOGO_WIDTH: u32 = 10;
+const LOGO_HEIGHT: u32 = 6;
+/// Row padding past the pixels, which must come back untouched
+const PADDING: usize = 8;
+const BACKGROUND: [u8; 4] = [40, 80, 120, 255];
+
+/// Opaque white logo whose right half is half transparent
+fn logo() -> WatermarkImage {
+    let mut data = Vec::new();
+    for _ in 0..LOGO_HEIGHT {
+        for x in 0..LOGO_WIDTH {
+            let alpha = if x < LOGO_WIDTH / 2 { 255 } else { 128 };
+            data.extend_from_slice(&[255, 255, 255, alpha]);
+        }
+    }
+    WatermarkImage::Rgba {
+        data,
+        width: LOGO_WIDTH,
+        height: LOGO_HEIGHT,
+    }
+}
+
+#[test]
+fn logo_sits_at_bottom_right_margin() {
+    let watermark = Watermark {
+        image: logo(),
+        corner: BadgeCorner::BottomRight,
+        margin_x_px: 24.0,
+        margin_y_px: 12.0,
+        scale: 1.0,
+        opacity: 0.5,
+    };
+    let overlay = WatermarkOverlay::new(&watermark, false).unwrap();
+
+    // The same margins at any resolution
+    for (width, height) in [(160u32, 90u32), (333, 201)] {
+        let stride = width as usize * 4 + PADDING;
+        let mut data = vec![0xA5; stride * height as usize];
+        for row in data.chunks_exact_mut(stride) {
+            for px in row[..width as usize * 4].chunks_exact_mut(4) {
+                px.copy_from_slice(&BACKGROUND);
+            }
+        }
+        overlay.render(&mut FrameView::new(&mut data, width, height, stride), 0.0);
+
+        let left = width - 24 - LOGO_WIDTH;
+        let top = height - 12 - LOGO_HEIGHT;
+        for (y, row) in data.chunks_exact(stride).enumerate() {
+            let (pixels, padding) = row.split_at(width as usize * 4);
+            assert!(padding.iter().all(|&b| b == 0xA5), "row {} padding", y);
+            for (x, px) in pixels.chunks_exact(4).enumerate() {
+                let (x, y) = (x as u32, y as u32);
+                let inside =
+                    (left..left + LOGO_WIDTH).contains(&x) && (top..top + LOGO_HEIGHT).contains(&y);
+                if !inside {
+                    assert_eq!(px, BACKGROUND, "{}x{}: ({}, {})", width, height, x, y);
+                    continue;
+                }
+                // White over the background at opacity x the logo's alpha
+                let coverage = if x - left < LOGO_WIDTH / 2 { 0.5 } else { 0.25 };
+                for c in 0..3 {
+                    let expected = BACKGROUND[c] as f32 + (255.0 - BACKGROUND[c] as f32) * coverage;
+                    assert!(
+                        (px[c] as f32 - expected).abs() <= 1.5,
+                        "{}x{}: ({}, {}) channel {} is {}, expected {}",
+                        width,
+                        height,
+                        x,
+                        y,
+                        c,
+                        px[c],
+                        expected
+                    );
+                }
+                assert_eq!(px[3], 255);
+            }
+        }
+    }
+}
+
+#[test]
+fn mis_sized_rgba_logo_is_rejected() {
+    let options = ProcessingOptions {
+        watermark: Some(Watermark {
+            image: WatermarkImage::Rgba {
+                data: vec![255; 10],
+                width: LOGO_WIDTH,
+                height: LOGO_HEIGHT,
+            },
+            ..Watermark::default()
+        }),
+        ..ProcessingOptions::default()
+    };
+    assert!(matches!(
+        options.validated(),
+        Err(EngineError::InvalidBuffer(_))
+    ));
+}
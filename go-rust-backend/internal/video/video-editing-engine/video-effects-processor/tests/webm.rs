@@ -0,0 +1,83 @@
+// webm.rs - VP9 exports go into WebM, with the libvpx speed options under the
+// caller's control
+//
+// The input is generated with the ffmpeg CLI and the output is read back
+// with ffprobe; the export test is skipped when either is missing or the
+// build has no libvpx-vp9.
+mod common;
+
+use common::{drag, encoder_available, ffmpeg_command, generate, probe, white_sprite, work_dir};
+use std::path::Path;
+use video_effects_processor::engine::{
+    self, Container, EngineError, ProcessingOptions, Progress, VideoCodec, Vp9Deadline,
+    MAX_VP9_CPU_USED,
+};
+
+/// A one second, 160x120 test pattern
+fn generate_input(path: &Path) {
+    generate(
+        ffmpeg_command()
+            .args(["-f", "lavfi", "-i", "testsrc=size=160x120:rate=10"])
+            .args(["-t", "1"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"]),
+        path,
+    );
+}
+
+fn render(input: &Path, output: &Path, opts: &ProcessingOptions) -> Result<(), EngineError> {
+    let sprite = white_sprite(8);
+    let points = drag((20.0, 20.0), (140.0, 100.0), 1000.0);
+    engine::process_video(input, output, &sprite, &points, opts, |_: Progress| {}).map(|_| ())
+}
+
+#[test]
+fn vp9_export_reopens_as_webm() {
+    if !encoder_available("libvpx-vp9") {
+        return;
+    }
+    let dir = work_dir("webm");
+    let (input, output) = (dir.join("input.mp4"), dir.join("output.webm"));
+    generate_input(&input);
+    let opts = ProcessingOptions {
+        codec: VideoCodec::Vp9,
+        vp9_deadline: Vp9Deadline::Realtime,
+        vp9_cpu_used: MAX_VP9_CPU_USED,
+        ..ProcessingOptions::default()
+    };
+    render(&input, &output, &opts).unwrap();
+
+    let format = probe(&output, "", "format=format_name");
+    assert!(format.contains("webm"), "output format {}", format);
+    assert_eq!(probe(&output, "v:0", "stream=codec_name"), "vp9");
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn vp9_is_rejected_in_mp4() {
+    // Checked before the input is opened, so no input is needed
+    let dir = work_dir("webm-mp4");
+    let opts = ProcessingOptions {
+        codec: VideoCodec::Vp9,
+        ..ProcessingOptions::default()
+    };
+    let result = render(&dir.join("missing.mp4"), &dir.join("output.mp4"), &opts);
+    assert!(matches!(
+        result,
+        Err(EngineError::UnsupportedOutput {
+            codec: VideoCodec::Vp9,
+            container: Container::Mp4,
+        })
+    ));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn vp9_speed_is_clamped() {
+    let opts = ProcessingOptions {
+        vp9_cpu_used: 99,
+        ..ProcessingOptions::default()
+    };
+    assert_eq!(opts.validated().unwrap().vp9_cpu_used, MAX_VP9_CPU_USED);
+    assert_eq!(Vp9Deadline::from_raw(2), Some(Vp9Deadline::Realtime));
+    assert_eq!(Vp9Deadline::from_raw(3), None);
+}
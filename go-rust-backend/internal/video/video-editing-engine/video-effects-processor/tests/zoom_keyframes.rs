@@ -0,0 +1,167 @@
+// zoom_keyframes.rs - Manual zoom keyframes crop the frame the same way the camera does
+use video_effects_processor::engine::{
+    parse_zoom_keyframes, CameraOverlay, CameraPlan, EngineError, FrameView, Overlay,
+    ProcessingOptions, ZoomEasing, ZoomKeyframe,
+};
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 180;
+
+/// 2x around (100, 60) for 0-4 s: the held crop is 160x90 at (20, 15)
+fn keyframe() -> ZoomKeyframe {
+    ZoomKeyframe {
+        start_ms: 0.0,
+        end_ms: 4000.0,
+        zoom: 2.0,
+        center_x: 100.0,
+        center_y: 60.0,
+        easing: ZoomEasing::Smoothstep,
+    }
+}
+
+/// Opaque frame with every channel varying
+fn pattern() -> Vec<u8> {
+    let mut data = Vec::with_capacity((WIDTH * HEIGHT * 4) as usize);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            data.extend_from_slice(&[
+                (x % 256) as u8,
+                (y * 7 % 256) as u8,
+                ((x ^ y) % 256) as u8,
+                255,
+            ]);
+        }
+    }
+    data
+}
+
+fn pixel(data: &[u8], x: u32, y: u32) -> &[u8] {
+    let i = ((y * WIDTH + x) * 4) as usize;
+    &data[i..i + 4]
+}
+
+#[test]
+fn zoomed_frame_is_the_scaled_crop() {
+    let plan = CameraPlan::from_keyframes(&[keyframe()], WIDTH, HEIGHT);
+    let original = pattern();
+    let mut zoomed = original.clone();
+    CameraOverlay::new(&plan).render(
+        &mut FrameView::new(&mut zoomed, WIDTH, HEIGHT, WIDTH as usize * 4),
+        2000.0,
+    );
+
+    for y in 0..HEIGHT - 1 {
+        for x in 0..WIDTH - 1 {
+            let (sx, sy) = (20 + x / 2, 15 + y / 2);
+            let actual = pixel(&zoomed, x, y);
+            if x % 2 == 0 && y % 2 == 0 {
+                // Lands exactly on a source pixel
+                assert_eq!(actual, pixel(&original, sx, sy), "at ({}, {})", x, y);
+                continue;
+            }
+            // Halfway between source pixels: the average of the neighbours
+            let (nx, ny) = (sx + x % 2, sy + y % 2);
+            for (c, &value) in actual.iter().enumerate() {
+                let sum: u32 = [(sx, sy), (nx, sy), (sx, ny), (nx, ny)]
+                    .iter()
+                    .map(|&(px, py)| pixel(&original, px, py)[c] as u32)
+                    .sum();
+                let expected = sum as f32 / 4.0;
+                assert!(
+                    (value as f32 - expected).abs() <= 1.0,
+                    "at ({}, {}) channel {}: {} vs {}",
+                    x,
+                    y,
+                    c,
+                    value,
+                    expected
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn full_frame_between_keyframes() {
+    let later = ZoomKeyframe {
+        start_ms: 6000.0,
+        end_ms: 7000.0,
+        easing: ZoomEasing::Spring,
+        ..keyframe()
+    };
+    let plan = CameraPlan::from_keyframes(&[later, keyframe()], WIDTH, HEIGHT);
+    for t in [0.0, 4000.0, 5000.0, 6000.0, 7000.0, 9000.0] {
+        let crop = plan.crop_at(t);
+        assert_eq!((crop.x, crop.y), (0.0, 0.0), "at {} ms", t);
+        assert_eq!((crop.width, crop.height), (WIDTH as f32, HEIGHT as f32));
+    }
+    // Zooming in narrows the crop monotonically
+    let widths: Vec<f32> = (0..=12)
+        .map(|k| plan.crop_at(k as f64 * 50.0).width)
+        .collect();
+    assert!(widths.windows(2).all(|w| w[1] <= w[0]));
+    assert_eq!(plan.crop_at(6500.0).width, WIDTH as f32 / 2.0);
+}
+
+#[test]
+fn parses_json_keyframes() {
+    let keyframes = parse_zoom_keyframes(
+        r#"[{"start_ms": 0, "end_ms": 4000, "zoom": 2, "center_x": 100, "center_y": 60},
+            {"start_ms": 5000, "end_ms": 6000, "zoom": 3, "center_x": 10, "center_y": 20,
+             "easing": "spring"}]"#,
+    )
+    .unwrap();
+    assert_eq!(keyframes[0], keyframe());
+    assert_eq!(keyframes[1].easing, ZoomEasing::Spring);
+
+    for bad in [
+        "",
+        "{}",
+        r#"[{"start_ms": 0}]"#,
+        r#"[{"start_ms": 0, "end_ms": 1, "zoom": 2, "center_x": 0, "center_y": 0, "easing": "bounce"}]"#,
+    ] {
+        assert!(
+            matches!(
+                parse_zoom_keyframes(bad),
+                Err(EngineError::InvalidZoomKeyframes(_))
+            ),
+            "{:?} parsed",
+            bad
+        );
+    }
+}
+
+#[test]
+fn overlapping_keyframes_are_rejected() {
+    let overlapping = ZoomKeyframe {
+        start_ms: 3500.0,
+        end_ms: 5000.0,
+        ..keyframe()
+    };
+    let options = ProcessingOptions {
+        zoom_keyframes: vec![overlapping, keyframe()],
+        ..ProcessingOptions::default()
+    };
+    let err = options.validated().unwrap_err();
+    assert!(matches!(err, EngineError::InvalidZoomKeyframes(_)));
+    assert!(
+        err.to_string()
+            .contains("0-4000 ms and 3500-5000 ms overlap"),
+        "{}",
+        err
+    );
+
+    // Touching end to start is fine
+    let options = ProcessingOptions {
+        zoom_keyframes: vec![
+            ZoomKeyframe {
+                start_ms: 4000.0,
+                ..overlapping
+            },
+            keyframe(),
+        ],
+        ..ProcessingOptions::default()
+    };
+    let validated = options.validated().unwrap();
+    assert_eq!(validated.zoom_keyframes[0], keyframe());
+}